@@ -0,0 +1,69 @@
+use std::hint::black_box;
+
+use criterion::Criterion;
+use valence::prelude::*;
+
+/// Compares looking up whether blocks are solid through their collision shapes
+/// with the cached solid masks of chunk sections.
+pub fn collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision");
+
+    let mut app = App::new();
+
+    app.add_plugins(DefaultPlugins);
+    app.update();
+
+    let mut layer = LayerBundle::new(
+        ident!("overworld"),
+        app.world.resource::<DimensionTypeRegistry>(),
+        app.world.resource::<BiomeRegistry>(),
+        app.world.resource::<Server>(),
+    )
+    .chunk;
+
+    for z in -2..2 {
+        for x in -2..2 {
+            layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    // A mix of solid and non-solid blocks, so sections use indirect palettes.
+    for z in -32_i32..32 {
+        for x in -32..32 {
+            for y in 0..32 {
+                let state = match (x + y + z).rem_euclid(4) {
+                    0 => BlockState::STONE,
+                    1 => BlockState::OAK_SLAB,
+                    2 => BlockState::TORCH,
+                    _ => BlockState::AIR,
+                };
+
+                layer.set_block([x, y, z], state);
+            }
+        }
+    }
+
+    let positions: Vec<_> = (-32..32)
+        .flat_map(|z| (-32..32).flat_map(move |x| (0..32).map(move |y| BlockPos::new(x, y, z))))
+        .collect();
+
+    group.bench_function("collision_shapes", |b| {
+        b.iter(|| {
+            for &pos in black_box(&positions) {
+                black_box(
+                    layer
+                        .block(pos)
+                        .is_some_and(|block| block.state.collision_shapes().len() > 0),
+                );
+            }
+        });
+    });
+
+    group.bench_function("solid_mask", |b| {
+        b.iter(|| {
+            for &pos in black_box(&positions) {
+                black_box(layer.is_solid(pos));
+            }
+        });
+    });
+}
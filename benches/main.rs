@@ -2,9 +2,11 @@ use criterion::{criterion_group, criterion_main};
 
 mod anvil;
 mod block;
+mod collision;
 mod decode_array;
 mod idle;
 mod many_players;
+mod nbt;
 mod packet;
 mod var_int;
 mod var_long;
@@ -12,12 +14,14 @@ mod var_long;
 criterion_group! {
     benches,
     block::block,
+    collision::collision,
     decode_array::decode_array,
     idle::idle_update,
     packet::packet,
     var_int::var_int,
     var_long::var_long,
     many_players::many_players,
+    nbt::nbt,
 }
 
 criterion_main!(benches);
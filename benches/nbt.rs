@@ -0,0 +1,123 @@
+// The fields of the block entities are only deserialized.
+#![allow(dead_code)]
+
+use std::borrow::Cow;
+use std::hint::black_box;
+
+use criterion::Criterion;
+use serde::Deserialize;
+use valence_nbt::serde::from_binary_borrowed;
+use valence_nbt::{compound, from_binary, to_binary, Compound, List};
+
+/// The block entities of a chunk, with every field owned.
+#[derive(Deserialize)]
+struct OwnedChunk {
+    block_entities: Vec<OwnedBlockEntity>,
+}
+
+#[derive(Deserialize)]
+struct OwnedBlockEntity {
+    id: String,
+    x: i32,
+    y: i32,
+    z: i32,
+    front_text: OwnedSignText,
+    data: Vec<i8>,
+}
+
+#[derive(Deserialize)]
+struct OwnedSignText {
+    messages: Vec<String>,
+    color: String,
+}
+
+/// The same block entities, borrowing strings and bytes from the input.
+#[derive(Deserialize)]
+struct BorrowedChunk<'a> {
+    #[serde(borrow)]
+    block_entities: Vec<BorrowedBlockEntity<'a>>,
+}
+
+#[derive(Deserialize)]
+struct BorrowedBlockEntity<'a> {
+    id: &'a str,
+    x: i32,
+    y: i32,
+    z: i32,
+    #[serde(borrow)]
+    front_text: BorrowedSignText<'a>,
+    data: &'a [u8],
+}
+
+#[derive(Deserialize)]
+struct BorrowedSignText<'a> {
+    #[serde(borrow)]
+    messages: Vec<Cow<'a, str>>,
+    color: &'a str,
+}
+
+fn chunk_nbt() -> Vec<u8> {
+    let block_entities = (0..256)
+        .map(|i| {
+            compound! {
+                "id" => "minecraft:oak_sign",
+                "x" => i % 16,
+                "y" => 64,
+                "z" => i / 16,
+                "front_text" => compound! {
+                    "messages" => List::String(
+                        (0..4).map(|line| format!(r#"{{"text":"Line {line} of sign {i}"}}"#)).collect()
+                    ),
+                    "color" => "black",
+                },
+                "data" => vec![0_i8; 64],
+            }
+        })
+        .collect();
+
+    let mut buf = vec![];
+
+    to_binary(
+        &compound! { "block_entities" => List::Compound(block_entities) },
+        &mut buf,
+        "",
+    )
+    .unwrap();
+
+    buf
+}
+
+pub fn nbt(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nbt");
+
+    let buf = chunk_nbt();
+
+    group.bench_function("from_binary", |b| {
+        b.iter(|| {
+            let mut r = black_box(buf.as_slice());
+            black_box(from_binary::<String>(&mut r).unwrap());
+        });
+    });
+
+    group.bench_function("from_binary_then_deserialize", |b| {
+        b.iter(|| {
+            let mut r = black_box(buf.as_slice());
+            let (compound, _): (Compound, _) = from_binary(&mut r).unwrap();
+            black_box(OwnedChunk::deserialize(compound).unwrap());
+        });
+    });
+
+    group.bench_function("from_binary_borrowed_owned", |b| {
+        b.iter(|| {
+            let mut r = black_box(buf.as_slice());
+            black_box(from_binary_borrowed::<OwnedChunk>(&mut r).unwrap());
+        });
+    });
+
+    group.bench_function("from_binary_borrowed", |b| {
+        b.iter(|| {
+            let mut r = black_box(buf.as_slice());
+            black_box(from_binary_borrowed::<BorrowedChunk>(&mut r).unwrap());
+        });
+    });
+}
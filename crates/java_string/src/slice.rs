@@ -2195,13 +2195,13 @@ unsafe impl JavaStrSliceIndex for RangeFrom<usize> {
 
     #[inline]
     unsafe fn get_unchecked(self, slice: *const JavaStr) -> *const JavaStr {
-        let len = unsafe { (*(slice as *const [u8])).len() };
+        let len = unsafe { (&*(slice as *const [u8])).len() };
         unsafe { (self.start..len).get_unchecked(slice) }
     }
 
     #[inline]
     unsafe fn get_unchecked_mut(self, slice: *mut JavaStr) -> *mut JavaStr {
-        let len = unsafe { (*(slice as *mut [u8])).len() };
+        let len = unsafe { (&*(slice as *mut [u8])).len() };
         unsafe { (self.start..len).get_unchecked_mut(slice) }
     }
 }
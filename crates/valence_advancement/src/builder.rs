@@ -0,0 +1,204 @@
+use std::borrow::Cow;
+
+use bevy_ecs::prelude::*;
+use bevy_hierarchy::BuildChildren;
+use rustc_hash::FxHashMap;
+use valence_server::Ident;
+
+use crate::{
+    Advancement, AdvancementBundle, AdvancementCriteria, AdvancementDisplay,
+    AdvancementRequirements,
+};
+
+/// Describes an advancement and the advancements below it, so a whole tree
+/// can be spawned at once with [`spawn`](Self::spawn).
+///
+/// An advancement without a parent is the root of a tab. Its background is
+/// the background of the tab.
+///
+/// ```
+/// # use bevy_ecs::prelude::*;
+/// # use valence_advancement::*;
+/// # use valence_server::{ident, ItemKind, ItemStack};
+/// fn setup(mut commands: Commands) {
+///     let tree = AdvancementBuilder::new(ident!("quests:root").into())
+///         .with_display(
+///             AdvancementDisplay::new(
+///                 "Quests",
+///                 "Things to do",
+///                 ItemStack::new(ItemKind::Book, 1, None),
+///             )
+///             .with_background(ident!("textures/block/stone.png").into()),
+///         )
+///         .with_criterion(ident!("joined").into())
+///         .with_child(
+///             AdvancementBuilder::new(ident!("quests:mine_stone").into())
+///                 .with_display(AdvancementDisplay::new(
+///                     "Stone Age",
+///                     "Mine some stone",
+///                     ItemStack::new(ItemKind::Cobblestone, 1, None),
+///                 ))
+///                 .with_criterion(ident!("mined_stone").into()),
+///         )
+///         .spawn(&mut commands);
+///
+///     let criterion = tree.criterion(
+///         &ident!("quests:mine_stone").into(),
+///         &ident!("mined_stone").into(),
+///     );
+///     # let _ = criterion;
+/// }
+/// # let _ = setup;
+/// ```
+#[derive(Clone, Debug)]
+pub struct AdvancementBuilder {
+    ident: Ident<Cow<'static, str>>,
+    display: Option<AdvancementDisplay>,
+    criteria: Vec<Ident<Cow<'static, str>>>,
+    requirements: Vec<Vec<usize>>,
+    children: Vec<AdvancementBuilder>,
+}
+
+impl AdvancementBuilder {
+    pub fn new(ident: Ident<Cow<'static, str>>) -> Self {
+        Self {
+            ident,
+            display: None,
+            criteria: vec![],
+            requirements: vec![],
+            children: vec![],
+        }
+    }
+
+    /// Shows the advancement in the advancements screen. Advancements
+    /// without a display are invisible, but can still be completed.
+    pub fn with_display(mut self, display: AdvancementDisplay) -> Self {
+        self.display = Some(display);
+        self
+    }
+
+    /// Adds a criterion which must be done to complete the advancement.
+    pub fn with_criterion(mut self, criterion: Ident<Cow<'static, str>>) -> Self {
+        self.requirements.push(vec![self.criteria.len()]);
+        self.criteria.push(criterion);
+        self
+    }
+
+    /// Adds criteria of which any one must be done to complete the
+    /// advancement.
+    pub fn with_any_criterion(
+        mut self,
+        criteria: impl IntoIterator<Item = Ident<Cow<'static, str>>>,
+    ) -> Self {
+        let start = self.criteria.len();
+        self.criteria.extend(criteria);
+        self.requirements
+            .push((start..self.criteria.len()).collect());
+        self
+    }
+
+    /// Adds an advancement below this one.
+    pub fn with_child(mut self, child: AdvancementBuilder) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Spawns the advancements and their criteria. The criteria are children
+    /// of their advancement, and advancements are children of their parent.
+    pub fn spawn(self, commands: &mut Commands) -> AdvancementTree {
+        let mut tree = AdvancementTree {
+            root: Entity::PLACEHOLDER,
+            advancements: FxHashMap::default(),
+            criteria: FxHashMap::default(),
+        };
+
+        tree.root = self.spawn_inner(commands, None, &mut tree);
+        tree
+    }
+
+    fn spawn_inner(
+        self,
+        commands: &mut Commands,
+        parent: Option<Entity>,
+        tree: &mut AdvancementTree,
+    ) -> Entity {
+        let criteria: Vec<_> = self
+            .criteria
+            .iter()
+            .map(|ident| commands.spawn(AdvancementCriteria::new(ident.clone())).id())
+            .collect();
+
+        let requirements = self
+            .requirements
+            .iter()
+            .map(|column| column.iter().map(|&i| criteria[i]).collect())
+            .collect();
+
+        let mut advancement = commands.spawn(AdvancementBundle {
+            advancement: Advancement::new(self.ident.clone()),
+            requirements: AdvancementRequirements(requirements),
+            cached_bytes: Default::default(),
+        });
+
+        advancement.push_children(&criteria);
+
+        if let Some(display) = self.display {
+            advancement.insert(display);
+        }
+
+        if let Some(parent) = parent {
+            advancement.set_parent(parent);
+        }
+
+        let entity = advancement.id();
+
+        for (ident, criterion) in self.criteria.into_iter().zip(criteria) {
+            tree.criteria.insert((self.ident.clone(), ident), criterion);
+        }
+
+        tree.advancements.insert(self.ident, entity);
+
+        for child in self.children {
+            child.spawn_inner(commands, Some(entity), tree);
+        }
+
+        entity
+    }
+}
+
+/// The entities of advancements spawned by [`AdvancementBuilder::spawn`].
+#[derive(Clone, Debug)]
+pub struct AdvancementTree {
+    root: Entity,
+    advancements: FxHashMap<Ident<Cow<'static, str>>, Entity>,
+    criteria: FxHashMap<(Ident<Cow<'static, str>>, Ident<Cow<'static, str>>), Entity>,
+}
+
+impl AdvancementTree {
+    /// The advancement at the root of the tree.
+    pub fn root(&self) -> Entity {
+        self.root
+    }
+
+    /// Returns the entity of the advancement `advancement`.
+    pub fn advancement(&self, advancement: &Ident<Cow<'static, str>>) -> Option<Entity> {
+        self.advancements.get(advancement).copied()
+    }
+
+    /// Returns the entity of the criterion `criterion` of the advancement
+    /// `advancement`.
+    pub fn criterion(
+        &self,
+        advancement: &Ident<Cow<'static, str>>,
+        criterion: &Ident<Cow<'static, str>>,
+    ) -> Option<Entity> {
+        self.criteria
+            .get(&(advancement.clone(), criterion.clone()))
+            .copied()
+    }
+
+    /// Returns an iterator over all the advancements of the tree.
+    pub fn advancements(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.advancements.values().copied()
+    }
+}
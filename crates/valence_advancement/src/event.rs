@@ -1,28 +1,46 @@
 use bevy_ecs::prelude::*;
+use bevy_hierarchy::Parent;
 use valence_server::event_loop::PacketEvent;
 use valence_server::protocol::packets::play::AdvancementTabC2s;
+use valence_server::protocol_error::ProtocolErrorSink;
 use valence_server::Ident;
 
+use crate::Advancement;
+
 /// This event sends when the client changes or closes advancement's tab.
 #[derive(Event, Clone, PartialEq, Eq, Debug)]
 pub struct AdvancementTabChangeEvent {
     pub client: Entity,
     /// If None then the client has closed advancement's tabs.
     pub opened_tab: Option<Ident<String>>,
+    /// The root advancement of the opened tab, if it exists.
+    pub tab: Option<Entity>,
 }
 
 pub(crate) fn handle_advancement_tab_change(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut advancement_tab_change_events: EventWriter<AdvancementTabChangeEvent>,
+    roots: Query<(Entity, &Advancement), Without<Parent>>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<AdvancementTabC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<AdvancementTabC2s>(&errors) {
+            let opened_tab: Option<Ident<String>> = match pkt {
+                AdvancementTabC2s::ClosedScreen => None,
+                AdvancementTabC2s::OpenedTab { tab_id } => Some(tab_id.into()),
+            };
+
+            let tab = opened_tab.as_ref().and_then(|opened_tab| {
+                roots
+                    .iter()
+                    .find(|(_, advancement)| advancement.as_str() == opened_tab.as_str())
+                    .map(|(entity, _)| entity)
+            });
+
             advancement_tab_change_events.send(AdvancementTabChangeEvent {
                 client: packet.client,
-                opened_tab: match pkt {
-                    AdvancementTabC2s::ClosedScreen => None,
-                    AdvancementTabC2s::OpenedTab { tab_id } => Some(tab_id.into()),
-                },
+                opened_tab,
+                tab,
             })
         }
     }
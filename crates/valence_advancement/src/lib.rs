@@ -1,7 +1,10 @@
 #![doc = include_str!("../README.md")]
 #![allow(clippy::type_complexity)]
 
+mod builder;
 pub mod event;
+mod progress;
+mod toast;
 
 use std::borrow::Cow;
 use std::io::Write;
@@ -12,9 +15,14 @@ use bevy_ecs::prelude::*;
 use bevy_ecs::system::SystemParam;
 pub use bevy_hierarchy;
 use bevy_hierarchy::{Children, HierarchyPlugin, Parent};
+pub use builder::{AdvancementBuilder, AdvancementTree};
 use derive_more::{Deref, DerefMut};
 use event::{handle_advancement_tab_change, AdvancementTabChangeEvent};
+pub use progress::{
+    AdvancementCompletedEvent, AdvancementProgress, GrantAdvancement, RevokeAdvancement,
+};
 use rustc_hash::FxHashMap;
+pub use toast::ShowToast;
 use valence_server::client::{Client, FlushPacketsSet, SpawnClientsSet};
 use valence_server::protocol::packets::play::{
     advancement_update_s2c as packet, SelectAdvancementTabS2c,
@@ -43,6 +51,7 @@ impl Plugin for AdvancementPlugin {
                 ),
             )
             .add_event::<AdvancementTabChangeEvent>()
+            .add_event::<AdvancementCompletedEvent>()
             .add_systems(
                 PreUpdate,
                 (
@@ -53,8 +62,14 @@ impl Plugin for AdvancementPlugin {
             .add_systems(
                 PostUpdate,
                 (
+                    (
+                        progress::update_advancement_progress,
+                        toast::remove_toast_advancements,
+                    )
+                        .before(WriteAdvancementPacketToClientsSet),
                     update_advancement_cached_bytes.in_set(WriteAdvancementToCacheSet),
                     send_advancement_update_packet.in_set(WriteAdvancementPacketToClientsSet),
+                    toast::advance_toast_advancements.after(WriteAdvancementPacketToClientsSet),
                 ),
             );
     }
@@ -76,9 +91,10 @@ fn add_advancement_update_component_to_new_clients(
     query: Query<Entity, Added<Client>>,
 ) {
     for client in query.iter() {
-        commands
-            .entity(client)
-            .insert(AdvancementClientUpdate::default());
+        commands.entity(client).insert((
+            AdvancementClientUpdate::default(),
+            AdvancementProgress::default(),
+        ));
     }
 }
 
@@ -335,15 +351,16 @@ impl Advancement {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub enum AdvancementFrameType {
+    #[default]
     Task,
     Challenge,
     Goal,
 }
 
 /// Advancement display. Optional component
-#[derive(Component)]
+#[derive(Component, Clone, Debug)]
 pub struct AdvancementDisplay {
     pub title: Text,
     pub description: Text,
@@ -357,6 +374,52 @@ pub struct AdvancementDisplay {
 }
 
 impl AdvancementDisplay {
+    /// Creates a display for a task which shows a toast when completed, at
+    /// the top left of its tab.
+    pub fn new(title: impl Into<Text>, description: impl Into<Text>, icon: ItemStack) -> Self {
+        Self {
+            title: title.into(),
+            description: description.into(),
+            icon,
+            frame_type: AdvancementFrameType::Task,
+            show_toast: true,
+            hidden: false,
+            background_texture: None,
+            x_coord: 0.0,
+            y_coord: 0.0,
+        }
+    }
+
+    pub fn with_frame_type(mut self, frame_type: AdvancementFrameType) -> Self {
+        self.frame_type = frame_type;
+        self
+    }
+
+    pub fn with_show_toast(mut self, show_toast: bool) -> Self {
+        self.show_toast = show_toast;
+        self
+    }
+
+    /// Hides the advancement and its children until it is completed.
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Sets the background of the tab, like `textures/block/stone.png`. Only
+    /// used for root advancements.
+    pub fn with_background(mut self, background_texture: Ident<Cow<'static, str>>) -> Self {
+        self.background_texture = Some(background_texture);
+        self
+    }
+
+    /// Sets the position of the advancement in its tab.
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.x_coord = x;
+        self.y_coord = y;
+        self
+    }
+
     pub(crate) fn flags(&self) -> i32 {
         let mut flags = 0;
         flags |= self.background_texture.is_some() as i32;
@@ -471,15 +534,7 @@ impl AdvancementClientUpdate {
 
     /// Marks criteria as done
     pub fn criteria_done(&mut self, criteria: Entity) {
-        self.progress.push((
-            criteria,
-            Some(
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64,
-            ),
-        ))
+        self.progress.push((criteria, Some(now_millis())))
     }
 
     /// Marks criteria as undone
@@ -487,3 +542,11 @@ impl AdvancementClientUpdate {
         self.progress.push((criteria, None))
     }
 }
+
+/// The current time in milliseconds since the Unix epoch.
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
@@ -0,0 +1,178 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use bevy_hierarchy::{Children, Parent};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{now_millis, AdvancementClientUpdate, AdvancementCriteria, AdvancementRequirements};
+
+/// The criteria a client has done. Added to clients automatically.
+///
+/// Changes are sent to the client, which shows a toast for advancements with
+/// [`show_toast`](crate::AdvancementDisplay::show_toast) when they are
+/// completed. An [`AdvancementCompletedEvent`] is sent when all the
+/// requirements of an advancement are met.
+#[derive(Component, Default, Debug)]
+pub struct AdvancementProgress {
+    /// The criteria which are done and the times they were done, in
+    /// milliseconds since the Unix epoch.
+    done: FxHashMap<Entity, i64>,
+    completed: FxHashSet<Entity>,
+    changed: Vec<Entity>,
+}
+
+impl AdvancementProgress {
+    /// Returns whether the criterion `criterion` is done.
+    pub fn is_done(&self, criterion: Entity) -> bool {
+        self.done.contains_key(&criterion)
+    }
+
+    /// Returns whether the advancement `advancement` was completed.
+    pub fn is_completed(&self, advancement: Entity) -> bool {
+        self.completed.contains(&advancement)
+    }
+
+    /// Returns an iterator over the criteria which are done and the times
+    /// they were done, in milliseconds since the Unix epoch.
+    pub fn iter_done(&self) -> impl Iterator<Item = (Entity, i64)> + '_ {
+        self.done
+            .iter()
+            .map(|(&criterion, &time)| (criterion, time))
+    }
+
+    /// Marks the criterion `criterion` as done. Returns whether it wasn't
+    /// done before.
+    pub fn grant_criterion(&mut self, criterion: Entity) -> bool {
+        if self.done.contains_key(&criterion) {
+            return false;
+        }
+
+        self.done.insert(criterion, now_millis());
+        self.changed.push(criterion);
+        true
+    }
+
+    /// Marks the criterion `criterion` as not done. Returns whether it was
+    /// done before.
+    pub fn revoke_criterion(&mut self, criterion: Entity) -> bool {
+        if self.done.remove(&criterion).is_none() {
+            return false;
+        }
+
+        self.changed.push(criterion);
+        true
+    }
+
+    fn meets(&self, requirements: &AdvancementRequirements) -> bool {
+        requirements
+            .iter()
+            .all(|column| column.iter().any(|criterion| self.is_done(*criterion)))
+    }
+}
+
+/// Sent when a client completes an advancement.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AdvancementCompletedEvent {
+    pub client: Entity,
+    pub advancement: Entity,
+}
+
+/// A [`Command`] to grant all the criteria of an advancement to a client.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GrantAdvancement {
+    pub client: Entity,
+    pub advancement: Entity,
+}
+
+/// A [`Command`] to revoke all the criteria of an advancement from a client.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RevokeAdvancement {
+    pub client: Entity,
+    pub advancement: Entity,
+}
+
+fn criteria_of(world: &World, advancement: Entity) -> Vec<Entity> {
+    world
+        .get::<Children>(advancement)
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|&child| world.get::<AdvancementCriteria>(child).is_some())
+        .collect()
+}
+
+impl Command for GrantAdvancement {
+    fn apply(self, world: &mut World) {
+        let criteria = criteria_of(world, self.advancement);
+
+        if let Some(mut progress) = world.get_mut::<AdvancementProgress>(self.client) {
+            for criterion in criteria {
+                progress.grant_criterion(criterion);
+            }
+        }
+    }
+}
+
+impl Command for RevokeAdvancement {
+    fn apply(self, world: &mut World) {
+        let criteria = criteria_of(world, self.advancement);
+
+        if let Some(mut progress) = world.get_mut::<AdvancementProgress>(self.client) {
+            for criterion in criteria {
+                progress.revoke_criterion(criterion);
+            }
+        }
+    }
+}
+
+pub(crate) fn update_advancement_progress(
+    mut clients: Query<
+        (
+            Entity,
+            &mut AdvancementProgress,
+            &mut AdvancementClientUpdate,
+        ),
+        Changed<AdvancementProgress>,
+    >,
+    parents: Query<&Parent, With<AdvancementCriteria>>,
+    requirements: Query<&AdvancementRequirements>,
+    mut events: EventWriter<AdvancementCompletedEvent>,
+) {
+    for (client, mut progress, mut update) in &mut clients {
+        if progress.changed.is_empty() {
+            continue;
+        }
+
+        let progress = &mut *progress;
+        let mut advancements = vec![];
+
+        for criterion in progress.changed.drain(..) {
+            update
+                .progress
+                .push((criterion, progress.done.get(&criterion).copied()));
+
+            if let Ok(parent) = parents.get(criterion) {
+                if !advancements.contains(&parent.get()) {
+                    advancements.push(parent.get());
+                }
+            }
+        }
+
+        for advancement in advancements {
+            let Ok(reqs) = requirements.get(advancement) else {
+                continue;
+            };
+
+            // Advancements without requirements can't be completed.
+            if !reqs.is_empty() && progress.meets(reqs) {
+                if progress.completed.insert(advancement) {
+                    events.send(AdvancementCompletedEvent {
+                        client,
+                        advancement,
+                    });
+                }
+            } else {
+                progress.completed.remove(&advancement);
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use bevy_hierarchy::{BuildWorldChildren, DespawnRecursiveExt};
+use valence_server::{Ident, ItemStack, Text};
+
+use crate::{
+    Advancement, AdvancementBundle, AdvancementClientUpdate, AdvancementCriteria,
+    AdvancementDisplay, AdvancementFrameType, AdvancementRequirements,
+};
+
+/// A [`Command`] to show a toast in the corner of a client's screen, like the
+/// ones of completed advancements, without adding an advancement to its
+/// advancements screen.
+///
+/// The toast is shown with a temporary advancement, which is removed from the
+/// client again on the next tick. Toasts aren't shown in the first tick of a
+/// client, in which its advancements are reset.
+#[derive(Clone, Debug)]
+pub struct ShowToast {
+    pub client: Entity,
+    /// The text below the heading of the toast.
+    pub title: Text,
+    pub icon: ItemStack,
+    /// Determines the heading of the toast, like "Challenge Complete!".
+    ///
+    /// # Default Value
+    ///
+    /// [`AdvancementFrameType::Task`]
+    pub frame_type: AdvancementFrameType,
+}
+
+impl ShowToast {
+    pub fn new(client: Entity, title: impl Into<Text>, icon: ItemStack) -> Self {
+        Self {
+            client,
+            title: title.into(),
+            icon,
+            frame_type: AdvancementFrameType::Task,
+        }
+    }
+
+    pub fn with_frame_type(mut self, frame_type: AdvancementFrameType) -> Self {
+        self.frame_type = frame_type;
+        self
+    }
+}
+
+/// The state of the temporary advancement of a [`ShowToast`].
+#[derive(Component, Copy, Clone, Debug)]
+pub(crate) enum ToastAdvancement {
+    /// The advancement is sent to the client this tick.
+    New { client: Entity },
+    /// The advancement was sent to the client on a previous tick.
+    Shown { client: Entity },
+    /// The advancement is removed from the client this tick.
+    Removed,
+}
+
+impl Command for ShowToast {
+    fn apply(self, world: &mut World) {
+        if world.get::<AdvancementClientUpdate>(self.client).is_none() {
+            return;
+        }
+
+        let criterion = world
+            .spawn(AdvancementCriteria::new(Ident::new_unchecked(
+                Cow::Borrowed("valence:toast"),
+            )))
+            .id();
+
+        let entity = world.spawn_empty().id();
+
+        world
+            .entity_mut(entity)
+            .insert((
+                AdvancementBundle {
+                    advancement: Advancement::new(Ident::new_unchecked(Cow::Owned(format!(
+                        "valence:toast/{}",
+                        entity.to_bits()
+                    )))),
+                    requirements: AdvancementRequirements(vec![vec![criterion]]),
+                    cached_bytes: Default::default(),
+                },
+                AdvancementDisplay::new(self.title, Text::default(), self.icon)
+                    .with_frame_type(self.frame_type)
+                    .with_show_toast(true)
+                    .with_hidden(true),
+                ToastAdvancement::New {
+                    client: self.client,
+                },
+            ))
+            .add_child(criterion);
+
+        let mut update = world
+            .get_mut::<AdvancementClientUpdate>(self.client)
+            .expect("client must have advancement update");
+
+        update.new_advancements.push(entity);
+        update.criteria_done(criterion);
+    }
+}
+
+/// Removes the advancements of toasts shown on the previous tick from their
+/// clients.
+pub(crate) fn remove_toast_advancements(
+    mut toasts: Query<(Entity, &mut ToastAdvancement)>,
+    mut clients: Query<&mut AdvancementClientUpdate>,
+) {
+    for (entity, mut toast) in &mut toasts {
+        let ToastAdvancement::Shown { client } = *toast else {
+            continue;
+        };
+
+        if let Ok(mut update) = clients.get_mut(client) {
+            update.remove_advancements.push(entity);
+        }
+
+        *toast = ToastAdvancement::Removed;
+    }
+}
+
+pub(crate) fn advance_toast_advancements(
+    mut toasts: Query<(Entity, &mut ToastAdvancement)>,
+    mut commands: Commands,
+) {
+    for (entity, mut toast) in &mut toasts {
+        match *toast {
+            ToastAdvancement::New { client } => *toast = ToastAdvancement::Shown { client },
+            ToastAdvancement::Shown { .. } => {}
+            ToastAdvancement::Removed => commands.entity(entity).despawn_recursive(),
+        }
+    }
+}
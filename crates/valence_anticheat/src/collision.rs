@@ -0,0 +1,289 @@
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::math::{Aabb, DVec3};
+use valence_server::{BlockPos, BlockState, ChunkLayer};
+
+/// The width of the hitbox of players.
+const PLAYER_WIDTH: f64 = 0.6;
+/// The height of the hitbox of standing players.
+const PLAYER_HEIGHT: f64 = 1.8;
+/// How far hitboxes are shrunk so that touching a block doesn't count as
+/// colliding with it.
+const EPSILON: f64 = 1e-3;
+/// How far below their feet players may be supported by a block, to allow for
+/// rounding on the client.
+const SUPPORT_TOLERANCE: f64 = 0.1;
+/// How high players walk up blocks without jumping.
+const STEP_HEIGHT: f64 = 0.6;
+/// The longest distance between the hitboxes checked along a movement.
+const CLIP_STEP: f64 = 0.25;
+
+/// The blocks movement is checked against.
+pub(crate) trait Blocks {
+    /// Returns the block at `pos`, or air if it isn't loaded.
+    fn block(&self, pos: BlockPos) -> BlockState;
+
+    /// Returns whether the block at `pos` has any collision shapes.
+    fn is_solid(&self, pos: BlockPos) -> bool {
+        self.block(pos).collision_shapes().len() > 0
+    }
+}
+
+impl Blocks for ChunkLayer {
+    fn block(&self, pos: BlockPos) -> BlockState {
+        ChunkLayer::block(self, pos).map_or(BlockState::AIR, |block| block.state)
+    }
+
+    fn is_solid(&self, pos: BlockPos) -> bool {
+        // Uses the cached solid masks of the chunk sections.
+        ChunkLayer::is_solid(self, pos)
+    }
+}
+
+/// Returns the hitbox of a player standing at `pos`, shrunk by [`EPSILON`].
+pub(crate) fn player_hitbox(pos: DVec3) -> Aabb {
+    let size = DVec3::new(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH) - 2.0 * EPSILON;
+    Aabb::from_bottom_size(pos + DVec3::new(0.0, EPSILON, 0.0), size)
+}
+
+/// Returns whether `aabb` intersects the collision shapes of any block.
+pub(crate) fn collides(blocks: &impl Blocks, aabb: Aabb) -> bool {
+    let min = aabb.min().floor().as_ivec3();
+    let max = aabb.max().floor().as_ivec3();
+
+    // Fences and walls reach into the block above them.
+    for y in min.y - 1..=max.y {
+        for z in min.z..=max.z {
+            for x in min.x..=max.x {
+                let pos = BlockPos::new(x, y, z);
+
+                if !blocks.is_solid(pos) {
+                    continue;
+                }
+
+                let offset = DVec3::new(x as f64, y as f64, z as f64);
+
+                let hit = blocks
+                    .block(pos)
+                    .collision_shapes()
+                    .any(|shape| aabb.intersects(shape + offset));
+
+                if hit {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Returns the block supporting a player standing at `pos`, if any.
+pub(crate) fn supporting_block(blocks: &impl Blocks, pos: DVec3) -> Option<BlockState> {
+    let half = PLAYER_WIDTH / 2.0 - EPSILON;
+
+    let feet = Aabb::new(
+        pos + DVec3::new(-half, -SUPPORT_TOLERANCE, -half),
+        pos + DVec3::new(half, 0.0, half),
+    );
+
+    let min = feet.min().floor().as_ivec3();
+    let max = feet.max().floor().as_ivec3();
+
+    for y in (min.y - 1..=max.y).rev() {
+        for z in min.z..=max.z {
+            for x in min.x..=max.x {
+                let pos = BlockPos::new(x, y, z);
+
+                if !blocks.is_solid(pos) {
+                    continue;
+                }
+
+                let state = blocks.block(pos);
+                let offset = DVec3::new(x as f64, y as f64, z as f64);
+
+                if state
+                    .collision_shapes()
+                    .any(|shape| feet.intersects(shape + offset))
+                {
+                    return Some(state);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns whether a player at `pos` is in a liquid or on a block which lets
+/// them move up and down freely, like a ladder.
+pub(crate) fn is_climbing_or_swimming(blocks: &impl Blocks, pos: DVec3) -> bool {
+    let hitbox = player_hitbox(pos);
+    let min = hitbox.min().floor().as_ivec3();
+    let max = hitbox.max().floor().as_ivec3();
+
+    for y in min.y..=max.y {
+        for z in min.z..=max.z {
+            for x in min.x..=max.x {
+                let state = blocks.block(BlockPos::new(x, y, z));
+
+                if state.is_liquid()
+                    || state.get(PropName::Waterlogged) == Some(PropValue::True)
+                    || is_climbable(state.to_kind())
+                {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+fn is_climbable(kind: BlockKind) -> bool {
+    matches!(
+        kind,
+        BlockKind::Ladder
+            | BlockKind::Vine
+            | BlockKind::Scaffolding
+            | BlockKind::TwistingVines
+            | BlockKind::TwistingVinesPlant
+            | BlockKind::WeepingVines
+            | BlockKind::WeepingVinesPlant
+            | BlockKind::CaveVines
+            | BlockKind::CaveVinesPlant
+            | BlockKind::Cobweb
+            | BlockKind::PowderSnow
+            | BlockKind::BubbleColumn
+    )
+}
+
+/// Returns whether players bounce off the block, which lets them rise higher
+/// than they can jump.
+pub(crate) fn is_bouncy(state: BlockState) -> bool {
+    state.to_kind() == BlockKind::SlimeBlock || state.to_kind().to_str().ends_with("_bed")
+}
+
+/// Returns whether moving in a straight line from `from` to `to` passes
+/// through a block. Movements starting inside a block are allowed, so players
+/// can get out of blocks placed on them.
+pub(crate) fn clips_through_block(blocks: &impl Blocks, from: DVec3, to: DVec3) -> bool {
+    if collides(blocks, player_hitbox(from)) {
+        return false;
+    }
+
+    let steps = ((to - from).length() / CLIP_STEP).ceil().min(64.0) as u32;
+
+    for i in 1..steps {
+        let hitbox = player_hitbox(from.lerp(to, i as f64 / steps as f64));
+
+        // Players walk up slabs and stairs without jumping, so the path
+        // between the positions may cut through the corner of a block.
+        let hitbox = Aabb::new(hitbox.min() + DVec3::Y * STEP_HEIGHT, hitbox.max());
+
+        if collides(blocks, hitbox) {
+            return true;
+        }
+    }
+
+    collides(blocks, player_hitbox(to))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    impl Blocks for HashMap<BlockPos, BlockState> {
+        fn block(&self, pos: BlockPos) -> BlockState {
+            self.get(&pos).copied().unwrap_or(BlockState::AIR)
+        }
+    }
+
+    /// A stone floor at y = 0 with a wall at x = 2 and a slab at x = -2.
+    fn blocks() -> HashMap<BlockPos, BlockState> {
+        let mut blocks = HashMap::new();
+
+        for x in -4..=4 {
+            for z in -4..=4 {
+                blocks.insert(BlockPos::new(x, 0, z), BlockState::STONE);
+            }
+        }
+
+        for y in 1..=3 {
+            for z in -4..=4 {
+                blocks.insert(BlockPos::new(2, y, z), BlockState::STONE);
+            }
+        }
+
+        for z in -4..=4 {
+            blocks.insert(BlockPos::new(-2, 1, z), BlockState::STONE_SLAB);
+        }
+
+        blocks.insert(BlockPos::new(0, 1, 3), BlockState::LADDER);
+
+        blocks
+    }
+
+    #[test]
+    fn standing_on_blocks() {
+        let blocks = blocks();
+
+        assert!(supporting_block(&blocks, DVec3::new(0.5, 1.0, 0.5)).is_some());
+        assert!(supporting_block(&blocks, DVec3::new(0.5, 1.05, 0.5)).is_some());
+        assert!(supporting_block(&blocks, DVec3::new(0.5, 1.5, 0.5)).is_none());
+
+        // Standing on the top of a slab.
+        assert!(supporting_block(&blocks, DVec3::new(-1.5, 1.5, 0.5)).is_some());
+        assert!(!collides(
+            &blocks,
+            player_hitbox(DVec3::new(-1.5, 1.5, 0.5))
+        ));
+        assert!(collides(&blocks, player_hitbox(DVec3::new(-1.5, 1.2, 0.5))));
+    }
+
+    #[test]
+    fn clipping_through_walls() {
+        let blocks = blocks();
+
+        let start = DVec3::new(1.5, 1.0, 0.5);
+
+        assert!(!clips_through_block(
+            &blocks,
+            start,
+            DVec3::new(1.69, 1.0, 0.5)
+        ));
+        assert!(clips_through_block(
+            &blocks,
+            start,
+            DVec3::new(3.5, 1.0, 0.5)
+        ));
+        assert!(clips_through_block(
+            &blocks,
+            start,
+            DVec3::new(1.5, -1.0, 0.5)
+        ));
+
+        // Walking up a slab.
+        assert!(!clips_through_block(
+            &blocks,
+            DVec3::new(-0.5, 1.0, 0.5),
+            DVec3::new(-1.2, 1.5, 0.5)
+        ));
+
+        // Players inside a block may leave it.
+        assert!(!clips_through_block(
+            &blocks,
+            DVec3::new(2.5, 1.0, 0.5),
+            DVec3::new(1.5, 1.0, 0.5)
+        ));
+    }
+
+    #[test]
+    fn climbing_ladders() {
+        let blocks = blocks();
+
+        assert!(is_climbing_or_swimming(&blocks, DVec3::new(0.5, 1.5, 3.5)));
+        assert!(!is_climbing_or_swimming(&blocks, DVec3::new(0.5, 1.5, 0.5)));
+    }
+}
@@ -0,0 +1,400 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod collision;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use collision::{clips_through_block, is_bouncy, is_climbing_or_swimming, supporting_block};
+use tracing::warn;
+use valence_server::abilities::PlayerAbilitiesFlags;
+use valence_server::client::{
+    ClientMarker, DisconnectClient, SpawnClientsSet, Username, VisibleChunkLayer,
+};
+use valence_server::entity::active_status_effects::ActiveStatusEffects;
+use valence_server::entity::entity::Pose;
+use valence_server::entity::{Pose as PoseKind, Position};
+use valence_server::math::DVec3;
+use valence_server::movement::MovementEvent;
+use valence_server::protocol::status_effects::StatusEffect;
+use valence_server::{ChunkLayer, ChunkPos, EventLoopUpdate, GameMode, Server, Text};
+
+/// Checks the movement of clients and reacts to illegal movement. See the
+/// crate documentation for more information.
+pub struct MovementValidationPlugin;
+
+impl Plugin for MovementValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MovementValidationSettings>()
+            .add_event::<MovementViolationEvent>()
+            .add_systems(PreUpdate, init_validation_state.after(SpawnClientsSet))
+            .add_systems(EventLoopUpdate, validate_movement);
+    }
+}
+
+/// Settings for [`MovementValidationPlugin`]. The limits are generous so that
+/// lag doesn't get players punished, and can be raised for games which make
+/// players faster.
+#[derive(Resource, Clone, Debug)]
+pub struct MovementValidationSettings {
+    /// The distance players may move horizontally per tick, in blocks.
+    /// Sprint-jumping is about 0.6 blocks per tick. Increased by the speed
+    /// effect.
+    ///
+    /// # Default Value
+    ///
+    /// `1.0`
+    pub max_horizontal_speed: f64,
+    /// The number of ticks of unused movement players may catch up on, for
+    /// when their movement packets arrive in bursts.
+    ///
+    /// # Default Value
+    ///
+    /// `10`
+    pub speed_buffer_ticks: u32,
+    /// How high above the last block they stood on players may get without
+    /// touching a block. Jumping reaches about 1.25 blocks. Increased by the
+    /// jump boost effect.
+    ///
+    /// # Default Value
+    ///
+    /// `1.5`
+    pub max_jump_height: f64,
+    /// The number of ticks players may stay in the air without falling.
+    ///
+    /// # Default Value
+    ///
+    /// `10`
+    pub max_hover_ticks: u32,
+    pub speed: ViolationResponse,
+    pub fly: ViolationResponse,
+    pub no_fall: ViolationResponse,
+    pub wall_clip: ViolationResponse,
+    /// The reason shown to clients kicked for illegal movement.
+    pub kick_message: Text,
+}
+
+impl Default for MovementValidationSettings {
+    fn default() -> Self {
+        Self {
+            max_horizontal_speed: 1.0,
+            speed_buffer_ticks: 10,
+            max_jump_height: 1.5,
+            max_hover_ticks: 10,
+            speed: ViolationResponse::RubberBand,
+            fly: ViolationResponse::RubberBand,
+            no_fall: ViolationResponse::RubberBand,
+            wall_clip: ViolationResponse::RubberBand,
+            kick_message: "Illegal movement".into(),
+        }
+    }
+}
+
+/// What happens when a client moves illegally.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ViolationResponse {
+    /// The check is skipped.
+    Disabled,
+    /// A [`MovementViolationEvent`] is sent and a warning is logged, but the
+    /// movement is allowed.
+    Log,
+    /// The client is teleported back to where it was before the movement.
+    RubberBand,
+    /// The client is disconnected.
+    Kick,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MovementViolation {
+    /// The client moved faster than
+    /// [`MovementValidationSettings::max_horizontal_speed`].
+    Speed,
+    /// The client rose too high or stayed in the air too long.
+    Fly,
+    /// The client claimed to be on the ground while in the air, which avoids
+    /// fall damage.
+    NoFall,
+    /// The client moved through a block.
+    WallClip,
+}
+
+/// Sent when a client moves illegally, after the [`ViolationResponse`] was
+/// applied.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct MovementViolationEvent {
+    pub client: Entity,
+    pub violation: MovementViolation,
+    pub from: DVec3,
+    pub to: DVec3,
+    pub response: ViolationResponse,
+}
+
+/// Exempts a client from movement validation, for example while a minigame
+/// launches it into the air.
+#[derive(Component, Copy, Clone, Default, Debug)]
+pub struct MovementValidationExempt;
+
+/// What the checks remember about a client between movements.
+#[derive(Component, Default, Debug)]
+struct ValidationState {
+    /// The tick of the previous movement.
+    last_tick: Option<i64>,
+    /// The horizontal distance the client may still move.
+    speed_budget: f64,
+    /// The height of the last position the client was supported at.
+    ground_y: Option<f64>,
+    /// The last tick the client was supported or falling.
+    last_descent_tick: i64,
+    /// Whether the client bounced off a block since it last stood on a block.
+    bouncing: bool,
+}
+
+fn init_validation_state(clients: Query<Entity, Added<ClientMarker>>, mut commands: Commands) {
+    for client in &clients {
+        commands.entity(client).insert(ValidationState::default());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn validate_movement(
+    mut movements: EventReader<MovementEvent>,
+    mut clients: Query<
+        (
+            &mut Position,
+            &mut ValidationState,
+            &VisibleChunkLayer,
+            &GameMode,
+            &PlayerAbilitiesFlags,
+            &Pose,
+            Option<&ActiveStatusEffects>,
+            &Username,
+        ),
+        Without<MovementValidationExempt>,
+    >,
+    layers: Query<&ChunkLayer>,
+    settings: Res<MovementValidationSettings>,
+    server: Res<Server>,
+    mut violations: EventWriter<MovementViolationEvent>,
+    mut commands: Commands,
+) {
+    for mov in movements.read() {
+        let Ok((mut pos, mut state, layer, game_mode, abilities, pose, effects, username)) =
+            clients.get_mut(mov.client)
+        else {
+            continue;
+        };
+
+        if *game_mode == GameMode::Spectator {
+            continue;
+        }
+
+        let Ok(layer) = layers.get(layer.0) else {
+            continue;
+        };
+
+        // Blocks around clients in unloaded chunks are missing.
+        if layer.chunk(ChunkPos::from(mov.position)).is_none() {
+            continue;
+        }
+
+        let limits = Limits::new(&settings, effects);
+
+        let check = Check {
+            layer,
+            mov,
+            state: &mut state,
+            now: server.current_tick(),
+            free_flight: abilities.allow_flying() || abilities.flying(),
+            gliding: matches!(pose.0, PoseKind::FallFlying | PoseKind::SpinAttack),
+            levitating: effects.is_some_and(|e| {
+                e.has_effect(StatusEffect::Levitation) || e.has_effect(StatusEffect::SlowFalling)
+            }),
+            limits,
+        };
+
+        let Some(violation) = check.run(&settings) else {
+            continue;
+        };
+
+        let response = match violation {
+            MovementViolation::Speed => settings.speed,
+            MovementViolation::Fly => settings.fly,
+            MovementViolation::NoFall => settings.no_fall,
+            MovementViolation::WallClip => settings.wall_clip,
+        };
+
+        match response {
+            ViolationResponse::Disabled => continue,
+            ViolationResponse::Log => {
+                warn!(
+                    "{} moved illegally ({violation:?}) from {} to {}",
+                    username.0, mov.old_position, mov.position
+                );
+            }
+            ViolationResponse::RubberBand => {
+                // Differs from the synced position, so the client is
+                // teleported back.
+                pos.0 = mov.old_position;
+            }
+            ViolationResponse::Kick => {
+                commands.add(DisconnectClient {
+                    client: mov.client,
+                    reason: settings.kick_message.clone(),
+                });
+            }
+        }
+
+        violations.send(MovementViolationEvent {
+            client: mov.client,
+            violation,
+            from: mov.old_position,
+            to: mov.position,
+            response,
+        });
+    }
+}
+
+/// The limits of a client, with its status effects applied.
+struct Limits {
+    max_horizontal_speed: f64,
+    max_jump_height: f64,
+}
+
+impl Limits {
+    fn new(settings: &MovementValidationSettings, effects: Option<&ActiveStatusEffects>) -> Self {
+        let level = |effect| {
+            effects
+                .and_then(|e| e.get_current_effect(effect))
+                .map_or(0.0, |e| e.amplifier() as f64 + 1.0)
+        };
+
+        Self {
+            max_horizontal_speed: settings.max_horizontal_speed
+                * (1.0 + 0.2 * level(StatusEffect::Speed)),
+            // Every level of jump boost adds a bit more than half a block.
+            max_jump_height: settings.max_jump_height + 0.75 * level(StatusEffect::JumpBoost),
+        }
+    }
+}
+
+struct Check<'a> {
+    layer: &'a ChunkLayer,
+    mov: &'a MovementEvent,
+    state: &'a mut ValidationState,
+    now: i64,
+    free_flight: bool,
+    gliding: bool,
+    levitating: bool,
+    limits: Limits,
+}
+
+impl Check<'_> {
+    /// Returns the first violation of the movement among the enabled checks,
+    /// and updates the state of the client.
+    fn run(mut self, settings: &MovementValidationSettings) -> Option<MovementViolation> {
+        let enabled = |response| response != ViolationResponse::Disabled;
+
+        if enabled(settings.wall_clip) && self.wall_clip() {
+            return Some(MovementViolation::WallClip);
+        }
+
+        let speed = self.speed(settings);
+        let (fly, no_fall) = self.air(settings);
+
+        if enabled(settings.speed) && speed {
+            Some(MovementViolation::Speed)
+        } else if enabled(settings.fly) && fly {
+            Some(MovementViolation::Fly)
+        } else if enabled(settings.no_fall) && no_fall {
+            Some(MovementViolation::NoFall)
+        } else {
+            None
+        }
+    }
+
+    fn wall_clip(&self) -> bool {
+        clips_through_block(self.layer, self.mov.old_position, self.mov.position)
+    }
+
+    /// Spends the horizontal distance of the movement from the speed budget.
+    /// Returns whether the budget was exceeded.
+    fn speed(&mut self, settings: &MovementValidationSettings) -> bool {
+        let max_speed = self.limits.max_horizontal_speed;
+        let max_budget = max_speed * settings.speed_buffer_ticks.max(1) as f64;
+
+        let elapsed = match self.state.last_tick.replace(self.now) {
+            Some(last) => (self.now - last).max(0) as f64,
+            None => f64::INFINITY,
+        };
+
+        self.state.speed_budget = (self.state.speed_budget + elapsed * max_speed).min(max_budget);
+
+        if self.free_flight || self.gliding {
+            return false;
+        }
+
+        let delta = self.mov.position - self.mov.old_position;
+        let distance = DVec3::new(delta.x, 0.0, delta.z).length();
+
+        if distance > self.state.speed_budget + 1e-6 {
+            return true;
+        }
+
+        self.state.speed_budget -= distance;
+        false
+    }
+
+    /// Tracks how long the client has been in the air. Returns whether the
+    /// client is flying, and whether it claims to be on the ground while in
+    /// the air.
+    fn air(&mut self, settings: &MovementValidationSettings) -> (bool, bool) {
+        let pos = self.mov.position;
+        let state = &mut *self.state;
+
+        let exempt = self.free_flight || self.gliding || self.levitating;
+
+        if exempt || is_climbing_or_swimming(self.layer, pos) {
+            state.ground_y = Some(pos.y);
+            state.last_descent_tick = self.now;
+            state.bouncing = false;
+            return (false, false);
+        }
+
+        if let Some(support) = supporting_block(self.layer, pos) {
+            state.ground_y = Some(pos.y);
+            state.last_descent_tick = self.now;
+            state.bouncing = is_bouncy(support);
+            return (false, false);
+        }
+
+        let ground_y = *state.ground_y.get_or_insert(pos.y);
+
+        if pos.y < self.mov.old_position.y {
+            state.last_descent_tick = self.now;
+        }
+
+        let too_high = pos.y - ground_y > self.limits.max_jump_height;
+        let hovering = self.now - state.last_descent_tick > settings.max_hover_ticks as i64;
+
+        let fly = !state.bouncing && (too_high || hovering);
+        let no_fall = self.mov.on_ground;
+
+        (fly, no_fall)
+    }
+}
@@ -0,0 +1,374 @@
+//! Zip snapshots of world directories.
+//!
+//! Sending a [`BackupRequest`] archives the directory of a world managed by
+//! [`Worlds`] into [`BackupSettings::dir`]. Setting
+//! [`BackupSettings::interval`] also backs up every loaded world
+//! periodically. Archives are written on a separate thread, and a
+//! [`BackupFinished`] event with the path of the archive is sent once the
+//! archive is complete.
+//!
+//! Archives are made from the files on disk. Systems which save chunks to a
+//! world directory should run before [`BackupSet`] and flush the chunks of a
+//! world when they read a [`BackupRequest`] for it, so that the archive
+//! contains the latest changes.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_anvil::backup::{BackupFinished, BackupRequest};
+//!
+//! fn backup_lobby(mut requests: EventWriter<BackupRequest>) {
+//!     requests.send(BackupRequest {
+//!         world: "lobby".into(),
+//!     });
+//! }
+//!
+//! fn report_backups(mut events: EventReader<BackupFinished>) {
+//!     for event in events.read() {
+//!         match &event.result {
+//!             Ok(path) => println!("backed up {} to {}", event.world, path.display()),
+//!             Err(e) => eprintln!("failed to back up {}: {e}", event.world),
+//!         }
+//!     }
+//! }
+//! # let _ = (backup_lobby, report_backups);
+//! ```
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use flume::{Receiver, Sender};
+use valence_server::layer::UpdateLayersPreClientSet;
+use valence_server::Server;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::worlds::{WorldError, Worlds};
+
+pub(crate) fn build(app: &mut App) {
+    app.init_resource::<BackupSettings>()
+        .init_resource::<BackupState>()
+        .add_event::<BackupRequest>()
+        .add_event::<BackupFinished>()
+        .configure_sets(PostUpdate, BackupSet.before(UpdateLayersPreClientSet))
+        // Periodic requests are sent early so that every system can see them.
+        .add_systems(PreUpdate, request_periodic_backups)
+        .add_systems(
+            PostUpdate,
+            (start_backups, finish_backups).chain().in_set(BackupSet),
+        );
+}
+
+/// Configures where and how often backups are written.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct BackupSettings {
+    /// The directory the archives are written to. Archives are named after
+    /// the world and the time the backup was taken, like
+    /// `lobby-1700000000.zip`.
+    ///
+    /// # Default Value
+    ///
+    /// `backups`
+    pub dir: PathBuf,
+    /// The number of ticks between periodic backups of every loaded world, or
+    /// `None` to only take backups on request.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub interval: Option<u64>,
+    /// The number of archives kept for each world. The oldest archives are
+    /// deleted after a backup is finished. `None` keeps every archive.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub keep: Option<usize>,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            dir: "backups".into(),
+            interval: None,
+            keep: None,
+        }
+    }
+}
+
+impl BackupSettings {
+    /// Returns the archives of the world named `world` in
+    /// [`BackupSettings::dir`], from oldest to newest.
+    pub fn archives(&self, world: &str) -> io::Result<Vec<PathBuf>> {
+        archives(&self.dir, world)
+    }
+}
+
+/// Requests a backup of the world named `world`. Requests for a world which
+/// is already being backed up are ignored.
+#[derive(Event, Clone, PartialEq, Eq, Debug)]
+pub struct BackupRequest {
+    pub world: String,
+}
+
+/// Sent when a backup requested with [`BackupRequest`] is finished.
+#[derive(Event, Debug)]
+pub struct BackupFinished {
+    pub world: String,
+    /// The path of the archive, or the error which stopped the backup.
+    pub result: Result<PathBuf, WorldError>,
+}
+
+/// The system set where requested backups are started, in [`PostUpdate`].
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BackupSet;
+
+#[derive(Resource)]
+struct BackupState {
+    /// The worlds with a backup on another thread.
+    in_progress: HashSet<String>,
+    sender: Sender<BackupFinished>,
+    receiver: Receiver<BackupFinished>,
+}
+
+impl Default for BackupState {
+    fn default() -> Self {
+        let (sender, receiver) = flume::unbounded();
+
+        Self {
+            in_progress: HashSet::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+fn request_periodic_backups(
+    settings: Res<BackupSettings>,
+    server: Res<Server>,
+    worlds: Res<Worlds>,
+    mut requests: EventWriter<BackupRequest>,
+) {
+    let Some(interval) = settings.interval.filter(|&n| n > 0) else {
+        return;
+    };
+
+    let tick = server.current_tick() as u64;
+
+    if tick > 0 && tick.is_multiple_of(interval) {
+        for (name, _) in worlds.loaded() {
+            requests.send(BackupRequest { world: name.into() });
+        }
+    }
+}
+
+fn start_backups(
+    mut requests: EventReader<BackupRequest>,
+    settings: Res<BackupSettings>,
+    worlds: Res<Worlds>,
+    mut state: ResMut<BackupState>,
+    mut finished: EventWriter<BackupFinished>,
+) {
+    for request in requests.read() {
+        let world = request.world.clone();
+
+        if state.in_progress.contains(&world) {
+            continue;
+        }
+
+        if !worlds.exists(&world) {
+            finished.send(BackupFinished {
+                result: Err(WorldError::NotFound(world.clone())),
+                world,
+            });
+            continue;
+        }
+
+        state.in_progress.insert(world.clone());
+
+        let source = worlds.path(&world);
+        let dir = settings.dir.clone();
+        let keep = settings.keep;
+        let sender = state.sender.clone();
+
+        thread::spawn(move || {
+            let result = write_backup(&source, &dir, &world, keep).map_err(WorldError::from);
+            let _ = sender.send(BackupFinished { world, result });
+        });
+    }
+}
+
+fn finish_backups(mut state: ResMut<BackupState>, mut finished: EventWriter<BackupFinished>) {
+    let state = &mut *state;
+
+    for event in state.receiver.try_iter() {
+        state.in_progress.remove(&event.world);
+        finished.send(event);
+    }
+}
+
+/// Archives the world directory `source` into `dir`, and deletes the oldest
+/// archives of the world if there are more than `keep`.
+fn write_backup(
+    source: &Path,
+    dir: &Path,
+    world: &str,
+    keep: Option<usize>,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let mut path = dir.join(format!("{world}-{timestamp}.zip"));
+    let mut n = 1;
+
+    while path.exists() {
+        path = dir.join(format!("{world}-{timestamp}-{n}.zip"));
+        n += 1;
+    }
+
+    // An unfinished archive never has the name of a finished one.
+    let partial = path.with_extension("zip.partial");
+
+    if let Err(e) = write_zip(source, &partial).and_then(|()| fs::rename(&partial, &path)) {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    if let Some(keep) = keep {
+        let archives = archives(dir, world)?;
+        let excess = archives.len().saturating_sub(keep);
+
+        for old in &archives[..excess] {
+            fs::remove_file(old)?;
+        }
+    }
+
+    Ok(path)
+}
+
+fn write_zip(source: &Path, dest: &Path) -> io::Result<()> {
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(dest)?));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    add_dir(&mut zip, source, "", options)?;
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+fn add_dir(
+    zip: &mut ZipWriter<BufWriter<File>>,
+    dir: &Path,
+    prefix: &str,
+    options: FileOptions,
+) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_unstable_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let Some(name) = entry
+            .file_name()
+            .to_str()
+            .map(|name| format!("{prefix}{name}"))
+        else {
+            continue;
+        };
+
+        if entry.file_type()?.is_dir() {
+            zip.add_directory(name.as_str(), options)?;
+            add_dir(zip, &entry.path(), &format!("{name}/"), options)?;
+        } else {
+            zip.start_file(name, options)?;
+            io::copy(&mut File::open(entry.path())?, zip)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn archives(dir: &Path, world: &str) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut archives = vec![];
+
+    for entry in entries {
+        let entry = entry?;
+
+        let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+
+        // `<world>-<timestamp>.zip` or `<world>-<timestamp>-<n>.zip`.
+        let Some(stamp) = name
+            .strip_prefix(world)
+            .and_then(|s| s.strip_prefix('-'))
+            .and_then(|s| s.strip_suffix(".zip"))
+        else {
+            continue;
+        };
+
+        let mut parts = stamp.split('-').map(str::parse::<u64>);
+
+        let key = match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(timestamp)), None, None) => (timestamp, 0),
+            (Some(Ok(timestamp)), Some(Ok(n)), None) => (timestamp, n),
+            _ => continue,
+        };
+
+        archives.push((key, entry.path()));
+    }
+
+    archives.sort_unstable();
+
+    Ok(archives.into_iter().map(|(_, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn backups_are_rotated() {
+        let root = env::temp_dir().join(format!("valence_backup_test_{}", std::process::id()));
+        let world = root.join("worlds").join("lobby");
+        let dir = root.join("backups");
+
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::write(world.join("region").join("r.0.0.mca"), [1, 2, 3]).unwrap();
+
+        // Archives of another world with a similar name are not counted.
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lobby-old-5.zip"), []).unwrap();
+
+        let paths: Vec<_> = (0..3)
+            .map(|_| write_backup(&world, &dir, "lobby", Some(2)).unwrap())
+            .collect();
+
+        assert_eq!(archives(&dir, "lobby").unwrap(), paths[1..]);
+        assert!(dir.join("lobby-old-5.zip").exists());
+
+        let zip = zip::ZipArchive::new(File::open(&paths[2]).unwrap()).unwrap();
+        let mut names: Vec<_> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, ["region/", "region/r.0.0.mca"]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,21 +1,36 @@
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::path::PathBuf;
-use std::thread;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use flume::{Receiver, Sender};
 use valence_server::client::{Client, OldView, View};
 use valence_server::entity::{EntityLayerId, OldEntityLayerId};
+use valence_server::layer::chunk::UnloadedChunk;
 use valence_server::layer::UpdateLayersPreClientSet;
 use valence_server::protocol::anyhow;
 use valence_server::registry::BiomeRegistry;
 use valence_server::{ChunkLayer, ChunkPos};
 
 use crate::parsing::{DimensionFolder, ParsedChunk};
+use crate::worlds::{delete_unloaded_worlds, remove_despawned_worlds, Worlds};
 
-type WorkerResult = anyhow::Result<Option<ParsedChunk>>;
+type WorkerResult = anyhow::Result<WorkerOutput>;
+
+#[derive(Debug)]
+enum WorkerOutput {
+    Parsed(ParsedChunk),
+    Generated(UnloadedChunk),
+    Empty,
+}
+
+/// Generates chunks which are missing from an Anvil level. Generators run on
+/// the level's chunk worker thread.
+pub type ChunkGenerator = Arc<dyn Fn(ChunkPos) -> UnloadedChunk + Send + Sync>;
 
 /// The order in which chunks should be processed by the anvil worker. Smaller
 /// values are sent first.
@@ -25,6 +40,8 @@ type Priority = u64;
 pub struct AnvilLevel {
     /// Chunk worker state to be moved to another thread.
     worker_state: Option<ChunkWorkerState>,
+    /// The chunk worker thread once it has been started.
+    worker: Option<JoinHandle<()>>,
     /// The set of chunk positions that should not be loaded or unloaded by
     /// the anvil system.
     ///
@@ -49,7 +66,9 @@ impl AnvilLevel {
                 dimension_folder: DimensionFolder::new(world_root, biomes),
                 sender: finished_sender,
                 receiver: pending_receiver,
+                generator: None,
             }),
+            worker: None,
             ignored_chunks: HashSet::new(),
             pending: HashMap::new(),
             sender: pending_sender,
@@ -57,6 +76,21 @@ impl AnvilLevel {
         }
     }
 
+    /// Sets the generator used for chunks which are not present in the level.
+    /// Generated chunks are inserted into the layer and reported with
+    /// [`ChunkLoadStatus::Generated`] instead of [`ChunkLoadStatus::Empty`].
+    ///
+    /// This has no effect once the level has been added to a layer.
+    pub fn with_generator(
+        mut self,
+        generator: impl Fn(ChunkPos) -> UnloadedChunk + Send + Sync + 'static,
+    ) -> Self {
+        if let Some(state) = &mut self.worker_state {
+            state.generator = Some(Arc::new(generator));
+        }
+        self
+    }
+
     /// Forces a chunk to be loaded at a specific position in this world. This
     /// will bypass [`AnvilLevel::ignored_chunks`].
     /// Note that the chunk will be unloaded next tick unless it has been added
@@ -77,9 +111,26 @@ impl AnvilLevel {
             }
         }
     }
+
+    /// Stops the chunk worker and waits for it to exit, so that the level's
+    /// files are no longer in use. Chunks which haven't been loaded yet are
+    /// discarded.
+    pub(crate) fn stop_worker(&mut self) {
+        self.worker_state = None;
+
+        // The worker exits once it can no longer receive positions or send
+        // back chunks, so replacing our ends of the channels stops it after
+        // the chunk it is working on.
+        self.sender = flume::unbounded().0;
+        self.receiver = flume::bounded(0).1;
+        self.pending.clear();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
 }
 
-#[derive(Debug)]
 struct ChunkWorkerState {
     /// The world folder containing the region folder where chunks are loaded
     /// from.
@@ -88,15 +139,35 @@ struct ChunkWorkerState {
     sender: Sender<(ChunkPos, WorkerResult)>,
     /// Receiver of pending chunks.
     receiver: Receiver<ChunkPos>,
+    /// Generator for chunks missing from the dimension folder.
+    generator: Option<ChunkGenerator>,
+}
+
+impl fmt::Debug for ChunkWorkerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkWorkerState")
+            .field("dimension_folder", &self.dimension_folder)
+            .field("sender", &self.sender)
+            .field("receiver", &self.receiver)
+            .field("generator", &self.generator.is_some())
+            .finish()
+    }
 }
 
 pub struct AnvilPlugin;
 
 impl Plugin for AnvilPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<ChunkLoadEvent>()
+        crate::backup::build(app);
+
+        app.init_resource::<Worlds>()
+            .add_event::<ChunkLoadEvent>()
             .add_event::<ChunkUnloadEvent>()
             .add_systems(PreUpdate, remove_unviewed_chunks)
+            .add_systems(
+                PostUpdate,
+                (remove_despawned_worlds, delete_unloaded_worlds),
+            )
             .add_systems(
                 PostUpdate,
                 (init_anvil, update_client_views, send_recv_chunks)
@@ -109,7 +180,7 @@ impl Plugin for AnvilPlugin {
 fn init_anvil(mut query: Query<&mut AnvilLevel, (Added<AnvilLevel>, With<ChunkLayer>)>) {
     for mut level in &mut query {
         if let Some(state) = level.worker_state.take() {
-            thread::spawn(move || anvil_worker(state));
+            level.worker = Some(thread::spawn(move || anvil_worker(state)));
         }
     }
 }
@@ -192,11 +263,15 @@ fn send_recv_chunks(
             anvil.pending.remove(&pos);
 
             let status = match res {
-                Ok(Some(ParsedChunk { chunk, timestamp })) => {
+                Ok(WorkerOutput::Parsed(ParsedChunk { chunk, timestamp })) => {
                     layer.insert_chunk(pos, chunk);
                     ChunkLoadStatus::Success { timestamp }
                 }
-                Ok(None) => ChunkLoadStatus::Empty,
+                Ok(WorkerOutput::Generated(chunk)) => {
+                    layer.insert_chunk(pos, chunk);
+                    ChunkLoadStatus::Generated
+                }
+                Ok(WorkerOutput::Empty) => ChunkLoadStatus::Empty,
                 Err(e) => ChunkLoadStatus::Failed(e),
             };
 
@@ -226,12 +301,18 @@ fn send_recv_chunks(
 
 fn anvil_worker(mut state: ChunkWorkerState) {
     while let Ok(pos) = state.receiver.recv() {
-        let res = state
-            .dimension_folder
-            .get_chunk(pos)
-            .map_err(anyhow::Error::from);
+        let res = match state.dimension_folder.get_chunk(pos) {
+            Ok(Some(parsed)) => Ok(WorkerOutput::Parsed(parsed)),
+            Ok(None) => match &state.generator {
+                Some(generator) => Ok(WorkerOutput::Generated(generator(pos))),
+                None => Ok(WorkerOutput::Empty),
+            },
+            Err(e) => Err(anyhow::Error::from(e)),
+        };
 
-        let _ = state.sender.send((pos, res));
+        if state.sender.send((pos, res)).is_err() {
+            break;
+        }
     }
 }
 
@@ -253,8 +334,12 @@ pub enum ChunkLoadStatus {
         /// epoch.
         timestamp: u32,
     },
-    /// The Anvil level does not have a chunk at the position. No chunk was
-    /// loaded.
+    /// The Anvil level does not have a chunk at the position, so a new chunk
+    /// was created by the level's [`ChunkGenerator`] and inserted into the
+    /// layer.
+    Generated,
+    /// The Anvil level does not have a chunk at the position and has no
+    /// generator. No chunk was loaded.
     Empty,
     /// An attempt was made to load the chunk, but something went wrong.
     Failed(anyhow::Error),
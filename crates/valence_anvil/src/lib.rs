@@ -35,10 +35,14 @@ use thiserror::Error;
 use valence_nbt::binary::{FromModifiedUtf8, ToModifiedUtf8};
 use valence_nbt::Compound;
 
+#[cfg(feature = "bevy_plugin")]
+pub mod backup;
 #[cfg(feature = "bevy_plugin")]
 mod bevy;
 #[cfg(feature = "parsing")]
 pub mod parsing;
+#[cfg(feature = "bevy_plugin")]
+pub mod worlds;
 
 const LRU_CACHE_SIZE: NonZeroUsize = match NonZeroUsize::new(256) {
     Some(n) => n,
@@ -53,6 +53,7 @@ impl DimensionFolder {
 }
 
 /// A chunk parsed to show block information, biome information etc.
+#[derive(Debug)]
 pub struct ParsedChunk {
     pub chunk: UnloadedChunk,
     pub timestamp: u32,
@@ -0,0 +1,347 @@
+//! Runtime management of world directories.
+//!
+//! The [`Worlds`] resource creates, loads, clones, and deletes world
+//! directories under a common root. Each loaded world is a layer entity with
+//! an [`AnvilLevel`] reading chunks from the world's directory.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use bevy_ecs::prelude::*;
+use thiserror::Error;
+use tracing::warn;
+use valence_server::registry::BiomeRegistry;
+use valence_server::{Despawned, LayerBundle};
+
+use crate::{AnvilLevel, ChunkGenerator};
+
+/// The name of the world directory a layer was loaded from by [`Worlds`].
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct WorldName(String);
+
+impl WorldName {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WorldError {
+    #[error("invalid world name `{0}`")]
+    InvalidName(String),
+    #[error("world `{0}` already exists")]
+    AlreadyExists(String),
+    #[error("world `{0}` does not exist")]
+    NotFound(String),
+    #[error("world `{0}` is already loaded")]
+    AlreadyLoaded(String),
+    #[error("world `{0}` is not loaded")]
+    NotLoaded(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Manages the world directories in a root directory, which is `worlds` by
+/// default. Every subdirectory of the root is a world with a `region` folder.
+///
+/// Loaded worlds are layer entities with an [`AnvilLevel`] and a
+/// [`WorldName`]. Giving a loaded world the [`Despawned`] component unloads it.
+#[derive(Resource, Debug)]
+pub struct Worlds {
+    root: PathBuf,
+    loaded: BTreeMap<String, Entity>,
+    /// Directories of deleted worlds to remove once their layers' chunk
+    /// workers have stopped.
+    deleted: Vec<(Entity, PathBuf)>,
+}
+
+impl Default for Worlds {
+    fn default() -> Self {
+        Self::new("worlds")
+    }
+}
+
+impl Worlds {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            loaded: BTreeMap::new(),
+            deleted: vec![],
+        }
+    }
+
+    /// The directory containing the world directories.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the directory of the world named `name`. The directory might
+    /// not exist.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    /// Returns whether a directory exists for the world named `name`.
+    pub fn exists(&self, name: &str) -> bool {
+        is_valid_name(name) && self.path(name).is_dir()
+    }
+
+    /// Returns the layer of the world named `name` if it is loaded.
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        self.loaded.get(name).copied()
+    }
+
+    /// Returns an iterator over the names and layers of the loaded worlds.
+    pub fn loaded(&self) -> impl Iterator<Item = (&str, Entity)> + '_ {
+        self.loaded
+            .iter()
+            .map(|(name, &layer)| (name.as_str(), layer))
+    }
+
+    /// Returns the names of all world directories in the root in sorted
+    /// order, whether they are loaded or not.
+    pub fn list(&self) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut names = vec![];
+
+        for entry in entries {
+            let entry = entry?;
+
+            if entry.file_type()?.is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    if is_valid_name(name) {
+                        names.push(name.to_owned());
+                    }
+                }
+            }
+        }
+
+        names.sort_unstable();
+
+        Ok(names)
+    }
+
+    /// Creates the directory for a new world and spawns `layer` with an
+    /// [`AnvilLevel`] reading from it. Chunks missing from the world are made
+    /// by `generator` if one is given.
+    pub fn create(
+        &mut self,
+        commands: &mut Commands,
+        name: &str,
+        layer: LayerBundle,
+        biomes: &BiomeRegistry,
+        generator: Option<ChunkGenerator>,
+    ) -> Result<Entity, WorldError> {
+        check_name(name)?;
+
+        let path = self.path(name);
+        if path.exists() {
+            return Err(WorldError::AlreadyExists(name.into()));
+        }
+
+        fs::create_dir_all(path.join("region"))?;
+
+        self.spawn(commands, name, layer, biomes, generator)
+    }
+
+    /// Spawns `layer` with an [`AnvilLevel`] reading from the existing world
+    /// named `name`.
+    pub fn load(
+        &mut self,
+        commands: &mut Commands,
+        name: &str,
+        layer: LayerBundle,
+        biomes: &BiomeRegistry,
+        generator: Option<ChunkGenerator>,
+    ) -> Result<Entity, WorldError> {
+        check_name(name)?;
+
+        if !self.path(name).is_dir() {
+            return Err(WorldError::NotFound(name.into()));
+        }
+
+        self.spawn(commands, name, layer, biomes, generator)
+    }
+
+    /// Loads the world named `name`, creating it first if it doesn't exist.
+    pub fn load_or_create(
+        &mut self,
+        commands: &mut Commands,
+        name: &str,
+        layer: LayerBundle,
+        biomes: &BiomeRegistry,
+        generator: Option<ChunkGenerator>,
+    ) -> Result<Entity, WorldError> {
+        if self.exists(name) {
+            self.load(commands, name, layer, biomes, generator)
+        } else {
+            self.create(commands, name, layer, biomes, generator)
+        }
+    }
+
+    /// Copies the directory of the world named `source` to a new world named
+    /// `dest`. The new world is not loaded.
+    ///
+    /// Only the files on disk are copied. Changes made to the chunks of a
+    /// loaded `source` world are not part of the copy.
+    pub fn clone_world(&self, source: &str, dest: &str) -> Result<(), WorldError> {
+        check_name(source)?;
+        check_name(dest)?;
+
+        let source_path = self.path(source);
+        if !source_path.is_dir() {
+            return Err(WorldError::NotFound(source.into()));
+        }
+
+        let dest_path = self.path(dest);
+        if dest_path.exists() {
+            return Err(WorldError::AlreadyExists(dest.into()));
+        }
+
+        copy_dir(&source_path, &dest_path)?;
+
+        Ok(())
+    }
+
+    /// Despawns the layer of the world named `name` at the end of the tick.
+    /// The world directory is kept.
+    pub fn unload(&mut self, commands: &mut Commands, name: &str) -> Result<Entity, WorldError> {
+        let layer = self
+            .loaded
+            .remove(name)
+            .ok_or_else(|| WorldError::NotLoaded(name.into()))?;
+
+        commands.entity(layer).insert(Despawned);
+
+        Ok(layer)
+    }
+
+    /// Unloads the world named `name` if it is loaded and removes its
+    /// directory.
+    ///
+    /// The directory of a loaded world can still be in use by the world's
+    /// chunk worker, so it is removed later in the tick, once the worker has
+    /// stopped. Until then the world still [exists](Self::exists), and errors
+    /// removing the directory are logged instead of returned.
+    pub fn delete(&mut self, commands: &mut Commands, name: &str) -> Result<(), WorldError> {
+        check_name(name)?;
+
+        let path = self.path(name);
+
+        if self.loaded.contains_key(name) {
+            if !path.is_dir() {
+                return Err(WorldError::NotFound(name.into()));
+            }
+
+            let layer = self.unload(commands, name)?;
+            self.deleted.push((layer, path));
+
+            return Ok(());
+        }
+
+        match fs::remove_dir_all(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(WorldError::NotFound(name.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn spawn(
+        &mut self,
+        commands: &mut Commands,
+        name: &str,
+        layer: LayerBundle,
+        biomes: &BiomeRegistry,
+        generator: Option<ChunkGenerator>,
+    ) -> Result<Entity, WorldError> {
+        if self.loaded.contains_key(name) {
+            return Err(WorldError::AlreadyLoaded(name.into()));
+        }
+
+        let mut level = AnvilLevel::new(self.path(name), biomes);
+
+        if let Some(generator) = generator {
+            level = level.with_generator(move |pos| generator(pos));
+        }
+
+        let entity = commands.spawn((layer, level, WorldName(name.into()))).id();
+
+        self.loaded.insert(name.into(), entity);
+
+        Ok(entity)
+    }
+}
+
+/// Forgets worlds whose layers were despawned without [`Worlds::unload`].
+pub(crate) fn remove_despawned_worlds(
+    mut worlds: ResMut<Worlds>,
+    layers: Query<(Entity, &WorldName), With<Despawned>>,
+) {
+    for (entity, name) in &layers {
+        if worlds.loaded.get(&name.0) == Some(&entity) {
+            worlds.loaded.remove(&name.0);
+        }
+    }
+}
+
+/// Removes the directories of worlds deleted while they were loaded.
+pub(crate) fn delete_unloaded_worlds(
+    mut worlds: ResMut<Worlds>,
+    mut levels: Query<&mut AnvilLevel>,
+) {
+    for (layer, path) in std::mem::take(&mut worlds.deleted) {
+        if let Ok(mut level) = levels.get_mut(layer) {
+            level.stop_worker();
+        }
+
+        if let Err(e) = fs::remove_dir_all(&path) {
+            if e.kind() != ErrorKind::NotFound {
+                warn!("failed to delete world directory {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+/// World names are used as directory names, so they are limited to a set of
+/// characters which can't escape the root directory.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn check_name(name: &str) -> Result<(), WorldError> {
+    if is_valid_name(name) {
+        Ok(())
+    } else {
+        Err(WorldError::InvalidName(name.into()))
+    }
+}
+
+fn copy_dir(source: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let dest = dest.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dest)?;
+        } else {
+            fs::copy(entry.path(), dest)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
+use std::collections::BTreeSet;
 
-use bevy_ecs::prelude::{Bundle, Component};
+use bevy_ecs::prelude::{Bundle, Component, Entity};
 use derive_more::{Deref, DerefMut};
 use valence_entity::EntityLayerId;
 use valence_server::protocol::packets::play::boss_bar_s2c::{
@@ -63,3 +64,83 @@ impl ToPacketAction for BossBarFlags {
 pub(crate) trait ToPacketAction {
     fn to_packet_action(&self) -> BossBarAction;
 }
+
+/// Component which makes a boss bar visible only to the listed clients,
+/// instead of every client viewing the bar's [`EntityLayerId`].
+///
+/// Clients are removed from the set automatically when they despawn.
+#[derive(Component, Clone, Default, Debug)]
+pub struct BossBarViewers {
+    pub(crate) viewers: BTreeSet<Entity>,
+    /// The viewers the boss bar has been sent to.
+    pub(crate) sent: BTreeSet<Entity>,
+}
+
+impl BossBarViewers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shows the boss bar to `client`. Returns `false` if the client was
+    /// already a viewer.
+    pub fn insert(&mut self, client: Entity) -> bool {
+        self.viewers.insert(client)
+    }
+
+    /// Hides the boss bar from `client`. Returns `false` if the client wasn't
+    /// a viewer.
+    pub fn remove(&mut self, client: Entity) -> bool {
+        self.viewers.remove(&client)
+    }
+
+    pub fn contains(&self, client: Entity) -> bool {
+        self.viewers.contains(&client)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.viewers.iter().copied()
+    }
+}
+
+/// Attaches a boss bar to an entity. The health of the boss bar follows the
+/// health of the entity, and the boss bar is despawned together with the
+/// entity.
+///
+/// If `range` is set, the [`BossBarViewers`] of the boss bar are updated to
+/// the clients within `range` blocks of the entity which can see its layer.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct BossBarAttachment {
+    pub entity: Entity,
+    pub range: Option<f64>,
+}
+
+/// The bundle of components for a boss bar attached to an entity.
+#[derive(Bundle)]
+pub struct AttachedBossBarBundle {
+    pub id: UniqueId,
+    pub title: BossBarTitle,
+    pub health: BossBarHealth,
+    pub style: BossBarStyle,
+    pub flags: BossBarFlags,
+    pub viewers: BossBarViewers,
+    pub attachment: BossBarAttachment,
+}
+
+impl AttachedBossBarBundle {
+    /// Creates a boss bar for `entity`, shown to clients within `range`
+    /// blocks.
+    pub fn new(entity: Entity, range: f64) -> Self {
+        Self {
+            id: UniqueId::default(),
+            title: BossBarTitle::default(),
+            health: BossBarHealth(1.0),
+            style: BossBarStyle::default(),
+            flags: BossBarFlags::default(),
+            viewers: BossBarViewers::default(),
+            attachment: BossBarAttachment {
+                entity,
+                range: Some(range),
+            },
+        }
+    }
+}
@@ -34,6 +34,8 @@ use valence_server::protocol::WritePacket;
 use valence_server::{ChunkView, Despawned, EntityLayer, Layer, UniqueId};
 
 mod components;
+mod viewers;
+
 pub use components::*;
 use valence_entity::{EntityLayerId, OldPosition, Position};
 
@@ -53,12 +55,33 @@ impl Plugin for BossBarPlugin {
                 boss_bar_despawn,
             )
                 .before(UpdateLayersPreClientSet),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                viewers::update_attached_boss_bars,
+                // So boss bars of despawned entities are removed this tick.
+                apply_deferred,
+                viewers::update_boss_bar_viewers,
+                (
+                    viewers::update_viewer_boss_bar::<BossBarTitle>,
+                    viewers::update_viewer_boss_bar::<BossBarHealth>,
+                    viewers::update_viewer_boss_bar::<BossBarStyle>,
+                    viewers::update_viewer_boss_bar::<BossBarFlags>,
+                    viewers::viewer_boss_bar_despawn,
+                ),
+            )
+                .chain()
+                .before(UpdateLayersPreClientSet),
         );
     }
 }
 
 fn update_boss_bar<T: Component + ToPacketAction>(
-    boss_bars_query: Query<(&UniqueId, &T, &EntityLayerId, Option<&Position>), Changed<T>>,
+    boss_bars_query: Query<
+        (&UniqueId, &T, &EntityLayerId, Option<&Position>),
+        (Changed<T>, Without<BossBarViewers>),
+    >,
     mut entity_layers_query: Query<&mut EntityLayer>,
 ) {
     for (id, part, entity_layer_id, pos) in boss_bars_query.iter() {
@@ -89,15 +112,18 @@ fn update_boss_bar_layer_view(
         ),
         Changed<VisibleEntityLayers>,
     >,
-    boss_bars_query: Query<(
-        &UniqueId,
-        &BossBarTitle,
-        &BossBarHealth,
-        &BossBarStyle,
-        &BossBarFlags,
-        &EntityLayerId,
-        Option<&Position>,
-    )>,
+    boss_bars_query: Query<
+        (
+            &UniqueId,
+            &BossBarTitle,
+            &BossBarHealth,
+            &BossBarStyle,
+            &BossBarFlags,
+            &EntityLayerId,
+            Option<&Position>,
+        ),
+        Without<BossBarViewers>,
+    >,
 ) {
     for (
         mut client,
@@ -183,15 +209,18 @@ fn update_boss_bar_chunk_view(
         ),
         Changed<Position>,
     >,
-    boss_bars_query: Query<(
-        &UniqueId,
-        &BossBarTitle,
-        &BossBarHealth,
-        &BossBarStyle,
-        &BossBarFlags,
-        &EntityLayerId,
-        &Position,
-    )>,
+    boss_bars_query: Query<
+        (
+            &UniqueId,
+            &BossBarTitle,
+            &BossBarHealth,
+            &BossBarStyle,
+            &BossBarFlags,
+            &EntityLayerId,
+            &Position,
+        ),
+        Without<BossBarViewers>,
+    >,
 ) {
     for (
         mut client,
@@ -238,7 +267,10 @@ fn update_boss_bar_chunk_view(
 }
 
 fn boss_bar_despawn(
-    boss_bars_query: Query<(&UniqueId, &EntityLayerId, Option<&Position>), With<Despawned>>,
+    boss_bars_query: Query<
+        (&UniqueId, &EntityLayerId, Option<&Position>),
+        (With<Despawned>, With<BossBarTitle>, Without<BossBarViewers>),
+    >,
     mut entity_layer_query: Query<&mut EntityLayer>,
 ) {
     for (id, entity_layer_id, position) in boss_bars_query.iter() {
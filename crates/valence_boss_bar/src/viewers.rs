@@ -0,0 +1,162 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+use bevy_ecs::prelude::*;
+use valence_entity::attributes::{EntityAttribute, EntityAttributes};
+use valence_entity::living::Health;
+use valence_entity::{EntityLayerId, Position};
+use valence_server::client::{Client, VisibleEntityLayers};
+use valence_server::protocol::packets::play::boss_bar_s2c::{BossBarAction, BossBarFlags};
+use valence_server::protocol::packets::play::BossBarS2c;
+use valence_server::protocol::WritePacket;
+use valence_server::{Despawned, UniqueId};
+
+use crate::components::ToPacketAction;
+use crate::{BossBarAttachment, BossBarHealth, BossBarStyle, BossBarTitle, BossBarViewers};
+
+/// Copies the health of attached entities to their boss bars and recomputes
+/// the viewers of boss bars with a range.
+pub(crate) fn update_attached_boss_bars(
+    mut boss_bars: Query<
+        (
+            Entity,
+            &BossBarAttachment,
+            &mut BossBarHealth,
+            &mut BossBarViewers,
+        ),
+        Without<Despawned>,
+    >,
+    targets: Query<
+        (
+            &Position,
+            &EntityLayerId,
+            Option<&Health>,
+            Option<&EntityAttributes>,
+        ),
+        Without<Despawned>,
+    >,
+    clients: Query<(Entity, &Position, &VisibleEntityLayers), (With<Client>, Without<Despawned>)>,
+    mut commands: Commands,
+) {
+    for (entity, attachment, mut health, mut viewers) in &mut boss_bars {
+        let Ok((pos, layer, target_health, attributes)) = targets.get(attachment.entity) else {
+            // The entity is gone, so is its boss bar.
+            commands.entity(entity).insert(Despawned);
+            continue;
+        };
+
+        if let Some(target_health) = target_health {
+            let max_health = attributes
+                .and_then(|a| a.get_compute_value(EntityAttribute::GenericMaxHealth))
+                .unwrap_or(20.0) as f32;
+
+            let fraction = (target_health.0 / max_health).clamp(0.0, 1.0);
+
+            if health.0 != fraction {
+                health.0 = fraction;
+            }
+        }
+
+        if let Some(range) = attachment.range {
+            let in_range: BTreeSet<_> = clients
+                .iter()
+                .filter(|(_, client_pos, visible)| {
+                    visible.0.contains(&layer.0) && client_pos.0.distance(pos.0) <= range
+                })
+                .map(|(client, _, _)| client)
+                .collect();
+
+            if viewers.viewers != in_range {
+                viewers.viewers = in_range;
+            }
+        }
+    }
+}
+
+/// Sends the boss bar to new viewers and removes it from old ones.
+pub(crate) fn update_boss_bar_viewers(
+    mut boss_bars: Query<
+        (
+            &UniqueId,
+            &BossBarTitle,
+            &BossBarHealth,
+            &BossBarStyle,
+            &BossBarFlags,
+            &mut BossBarViewers,
+        ),
+        Changed<BossBarViewers>,
+    >,
+    mut clients: Query<&mut Client, Without<Despawned>>,
+) {
+    for (id, title, health, style, flags, mut viewers) in &mut boss_bars {
+        let viewers = viewers.bypass_change_detection();
+
+        // Forget about viewers which have despawned.
+        viewers.viewers.retain(|&viewer| clients.contains(viewer));
+
+        for &removed in viewers.sent.difference(&viewers.viewers) {
+            if let Ok(mut client) = clients.get_mut(removed) {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::Remove,
+                });
+            }
+        }
+
+        for &added in viewers.viewers.difference(&viewers.sent) {
+            if let Ok(mut client) = clients.get_mut(added) {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::Add {
+                        title: Cow::Borrowed(&title.0),
+                        health: health.0,
+                        color: style.color,
+                        division: style.division,
+                        flags: *flags,
+                    },
+                });
+            }
+        }
+
+        viewers.sent.clone_from(&viewers.viewers);
+    }
+}
+
+pub(crate) fn update_viewer_boss_bar<T: Component + ToPacketAction>(
+    boss_bars: Query<(&UniqueId, Ref<T>, &BossBarViewers), Changed<T>>,
+    mut clients: Query<&mut Client, Without<Despawned>>,
+) {
+    for (id, part, viewers) in &boss_bars {
+        // New boss bars are sent in full by `update_boss_bar_viewers`.
+        if part.is_added() {
+            continue;
+        }
+
+        let packet = BossBarS2c {
+            id: id.0,
+            action: part.to_packet_action(),
+        };
+
+        for &viewer in &viewers.sent {
+            if let Ok(mut client) = clients.get_mut(viewer) {
+                client.write_packet(&packet);
+            }
+        }
+    }
+}
+
+pub(crate) fn viewer_boss_bar_despawn(
+    boss_bars: Query<(&UniqueId, &BossBarViewers), With<Despawned>>,
+    mut clients: Query<&mut Client, Without<Despawned>>,
+) {
+    for (id, viewers) in &boss_bars {
+        for &viewer in &viewers.sent {
+            if let Ok(mut client) = clients.get_mut(viewer) {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::Remove,
+                });
+            }
+        }
+    }
+}
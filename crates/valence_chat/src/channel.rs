@@ -0,0 +1,126 @@
+use bevy_ecs::prelude::*;
+use valence_lang::keys;
+use valence_server::text::{Color, IntoText};
+use valence_server::Text;
+
+/// Component for clients selecting who receives the messages they send.
+/// Clients without this component chat in [`ChatChannel::Global`].
+#[derive(Component, Copy, Clone, PartialEq, Debug, Default)]
+pub enum ChatChannel {
+    /// Every client receives the message.
+    #[default]
+    Global,
+    /// Clients viewing the same chunk layer within `radius` blocks of the
+    /// sender receive the message.
+    Local { radius: f64 },
+    /// Clients on the same scoreboard team as the sender receive the message.
+    Team,
+}
+
+/// Information about a chat message passed to the [`ChatRouter`] formatter.
+#[derive(Copy, Clone, Debug)]
+pub struct ChatContext<'a> {
+    /// The client that sent the message.
+    pub sender: Entity,
+    pub username: &'a str,
+    pub message: &'a str,
+    pub channel: ChatChannel,
+    /// The display name of the sender's team if the message was sent to
+    /// [`ChatChannel::Team`].
+    pub team: Option<&'a Text>,
+}
+
+/// Returns the text shown for a chat message, or `None` to use the vanilla
+/// format.
+pub type ChatFormatter = Box<dyn Fn(&ChatContext) -> Option<Text> + Send + Sync>;
+
+/// Information about an executed command passed to the [`ChatRouter`] spy
+/// formatter.
+#[derive(Copy, Clone, Debug)]
+pub struct CommandSpyContext<'a> {
+    /// The client that executed the command.
+    pub sender: Entity,
+    pub username: &'a str,
+    /// The command without the leading slash.
+    pub command: &'a str,
+}
+
+/// Returns the text shown to [`CommandSpy`](crate::CommandSpy) clients for an
+/// executed command, or `None` to hide the command from them.
+pub type CommandSpyFormatter = Box<dyn Fn(&CommandSpyContext) -> Option<Text> + Send + Sync>;
+
+/// Routes [`ChatEvent`](crate::ChatEvent)s to the clients in the sender's
+/// [`ChatChannel`].
+///
+/// Signed messages in the vanilla format are sent as player chat messages,
+/// which keeps their signature intact. Messages which aren't signed or are
+/// changed by the formatter are sent as system messages instead.
+#[derive(Resource)]
+pub struct ChatRouter {
+    /// Whether chat events are routed at all. Disable this to handle
+    /// [`ChatEvent`](crate::ChatEvent)s yourself. Enabled by default.
+    pub enabled: bool,
+    formatter: Option<ChatFormatter>,
+    spy_formatter: Option<CommandSpyFormatter>,
+}
+
+impl Default for ChatRouter {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            formatter: None,
+            spy_formatter: None,
+        }
+    }
+}
+
+impl ChatRouter {
+    /// Sets the function which formats messages, replacing the previous one.
+    pub fn set_formatter(
+        &mut self,
+        formatter: impl Fn(&ChatContext) -> Option<Text> + Send + Sync + 'static,
+    ) {
+        self.formatter = Some(Box::new(formatter));
+    }
+
+    /// Removes the formatter so all messages use the vanilla format.
+    pub fn clear_formatter(&mut self) {
+        self.formatter = None;
+    }
+
+    pub(crate) fn format(&self, ctx: &ChatContext) -> Option<Text> {
+        self.formatter.as_ref().and_then(|f| f(ctx))
+    }
+
+    /// Sets the function which formats the commands shown to
+    /// [`CommandSpy`](crate::CommandSpy) clients, replacing the previous one.
+    pub fn set_spy_formatter(
+        &mut self,
+        formatter: impl Fn(&CommandSpyContext) -> Option<Text> + Send + Sync + 'static,
+    ) {
+        self.spy_formatter = Some(Box::new(formatter));
+    }
+
+    /// Removes the spy formatter so commands are shown in the format of
+    /// vanilla command feedback, such as `[Steve: /gamemode creative]`.
+    pub fn clear_spy_formatter(&mut self) {
+        self.spy_formatter = None;
+    }
+
+    pub(crate) fn format_spy(&self, ctx: &CommandSpyContext) -> Option<Text> {
+        match &self.spy_formatter {
+            Some(f) => f(ctx),
+            None => Some(
+                Text::translate(
+                    keys::CHAT_TYPE_ADMIN,
+                    [
+                        ctx.username.to_owned().into_text(),
+                        format!("/{}", ctx.command).into_text(),
+                    ],
+                )
+                .color(Color::GRAY)
+                .italic(),
+            ),
+        }
+    }
+}
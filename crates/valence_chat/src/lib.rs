@@ -0,0 +1,495 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod channel;
+mod session;
+
+use std::borrow::Cow;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use channel::*;
+use rsa::pkcs8::DecodePublicKey;
+pub use rsa::RsaPublicKey;
+pub use session::{ChatError, ChatSession, ChatState};
+use valence_lang::keys;
+use valence_scoreboard::{Team, TeamMembers, TeamSettings};
+use valence_server::client::{
+    Client, DisconnectClient, FlushPacketsSet, Username, VisibleChunkLayer,
+};
+use valence_server::entity::Position;
+use valence_server::event_loop::{EventLoopPreUpdate, PacketEvent};
+use valence_server::layer::UpdateLayersPreClientSet;
+use valence_server::message::SendMessage;
+use valence_server::protocol::packets::play::chat_message_s2c::{
+    MessageFilterType, MessageSignature,
+};
+use valence_server::protocol::packets::play::{
+    player_list_s2c, ChatMessageC2s, ChatMessageS2c, CommandExecutionC2s, MessageAcknowledgmentC2s,
+    PlayerListS2c, PlayerSessionC2s,
+};
+use valence_server::protocol::{Bounded, VarInt, WritePacket};
+use valence_server::protocol_error::ProtocolErrorSink;
+use valence_server::text::{Color, IntoText};
+use valence_server::{Despawned, Text, UniqueId};
+
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatSettings>()
+            .init_resource::<ChatRouter>()
+            .add_event::<ChatEvent>()
+            .add_systems(EventLoopPreUpdate, (handle_chat_packets, spy_on_commands))
+            .add_systems(
+                PostUpdate,
+                (
+                    init_chat_state,
+                    // Sessions are sent after the player list entries exist.
+                    init_chat_sessions_for_clients,
+                    broadcast_chat_sessions,
+                    route_chat_messages,
+                )
+                    .chain()
+                    .after(UpdateLayersPreClientSet)
+                    .before(FlushPacketsSet),
+            );
+    }
+}
+
+/// The index of `minecraft:chat` in the chat type registry.
+const CHAT_TYPE: i32 = 0;
+/// The index of `minecraft:team_msg_command_incoming` in the chat type
+/// registry.
+const TEAM_CHAT_TYPE: i32 = 5;
+
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ChatSettings {
+    /// Disconnect clients which send chat messages without a valid signature.
+    /// This should only be enabled in online mode, because clients can't sign
+    /// their messages otherwise. Disabled by default.
+    pub enforce_secure_chat: bool,
+    /// Mojang's public key which signs the public keys of chat sessions. The
+    /// signature of session keys is not checked without it.
+    pub mojang_public_key: Option<RsaPublicKey>,
+    /// Block chat messages so clients can only run commands, as on lobby
+    /// servers. Blocked messages are not sent as [`ChatEvent`]s, and their
+    /// senders are told that the message could not be sent. Disabled by
+    /// default.
+    pub commands_only: bool,
+}
+
+impl ChatSettings {
+    /// Sets [`mojang_public_key`](Self::mojang_public_key) from its DER
+    /// encoding, as found in the Yggdrasil session public key file.
+    pub fn set_mojang_public_key_der(&mut self, der: &[u8]) -> Result<(), ChatError> {
+        self.mojang_public_key =
+            Some(RsaPublicKey::from_public_key_der(der).map_err(|_| ChatError::InvalidPublicKey)?);
+
+        Ok(())
+    }
+}
+
+/// Sent when a client sends a chat message which passed validation.
+#[derive(Event, Clone, Debug)]
+pub struct ChatEvent {
+    pub client: Entity,
+    pub message: Box<str>,
+    /// Unix timestamp in milliseconds.
+    pub timestamp: u64,
+    /// Present if the client signed the message and the signature is valid.
+    pub signed: Option<SignedMessage>,
+}
+
+/// Marker component for clients which are shown the commands executed by
+/// other clients, such as staff members. See [`ChatRouter::set_spy_formatter`]
+/// to change how the commands are shown.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct CommandSpy;
+
+/// The signature of a chat message and the data needed by other clients to
+/// verify it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SignedMessage {
+    pub signature: Box<[u8; 256]>,
+    pub salt: u64,
+    /// The index of the message in the sender's chat session.
+    pub index: i32,
+    /// The signatures of the messages the sender had seen, oldest first.
+    pub last_seen: Vec<[u8; 256]>,
+}
+
+fn init_chat_state(clients: Query<Entity, Added<Client>>, mut commands: Commands) {
+    for entity in &clients {
+        commands.entity(entity).insert(ChatState::default());
+    }
+}
+
+fn handle_chat_packets(
+    mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    mut clients: Query<(&mut Client, &UniqueId, &mut ChatState, Option<&ChatSession>)>,
+    settings: Res<ChatSettings>,
+    mut events: EventWriter<ChatEvent>,
+    mut commands: Commands,
+) {
+    for packet in packets.read() {
+        let Ok((mut client, uuid, mut state, session)) = clients.get_mut(packet.client) else {
+            continue;
+        };
+
+        let result = if let Some(pkt) = packet.decode_reporting::<PlayerSessionC2s>(&errors) {
+            ChatSession::new(
+                uuid.0,
+                pkt.session_id,
+                pkt.expires_at,
+                pkt.public_key_data.0,
+                pkt.key_signature.0,
+                settings.mojang_public_key.as_ref(),
+            )
+            .map(|session| {
+                // A new session starts a new chain of messages.
+                state.next_index = 0;
+                commands.entity(packet.client).insert(session);
+            })
+        } else if let Some(pkt) = packet.decode_reporting::<MessageAcknowledgmentC2s>(&errors) {
+            state.last_seen.apply_offset(pkt.message_count.0)
+        } else if let Some(pkt) = packet.decode_reporting::<ChatMessageC2s>(&errors) {
+            // Blocked messages are still validated to keep track of the chain
+            // of signed messages.
+            validate_chat_message(&pkt, uuid, &mut state, session, &settings).map(|signed| {
+                if settings.commands_only {
+                    client.send_chat_message(
+                        Text::translate(keys::CHAT_CANNOT_SEND, []).color(Color::RED),
+                    );
+                    return;
+                }
+
+                events.send(ChatEvent {
+                    client: packet.client,
+                    message: pkt.message.0.into(),
+                    timestamp: pkt.timestamp,
+                    signed,
+                });
+            })
+        } else {
+            Ok(())
+        };
+
+        if let Err(e) = result {
+            commands.add(DisconnectClient {
+                client: packet.client,
+                reason: e.disconnect_reason(),
+            });
+        }
+    }
+}
+
+/// Returns the signature of the message if it can be preserved.
+fn validate_chat_message(
+    pkt: &ChatMessageC2s,
+    uuid: &UniqueId,
+    state: &mut ChatState,
+    session: Option<&ChatSession>,
+    settings: &ChatSettings,
+) -> Result<Option<SignedMessage>, ChatError> {
+    if pkt.timestamp < state.last_timestamp {
+        return Err(ChatError::OutOfOrder);
+    }
+
+    state.last_timestamp = pkt.timestamp;
+
+    let last_seen = state
+        .last_seen
+        .apply_update(pkt.message_count.0, &pkt.acknowledgement)?;
+
+    let (Some(session), Some(signature)) = (session, pkt.signature) else {
+        return if settings.enforce_secure_chat {
+            Err(ChatError::Unsigned)
+        } else {
+            Ok(None)
+        };
+    };
+
+    let index = state.next_index;
+
+    session.verify(
+        uuid.0,
+        index,
+        pkt.salt,
+        pkt.timestamp,
+        pkt.message.0,
+        &last_seen,
+        signature,
+    )?;
+
+    state.next_index += 1;
+
+    Ok(Some(SignedMessage {
+        signature: Box::new(*signature),
+        salt: pkt.salt,
+        index,
+        last_seen,
+    }))
+}
+
+/// Shows the commands executed by clients to the other [`CommandSpy`]
+/// clients.
+fn spy_on_commands(
+    mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    router: Res<ChatRouter>,
+    senders: Query<&Username>,
+    mut spies: Query<(Entity, &mut Client), (With<CommandSpy>, Without<Despawned>)>,
+) {
+    if spies.is_empty() {
+        // Skip the commands sent while there are no spies.
+        packets.clear();
+        return;
+    }
+
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode_reporting::<CommandExecutionC2s>(&errors) else {
+            continue;
+        };
+
+        let Ok(username) = senders.get(packet.client) else {
+            continue;
+        };
+
+        let Some(text) = router.format_spy(&CommandSpyContext {
+            sender: packet.client,
+            username: &username.0,
+            command: pkt.command.0,
+        }) else {
+            continue;
+        };
+
+        for (spy, mut client) in &mut spies {
+            if spy != packet.client {
+                client.send_chat_message(&text);
+            }
+        }
+    }
+}
+
+fn init_chat_sessions_for_clients(
+    mut clients: Query<&mut Client, Added<Client>>,
+    sessions: Query<(&UniqueId, &ChatSession)>,
+) {
+    if clients.is_empty() {
+        return;
+    }
+
+    let entries: Vec<_> = sessions
+        .iter()
+        .map(|(uuid, session)| player_list_s2c::PlayerListEntry {
+            player_uuid: uuid.0,
+            chat_data: Some(session.chat_data()),
+            ..Default::default()
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return;
+    }
+
+    for mut client in &mut clients {
+        client.write_packet(&PlayerListS2c {
+            actions: player_list_s2c::PlayerListActions::new().with_initialize_chat(true),
+            entries: Cow::Borrowed(&entries),
+        });
+    }
+}
+
+/// Sends new chat sessions to all clients so they can verify the messages
+/// signed with them.
+fn broadcast_chat_sessions(
+    sessions: Query<(&UniqueId, &ChatSession), Changed<ChatSession>>,
+    mut clients: Query<&mut Client, Without<Despawned>>,
+) {
+    let entries: Vec<_> = sessions
+        .iter()
+        .map(|(uuid, session)| player_list_s2c::PlayerListEntry {
+            player_uuid: uuid.0,
+            chat_data: Some(session.chat_data()),
+            ..Default::default()
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return;
+    }
+
+    for mut client in &mut clients {
+        client.write_packet(&PlayerListS2c {
+            actions: player_list_s2c::PlayerListActions::new().with_initialize_chat(true),
+            entries: Cow::Borrowed(&entries),
+        });
+    }
+}
+
+fn route_chat_messages(
+    mut events: EventReader<ChatEvent>,
+    router: Res<ChatRouter>,
+    senders: Query<(
+        &UniqueId,
+        &Username,
+        Option<&ChatChannel>,
+        &Position,
+        &VisibleChunkLayer,
+    )>,
+    mut clients: Query<
+        (
+            Entity,
+            &mut Client,
+            &mut ChatState,
+            &Username,
+            &Position,
+            &VisibleChunkLayer,
+        ),
+        Without<Despawned>,
+    >,
+    teams: Query<(&Team, &TeamMembers, Option<&TeamSettings>), Without<Despawned>>,
+    mut commands: Commands,
+) {
+    if !router.enabled {
+        events.clear();
+        return;
+    }
+
+    for event in events.read() {
+        let Ok((uuid, username, channel, sender_pos, sender_layer)) = senders.get(event.client)
+        else {
+            continue;
+        };
+
+        let channel = channel.copied().unwrap_or_default();
+
+        let (team_name, members) = if channel == ChatChannel::Team {
+            let Some((team, members, settings)) = teams
+                .iter()
+                .find(|(_, members, _)| members.contains(&username.0))
+            else {
+                if let Ok((_, mut client, ..)) = clients.get_mut(event.client) {
+                    client.send_chat_message(Text::translate(
+                        keys::COMMANDS_TEAMMSG_FAILED_NOTEAM,
+                        [],
+                    ));
+                }
+                continue;
+            };
+
+            let name = match settings {
+                Some(settings) if !settings.display_name.is_empty() => {
+                    settings.display_name.clone()
+                }
+                _ => team.name().to_owned().into_text(),
+            };
+
+            (Some(name), Some(members))
+        } else {
+            (None, None)
+        };
+
+        let formatted = router.format(&ChatContext {
+            sender: event.client,
+            username: &username.0,
+            message: &event.message,
+            channel,
+            team: team_name.as_ref(),
+        });
+
+        let sender_name = username.0.clone().into_text();
+
+        // Messages are sent as system messages if the signature can't be
+        // preserved.
+        let system_message = match (formatted, &event.signed) {
+            (None, Some(_)) => None,
+            (Some(formatted), _) => Some(formatted),
+            (None, None) => {
+                let message = Text::text(event.message.to_string());
+
+                Some(match &team_name {
+                    Some(team_name) => Text::translate(
+                        keys::CHAT_TYPE_TEAM_TEXT,
+                        [team_name.clone(), sender_name.clone(), message],
+                    ),
+                    None => Text::translate(keys::CHAT_TYPE_TEXT, [sender_name.clone(), message]),
+                })
+            }
+        };
+
+        let previous_messages: Vec<_> = event
+            .signed
+            .iter()
+            .flat_map(|signed| &signed.last_seen)
+            .map(|signature| MessageSignature {
+                message_id: -1,
+                signature: Some(signature),
+            })
+            .collect();
+
+        for (entity, mut client, mut state, recipient_name, pos, layer) in &mut clients {
+            let receives = match channel {
+                ChatChannel::Global => true,
+                ChatChannel::Local { radius } => {
+                    layer.0 == sender_layer.0 && pos.0.distance(sender_pos.0) <= radius
+                }
+                ChatChannel::Team => members.is_some_and(|m| m.contains(&recipient_name.0)),
+            };
+
+            if !receives {
+                continue;
+            }
+
+            if let Some(text) = &system_message {
+                client.send_chat_message(text);
+            } else if let Some(signed) = &event.signed {
+                client.write_packet(&ChatMessageS2c {
+                    sender: uuid.0,
+                    index: VarInt(signed.index),
+                    message_signature: Some(&signed.signature),
+                    message: Bounded(&*event.message),
+                    timestamp: event.timestamp,
+                    salt: signed.salt,
+                    previous_messages: previous_messages.clone(),
+                    unsigned_content: None,
+                    filter_type: MessageFilterType::PassThrough,
+                    filter_type_bits: None,
+                    chat_type: VarInt(if team_name.is_some() {
+                        TEAM_CHAT_TYPE
+                    } else {
+                        CHAT_TYPE
+                    }),
+                    network_name: Cow::Borrowed(&sender_name),
+                    network_target_name: team_name.as_ref().map(Cow::Borrowed),
+                });
+
+                if let Err(e) = state.last_seen.add_pending(&signed.signature) {
+                    commands.add(DisconnectClient {
+                        client: entity,
+                        reason: e.disconnect_reason(),
+                    });
+                }
+            }
+        }
+    }
+}
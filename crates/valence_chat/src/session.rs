@@ -0,0 +1,494 @@
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy_ecs::prelude::*;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use valence_lang::keys;
+use valence_server::protocol::packets::play::player_list_s2c::ChatData;
+use valence_server::protocol::FixedBitSet;
+use valence_server::uuid::Uuid;
+use valence_server::Text;
+
+/// The number of messages a client acknowledges in each chat message.
+pub(crate) const LAST_SEEN_COUNT: usize = 20;
+
+/// The number of unacknowledged messages after which a client is
+/// disconnected, matching the vanilla server.
+pub(crate) const MAX_PENDING_MESSAGES: usize = 4096;
+
+/// Component for clients holding the chat session they sign their messages
+/// with. Inserted when the client sends its public key.
+#[derive(Component, Clone, Debug)]
+pub struct ChatSession {
+    session_id: Uuid,
+    expires_at: i64,
+    public_key: RsaPublicKey,
+    public_key_der: Box<[u8]>,
+    key_signature: Box<[u8]>,
+}
+
+impl ChatSession {
+    /// Parses and validates a session sent by the client `uuid`. The key
+    /// signature is only checked if `mojang_public_key` is present.
+    pub(crate) fn new(
+        uuid: Uuid,
+        session_id: Uuid,
+        expires_at: i64,
+        public_key_der: &[u8],
+        key_signature: &[u8],
+        mojang_public_key: Option<&RsaPublicKey>,
+    ) -> Result<Self, ChatError> {
+        let public_key = RsaPublicKey::from_public_key_der(public_key_der)
+            .map_err(|_| ChatError::InvalidPublicKey)?;
+
+        if expires_at < unix_millis() {
+            return Err(ChatError::ExpiredPublicKey);
+        }
+
+        if let Some(mojang_public_key) = mojang_public_key {
+            let mut hasher = Sha1::new();
+            hasher.update(uuid.as_bytes());
+            hasher.update(expires_at.to_be_bytes());
+            hasher.update(public_key_der);
+
+            mojang_public_key
+                .verify(
+                    Pkcs1v15Sign::new::<Sha1>(),
+                    &hasher.finalize(),
+                    key_signature,
+                )
+                .map_err(|_| ChatError::InvalidKeySignature)?;
+        }
+
+        Ok(Self {
+            session_id,
+            expires_at,
+            public_key,
+            public_key_der: public_key_der.into(),
+            key_signature: key_signature.into(),
+        })
+    }
+
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// Unix timestamp in milliseconds after which the session can no longer
+    /// be used to sign messages.
+    pub fn expires_at(&self) -> i64 {
+        self.expires_at
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < unix_millis()
+    }
+
+    pub fn public_key(&self) -> &RsaPublicKey {
+        &self.public_key
+    }
+
+    /// Checks the `signature` of a message sent by the client `sender`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn verify(
+        &self,
+        sender: Uuid,
+        index: i32,
+        salt: u64,
+        timestamp: u64,
+        message: &str,
+        last_seen: &[[u8; 256]],
+        signature: &[u8; 256],
+    ) -> Result<(), ChatError> {
+        if self.is_expired() {
+            return Err(ChatError::ExpiredPublicKey);
+        }
+
+        let hash = message_hash(
+            sender,
+            self.session_id,
+            index,
+            salt,
+            timestamp,
+            message,
+            last_seen,
+        );
+
+        self.public_key
+            .verify(Pkcs1v15Sign::new::<Sha256>(), &hash, signature)
+            .map_err(|_| ChatError::InvalidSignature)
+    }
+
+    pub(crate) fn chat_data(&self) -> ChatData<'_> {
+        ChatData {
+            session_id: self.session_id,
+            key_expiry_time: self.expires_at,
+            public_key: &self.public_key_der,
+            public_key_signature: &self.key_signature,
+        }
+    }
+}
+
+/// Computes the hash a client signs a chat message with.
+fn message_hash(
+    sender: Uuid,
+    session_id: Uuid,
+    index: i32,
+    salt: u64,
+    timestamp: u64,
+    message: &str,
+    last_seen: &[[u8; 256]],
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+
+    // Version of the signature format.
+    hasher.update(1_i32.to_be_bytes());
+
+    hasher.update(sender.as_bytes());
+    hasher.update(session_id.as_bytes());
+    hasher.update(index.to_be_bytes());
+
+    hasher.update(salt.to_be_bytes());
+    // The timestamp is signed in seconds.
+    hasher.update((timestamp / 1000).to_be_bytes());
+    hasher.update((message.len() as i32).to_be_bytes());
+    hasher.update(message.as_bytes());
+
+    hasher.update((last_seen.len() as i32).to_be_bytes());
+    for signature in last_seen {
+        hasher.update(signature);
+    }
+
+    hasher.finalize().into()
+}
+
+/// Reasons a client is disconnected by the chat pipeline.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Error)]
+pub enum ChatError {
+    #[error("invalid public key")]
+    InvalidPublicKey,
+    #[error("expired public key")]
+    ExpiredPublicKey,
+    #[error("invalid public key signature")]
+    InvalidKeySignature,
+    #[error("unsigned chat message")]
+    Unsigned,
+    #[error("invalid chat message signature")]
+    InvalidSignature,
+    #[error("out-of-order chat message")]
+    OutOfOrder,
+    #[error("invalid message acknowledgements")]
+    InvalidAcknowledgements,
+    #[error("too many unacknowledged chat messages")]
+    TooManyPending,
+}
+
+impl ChatError {
+    /// The reason shown to the client when it is disconnected.
+    pub fn disconnect_reason(self) -> Text {
+        let key = match self {
+            ChatError::InvalidPublicKey | ChatError::InvalidKeySignature => {
+                keys::MULTIPLAYER_DISCONNECT_INVALID_PUBLIC_KEY_SIGNATURE
+            }
+            ChatError::ExpiredPublicKey => keys::MULTIPLAYER_DISCONNECT_EXPIRED_PUBLIC_KEY,
+            ChatError::Unsigned => keys::MULTIPLAYER_DISCONNECT_UNSIGNED_CHAT,
+            ChatError::InvalidSignature | ChatError::InvalidAcknowledgements => {
+                keys::MULTIPLAYER_DISCONNECT_CHAT_VALIDATION_FAILED
+            }
+            ChatError::OutOfOrder => keys::MULTIPLAYER_DISCONNECT_OUT_OF_ORDER_CHAT,
+            ChatError::TooManyPending => keys::MULTIPLAYER_DISCONNECT_TOO_MANY_PENDING_CHATS,
+        };
+
+        Text::translate(key, [])
+    }
+}
+
+/// Component for clients holding the state of their chat which is needed to
+/// validate their messages.
+#[derive(Component, Clone, Debug)]
+pub struct ChatState {
+    /// The index of the next message in the session.
+    pub(crate) next_index: i32,
+    /// The timestamp of the last message, in milliseconds.
+    pub(crate) last_timestamp: u64,
+    pub(crate) last_seen: LastSeenTracker,
+}
+
+impl Default for ChatState {
+    fn default() -> Self {
+        Self {
+            next_index: 0,
+            last_timestamp: 0,
+            last_seen: LastSeenTracker::new(),
+        }
+    }
+}
+
+/// Tracks the signed messages sent to a client, so that the messages it
+/// acknowledges can be resolved to their signatures.
+#[derive(Clone, Debug)]
+pub(crate) struct LastSeenTracker {
+    tracked: VecDeque<Option<TrackedMessage>>,
+}
+
+#[derive(Clone, Debug)]
+struct TrackedMessage {
+    signature: Box<[u8; 256]>,
+    pending: bool,
+}
+
+impl LastSeenTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            tracked: std::iter::repeat_with(|| None)
+                .take(LAST_SEEN_COUNT)
+                .collect(),
+        }
+    }
+
+    /// Records a signed message sent to the client.
+    pub(crate) fn add_pending(&mut self, signature: &[u8; 256]) -> Result<(), ChatError> {
+        self.tracked.push_back(Some(TrackedMessage {
+            signature: Box::new(*signature),
+            pending: true,
+        }));
+
+        if self.tracked.len() > MAX_PENDING_MESSAGES {
+            return Err(ChatError::TooManyPending);
+        }
+
+        Ok(())
+    }
+
+    /// Drops the `offset` oldest messages, which the client no longer tracks.
+    pub(crate) fn apply_offset(&mut self, offset: i32) -> Result<(), ChatError> {
+        let available = self.tracked.len() - LAST_SEEN_COUNT;
+
+        match usize::try_from(offset) {
+            Ok(offset) if offset <= available => {
+                self.tracked.drain(..offset);
+                Ok(())
+            }
+            _ => Err(ChatError::InvalidAcknowledgements),
+        }
+    }
+
+    /// Applies the acknowledgements of a chat message and returns the
+    /// signatures of the messages the client has seen, oldest first.
+    pub(crate) fn apply_update(
+        &mut self,
+        offset: i32,
+        acknowledged: &FixedBitSet<LAST_SEEN_COUNT, 3>,
+    ) -> Result<Vec<[u8; 256]>, ChatError> {
+        self.apply_offset(offset)?;
+
+        let mut last_seen = vec![];
+
+        for (i, entry) in self.tracked.iter_mut().take(LAST_SEEN_COUNT).enumerate() {
+            if acknowledged.bit(i) {
+                let Some(message) = entry else {
+                    return Err(ChatError::InvalidAcknowledgements);
+                };
+
+                message.pending = false;
+                last_seen.push(*message.signature);
+            } else {
+                // Messages can't be un-acknowledged.
+                if entry.as_ref().is_some_and(|m| !m.pending) {
+                    return Err(ChatError::InvalidAcknowledgements);
+                }
+
+                *entry = None;
+            }
+        }
+
+        Ok(last_seen)
+    }
+}
+
+pub(crate) fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::pkcs8::EncodePublicKey;
+    use rsa::RsaPrivateKey;
+
+    use super::*;
+
+    const SENDER: Uuid = Uuid::from_u128(1);
+    const SESSION_ID: Uuid = Uuid::from_u128(2);
+
+    fn private_key() -> RsaPrivateKey {
+        RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap()
+    }
+
+    fn public_key_der(key: &RsaPrivateKey) -> Vec<u8> {
+        key.to_public_key().to_public_key_der().unwrap().into_vec()
+    }
+
+    fn expires_at() -> i64 {
+        unix_millis() + 60_000
+    }
+
+    /// Signs the session key `der` the way Mojang does.
+    fn sign_key(mojang_key: &RsaPrivateKey, expires_at: i64, der: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha1::new();
+        hasher.update(SENDER.as_bytes());
+        hasher.update(expires_at.to_be_bytes());
+        hasher.update(der);
+
+        mojang_key
+            .sign(Pkcs1v15Sign::new::<Sha1>(), &hasher.finalize())
+            .unwrap()
+    }
+
+    fn sign_message(key: &RsaPrivateKey, index: i32, timestamp: u64, message: &str) -> [u8; 256] {
+        let hash = message_hash(SENDER, SESSION_ID, index, 42, timestamp, message, &[]);
+
+        key.sign(Pkcs1v15Sign::new::<Sha256>(), &hash)
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn session_key_signature() {
+        let mojang_key = private_key();
+        let key = private_key();
+        let der = public_key_der(&key);
+        let expires_at = expires_at();
+        let key_signature = sign_key(&mojang_key, expires_at, &der);
+        let mojang_public_key = mojang_key.to_public_key();
+
+        let new_session = |expires_at, key_signature: &[u8]| {
+            ChatSession::new(
+                SENDER,
+                SESSION_ID,
+                expires_at,
+                &der,
+                key_signature,
+                Some(&mojang_public_key),
+            )
+            .map(|_| ())
+        };
+
+        assert_eq!(new_session(expires_at, &key_signature), Ok(()));
+
+        // The signature covers the expiry time.
+        assert_eq!(
+            new_session(expires_at + 1, &key_signature),
+            Err(ChatError::InvalidKeySignature)
+        );
+
+        let mut bad_signature = key_signature.clone();
+        bad_signature[0] ^= 1;
+        assert_eq!(
+            new_session(expires_at, &bad_signature),
+            Err(ChatError::InvalidKeySignature)
+        );
+
+        // Signed by a different key.
+        let other_signature = sign_key(&key, expires_at, &der);
+        assert_eq!(
+            new_session(expires_at, &other_signature),
+            Err(ChatError::InvalidKeySignature)
+        );
+
+        // The key signature isn't checked without Mojang's key.
+        assert!(
+            ChatSession::new(SENDER, SESSION_ID, expires_at, &der, &bad_signature, None).is_ok()
+        );
+
+        assert_eq!(
+            ChatSession::new(SENDER, SESSION_ID, expires_at, &[1, 2, 3], &[], None).map(|_| ()),
+            Err(ChatError::InvalidPublicKey)
+        );
+    }
+
+    #[test]
+    fn message_signature() {
+        let key = private_key();
+        let der = public_key_der(&key);
+        let session = ChatSession::new(SENDER, SESSION_ID, expires_at(), &der, &[], None).unwrap();
+
+        let timestamp = 1_700_000_000_000;
+        let signature = sign_message(&key, 3, timestamp, "hello");
+
+        let verify = |index, timestamp, message, signature: &[u8; 256]| {
+            session.verify(SENDER, index, 42, timestamp, message, &[], signature)
+        };
+
+        assert_eq!(verify(3, timestamp, "hello", &signature), Ok(()));
+
+        // Tampered message.
+        assert_eq!(
+            verify(3, timestamp, "goodbye", &signature),
+            Err(ChatError::InvalidSignature)
+        );
+
+        // Bad signature.
+        let mut bad_signature = signature;
+        bad_signature[100] ^= 1;
+        assert_eq!(
+            verify(3, timestamp, "hello", &bad_signature),
+            Err(ChatError::InvalidSignature)
+        );
+
+        // Replayed at a different index of the session.
+        assert_eq!(
+            verify(4, timestamp, "hello", &signature),
+            Err(ChatError::InvalidSignature)
+        );
+
+        // Only whole seconds of the timestamp are signed.
+        assert_eq!(verify(3, timestamp + 999, "hello", &signature), Ok(()));
+        assert_eq!(
+            verify(3, timestamp + 1000, "hello", &signature),
+            Err(ChatError::InvalidSignature)
+        );
+
+        // Signed by a different key.
+        let other_signature = sign_message(&private_key(), 3, timestamp, "hello");
+        assert_eq!(
+            verify(3, timestamp, "hello", &other_signature),
+            Err(ChatError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn last_seen_acknowledgements() {
+        let mut tracker = LastSeenTracker::new();
+
+        tracker.add_pending(&[1; 256]).unwrap();
+        tracker.add_pending(&[2; 256]).unwrap();
+
+        // The client shifts its window by the two new messages.
+        let mut acks = FixedBitSet::<LAST_SEEN_COUNT, 3>([0; 3]);
+        acks.set_bit(18, true);
+        acks.set_bit(19, true);
+
+        let last_seen = tracker.apply_update(2, &acks).unwrap();
+        assert_eq!(last_seen, [[1; 256], [2; 256]]);
+
+        // Acknowledged messages can't be dropped again.
+        let acks = FixedBitSet::<LAST_SEEN_COUNT, 3>([0; 3]);
+        assert_eq!(
+            tracker.apply_update(0, &acks),
+            Err(ChatError::InvalidAcknowledgements)
+        );
+
+        // Can't shift past the messages that were sent.
+        let mut tracker = LastSeenTracker::new();
+        assert_eq!(
+            tracker.apply_offset(1),
+            Err(ChatError::InvalidAcknowledgements)
+        );
+    }
+}
@@ -77,7 +77,7 @@ use std::fmt::{Display, Formatter};
 use petgraph::dot::Dot;
 use petgraph::prelude::*;
 use valence_server::protocol::packets::play::command_tree_s2c::{
-    Node, NodeData, Parser, StringArg,
+    Node, NodeData, Parser, StringArg, Suggestion,
 };
 use valence_server::protocol::packets::play::CommandTreeS2c;
 use valence_server::protocol::VarInt;
@@ -519,6 +519,27 @@ impl<'a, T> CommandGraphBuilder<'a, T> {
         self
     }
 
+    /// Sets the suggestions the client asks for when completing the current
+    /// node. The node should be an argument node or nothing will happen.
+    ///
+    /// # Arguments
+    /// * suggestion - the kind of suggestions to show, such as
+    ///   [`Suggestion::AskServer`] or [`Suggestion::SummonableEntities`]
+    pub fn with_suggestion(&mut self, suggestion: Suggestion) -> &mut Self {
+        let graph = &mut self.graph.graph;
+        let node = graph.node_weight_mut(self.current_node).unwrap();
+
+        if let NodeData::Argument {
+            suggestion: node_suggestion,
+            ..
+        } = &mut node.data
+        {
+            *node_suggestion = Some(suggestion);
+        }
+
+        self
+    }
+
     /// Transitions to the node specified.
     pub fn at(&mut self, node: NodeIndex) -> &mut Self {
         self.current_node = node;
@@ -4,11 +4,14 @@ use std::marker::PhantomData;
 use bevy_app::{App, Plugin, PostStartup};
 use bevy_ecs::change_detection::ResMut;
 use bevy_ecs::event::{Event, EventReader, EventWriter};
-use bevy_ecs::prelude::{Entity, IntoSystemConfigs, Resource};
+use bevy_ecs::prelude::{Commands, Entity, IntoSystemConfigs, Resource, World};
+use bevy_ecs::system::RunSystemOnce;
 use petgraph::prelude::NodeIndex;
+use petgraph::Direction;
+use tracing::warn;
 use valence_server::EventLoopPreUpdate;
 
-use crate::graph::CommandGraphBuilder;
+use crate::graph::{CommandEdgeType, CommandGraphBuilder};
 use crate::modifier_value::ModifierValue;
 use crate::parsers::ParseInput;
 use crate::{
@@ -22,11 +25,14 @@ where
     fn build(&self, app: &mut App) {
         app.add_event::<CommandResultEvent<T>>()
             .insert_resource(CommandResource::<T>::new())
-            .add_systems(PostStartup, command_startup_system::<T>)
             .add_systems(
                 EventLoopPreUpdate,
                 command_event_system::<T>.after(CommandSystemSet),
             );
+
+        if self.register_on_startup {
+            app.add_systems(PostStartup, command_startup_system::<T>);
+        }
     }
 }
 
@@ -35,6 +41,7 @@ where
     T: Command,
 {
     command: PhantomData<T>,
+    register_on_startup: bool,
 }
 
 impl<T: Command> Default for CommandHandlerPlugin<T> {
@@ -50,6 +57,17 @@ where
     pub fn new() -> Self {
         CommandHandlerPlugin {
             command: PhantomData,
+            register_on_startup: true,
+        }
+    }
+
+    /// Creates a plugin which doesn't add the command to the command graph
+    /// on startup. Use [`CommandRegistrationExt::register_command`] to add it
+    /// later.
+    pub fn unregistered() -> Self {
+        CommandHandlerPlugin {
+            command: PhantomData,
+            register_on_startup: false,
         }
     }
 }
@@ -58,6 +76,23 @@ where
 struct CommandResource<T: Command + Send + Sync> {
     command: PhantomData<T>,
     executables: HashMap<NodeIndex, fn(&mut ParseInput) -> T>,
+    /// The nodes the command added to the command graph.
+    nodes: Vec<NodeIndex>,
+    /// The parts of the command graph removed when the command was
+    /// unregistered, so the same nodes can be reused when it is registered
+    /// again.
+    detached: Option<DetachedCommand>,
+    registered: bool,
+}
+
+#[allow(clippy::type_complexity)]
+struct DetachedCommand {
+    edges: Vec<(NodeIndex, NodeIndex, CommandEdgeType)>,
+    parsers: Vec<(NodeIndex, fn(&mut ParseInput) -> bool)>,
+    modifiers: Vec<(
+        NodeIndex,
+        fn(String, &mut HashMap<ModifierValue, ModifierValue>),
+    )>,
 }
 
 impl<T: Command + Send + Sync> CommandResource<T> {
@@ -65,6 +100,9 @@ impl<T: Command + Send + Sync> CommandResource<T> {
         CommandResource {
             command: PhantomData,
             executables: HashMap::new(),
+            nodes: Vec::new(),
+            detached: None,
+            registered: false,
         }
     }
 }
@@ -80,6 +118,51 @@ where
     pub modifiers: HashMap<ModifierValue, ModifierValue>,
 }
 
+/// Extension trait for [`Commands`] to add and remove commands from the
+/// command graph while the server is running, for example when a module of
+/// your server is toggled. Clients are sent the new command tree if the
+/// commands they can use have changed.
+///
+/// The command must have been added with
+/// [`add_command`](crate::AddCommand::add_command) or a
+/// [`CommandHandlerPlugin`] beforehand.
+pub trait CommandRegistrationExt {
+    /// Adds `T` to the command graph. Does nothing if it is already there.
+    fn register_command<T: Command + Send + Sync + 'static>(&mut self);
+
+    /// Removes `T` from the command graph. Does nothing if it isn't there.
+    fn unregister_command<T: Command + Send + Sync + 'static>(&mut self);
+}
+
+impl CommandRegistrationExt for Commands<'_, '_> {
+    fn register_command<T: Command + Send + Sync + 'static>(&mut self) {
+        self.add(|world: &mut World| {
+            if has_command_resource::<T>(world) {
+                world.run_system_once(command_startup_system::<T>);
+            }
+        });
+    }
+
+    fn unregister_command<T: Command + Send + Sync + 'static>(&mut self) {
+        self.add(|world: &mut World| {
+            if has_command_resource::<T>(world) {
+                world.run_system_once(command_unregister_system::<T>);
+            }
+        });
+    }
+}
+
+fn has_command_resource<T: Command + Send + Sync + 'static>(world: &World) -> bool {
+    let exists = world.contains_resource::<CommandResource<T>>();
+    if !exists {
+        warn!(
+            "the command `{}` was never added to the app so it can't be registered or unregistered",
+            std::any::type_name::<T>()
+        );
+    }
+    exists
+}
+
 fn command_startup_system<T>(
     mut registry: ResMut<CommandRegistry>,
     mut scope_registry: ResMut<CommandScopeRegistry>,
@@ -87,6 +170,20 @@ fn command_startup_system<T>(
 ) where
     T: Command + Send + Sync + 'static,
 {
+    if command.registered {
+        return;
+    }
+
+    if let Some(detached) = command.detached.take() {
+        reattach_command(&mut registry, &command, detached);
+        command.registered = true;
+        return;
+    }
+
+    // Nodes are never removed from the graph, so every node the command adds
+    // has an index after the current last node.
+    let first_node = registry.graph.graph.node_count();
+
     let mut executables = HashMap::new();
     let mut parsers = HashMap::new();
     let mut modifiers = HashMap::new();
@@ -103,6 +200,75 @@ fn command_startup_system<T>(
     registry.parsers.extend(parsers);
     registry.modifiers.extend(modifiers);
     registry.executables.extend(executables.keys());
+
+    command.nodes = (first_node..registry.graph.graph.node_count())
+        .map(NodeIndex::new)
+        .collect();
+    command.registered = true;
+}
+
+/// Restores the edges and handlers of a command which was unregistered.
+fn reattach_command<T: Command + Send + Sync>(
+    registry: &mut CommandRegistry,
+    command: &CommandResource<T>,
+    detached: DetachedCommand,
+) {
+    for (source, target, edge) in detached.edges {
+        registry.graph.graph.add_edge(source, target, edge);
+    }
+
+    registry.parsers.extend(detached.parsers);
+    registry.modifiers.extend(detached.modifiers);
+    registry.executables.extend(command.executables.keys());
+}
+
+/// Detaches the nodes of the command from the command graph so they can't be
+/// reached from the root anymore. The nodes are kept so that registering the
+/// command again doesn't add new ones.
+fn command_unregister_system<T>(
+    mut registry: ResMut<CommandRegistry>,
+    mut command: ResMut<CommandResource<T>>,
+) where
+    T: Command + Send + Sync + 'static,
+{
+    if !command.registered {
+        return;
+    }
+
+    let command = &mut *command;
+    let registry = &mut *registry;
+    let graph = &mut registry.graph.graph;
+
+    let mut detached = DetachedCommand {
+        edges: vec![],
+        parsers: vec![],
+        modifiers: vec![],
+    };
+
+    for &node in &command.nodes {
+        for direction in [Direction::Outgoing, Direction::Incoming] {
+            while let Some(edge) = graph.first_edge(node, direction) {
+                let (source, target) = graph.edge_endpoints(edge).unwrap();
+                detached.edges.push((source, target, graph[edge]));
+                graph.remove_edge(edge);
+            }
+        }
+
+        if let Some(parser) = registry.parsers.remove(&node) {
+            detached.parsers.push((node, parser));
+        }
+
+        if let Some(modifier) = registry.modifiers.remove(&node) {
+            detached.modifiers.push((node, modifier));
+        }
+    }
+
+    for node in command.executables.keys() {
+        registry.executables.remove(node);
+    }
+
+    command.detached = Some(detached);
+    command.registered = false;
 }
 
 /// This system reads incoming command events.
@@ -124,3 +290,79 @@ fn command_event_system<T>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::CommandArg;
+
+    struct GiveCommand {
+        amount: i32,
+    }
+
+    impl Command for GiveCommand {
+        fn assemble_graph(graph: &mut CommandGraphBuilder<Self>) {
+            let give = graph
+                .root()
+                .literal("give")
+                .argument("amount")
+                .with_parser::<i32>()
+                .with_executable(|args| GiveCommand {
+                    amount: i32::parse_arg(args).unwrap(),
+                })
+                .id();
+
+            graph.root().literal("g").redirect_to(give);
+        }
+    }
+
+    fn counts(world: &World) -> (usize, usize, usize, usize) {
+        let registry = world.resource::<CommandRegistry>();
+        (
+            registry.graph.graph.node_count(),
+            registry.graph.graph.edge_count(),
+            registry.parsers.len(),
+            registry.executables.len(),
+        )
+    }
+
+    #[test]
+    fn reregistering_reuses_nodes() {
+        let mut world = World::new();
+        world.init_resource::<CommandRegistry>();
+        world.init_resource::<CommandScopeRegistry>();
+        world.insert_resource(CommandResource::<GiveCommand>::new());
+
+        world.run_system_once(command_startup_system::<GiveCommand>);
+
+        let registered = counts(&world);
+        // root, give, <amount>, g
+        assert_eq!(registered, (4, 4, 1, 1));
+
+        world.run_system_once(command_unregister_system::<GiveCommand>);
+
+        // The nodes are kept but nothing is connected to the root anymore.
+        let registry = world.resource::<CommandRegistry>();
+        assert_eq!(counts(&world), (4, 0, 0, 0));
+        assert_eq!(
+            registry.graph.graph.neighbors(registry.graph.root).count(),
+            0
+        );
+
+        world.run_system_once(command_startup_system::<GiveCommand>);
+        assert_eq!(counts(&world), registered);
+
+        // Registering twice in a row is a no-op.
+        world.run_system_once(command_startup_system::<GiveCommand>);
+        assert_eq!(counts(&world), registered);
+
+        world.run_system_once(command_unregister_system::<GiveCommand>);
+        world.run_system_once(command_startup_system::<GiveCommand>);
+        assert_eq!(counts(&world), registered);
+
+        // The restored executable still parses the command.
+        let command = world.resource::<CommandResource<GiveCommand>>();
+        let executable = command.executables.values().next().unwrap();
+        assert_eq!(executable(&mut ParseInput::new("5")).amount, 5);
+    }
+}
@@ -10,6 +10,7 @@ use std::fmt::Debug;
 
 use bevy_app::App;
 use bevy_ecs::prelude::{Resource, SystemSet};
+pub use handler::CommandRegistrationExt;
 pub use manager::{CommandExecutionEvent, CommandProcessedEvent};
 pub use modifier_value::ModifierValue;
 use petgraph::prelude::NodeIndex;
@@ -39,10 +40,18 @@ pub trait Command {
 
 pub trait AddCommand {
     fn add_command<T: Command + Send + Sync + 'static>(&mut self) -> &mut Self;
+
+    /// Adds a command without putting it in the command graph. Use
+    /// [`CommandRegistrationExt::register_command`] to enable it at runtime.
+    fn add_unregistered_command<T: Command + Send + Sync + 'static>(&mut self) -> &mut Self;
 }
 
 impl AddCommand for App {
     fn add_command<T: Command + Send + Sync + 'static>(&mut self) -> &mut Self {
         self.add_plugins(CommandHandlerPlugin::<T>::new())
     }
+
+    fn add_unregistered_command<T: Command + Send + Sync + 'static>(&mut self) -> &mut Self {
+        self.add_plugins(CommandHandlerPlugin::<T>::unregistered())
+    }
 }
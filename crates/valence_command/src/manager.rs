@@ -3,8 +3,8 @@ use std::collections::{HashMap, HashSet};
 use bevy_app::{App, Plugin, PreUpdate};
 use bevy_ecs::entity::Entity;
 use bevy_ecs::prelude::{
-    Added, Changed, Commands, DetectChanges, Event, EventReader, EventWriter, IntoSystemConfigs,
-    Mut, Or, Query, Res,
+    Added, Changed, Commands, Component, DetectChanges, Event, EventReader, EventWriter,
+    IntoSystemConfigs, Mut, Or, Query, Res,
 };
 use petgraph::graph::NodeIndex;
 use petgraph::prelude::EdgeRef;
@@ -14,7 +14,8 @@ use valence_server::client::{Client, SpawnClientsSet};
 use valence_server::event_loop::PacketEvent;
 use valence_server::protocol::packets::play::command_tree_s2c::NodeData;
 use valence_server::protocol::packets::play::{CommandExecutionC2s, CommandTreeS2c};
-use valence_server::protocol::WritePacket;
+use valence_server::protocol::{Encode, WritePacket};
+use valence_server::protocol_error::ProtocolErrorSink;
 use valence_server::EventLoopPreUpdate;
 
 use crate::graph::{CommandEdgeType, CommandGraph, CommandNode};
@@ -80,19 +81,27 @@ pub struct CommandProcessedEvent {
     pub node: NodeIndex,
 }
 
+/// The encoded command tree last sent to a client. Trees are only resent to
+/// clients when the commands they can see have changed.
+#[derive(Component, Default)]
+struct SentCommandTree(Vec<u8>);
+
 fn insert_scope_component(mut clients: Query<Entity, Added<Client>>, mut commands: Commands) {
     for client in clients.iter_mut() {
-        commands.entity(client).insert(CommandScopes::new());
+        commands
+            .entity(client)
+            .insert((CommandScopes::new(), SentCommandTree::default()));
     }
 }
 
 fn read_incoming_packets(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut event_writer: EventWriter<CommandExecutionEvent>,
 ) {
     for packet in packets.read() {
         let client = packet.client;
-        if let Some(packet) = packet.decode::<CommandExecutionC2s>() {
+        if let Some(packet) = packet.decode_reporting::<CommandExecutionC2s>(&errors) {
             event_writer.send(CommandExecutionEvent {
                 command: packet.command.to_string(),
                 executor: client,
@@ -106,7 +115,7 @@ fn command_tree_update_with_client(
     command_registry: Res<CommandRegistry>,
     scope_registry: Res<CommandScopeRegistry>,
     mut updated_clients: Query<
-        (&mut Client, &CommandScopes),
+        (&mut Client, &CommandScopes, &mut SentCommandTree),
         Or<(Added<Client>, Changed<CommandScopes>)>,
     >,
 ) {
@@ -120,7 +129,7 @@ fn command_tree_update_with_client(
 fn update_command_tree(
     command_registry: Res<CommandRegistry>,
     scope_registry: Res<CommandScopeRegistry>,
-    mut clients: Query<(&mut Client, &CommandScopes)>,
+    mut clients: Query<(&mut Client, &CommandScopes, &mut SentCommandTree)>,
 ) {
     if command_registry.is_changed() {
         update_client_command_tree(
@@ -134,9 +143,9 @@ fn update_command_tree(
 fn update_client_command_tree(
     command_registry: &Res<CommandRegistry>,
     scope_registry: Res<CommandScopeRegistry>,
-    updated_clients: &mut Vec<(Mut<Client>, &CommandScopes)>,
+    updated_clients: &mut Vec<(Mut<Client>, &CommandScopes, Mut<SentCommandTree>)>,
 ) {
-    for (ref mut client, client_scopes) in updated_clients {
+    for (ref mut client, client_scopes, ref mut sent_tree) in updated_clients {
         let time = std::time::Instant::now();
 
         let old_graph = &command_registry.graph;
@@ -195,7 +204,19 @@ fn update_client_command_tree(
                 };
                 let packet: CommandTreeS2c = command_graph.into();
 
+                let mut encoded = Vec::new();
+                if let Err(e) = packet.encode(&mut encoded) {
+                    warn!("failed to encode command tree: {e:#}");
+                    continue;
+                }
+
+                // The commands this client can use are unchanged.
+                if encoded == sent_tree.0 {
+                    continue;
+                }
+
                 client.write_packet(&packet);
+                sent_tree.0 = encoded;
             }
             None => {
                 warn!(
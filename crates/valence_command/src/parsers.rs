@@ -1,13 +1,16 @@
 //! A collection of parses for use in command argument nodes.
 pub mod angle;
 pub mod block_pos;
+pub mod block_state;
 pub mod bool;
 pub mod color;
 pub mod column_pos;
+pub mod coordinates;
 pub mod entity_anchor;
 pub mod entity_selector;
 pub mod gamemode;
 pub mod inventory_slot;
+pub mod item_predicate;
 pub mod numbers;
 pub mod rotation;
 pub mod score_holder;
@@ -20,10 +23,13 @@ pub mod vec3;
 use std::ops::Add;
 
 pub use block_pos::BlockPos;
+pub use block_state::BlockStateArg;
 pub use column_pos::ColumnPos;
+pub use coordinates::{BlockCoordinates, Coordinates, LocalCoordinates};
 pub use entity_anchor::EntityAnchor;
-pub use entity_selector::EntitySelector;
+pub use entity_selector::{EntitySelector, SelectorArguments};
 pub use inventory_slot::InventorySlot;
+pub use item_predicate::ItemPredicate;
 pub use rotation::Rotation;
 pub use score_holder::ScoreHolder;
 pub use strings::{GreedyString, QuotableString};
@@ -31,7 +37,10 @@ pub use swizzle::Swizzle;
 use thiserror::Error;
 pub use time::Time;
 use tracing::error;
+use valence_nbt::snbt::SnbtReader;
+use valence_nbt::{Compound, Value};
 pub(crate) use valence_server::protocol::packets::play::command_tree_s2c::Parser;
+use valence_server::Ident;
 pub use vec2::Vec2;
 pub use vec3::Vec3;
 
@@ -172,6 +181,39 @@ impl<'a> ParseInput<'a> {
         self.0
     }
 
+    /// Returns the next resource identifier and advances the input
+    pub fn pop_ident(&mut self) -> Result<Ident<String>, CommandArgParseError> {
+        let len = self
+            .0
+            .find(|c: char| !matches!(c, 'a'..='z' | '0'..='9' | '_' | '-' | '.' | '/' | ':'))
+            .unwrap_or(self.0.len());
+        let s = &self.0[..len];
+
+        let ident = Ident::new(s).map_err(|_| CommandArgParseError::InvalidArgument {
+            expected: "resource location".to_string(),
+            got: s.to_string(),
+        })?;
+
+        self.advance_n_bytes(len);
+        Ok(ident.to_string_ident())
+    }
+
+    /// Returns the SNBT compound at the front of the input and advances past it
+    pub fn pop_nbt_compound(&mut self) -> Result<Compound, CommandArgParseError> {
+        let mut reader = SnbtReader::new(self.0);
+
+        match reader.parse_element() {
+            Ok(Value::Compound(compound)) => {
+                self.advance_n_bytes(reader.bytes_read());
+                Ok(compound)
+            }
+            _ => Err(CommandArgParseError::InvalidArgument {
+                expected: "nbt compound".to_string(),
+                got: self.peek_word().to_string(),
+            }),
+        }
+    }
+
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self.0.len()
@@ -0,0 +1,125 @@
+use valence_nbt::Compound;
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::BlockState;
+
+use super::Parser;
+use crate::parsers::{CommandArg, CommandArgParseError, ParseInput};
+
+/// A block state with optional block entity data, written as
+/// `minecraft:oak_stairs[facing=north,half=top]{...}`. Properties which are
+/// not given keep their default value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockStateArg {
+    pub state: BlockState,
+    pub nbt: Option<Compound>,
+}
+
+impl Default for BlockStateArg {
+    fn default() -> Self {
+        Self {
+            state: BlockState::AIR,
+            nbt: None,
+        }
+    }
+}
+
+fn invalid(expected: &str, got: impl Into<String>) -> CommandArgParseError {
+    CommandArgParseError::InvalidArgument {
+        expected: expected.to_string(),
+        got: got.into(),
+    }
+}
+
+impl CommandArg for BlockStateArg {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+
+        let ident = input.pop_ident()?;
+        let kind = (ident.namespace() == "minecraft")
+            .then(|| BlockKind::from_str(ident.path()))
+            .flatten()
+            .ok_or_else(|| invalid("block", ident.as_str()))?;
+
+        let mut state = kind.to_state();
+
+        if input.peek() == Some('[') {
+            input.advance();
+
+            loop {
+                input.skip_whitespace();
+                if input.peek() == Some(']') {
+                    input.advance();
+                    break;
+                }
+
+                let Some(name) = input.pop_to_next('=') else {
+                    return Err(invalid("block property", input.peek_word()));
+                };
+                let name = name.trim().to_string();
+                input.advance(); // pop the '='
+                input.skip_whitespace();
+
+                let rest = input.0;
+                let value_len = rest
+                    .find([',', ']'])
+                    .ok_or(CommandArgParseError::InvalidArgLength)?;
+                let value = rest[..value_len].trim();
+                input.advance_n_bytes(value_len);
+
+                let prop_name = PropName::from_str(&name)
+                    .filter(|&n| state.get(n).is_some())
+                    .ok_or_else(|| invalid("block property", name.as_str()))?;
+                let prop_value =
+                    PropValue::from_str(value).ok_or_else(|| invalid("property value", value))?;
+
+                let new_state = state.set(prop_name, prop_value);
+                if new_state.get(prop_name) != Some(prop_value) {
+                    return Err(invalid("property value", value));
+                }
+                state = new_state;
+
+                if input.peek() == Some(',') {
+                    input.advance();
+                }
+            }
+        }
+
+        let nbt = if input.peek() == Some('{') {
+            Some(input.pop_nbt_compound()?)
+        } else {
+            None
+        };
+
+        Ok(BlockStateArg { state, nbt })
+    }
+
+    fn display() -> Parser {
+        Parser::BlockState
+    }
+}
+
+#[test]
+fn test_block_state() {
+    let mut input = ParseInput::new("minecraft:oak_stairs[facing=north, half=top] rest");
+    let arg = BlockStateArg::parse_arg(&mut input).unwrap();
+    assert_eq!(arg.state.to_kind(), BlockKind::OakStairs);
+    assert_eq!(arg.state.get(PropName::Facing), Some(PropValue::North));
+    assert_eq!(arg.state.get(PropName::Half), Some(PropValue::Top));
+    assert_eq!(arg.nbt, None);
+    assert!(!input.is_done());
+
+    let mut input = ParseInput::new("chest{Lock:\"key\"}");
+    let arg = BlockStateArg::parse_arg(&mut input).unwrap();
+    assert_eq!(arg.state, BlockState::CHEST);
+    assert!(arg.nbt.unwrap().contains_key("Lock"));
+    assert!(input.is_done());
+
+    let mut input = ParseInput::new("stone[facing=north]");
+    assert!(BlockStateArg::parse_arg(&mut input).is_err());
+
+    let mut input = ParseInput::new("oak_stairs[facing=up]");
+    assert!(BlockStateArg::parse_arg(&mut input).is_err());
+
+    let mut input = ParseInput::new("not_a_block");
+    assert!(BlockStateArg::parse_arg(&mut input).is_err());
+}
@@ -0,0 +1,197 @@
+use valence_server::math::DVec3;
+
+use super::Parser;
+use crate::parsers::{BlockPos, CommandArg, CommandArgParseError, ParseInput, Vec3};
+
+/// Coordinates relative to the rotation of the executor, written as
+/// `^left ^up ^forwards`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LocalCoordinates {
+    pub left: f64,
+    pub up: f64,
+    pub forwards: f64,
+}
+
+impl LocalCoordinates {
+    /// Converts these coordinates to a position in the world, seen from
+    /// `origin` looking in the direction of `yaw` and `pitch` (in degrees).
+    pub fn resolve(&self, origin: DVec3, yaw: f32, pitch: f32) -> DVec3 {
+        let yaw = (yaw as f64 + 90.0).to_radians();
+        let pitch = -(pitch as f64).to_radians();
+
+        let forwards = DVec3::new(
+            yaw.cos() * pitch.cos(),
+            pitch.sin(),
+            yaw.sin() * pitch.cos(),
+        );
+        let up = DVec3::new(
+            yaw.cos() * (pitch + std::f64::consts::FRAC_PI_2).cos(),
+            (pitch + std::f64::consts::FRAC_PI_2).sin(),
+            yaw.sin() * (pitch + std::f64::consts::FRAC_PI_2).cos(),
+        );
+        let left = -forwards.cross(up);
+
+        origin + forwards * self.forwards + up * self.up + left * self.left
+    }
+}
+
+impl CommandArg for LocalCoordinates {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        let parse_component = |input: &mut ParseInput| {
+            input.skip_whitespace();
+            if input.pop() != Some('^') {
+                return Err(CommandArgParseError::InvalidArgument {
+                    expected: "local coordinate".to_string(),
+                    got: input.peek_word().to_string(),
+                });
+            }
+            if input.peek() == Some(' ') || input.peek().is_none() {
+                Ok(0.0)
+            } else {
+                f64::parse_arg(input)
+            }
+        };
+
+        let left = parse_component(input)?;
+        let up = parse_component(input)?;
+        let forwards = parse_component(input)?;
+
+        Ok(LocalCoordinates { left, up, forwards })
+    }
+
+    fn display() -> Parser {
+        Parser::Vec3
+    }
+}
+
+/// A position given in world coordinates (`~ ~1 ~`) or local coordinates
+/// (`^ ^ ^5`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinates {
+    World(Vec3),
+    Local(LocalCoordinates),
+}
+
+impl Coordinates {
+    /// Converts these coordinates to a position in the world for an executor
+    /// at `origin` looking in the direction of `yaw` and `pitch`.
+    pub fn resolve(&self, origin: DVec3, yaw: f32, pitch: f32) -> DVec3 {
+        match self {
+            Coordinates::World(pos) => DVec3::new(
+                pos.x.get(origin.x as f32) as f64,
+                pos.y.get(origin.y as f32) as f64,
+                pos.z.get(origin.z as f32) as f64,
+            ),
+            Coordinates::Local(local) => local.resolve(origin, yaw, pitch),
+        }
+    }
+}
+
+impl Default for Coordinates {
+    fn default() -> Self {
+        Coordinates::World(Vec3::default())
+    }
+}
+
+impl CommandArg for Coordinates {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        if input.peek() == Some('^') {
+            Ok(Coordinates::Local(LocalCoordinates::parse_arg(input)?))
+        } else {
+            Ok(Coordinates::World(Vec3::parse_arg(input)?))
+        }
+    }
+
+    fn display() -> Parser {
+        Parser::Vec3
+    }
+}
+
+/// A block position given in world coordinates (`~ ~-1 ~`) or local
+/// coordinates (`^ ^ ^1`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockCoordinates {
+    World(BlockPos),
+    Local(LocalCoordinates),
+}
+
+impl BlockCoordinates {
+    /// Converts these coordinates to a block position for an executor at
+    /// `origin` looking in the direction of `yaw` and `pitch`.
+    pub fn resolve(&self, origin: DVec3, yaw: f32, pitch: f32) -> valence_server::BlockPos {
+        match self {
+            BlockCoordinates::World(pos) => {
+                let block = valence_server::BlockPos::from(origin);
+                valence_server::BlockPos::new(
+                    pos.x.get(block.x),
+                    pos.y.get(block.y),
+                    pos.z.get(block.z),
+                )
+            }
+            BlockCoordinates::Local(local) => local.resolve(origin, yaw, pitch).into(),
+        }
+    }
+}
+
+impl Default for BlockCoordinates {
+    fn default() -> Self {
+        BlockCoordinates::World(BlockPos::default())
+    }
+}
+
+impl CommandArg for BlockCoordinates {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        if input.peek() == Some('^') {
+            Ok(BlockCoordinates::Local(LocalCoordinates::parse_arg(input)?))
+        } else {
+            Ok(BlockCoordinates::World(BlockPos::parse_arg(input)?))
+        }
+    }
+
+    fn display() -> Parser {
+        Parser::BlockPos
+    }
+}
+
+#[test]
+fn test_local_coordinates() {
+    let mut input = ParseInput::new("^ ^ ^5");
+    let coords = Coordinates::parse_arg(&mut input).unwrap();
+    assert_eq!(
+        coords,
+        Coordinates::Local(LocalCoordinates {
+            left: 0.0,
+            up: 0.0,
+            forwards: 5.0
+        })
+    );
+    assert!(input.is_done());
+
+    // Facing south (positive z) with a yaw of 0.
+    let pos = coords.resolve(DVec3::new(1.0, 2.0, 3.0), 0.0, 0.0);
+    assert!((pos - DVec3::new(1.0, 2.0, 8.0)).length() < 1e-9);
+
+    // Left of south is east (positive x).
+    let left = LocalCoordinates {
+        left: 1.0,
+        up: 2.0,
+        forwards: 0.0,
+    };
+    assert!((left.resolve(DVec3::ZERO, 0.0, 0.0) - DVec3::new(1.0, 2.0, 0.0)).length() < 1e-9);
+
+    let mut input = ParseInput::new("^1 ~ ^");
+    assert!(Coordinates::parse_arg(&mut input).is_err());
+}
+
+#[test]
+fn test_world_coordinates() {
+    let mut input = ParseInput::new("~ ~1 ~ rest");
+    let coords = BlockCoordinates::parse_arg(&mut input).unwrap();
+    assert!(!input.is_done());
+    assert_eq!(
+        coords.resolve(DVec3::new(0.5, 64.0, -0.5), 0.0, 0.0),
+        valence_server::BlockPos::new(0, 65, -1)
+    );
+}
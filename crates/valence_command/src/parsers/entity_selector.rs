@@ -1,3 +1,10 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use valence_nbt::Compound;
+use valence_server::math::DVec3;
+use valence_server::GameMode;
+
 use super::Parser;
 use crate::parsers::{CommandArg, CommandArgParseError, ParseInput};
 
@@ -57,15 +64,32 @@ impl CommandArg for EntitySelector {
                         });
                     }
                     let mut s = String::new();
+                    // Brackets in NBT or quoted strings don't end the selector.
+                    let mut depth = 0_usize;
+                    let mut quote = None;
                     while let Some(c) = input.pop() {
-                        if c == ']' {
-                            return Ok(EntitySelector::ComplexSelector(
-                                simple_selector.unwrap(),
-                                s.trim().to_string(),
-                            ));
-                        } else {
-                            s.push(c);
+                        match (quote, c) {
+                            (Some(_), '\\') => {
+                                s.push(c);
+                                if let Some(escaped) = input.pop() {
+                                    s.push(escaped);
+                                }
+                                continue;
+                            }
+                            (Some(q), c) if c == q => quote = None,
+                            (Some(_), _) => {}
+                            (None, '"' | '\'') => quote = Some(c),
+                            (None, '[' | '{') => depth += 1,
+                            (None, ']') if depth == 0 => {
+                                return Ok(EntitySelector::ComplexSelector(
+                                    simple_selector.unwrap(),
+                                    s.trim().to_string(),
+                                ));
+                            }
+                            (None, ']' | '}') => depth = depth.saturating_sub(1),
+                            _ => {}
                         }
+                        s.push(c);
                     }
                 }
                 _ => {
@@ -86,6 +110,307 @@ impl CommandArg for EntitySelector {
     }
 }
 
+impl EntitySelector {
+    /// Parses the arguments of the selector. Simple selectors have no
+    /// arguments.
+    pub fn arguments(&self) -> Result<SelectorArguments, CommandArgParseError> {
+        match self {
+            EntitySelector::SimpleSelector(_) => Ok(SelectorArguments::default()),
+            EntitySelector::ComplexSelector(_, args) => SelectorArguments::parse(args),
+        }
+    }
+}
+
+fn invalid(expected: &str, got: &str) -> CommandArgParseError {
+    CommandArgParseError::InvalidArgument {
+        expected: expected.to_string(),
+        got: got.to_string(),
+    }
+}
+
+/// A range of values written as `5`, `..5`, `1..` or `1..5`. Both ends are
+/// inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bounds<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T: PartialOrd> Bounds<T> {
+    pub fn contains(&self, value: &T) -> bool {
+        self.min.as_ref().is_none_or(|min| min <= value)
+            && self.max.as_ref().is_none_or(|max| value <= max)
+    }
+}
+
+impl<T: FromStr + Copy> Bounds<T> {
+    fn parse(s: &str) -> Result<Self, CommandArgParseError> {
+        let parse = |bound: &str| bound.parse::<T>().map_err(|_| invalid("number", bound));
+
+        match s.split_once("..") {
+            Some(("", "")) => Err(invalid("range", s)),
+            Some((min, max)) => Ok(Bounds {
+                min: (!min.is_empty()).then(|| parse(min)).transpose()?,
+                max: (!max.is_empty()).then(|| parse(max)).transpose()?,
+            }),
+            None => {
+                let value = parse(s)?;
+                Ok(Bounds {
+                    min: Some(value),
+                    max: Some(value),
+                })
+            }
+        }
+    }
+}
+
+/// A selector argument value which is inverted by a leading `!`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negatable<T> {
+    pub value: T,
+    pub negated: bool,
+}
+
+impl<T: PartialEq> Negatable<T> {
+    pub fn matches(&self, value: &T) -> bool {
+        (self.value == *value) != self.negated
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorSort {
+    Nearest,
+    Furthest,
+    Random,
+    Arbitrary,
+}
+
+/// The arguments of an entity selector, such as `distance=..10` in
+/// `@p[distance=..10]`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SelectorArguments {
+    /// Overrides the position the selector is evaluated from.
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    pub distance: Option<Bounds<f64>>,
+    /// The size of the volume entities must be in, starting at the position
+    /// the selector is evaluated from.
+    pub dx: Option<f64>,
+    pub dy: Option<f64>,
+    pub dz: Option<f64>,
+    /// The pitch of the entity.
+    pub x_rotation: Option<Bounds<f32>>,
+    /// The yaw of the entity.
+    pub y_rotation: Option<Bounds<f32>>,
+    pub scores: BTreeMap<String, Bounds<i32>>,
+    pub tag: Vec<Negatable<String>>,
+    pub team: Vec<Negatable<String>>,
+    pub name: Vec<Negatable<String>>,
+    /// Entity types, or entity type tags starting with `#`.
+    pub kind: Vec<Negatable<String>>,
+    pub predicate: Vec<Negatable<String>>,
+    pub nbt: Vec<Negatable<Compound>>,
+    pub level: Option<Bounds<i32>>,
+    pub gamemode: Vec<Negatable<GameMode>>,
+    /// The unparsed advancement requirements.
+    pub advancements: Option<String>,
+    pub limit: Option<usize>,
+    pub sort: Option<SelectorSort>,
+}
+
+impl SelectorArguments {
+    /// Parses the comma separated arguments between the brackets of a
+    /// selector.
+    pub fn parse(args: &str) -> Result<Self, CommandArgParseError> {
+        let mut result = SelectorArguments::default();
+
+        for arg in split_top_level(args, ',') {
+            let arg = arg.trim();
+            if arg.is_empty() {
+                continue;
+            }
+
+            let (key, value) = arg
+                .split_once('=')
+                .ok_or_else(|| invalid("selector argument", arg))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            fn negatable(value: &str) -> (&str, bool) {
+                match value.strip_prefix('!') {
+                    Some(value) => (value.trim(), true),
+                    None => (value, false),
+                }
+            }
+
+            let number = |value: &str| value.parse::<f64>().map_err(|_| invalid("number", value));
+
+            match key {
+                "x" => result.x = Some(number(value)?),
+                "y" => result.y = Some(number(value)?),
+                "z" => result.z = Some(number(value)?),
+                "dx" => result.dx = Some(number(value)?),
+                "dy" => result.dy = Some(number(value)?),
+                "dz" => result.dz = Some(number(value)?),
+                "distance" => {
+                    let distance = Bounds::parse(value)?;
+                    if distance.min.is_some_and(|min| min < 0.0) {
+                        return Err(invalid("positive distance", value));
+                    }
+                    result.distance = Some(distance);
+                }
+                "x_rotation" => result.x_rotation = Some(Bounds::parse(value)?),
+                "y_rotation" => result.y_rotation = Some(Bounds::parse(value)?),
+                "level" => result.level = Some(Bounds::parse(value)?),
+                "limit" => {
+                    let limit = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&limit| limit > 0)
+                        .ok_or_else(|| invalid("positive integer", value))?;
+                    result.limit = Some(limit);
+                }
+                "sort" => {
+                    result.sort = Some(match value {
+                        "nearest" => SelectorSort::Nearest,
+                        "furthest" => SelectorSort::Furthest,
+                        "random" => SelectorSort::Random,
+                        "arbitrary" => SelectorSort::Arbitrary,
+                        _ => return Err(invalid("sort", value)),
+                    })
+                }
+                "scores" => {
+                    let scores = value
+                        .strip_prefix('{')
+                        .and_then(|v| v.strip_suffix('}'))
+                        .ok_or_else(|| invalid("scores", value))?;
+
+                    for score in scores.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                        let (objective, bounds) = score
+                            .split_once('=')
+                            .ok_or_else(|| invalid("score", score))?;
+                        result
+                            .scores
+                            .insert(objective.trim().to_string(), Bounds::parse(bounds.trim())?);
+                    }
+                }
+                "advancements" => result.advancements = Some(value.to_string()),
+                "tag" | "team" | "name" | "type" | "predicate" => {
+                    let (value, negated) = negatable(value);
+                    let value = Negatable {
+                        value: unquote(value).to_string(),
+                        negated,
+                    };
+
+                    match key {
+                        "tag" => result.tag.push(value),
+                        "team" => result.team.push(value),
+                        "name" => result.name.push(value),
+                        "type" => result.kind.push(value),
+                        _ => result.predicate.push(value),
+                    }
+                }
+                "gamemode" => {
+                    let (value, negated) = negatable(value);
+                    result.gamemode.push(Negatable {
+                        value: GameMode::arg_from_str(value)?,
+                        negated,
+                    });
+                }
+                "nbt" => {
+                    let (value, negated) = negatable(value);
+                    let mut input = ParseInput::new(value);
+                    result.nbt.push(Negatable {
+                        value: input.pop_nbt_compound()?,
+                        negated,
+                    });
+                }
+                _ => return Err(invalid("selector argument", key)),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the position the selector is evaluated from when it is
+    /// executed at `executor`.
+    pub fn origin(&self, executor: DVec3) -> DVec3 {
+        DVec3::new(
+            self.x.unwrap_or(executor.x),
+            self.y.unwrap_or(executor.y),
+            self.z.unwrap_or(executor.z),
+        )
+    }
+
+    /// Returns whether an entity at `pos` matches the `distance` and volume
+    /// arguments when the selector is executed at `executor`.
+    pub fn matches_position(&self, executor: DVec3, pos: DVec3) -> bool {
+        let origin = self.origin(executor);
+
+        if let Some(distance) = &self.distance {
+            if !distance.contains(&origin.distance(pos)) {
+                return false;
+            }
+        }
+
+        if self.dx.is_some() || self.dy.is_some() || self.dz.is_some() {
+            let size = DVec3::new(
+                self.dx.unwrap_or(0.0),
+                self.dy.unwrap_or(0.0),
+                self.dz.unwrap_or(0.0),
+            );
+
+            let min = origin + size.min(DVec3::ZERO);
+            let max = origin + size.max(DVec3::ZERO) + DVec3::ONE;
+
+            if pos.cmplt(min).any() || pos.cmpge(max).any() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Removes the quotes around a quoted string.
+fn unquote(s: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(s) = s.strip_prefix(quote).and_then(|s| s.strip_suffix(quote)) {
+            return s;
+        }
+    }
+    s
+}
+
+/// Splits `s` at every `separator` which is not inside brackets or quotes.
+fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0_usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth = depth.saturating_sub(1),
+            (None, c) if c == separator && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&s[start..]);
+    parts
+}
+
 #[test]
 fn test_entity_selector() {
     let mut input = ParseInput::new("@e");
@@ -134,3 +459,52 @@ fn test_entity_selector() {
     );
     assert!(!input.is_done());
 }
+
+#[test]
+fn test_selector_arguments() {
+    let mut input = ParseInput::new(
+        "@e[type=!minecraft:zombie, distance=..10, limit=3,sort=nearest,name=\"a, \
+         b\",nbt={Tags:[\"x\"]},scores={kills=1..}]",
+    );
+    let selector = EntitySelector::parse_arg(&mut input).unwrap();
+    assert!(input.is_done());
+
+    let args = selector.arguments().unwrap();
+    assert_eq!(
+        args.kind,
+        vec![Negatable {
+            value: "minecraft:zombie".to_string(),
+            negated: true
+        }]
+    );
+    assert_eq!(
+        args.distance,
+        Some(Bounds {
+            min: None,
+            max: Some(10.0)
+        })
+    );
+    assert_eq!(args.limit, Some(3));
+    assert_eq!(args.sort, Some(SelectorSort::Nearest));
+    assert_eq!(args.name[0].value, "a, b");
+    assert!(args.nbt[0].value.contains_key("Tags"));
+    assert_eq!(
+        args.scores.get("kills"),
+        Some(&Bounds {
+            min: Some(1),
+            max: None
+        })
+    );
+
+    assert!(args.matches_position(DVec3::ZERO, DVec3::new(0.0, 0.0, 10.0)));
+    assert!(!args.matches_position(DVec3::ZERO, DVec3::new(0.0, 0.0, 10.5)));
+
+    let args = SelectorArguments::parse("x=0,y=0,z=0,dx=2,dy=2,dz=2,gamemode=creative").unwrap();
+    assert!(args.matches_position(DVec3::splat(100.0), DVec3::splat(2.5)));
+    assert!(!args.matches_position(DVec3::splat(100.0), DVec3::splat(3.5)));
+    assert!(args.gamemode[0].matches(&GameMode::Creative));
+
+    assert!(SelectorArguments::parse("limit=0").is_err());
+    assert!(SelectorArguments::parse("unknown=1").is_err());
+    assert!(SelectorArguments::parse("distance=..").is_err());
+}
@@ -0,0 +1,134 @@
+use valence_nbt::{Compound, Value};
+use valence_server::protocol::VarInt;
+use valence_server::registry::TagsRegistry;
+use valence_server::{Ident, ItemKind, ItemStack};
+
+use super::Parser;
+use crate::parsers::{CommandArg, CommandArgParseError, ParseInput};
+
+/// Matches item stacks by their kind or an item tag, and optionally by their
+/// NBT. Written as `minecraft:stick`, `#minecraft:logs`, or
+/// `diamond_sword{Damage:0}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemPredicate {
+    pub item: ItemPredicateKind,
+    /// The NBT the item stack must contain. Other tags in the item stack are
+    /// ignored.
+    pub nbt: Option<Compound>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemPredicateKind {
+    Item(ItemKind),
+    Tag(Ident<String>),
+}
+
+impl Default for ItemPredicate {
+    fn default() -> Self {
+        Self {
+            item: ItemPredicateKind::Item(ItemKind::Air),
+            nbt: None,
+        }
+    }
+}
+
+impl ItemPredicate {
+    /// Returns whether `stack` matches this predicate. Item tags are looked up
+    /// in `tags`.
+    pub fn matches(&self, stack: &ItemStack, tags: &TagsRegistry) -> bool {
+        let kind_matches = match &self.item {
+            ItemPredicateKind::Item(kind) => stack.item == *kind,
+            ItemPredicateKind::Tag(tag) => tags
+                .registries
+                .get("minecraft:item")
+                .and_then(|item_tags| item_tags.get(tag))
+                .is_some_and(|items| items.contains(&VarInt(stack.item.to_raw() as i32))),
+        };
+
+        kind_matches
+            && match (&self.nbt, &stack.nbt) {
+                (None, _) => true,
+                (Some(expected), Some(actual)) => compound_matches(expected, actual),
+                (Some(expected), None) => expected.is_empty(),
+            }
+    }
+}
+
+/// Returns whether every tag in `expected` is also in `actual`.
+fn compound_matches(expected: &Compound, actual: &Compound) -> bool {
+    expected.iter().all(|(key, expected)| {
+        actual
+            .get(key)
+            .is_some_and(|actual| match (expected, actual) {
+                (Value::Compound(expected), Value::Compound(actual)) => {
+                    compound_matches(expected, actual)
+                }
+                _ => expected == actual,
+            })
+    })
+}
+
+impl CommandArg for ItemPredicate {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+
+        let item = if input.peek() == Some('#') {
+            input.advance();
+            ItemPredicateKind::Tag(input.pop_ident()?)
+        } else {
+            let ident = input.pop_ident()?;
+
+            let kind = (ident.namespace() == "minecraft")
+                .then(|| ItemKind::from_str(ident.path()))
+                .flatten()
+                .ok_or_else(|| CommandArgParseError::InvalidArgument {
+                    expected: "item".to_string(),
+                    got: ident.as_str().to_string(),
+                })?;
+
+            ItemPredicateKind::Item(kind)
+        };
+
+        let nbt = if input.peek() == Some('{') {
+            Some(input.pop_nbt_compound()?)
+        } else {
+            None
+        };
+
+        Ok(ItemPredicate { item, nbt })
+    }
+
+    fn display() -> Parser {
+        Parser::ItemPredicate
+    }
+}
+
+#[test]
+fn test_item_predicate() {
+    let mut input = ParseInput::new("#minecraft:logs rest");
+    let predicate = ItemPredicate::parse_arg(&mut input).unwrap();
+    assert_eq!(
+        predicate.item,
+        ItemPredicateKind::Tag(Ident::new("logs").unwrap().to_string_ident())
+    );
+    assert!(!input.is_done());
+
+    let mut input = ParseInput::new("diamond_sword{Damage:3,display:{Name:\"x\"}}");
+    let predicate = ItemPredicate::parse_arg(&mut input).unwrap();
+    assert_eq!(
+        predicate.item,
+        ItemPredicateKind::Item(ItemKind::DiamondSword)
+    );
+    assert!(input.is_done());
+
+    let tags = TagsRegistry::default();
+
+    let mut nbt = predicate.nbt.clone().unwrap();
+    nbt.insert("Unbreakable", 1_i8);
+    assert!(predicate.matches(&ItemStack::new(ItemKind::DiamondSword, 1, Some(nbt)), &tags));
+    assert!(!predicate.matches(&ItemStack::new(ItemKind::DiamondSword, 1, None), &tags));
+    assert!(!predicate.matches(&ItemStack::new(ItemKind::Stick, 1, None), &tags));
+
+    let mut input = ParseInput::new("not_an_item");
+    assert!(ItemPredicate::parse_arg(&mut input).is_err());
+}
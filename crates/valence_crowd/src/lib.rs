@@ -0,0 +1,395 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::{ClientMarker, VisibleEntityLayers};
+use valence_server::entity::tracked_data::TrackedData;
+use valence_server::entity::{
+    EntityAnimations, EntityId, EntityKind, EntityLayerId, EntityStatuses, HeadYaw,
+    InitEntitiesSet, Look, ObjectData, OldEntityLayerId, OldPosition, OnGround, Position,
+    UpdateTrackedDataSet, Velocity,
+};
+use valence_server::layer::UpdateLayersPreClientSet;
+use valence_server::math::DVec3;
+use valence_server::{Despawned, EntityLayer, Server, UniqueId};
+
+/// How much further away a client must move than the distance it crossed to
+/// change the level of detail again. Keeps clients on the boundary from
+/// switching back and forth.
+const HYSTERESIS: f64 = 2.0;
+
+pub struct CrowdPlugin;
+
+/// The system set where crowd proxies and the visibility of crowds are
+/// updated.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CrowdSet;
+
+impl Plugin for CrowdPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(PostUpdate, CrowdSet.before(InitEntitiesSet))
+            .add_systems(
+                PostUpdate,
+                (
+                    init_crowds,
+                    apply_deferred,
+                    update_proxies,
+                    apply_deferred, // So new proxies are initialized this tick.
+                    update_crowd_visibility,
+                )
+                    .chain()
+                    .in_set(CrowdSet),
+            )
+            .add_systems(
+                PostUpdate,
+                copy_proxy_tracked_data
+                    .after(CrowdSet)
+                    .after(UpdateTrackedDataSet)
+                    .before(UpdateLayersPreClientSet),
+            );
+    }
+}
+
+/// Bundle for spawning a crowd. Members of the crowd are spawned with their
+/// [`EntityLayerId`] set to the crowd entity.
+///
+/// # Despawning crowds
+///
+/// The [`Despawned`] component must be used to despawn crowds. The members
+/// must be despawned separately.
+#[derive(Bundle)]
+pub struct CrowdBundle {
+    pub crowd: Crowd,
+    /// The layer containing the members.
+    pub layer: EntityLayer,
+}
+
+impl CrowdBundle {
+    pub fn new(parent: Entity, server: &Server) -> Self {
+        Self {
+            crowd: Crowd::new(parent),
+            layer: EntityLayer::new(server),
+        }
+    }
+}
+
+/// The level of detail settings of a crowd.
+///
+/// The distance between a client and the crowd is measured to the closest
+/// point of the box containing all members.
+#[derive(Component, Clone, Debug)]
+pub struct Crowd {
+    /// The entity layer the crowd is a part of. The crowd is only shown to
+    /// clients viewing this layer.
+    pub parent: Entity,
+    /// Clients within this distance see the members of the crowd.
+    ///
+    /// # Default Value
+    ///
+    /// 24 blocks
+    pub detail_distance: f64,
+    /// Clients within this distance, but further away than
+    /// [`detail_distance`](Self::detail_distance), see the proxies of the
+    /// crowd. Clients further away see nothing.
+    ///
+    /// # Default Value
+    ///
+    /// 96 blocks
+    pub cull_distance: f64,
+    /// The size of the cubic cells members are grouped by. Each non-empty
+    /// cell is represented by one proxy.
+    ///
+    /// # Default Value
+    ///
+    /// 8 blocks
+    pub cell_size: f64,
+    /// The layer containing the proxies.
+    summary_layer: Entity,
+    proxies: BTreeMap<[i64; 3], Entity>,
+    /// The corners of the box containing all members, if there are any.
+    bounds: Option<(DVec3, DVec3)>,
+}
+
+impl Crowd {
+    pub fn new(parent: Entity) -> Self {
+        Self {
+            parent,
+            detail_distance: 24.0,
+            cull_distance: 96.0,
+            cell_size: 8.0,
+            summary_layer: Entity::PLACEHOLDER,
+            proxies: BTreeMap::new(),
+            bounds: None,
+        }
+    }
+
+    /// The entity layer containing the proxies of this crowd. This is
+    /// [`Entity::PLACEHOLDER`] until the crowd is initialized.
+    pub fn summary_layer(&self) -> Entity {
+        self.summary_layer
+    }
+
+    /// The number of proxies representing this crowd.
+    pub fn proxy_count(&self) -> usize {
+        self.proxies.len()
+    }
+
+    /// Returns the distance from `pos` to the closest member, approximately.
+    /// Returns `None` if the crowd has no members.
+    pub fn distance_to(&self, pos: DVec3) -> Option<f64> {
+        self.bounds
+            .map(|(min, max)| pos.clamp(min, max).distance(pos))
+    }
+}
+
+/// Component for the entities standing in for groups of members of a
+/// [`Crowd`]. Proxies look like one of the members of their group and are
+/// despawned when the group is empty.
+///
+/// Clients may attack or interact with proxies like any other entity.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CrowdProxy {
+    /// The crowd entity.
+    pub crowd: Entity,
+    /// The member this proxy looks like.
+    pub representative: Entity,
+}
+
+/// A group of crowd members in the same cell.
+struct Cell {
+    sum: DVec3,
+    count: u32,
+    representative: Entity,
+}
+
+fn init_crowds(
+    mut crowds: Query<&mut Crowd, Added<Crowd>>,
+    server: Res<Server>,
+    mut commands: Commands,
+) {
+    for mut crowd in &mut crowds {
+        crowd.summary_layer = commands.spawn(EntityLayer::new(&server)).id();
+    }
+}
+
+fn update_proxies(
+    mut crowds: Query<(Entity, &mut Crowd, Has<Despawned>)>,
+    members: Query<
+        (Entity, &EntityLayerId, &Position, &EntityKind),
+        (
+            Without<CrowdProxy>,
+            Without<ClientMarker>,
+            Without<Despawned>,
+        ),
+    >,
+    representatives: Query<(&Look, &HeadYaw), Without<CrowdProxy>>,
+    mut proxies: Query<(&mut CrowdProxy, &mut Position, &mut Look, &mut HeadYaw)>,
+    mut commands: Commands,
+) {
+    let mut cells = HashMap::<Entity, HashMap<[i64; 3], Cell>>::new();
+    let mut bounds = HashMap::<Entity, (DVec3, DVec3)>::new();
+
+    for (entity, layer_id, pos, _) in &members {
+        let Ok((_, crowd, false)) = crowds.get(layer_id.0) else {
+            continue;
+        };
+
+        let key = (pos.0 / crowd.cell_size.max(1.0))
+            .floor()
+            .as_i64vec3()
+            .to_array();
+
+        match cells.entry(layer_id.0).or_default().entry(key) {
+            Entry::Occupied(mut cell) => {
+                let cell = cell.get_mut();
+                cell.sum += pos.0;
+                cell.count += 1;
+                // Choose the representative independently of iteration order.
+                cell.representative = cell.representative.min(entity);
+            }
+            Entry::Vacant(cell) => {
+                cell.insert(Cell {
+                    sum: pos.0,
+                    count: 1,
+                    representative: entity,
+                });
+            }
+        }
+
+        bounds
+            .entry(layer_id.0)
+            .and_modify(|(min, max)| {
+                *min = min.min(pos.0);
+                *max = max.max(pos.0);
+            })
+            .or_insert((pos.0, pos.0));
+    }
+
+    for (crowd_entity, mut crowd, despawned) in &mut crowds {
+        let crowd = &mut *crowd;
+
+        if despawned {
+            if let Some(mut layer) = commands.get_entity(crowd.summary_layer) {
+                layer.insert(Despawned);
+            }
+
+            for &proxy in crowd.proxies.values() {
+                commands.entity(proxy).insert(Despawned);
+            }
+
+            crowd.proxies.clear();
+            crowd.bounds = None;
+            continue;
+        }
+
+        let cells = cells.remove(&crowd_entity).unwrap_or_default();
+        crowd.bounds = bounds.get(&crowd_entity).copied();
+
+        // Despawn the proxies of empty cells.
+        crowd.proxies.retain(|key, &mut proxy| {
+            let keep = cells.contains_key(key);
+
+            if !keep {
+                commands.entity(proxy).insert(Despawned);
+            }
+
+            keep
+        });
+
+        for (key, cell) in cells {
+            let center = cell.sum / f64::from(cell.count);
+
+            let (look, head_yaw) = representatives
+                .get(cell.representative)
+                .map_or((Look::default(), HeadYaw::default()), |(l, h)| (*l, *h));
+
+            if let Some(&proxy) = crowd.proxies.get(&key) {
+                if let Ok((mut state, mut pos, mut proxy_look, mut proxy_head_yaw)) =
+                    proxies.get_mut(proxy)
+                {
+                    state.set_if_neq(CrowdProxy {
+                        crowd: crowd_entity,
+                        representative: cell.representative,
+                    });
+                    pos.set_if_neq(Position(center));
+                    proxy_look.set_if_neq(look);
+                    proxy_head_yaw.set_if_neq(head_yaw);
+                }
+
+                continue;
+            }
+
+            let Ok((_, _, _, kind)) = members.get(cell.representative) else {
+                continue;
+            };
+
+            let proxy = commands
+                .spawn((
+                    CrowdProxy {
+                        crowd: crowd_entity,
+                        representative: cell.representative,
+                    },
+                    *kind,
+                    EntityId::default(),
+                    UniqueId::default(),
+                    EntityLayerId(crowd.summary_layer),
+                    OldEntityLayerId::default(),
+                    Position(center),
+                    OldPosition::new(center),
+                    look,
+                    head_yaw,
+                    (
+                        OnGround::default(),
+                        Velocity::default(),
+                        EntityStatuses::default(),
+                        EntityAnimations::default(),
+                        ObjectData::default(),
+                        TrackedData::default(),
+                    ),
+                ))
+                .id();
+
+            crowd.proxies.insert(key, proxy);
+        }
+    }
+}
+
+fn update_crowd_visibility(
+    crowds: Query<(Entity, &Crowd, Has<Despawned>)>,
+    mut clients: Query<(&Position, &mut VisibleEntityLayers), With<ClientMarker>>,
+) {
+    for (pos, mut visible) in &mut clients {
+        for (crowd_entity, crowd, despawned) in &crowds {
+            let shows_detail = visible.0.contains(&crowd_entity);
+            let shows_summary = visible.0.contains(&crowd.summary_layer);
+
+            let (detail, summary) = match crowd.distance_to(pos.0) {
+                _ if despawned || !visible.0.contains(&crowd.parent) => (false, false),
+                None => (false, false),
+                Some(dist) => {
+                    let margin = |shown| if shown { HYSTERESIS } else { 0.0 };
+
+                    if dist <= crowd.detail_distance + margin(shows_detail) {
+                        (true, false)
+                    } else {
+                        let shown = shows_detail || shows_summary;
+                        (false, dist <= crowd.cull_distance + margin(shown))
+                    }
+                }
+            };
+
+            // Avoid triggering change detection when nothing changed.
+            if detail != shows_detail {
+                if detail {
+                    visible.0.insert(crowd_entity);
+                } else {
+                    visible.0.remove(&crowd_entity);
+                }
+            }
+
+            if summary != shows_summary {
+                if summary {
+                    visible.0.insert(crowd.summary_layer);
+                } else {
+                    visible.0.remove(&crowd.summary_layer);
+                }
+            }
+        }
+    }
+}
+
+fn copy_proxy_tracked_data(
+    mut proxies: Query<(Ref<CrowdProxy>, &mut TrackedData)>,
+    representatives: Query<Ref<TrackedData>, Without<CrowdProxy>>,
+) {
+    for (proxy, mut data) in &mut proxies {
+        let Ok(rep_data) = representatives.get(proxy.representative) else {
+            continue;
+        };
+
+        if proxy.is_changed() || rep_data.is_changed() {
+            *data = rep_data.clone();
+        }
+    }
+}
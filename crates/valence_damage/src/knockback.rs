@@ -0,0 +1,343 @@
+//! Knocking back the victims of attacks.
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use valence_inventory::{HeldItem, Inventory};
+use valence_server::entity::attributes::{EntityAttribute, EntityAttributes};
+use valence_server::entity::entity::Flags;
+use valence_server::entity::{Look, OnGround, Position, Velocity};
+use valence_server::math::{Vec2, Vec3};
+use valence_server::velocity::SetVelocity;
+use valence_server::Despawned;
+
+use crate::modifiers::enchantments;
+
+/// The constants of the knockback formula, in blocks per tick like vanilla.
+///
+/// Every attack pushes the victim away from the attacker. Attacks with bonus
+/// knockback also push the victim in the direction the attacker is looking.
+/// The level of bonus knockback is the level of the knockback enchantment on
+/// the attacker's held item, plus one for a sprint hit.
+///
+/// The resource is [`MODERN`](Self::MODERN) by default. Other constants can be
+/// used by replacing the resource.
+#[derive(Resource, Copy, Clone, PartialEq, Debug)]
+pub struct KnockbackProfile {
+    /// The horizontal push of every attack.
+    pub horizontal: f32,
+    /// The upwards push of every attack.
+    pub vertical: f32,
+    /// The fastest upwards velocity knockback leaves the victim with.
+    pub vertical_limit: f32,
+    /// The fraction of the victim's velocity kept when it is pushed.
+    pub friction: f32,
+    /// Whether victims in the air are pushed upwards.
+    pub airborne_vertical: bool,
+    /// The horizontal push in the direction the attacker is looking per level
+    /// of bonus knockback.
+    pub bonus_horizontal: f32,
+    /// The upwards push of bonus knockback. If
+    /// [`bonus_friction`](Self::bonus_friction) is set, it is multiplied by
+    /// the level of bonus knockback like the horizontal push, otherwise it is
+    /// added once.
+    pub bonus_vertical: f32,
+    /// Whether bonus knockback is applied like a second push, with friction
+    /// and the vertical limit, instead of being added to the velocity.
+    pub bonus_friction: bool,
+    /// Whether sprinting adds a level of bonus knockback.
+    pub sprint_bonus: bool,
+    /// Whether the attacker stops sprinting after a sprint hit, so it has to
+    /// start sprinting again for the next one.
+    pub stop_sprint: bool,
+}
+
+impl KnockbackProfile {
+    /// Knockback like in 1.8, where victims are also pushed upwards in the
+    /// air, and bonus knockback is added on top of the regular knockback.
+    pub const LEGACY: Self = Self {
+        horizontal: 0.4,
+        vertical: 0.4,
+        vertical_limit: 0.4,
+        friction: 0.5,
+        airborne_vertical: true,
+        bonus_horizontal: 0.5,
+        bonus_vertical: 0.1,
+        bonus_friction: false,
+        sprint_bonus: true,
+        stop_sprint: true,
+    };
+
+    /// Knockback like since 1.9, where victims are only pushed upwards on the
+    /// ground, and bonus knockback is a second push.
+    pub const MODERN: Self = Self {
+        horizontal: 0.4,
+        vertical: 0.4,
+        vertical_limit: 0.4,
+        friction: 0.5,
+        airborne_vertical: false,
+        bonus_horizontal: 0.5,
+        bonus_vertical: 0.5,
+        bonus_friction: true,
+        sprint_bonus: true,
+        stop_sprint: true,
+    };
+
+    /// Returns the velocity of a victim after knockback, in blocks per tick.
+    ///
+    /// `away` is the horizontal direction from the attacker to the victim and
+    /// `look` is the horizontal direction the attacker is looking in. The
+    /// pushes are scaled by `1.0 - resistance`.
+    pub fn apply(
+        &self,
+        velocity: Vec3,
+        on_ground: bool,
+        away: Vec2,
+        look: Vec2,
+        bonus_levels: u32,
+        resistance: f32,
+    ) -> Vec3 {
+        let scale = (1.0 - resistance).max(0.0);
+
+        let mut velocity = self.push(
+            velocity,
+            on_ground,
+            away,
+            self.horizontal * scale,
+            self.vertical * scale,
+        );
+
+        if bonus_levels > 0 {
+            let levels = bonus_levels as f32;
+
+            if self.bonus_friction {
+                velocity = self.push(
+                    velocity,
+                    on_ground,
+                    look,
+                    self.bonus_horizontal * levels * scale,
+                    self.bonus_vertical * levels * scale,
+                );
+            } else {
+                let push = look * self.bonus_horizontal * levels * scale;
+                velocity += Vec3::new(push.x, self.bonus_vertical * scale, push.y);
+            }
+        }
+
+        velocity
+    }
+
+    fn push(
+        &self,
+        velocity: Vec3,
+        on_ground: bool,
+        direction: Vec2,
+        horizontal: f32,
+        vertical: f32,
+    ) -> Vec3 {
+        if horizontal <= 0.0 && vertical <= 0.0 {
+            return velocity;
+        }
+
+        let push = direction.normalize_or_zero() * horizontal;
+
+        let y = if on_ground || self.airborne_vertical {
+            (velocity.y * self.friction + vertical).min(self.vertical_limit)
+        } else {
+            velocity.y
+        };
+
+        Vec3::new(
+            velocity.x * self.friction + push.x,
+            y,
+            velocity.z * self.friction + push.y,
+        )
+    }
+}
+
+impl Default for KnockbackProfile {
+    fn default() -> Self {
+        Self::MODERN
+    }
+}
+
+/// [`Command`] to knock `victim` back from an attack by `attacker` with the
+/// [`KnockbackProfile`], and send a [`KnockbackEvent`].
+///
+/// Nothing happens if the victim or the attacker doesn't exist or is
+/// despawned.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Knockback {
+    pub victim: Entity,
+    pub attacker: Entity,
+}
+
+impl Knockback {
+    pub fn new(victim: Entity, attacker: Entity) -> Self {
+        Self { victim, attacker }
+    }
+}
+
+impl Command for Knockback {
+    fn apply(self, world: &mut World) {
+        let profile = world
+            .get_resource::<KnockbackProfile>()
+            .copied()
+            .unwrap_or_default();
+
+        let (Some(victim), Some(attacker)) = (
+            world.get_entity(self.victim),
+            world.get_entity(self.attacker),
+        ) else {
+            return;
+        };
+
+        if victim.contains::<Despawned>() || attacker.contains::<Despawned>() {
+            return;
+        }
+
+        let (Some(victim_pos), Some(attacker_pos)) =
+            (victim.get::<Position>(), attacker.get::<Position>())
+        else {
+            return;
+        };
+
+        let yaw = attacker.get::<Look>().map_or(0.0, |look| look.yaw);
+        let look = Vec2::new(-yaw.to_radians().sin(), yaw.to_radians().cos());
+
+        let away = (victim_pos.0 - attacker_pos.0).as_vec3();
+        let away = match Vec2::new(away.x, away.z).try_normalize() {
+            Some(away) => away,
+            // The attacker is inside the victim.
+            None => look,
+        };
+
+        let sprint_hit =
+            profile.sprint_bonus && attacker.get::<Flags>().is_some_and(|f| f.sprinting());
+
+        let bonus_levels = knockback_level(attacker) + u32::from(sprint_hit);
+
+        let resistance = victim
+            .get::<EntityAttributes>()
+            .and_then(|a| a.get_compute_value(EntityAttribute::GenericKnockbackResistance))
+            .unwrap_or(0.0) as f32;
+
+        let on_ground = victim.get::<OnGround>().is_none_or(|g| g.0);
+        let velocity = victim.get::<Velocity>().map_or(Vec3::ZERO, |v| v.0) / 20.0;
+
+        let velocity = profile.apply(
+            velocity,
+            on_ground,
+            away,
+            look,
+            bonus_levels,
+            resistance.clamp(0.0, 1.0),
+        ) * 20.0;
+
+        SetVelocity {
+            entity: self.victim,
+            velocity,
+        }
+        .apply(world);
+
+        if sprint_hit && profile.stop_sprint {
+            if let Some(mut flags) = world.get_mut::<Flags>(self.attacker) {
+                flags.set_sprinting(false);
+            }
+        }
+
+        world.send_event(KnockbackEvent {
+            victim: self.victim,
+            attacker: self.attacker,
+            velocity,
+            bonus_levels,
+            sprint_hit,
+        });
+    }
+}
+
+/// Returns the level of the knockback enchantment on the held item of an
+/// entity.
+fn knockback_level(entity: EntityRef) -> u32 {
+    let (Some(inventory), Some(held_item)) = (entity.get::<Inventory>(), entity.get::<HeldItem>())
+    else {
+        return 0;
+    };
+
+    enchantments(inventory.slot(held_item.slot()))
+        .filter(|&(id, _)| id.strip_prefix("minecraft:").unwrap_or(id) == "knockback")
+        .map(|(_, lvl)| lvl.max(0) as u32)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Sent after a victim was knocked back with the [`Knockback`] command.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct KnockbackEvent {
+    pub victim: Entity,
+    pub attacker: Entity,
+    /// The new velocity of the victim in m/s.
+    pub velocity: Vec3,
+    /// The level of bonus knockback, including one for a sprint hit.
+    pub bonus_levels: u32,
+    /// Whether the attacker was sprinting and got bonus knockback for it.
+    pub sprint_hit: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn modern_knockback() {
+        let profile = KnockbackProfile::MODERN;
+        let away = Vec2::new(1.0, 0.0);
+
+        assert_close(
+            profile.apply(Vec3::ZERO, true, away, away, 0, 0.0),
+            Vec3::new(0.4, 0.4, 0.0),
+        );
+
+        // Not pushed upwards in the air.
+        assert_close(
+            profile.apply(Vec3::new(0.0, -0.2, 0.0), false, away, away, 0, 0.0),
+            Vec3::new(0.4, -0.2, 0.0),
+        );
+
+        // A sprint hit pushes again and halves the first push.
+        assert_close(
+            profile.apply(Vec3::ZERO, true, away, away, 1, 0.0),
+            Vec3::new(0.7, 0.4, 0.0),
+        );
+
+        assert_close(
+            profile.apply(Vec3::ZERO, true, away, away, 0, 0.5),
+            Vec3::new(0.2, 0.2, 0.0),
+        );
+
+        assert_eq!(
+            profile.apply(Vec3::ZERO, true, away, away, 2, 1.0),
+            Vec3::ZERO
+        );
+    }
+
+    #[test]
+    fn legacy_knockback() {
+        let profile = KnockbackProfile::LEGACY;
+        let away = Vec2::new(0.0, 1.0);
+
+        assert_close(
+            profile.apply(Vec3::new(0.0, -0.2, 0.0), false, away, away, 0, 0.0),
+            Vec3::new(0.0, 0.3, 0.4),
+        );
+
+        // Bonus knockback is added on top of the regular knockback.
+        assert_close(
+            profile.apply(Vec3::ZERO, true, away, away, 2, 0.0),
+            Vec3::new(0.0, 0.5, 1.4),
+        );
+    }
+}
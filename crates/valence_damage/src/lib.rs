@@ -0,0 +1,347 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod knockback;
+mod modifiers;
+
+use std::borrow::Cow;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+pub use knockback::{Knockback, KnockbackEvent, KnockbackProfile};
+pub use modifiers::{armor_modifier, difficulty_modifier, effects_modifier, enchantments_modifier};
+use valence_server::entity::living::{Absorption, Health};
+use valence_server::entity::player::AbsorptionAmount;
+use valence_server::Despawned;
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DamageModifiers>()
+            .init_resource::<KnockbackProfile>()
+            .add_event::<DamageEvent>()
+            .add_event::<KnockbackEvent>();
+    }
+}
+
+/// The kind of harm which caused damage. Determines which of the built-in
+/// modifiers apply.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DamageKind {
+    Generic,
+    /// A melee attack.
+    Attack,
+    Projectile,
+    Explosion,
+    /// Standing in fire or lava.
+    Fire,
+    /// Being on fire.
+    Burning,
+    Fall,
+    Drown,
+    Starve,
+    Magic,
+    /// Falling out of the world.
+    Void,
+}
+
+impl DamageKind {
+    /// Whether armor doesn't reduce this kind of damage.
+    pub fn bypasses_armor(self) -> bool {
+        matches!(
+            self,
+            Self::Generic
+                | Self::Burning
+                | Self::Fall
+                | Self::Drown
+                | Self::Starve
+                | Self::Magic
+                | Self::Void
+        )
+    }
+
+    /// Whether the resistance effect doesn't reduce this kind of damage.
+    pub fn bypasses_resistance(self) -> bool {
+        matches!(self, Self::Starve | Self::Void)
+    }
+
+    /// Whether protection enchantments don't reduce this kind of damage.
+    pub fn bypasses_enchantments(self) -> bool {
+        matches!(self, Self::Starve | Self::Void)
+    }
+
+    pub fn is_fire(self) -> bool {
+        matches!(self, Self::Fire | Self::Burning)
+    }
+}
+
+/// Where damage came from.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DamageSource {
+    pub kind: DamageKind,
+    /// The entity responsible for the damage, if any.
+    pub attacker: Option<Entity>,
+}
+
+/// [`Command`] to deal `amount` damage to `victim`, which must be a living
+/// entity. The amount is passed through the [`DamageModifiers`] before it is
+/// taken from the victim's absorption and health, and a [`DamageEvent`] is
+/// sent afterwards.
+///
+/// Nothing happens if the victim doesn't exist, is despawned, or is already
+/// dead.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Damage {
+    pub victim: Entity,
+    pub amount: f32,
+    pub source: DamageSource,
+}
+
+impl Damage {
+    pub fn new(victim: Entity, amount: f32, kind: DamageKind) -> Self {
+        Self {
+            victim,
+            amount,
+            source: DamageSource {
+                kind,
+                attacker: None,
+            },
+        }
+    }
+
+    pub fn with_attacker(mut self, attacker: Entity) -> Self {
+        self.source.attacker = Some(attacker);
+        self
+    }
+}
+
+impl Command for Damage {
+    fn apply(self, world: &mut World) {
+        match world.get_entity(self.victim) {
+            Some(victim) if !victim.contains::<Despawned>() => {
+                if !victim.get::<Health>().is_some_and(|h| h.0 > 0.0) {
+                    return;
+                }
+            }
+            _ => return,
+        }
+
+        if !world.contains_resource::<DamageModifiers>() {
+            world.init_resource::<DamageModifiers>();
+        }
+
+        let (amount, steps) = world.resource_scope(|world, modifiers: Mut<DamageModifiers>| {
+            let ctx = DamageContext {
+                world,
+                victim: world.entity(self.victim),
+                attacker: self.source.attacker.and_then(|e| world.get_entity(e)),
+                source: self.source,
+            };
+
+            modifiers.apply(&ctx, self.amount)
+        });
+
+        let mut victim = world.entity_mut(self.victim);
+
+        // Absorption is used up before health.
+        let absorbed = if let Some(mut absorption) = victim.get_mut::<AbsorptionAmount>() {
+            let absorbed = absorption.0.clamp(0.0, amount);
+            absorption.0 -= absorbed;
+            absorbed
+        } else if let Some(mut absorption) = victim.get_mut::<Absorption>() {
+            let absorbed = absorption.0.clamp(0.0, amount);
+            absorption.0 -= absorbed;
+            absorbed
+        } else {
+            0.0
+        };
+
+        if let Some(mut health) = victim.get_mut::<Health>() {
+            health.0 = (health.0 - (amount - absorbed)).max(0.0);
+        }
+
+        world.send_event(DamageEvent {
+            victim: self.victim,
+            source: self.source,
+            initial_amount: self.amount,
+            steps,
+            amount,
+            absorbed,
+        });
+    }
+}
+
+/// Sent after damage was dealt with the [`Damage`] command.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct DamageEvent {
+    pub victim: Entity,
+    pub source: DamageSource,
+    /// The amount of damage before any modifiers were applied.
+    pub initial_amount: f32,
+    /// The modifiers which were applied in order.
+    pub steps: Vec<DamageStep>,
+    /// The amount of damage after all modifiers were applied.
+    pub amount: f32,
+    /// The part of [`amount`](Self::amount) which was taken from the victim's
+    /// absorption instead of its health.
+    pub absorbed: f32,
+}
+
+/// The application of a single modifier in a [`DamageEvent`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct DamageStep {
+    pub name: Cow<'static, str>,
+    pub priority: i32,
+    /// The amount of damage before this modifier.
+    pub before: f32,
+    /// The amount of damage after this modifier.
+    pub after: f32,
+}
+
+/// The damage being dealt, as seen by a [`DamageModifier`].
+pub struct DamageContext<'w> {
+    /// The world, for modifiers which depend on more than the victim and the
+    /// attacker.
+    pub world: &'w World,
+    pub victim: EntityRef<'w>,
+    /// The attacker of the [`DamageSource`], if it exists.
+    pub attacker: Option<EntityRef<'w>>,
+    pub source: DamageSource,
+}
+
+/// A step in the damage calculation.
+pub trait DamageModifier: Send + Sync + 'static {
+    /// Returns the new amount of damage. Negative amounts are treated as
+    /// zero.
+    fn modify(&self, ctx: &DamageContext, amount: f32) -> f32;
+}
+
+impl<F> DamageModifier for F
+where
+    F: Fn(&DamageContext, f32) -> f32 + Send + Sync + 'static,
+{
+    fn modify(&self, ctx: &DamageContext, amount: f32) -> f32 {
+        self(ctx, amount)
+    }
+}
+
+struct RegisteredModifier {
+    name: Cow<'static, str>,
+    priority: i32,
+    modifier: Box<dyn DamageModifier>,
+}
+
+/// The ordered pipeline of [`DamageModifier`]s. Modifiers with lower
+/// priorities are applied first, and modifiers with the same priority are
+/// applied in the order they were added.
+///
+/// By default, the modifiers for armor, effects, and enchantments are
+/// registered with the priorities [`ARMOR`](Self::ARMOR),
+/// [`EFFECTS`](Self::EFFECTS), and [`ENCHANTMENTS`](Self::ENCHANTMENTS).
+/// The [`difficulty_modifier`] is not registered by default, and is meant to
+/// be added with the priority [`DIFFICULTY`](Self::DIFFICULTY).
+#[derive(Resource)]
+pub struct DamageModifiers {
+    modifiers: Vec<RegisteredModifier>,
+}
+
+impl DamageModifiers {
+    pub const DIFFICULTY: i32 = 50;
+    pub const ARMOR: i32 = 100;
+    pub const EFFECTS: i32 = 200;
+    pub const ENCHANTMENTS: i32 = 300;
+
+    /// Creates a pipeline without any modifiers.
+    pub fn empty() -> Self {
+        Self { modifiers: vec![] }
+    }
+
+    /// Adds a modifier to the pipeline. Modifiers are identified by `name` in
+    /// [`DamageStep`]s and for removal.
+    pub fn add(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        priority: i32,
+        modifier: impl DamageModifier,
+    ) -> &mut Self {
+        let idx = self.modifiers.partition_point(|m| m.priority <= priority);
+
+        self.modifiers.insert(
+            idx,
+            RegisteredModifier {
+                name: name.into(),
+                priority,
+                modifier: Box::new(modifier),
+            },
+        );
+
+        self
+    }
+
+    /// Removes all modifiers named `name`. Returns whether any were removed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.modifiers.len();
+        self.modifiers.retain(|m| m.name != name);
+        self.modifiers.len() != len
+    }
+
+    /// Returns the names and priorities of the modifiers in the order they
+    /// are applied.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i32)> + '_ {
+        self.modifiers.iter().map(|m| (m.name.as_ref(), m.priority))
+    }
+
+    /// Passes `amount` through every modifier. Returns the final amount and
+    /// the steps taken.
+    pub fn apply(&self, ctx: &DamageContext, amount: f32) -> (f32, Vec<DamageStep>) {
+        let mut amount = amount.max(0.0);
+        let mut steps = Vec::with_capacity(self.modifiers.len());
+
+        for m in &self.modifiers {
+            let after = m.modifier.modify(ctx, amount).max(0.0);
+
+            steps.push(DamageStep {
+                name: m.name.clone(),
+                priority: m.priority,
+                before: amount,
+                after,
+            });
+
+            amount = after;
+        }
+
+        (amount, steps)
+    }
+}
+
+impl Default for DamageModifiers {
+    fn default() -> Self {
+        let mut modifiers = Self::empty();
+
+        modifiers
+            .add("armor", Self::ARMOR, armor_modifier)
+            .add("effects", Self::EFFECTS, effects_modifier)
+            .add("enchantments", Self::ENCHANTMENTS, enchantments_modifier);
+
+        modifiers
+    }
+}
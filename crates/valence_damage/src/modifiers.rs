@@ -0,0 +1,264 @@
+//! The built-in damage modifiers, following the vanilla formulas.
+
+use valence_difficulty::WorldDifficulty;
+use valence_inventory::Inventory;
+use valence_server::client::{Client, VisibleChunkLayer};
+use valence_server::entity::active_status_effects::ActiveStatusEffects;
+use valence_server::entity::attributes::{EntityAttribute, EntityAttributes};
+use valence_server::entity::living::Health;
+use valence_server::nbt::{List, Value};
+use valence_server::protocol::status_effects::StatusEffect;
+use valence_server::{Difficulty, ItemKind, ItemStack};
+
+use crate::{DamageContext, DamageKind};
+
+/// The slots of the armor in a player's inventory, from head to feet.
+const ARMOR_SLOTS: std::ops::RangeInclusive<u16> = 5..=8;
+
+/// Reduces damage by the armor points and toughness of the victim. The armor
+/// of the victim is the sum of its `generic.armor` attributes and the armor
+/// in its inventory.
+pub fn armor_modifier(ctx: &DamageContext, amount: f32) -> f32 {
+    if ctx.source.kind.bypasses_armor() {
+        return amount;
+    }
+
+    let mut armor = 0.0;
+    let mut toughness = 0.0;
+
+    if let Some(attributes) = ctx.victim.get::<EntityAttributes>() {
+        armor += attributes
+            .get_compute_value(EntityAttribute::GenericArmor)
+            .unwrap_or(0.0) as f32;
+        toughness += attributes
+            .get_compute_value(EntityAttribute::GenericArmorToughness)
+            .unwrap_or(0.0) as f32;
+    }
+
+    for stack in worn_armor(ctx) {
+        let (points, tough) = armor_points(stack.item);
+        armor += points;
+        toughness += tough;
+    }
+
+    apply_armor(amount, armor, toughness)
+}
+
+/// Scales attacks by mobs on players with the difficulty of the player's
+/// layer, like vanilla. Peaceful cancels the damage, easy halves it and adds
+/// one, and hard increases it by half.
+pub fn difficulty_modifier(ctx: &DamageContext, amount: f32) -> f32 {
+    let by_mob = ctx
+        .attacker
+        .is_some_and(|attacker| attacker.contains::<Health>() && !attacker.contains::<Client>());
+
+    let scales = matches!(
+        ctx.source.kind,
+        DamageKind::Attack | DamageKind::Projectile | DamageKind::Explosion
+    ) && by_mob
+        && ctx.victim.contains::<Client>();
+
+    if !scales {
+        return amount;
+    }
+
+    let difficulty = ctx
+        .victim
+        .get::<VisibleChunkLayer>()
+        .and_then(|layer| ctx.world.get::<WorldDifficulty>(layer.0))
+        .copied()
+        .unwrap_or_default();
+
+    apply_difficulty(amount, difficulty.difficulty)
+}
+
+/// Reduces damage by the resistance effect of the victim, and cancels fire
+/// damage if the victim has fire resistance.
+pub fn effects_modifier(ctx: &DamageContext, amount: f32) -> f32 {
+    let Some(effects) = ctx.victim.get::<ActiveStatusEffects>() else {
+        return amount;
+    };
+
+    if ctx.source.kind.is_fire() && effects.has_effect(StatusEffect::FireResistance) {
+        return 0.0;
+    }
+
+    if ctx.source.kind.bypasses_resistance() {
+        return amount;
+    }
+
+    match effects.get_current_effect(StatusEffect::Resistance) {
+        Some(effect) => apply_resistance(amount, effect.amplifier()),
+        None => amount,
+    }
+}
+
+/// Reduces damage by the protection enchantments on the armor of the victim.
+pub fn enchantments_modifier(ctx: &DamageContext, amount: f32) -> f32 {
+    let kind = ctx.source.kind;
+
+    if kind.bypasses_enchantments() {
+        return amount;
+    }
+
+    let epf: i32 = worn_armor(ctx)
+        .flat_map(enchantments)
+        .map(|(id, lvl)| protection_factor(kind, id, lvl))
+        .sum();
+
+    apply_protection(amount, epf)
+}
+
+fn apply_difficulty(amount: f32, difficulty: Difficulty) -> f32 {
+    match difficulty {
+        Difficulty::Peaceful => 0.0,
+        Difficulty::Easy => (amount / 2.0 + 1.0).min(amount),
+        Difficulty::Normal => amount,
+        Difficulty::Hard => amount * 1.5,
+    }
+}
+
+fn worn_armor<'a>(ctx: &'a DamageContext) -> impl Iterator<Item = &'a ItemStack> + 'a {
+    ctx.victim
+        .get::<Inventory>()
+        .into_iter()
+        .flat_map(|inv| ARMOR_SLOTS.map(move |idx| inv.slot(idx)))
+        .filter(|stack| !stack.is_empty())
+}
+
+/// Returns the enchantment IDs and levels of an item.
+pub(crate) fn enchantments(stack: &ItemStack) -> impl Iterator<Item = (&str, i32)> + '_ {
+    let list = match stack.nbt.as_ref().and_then(|nbt| nbt.get("Enchantments")) {
+        Some(Value::List(List::Compound(list))) => list.as_slice(),
+        _ => &[],
+    };
+
+    list.iter().filter_map(|ench| {
+        let Some(Value::String(id)) = ench.get("id") else {
+            return None;
+        };
+
+        let lvl = match ench.get("lvl")? {
+            Value::Byte(lvl) => i32::from(*lvl),
+            Value::Short(lvl) => i32::from(*lvl),
+            Value::Int(lvl) => *lvl,
+            _ => return None,
+        };
+
+        Some((id.as_str(), lvl))
+    })
+}
+
+/// Returns the enchantment protection factor of an enchantment against a kind
+/// of damage.
+fn protection_factor(kind: DamageKind, id: &str, lvl: i32) -> i32 {
+    let id = id.strip_prefix("minecraft:").unwrap_or(id);
+
+    let factor = match id {
+        "protection" => 1,
+        "fire_protection" if kind.is_fire() => 2,
+        "blast_protection" if kind == DamageKind::Explosion => 2,
+        "projectile_protection" if kind == DamageKind::Projectile => 2,
+        "feather_falling" if kind == DamageKind::Fall => 3,
+        _ => 0,
+    };
+
+    factor * lvl.max(0)
+}
+
+/// Returns the armor points and toughness of a piece of armor.
+fn armor_points(item: ItemKind) -> (f32, f32) {
+    match item {
+        ItemKind::LeatherHelmet => (1.0, 0.0),
+        ItemKind::LeatherChestplate => (3.0, 0.0),
+        ItemKind::LeatherLeggings => (2.0, 0.0),
+        ItemKind::LeatherBoots => (1.0, 0.0),
+        ItemKind::ChainmailHelmet => (2.0, 0.0),
+        ItemKind::ChainmailChestplate => (5.0, 0.0),
+        ItemKind::ChainmailLeggings => (4.0, 0.0),
+        ItemKind::ChainmailBoots => (1.0, 0.0),
+        ItemKind::IronHelmet => (2.0, 0.0),
+        ItemKind::IronChestplate => (6.0, 0.0),
+        ItemKind::IronLeggings => (5.0, 0.0),
+        ItemKind::IronBoots => (2.0, 0.0),
+        ItemKind::GoldenHelmet => (2.0, 0.0),
+        ItemKind::GoldenChestplate => (5.0, 0.0),
+        ItemKind::GoldenLeggings => (3.0, 0.0),
+        ItemKind::GoldenBoots => (1.0, 0.0),
+        ItemKind::DiamondHelmet => (3.0, 2.0),
+        ItemKind::DiamondChestplate => (8.0, 2.0),
+        ItemKind::DiamondLeggings => (6.0, 2.0),
+        ItemKind::DiamondBoots => (3.0, 2.0),
+        ItemKind::NetheriteHelmet => (3.0, 3.0),
+        ItemKind::NetheriteChestplate => (8.0, 3.0),
+        ItemKind::NetheriteLeggings => (6.0, 3.0),
+        ItemKind::NetheriteBoots => (3.0, 3.0),
+        ItemKind::TurtleHelmet => (2.0, 0.0),
+        _ => (0.0, 0.0),
+    }
+}
+
+fn apply_armor(amount: f32, armor: f32, toughness: f32) -> f32 {
+    let f = 2.0 + toughness / 4.0;
+    let g = (armor - amount / f).clamp(armor * 0.2, 20.0);
+    amount * (1.0 - g / 25.0)
+}
+
+fn apply_resistance(amount: f32, amplifier: u8) -> f32 {
+    (amount * (1.0 - 0.2 * (f32::from(amplifier) + 1.0))).max(0.0)
+}
+
+fn apply_protection(amount: f32, epf: i32) -> f32 {
+    amount * (1.0 - epf.clamp(0, 20) as f32 / 25.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn armor_formula() {
+        // Full diamond armor against a 10 damage attack.
+        let dmg = apply_armor(10.0, 20.0, 8.0);
+        assert!((dmg - 3.0).abs() < 1e-4, "{dmg}");
+
+        // Armor is less effective against large amounts of damage.
+        let dmg = apply_armor(40.0, 20.0, 0.0);
+        assert!((dmg - 40.0 * (1.0 - 4.0 / 25.0)).abs() < 1e-4, "{dmg}");
+
+        assert_eq!(apply_armor(5.0, 0.0, 0.0), 5.0);
+    }
+
+    #[test]
+    fn difficulty_formula() {
+        assert_eq!(apply_difficulty(6.0, Difficulty::Peaceful), 0.0);
+        assert_eq!(apply_difficulty(6.0, Difficulty::Easy), 4.0);
+        assert_eq!(apply_difficulty(1.0, Difficulty::Easy), 1.0);
+        assert_eq!(apply_difficulty(6.0, Difficulty::Normal), 6.0);
+        assert_eq!(apply_difficulty(6.0, Difficulty::Hard), 9.0);
+    }
+
+    #[test]
+    fn resistance_formula() {
+        assert!((apply_resistance(10.0, 0) - 8.0).abs() < 1e-4);
+        assert!((apply_resistance(10.0, 1) - 6.0).abs() < 1e-4);
+        assert_eq!(apply_resistance(10.0, 4), 0.0);
+        assert_eq!(apply_resistance(10.0, 9), 0.0);
+    }
+
+    #[test]
+    fn protection_factor_cap() {
+        assert!((apply_protection(10.0, 4) - 8.4).abs() < 1e-4);
+        assert!((apply_protection(10.0, 40) - 2.0).abs() < 1e-4);
+
+        assert_eq!(
+            protection_factor(DamageKind::Fall, "minecraft:feather_falling", 4),
+            12
+        );
+        assert_eq!(
+            protection_factor(DamageKind::Attack, "feather_falling", 4),
+            0
+        );
+        assert_eq!(protection_factor(DamageKind::Attack, "protection", 3), 3);
+    }
+}
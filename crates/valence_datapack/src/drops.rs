@@ -0,0 +1,203 @@
+//! Drops of blocks broken by players, from the `minecraft:blocks/<block>` loot
+//! tables.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_inventory::{HeldItem, Inventory};
+use valence_server::action::{DiggingEvent, DiggingState};
+use valence_server::client::{UpdateClientsSet, VisibleChunkLayer};
+use valence_server::entity::item::{ItemEntityBundle, Stack};
+use valence_server::entity::{EntityLayerId, Position, Velocity};
+use valence_server::event_loop::{EventLoopPreUpdate, HandleActionPacketsSet};
+use valence_server::math::{DVec3, Vec3};
+use valence_server::rand::Rng;
+use valence_server::registry::tags::TagsRegistry;
+use valence_server::{BlockPos, BlockState, ChunkLayer, GameMode, ItemStack};
+
+use crate::loot::{LootContext, LootTables};
+
+/// Drops the loot of blocks broken by players in survival or adventure mode.
+///
+/// Valence doesn't break blocks by itself, so a block only drops its loot if
+/// it was removed or replaced in the tick a player finished digging it. This
+/// way, blocks which are protected from breaking don't drop anything.
+pub struct BlockDropsPlugin;
+
+impl Plugin for BlockDropsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingBlockDrops>()
+            .add_event::<BlockDropsEvent>()
+            .configure_sets(
+                EventLoopPreUpdate,
+                BlockDropsSet.after(HandleActionPacketsSet),
+            )
+            .add_systems(EventLoopPreUpdate, queue_block_drops.in_set(BlockDropsSet))
+            .add_systems(PostUpdate, drop_broken_blocks.before(UpdateClientsSet));
+    }
+}
+
+/// The system set in [`EventLoopPreUpdate`] which records the state of the
+/// blocks players finished digging, right after the [`DiggingEvent`]s are
+/// sent.
+///
+/// Systems breaking blocks in response to [`DiggingEvent`]s in
+/// [`EventLoopUpdate`] or later always run after it. Systems doing so in
+/// [`EventLoopPreUpdate`] must be ordered after this set, or the block is
+/// already gone and doesn't drop anything.
+///
+/// [`EventLoopUpdate`]: valence_server::EventLoopUpdate
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BlockDropsSet;
+
+/// Sent when a block broken by a player drops loot. The items are spawned as
+/// item entities around the block.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct BlockDropsEvent {
+    pub client: Entity,
+    pub position: BlockPos,
+    /// The state of the block before it was broken.
+    pub state: BlockState,
+    pub items: Vec<ItemStack>,
+}
+
+/// The blocks players finished digging this tick.
+#[derive(Resource, Default)]
+struct PendingBlockDrops(Vec<PendingDrop>);
+
+struct PendingDrop {
+    client: Entity,
+    chunk_layer: Entity,
+    entity_layer: Entity,
+    position: BlockPos,
+    state: BlockState,
+    tool: ItemStack,
+}
+
+fn queue_block_drops(
+    mut events: EventReader<DiggingEvent>,
+    clients: Query<(
+        &GameMode,
+        &VisibleChunkLayer,
+        &EntityLayerId,
+        &Inventory,
+        &HeldItem,
+    )>,
+    layers: Query<&ChunkLayer>,
+    mut pending: ResMut<PendingBlockDrops>,
+) {
+    for event in events.read() {
+        // Blocks which break instantly are only started.
+        if !matches!(event.state, DiggingState::Start | DiggingState::Stop) {
+            continue;
+        }
+
+        let Ok((game_mode, chunk_layer, entity_layer, inventory, held_item)) =
+            clients.get(event.client)
+        else {
+            continue;
+        };
+
+        if !matches!(game_mode, GameMode::Survival | GameMode::Adventure) {
+            continue;
+        }
+
+        let Some(block) = layers
+            .get(chunk_layer.0)
+            .ok()
+            .and_then(|layer| layer.block(event.position))
+            .filter(|block| !block.state.is_air())
+        else {
+            continue;
+        };
+
+        if pending
+            .0
+            .iter()
+            .any(|drop| drop.chunk_layer == chunk_layer.0 && drop.position == event.position)
+        {
+            continue;
+        }
+
+        pending.0.push(PendingDrop {
+            client: event.client,
+            chunk_layer: chunk_layer.0,
+            entity_layer: entity_layer.0,
+            position: event.position,
+            state: block.state,
+            tool: inventory.slot(held_item.slot()).clone(),
+        });
+    }
+}
+
+fn drop_broken_blocks(
+    mut pending: ResMut<PendingBlockDrops>,
+    layers: Query<&ChunkLayer>,
+    loot_tables: Res<LootTables>,
+    tags: Res<TagsRegistry>,
+    mut events: EventWriter<BlockDropsEvent>,
+    mut commands: Commands,
+) {
+    let mut rng = valence_server::rand::thread_rng();
+
+    for drop in pending.0.drain(..) {
+        let broken = layers
+            .get(drop.chunk_layer)
+            .ok()
+            .and_then(|layer| layer.block(drop.position))
+            .is_some_and(|block| block.state != drop.state);
+
+        if !broken {
+            continue;
+        }
+
+        let name = format!("minecraft:blocks/{}", drop.state.to_kind().to_str());
+
+        let Some(table) = loot_tables.get(&name) else {
+            continue;
+        };
+
+        let mut ctx =
+            LootContext::new(&loot_tables, &tags.registries, &mut rng).with_block_state(drop.state);
+
+        if !drop.tool.is_empty() {
+            ctx = ctx.with_tool(&drop.tool);
+        }
+
+        let items = table.generate(&mut ctx);
+
+        if items.is_empty() {
+            continue;
+        }
+
+        let center = DVec3::new(
+            f64::from(drop.position.x) + 0.5,
+            f64::from(drop.position.y) + 0.5,
+            f64::from(drop.position.z) + 0.5,
+        );
+
+        for item in &items {
+            // Like vanilla, items pop out of the block in a random direction.
+            let offset = DVec3::new(
+                rng.gen_range(-0.25..0.25),
+                rng.gen_range(-0.25..0.25) - 0.125,
+                rng.gen_range(-0.25..0.25),
+            );
+            let velocity = Vec3::new(rng.gen_range(-2.0..2.0), 4.0, rng.gen_range(-2.0..2.0));
+
+            commands.spawn(ItemEntityBundle {
+                item_stack: Stack(item.clone()),
+                layer: EntityLayerId(drop.entity_layer),
+                position: Position(center + offset),
+                velocity: Velocity(velocity),
+                ..Default::default()
+            });
+        }
+
+        events.send(BlockDropsEvent {
+            client: drop.client,
+            position: drop.position,
+            state: drop.state,
+            items,
+        });
+    }
+}
@@ -0,0 +1,180 @@
+//! The enchantments of items, as far as loot functions need them.
+
+use valence_server::nbt::{compound, List, Value};
+use valence_server::{ItemKind, ItemStack};
+
+/// The kinds of items an enchantment can be applied to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Target {
+    Armor,
+    ArmorHead,
+    ArmorFeet,
+    Wearable,
+    Weapon,
+    Digger,
+    Breakable,
+    Bow,
+    Crossbow,
+    Trident,
+    FishingRod,
+    Vanishable,
+}
+
+/// The enchantments which can be found in loot, with their maximum levels.
+/// Soul speed and swift sneak are missing, since they can't be found.
+const ENCHANTMENTS: [(&str, i32, Target); 37] = [
+    ("protection", 4, Target::Armor),
+    ("fire_protection", 4, Target::Armor),
+    ("feather_falling", 4, Target::ArmorFeet),
+    ("blast_protection", 4, Target::Armor),
+    ("projectile_protection", 4, Target::Armor),
+    ("respiration", 3, Target::ArmorHead),
+    ("aqua_affinity", 1, Target::ArmorHead),
+    ("thorns", 3, Target::Armor),
+    ("depth_strider", 3, Target::ArmorFeet),
+    ("frost_walker", 2, Target::ArmorFeet),
+    ("binding_curse", 1, Target::Wearable),
+    ("sharpness", 5, Target::Weapon),
+    ("smite", 5, Target::Weapon),
+    ("bane_of_arthropods", 5, Target::Weapon),
+    ("knockback", 2, Target::Weapon),
+    ("fire_aspect", 2, Target::Weapon),
+    ("looting", 3, Target::Weapon),
+    ("sweeping", 3, Target::Weapon),
+    ("efficiency", 5, Target::Digger),
+    ("silk_touch", 1, Target::Digger),
+    ("unbreaking", 3, Target::Breakable),
+    ("fortune", 3, Target::Digger),
+    ("power", 5, Target::Bow),
+    ("punch", 2, Target::Bow),
+    ("flame", 1, Target::Bow),
+    ("infinity", 1, Target::Bow),
+    ("luck_of_the_sea", 3, Target::FishingRod),
+    ("lure", 3, Target::FishingRod),
+    ("loyalty", 3, Target::Trident),
+    ("impaling", 5, Target::Trident),
+    ("riptide", 3, Target::Trident),
+    ("channeling", 1, Target::Trident),
+    ("multishot", 1, Target::Crossbow),
+    ("quick_charge", 3, Target::Crossbow),
+    ("piercing", 4, Target::Crossbow),
+    ("mending", 1, Target::Breakable),
+    ("vanishing_curse", 1, Target::Vanishable),
+];
+
+impl Target {
+    fn matches(self, item: ItemKind) -> bool {
+        let name = item.to_str();
+        let armor = |suffix: &str| name.ends_with(suffix);
+
+        match self {
+            Self::Armor => ["_helmet", "_chestplate", "_leggings", "_boots"]
+                .into_iter()
+                .any(armor),
+            Self::ArmorHead => armor("_helmet"),
+            Self::ArmorFeet => armor("_boots"),
+            Self::Wearable => {
+                Self::Armor.matches(item)
+                    || matches!(item, ItemKind::Elytra | ItemKind::CarvedPumpkin)
+                    || name.ends_with("_head")
+                    || name.ends_with("_skull")
+            }
+            // Axes are included, since they can get the damage enchantments.
+            Self::Weapon => name.ends_with("_sword") || name.ends_with("_axe"),
+            Self::Digger => ["_pickaxe", "_shovel", "_axe", "_hoe"]
+                .into_iter()
+                .any(|suffix| name.ends_with(suffix)),
+            Self::Breakable => item.max_durability() > 0,
+            Self::Bow => item == ItemKind::Bow,
+            Self::Crossbow => item == ItemKind::Crossbow,
+            Self::Trident => item == ItemKind::Trident,
+            Self::FishingRod => item == ItemKind::FishingRod,
+            Self::Vanishable => {
+                Self::Breakable.matches(item)
+                    || Self::Wearable.matches(item)
+                    || matches!(item, ItemKind::Compass | ItemKind::RecoveryCompass)
+            }
+        }
+    }
+}
+
+/// Returns the maximum level of an enchantment, or `None` if it can't be
+/// found in loot.
+pub(crate) fn max_level(id: &str) -> Option<i32> {
+    let id = id.strip_prefix("minecraft:").unwrap_or(id);
+
+    ENCHANTMENTS
+        .iter()
+        .find(|(name, ..)| *name == id)
+        .map(|&(_, max, _)| max)
+}
+
+/// Returns the enchantments which can be applied to an item by loot, with
+/// their maximum levels. Books can get any enchantment.
+pub(crate) fn applicable(item: ItemKind) -> impl Iterator<Item = (&'static str, i32)> {
+    ENCHANTMENTS
+        .iter()
+        .filter(move |(_, _, target)| item == ItemKind::Book || target.matches(item))
+        .map(|&(name, max, _)| (name, max))
+}
+
+/// Returns whether the enchantment can be applied to the item. Unknown
+/// enchantments can only be applied to books.
+pub(crate) fn can_apply(id: &str, item: ItemKind) -> bool {
+    let id = id.strip_prefix("minecraft:").unwrap_or(id);
+
+    item == ItemKind::Book || applicable(item).any(|(name, _)| name == id)
+}
+
+/// Returns the level of an enchantment on an item, or zero if the item doesn't
+/// have it.
+pub(crate) fn level(stack: &ItemStack, id: &str) -> i32 {
+    let id = id.strip_prefix("minecraft:").unwrap_or(id);
+
+    let list = match stack.nbt.as_ref().and_then(|nbt| nbt.get("Enchantments")) {
+        Some(Value::List(List::Compound(list))) => list.as_slice(),
+        _ => &[],
+    };
+
+    list.iter()
+        .filter(|ench| match ench.get("id") {
+            Some(Value::String(ench)) => ench.strip_prefix("minecraft:").unwrap_or(ench) == id,
+            _ => false,
+        })
+        .find_map(|ench| match ench.get("lvl")? {
+            Value::Byte(lvl) => Some(i32::from(*lvl)),
+            Value::Short(lvl) => Some(i32::from(*lvl)),
+            Value::Int(lvl) => Some(*lvl),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+/// Adds an enchantment to an item. Books are turned into enchanted books.
+pub(crate) fn enchant(stack: &mut ItemStack, id: &str, lvl: i32) {
+    let key = if matches!(stack.item, ItemKind::Book | ItemKind::EnchantedBook) {
+        stack.item = ItemKind::EnchantedBook;
+        "StoredEnchantments"
+    } else {
+        "Enchantments"
+    };
+
+    let id = if id.contains(':') {
+        id.to_owned()
+    } else {
+        format!("minecraft:{id}")
+    };
+
+    let nbt = stack.nbt.get_or_insert_with(Default::default);
+
+    if !matches!(nbt.get(key), Some(Value::List(List::Compound(_)))) {
+        nbt.insert(key, List::Compound(vec![]));
+    }
+
+    if let Some(Value::List(List::Compound(list))) = nbt.get_mut(key) {
+        list.push(compound! {
+            "id" => id,
+            "lvl" => lvl.clamp(0, i32::from(i16::MAX)) as i16,
+        });
+    }
+}
@@ -0,0 +1,407 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+pub mod drops;
+mod enchantment;
+pub mod loot;
+pub mod recipe;
+mod tags;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+pub use drops::{BlockDropsEvent, BlockDropsPlugin, BlockDropsSet};
+pub use loot::{LootContext, LootTables};
+pub use recipe::Recipes;
+use serde_json::Value;
+use thiserror::Error;
+use tracing::{debug, error, info, warn};
+use valence_command::graph::CommandGraphBuilder;
+use valence_command::handler::CommandResultEvent;
+use valence_command::AddCommand;
+use valence_server::client::{Client, FlushPacketsSet, UpdateClientsSet};
+use valence_server::ident::IdentError;
+use valence_server::message::SendMessage;
+use valence_server::protocol::encode::{PacketWriter, WritePacket};
+use valence_server::protocol::packets::play::SynchronizeRecipesS2c;
+use valence_server::protocol::RawBytes;
+use valence_server::registry::tags::{RegistryMap, TagsRegistry};
+use valence_server::{Ident, Server};
+
+use crate::loot::LootTable;
+use crate::recipe::Recipe;
+
+/// Loads the data packs in [`DatapackSettings`] on startup, and again when
+/// the `/reload` command or the [`ReloadDatapacks`] command is run.
+pub struct DatapackPlugin;
+
+impl Plugin for DatapackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DatapackSettings>()
+            .init_resource::<LootTables>()
+            .init_resource::<Recipes>()
+            .init_resource::<DatapackState>()
+            .add_event::<DatapacksReloadedEvent>()
+            .add_command::<ReloadCommand>()
+            .add_systems(Startup, load_datapacks)
+            .add_systems(
+                Update,
+                (
+                    handle_reload_command,
+                    apply_deferred, // So the reload happens before feedback is sent.
+                    send_reload_feedback,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                (cache_recipes_packet, sync_clients)
+                    .chain()
+                    .after(UpdateClientsSet)
+                    .before(FlushPacketsSet),
+            );
+    }
+}
+
+#[derive(Resource, Clone, Default, Debug)]
+pub struct DatapackSettings {
+    /// The root directories of the data packs to load, each containing a
+    /// `data` directory. Files in later packs override files in earlier ones.
+    pub packs: Vec<PathBuf>,
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DatapackError {
+    #[error("failed to read \"{}\": {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("failed to parse \"{}\": {source}", path.display())]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Ident(#[from] IdentError),
+    #[error("invalid tag \"{0}\"")]
+    InvalidTag(Ident<String>),
+    #[error("tag \"{0}\" includes itself")]
+    TagCycle(Ident<String>),
+    #[error("tag \"{tag}\" has unknown entry \"{entry}\"")]
+    UnknownTagEntry {
+        tag: Ident<String>,
+        entry: Ident<String>,
+    },
+    #[error("invalid loot table \"{0}\"")]
+    InvalidLootTable(Ident<String>),
+    #[error("invalid recipe \"{0}\"")]
+    InvalidRecipe(Ident<String>),
+}
+
+/// The number of files of each kind loaded from data packs.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct DatapackSummary {
+    pub tags: usize,
+    pub loot_tables: usize,
+    pub recipes: usize,
+}
+
+/// Sent after the data packs were loaded. If loading failed, the previously
+/// loaded data is kept.
+#[derive(Event, Debug)]
+pub struct DatapacksReloadedEvent {
+    pub result: Result<DatapackSummary, DatapackError>,
+}
+
+/// [`Command`] to load the data packs in [`DatapackSettings`] again and
+/// send the new tags and recipes to all clients.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct ReloadDatapacks;
+
+impl Command for ReloadDatapacks {
+    fn apply(self, world: &mut World) {
+        let result = world.resource_scope(|world, mut state: Mut<DatapackState>| {
+            // Leave the registries alone unless there are packs to load or
+            // previously loaded packs to unload.
+            if world.resource::<DatapackSettings>().packs.is_empty() && state.base_tags.is_none() {
+                return Ok(DatapackSummary::default());
+            }
+
+            let base = state
+                .base_tags
+                .get_or_insert_with(|| world.resource::<TagsRegistry>().registries.clone());
+
+            let loaded = load(&world.resource::<DatapackSettings>().packs, base)?;
+            let summary = loaded.summary;
+
+            world.resource_mut::<TagsRegistry>().registries = loaded.tags;
+            world.resource_mut::<LootTables>().tables = loaded.loot_tables;
+            world.resource_mut::<Recipes>().recipes = loaded.recipes;
+
+            Ok(summary)
+        });
+
+        match &result {
+            Ok(summary) if *summary == DatapackSummary::default() => {
+                debug!("no data packs loaded")
+            }
+            Ok(summary) => info!(
+                "loaded {} tags, {} loot tables and {} recipes from data packs",
+                summary.tags, summary.loot_tables, summary.recipes
+            ),
+            Err(e) => error!("failed to load data packs: {e}"),
+        }
+
+        world.send_event(DatapacksReloadedEvent { result });
+    }
+}
+
+#[derive(Resource, Default)]
+struct DatapackState {
+    /// The tags before any data packs were applied.
+    base_tags: Option<RegistryMap>,
+    /// The clients which ran `/reload` and are waiting for the result.
+    requesters: Vec<Entity>,
+}
+
+/// The `/reload` command.
+struct ReloadCommand;
+
+impl valence_command::Command for ReloadCommand {
+    fn assemble_graph(graph: &mut CommandGraphBuilder<Self>) {
+        graph
+            .root()
+            .literal("reload")
+            .with_executable(|_| ReloadCommand)
+            .with_scopes(vec!["valence.command.reload"]);
+    }
+}
+
+struct Loaded {
+    tags: RegistryMap,
+    loot_tables: BTreeMap<Ident<String>, LootTable>,
+    recipes: BTreeMap<Ident<String>, Recipe>,
+    summary: DatapackSummary,
+}
+
+fn load(packs: &[PathBuf], base: &RegistryMap) -> Result<Loaded, DatapackError> {
+    let mut tag_defs = tags::TagDefinitions::new();
+    let mut loot_files = BTreeMap::new();
+    let mut recipe_files = BTreeMap::new();
+
+    for pack in packs {
+        for (namespace, dir) in read_dir(&pack.join("data"))? {
+            if !dir.is_dir() {
+                continue;
+            }
+
+            for (path, json) in read_json_files(&dir.join("tags"))? {
+                let Some((registry, tag)) = tags::split_tag_path(&path, base) else {
+                    warn!("ignoring tag \"{namespace}:{path}\" of unknown registry");
+                    continue;
+                };
+
+                let tag = parse_ident(&format!("{namespace}:{tag}"))?;
+                tags::add_tag_file(&mut tag_defs, registry, tag, &json)?;
+            }
+
+            for (path, json) in read_json_files(&dir.join("loot_tables"))? {
+                loot_files.insert(parse_ident(&format!("{namespace}:{path}"))?, json);
+            }
+
+            for (path, json) in read_json_files(&dir.join("recipes"))? {
+                recipe_files.insert(parse_ident(&format!("{namespace}:{path}"))?, json);
+            }
+        }
+    }
+
+    let tags = tags::apply_tags(base, &tag_defs)?;
+
+    let loot_tables = loot_files
+        .iter()
+        .map(|(name, json)| Ok((name.clone(), LootTable::from_json(name, json)?)))
+        .collect::<Result<BTreeMap<_, _>, DatapackError>>()?;
+
+    let recipes = recipe_files
+        .iter()
+        .map(|(name, json)| Ok((name.clone(), Recipe::from_json(name, json, &tags)?)))
+        .collect::<Result<BTreeMap<_, _>, DatapackError>>()?;
+
+    Ok(Loaded {
+        summary: DatapackSummary {
+            tags: tag_defs.len(),
+            loot_tables: loot_tables.len(),
+            recipes: recipes.len(),
+        },
+        tags,
+        loot_tables,
+        recipes,
+    })
+}
+
+/// Returns the names and paths of the entries of a directory, sorted by name.
+fn read_dir(dir: &Path) -> Result<Vec<(String, PathBuf)>, DatapackError> {
+    let io_err = |source| DatapackError::Io {
+        path: dir.into(),
+        source,
+    };
+
+    let mut entries = vec![];
+
+    for entry in fs::read_dir(dir).map_err(io_err)? {
+        let entry = entry.map_err(io_err)?;
+
+        if let Ok(name) = entry.file_name().into_string() {
+            entries.push((name, entry.path()));
+        }
+    }
+
+    entries.sort();
+
+    Ok(entries)
+}
+
+/// Reads the JSON files in a directory and its subdirectories. Returns the
+/// path of each file relative to `dir` without the `.json` extension, using
+/// `/` as the separator. Returns nothing if the directory doesn't exist.
+fn read_json_files(dir: &Path) -> Result<Vec<(String, Value)>, DatapackError> {
+    fn walk(
+        dir: &Path,
+        prefix: &str,
+        files: &mut Vec<(String, Value)>,
+    ) -> Result<(), DatapackError> {
+        for (name, path) in read_dir(dir)? {
+            if path.is_dir() {
+                walk(&path, &format!("{prefix}{name}/"), files)?;
+            } else if let Some(stem) = name.strip_suffix(".json") {
+                let contents = fs::read(&path).map_err(|source| DatapackError::Io {
+                    path: path.clone(),
+                    source,
+                })?;
+
+                let json = serde_json::from_slice(&contents)
+                    .map_err(|source| DatapackError::Json { path, source })?;
+
+                files.push((format!("{prefix}{stem}"), json));
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = vec![];
+
+    if dir.is_dir() {
+        walk(dir, "", &mut files)?;
+    }
+
+    Ok(files)
+}
+
+fn parse_ident(s: &str) -> Result<Ident<String>, DatapackError> {
+    Ok(Ident::<String>::try_from(s)?)
+}
+
+fn load_datapacks(world: &mut World) {
+    ReloadDatapacks.apply(world);
+}
+
+fn handle_reload_command(
+    mut events: EventReader<CommandResultEvent<ReloadCommand>>,
+    mut state: ResMut<DatapackState>,
+    mut commands: Commands,
+) {
+    if events.is_empty() {
+        return;
+    }
+
+    for event in events.read() {
+        state.requesters.push(event.executor);
+    }
+
+    commands.add(ReloadDatapacks);
+}
+
+fn send_reload_feedback(
+    mut events: EventReader<DatapacksReloadedEvent>,
+    mut state: ResMut<DatapackState>,
+    mut clients: Query<&mut Client>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    let message = match &event.result {
+        Ok(summary) => format!(
+            "Reloaded {} tags, {} loot tables and {} recipes",
+            summary.tags, summary.loot_tables, summary.recipes
+        ),
+        Err(e) => format!("Failed to reload data packs: {e}"),
+    };
+
+    for requester in state.requesters.drain(..) {
+        if let Ok(mut client) = clients.get_mut(requester) {
+            client.send_chat_message(message.clone());
+        }
+    }
+}
+
+fn cache_recipes_packet(server: Res<Server>, recipes: ResMut<Recipes>) {
+    if recipes.is_changed() {
+        let recipes = recipes.into_inner();
+        let mut data = vec![];
+        recipes.encode(&mut data).expect("failed to encode recipes");
+
+        let mut bytes = vec![];
+        let mut writer = PacketWriter::new(&mut bytes, server.compression_threshold());
+
+        writer.write_packet(&SynchronizeRecipesS2c {
+            recipes: RawBytes(&data),
+        });
+        recipes.cached_packet = bytes;
+    }
+}
+
+/// Sends the recipes to joining clients, and the tags and recipes to all
+/// other clients when they change.
+fn sync_clients(tags: Res<TagsRegistry>, recipes: Res<Recipes>, mut clients: Query<&mut Client>) {
+    // Everything is changed on the first run.
+    let first_run = tags.is_added();
+
+    for mut client in &mut clients {
+        if client.is_added() {
+            // Tags are sent by the join.
+            if !recipes.recipes.is_empty() {
+                client.write_packet_bytes(recipes.sync_recipes_packet());
+            }
+        } else if !first_run {
+            if recipes.is_changed() {
+                client.write_packet_bytes(recipes.sync_recipes_packet());
+            }
+
+            if tags.is_changed() {
+                client.write_packet_bytes(tags.sync_tags_packet());
+            }
+        }
+    }
+}
@@ -0,0 +1,1176 @@
+//! Loot tables loaded from data packs.
+//!
+//! Loot tables are rolled with [`LootTable::generate`], given a
+//! [`LootContext`] describing what the loot is generated for, such as the
+//! broken block and the tool used to break it. Conditions which depend on
+//! something the context doesn't have, such as the weather, always pass.
+//! Functions other than `set_count`, `limit_count`, `explosion_decay`,
+//! `apply_bonus`, `enchant_randomly` and `set_damage` are ignored.
+
+use std::collections::BTreeMap;
+
+use bevy_ecs::prelude::*;
+use serde_json::Value;
+use valence_server::block::{BlockKind, BlockState, PropName, PropValue};
+use valence_server::nbt::Value as NbtValue;
+use valence_server::rand::{Rng, RngCore};
+use valence_server::registry::tags::RegistryMap;
+use valence_server::{Ident, ItemKind, ItemStack};
+
+use crate::tags::item_tag;
+use crate::{enchantment, parse_ident, DatapackError};
+
+/// The maximum depth of nested loot table references, to stop tables which
+/// reference themselves.
+const MAX_DEPTH: usize = 16;
+
+/// The loot tables of the loaded data packs, keyed by name (such as
+/// `minecraft:blocks/stone`).
+#[derive(Resource, Clone, Default, Debug)]
+pub struct LootTables {
+    pub tables: BTreeMap<Ident<String>, LootTable>,
+}
+
+impl LootTables {
+    pub fn get(&self, name: &str) -> Option<&LootTable> {
+        self.tables.get(name)
+    }
+}
+
+/// The situation loot is generated in.
+pub struct LootContext<'a> {
+    /// The tables which can be referenced by `loot_table` entries.
+    pub tables: &'a LootTables,
+    pub tags: &'a RegistryMap,
+    pub rng: &'a mut dyn RngCore,
+    /// The item used to break the block or kill the entity.
+    pub tool: Option<&'a ItemStack>,
+    /// The broken block.
+    pub block_state: Option<BlockState>,
+    /// The radius of the explosion which destroyed the block or killed the
+    /// entity.
+    pub explosion_radius: Option<f32>,
+    pub killed_by_player: bool,
+    pub luck: f32,
+}
+
+impl<'a> LootContext<'a> {
+    pub fn new(tables: &'a LootTables, tags: &'a RegistryMap, rng: &'a mut dyn RngCore) -> Self {
+        Self {
+            tables,
+            tags,
+            rng,
+            tool: None,
+            block_state: None,
+            explosion_radius: None,
+            killed_by_player: false,
+            luck: 0.0,
+        }
+    }
+
+    pub fn with_tool(mut self, tool: &'a ItemStack) -> Self {
+        self.tool = Some(tool);
+        self
+    }
+
+    pub fn with_block_state(mut self, state: BlockState) -> Self {
+        self.block_state = Some(state);
+        self
+    }
+
+    pub fn with_explosion_radius(mut self, radius: f32) -> Self {
+        self.explosion_radius = Some(radius);
+        self
+    }
+
+    pub fn with_killed_by_player(mut self) -> Self {
+        self.killed_by_player = true;
+        self
+    }
+
+    pub fn with_luck(mut self, luck: f32) -> Self {
+        self.luck = luck;
+        self
+    }
+
+    /// Returns the level of an enchantment on the tool.
+    fn tool_enchantment(&self, id: &str) -> i32 {
+        self.tool.map_or(0, |tool| enchantment::level(tool, id))
+    }
+}
+
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct LootTable {
+    pub pools: Vec<LootPool>,
+    /// The functions applied to every item of the table.
+    pub functions: Vec<LootFunction>,
+}
+
+impl LootTable {
+    pub(crate) fn from_json(name: &Ident<String>, json: &Value) -> Result<Self, DatapackError> {
+        let invalid = || DatapackError::InvalidLootTable(name.clone());
+
+        let pools = match json.get("pools") {
+            Some(Value::Array(pools)) => pools
+                .iter()
+                .map(|pool| LootPool::from_json(pool).ok_or_else(invalid))
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err(invalid()),
+            None => vec![],
+        };
+
+        Ok(Self {
+            pools,
+            functions: functions_from_json(json).ok_or_else(invalid)?,
+        })
+    }
+
+    /// Rolls the loot table. Stacks larger than the maximum stack size of
+    /// their item are split up.
+    pub fn generate(&self, ctx: &mut LootContext) -> Vec<ItemStack> {
+        let mut items = vec![];
+        self.generate_into(ctx, 0, &mut items);
+
+        let mut stacks = vec![];
+
+        for mut item in items {
+            let max = item.item.max_stack().max(1);
+
+            while item.count > max {
+                stacks.push(item.clone().with_count(max));
+                item.count -= max;
+            }
+
+            stacks.push(item);
+        }
+
+        stacks
+    }
+
+    fn generate_into(&self, ctx: &mut LootContext, depth: usize, items: &mut Vec<ItemStack>) {
+        let start = items.len();
+
+        for pool in &self.pools {
+            pool.generate_into(ctx, depth, items);
+        }
+
+        apply_functions(&self.functions, &mut items[start..], ctx);
+
+        items.retain(|item| !item.is_empty());
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LootPool {
+    pub rolls: NumberProvider,
+    /// The additional rolls per point of luck.
+    pub bonus_rolls: NumberProvider,
+    pub entries: Vec<LootEntry>,
+    pub conditions: Vec<LootCondition>,
+    pub functions: Vec<LootFunction>,
+}
+
+impl LootPool {
+    fn from_json(json: &Value) -> Option<Self> {
+        Some(Self {
+            rolls: NumberProvider::from_json(json.get("rolls")?)?,
+            bonus_rolls: match json.get("bonus_rolls") {
+                Some(rolls) => NumberProvider::from_json(rolls)?,
+                None => NumberProvider::Constant(0.0),
+            },
+            entries: json
+                .get("entries")?
+                .as_array()?
+                .iter()
+                .map(LootEntry::from_json)
+                .collect::<Option<_>>()?,
+            conditions: conditions_from_json(json)?,
+            functions: functions_from_json(json)?,
+        })
+    }
+
+    fn generate_into(&self, ctx: &mut LootContext, depth: usize, items: &mut Vec<ItemStack>) {
+        if !test_conditions(&self.conditions, ctx) {
+            return;
+        }
+
+        let start = items.len();
+        let rolls = self.rolls.roll(ctx) + (self.bonus_rolls.sample(ctx) * ctx.luck).floor() as i32;
+
+        for _ in 0..rolls.max(0) {
+            // Expand the entries into the choices of this roll, then pick one by weight.
+            let mut choices = vec![];
+
+            for entry in &self.entries {
+                entry.expand(ctx, &mut choices);
+            }
+
+            let total: u32 = choices.iter().map(|(_, weight)| *weight).sum();
+
+            if total == 0 {
+                continue;
+            }
+
+            let mut pick = ctx.rng.gen_range(0..total);
+
+            for (choice, weight) in choices {
+                if pick < weight {
+                    choice.generate_into(ctx, depth, items);
+                    break;
+                }
+
+                pick -= weight;
+            }
+        }
+
+        apply_functions(&self.functions, &mut items[start..], ctx);
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LootEntry {
+    pub kind: LootEntryKind,
+    pub weight: u32,
+    /// The additional weight per point of luck.
+    pub quality: i32,
+    pub conditions: Vec<LootCondition>,
+    pub functions: Vec<LootFunction>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LootEntryKind {
+    Item(ItemKind),
+    /// The items of an item tag. If `expand` is set, each item is a separate
+    /// choice, otherwise all of them are dropped together.
+    Tag {
+        tag: Ident<String>,
+        expand: bool,
+    },
+    LootTable(Ident<String>),
+    Empty,
+    /// The first child whose conditions pass.
+    Alternatives(Vec<LootEntry>),
+    /// All children whose conditions pass.
+    Group(Vec<LootEntry>),
+    /// The children up to the first whose conditions fail.
+    Sequence(Vec<LootEntry>),
+}
+
+/// A single choice of a pool roll.
+enum Choice<'a> {
+    Entry(&'a LootEntry),
+    Item(&'a LootEntry, ItemKind),
+}
+
+impl Choice<'_> {
+    fn generate_into(&self, ctx: &mut LootContext, depth: usize, items: &mut Vec<ItemStack>) {
+        let (entry, start) = (self.entry(), items.len());
+
+        match (self, &entry.kind) {
+            (Choice::Item(_, item), _) | (_, LootEntryKind::Item(item)) => {
+                items.push(ItemStack::new(*item, 1, None))
+            }
+            (_, LootEntryKind::Tag { tag, .. }) => items.extend(
+                item_tag(ctx.tags, tag)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|item| ItemStack::new(item, 1, None)),
+            ),
+            (_, LootEntryKind::LootTable(name)) if depth < MAX_DEPTH => {
+                if let Some(table) = ctx.tables.get(name.as_str()) {
+                    table.generate_into(ctx, depth + 1, items);
+                }
+            }
+            _ => {}
+        }
+
+        apply_functions(&entry.functions, &mut items[start..], ctx);
+    }
+
+    fn entry(&self) -> &LootEntry {
+        match self {
+            Choice::Entry(entry) | Choice::Item(entry, _) => entry,
+        }
+    }
+}
+
+impl LootEntry {
+    fn from_json(json: &Value) -> Option<Self> {
+        let ty = json.get("type")?.as_str()?;
+        let ty = ty.strip_prefix("minecraft:").unwrap_or(ty);
+
+        let children = || {
+            json.get("children")?
+                .as_array()?
+                .iter()
+                .map(Self::from_json)
+                .collect::<Option<Vec<_>>>()
+        };
+
+        let kind = match ty {
+            "item" => LootEntryKind::Item(item_from_json(json.get("name")?)?),
+            "tag" => LootEntryKind::Tag {
+                tag: parse_ident(json.get("name")?.as_str()?).ok()?,
+                expand: json.get("expand")?.as_bool()?,
+            },
+            "loot_table" => {
+                LootEntryKind::LootTable(parse_ident(json.get("name")?.as_str()?).ok()?)
+            }
+            "empty" => LootEntryKind::Empty,
+            "alternatives" => LootEntryKind::Alternatives(children()?),
+            "group" => LootEntryKind::Group(children()?),
+            "sequence" => LootEntryKind::Sequence(children()?),
+            // Dynamic entries, such as the contents of shulker boxes, need a world context.
+            "dynamic" => LootEntryKind::Empty,
+            _ => return None,
+        };
+
+        Some(Self {
+            kind,
+            weight: match json.get("weight") {
+                Some(weight) => u32::try_from(weight.as_u64()?).ok()?,
+                None => 1,
+            },
+            quality: match json.get("quality") {
+                Some(quality) => i32::try_from(quality.as_i64()?).ok()?,
+                None => 0,
+            },
+            conditions: conditions_from_json(json)?,
+            functions: functions_from_json(json)?,
+        })
+    }
+
+    /// Adds the choices of this entry to `choices` if its conditions pass.
+    /// Returns whether they passed.
+    fn expand<'a>(&'a self, ctx: &mut LootContext, choices: &mut Vec<(Choice<'a>, u32)>) -> bool {
+        if !test_conditions(&self.conditions, ctx) {
+            return false;
+        }
+
+        let weight = (self.weight as f32 + self.quality as f32 * ctx.luck)
+            .floor()
+            .max(0.0) as u32;
+
+        match &self.kind {
+            LootEntryKind::Tag { tag, expand: true } => {
+                for item in item_tag(ctx.tags, tag).unwrap_or_default() {
+                    choices.push((Choice::Item(self, item), weight));
+                }
+            }
+            LootEntryKind::Alternatives(children) => {
+                for child in children {
+                    if child.expand(ctx, choices) {
+                        break;
+                    }
+                }
+            }
+            LootEntryKind::Group(children) => {
+                for child in children {
+                    child.expand(ctx, choices);
+                }
+            }
+            LootEntryKind::Sequence(children) => {
+                for child in children {
+                    if !child.expand(ctx, choices) {
+                        break;
+                    }
+                }
+            }
+            _ => choices.push((Choice::Entry(self), weight)),
+        }
+
+        true
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LootCondition {
+    RandomChance(f32),
+    /// A random chance which increases with the looting level of the tool.
+    RandomChanceWithLooting {
+        chance: f32,
+        looting_multiplier: f32,
+    },
+    Inverted(Box<LootCondition>),
+    AnyOf(Vec<LootCondition>),
+    AllOf(Vec<LootCondition>),
+    /// Passes with a chance of one over the explosion radius, or always if
+    /// there was no explosion.
+    SurvivesExplosion,
+    MatchTool(ItemPredicate),
+    /// Passes if the broken block is one of the blocks, and each of the
+    /// properties has one of the values.
+    BlockStateProperty {
+        blocks: Vec<BlockKind>,
+        properties: Vec<(PropName, Vec<PropValue>)>,
+    },
+    /// A random chance which depends on the level of an enchantment on the
+    /// tool. The chance for the level is the entry at that index.
+    TableBonus {
+        enchantment: Ident<String>,
+        chances: Vec<f32>,
+    },
+    KilledByPlayer,
+    /// A condition which needs more context, such as `weather_check`. Always
+    /// passes.
+    Other(Ident<String>),
+}
+
+impl LootCondition {
+    fn from_json(json: &Value) -> Option<Self> {
+        let name = parse_ident(json.get("condition")?.as_str()?).ok()?;
+
+        let terms = || {
+            json.get("terms")?
+                .as_array()?
+                .iter()
+                .map(Self::from_json)
+                .collect::<Option<Vec<_>>>()
+        };
+
+        let number = |key| Some(json.get(key)?.as_f64()? as f32);
+
+        Some(match name.as_str() {
+            "minecraft:random_chance" => Self::RandomChance(number("chance")?),
+            "minecraft:random_chance_with_looting" => Self::RandomChanceWithLooting {
+                chance: number("chance")?,
+                looting_multiplier: number("looting_multiplier")?,
+            },
+            "minecraft:inverted" => Self::Inverted(Box::new(Self::from_json(json.get("term")?)?)),
+            "minecraft:any_of" | "minecraft:alternative" => Self::AnyOf(terms()?),
+            "minecraft:all_of" => Self::AllOf(terms()?),
+            "minecraft:survives_explosion" => Self::SurvivesExplosion,
+            "minecraft:match_tool" => Self::MatchTool(match json.get("predicate") {
+                Some(predicate) => ItemPredicate::from_json(predicate)?,
+                None => ItemPredicate::default(),
+            }),
+            "minecraft:block_state_property" => {
+                let block = parse_ident(json.get("block")?.as_str()?).ok()?;
+                let block = BlockKind::from_str(block.path())?;
+
+                let properties = match json.get("properties") {
+                    Some(props) => props
+                        .as_object()?
+                        .iter()
+                        .map(|(name, value)| {
+                            Some((PropName::from_str(name)?, prop_values_from_json(value)?))
+                        })
+                        .collect::<Option<_>>()?,
+                    None => vec![],
+                };
+
+                Self::BlockStateProperty {
+                    blocks: vec![block],
+                    properties,
+                }
+            }
+            "minecraft:table_bonus" => Self::TableBonus {
+                enchantment: parse_ident(json.get("enchantment")?.as_str()?).ok()?,
+                chances: json
+                    .get("chances")?
+                    .as_array()?
+                    .iter()
+                    .map(|chance| Some(chance.as_f64()? as f32))
+                    .collect::<Option<_>>()?,
+            },
+            "minecraft:killed_by_player" => Self::KilledByPlayer,
+            _ => Self::Other(name),
+        })
+    }
+
+    pub fn test(&self, ctx: &mut LootContext) -> bool {
+        match self {
+            Self::RandomChance(chance) => ctx.rng.gen::<f32>() < *chance,
+            Self::RandomChanceWithLooting {
+                chance,
+                looting_multiplier,
+            } => {
+                let looting = ctx.tool_enchantment("looting") as f32;
+                ctx.rng.gen::<f32>() < chance + looting * looting_multiplier
+            }
+            Self::Inverted(cond) => !cond.test(ctx),
+            Self::AnyOf(conds) => conds.iter().any(|cond| cond.test(ctx)),
+            Self::AllOf(conds) => conds.iter().all(|cond| cond.test(ctx)),
+            Self::SurvivesExplosion => match ctx.explosion_radius {
+                Some(radius) => ctx.rng.gen::<f32>() <= 1.0 / radius,
+                None => true,
+            },
+            Self::MatchTool(predicate) => {
+                ctx.tool.is_some_and(|tool| predicate.test(tool, ctx.tags))
+            }
+            Self::BlockStateProperty { blocks, properties } => {
+                ctx.block_state.is_some_and(|state| {
+                    blocks.contains(&state.to_kind())
+                        && properties.iter().all(|(name, values)| {
+                            state.get(*name).is_some_and(|v| values.contains(&v))
+                        })
+                })
+            }
+            Self::TableBonus {
+                enchantment,
+                chances,
+            } => {
+                let level = ctx.tool_enchantment(enchantment.as_str()).max(0) as usize;
+                let chance = chances
+                    .get(level)
+                    .or(chances.last())
+                    .copied()
+                    .unwrap_or(0.0);
+
+                ctx.rng.gen::<f32>() < chance
+            }
+            Self::KilledByPlayer => ctx.killed_by_player,
+            Self::Other(_) => true,
+        }
+    }
+}
+
+/// A predicate of the tool used to generate loot, as used by `match_tool`.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct ItemPredicate {
+    /// The items the tool must be one of, if not empty.
+    pub items: Vec<ItemKind>,
+    /// The item tag the tool must be in.
+    pub tag: Option<Ident<String>>,
+    /// The enchantments the tool must have, with the minimum and maximum
+    /// levels.
+    pub enchantments: Vec<(Option<Ident<String>>, i32, i32)>,
+}
+
+impl ItemPredicate {
+    fn from_json(json: &Value) -> Option<Self> {
+        let items = match json.get("items") {
+            Some(items) => items
+                .as_array()?
+                .iter()
+                .map(item_from_json)
+                .collect::<Option<_>>()?,
+            None => vec![],
+        };
+
+        let tag = match json.get("tag") {
+            Some(tag) => Some(parse_ident(tag.as_str()?).ok()?),
+            None => None,
+        };
+
+        let enchantments = match json.get("enchantments") {
+            Some(enchantments) => enchantments
+                .as_array()?
+                .iter()
+                .map(|ench| {
+                    let id = match ench.get("enchantment") {
+                        Some(id) => Some(parse_ident(id.as_str()?).ok()?),
+                        None => None,
+                    };
+
+                    let (min, max) = match ench.get("levels") {
+                        Some(Value::Number(level)) => {
+                            let level = i32::try_from(level.as_i64()?).ok()?;
+                            (level, level)
+                        }
+                        Some(levels) => {
+                            let bound = |key, default| match levels.get(key) {
+                                Some(level) => i32::try_from(level.as_i64()?).ok(),
+                                None => Some(default),
+                            };
+
+                            (bound("min", 1)?, bound("max", i32::MAX)?)
+                        }
+                        None => (1, i32::MAX),
+                    };
+
+                    Some((id, min, max))
+                })
+                .collect::<Option<_>>()?,
+            None => vec![],
+        };
+
+        Some(Self {
+            items,
+            tag,
+            enchantments,
+        })
+    }
+
+    pub fn test(&self, tool: &ItemStack, tags: &RegistryMap) -> bool {
+        if tool.is_empty() {
+            return false;
+        }
+
+        if !self.items.is_empty() && !self.items.contains(&tool.item) {
+            return false;
+        }
+
+        if let Some(tag) = &self.tag {
+            if !item_tag(tags, tag).is_some_and(|items| items.contains(&tool.item)) {
+                return false;
+            }
+        }
+
+        self.enchantments.iter().all(|(id, min, max)| match id {
+            Some(id) => (*min..=*max).contains(&enchantment::level(tool, id.as_str())),
+            // Without an enchantment, the predicate checks that the tool is enchanted.
+            None => tool
+                .nbt
+                .as_ref()
+                .and_then(|nbt| nbt.get("Enchantments"))
+                .is_some_and(|list| matches!(list, NbtValue::List(list) if !list.is_empty())),
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LootFunction {
+    pub kind: LootFunctionKind,
+    pub conditions: Vec<LootCondition>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LootFunctionKind {
+    SetCount {
+        count: NumberProvider,
+        /// Whether the count is added to the current count instead of
+        /// replacing it.
+        add: bool,
+    },
+    LimitCount {
+        min: Option<NumberProvider>,
+        max: Option<NumberProvider>,
+    },
+    /// Removes each item with a chance of one minus one over the explosion
+    /// radius.
+    ExplosionDecay,
+    /// Increases the count by a formula of the level of an enchantment on
+    /// the tool.
+    ApplyBonus {
+        enchantment: Ident<String>,
+        formula: BonusFormula,
+    },
+    /// Adds a random one of the enchantments, or of all enchantments which
+    /// can be applied to the item if the list is empty, at a random level.
+    EnchantRandomly { enchantments: Vec<Ident<String>> },
+    /// Sets the durability of the item as a fraction of its maximum.
+    SetDamage { damage: NumberProvider, add: bool },
+    /// A function which isn't evaluated. Items are left unchanged.
+    Other(Ident<String>),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BonusFormula {
+    /// Like fortune on ores.
+    OreDrops,
+    UniformBonusCount {
+        bonus_multiplier: i32,
+    },
+    BinomialWithBonusCount {
+        extra: i32,
+        probability: f32,
+    },
+}
+
+impl LootFunction {
+    fn from_json(json: &Value) -> Option<Self> {
+        let name = parse_ident(json.get("function")?.as_str()?).ok()?;
+        let add = || match json.get("add") {
+            Some(add) => add.as_bool(),
+            None => Some(false),
+        };
+
+        let kind = match name.as_str() {
+            "minecraft:set_count" => LootFunctionKind::SetCount {
+                count: NumberProvider::from_json(json.get("count")?)?,
+                add: add()?,
+            },
+            "minecraft:limit_count" => {
+                let limit = json.get("limit")?;
+                let bound = |key| match limit.get(key) {
+                    Some(bound) => NumberProvider::from_json(bound).map(Some),
+                    None => Some(None),
+                };
+
+                match limit {
+                    Value::Number(_) => {
+                        let limit = NumberProvider::from_json(limit)?;
+                        LootFunctionKind::LimitCount {
+                            min: Some(limit.clone()),
+                            max: Some(limit),
+                        }
+                    }
+                    _ => LootFunctionKind::LimitCount {
+                        min: bound("min")?,
+                        max: bound("max")?,
+                    },
+                }
+            }
+            "minecraft:explosion_decay" => LootFunctionKind::ExplosionDecay,
+            "minecraft:apply_bonus" => {
+                let formula = parse_ident(json.get("formula")?.as_str()?).ok()?;
+                let param = |key| json.get("parameters")?.get(key);
+
+                LootFunctionKind::ApplyBonus {
+                    enchantment: parse_ident(json.get("enchantment")?.as_str()?).ok()?,
+                    formula: match formula.as_str() {
+                        "minecraft:ore_drops" => BonusFormula::OreDrops,
+                        "minecraft:uniform_bonus_count" => BonusFormula::UniformBonusCount {
+                            bonus_multiplier: i32::try_from(param("bonusMultiplier")?.as_i64()?)
+                                .ok()?,
+                        },
+                        "minecraft:binomial_with_bonus_count" => {
+                            BonusFormula::BinomialWithBonusCount {
+                                extra: i32::try_from(param("extra")?.as_i64()?).ok()?,
+                                probability: param("probability")?.as_f64()? as f32,
+                            }
+                        }
+                        _ => return None,
+                    },
+                }
+            }
+            "minecraft:enchant_randomly" => LootFunctionKind::EnchantRandomly {
+                enchantments: match json.get("enchantments") {
+                    Some(enchantments) => enchantments
+                        .as_array()?
+                        .iter()
+                        .map(|id| parse_ident(id.as_str()?).ok())
+                        .collect::<Option<_>>()?,
+                    None => vec![],
+                },
+            },
+            "minecraft:set_damage" => LootFunctionKind::SetDamage {
+                damage: NumberProvider::from_json(json.get("damage")?)?,
+                add: add()?,
+            },
+            _ => LootFunctionKind::Other(name),
+        };
+
+        Some(Self {
+            kind,
+            conditions: conditions_from_json(json)?,
+        })
+    }
+
+    pub fn apply(&self, item: &mut ItemStack, ctx: &mut LootContext) {
+        if !test_conditions(&self.conditions, ctx) {
+            return;
+        }
+
+        match &self.kind {
+            LootFunctionKind::SetCount { count, add } => {
+                let count = count.roll(ctx);
+                let count = if *add {
+                    i32::from(item.count) + count
+                } else {
+                    count
+                };
+
+                set_count(item, count);
+            }
+            LootFunctionKind::LimitCount { min, max } => {
+                let mut count = i32::from(item.count);
+
+                if let Some(min) = min {
+                    count = count.max(min.roll(ctx));
+                }
+
+                if let Some(max) = max {
+                    count = count.min(max.roll(ctx));
+                }
+
+                set_count(item, count);
+            }
+            LootFunctionKind::ExplosionDecay => {
+                if let Some(radius) = ctx.explosion_radius {
+                    let count = (0..item.count)
+                        .filter(|_| ctx.rng.gen::<f32>() <= 1.0 / radius)
+                        .count();
+
+                    set_count(item, count as i32);
+                }
+            }
+            LootFunctionKind::ApplyBonus {
+                enchantment,
+                formula,
+            } => {
+                let level = ctx.tool_enchantment(enchantment.as_str());
+                let count = i32::from(item.count);
+
+                let count = match *formula {
+                    BonusFormula::OreDrops if level > 0 => {
+                        count * (ctx.rng.gen_range(0..level + 2) - 1).max(0) + count
+                    }
+                    BonusFormula::OreDrops => count,
+                    BonusFormula::UniformBonusCount { bonus_multiplier } => {
+                        count + ctx.rng.gen_range(0..=(bonus_multiplier * level).max(0))
+                    }
+                    BonusFormula::BinomialWithBonusCount { extra, probability } => {
+                        count
+                            + (0..level + extra)
+                                .filter(|_| ctx.rng.gen::<f32>() < probability)
+                                .count() as i32
+                    }
+                };
+
+                set_count(item, count);
+            }
+            LootFunctionKind::EnchantRandomly { enchantments } => {
+                let choices: Vec<(&str, i32)> = if enchantments.is_empty() {
+                    enchantment::applicable(item.item).collect()
+                } else {
+                    enchantments
+                        .iter()
+                        .filter(|id| enchantment::can_apply(id.as_str(), item.item))
+                        .map(|id| {
+                            (
+                                id.as_str(),
+                                enchantment::max_level(id.as_str()).unwrap_or(1),
+                            )
+                        })
+                        .collect()
+                };
+
+                if !choices.is_empty() {
+                    let (id, max) = choices[ctx.rng.gen_range(0..choices.len())];
+                    let level = ctx.rng.gen_range(1..=max.max(1));
+
+                    enchantment::enchant(item, id, level);
+                }
+            }
+            LootFunctionKind::SetDamage { damage, add } => {
+                let max = f32::from(item.item.max_durability());
+
+                if max > 0.0 {
+                    let current = match item.nbt.as_ref().and_then(|nbt| nbt.get("Damage")) {
+                        Some(NbtValue::Int(damage)) => *damage as f32,
+                        _ => 0.0,
+                    };
+
+                    let mut fraction = damage.sample(ctx);
+
+                    if *add {
+                        fraction += 1.0 - current / max;
+                    }
+
+                    let damage = ((1.0 - fraction.clamp(0.0, 1.0)) * max).floor() as i32;
+
+                    item.nbt
+                        .get_or_insert_with(Default::default)
+                        .insert("Damage", damage);
+                }
+            }
+            LootFunctionKind::Other(_) => {}
+        }
+    }
+}
+
+/// A number which can be random.
+#[derive(Clone, PartialEq, Debug)]
+pub enum NumberProvider {
+    Constant(f32),
+    Uniform { min: f32, max: f32 },
+    Binomial { n: i32, p: f32 },
+}
+
+impl NumberProvider {
+    fn from_json(json: &Value) -> Option<Self> {
+        if let Some(value) = json.as_f64() {
+            return Some(Self::Constant(value as f32));
+        }
+
+        let number = |key| Some(json.get(key)?.as_f64()? as f32);
+
+        let ty = match json.get("type") {
+            Some(ty) => parse_ident(ty.as_str()?).ok()?,
+            // Objects with a min and max but without a type are uniform.
+            None => parse_ident("uniform").ok()?,
+        };
+
+        Some(match ty.as_str() {
+            "minecraft:constant" => Self::Constant(number("value")?),
+            "minecraft:uniform" => Self::Uniform {
+                min: number("min")?,
+                max: number("max")?,
+            },
+            "minecraft:binomial" => Self::Binomial {
+                n: i32::try_from(json.get("n")?.as_i64()?).ok()?,
+                p: number("p")?,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Returns a random value.
+    pub fn sample(&self, ctx: &mut LootContext) -> f32 {
+        match *self {
+            Self::Constant(value) => value,
+            Self::Uniform { min, max } if min < max => ctx.rng.gen_range(min..max),
+            Self::Uniform { min, .. } => min,
+            Self::Binomial { n, p } => (0..n).filter(|_| ctx.rng.gen::<f32>() < p).count() as f32,
+        }
+    }
+
+    /// Returns a random integer. Uniform integers include the maximum.
+    pub fn roll(&self, ctx: &mut LootContext) -> i32 {
+        match *self {
+            Self::Uniform { min, max } => {
+                let (min, max) = (min.round() as i32, max.round() as i32);
+
+                if min < max {
+                    ctx.rng.gen_range(min..=max)
+                } else {
+                    min
+                }
+            }
+            _ => self.sample(ctx).round() as i32,
+        }
+    }
+}
+
+fn item_from_json(json: &Value) -> Option<ItemKind> {
+    let name = parse_ident(json.as_str()?).ok()?;
+
+    if name.namespace() != "minecraft" {
+        return None;
+    }
+
+    ItemKind::from_str(name.path())
+}
+
+/// Parses the value of a block state property, which is either a value or a
+/// range of integer values.
+fn prop_values_from_json(json: &Value) -> Option<Vec<PropValue>> {
+    let bound = |json: &Value| match json {
+        Value::String(value) => value.parse::<u16>().ok(),
+        Value::Number(value) => u16::try_from(value.as_u64()?).ok(),
+        _ => None,
+    };
+
+    match json {
+        Value::String(value) => Some(vec![PropValue::from_str(value)?]),
+        Value::Bool(value) => Some(vec![PropValue::from_bool(*value)]),
+        Value::Number(_) => Some(vec![PropValue::from_u16(bound(json)?)?]),
+        Value::Object(range) => {
+            let min = range.get("min").map_or(Some(0), bound)?;
+            let max = range.get("max").map_or(Some(u16::MAX), bound)?;
+
+            Some(
+                (min..=max.min(32))
+                    .filter_map(PropValue::from_u16)
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+fn conditions_from_json(json: &Value) -> Option<Vec<LootCondition>> {
+    match json.get("conditions") {
+        Some(conds) => conds
+            .as_array()?
+            .iter()
+            .map(LootCondition::from_json)
+            .collect(),
+        None => Some(vec![]),
+    }
+}
+
+fn functions_from_json(json: &Value) -> Option<Vec<LootFunction>> {
+    match json.get("functions") {
+        Some(funcs) => funcs
+            .as_array()?
+            .iter()
+            .map(LootFunction::from_json)
+            .collect(),
+        None => Some(vec![]),
+    }
+}
+
+fn test_conditions(conditions: &[LootCondition], ctx: &mut LootContext) -> bool {
+    conditions.iter().all(|cond| cond.test(ctx))
+}
+
+fn apply_functions(functions: &[LootFunction], items: &mut [ItemStack], ctx: &mut LootContext) {
+    for item in items {
+        for function in functions {
+            function.apply(item, ctx);
+        }
+    }
+}
+
+fn set_count(item: &mut ItemStack, count: i32) {
+    item.count = count.clamp(0, i32::from(i8::MAX)) as i8;
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use valence_server::nbt::{compound, List};
+    use valence_server::rand::rngs::mock::StepRng;
+    use valence_server::rand::thread_rng;
+
+    use super::*;
+
+    fn table(json: Value) -> LootTable {
+        LootTable::from_json(&parse_ident("test:table").unwrap(), &json).unwrap()
+    }
+
+    #[test]
+    fn parse_and_generate_loot_table() {
+        let table = table(json!({
+            "type": "minecraft:block",
+            "pools": [{
+                "rolls": 1,
+                "bonus_rolls": 0.0,
+                "entries": [{
+                    "type": "minecraft:alternatives",
+                    "children": [
+                        {
+                            "type": "minecraft:item",
+                            "name": "minecraft:emerald",
+                            "conditions": [{ "condition": "minecraft:random_chance", "chance": 0.0 }]
+                        },
+                        {
+                            "type": "minecraft:item",
+                            "name": "minecraft:diamond",
+                            "functions": [{
+                                "function": "minecraft:set_count",
+                                "count": { "min": 2.0, "max": 2.0 }
+                            }]
+                        }
+                    ]
+                }],
+                "conditions": [{ "condition": "minecraft:survives_explosion" }]
+            }]
+        }));
+
+        let tables = LootTables::default();
+        let tags = RegistryMap::new();
+        let mut rng = StepRng::new(0, 1);
+
+        assert_eq!(
+            table.generate(&mut LootContext::new(&tables, &tags, &mut rng)),
+            [ItemStack::new(ItemKind::Diamond, 2, None)]
+        );
+    }
+
+    #[test]
+    fn block_drops_depend_on_tool() {
+        // Like the vanilla loot table of diamond ore.
+        let table = table(json!({
+            "pools": [{
+                "rolls": 1,
+                "entries": [{
+                    "type": "minecraft:alternatives",
+                    "children": [
+                        {
+                            "type": "minecraft:item",
+                            "name": "minecraft:diamond_ore",
+                            "conditions": [{
+                                "condition": "minecraft:match_tool",
+                                "predicate": {
+                                    "enchantments": [{
+                                        "enchantment": "minecraft:silk_touch",
+                                        "levels": { "min": 1 }
+                                    }]
+                                }
+                            }]
+                        },
+                        {
+                            "type": "minecraft:item",
+                            "name": "minecraft:diamond",
+                            "functions": [
+                                {
+                                    "function": "minecraft:apply_bonus",
+                                    "enchantment": "minecraft:fortune",
+                                    "formula": "minecraft:uniform_bonus_count",
+                                    "parameters": { "bonusMultiplier": 1 }
+                                },
+                                { "function": "minecraft:explosion_decay" }
+                            ]
+                        }
+                    ]
+                }]
+            }]
+        }));
+
+        let tables = LootTables::default();
+        let tags = RegistryMap::new();
+        let mut rng = thread_rng();
+
+        let enchanted = |id: &str| {
+            ItemStack::new(
+                ItemKind::DiamondPickaxe,
+                1,
+                Some(compound! {
+                    "Enchantments" => List::Compound(vec![compound! {
+                        "id" => id,
+                        "lvl" => 3_i16,
+                    }]),
+                }),
+            )
+        };
+
+        let silk_touch = enchanted("minecraft:silk_touch");
+        let fortune = enchanted("minecraft:fortune");
+        let plain = ItemStack::new(ItemKind::DiamondPickaxe, 1, None);
+
+        let mut generate =
+            |tool| table.generate(&mut LootContext::new(&tables, &tags, &mut rng).with_tool(tool));
+
+        assert_eq!(
+            generate(&silk_touch),
+            [ItemStack::new(ItemKind::DiamondOre, 1, None)]
+        );
+        // Fortune III adds up to three diamonds.
+        let items = generate(&fortune);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].item, ItemKind::Diamond);
+        assert!((1..=4).contains(&items[0].count));
+        assert_eq!(
+            generate(&plain),
+            [ItemStack::new(ItemKind::Diamond, 1, None)]
+        );
+    }
+
+    #[test]
+    fn enchant_randomly() {
+        let table = table(json!({
+            "pools": [{
+                "rolls": 1,
+                "entries": [{ "type": "minecraft:item", "name": "minecraft:book" }],
+                "functions": [{
+                    "function": "minecraft:enchant_randomly",
+                    "enchantments": ["minecraft:mending"]
+                }]
+            }]
+        }));
+
+        let tables = LootTables::default();
+        let tags = RegistryMap::new();
+        let mut rng = StepRng::new(0, 1);
+
+        let items = table.generate(&mut LootContext::new(&tables, &tags, &mut rng));
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].item, ItemKind::EnchantedBook);
+        assert_eq!(
+            items[0].nbt,
+            Some(compound! {
+                "StoredEnchantments" => List::Compound(vec![compound! {
+                    "id" => "minecraft:mending",
+                    "lvl" => 1_i16,
+                }]),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_loot_table() {
+        let name = parse_ident("test:invalid").unwrap();
+
+        assert!(LootTable::from_json(&name, &json!({ "pools": [{ "rolls": 1 }] })).is_err());
+        assert!(LootTable::from_json(
+            &name,
+            &json!({ "pools": [{ "rolls": 1, "entries": [{ "type": "minecraft:item", "name": "minecraft:not_an_item" }] }] })
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,538 @@
+//! Recipes loaded from data packs, which are sent to clients for the recipe
+//! book.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use bevy_ecs::prelude::*;
+use serde_json::Value;
+use valence_server::protocol::{Encode, VarInt};
+use valence_server::registry::tags::RegistryMap;
+use valence_server::{ident, Ident, ItemKind, ItemStack};
+
+use crate::tags::item_tag;
+use crate::{parse_ident, DatapackError};
+
+/// The recipes of the loaded data packs, keyed by name (such as
+/// `minecraft:oak_planks`).
+#[derive(Resource, Clone, Default, Debug)]
+pub struct Recipes {
+    pub recipes: BTreeMap<Ident<String>, Recipe>,
+    pub(crate) cached_packet: Vec<u8>,
+}
+
+impl Recipes {
+    pub fn get(&self, name: &str) -> Option<&Recipe> {
+        self.recipes.get(name)
+    }
+
+    /// Returns bytes of the cached `SynchronizeRecipesS2c` packet.
+    pub fn sync_recipes_packet(&self) -> &[u8] {
+        &self.cached_packet
+    }
+
+    /// Writes the data of a `SynchronizeRecipesS2c` packet.
+    pub(crate) fn encode(&self, mut w: impl Write) -> anyhow::Result<()> {
+        VarInt(self.recipes.len() as i32).encode(&mut w)?;
+
+        for (name, recipe) in &self.recipes {
+            recipe.kind().encode(&mut w)?;
+            name.encode(&mut w)?;
+            recipe.encode_data(&mut w)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Recipe {
+    Shaped {
+        group: String,
+        category: CraftingCategory,
+        width: usize,
+        height: usize,
+        /// The ingredients row by row. The length is `width * height`.
+        ingredients: Vec<Ingredient>,
+        result: ItemStack,
+        show_notification: bool,
+    },
+    Shapeless {
+        group: String,
+        category: CraftingCategory,
+        ingredients: Vec<Ingredient>,
+        result: ItemStack,
+    },
+    Cooking {
+        kind: CookingKind,
+        group: String,
+        category: CookingCategory,
+        ingredient: Ingredient,
+        result: ItemStack,
+        experience: f32,
+        /// The cooking time in ticks.
+        cooking_time: i32,
+    },
+    Stonecutting {
+        group: String,
+        ingredient: Ingredient,
+        result: ItemStack,
+    },
+    SmithingTransform {
+        template: Ingredient,
+        base: Ingredient,
+        addition: Ingredient,
+        result: ItemStack,
+    },
+    SmithingTrim {
+        template: Ingredient,
+        base: Ingredient,
+        addition: Ingredient,
+    },
+    /// A recipe implemented by the client, such as `crafting_special_armordye`.
+    Special {
+        kind: Ident<String>,
+        category: CraftingCategory,
+    },
+}
+
+/// The items which can be used for an ingredient slot. Empty for an empty
+/// slot.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Ingredient(pub Vec<ItemKind>);
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum CraftingCategory {
+    Building,
+    Redstone,
+    Equipment,
+    #[default]
+    Misc,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CookingKind {
+    Smelting,
+    Blasting,
+    Smoking,
+    CampfireCooking,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum CookingCategory {
+    Food,
+    Blocks,
+    #[default]
+    Misc,
+}
+
+impl Recipe {
+    pub(crate) fn from_json(
+        name: &Ident<String>,
+        json: &Value,
+        tags: &RegistryMap,
+    ) -> Result<Self, DatapackError> {
+        Self::parse(json, tags).ok_or_else(|| DatapackError::InvalidRecipe(name.clone()))
+    }
+
+    fn parse(json: &Value, tags: &RegistryMap) -> Option<Self> {
+        let kind = parse_ident(json.get("type")?.as_str()?).ok()?;
+
+        let group = || match json.get("group") {
+            Some(group) => Some(group.as_str()?.to_owned()),
+            None => Some(String::new()),
+        };
+
+        let crafting_category = || match json.get("category").map(Value::as_str) {
+            Some(Some("building")) => Some(CraftingCategory::Building),
+            Some(Some("redstone")) => Some(CraftingCategory::Redstone),
+            Some(Some("equipment")) => Some(CraftingCategory::Equipment),
+            Some(Some("misc")) | None => Some(CraftingCategory::Misc),
+            Some(_) => None,
+        };
+
+        let ingredient = |key| Ingredient::from_json(json.get(key)?, tags);
+
+        let cooking = |kind, default_time| {
+            Some(Self::Cooking {
+                kind,
+                group: group()?,
+                category: match json.get("category").map(Value::as_str) {
+                    Some(Some("food")) => CookingCategory::Food,
+                    Some(Some("blocks")) => CookingCategory::Blocks,
+                    Some(Some("misc")) | None => CookingCategory::Misc,
+                    Some(_) => return None,
+                },
+                ingredient: ingredient("ingredient")?,
+                result: ItemStack::new(item_from_json(json.get("result")?)?, 1, None),
+                experience: match json.get("experience") {
+                    Some(exp) => exp.as_f64()? as f32,
+                    None => 0.0,
+                },
+                cooking_time: match json.get("cookingtime") {
+                    Some(time) => i32::try_from(time.as_i64()?).ok()?,
+                    None => default_time,
+                },
+            })
+        };
+
+        Some(match kind.as_str() {
+            "minecraft:crafting_shaped" => {
+                let pattern = json
+                    .get("pattern")?
+                    .as_array()?
+                    .iter()
+                    .map(Value::as_str)
+                    .collect::<Option<Vec<_>>>()?;
+
+                let key = json
+                    .get("key")?
+                    .as_object()?
+                    .iter()
+                    .map(|(key, value)| {
+                        let mut chars = key.chars();
+
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) if c != ' ' => {
+                                Some((c, Ingredient::from_json(value, tags)?))
+                            }
+                            _ => None,
+                        }
+                    })
+                    .collect::<Option<BTreeMap<_, _>>>()?;
+
+                let width = pattern.first()?.chars().count();
+                let height = pattern.len();
+
+                if !(1..=3).contains(&width)
+                    || height > 3
+                    || pattern.iter().any(|row| row.chars().count() != width)
+                {
+                    return None;
+                }
+
+                let ingredients = pattern
+                    .iter()
+                    .flat_map(|row| row.chars())
+                    .map(|c| match c {
+                        ' ' => Some(Ingredient::default()),
+                        c => key.get(&c).cloned(),
+                    })
+                    .collect::<Option<_>>()?;
+
+                Self::Shaped {
+                    group: group()?,
+                    category: crafting_category()?,
+                    width,
+                    height,
+                    ingredients,
+                    result: result_from_json(json.get("result")?)?,
+                    show_notification: match json.get("show_notification") {
+                        Some(show) => show.as_bool()?,
+                        None => true,
+                    },
+                }
+            }
+            "minecraft:crafting_shapeless" => {
+                let ingredients = json
+                    .get("ingredients")?
+                    .as_array()?
+                    .iter()
+                    .map(|ingredient| Ingredient::from_json(ingredient, tags))
+                    .collect::<Option<Vec<_>>>()?;
+
+                if !(1..=9).contains(&ingredients.len()) {
+                    return None;
+                }
+
+                Self::Shapeless {
+                    group: group()?,
+                    category: crafting_category()?,
+                    ingredients,
+                    result: result_from_json(json.get("result")?)?,
+                }
+            }
+            "minecraft:smelting" => cooking(CookingKind::Smelting, 200)?,
+            "minecraft:blasting" => cooking(CookingKind::Blasting, 100)?,
+            "minecraft:smoking" => cooking(CookingKind::Smoking, 100)?,
+            "minecraft:campfire_cooking" => cooking(CookingKind::CampfireCooking, 600)?,
+            "minecraft:stonecutting" => Self::Stonecutting {
+                group: group()?,
+                ingredient: ingredient("ingredient")?,
+                result: ItemStack::new(
+                    item_from_json(json.get("result")?)?,
+                    count_from_json(json)?,
+                    None,
+                ),
+            },
+            "minecraft:smithing_transform" => Self::SmithingTransform {
+                template: ingredient("template")?,
+                base: ingredient("base")?,
+                addition: ingredient("addition")?,
+                result: result_from_json(json.get("result")?)?,
+            },
+            "minecraft:smithing_trim" => Self::SmithingTrim {
+                template: ingredient("template")?,
+                base: ingredient("base")?,
+                addition: ingredient("addition")?,
+            },
+            name if name.starts_with("minecraft:crafting_special_")
+                || name == "minecraft:crafting_decorated_pot" =>
+            {
+                Self::Special {
+                    category: crafting_category()?,
+                    kind,
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// Returns the name of the recipe serializer.
+    pub fn kind(&self) -> Ident<&str> {
+        match self {
+            Self::Shaped { .. } => ident!("crafting_shaped"),
+            Self::Shapeless { .. } => ident!("crafting_shapeless"),
+            Self::Cooking { kind, .. } => match kind {
+                CookingKind::Smelting => ident!("smelting"),
+                CookingKind::Blasting => ident!("blasting"),
+                CookingKind::Smoking => ident!("smoking"),
+                CookingKind::CampfireCooking => ident!("campfire_cooking"),
+            },
+            Self::Stonecutting { .. } => ident!("stonecutting"),
+            Self::SmithingTransform { .. } => ident!("smithing_transform"),
+            Self::SmithingTrim { .. } => ident!("smithing_trim"),
+            Self::Special { kind, .. } => kind.as_str_ident(),
+        }
+    }
+
+    fn encode_data(&self, mut w: impl Write) -> anyhow::Result<()> {
+        match self {
+            Self::Shaped {
+                group,
+                category,
+                width,
+                height,
+                ingredients,
+                result,
+                show_notification,
+            } => {
+                VarInt(*width as i32).encode(&mut w)?;
+                VarInt(*height as i32).encode(&mut w)?;
+                group.encode(&mut w)?;
+                category.encode(&mut w)?;
+                for ingredient in ingredients {
+                    ingredient.encode(&mut w)?;
+                }
+                result.encode(&mut w)?;
+                show_notification.encode(w)
+            }
+            Self::Shapeless {
+                group,
+                category,
+                ingredients,
+                result,
+            } => {
+                group.encode(&mut w)?;
+                category.encode(&mut w)?;
+                VarInt(ingredients.len() as i32).encode(&mut w)?;
+                for ingredient in ingredients {
+                    ingredient.encode(&mut w)?;
+                }
+                result.encode(w)
+            }
+            Self::Cooking {
+                kind: _,
+                group,
+                category,
+                ingredient,
+                result,
+                experience,
+                cooking_time,
+            } => {
+                group.encode(&mut w)?;
+                category.encode(&mut w)?;
+                ingredient.encode(&mut w)?;
+                result.encode(&mut w)?;
+                experience.encode(&mut w)?;
+                VarInt(*cooking_time).encode(w)
+            }
+            Self::Stonecutting {
+                group,
+                ingredient,
+                result,
+            } => {
+                group.encode(&mut w)?;
+                ingredient.encode(&mut w)?;
+                result.encode(w)
+            }
+            Self::SmithingTransform {
+                template,
+                base,
+                addition,
+                result,
+            } => {
+                template.encode(&mut w)?;
+                base.encode(&mut w)?;
+                addition.encode(&mut w)?;
+                result.encode(w)
+            }
+            Self::SmithingTrim {
+                template,
+                base,
+                addition,
+            } => {
+                template.encode(&mut w)?;
+                base.encode(&mut w)?;
+                addition.encode(w)
+            }
+            Self::Special { kind: _, category } => category.encode(w),
+        }
+    }
+}
+
+impl Ingredient {
+    /// Parses an ingredient, which is either an `item` or `tag` object or a
+    /// list of them.
+    fn from_json(json: &Value, tags: &RegistryMap) -> Option<Self> {
+        let mut items = vec![];
+
+        let choices = match json {
+            Value::Array(choices) => choices.as_slice(),
+            choice => std::slice::from_ref(choice),
+        };
+
+        for choice in choices {
+            if let Some(item) = choice.get("item") {
+                items.push(item_from_json(item)?);
+            } else {
+                let tag = parse_ident(choice.get("tag")?.as_str()?).ok()?;
+                items.extend(item_tag(tags, &tag)?);
+            }
+        }
+
+        if items.is_empty() {
+            return None;
+        }
+
+        Some(Self(items))
+    }
+
+    /// Returns whether the item can be used for this ingredient.
+    pub fn test(&self, item: ItemKind) -> bool {
+        self.0.contains(&item)
+    }
+}
+
+impl Encode for Ingredient {
+    fn encode(&self, mut w: impl Write) -> anyhow::Result<()> {
+        VarInt(self.0.len() as i32).encode(&mut w)?;
+
+        for &item in &self.0 {
+            ItemStack::new(item, 1, None).encode(&mut w)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Encode for CraftingCategory {
+    fn encode(&self, w: impl Write) -> anyhow::Result<()> {
+        VarInt(*self as i32).encode(w)
+    }
+}
+
+impl Encode for CookingCategory {
+    fn encode(&self, w: impl Write) -> anyhow::Result<()> {
+        VarInt(*self as i32).encode(w)
+    }
+}
+
+fn item_from_json(json: &Value) -> Option<ItemKind> {
+    let name = parse_ident(json.as_str()?).ok()?;
+
+    if name.namespace() != "minecraft" {
+        return None;
+    }
+
+    ItemKind::from_str(name.path()).filter(|&item| item != ItemKind::Air)
+}
+
+/// Parses a result object with an `item` and optional `count`.
+fn result_from_json(json: &Value) -> Option<ItemStack> {
+    Some(ItemStack::new(
+        item_from_json(json.get("item")?)?,
+        count_from_json(json)?,
+        None,
+    ))
+}
+
+fn count_from_json(json: &Value) -> Option<i8> {
+    match json.get("count") {
+        Some(count) => i8::try_from(count.as_i64()?)
+            .ok()
+            .filter(|count| (1..=64).contains(count)),
+        None => Some(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_shaped_recipe() {
+        let name = parse_ident("test:stick_pair").unwrap();
+        let recipe = Recipe::from_json(
+            &name,
+            &json!({
+                "type": "minecraft:crafting_shaped",
+                "category": "equipment",
+                "key": {
+                    "#": { "item": "minecraft:stick" },
+                    "D": [{ "item": "minecraft:diamond" }, { "item": "minecraft:emerald" }]
+                },
+                "pattern": ["D ", "##"],
+                "result": { "item": "minecraft:diamond_sword", "count": 2 }
+            }),
+            &RegistryMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            recipe,
+            Recipe::Shaped {
+                group: String::new(),
+                category: CraftingCategory::Equipment,
+                width: 2,
+                height: 2,
+                ingredients: vec![
+                    Ingredient(vec![ItemKind::Diamond, ItemKind::Emerald]),
+                    Ingredient::default(),
+                    Ingredient(vec![ItemKind::Stick]),
+                    Ingredient(vec![ItemKind::Stick]),
+                ],
+                result: ItemStack::new(ItemKind::DiamondSword, 2, None),
+                show_notification: true,
+            }
+        );
+        assert_eq!(recipe.kind(), ident!("crafting_shaped"));
+    }
+
+    #[test]
+    fn invalid_recipes() {
+        let name = parse_ident("test:invalid").unwrap();
+        let tags = RegistryMap::new();
+
+        for json in [
+            json!({ "type": "minecraft:smelting", "ingredient": { "item": "minecraft:stone" } }),
+            json!({ "type": "minecraft:smelting", "ingredient": { "tag": "minecraft:logs" }, "result": "minecraft:charcoal" }),
+            json!({ "type": "minecraft:crafting_shapeless", "ingredients": [], "result": { "item": "minecraft:stone" } }),
+            json!({ "type": "test:custom" }),
+        ] {
+            assert!(Recipe::from_json(&name, &json, &tags).is_err(), "{json}");
+        }
+    }
+}
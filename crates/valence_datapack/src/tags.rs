@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+use tracing::warn;
+use valence_server::block::BlockKind;
+use valence_server::protocol::VarInt;
+use valence_server::registry::tags::RegistryMap;
+use valence_server::{Ident, ItemKind};
+
+use crate::{parse_ident, DatapackError};
+
+/// The names of the tag directories whose registry is named differently.
+const TAG_DIRS: [(&str, &str); 5] = [
+    ("blocks", "block"),
+    ("items", "item"),
+    ("fluids", "fluid"),
+    ("entity_types", "entity_type"),
+    ("game_events", "game_event"),
+];
+
+/// The contents of the tag files of all data packs for a single tag.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct TagDefinition {
+    /// Whether a tag file replaced the vanilla tag.
+    replace: bool,
+    entries: Vec<TagEntry>,
+}
+
+#[derive(Clone, Debug)]
+struct TagEntry {
+    id: Ident<String>,
+    /// Whether the entry refers to another tag.
+    tag: bool,
+    required: bool,
+}
+
+/// The tags defined by data packs, keyed by registry and tag name.
+pub(crate) type TagDefinitions = BTreeMap<(Ident<String>, Ident<String>), TagDefinition>;
+
+/// Returns the registry of the tag directory at the start of `path`, and the
+/// rest of the path. `path` is relative to the `tags` directory of a
+/// namespace.
+pub(crate) fn split_tag_path<'a>(
+    path: &'a str,
+    base: &RegistryMap,
+) -> Option<(Ident<String>, &'a str)> {
+    let mut best: Option<(String, &str)> = None;
+
+    let dirs = TAG_DIRS
+        .iter()
+        .map(|&(dir, registry)| (dir.to_owned(), registry.to_owned()))
+        .chain(base.keys().map(|registry| {
+            let path = registry.path().to_owned();
+            (path.clone(), path)
+        }));
+
+    for (dir, registry) in dirs {
+        let Some(rest) = path
+            .strip_prefix(dir.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+        else {
+            continue;
+        };
+
+        match &best {
+            Some((_, best)) if best.len() <= rest.len() => {}
+            _ => best = Some((registry, rest)),
+        }
+    }
+
+    let (registry, rest) = best?;
+
+    Some((parse_ident(&format!("minecraft:{registry}")).ok()?, rest))
+}
+
+/// Adds a tag file to the definitions. Files of later data packs are added
+/// after earlier ones.
+pub(crate) fn add_tag_file(
+    defs: &mut TagDefinitions,
+    registry: Ident<String>,
+    tag: Ident<String>,
+    json: &Value,
+) -> Result<(), DatapackError> {
+    let invalid = || DatapackError::InvalidTag(tag.clone());
+
+    let Some(values) = json.get("values").and_then(Value::as_array) else {
+        return Err(invalid());
+    };
+
+    let replace = json
+        .get("replace")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    let mut entries = vec![];
+
+    for value in values {
+        let (id, required) = match value {
+            Value::String(id) => (id.as_str(), true),
+            Value::Object(entry) => match entry.get("id").and_then(Value::as_str) {
+                Some(id) => (
+                    id,
+                    entry
+                        .get("required")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(true),
+                ),
+                None => return Err(invalid()),
+            },
+            _ => return Err(invalid()),
+        };
+
+        let (id, tag) = match id.strip_prefix('#') {
+            Some(id) => (id, true),
+            None => (id, false),
+        };
+
+        entries.push(TagEntry {
+            id: parse_ident(id)?,
+            tag,
+            required,
+        });
+    }
+
+    let def = defs.entry((registry, tag)).or_default();
+
+    if replace {
+        def.replace = true;
+        def.entries = entries;
+    } else {
+        def.entries.extend(entries);
+    }
+
+    Ok(())
+}
+
+/// Applies the tag definitions of data packs on top of the vanilla tags.
+pub(crate) fn apply_tags(
+    base: &RegistryMap,
+    defs: &TagDefinitions,
+) -> Result<RegistryMap, DatapackError> {
+    let mut resolver = Resolver {
+        base,
+        defs,
+        resolved: BTreeMap::new(),
+        resolving: BTreeSet::new(),
+    };
+
+    for (registry, tag) in defs.keys() {
+        resolver.resolve(registry, tag)?;
+    }
+
+    let mut tags = base.clone();
+
+    for ((registry, tag), values) in resolver.resolved {
+        let Some(values) = values else {
+            continue;
+        };
+
+        tags.entry(registry)
+            .or_default()
+            .insert(tag, values.into_iter().map(VarInt).collect());
+    }
+
+    Ok(tags)
+}
+
+struct Resolver<'a> {
+    base: &'a RegistryMap,
+    defs: &'a TagDefinitions,
+    /// The values of the tags resolved so far, or `None` for tags which don't
+    /// exist.
+    resolved: BTreeMap<(Ident<String>, Ident<String>), Option<Vec<i32>>>,
+    /// The tags being resolved, to detect cycles.
+    resolving: BTreeSet<(Ident<String>, Ident<String>)>,
+}
+
+impl Resolver<'_> {
+    fn resolve(
+        &mut self,
+        registry: &Ident<String>,
+        tag: &Ident<String>,
+    ) -> Result<Option<Vec<i32>>, DatapackError> {
+        let key = (registry.clone(), tag.clone());
+
+        if let Some(values) = self.resolved.get(&key) {
+            return Ok(values.clone());
+        }
+
+        let base = self
+            .base
+            .get(registry)
+            .and_then(|tags| tags.get(tag))
+            .map(|values| values.iter().map(|v| v.0).collect::<Vec<_>>());
+
+        let Some(def) = self.defs.get(&key) else {
+            return Ok(base);
+        };
+
+        if !self.resolving.insert(key.clone()) {
+            return Err(DatapackError::TagCycle(tag.clone()));
+        }
+
+        let mut values = if def.replace {
+            vec![]
+        } else {
+            base.unwrap_or_default()
+        };
+
+        for entry in &def.entries {
+            let resolved = if entry.tag {
+                self.resolve(registry, &entry.id)?
+            } else if has_raw_ids(registry) {
+                raw_id(registry, &entry.id).map(|id| vec![id])
+            } else {
+                // The entry can't be resolved without the IDs of the registry,
+                // but tags referenced by the tag still can.
+                if entry.required {
+                    warn!(
+                        "ignoring entry \"{}\" of tag \"{tag}\" because entries of registry \
+                         \"{registry}\" are not supported",
+                        entry.id
+                    );
+                }
+
+                continue;
+            };
+
+            match resolved {
+                Some(ids) => {
+                    for id in ids {
+                        if !values.contains(&id) {
+                            values.push(id);
+                        }
+                    }
+                }
+                None if entry.required => {
+                    return Err(DatapackError::UnknownTagEntry {
+                        tag: tag.clone(),
+                        entry: entry.id.clone(),
+                    })
+                }
+                None => {}
+            }
+        }
+
+        self.resolving.remove(&key);
+        self.resolved.insert(key, Some(values.clone()));
+
+        Ok(Some(values))
+    }
+}
+
+/// Returns whether the protocol IDs of the entries of a registry are known.
+fn has_raw_ids(registry: &Ident<String>) -> bool {
+    matches!(registry.as_str(), "minecraft:block" | "minecraft:item")
+}
+
+/// Returns the protocol ID of an entry of a registry, or `None` if there is no
+/// such entry or the registry isn't supported by [`has_raw_ids`].
+fn raw_id(registry: &Ident<String>, id: &Ident<String>) -> Option<i32> {
+    if id.namespace() != "minecraft" {
+        return None;
+    }
+
+    match registry.as_str() {
+        "minecraft:block" => BlockKind::from_str(id.path()).map(|block| i32::from(block.to_raw())),
+        "minecraft:item" => ItemKind::from_str(id.path()).map(|item| i32::from(item.to_raw())),
+        _ => None,
+    }
+}
+
+/// Returns the items in an item tag, or `None` if there is no such tag.
+pub(crate) fn item_tag(tags: &RegistryMap, tag: &Ident<String>) -> Option<Vec<ItemKind>> {
+    let values = tags.get("minecraft:item")?.get(tag)?;
+
+    Some(
+        values
+            .iter()
+            .filter_map(|id| ItemKind::from_raw(u16::try_from(id.0).ok()?))
+            .collect(),
+    )
+}
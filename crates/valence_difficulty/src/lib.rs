@@ -0,0 +1,370 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use valence_server::client::{Client, FlushPacketsSet, UpdateClientsSet, VisibleChunkLayer};
+use valence_server::entity::Position;
+use valence_server::protocol::packets::play::DifficultyS2c;
+use valence_server::protocol::WritePacket;
+use valence_server::{BlockPos, ChunkLayer, ChunkPos, Difficulty, GameMode};
+use valence_time::WorldTime;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, tick_inhabited_time).add_systems(
+            PostUpdate,
+            (
+                init_difficulty_on_layer_join.before(FlushPacketsSet),
+                sync_layer_difficulty.before(UpdateClientsSet),
+            ),
+        );
+    }
+}
+
+/// The horizontal distance in blocks a player must be within for a chunk to
+/// count as inhabited, which is the range mobs spawn in.
+pub const INHABITED_RANGE: f64 = 128.0;
+
+/// The number of ticks it takes for the inhabited time of a chunk to reach
+/// its full effect on the regional difficulty, which is 150 days.
+pub const MAX_INHABITED_TIME: i64 = 3_600_000;
+
+/// Component for chunk layers which sets the difficulty of the layer.
+///
+/// Layers without this component have the default difficulty of
+/// [`Difficulty::Normal`].
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct WorldDifficulty {
+    pub difficulty: Difficulty,
+    /// Whether the client shows the difficulty as locked, which disables the
+    /// difficulty button in the options menu.
+    pub locked: bool,
+}
+
+impl WorldDifficulty {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            difficulty,
+            locked: false,
+        }
+    }
+
+    fn to_packet(self) -> DifficultyS2c {
+        DifficultyS2c {
+            difficulty: self.difficulty,
+            locked: self.locked,
+        }
+    }
+}
+
+impl Default for WorldDifficulty {
+    fn default() -> Self {
+        Self::new(Difficulty::Normal)
+    }
+}
+
+/// Component for chunk layers which counts the number of ticks players have
+/// spent within [`INHABITED_RANGE`] of each chunk. Spectators are not
+/// counted.
+///
+/// The counts are kept when chunks are unloaded, so they can be saved along
+/// with the chunks and restored with [`InhabitedTime::set`].
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct InhabitedTime {
+    chunks: HashMap<ChunkPos, i64>,
+}
+
+impl InhabitedTime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the inhabited time of the chunk at `pos` in ticks.
+    pub fn get(&self, pos: impl Into<ChunkPos>) -> i64 {
+        self.chunks.get(&pos.into()).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, pos: impl Into<ChunkPos>, ticks: i64) {
+        let pos = pos.into();
+
+        if ticks == 0 {
+            self.chunks.remove(&pos);
+        } else {
+            self.chunks.insert(pos, ticks);
+        }
+    }
+
+    /// Returns the chunks with a nonzero inhabited time.
+    pub fn iter(&self) -> impl Iterator<Item = (ChunkPos, i64)> + '_ {
+        self.chunks.iter().map(|(&pos, &ticks)| (pos, ticks))
+    }
+}
+
+/// The difficulty at a position, which increases with the age of the world,
+/// the time players have spent nearby, and the size of the moon. Mobs use it
+/// to scale their equipment and abilities.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RegionalDifficulty {
+    difficulty: Difficulty,
+    effective: f32,
+}
+
+impl RegionalDifficulty {
+    /// Computes the regional difficulty like vanilla does.
+    ///
+    /// - **`difficulty`**: The difficulty of the layer.
+    /// - **`time_of_day`**: The total time of day of the layer, including
+    ///   previous days.
+    /// - **`inhabited_time`**: The inhabited time of the chunk in ticks.
+    /// - **`moon_brightness`**: How much of the moon is lit, from 0 to 1.
+    pub fn new(
+        difficulty: Difficulty,
+        time_of_day: i64,
+        inhabited_time: i64,
+        moon_brightness: f32,
+    ) -> Self {
+        let effective = match difficulty {
+            Difficulty::Peaceful => 0.0,
+            _ => {
+                let is_hard = difficulty == Difficulty::Hard;
+
+                // The age of the world counts after the first three days, and
+                // reaches its full effect after 63 days.
+                let world_factor =
+                    ((time_of_day - 72000) as f32 / 1_440_000.0).clamp(0.0, 1.0) * 0.25;
+
+                let mut local_factor = (inhabited_time as f32 / MAX_INHABITED_TIME as f32)
+                    .clamp(0.0, 1.0)
+                    * if is_hard { 1.0 } else { 0.75 };
+
+                local_factor += (moon_brightness * 0.25).clamp(0.0, world_factor);
+
+                if difficulty == Difficulty::Easy {
+                    local_factor *= 0.5;
+                }
+
+                difficulty_id(difficulty) as f32 * (0.75 + world_factor + local_factor)
+            }
+        };
+
+        Self {
+            difficulty,
+            effective,
+        }
+    }
+
+    /// Returns the regional difficulty of `pos` in `layer`, using the
+    /// [`WorldDifficulty`], [`WorldTime`] and [`InhabitedTime`] of the layer
+    /// if it has them.
+    pub fn at(world: &World, layer: Entity, pos: impl Into<BlockPos>) -> Self {
+        let Some(layer) = world.get_entity(layer) else {
+            return Self::new(WorldDifficulty::default().difficulty, 0, 0, 0.0);
+        };
+
+        Self::from_components(
+            layer.get(),
+            layer.get(),
+            layer.get(),
+            ChunkPos::from(pos.into()),
+        )
+    }
+
+    fn from_components(
+        difficulty: Option<&WorldDifficulty>,
+        time: Option<&WorldTime>,
+        inhabited: Option<&InhabitedTime>,
+        pos: ChunkPos,
+    ) -> Self {
+        let difficulty = difficulty.copied().unwrap_or_default().difficulty;
+        let (time_of_day, moon_brightness) = time.map_or((0, 0.0), |time| {
+            (time.time_of_day(), time.moon_phase().size())
+        });
+        let inhabited_time = inhabited.map_or(0, |inhabited| inhabited.get(pos));
+
+        Self::new(difficulty, time_of_day, inhabited_time, moon_brightness)
+    }
+
+    /// The difficulty of the layer.
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// The regional difficulty shown in the debug screen, from 0 to 6.75.
+    pub fn effective(&self) -> f32 {
+        self.effective
+    }
+
+    /// Whether the effective difficulty is higher than `value`. Vanilla only
+    /// does this on difficulties other than peaceful, which already has an
+    /// effective difficulty of 0.
+    pub fn is_harder_than(&self, value: f32) -> bool {
+        self.effective > value
+    }
+
+    /// The effective difficulty mapped from \[2, 4\] to \[0, 1\]. Vanilla
+    /// scales the chance of mobs spawning with equipment and enchantments by
+    /// this.
+    pub fn special_multiplier(&self) -> f32 {
+        if self.effective < 2.0 {
+            0.0
+        } else if self.effective > 4.0 {
+            1.0
+        } else {
+            (self.effective - 2.0) / 2.0
+        }
+    }
+}
+
+fn difficulty_id(difficulty: Difficulty) -> i32 {
+    match difficulty {
+        Difficulty::Peaceful => 0,
+        Difficulty::Easy => 1,
+        Difficulty::Normal => 2,
+        Difficulty::Hard => 3,
+    }
+}
+
+/// [`SystemParam`] for looking up the [`RegionalDifficulty`] of positions in
+/// chunk layers.
+#[derive(SystemParam)]
+pub struct RegionalDifficulties<'w, 's> {
+    layers: Query<
+        'w,
+        's,
+        (
+            Option<&'static WorldDifficulty>,
+            Option<&'static WorldTime>,
+            Option<&'static InhabitedTime>,
+        ),
+    >,
+}
+
+impl RegionalDifficulties<'_, '_> {
+    /// Returns the regional difficulty of `pos` in `layer`. See
+    /// [`RegionalDifficulty::at`].
+    pub fn get(&self, layer: Entity, pos: impl Into<BlockPos>) -> RegionalDifficulty {
+        let pos = ChunkPos::from(pos.into());
+
+        match self.layers.get(layer) {
+            Ok((difficulty, time, inhabited)) => {
+                RegionalDifficulty::from_components(difficulty, time, inhabited, pos)
+            }
+            Err(_) => RegionalDifficulty::from_components(None, None, None, pos),
+        }
+    }
+}
+
+fn tick_inhabited_time(
+    mut layers: Query<&mut InhabitedTime>,
+    players: Query<(&Position, &VisibleChunkLayer, &GameMode), With<Client>>,
+) {
+    let range = (INHABITED_RANGE / 16.0).ceil() as i32;
+    let mut inhabited = HashSet::new();
+
+    for (pos, layer, game_mode) in &players {
+        if *game_mode == GameMode::Spectator || !layers.contains(layer.0) {
+            continue;
+        }
+
+        let center = ChunkPos::from(pos.0);
+
+        for z in center.z - range..=center.z + range {
+            for x in center.x - range..=center.x + range {
+                // Distance from the player to the center of the chunk.
+                let dx = x as f64 * 16.0 + 8.0 - pos.0.x;
+                let dz = z as f64 * 16.0 + 8.0 - pos.0.z;
+
+                if dx * dx + dz * dz < INHABITED_RANGE * INHABITED_RANGE {
+                    inhabited.insert((layer.0, ChunkPos::new(x, z)));
+                }
+            }
+        }
+    }
+
+    // Chunks near several players are only counted once.
+    for (layer, pos) in inhabited {
+        if let Ok(mut inhabited_time) = layers.get_mut(layer) {
+            *inhabited_time.chunks.entry(pos).or_insert(0) += 1;
+        }
+    }
+}
+
+fn sync_layer_difficulty(
+    mut layers: Query<(&mut ChunkLayer, &WorldDifficulty), Changed<WorldDifficulty>>,
+) {
+    for (mut layer, difficulty) in &mut layers {
+        layer.write_packet(&difficulty.to_packet());
+    }
+}
+
+fn init_difficulty_on_layer_join(
+    mut clients: Query<(&mut Client, &VisibleChunkLayer), Changed<VisibleChunkLayer>>,
+    layers: Query<&WorldDifficulty, With<ChunkLayer>>,
+) {
+    for (mut client, visible_chunk_layer) in &mut clients {
+        if let Ok(difficulty) = layers.get(visible_chunk_layer.0) {
+            client.write_packet(&difficulty.to_packet());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_regional_difficulty() {
+        // A new world on normal.
+        let new = RegionalDifficulty::new(Difficulty::Normal, 0, 0, 1.0);
+        assert_eq!(new.effective(), 1.5);
+        assert_eq!(new.special_multiplier(), 0.0);
+
+        // The maximum on hard, in a long inhabited chunk under a full moon.
+        let max = RegionalDifficulty::new(Difficulty::Hard, 2_000_000, MAX_INHABITED_TIME, 1.0);
+        assert_eq!(max.effective(), 6.75);
+        assert_eq!(max.special_multiplier(), 1.0);
+
+        // Easy halves the local factor.
+        let easy = RegionalDifficulty::new(Difficulty::Easy, 2_000_000, MAX_INHABITED_TIME, 1.0);
+        assert!((easy.effective() - 1.5).abs() < 1e-6);
+
+        let peaceful = RegionalDifficulty::new(Difficulty::Peaceful, 2_000_000, 1_000_000, 1.0);
+        assert_eq!(peaceful.effective(), 0.0);
+    }
+
+    #[test]
+    fn inhabited_time_counts() {
+        let mut inhabited = InhabitedTime::new();
+        assert_eq!(inhabited.get(ChunkPos::new(1, 2)), 0);
+
+        inhabited.set(ChunkPos::new(1, 2), 40);
+        assert_eq!(inhabited.get(ChunkPos::new(1, 2)), 40);
+        assert_eq!(inhabited.iter().count(), 1);
+
+        inhabited.set(ChunkPos::new(1, 2), 0);
+        assert_eq!(inhabited.iter().count(), 0);
+    }
+}
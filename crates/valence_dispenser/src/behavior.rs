@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use bevy_ecs::prelude::*;
+use valence_inventory::Inventory;
+use valence_server::block::{BlockTag, PropName, PropValue};
+use valence_server::client::Client;
+use valence_server::entity::arrow::ArrowEntityBundle;
+use valence_server::entity::hitbox::Hitbox;
+use valence_server::entity::item::{ItemEntityBundle, Stack};
+use valence_server::entity::{EntityLayerId, Position, Velocity};
+use valence_server::math::{Aabb, DVec3};
+use valence_server::rand::Rng;
+use valence_server::{BlockState, ChunkLayer, ItemKind, ItemStack};
+
+use crate::{direction_vec, DispenseContext};
+
+/// A function deciding what happens when an item is dispensed.
+///
+/// Returns the stack which should be put back into the slot the item was taken
+/// from, or `None` if the item could not be dispensed. The dispenser plays a
+/// failure sound in that case and the slot is left unchanged.
+pub type DispenseBehavior =
+    Box<dyn Fn(&mut World, &DispenseContext) -> Option<ItemStack> + Send + Sync + 'static>;
+
+/// Resource mapping item kinds to their [`DispenseBehavior`]. Items without a
+/// behavior are dropped in front of the dispenser.
+#[derive(Resource)]
+pub struct DispenserBehaviors {
+    behaviors: HashMap<ItemKind, DispenseBehavior>,
+}
+
+impl DispenserBehaviors {
+    /// Creates a registry without any behaviors. Every item is dropped.
+    pub fn empty() -> Self {
+        Self {
+            behaviors: HashMap::new(),
+        }
+    }
+
+    /// Sets the behavior for `item`, replacing the previous one.
+    pub fn register(
+        &mut self,
+        item: ItemKind,
+        behavior: impl Fn(&mut World, &DispenseContext) -> Option<ItemStack> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.behaviors.insert(item, Box::new(behavior));
+        self
+    }
+
+    /// Removes the behavior for `item`, making it be dropped.
+    pub fn unregister(&mut self, item: ItemKind) -> Option<DispenseBehavior> {
+        self.behaviors.remove(&item)
+    }
+
+    pub fn get(&self, item: ItemKind) -> Option<&DispenseBehavior> {
+        self.behaviors.get(&item)
+    }
+
+    /// Runs the behavior for the item in `ctx`.
+    pub fn dispense(&self, world: &mut World, ctx: &DispenseContext) -> Option<ItemStack> {
+        match self.get(ctx.stack.item) {
+            Some(behavior) => behavior(world, ctx),
+            None => drop_item(world, ctx),
+        }
+    }
+}
+
+impl Default for DispenserBehaviors {
+    fn default() -> Self {
+        let mut behaviors = Self::empty();
+
+        behaviors
+            .register(ItemKind::Arrow, shoot_arrow)
+            .register(ItemKind::WaterBucket, |world, ctx| {
+                place_liquid(world, ctx, BlockState::WATER)
+            })
+            .register(ItemKind::LavaBucket, |world, ctx| {
+                place_liquid(world, ctx, BlockState::LAVA)
+            })
+            .register(ItemKind::Bucket, pick_up_liquid)
+            .register(ItemKind::BoneMeal, use_bone_meal);
+
+        for item in ItemKind::ALL {
+            if armor_slot(item).is_some() {
+                behaviors.register(item, equip_armor);
+            }
+        }
+
+        behaviors
+    }
+}
+
+/// Drops a single item in front of the dispenser.
+pub fn drop_item(world: &mut World, ctx: &DispenseContext) -> Option<ItemStack> {
+    let mut rng = valence_server::rand::thread_rng();
+    let velocity = direction_vec(ctx.facing) * rng.gen_range(2.0..2.4)
+        + DVec3::new(
+            rng.gen_range(-0.15..0.15),
+            rng.gen_range(-0.15..0.15) + 4.0,
+            rng.gen_range(-0.15..0.15),
+        ) * 0.5;
+
+    world.spawn(ItemEntityBundle {
+        item_stack: Stack(ctx.stack.clone().with_count(1)),
+        layer: EntityLayerId(ctx.layer),
+        position: Position(ctx.spawn_position() - DVec3::new(0.0, 0.15, 0.0)),
+        velocity: Velocity(velocity.as_vec3()),
+        ..Default::default()
+    });
+
+    Some(ctx.decremented())
+}
+
+fn shoot_arrow(world: &mut World, ctx: &DispenseContext) -> Option<ItemStack> {
+    let mut rng = valence_server::rand::thread_rng();
+
+    // Vanilla shoots arrows at 1.1 blocks per tick with some inaccuracy, aimed
+    // slightly upwards.
+    let direction = (direction_vec(ctx.facing) + DVec3::new(0.0, 0.1, 0.0)).normalize()
+        + DVec3::new(
+            rng.gen_range(-0.05..0.05),
+            rng.gen_range(-0.05..0.05),
+            rng.gen_range(-0.05..0.05),
+        );
+
+    world.spawn(ArrowEntityBundle {
+        layer: EntityLayerId(ctx.layer),
+        position: Position(ctx.spawn_position()),
+        velocity: Velocity((direction * 1.1 * 20.0).as_vec3()),
+        ..Default::default()
+    });
+
+    Some(ctx.decremented())
+}
+
+fn place_liquid(world: &mut World, ctx: &DispenseContext, liquid: BlockState) -> Option<ItemStack> {
+    let target = ctx.target();
+    let mut layer = world.get_mut::<ChunkLayer>(ctx.layer)?;
+
+    if !layer.block(target)?.state.is_replaceable() {
+        return None;
+    }
+
+    layer.set_block(target, liquid);
+
+    Some(ItemStack::new(ItemKind::Bucket, 1, None))
+}
+
+fn pick_up_liquid(world: &mut World, ctx: &DispenseContext) -> Option<ItemStack> {
+    let target = ctx.target();
+    let mut layer = world.get_mut::<ChunkLayer>(ctx.layer)?;
+
+    let filled = match layer.block(target)?.state {
+        BlockState::WATER => ItemKind::WaterBucket,
+        BlockState::LAVA => ItemKind::LavaBucket,
+        _ => return None,
+    };
+
+    layer.set_block(target, BlockState::AIR);
+
+    let filled = ItemStack::new(filled, 1, None);
+
+    if ctx.stack.count == 1 {
+        return Some(filled);
+    }
+
+    // The filled bucket doesn't fit into the slot of the empty buckets, so it
+    // goes into a free slot or is dropped if there is none.
+    let free_slot = world
+        .get::<Inventory>(ctx.dispenser)?
+        .first_empty_slot()
+        .filter(|&slot| slot != ctx.slot);
+
+    match free_slot {
+        Some(slot) => world
+            .get_mut::<Inventory>(ctx.dispenser)?
+            .set_slot(slot, filled),
+        None => {
+            drop_item(
+                world,
+                &DispenseContext {
+                    stack: filled,
+                    ..ctx.clone()
+                },
+            );
+        }
+    }
+
+    Some(ctx.decremented())
+}
+
+fn use_bone_meal(world: &mut World, ctx: &DispenseContext) -> Option<ItemStack> {
+    let target = ctx.target();
+    let mut layer = world.get_mut::<ChunkLayer>(ctx.layer)?;
+
+    let grown = grow(layer.block(target)?.state)?;
+
+    layer.set_block(target, grown);
+
+    Some(ctx.decremented())
+}
+
+/// Returns the state of a crop after applying bone meal to it, or `None` if it
+/// isn't a crop or can't grow any further.
+fn grow(state: BlockState) -> Option<BlockState> {
+    if !state.is_in(BlockTag::CROPS) {
+        return None;
+    }
+
+    let mut grown = state;
+
+    // Crops grow by a random number of stages, but not beyond their maximum
+    // age.
+    for _ in 0..valence_server::rand::thread_rng().gen_range(2..=5) {
+        let next = grown
+            .get(PropName::Age)
+            .and_then(PropValue::to_u16)
+            .and_then(|age| PropValue::from_u16(age + 1))
+            .map(|v| grown.set(PropName::Age, v))
+            .filter(|next| next != &grown);
+
+        match next {
+            Some(next) => grown = next,
+            None => break,
+        }
+    }
+
+    (grown != state).then_some(grown)
+}
+
+/// Returns the slot in the player inventory `item` is worn in, if it is armor.
+fn armor_slot(item: ItemKind) -> Option<u16> {
+    let name = item.to_str();
+
+    if name.ends_with("_helmet") || item == ItemKind::CarvedPumpkin {
+        Some(5)
+    } else if name.ends_with("_chestplate") || item == ItemKind::Elytra {
+        Some(6)
+    } else if name.ends_with("_leggings") {
+        Some(7)
+    } else if name.ends_with("_boots") {
+        Some(8)
+    } else {
+        None
+    }
+}
+
+/// Equips the armor to a player in front of the dispenser. If there is none,
+/// the armor is dropped instead.
+///
+/// Only clients have an inventory to put the armor in, so armor stands and
+/// mobs are not equipped.
+fn equip_armor(world: &mut World, ctx: &DispenseContext) -> Option<ItemStack> {
+    let slot = armor_slot(ctx.stack.item)?;
+    let target = ctx.target();
+    let target_box = Aabb::new(
+        DVec3::new(target.x as f64, target.y as f64, target.z as f64),
+        DVec3::new(target.x as f64, target.y as f64, target.z as f64) + DVec3::ONE,
+    );
+
+    let mut players =
+        world.query_filtered::<(&Hitbox, &EntityLayerId, &mut Inventory), With<Client>>();
+
+    for (hitbox, layer, mut inventory) in players.iter_mut(world) {
+        if layer.0 == ctx.layer
+            && hitbox.get().intersects(target_box)
+            && inventory.slot(slot).is_empty()
+        {
+            inventory.set_slot(slot, ctx.stack.clone().with_count(1));
+            return Some(ctx.decremented());
+        }
+    }
+
+    drop_item(world, ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bone_meal_grows_crops_up_to_max_age() {
+        let grown = grow(BlockState::WHEAT).unwrap();
+        let age = grown.get(PropName::Age).unwrap().to_u16().unwrap();
+        assert!((2..=5).contains(&age));
+
+        let ripe = BlockState::WHEAT.set(PropName::Age, PropValue::_7);
+        assert_eq!(grow(ripe), None);
+
+        assert_eq!(grow(BlockState::STONE), None);
+
+        // Saplings grow into trees, which can't be placed.
+        assert_eq!(grow(BlockState::OAK_SAPLING), None);
+
+        // Other blocks with an age aren't crops.
+        assert_eq!(grow(BlockState::SUGAR_CANE), None);
+        assert_eq!(grow(BlockState::FIRE), None);
+
+        let stem = grow(BlockState::PUMPKIN_STEM).unwrap();
+        assert_ne!(stem.get(PropName::Age), Some(PropValue::_0));
+    }
+
+    #[test]
+    fn armor_slots() {
+        assert_eq!(armor_slot(ItemKind::DiamondHelmet), Some(5));
+        assert_eq!(armor_slot(ItemKind::Elytra), Some(6));
+        assert_eq!(armor_slot(ItemKind::IronLeggings), Some(7));
+        assert_eq!(armor_slot(ItemKind::LeatherBoots), Some(8));
+        assert_eq!(armor_slot(ItemKind::Stone), None);
+    }
+}
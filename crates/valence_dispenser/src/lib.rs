@@ -0,0 +1,204 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod behavior;
+
+pub use behavior::{drop_item, DispenseBehavior, DispenserBehaviors};
+use bevy_app::prelude::*;
+use bevy_ecs::event::ManualEventReader;
+use bevy_ecs::prelude::*;
+use valence_inventory::{Inventory, InventoryKind};
+use valence_server::block::{PropName, PropValue};
+use valence_server::math::DVec3;
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::rand::seq::IteratorRandom;
+use valence_server::{BlockPos, ChunkLayer, Direction, ItemStack};
+
+pub struct DispenserPlugin;
+
+impl Plugin for DispenserPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DispenserBehaviors>()
+            .add_event::<DispenseEvent>()
+            .add_systems(Update, dispense);
+    }
+}
+
+/// Component for entities which represent a dispenser block.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Dispenser {
+    /// The chunk layer the dispenser block is in.
+    pub layer: Entity,
+    /// The position of the dispenser block.
+    pub position: BlockPos,
+}
+
+#[derive(Bundle)]
+pub struct DispenserBundle {
+    pub dispenser: Dispenser,
+    /// The contents of the dispenser. Should be a
+    /// [`InventoryKind::Generic3x3`] inventory.
+    pub inventory: Inventory,
+}
+
+impl DispenserBundle {
+    pub fn new(layer: Entity, position: impl Into<BlockPos>) -> Self {
+        Self {
+            dispenser: Dispenser {
+                layer,
+                position: position.into(),
+            },
+            inventory: Inventory::new(InventoryKind::Generic3x3),
+        }
+    }
+}
+
+/// Send this event to activate a dispenser.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DispenseEvent {
+    /// The entity with the [`Dispenser`] component.
+    pub dispenser: Entity,
+}
+
+/// Information about the item being dispensed, passed to
+/// [`DispenseBehavior`]s.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DispenseContext {
+    /// The entity with the [`Dispenser`] component.
+    pub dispenser: Entity,
+    /// The chunk layer the dispenser block is in.
+    pub layer: Entity,
+    /// The position of the dispenser block.
+    pub position: BlockPos,
+    /// The direction the dispenser block is facing.
+    pub facing: Direction,
+    /// The slot of the dispenser inventory the item was taken from.
+    pub slot: u16,
+    /// The whole stack in the slot.
+    pub stack: ItemStack,
+}
+
+impl DispenseContext {
+    /// The position of the block in front of the dispenser.
+    pub fn target(&self) -> BlockPos {
+        self.position.get_in_direction(self.facing)
+    }
+
+    /// The position projectiles and items leave the dispenser from, in the
+    /// center of its front face.
+    pub fn spawn_position(&self) -> DVec3 {
+        let center = DVec3::new(
+            self.position.x as f64 + 0.5,
+            self.position.y as f64 + 0.5,
+            self.position.z as f64 + 0.5,
+        );
+
+        center + direction_vec(self.facing) * 0.7
+    }
+
+    /// The stack with one item less than [`DispenseContext::stack`].
+    pub fn decremented(&self) -> ItemStack {
+        if self.stack.count > 1 {
+            self.stack.clone().with_count(self.stack.count - 1)
+        } else {
+            ItemStack::EMPTY
+        }
+    }
+}
+
+/// Returns the unit vector pointing in `dir`.
+pub fn direction_vec(dir: Direction) -> DVec3 {
+    match dir {
+        Direction::Down => DVec3::NEG_Y,
+        Direction::Up => DVec3::Y,
+        Direction::North => DVec3::NEG_Z,
+        Direction::South => DVec3::Z,
+        Direction::West => DVec3::NEG_X,
+        Direction::East => DVec3::X,
+    }
+}
+
+fn dispense(world: &mut World, mut reader: Local<ManualEventReader<DispenseEvent>>) {
+    let events: Vec<_> = reader
+        .read(world.resource::<Events<DispenseEvent>>())
+        .copied()
+        .collect();
+
+    for event in events {
+        let Some(ctx) = dispense_context(world, event.dispenser) else {
+            continue;
+        };
+
+        let result = world.resource_scope(|world, behaviors: Mut<DispenserBehaviors>| {
+            behaviors.dispense(world, &ctx)
+        });
+
+        let sound = match result {
+            Some(stack) => {
+                if let Some(mut inventory) = world.get_mut::<Inventory>(ctx.dispenser) {
+                    inventory.set_slot(ctx.slot, stack);
+                }
+
+                Sound::BlockDispenserDispense
+            }
+            None => Sound::BlockDispenserFail,
+        };
+
+        if let Some(mut layer) = world.get_mut::<ChunkLayer>(ctx.layer) {
+            layer.play_sound(sound, SoundCategory::Block, ctx.spawn_position(), 1.0, 1.0);
+        }
+    }
+}
+
+fn dispense_context(world: &World, dispenser_entity: Entity) -> Option<DispenseContext> {
+    let entity = world.get_entity(dispenser_entity)?;
+    let dispenser = entity.get::<Dispenser>()?;
+    let inventory = entity.get::<Inventory>()?;
+
+    let state = world
+        .get::<ChunkLayer>(dispenser.layer)?
+        .block(dispenser.position)?
+        .state;
+
+    let facing = match state.get(PropName::Facing)? {
+        PropValue::Down => Direction::Down,
+        PropValue::Up => Direction::Up,
+        PropValue::North => Direction::North,
+        PropValue::South => Direction::South,
+        PropValue::West => Direction::West,
+        PropValue::East => Direction::East,
+        _ => return None,
+    };
+
+    let (slot, stack) = inventory
+        .slots()
+        .enumerate()
+        .filter(|(_, stack)| !stack.is_empty())
+        .choose(&mut valence_server::rand::thread_rng())?;
+
+    Some(DispenseContext {
+        dispenser: dispenser_entity,
+        layer: dispenser.layer,
+        position: dispenser.position,
+        facing,
+        slot: slot as u16,
+        stack: stack.clone(),
+    })
+}
@@ -3,7 +3,7 @@ use indexmap::IndexMap;
 use valence_protocol::status_effects::StatusEffect;
 
 /// Represents a change in the [`ActiveStatusEffects`] of an [`Entity`].
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum StatusEffectChange {
     Apply(ActiveStatusEffect),
     Replace(ActiveStatusEffect),
@@ -26,7 +26,7 @@ pub enum DurationResult {
 }
 
 /// [`Component`] that stores the [`ActiveStatusEffect`]s of an [`Entity`].
-#[derive(Component, Default, Debug)]
+#[derive(Component, Clone, Default, Debug)]
 pub struct ActiveStatusEffects {
     /// vec is always sorted in descending order of amplifier and ascending
     /// order of duration.
@@ -0,0 +1,403 @@
+//! Typed access to the tracked data of display entities, and builder methods
+//! for spawning them.
+//!
+//! Text, item, and block displays are configured through the components in the
+//! [`display`], [`text_display`], [`item_display`], and [`block_display`]
+//! modules. This module adds typed getters and setters for the components with
+//! packed values, and `with_*` methods to the display bundles so that
+//! holograms and custom models can be set up without knowing the raw values.
+//!
+//! Changing the transformation of a display with a nonzero
+//! [`InterpolationDuration`](display::InterpolationDuration) automatically
+//! restarts the interpolation, so the client animates the change.
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_entity::display_builder::{BillboardMode, DisplayTransform};
+//! use valence_entity::text_display::TextDisplayEntityBundle;
+//! use valence_math::Vec3;
+//!
+//! let hologram = TextDisplayEntityBundle::default()
+//!     .with_text("Welcome!")
+//!     .with_billboard(BillboardMode::Center)
+//!     .with_background(0x80_00_00_00)
+//!     .with_transform(DisplayTransform::default().with_scale(Vec3::splat(2.0)))
+//!     .with_interpolation_duration(5);
+//! # let _ = hologram;
+//! ```
+
+use valence_math::{Quat, Vec3};
+use valence_protocol::text::IntoText;
+use valence_protocol::{BlockState, ItemStack};
+
+use super::*;
+
+/// How a display entity rotates to face the viewer, from
+/// [`display::Billboard`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum BillboardMode {
+    /// The display doesn't rotate.
+    #[default]
+    Fixed,
+    /// The display rotates around the vertical axis.
+    Vertical,
+    /// The display rotates around the horizontal axis.
+    Horizontal,
+    /// The display always faces the viewer.
+    Center,
+}
+
+impl display::Billboard {
+    pub fn new(mode: BillboardMode) -> Self {
+        Self(mode as i8)
+    }
+
+    /// Returns the billboard mode, or [`BillboardMode::Fixed`] for unknown
+    /// values.
+    pub fn mode(&self) -> BillboardMode {
+        match self.0 {
+            1 => BillboardMode::Vertical,
+            2 => BillboardMode::Horizontal,
+            3 => BillboardMode::Center,
+            _ => BillboardMode::Fixed,
+        }
+    }
+}
+
+impl display::Brightness {
+    /// Overrides the light level used to render the display. Both levels are
+    /// clamped to 15.
+    pub fn new(block_light: u8, sky_light: u8) -> Self {
+        let block = i32::from(block_light.min(15));
+        let sky = i32::from(sky_light.min(15));
+        Self(block << 4 | sky << 20)
+    }
+
+    /// Renders the display with the light level at its position.
+    pub fn from_world() -> Self {
+        Self(-1)
+    }
+
+    /// Returns the block and sky light levels, or `None` if the light level of
+    /// the world is used.
+    pub fn get(&self) -> Option<(u8, u8)> {
+        (self.0 != -1).then_some((((self.0 >> 4) & 0xf) as u8, ((self.0 >> 20) & 0xf) as u8))
+    }
+}
+
+impl display::GlowColorOverride {
+    /// Uses `rgb` as the glow color instead of the team color.
+    pub fn new(rgb: u32) -> Self {
+        Self((rgb & 0xff_ff_ff) as i32)
+    }
+}
+
+/// The horizontal alignment of the lines of a text display.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum TextAlignment {
+    #[default]
+    Center,
+    Left,
+    Right,
+}
+
+impl text_display::TextDisplayFlags {
+    pub fn alignment(&self) -> TextAlignment {
+        match (self.0 >> 3) & 0b11 {
+            1 => TextAlignment::Left,
+            2 => TextAlignment::Right,
+            _ => TextAlignment::Center,
+        }
+    }
+
+    pub fn set_alignment(&mut self, alignment: TextAlignment) {
+        let bits = match alignment {
+            TextAlignment::Center => 0,
+            TextAlignment::Left => 1,
+            TextAlignment::Right => 2,
+        };
+
+        self.0 = (self.0 & !0b11000) | bits << 3;
+    }
+}
+
+impl text_display::Background {
+    /// Creates a background color from its alpha, red, green, and blue
+    /// components packed as `0xAARRGGBB`.
+    pub fn from_argb(argb: u32) -> Self {
+        Self(argb as i32)
+    }
+
+    pub fn argb(&self) -> u32 {
+        self.0 as u32
+    }
+}
+
+/// How the item of an item display is rendered, from
+/// [`item_display::ItemDisplay`]. Matches the item model transforms of
+/// resource packs.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum ItemDisplayTransform {
+    #[default]
+    None,
+    ThirdPersonLeftHand,
+    ThirdPersonRightHand,
+    FirstPersonLeftHand,
+    FirstPersonRightHand,
+    Head,
+    Gui,
+    Ground,
+    Fixed,
+}
+
+impl item_display::ItemDisplay {
+    pub fn new(transform: ItemDisplayTransform) -> Self {
+        Self(transform as i8)
+    }
+
+    /// Returns the item transform, or [`ItemDisplayTransform::None`] for
+    /// unknown values.
+    pub fn transform(&self) -> ItemDisplayTransform {
+        match self.0 {
+            1 => ItemDisplayTransform::ThirdPersonLeftHand,
+            2 => ItemDisplayTransform::ThirdPersonRightHand,
+            3 => ItemDisplayTransform::FirstPersonLeftHand,
+            4 => ItemDisplayTransform::FirstPersonRightHand,
+            5 => ItemDisplayTransform::Head,
+            6 => ItemDisplayTransform::Gui,
+            7 => ItemDisplayTransform::Ground,
+            8 => ItemDisplayTransform::Fixed,
+            _ => ItemDisplayTransform::None,
+        }
+    }
+}
+
+/// The transformation of a display entity, applied as translation, left
+/// rotation, scale, and right rotation, in that order.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DisplayTransform {
+    pub translation: Vec3,
+    pub left_rotation: Quat,
+    pub scale: Vec3,
+    pub right_rotation: Quat,
+}
+
+impl DisplayTransform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        left_rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+        right_rotation: Quat::IDENTITY,
+    };
+
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    /// Sets the left rotation, which is applied before scaling.
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.left_rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the right rotation, which is applied after scaling.
+    pub fn with_right_rotation(mut self, rotation: Quat) -> Self {
+        self.right_rotation = rotation;
+        self
+    }
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+macro_rules! display_bundle_methods {
+    ($($bundle:ty),* $(,)?) => {
+        $(
+            impl $bundle {
+                pub fn with_layer(mut self, layer: Entity) -> Self {
+                    self.layer = EntityLayerId(layer);
+                    self
+                }
+
+                pub fn with_position(mut self, position: impl Into<DVec3>) -> Self {
+                    self.position = Position(position.into());
+                    self
+                }
+
+                pub fn with_transform(mut self, transform: DisplayTransform) -> Self {
+                    self.display_translation = display::Translation(transform.translation);
+                    self.display_left_rotation = display::LeftRotation(transform.left_rotation);
+                    self.display_scale = display::Scale(transform.scale);
+                    self.display_right_rotation = display::RightRotation(transform.right_rotation);
+                    self
+                }
+
+                pub fn with_billboard(mut self, mode: BillboardMode) -> Self {
+                    self.display_billboard = display::Billboard::new(mode);
+                    self
+                }
+
+                /// Sets the number of ticks changes to the transformation are
+                /// interpolated over.
+                pub fn with_interpolation_duration(mut self, ticks: i32) -> Self {
+                    self.display_interpolation_duration = display::InterpolationDuration(ticks);
+                    self
+                }
+
+                pub fn with_brightness(mut self, block_light: u8, sky_light: u8) -> Self {
+                    self.display_brightness = display::Brightness::new(block_light, sky_light);
+                    self
+                }
+
+                /// Sets the distance the display is rendered at, as a multiple
+                /// of the entity render distance of the client.
+                pub fn with_view_range(mut self, view_range: f32) -> Self {
+                    self.display_view_range = display::ViewRange(view_range);
+                    self
+                }
+
+                pub fn with_shadow(mut self, radius: f32, strength: f32) -> Self {
+                    self.display_shadow_radius = display::ShadowRadius(radius);
+                    self.display_shadow_strength = display::ShadowStrength(strength);
+                    self
+                }
+
+                /// Sets the glow color, which is only visible if the display
+                /// is glowing.
+                pub fn with_glow_color(mut self, rgb: u32) -> Self {
+                    self.display_glow_color_override = display::GlowColorOverride::new(rgb);
+                    self
+                }
+            }
+        )*
+    }
+}
+
+display_bundle_methods!(
+    text_display::TextDisplayEntityBundle,
+    item_display::ItemDisplayEntityBundle,
+    block_display::BlockDisplayEntityBundle,
+);
+
+impl text_display::TextDisplayEntityBundle {
+    pub fn with_text<'a>(mut self, text: impl IntoText<'a>) -> Self {
+        self.text_display_text = text_display::Text(text.into_text());
+        self
+    }
+
+    /// Sets the width in pixels lines are wrapped at.
+    pub fn with_line_width(mut self, width: i32) -> Self {
+        self.text_display_line_width = text_display::LineWidth(width);
+        self
+    }
+
+    /// Sets the background color, packed as `0xAARRGGBB`.
+    pub fn with_background(mut self, argb: u32) -> Self {
+        self.text_display_background = text_display::Background::from_argb(argb);
+        self
+    }
+
+    pub fn with_text_opacity(mut self, opacity: u8) -> Self {
+        self.text_display_text_opacity = text_display::TextOpacity(opacity as i8);
+        self
+    }
+
+    pub fn with_text_shadow(mut self, shadow: bool) -> Self {
+        self.text_display_text_display_flags.set_shadow(shadow);
+        self
+    }
+
+    /// Makes the text visible through blocks.
+    pub fn with_see_through(mut self, see_through: bool) -> Self {
+        self.text_display_text_display_flags
+            .set_see_through(see_through);
+        self
+    }
+
+    pub fn with_alignment(mut self, alignment: TextAlignment) -> Self {
+        self.text_display_text_display_flags
+            .set_alignment(alignment);
+        self
+    }
+}
+
+impl item_display::ItemDisplayEntityBundle {
+    pub fn with_item(mut self, item: ItemStack) -> Self {
+        self.item_display_item = item_display::Item(item);
+        self
+    }
+
+    pub fn with_item_transform(mut self, transform: ItemDisplayTransform) -> Self {
+        self.item_display_item_display = item_display::ItemDisplay::new(transform);
+        self
+    }
+}
+
+impl block_display::BlockDisplayEntityBundle {
+    pub fn with_block(mut self, block: BlockState) -> Self {
+        self.block_display_block_state = block_display::BlockState(block);
+        self
+    }
+}
+
+/// Restarts the interpolation of displays whose transformation changed, since
+/// clients only interpolate when the start of the interpolation is sent.
+pub(crate) fn restart_display_interpolation(
+    mut displays: Query<
+        (
+            &display::InterpolationDuration,
+            &mut display::StartInterpolation,
+        ),
+        (
+            Or<(
+                Changed<display::Translation>,
+                Changed<display::Scale>,
+                Changed<display::LeftRotation>,
+                Changed<display::RightRotation>,
+            )>,
+            Without<Despawned>,
+        ),
+    >,
+) {
+    for (duration, mut start) in &mut displays {
+        if duration.0 > 0 && !start.is_added() {
+            start.set_changed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_values() {
+        assert_eq!(display::Brightness::new(15, 7).get(), Some((15, 7)));
+        assert_eq!(display::Brightness::from_world().get(), None);
+        assert_eq!(display::Brightness::default().get(), None);
+
+        let mut flags = text_display::TextDisplayFlags(0);
+        flags.set_see_through(true);
+        flags.set_alignment(TextAlignment::Right);
+        assert_eq!(flags.alignment(), TextAlignment::Right);
+        assert!(flags.see_through());
+        flags.set_alignment(TextAlignment::Center);
+        assert_eq!(flags.0, 0b10);
+
+        let billboard = display::Billboard::new(BillboardMode::Center);
+        assert_eq!(billboard.mode(), BillboardMode::Center);
+
+        let background = text_display::Background::from_argb(0x80_11_22_33);
+        assert_eq!(background.argb(), 0x80_11_22_33);
+    }
+}
@@ -123,6 +123,18 @@ flags! {
     spider::SpiderFlags {
         climbing_wall: 0,
     }
+    text_display::TextDisplayFlags {
+        shadow: 0,
+        see_through: 1,
+        default_background: 2,
+    }
+}
+
+impl player::PlayerModelParts {
+    /// Shows the cape and every outer layer of the skin. Clients send the
+    /// parts they show for themselves, but other player entities show none
+    /// by default.
+    pub const ALL: Self = Self(0x7f);
 }
 
 #[cfg(test)]
@@ -56,6 +56,7 @@ impl Plugin for HitboxPlugin {
                     update_slime_hitbox,
                     update_painting_hitbox,
                     update_shulker_hitbox,
+                    update_interaction_hitbox,
                 ),
             )
             .configure_sets(PostUpdate, HitboxComponentsAddSet)
@@ -64,7 +65,11 @@ impl Plugin for HitboxPlugin {
                 add_hitbox_component.in_set(HitboxComponentsAddSet),
             )
             .configure_sets(PreUpdate, HitboxUpdateSet.after(HitboxShapeUpdateSet))
-            .add_systems(PreUpdate, update_hitbox.in_set(HitboxUpdateSet));
+            .add_systems(PreUpdate, update_hitbox.in_set(HitboxUpdateSet))
+            // Entities moved during `Update` get their hitbox moved along before
+            // the end of the tick.
+            .configure_sets(PostUpdate, HitboxUpdateSet.after(HitboxComponentsAddSet))
+            .add_systems(PostUpdate, update_hitbox.in_set(HitboxUpdateSet));
     }
 }
 
@@ -253,6 +258,22 @@ fn update_area_effect_cloud_hitbox(
     }
 }
 
+fn update_interaction_hitbox(
+    mut query: Query<
+        (&mut HitboxShape, &interaction::Width, &interaction::Height),
+        Or<(
+            Changed<interaction::Width>,
+            Changed<interaction::Height>,
+            Added<HitboxShape>,
+        )>,
+    >,
+) {
+    for (mut hitbox, width, height) in query.iter_mut() {
+        let width = f64::from(width.0);
+        hitbox.centered([width, f64::from(height.0), width].into());
+    }
+}
+
 fn update_armor_stand_hitbox(
     mut query: Query<
         (&mut HitboxShape, &armor_stand::ArmorStandFlags),
@@ -396,6 +417,17 @@ fn update_player_hitbox(
     }
 }
 
+/// Returns the height of the eyes of a player in the given pose above its
+/// position. Reach and line of sight are measured from this point.
+pub fn player_eye_height(pose: Pose) -> f64 {
+    match pose {
+        Pose::Sleeping | Pose::Dying => 0.2,
+        Pose::FallFlying | Pose::Swimming | Pose::SpinAttack => 0.4,
+        Pose::Sneaking => 1.27,
+        _ => 1.62,
+    }
+}
+
 fn update_item_frame_hitbox(
     mut query: Query<
         (&mut HitboxShape, &item_frame::Rotation),
@@ -0,0 +1,52 @@
+//! Builder methods for spawning interaction entities.
+//!
+//! Interaction entities are invisible volumes which clients can click. They
+//! have no model, but clients target them like any other entity, so clicks
+//! are reported as regular `InteractEntityEvent`s by `valence_server`. This
+//! makes them useful for clickable holograms, shop counters, and buttons on
+//! custom models.
+//!
+//! The volume is [`Width`](interaction::Width) wide on both horizontal axes,
+//! centered on the entity's position, and extends
+//! [`Height`](interaction::Height) upwards from it. The entity's
+//! [`Hitbox`](crate::hitbox::Hitbox) matches the volume.
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_entity::interaction::InteractionEntityBundle;
+//!
+//! let button = InteractionEntityBundle::default()
+//!     .with_position([0.5, 64.0, 0.5])
+//!     .with_size(1.0, 2.0)
+//!     .with_response(true);
+//! # let _ = button;
+//! ```
+
+use super::*;
+
+impl interaction::InteractionEntityBundle {
+    pub fn with_layer(mut self, layer: Entity) -> Self {
+        self.layer = EntityLayerId(layer);
+        self
+    }
+
+    pub fn with_position(mut self, position: impl Into<DVec3>) -> Self {
+        self.position = Position(position.into());
+        self
+    }
+
+    /// Sets the width and height of the clickable volume, in blocks.
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.interaction_width = interaction::Width(width);
+        self.interaction_height = interaction::Height(height);
+        self
+    }
+
+    /// Sets whether clicking the entity makes the client swing its arm, as if
+    /// the click did something.
+    pub fn with_response(mut self, response: bool) -> Self {
+        self.interaction_response = interaction::Response(response);
+        self
+    }
+}
@@ -20,8 +20,10 @@
 
 pub mod active_status_effects;
 pub mod attributes;
+pub mod display_builder;
 mod flags;
 pub mod hitbox;
+pub mod interaction_builder;
 pub mod manager;
 pub mod query;
 pub mod tracked_data;
@@ -88,6 +90,12 @@ impl Plugin for EntityPlugin {
                     .chain()
                     .in_set(InitEntitiesSet),
             )
+            .add_systems(
+                PostUpdate,
+                display_builder::restart_display_interpolation
+                    .after(InitEntitiesSet)
+                    .before(UpdateTrackedDataSet),
+            )
             .add_systems(
                 PostUpdate,
                 (
@@ -393,7 +401,7 @@ impl EntityAnimations {
 /// - **Falling Block**: Block state
 /// - **Fishing Bobber**: Hook entity ID
 /// - **Warden**: Initial pose
-#[derive(Component, Default, Debug, Deref, DerefMut)]
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug, Deref, DerefMut)]
 pub struct ObjectData(pub i32);
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -6,7 +6,7 @@ use valence_protocol::Encode;
 /// [`EntityTrackerUpdateS2c`][packet] packet.
 ///
 /// [packet]: valence_protocol::packets::play::EntityTrackerUpdateS2c
-#[derive(Component, Default, Debug)]
+#[derive(Component, Clone, Default, Debug)]
 pub struct TrackedData {
     init_data: Vec<u8>,
     /// A map of tracked data indices to the byte length of the entry in
@@ -0,0 +1,202 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use bevy_ecs::prelude::*;
+use valence_server::client::{Client, Username, VisibleEntityLayers};
+use valence_server::entity::entity::Flags;
+use valence_server::entity::EntityLayerId;
+use valence_server::protocol::packets::play::team_s2c::{
+    CollisionRule, Mode, NameTagVisibility, TeamColor, TeamFlags,
+};
+use valence_server::protocol::packets::play::TeamS2c;
+use valence_server::protocol::WritePacket;
+use valence_server::{Text, UniqueId};
+
+/// Makes an entity glow, showing its outline through blocks.
+///
+/// The outline has the [`color`](Self::color) of the entity for all clients,
+/// unless a different color is set for a client with
+/// [`set_viewer_color`](Self::set_viewer_color). Colors other than white are
+/// shown by putting the entity on a team only known to each client.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct Glowing {
+    /// The color of the outline.
+    ///
+    /// # Default Value
+    ///
+    /// [`TeamColor::White`]
+    pub color: TeamColor,
+    viewer_colors: HashMap<Entity, TeamColor>,
+}
+
+impl Glowing {
+    pub fn new(color: TeamColor) -> Self {
+        Self {
+            color,
+            viewer_colors: HashMap::new(),
+        }
+    }
+
+    pub fn with_viewer_color(mut self, viewer: Entity, color: TeamColor) -> Self {
+        self.set_viewer_color(viewer, color);
+        self
+    }
+
+    /// Sets the color of the outline seen by the client `viewer`.
+    pub fn set_viewer_color(&mut self, viewer: Entity, color: TeamColor) {
+        self.viewer_colors.insert(viewer, color);
+    }
+
+    /// Makes the client `viewer` see the [`color`](Self::color) of the
+    /// entity again. Returns the color the client saw before.
+    pub fn remove_viewer_color(&mut self, viewer: Entity) -> Option<TeamColor> {
+        self.viewer_colors.remove(&viewer)
+    }
+
+    /// Returns the color of the outline seen by the client `viewer`.
+    pub fn color_for(&self, viewer: Entity) -> TeamColor {
+        self.viewer_colors
+            .get(&viewer)
+            .copied()
+            .unwrap_or(self.color)
+    }
+}
+
+impl Default for Glowing {
+    fn default() -> Self {
+        Self::new(TeamColor::White)
+    }
+}
+
+pub(crate) fn update_glowing_flags(mut entities: Query<&mut Flags, With<Glowing>>) {
+    for mut flags in &mut entities {
+        // Check first to avoid triggering change detection.
+        if !flags.glowing() {
+            flags.set_glowing(true);
+        }
+    }
+}
+
+pub(crate) fn remove_glowing_flags(
+    mut removed: RemovedComponents<Glowing>,
+    mut entities: Query<&mut Flags, Without<Glowing>>,
+) {
+    for entity in removed.read() {
+        if let Ok(mut flags) = entities.get_mut(entity) {
+            flags.set_glowing(false);
+        }
+    }
+}
+
+/// The glow teams a client knows about.
+#[derive(Component, Default, Debug)]
+pub(crate) struct GlowTeams {
+    /// Bit `n` is set if the team for the color `n` was created.
+    created: u32,
+    /// The team color of each entity on a team, by name.
+    members: HashMap<String, TeamColor>,
+}
+
+pub(crate) fn init_glow_teams(
+    clients: Query<Entity, (With<Client>, Without<GlowTeams>)>,
+    mut commands: Commands,
+) {
+    for entity in &clients {
+        commands.entity(entity).insert(GlowTeams::default());
+    }
+}
+
+pub(crate) fn update_glow_teams(
+    mut clients: Query<(Entity, &mut Client, &mut GlowTeams, &VisibleEntityLayers)>,
+    glowing: Query<(
+        Entity,
+        &Glowing,
+        &EntityLayerId,
+        &UniqueId,
+        Option<&Username>,
+    )>,
+) {
+    for (client_entity, mut client, mut teams, visible) in &mut clients {
+        if glowing.is_empty() && teams.members.is_empty() {
+            continue;
+        }
+
+        let mut wanted = HashMap::new();
+
+        for (entity, glow, layer, uuid, username) in &glowing {
+            let color = glow.color_for(client_entity);
+
+            // White is the color of entities without a team.
+            if entity == client_entity || color == TeamColor::White || !visible.0.contains(&layer.0)
+            {
+                continue;
+            }
+
+            let name = match username {
+                Some(username) => username.0.clone(),
+                None => uuid.0.to_string(),
+            };
+
+            wanted.insert(name, color);
+        }
+
+        if wanted == teams.members {
+            continue;
+        }
+
+        let mut removed: BTreeMap<u8, Vec<&str>> = BTreeMap::new();
+        let mut added: BTreeMap<u8, Vec<&str>> = BTreeMap::new();
+
+        for (name, &color) in &teams.members {
+            if wanted.get(name) != Some(&color) {
+                removed.entry(color as u8).or_default().push(name);
+            }
+        }
+
+        for (name, &color) in &wanted {
+            if teams.members.get(name) != Some(&color) {
+                added.entry(color as u8).or_default().push(name);
+            }
+        }
+
+        // Entities must leave their old team before joining another.
+        for (color, entities) in removed {
+            client.write_packet(&TeamS2c {
+                team_name: &team_name(color),
+                mode: Mode::RemoveEntities { entities },
+            });
+        }
+
+        for (color, entities) in added {
+            let team_name = team_name(color);
+
+            let mode = if teams.created & (1 << color) == 0 {
+                teams.created |= 1 << color;
+
+                Mode::CreateTeam {
+                    team_display_name: Cow::Owned(Text::text(team_name.clone())),
+                    friendly_flags: TeamFlags::new(),
+                    name_tag_visibility: NameTagVisibility::Always,
+                    collision_rule: CollisionRule::Always,
+                    team_color: wanted[entities[0]],
+                    team_prefix: Cow::Owned(Text::default()),
+                    team_suffix: Cow::Owned(Text::default()),
+                    entities,
+                }
+            } else {
+                Mode::AddEntities { entities }
+            };
+
+            client.write_packet(&TeamS2c {
+                team_name: &team_name,
+                mode,
+            });
+        }
+
+        teams.members = wanted;
+    }
+}
+
+fn team_name(color: u8) -> String {
+    format!("glow_{color}")
+}
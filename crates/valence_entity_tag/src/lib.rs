@@ -0,0 +1,220 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod glow;
+
+use std::collections::{BTreeSet, HashMap};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use glow::Glowing;
+use valence_server::client::UpdateClientsSet;
+use valence_server::entity::entity::{CustomName, NameVisible};
+use valence_server::layer::UpdateLayersPreClientSet;
+pub use valence_server::protocol::packets::play::team_s2c::TeamColor;
+use valence_server::Text;
+
+pub struct EntityTagPlugin;
+
+impl Plugin for EntityTagPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TagIndex>().add_systems(
+            PostUpdate,
+            (
+                (
+                    update_name_tags,
+                    remove_name_tags,
+                    glow::update_glowing_flags,
+                    glow::remove_glowing_flags,
+                )
+                    .before(UpdateLayersPreClientSet),
+                (
+                    glow::init_glow_teams,
+                    apply_deferred,
+                    glow::update_glow_teams,
+                )
+                    .chain()
+                    .in_set(UpdateClientsSet),
+                update_tag_index,
+            ),
+        );
+    }
+}
+
+/// A custom name shown above an entity, even when it isn't looked at.
+///
+/// Removing the component removes the name. Clients don't show custom names of
+/// players, whose name tags always show their username.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct NameTag(pub Text);
+
+impl NameTag {
+    pub fn new(name: impl Into<Text>) -> Self {
+        Self(name.into())
+    }
+}
+
+fn update_name_tags(
+    mut entities: Query<(&NameTag, &mut CustomName, &mut NameVisible), Changed<NameTag>>,
+) {
+    for (tag, mut name, mut visible) in &mut entities {
+        name.0 = Some(tag.0.clone());
+        visible.set_if_neq(NameVisible(true));
+    }
+}
+
+fn remove_name_tags(
+    mut removed: RemovedComponents<NameTag>,
+    mut entities: Query<(&mut CustomName, &mut NameVisible), Without<NameTag>>,
+) {
+    for entity in removed.read() {
+        if let Ok((mut name, mut visible)) = entities.get_mut(entity) {
+            name.0 = None;
+            visible.set_if_neq(NameVisible(false));
+        }
+    }
+}
+
+/// Marker tags of an entity, like those added with the vanilla `/tag`
+/// command. Use [`TagIndex`] to find the entities with a tag.
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Tags(BTreeSet<String>);
+
+impl Tags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, tag: impl Into<String>) -> Self {
+        self.insert(tag);
+        self
+    }
+
+    /// Adds a tag. Returns whether the tag was new.
+    pub fn insert(&mut self, tag: impl Into<String>) -> bool {
+        self.0.insert(tag.into())
+    }
+
+    /// Removes a tag. Returns whether the tag was present.
+    pub fn remove(&mut self, tag: &str) -> bool {
+        self.0.remove(tag)
+    }
+
+    pub fn contains(&self, tag: &str) -> bool {
+        self.0.contains(tag)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The entities with each of the [`Tags`]. Updated at the end of every tick,
+/// so changes to tags are reflected in the next tick.
+#[derive(Resource, Default, Debug)]
+pub struct TagIndex {
+    entities: HashMap<String, BTreeSet<Entity>>,
+    tags: HashMap<Entity, BTreeSet<String>>,
+}
+
+impl TagIndex {
+    /// Returns an iterator over the entities with `tag`.
+    pub fn entities_with<'a>(&'a self, tag: &str) -> impl Iterator<Item = Entity> + 'a {
+        self.entities.get(tag).into_iter().flatten().copied()
+    }
+
+    /// Returns the number of entities with `tag`.
+    pub fn count(&self, tag: &str) -> usize {
+        self.entities.get(tag).map_or(0, BTreeSet::len)
+    }
+
+    fn set(&mut self, entity: Entity, tags: Option<&BTreeSet<String>>) {
+        let old = self.tags.remove(&entity).unwrap_or_default();
+
+        for tag in &old {
+            if let Some(entities) = self.entities.get_mut(tag) {
+                entities.remove(&entity);
+
+                if entities.is_empty() {
+                    self.entities.remove(tag);
+                }
+            }
+        }
+
+        let Some(tags) = tags.filter(|tags| !tags.is_empty()) else {
+            return;
+        };
+
+        for tag in tags {
+            self.entities.entry(tag.clone()).or_default().insert(entity);
+        }
+
+        self.tags.insert(entity, tags.clone());
+    }
+}
+
+fn update_tag_index(
+    tags: Query<(Entity, &Tags), Changed<Tags>>,
+    mut removed: RemovedComponents<Tags>,
+    mut index: ResMut<TagIndex>,
+) {
+    for entity in removed.read() {
+        index.set(entity, None);
+    }
+
+    for (entity, tags) in &tags {
+        index.set(entity, Some(&tags.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_index() {
+        let mut world = World::new();
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+
+        let mut index = TagIndex::default();
+
+        index.set(a, Some(&Tags::new().with("red").with("alive").0));
+        index.set(b, Some(&Tags::new().with("red").0));
+
+        assert_eq!(index.count("red"), 2);
+        assert_eq!(index.entities_with("alive").collect::<Vec<_>>(), [a]);
+
+        index.set(a, Some(&Tags::new().with("blue").0));
+        index.set(b, None);
+
+        assert_eq!(index.count("red"), 0);
+        assert_eq!(index.count("alive"), 0);
+        assert_eq!(index.entities_with("blue").collect::<Vec<_>>(), [a]);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use heck::{ToPascalCase, ToShoutySnakeCase};
 use proc_macro2::TokenStream;
@@ -70,7 +70,10 @@ struct Shape {
 }
 
 pub fn build() -> anyhow::Result<TokenStream> {
-    rerun_if_changed(["extracted/blocks.json"]);
+    rerun_if_changed([
+        "extracted/blocks.json",
+        "../valence_registry/extracted/tags.json",
+    ]);
 
     let TopLevel {
         blocks,
@@ -78,6 +81,13 @@ pub fn build() -> anyhow::Result<TokenStream> {
         block_entity_types,
     } = serde_json::from_str(include_str!("../extracted/blocks.json"))?;
 
+    let mut tags: BTreeMap<String, BTreeMap<String, Vec<u16>>> =
+        serde_json::from_str(include_str!("../../valence_registry/extracted/tags.json"))?;
+
+    let block_tags = tags
+        .remove("minecraft:block")
+        .expect("tags.json must have block tags");
+
     let max_state_id = blocks.iter().map(|b| b.max_state_id()).max().unwrap();
 
     let kind_to_translation_key_arms = blocks
@@ -577,6 +587,86 @@ pub fn build() -> anyhow::Result<TokenStream> {
 
     let prop_value_count = prop_values.len();
 
+    let kind_to_state_range_arms = blocks
+        .iter()
+        .map(|b| {
+            let kind = ident(b.name.to_pascal_case());
+            let min_id = b.min_state_id();
+            let max_id = b.max_state_id();
+            quote! {
+                Self::#kind => #min_id..=#max_id,
+            }
+        })
+        .collect::<TokenStream>();
+
+    // Blocks with the same values for a property share a match arm.
+    let mut kinds_by_prop_values = BTreeMap::<(&str, &[String]), Vec<_>>::new();
+
+    for b in &blocks {
+        for p in &b.properties {
+            kinds_by_prop_values
+                .entry((&p.name, &p.values))
+                .or_default()
+                .push(ident(b.name.to_pascal_case()));
+        }
+    }
+
+    let kind_prop_values_arms = kinds_by_prop_values
+        .iter()
+        .map(|((name, values), kinds)| {
+            let name = ident(name.to_pascal_case());
+            let values = values.iter().map(|v| ident(v.to_pascal_case()));
+            quote! {
+                (#(Self::#kinds)|*, PropName::#name) => &[#(PropValue::#values,)*],
+            }
+        })
+        .collect::<TokenStream>();
+
+    let mut block_tag_names = vec![];
+
+    let block_tag_consts = block_tags
+        .iter()
+        .map(|(tag, ids)| {
+            let (namespace, path) = tag.split_once(':').expect("tags must have a namespace");
+
+            let const_name = if namespace == "minecraft" {
+                path.replace('/', "_").to_shouty_snake_case()
+            } else {
+                format!("{namespace}_{}", path.replace('/', "_")).to_shouty_snake_case()
+            };
+
+            assert!(
+                !block_tag_names.contains(&const_name),
+                "duplicate block tag constant {const_name}"
+            );
+
+            block_tag_names.push(const_name.clone());
+
+            let const_name = ident(const_name);
+
+            let mut ids = ids.clone();
+            ids.sort_unstable();
+            ids.dedup();
+
+            let kinds = ids
+                .iter()
+                .map(|&id| ident(blocks[id as usize].name.to_pascal_case()));
+
+            let doc = format!("The block tag `#{tag}`.");
+
+            quote! {
+                #[doc = #doc]
+                pub const #const_name: Self = Self {
+                    name: #tag,
+                    blocks: &[#(BlockKind::#kinds,)*],
+                };
+            }
+        })
+        .collect::<TokenStream>();
+
+    let block_tag_all = block_tag_names.iter().map(ident);
+    let block_tag_count = block_tag_names.len();
+
     Ok(quote! {
         use valence_math::{Aabb, DVec3};
 
@@ -764,6 +854,24 @@ pub fn build() -> anyhow::Result<TokenStream> {
                 }
             }
 
+            /// Returns the possible values of the property `name` for this block kind,
+            /// or an empty slice if it doesn't have the property.
+            pub const fn prop_values(self, name: PropName) -> &'static [PropValue] {
+                match (self, name) {
+                    #kind_prop_values_arms
+                    _ => &[],
+                }
+            }
+
+            /// Returns an iterator over all block states of this block kind.
+            pub fn states(self) -> impl DoubleEndedIterator<Item = BlockState> + ExactSizeIterator + FusedIterator + Clone {
+                let range = match self {
+                    #kind_to_state_range_arms
+                };
+
+                range.map(BlockState)
+            }
+
             pub const fn translation_key(self) -> &'static str {
                 match self {
                     #kind_to_translation_key_arms
@@ -820,6 +928,22 @@ pub fn build() -> anyhow::Result<TokenStream> {
             pub const ALL: [Self; #block_kind_count] = [#(Self::#block_kind_variants,)*];
         }
 
+        /// A block tag, like `#minecraft:logs`, with the blocks which are in it in
+        /// vanilla.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub struct BlockTag {
+            name: &'static str,
+            /// Sorted by [`BlockKind::to_raw`].
+            blocks: &'static [BlockKind],
+        }
+
+        impl BlockTag {
+            #block_tag_consts
+
+            /// An array of all block tags, sorted by name.
+            pub const ALL: [Self; #block_tag_count] = [#(Self::#block_tag_all,)*];
+        }
+
         /// The default block kind is `air`.
         impl Default for BlockKind {
             fn default() -> Self {
@@ -10,6 +10,96 @@ use crate::item::ItemKind;
 
 include!(concat!(env!("OUT_DIR"), "/block.rs"));
 
+impl BlockState {
+    /// Returns an iterator over the properties of this block state and their
+    /// values.
+    pub fn props(
+        self,
+    ) -> impl ExactSizeIterator<Item = (PropName, PropValue)> + FusedIterator + Clone {
+        self.to_kind()
+            .props()
+            .iter()
+            .map(move |&name| (name, self.get(name).unwrap()))
+    }
+
+    /// Sets a property by its name and value, like `with_property("facing",
+    /// "north")`, returning the modified block.
+    ///
+    /// If this block does not have the property or the value is invalid for
+    /// it, then `None` is returned.
+    pub fn with_property(self, name: &str, value: &str) -> Option<Self> {
+        let name = PropName::from_str(name)?;
+        let value = PropValue::from_str(value)?;
+
+        if self.to_kind().prop_values(name).contains(&value) {
+            Some(self.set(name, value))
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the kind of this block state is in the block tag.
+    pub fn is_in(self, tag: BlockTag) -> bool {
+        tag.contains(self.to_kind())
+    }
+}
+
+impl BlockKind {
+    /// Returns whether this block kind is in the block tag.
+    pub fn is_in(self, tag: BlockTag) -> bool {
+        tag.contains(self)
+    }
+
+    /// Returns an iterator over the block tags this block kind is in.
+    pub fn tags(self) -> impl Iterator<Item = BlockTag> {
+        BlockTag::ALL
+            .into_iter()
+            .filter(move |tag| tag.contains(self))
+    }
+}
+
+impl BlockTag {
+    /// Constructs a block tag from its name, like `#minecraft:logs`. The `#`
+    /// and the `minecraft` namespace may be left out.
+    ///
+    /// Returns `None` if the name is invalid.
+    pub fn from_str(name: &str) -> Option<Self> {
+        let name = name.strip_prefix('#').unwrap_or(name);
+
+        let index = if name.contains(':') {
+            BlockTag::ALL.binary_search_by(|tag| tag.name.cmp(name))
+        } else {
+            BlockTag::ALL.binary_search_by(|tag| {
+                let (namespace, path) = tag.name.split_once(':').unwrap();
+                namespace.cmp("minecraft").then(path.cmp(name))
+            })
+        };
+
+        index.ok().map(|i| BlockTag::ALL[i])
+    }
+
+    /// Returns the name of this block tag, like `minecraft:logs`.
+    pub const fn name(self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the block kinds in this block tag.
+    pub const fn blocks(self) -> &'static [BlockKind] {
+        self.blocks
+    }
+
+    /// Returns whether the block kind is in this block tag.
+    pub fn contains(self, kind: BlockKind) -> bool {
+        self.blocks.binary_search(&kind).is_ok()
+    }
+}
+
+impl fmt::Display for BlockTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.name)
+    }
+}
+
 impl fmt::Debug for BlockState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt_block_state(*self, f)
@@ -80,4 +170,69 @@ mod tests {
             Some(BlockState::GREEN_BANNER)
         );
     }
+
+    #[test]
+    fn set_props_by_name() {
+        let stairs = BlockState::OAK_STAIRS;
+
+        let north = stairs.with_property("facing", "north").unwrap();
+        assert_eq!(north.get(PropName::Facing), Some(PropValue::North));
+
+        let east = north.with_property("facing", "east").unwrap();
+        assert_eq!(east.get(PropName::Facing), Some(PropValue::East));
+        assert_eq!(east.get(PropName::Half), stairs.get(PropName::Half));
+
+        assert_eq!(stairs.with_property("facing", "up"), None);
+        assert_eq!(stairs.with_property("power", "1"), None);
+        assert_eq!(stairs.with_property("facing", "nowhere"), None);
+
+        assert_eq!(
+            BlockState::REDSTONE_WIRE.with_property("power", "15"),
+            Some(BlockState::REDSTONE_WIRE.set(PropName::Power, PropValue::_15))
+        );
+    }
+
+    #[test]
+    fn enumerate_states() {
+        for kind in BlockKind::ALL {
+            let count = kind
+                .props()
+                .iter()
+                .map(|&name| kind.prop_values(name).len())
+                .product::<usize>();
+
+            assert_eq!(kind.states().len(), count, "{kind:?}");
+            assert!(kind.states().all(|state| state.to_kind() == kind));
+            assert!(kind.states().any(|state| state == kind.to_state()));
+        }
+
+        let props = BlockState::OAK_STAIRS.props().collect::<Vec<_>>();
+        assert_eq!(props.len(), BlockKind::OakStairs.props().len());
+        assert!(props.contains(&(PropName::Facing, PropValue::North)));
+    }
+
+    #[test]
+    fn block_tags() {
+        assert!(BlockKind::OakLog.is_in(BlockTag::LOGS));
+        assert!(!BlockKind::Stone.is_in(BlockTag::LOGS));
+        assert!(BlockState::STONE.is_in(BlockTag::MINEABLE_PICKAXE));
+        assert!(BlockKind::Chest.is_in(BlockTag::C_CHESTS));
+
+        assert_eq!(BlockTag::from_str("#minecraft:logs"), Some(BlockTag::LOGS));
+        assert_eq!(BlockTag::from_str("minecraft:logs"), Some(BlockTag::LOGS));
+        assert_eq!(
+            BlockTag::from_str("mineable/pickaxe"),
+            Some(BlockTag::MINEABLE_PICKAXE)
+        );
+        assert_eq!(BlockTag::from_str("c:chests"), Some(BlockTag::C_CHESTS));
+        assert_eq!(BlockTag::from_str("chests"), None);
+        assert_eq!(BlockTag::from_str("minecraft:nope"), None);
+
+        assert_eq!(BlockTag::LOGS.to_string(), "#minecraft:logs");
+        assert!(BlockKind::OakLog.tags().any(|tag| tag == BlockTag::LOGS));
+
+        for tag in BlockTag::ALL {
+            assert_eq!(BlockTag::from_str(tag.name()), Some(tag));
+        }
+    }
 }
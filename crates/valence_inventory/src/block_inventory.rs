@@ -0,0 +1,473 @@
+//! Inventories of container blocks, like chests, barrels, and shulker boxes.
+//!
+//! The [`OpenBlockInventory`] command opens the inventory of the container
+//! block at a position in the client's [`VisibleChunkLayer`]. The inventory
+//! is an entity with an [`Inventory`] and a [`BlockInventory`] component,
+//! which is spawned the first time the block is opened and shared by every
+//! client opening the same block. The halves of a double chest share a
+//! single inventory.
+//!
+//! While a container is opened by at least one client, its lid is open and
+//! the vanilla open and close sounds are played. If the block is replaced, or
+//! a chest is connected to or disconnected from another chest, the inventory
+//! is despawned and the clients viewing it have their screens closed.
+//!
+//! The items of the inventory are loaded from the `Items` of the block
+//! entity when the inventory is spawned, and written back to the block
+//! entity whenever they change. This keeps the contents of placed shulker
+//! boxes when they are broken with [`item_from_block`].
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_inventory::block_inventory::OpenBlockInventory;
+//! use valence_server::interact_block::InteractBlockEvent;
+//!
+//! fn open_containers(mut events: EventReader<InteractBlockEvent>, mut commands: Commands) {
+//!     for event in events.read() {
+//!         commands.add(OpenBlockInventory {
+//!             client: event.client,
+//!             position: event.position,
+//!         });
+//!     }
+//! }
+//! # let _ = open_containers;
+//! ```
+
+use std::collections::HashMap;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::client::VisibleChunkLayer;
+use valence_server::layer::chunk::Block;
+use valence_server::layer::UpdateLayersPreClientSet;
+use valence_server::math::DVec3;
+use valence_server::nbt::{Compound, Value};
+use valence_server::protocol::packets::play::BlockEventS2c;
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::protocol::WritePacket;
+use valence_server::rand::Rng;
+use valence_server::{
+    BlockPos, BlockState, ChunkLayer, Despawned, Direction, ItemKind, ItemStack, Layer, Text,
+};
+
+use crate::contents::ContainerContents;
+use crate::{Inventory, InventoryKind, OpenInventory};
+
+pub(super) fn build(app: &mut App) {
+    app.init_resource::<BlockInventories>().add_systems(
+        PostUpdate,
+        update_block_inventories.before(UpdateLayersPreClientSet),
+    );
+}
+
+/// A [`Command`] to open the inventory of the container block at `position`
+/// for a client. Does nothing if the block is not a container.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct OpenBlockInventory {
+    pub client: Entity,
+    pub position: BlockPos,
+}
+
+impl Command for OpenBlockInventory {
+    fn apply(self, world: &mut World) {
+        let Some(layer) = world.get::<VisibleChunkLayer>(self.client).map(|l| l.0) else {
+            return;
+        };
+
+        let Some(inventory) = block_inventory(world, layer, self.position) else {
+            return;
+        };
+
+        if let Some(mut client) = world.get_entity_mut(self.client) {
+            client.insert(OpenInventory::new(inventory));
+        }
+    }
+}
+
+/// Component for inventory entities belonging to a container block. Spawned
+/// by [`OpenBlockInventory`].
+#[derive(Component, Clone, Debug)]
+pub struct BlockInventory {
+    layer: Entity,
+    container: Container,
+    viewers: Vec<Entity>,
+    /// The contents last written to the block entity of each position.
+    synced: Vec<ContainerContents>,
+}
+
+impl BlockInventory {
+    /// The chunk layer containing the block.
+    pub fn layer(&self) -> Entity {
+        self.layer
+    }
+
+    /// The positions of the container. Double chests have two positions,
+    /// starting with the half holding the first 27 slots.
+    pub fn positions(&self) -> &[BlockPos] {
+        &self.container.positions
+    }
+
+    /// The clients viewing the inventory.
+    pub fn viewers(&self) -> &[Entity] {
+        &self.viewers
+    }
+}
+
+/// The inventory entities of the container blocks which have been opened,
+/// indexed by chunk layer and position.
+#[derive(Resource, Default, Debug)]
+pub struct BlockInventories {
+    map: HashMap<(Entity, BlockPos), Entity>,
+}
+
+impl BlockInventories {
+    /// Returns the inventory entity of the container at `position` in
+    /// `layer`, if it has been opened before.
+    pub fn get(&self, layer: Entity, position: impl Into<BlockPos>) -> Option<Entity> {
+        self.map.get(&(layer, position.into())).copied()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum ContainerKind {
+    Chest,
+    Barrel,
+    ShulkerBox,
+}
+
+/// A container block in a chunk layer.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Container {
+    kind: ContainerKind,
+    block: BlockKind,
+    positions: Vec<BlockPos>,
+}
+
+impl Container {
+    fn at(layer: &ChunkLayer, pos: BlockPos) -> Option<Self> {
+        let state = layer.block(pos)?.state;
+        let block = state.to_kind();
+
+        let kind = match block {
+            BlockKind::Chest | BlockKind::TrappedChest => ContainerKind::Chest,
+            BlockKind::Barrel => ContainerKind::Barrel,
+            _ if block.to_str().ends_with("shulker_box") => ContainerKind::ShulkerBox,
+            _ => return None,
+        };
+
+        let mut positions = vec![pos];
+
+        if let Some((other_pos, first)) = other_chest_half(state, pos) {
+            // The other half must be connected to this half.
+            let connected = layer.block(other_pos).is_some_and(|other| {
+                other.state.to_kind() == block
+                    && other_chest_half(other.state, other_pos).map(|(p, _)| p) == Some(pos)
+            });
+
+            if connected {
+                if first {
+                    positions.push(other_pos);
+                } else {
+                    positions.insert(0, other_pos);
+                }
+            }
+        }
+
+        Some(Self {
+            kind,
+            block,
+            positions,
+        })
+    }
+
+    fn inventory(&self) -> Inventory {
+        let (kind, key) = match self.kind {
+            ContainerKind::Chest if self.positions.len() == 2 => {
+                (InventoryKind::Generic9x6, "container.chestDouble")
+            }
+            ContainerKind::Chest => (InventoryKind::Generic9x3, "container.chest"),
+            ContainerKind::Barrel => (InventoryKind::Generic9x3, "container.barrel"),
+            ContainerKind::ShulkerBox => (InventoryKind::ShulkerBox, "container.shulkerBox"),
+        };
+
+        Inventory::with_title(kind, Text::translate(key, []))
+    }
+
+    fn center(&self) -> DVec3 {
+        let sum = self.positions.iter().fold(DVec3::ZERO, |sum, pos| {
+            sum + DVec3::new(pos.x.into(), pos.y.into(), pos.z.into())
+        });
+
+        sum / self.positions.len() as f64 + 0.5
+    }
+}
+
+/// Returns the position of the other half of a double chest, and whether the
+/// half at `pos` holds the first 27 slots.
+fn other_chest_half(state: BlockState, pos: BlockPos) -> Option<(BlockPos, bool)> {
+    let facing = match state.get(PropName::Facing)? {
+        PropValue::North => Direction::North,
+        PropValue::East => Direction::East,
+        PropValue::South => Direction::South,
+        PropValue::West => Direction::West,
+        _ => return None,
+    };
+
+    let clockwise = match facing {
+        Direction::North => Direction::East,
+        Direction::East => Direction::South,
+        Direction::South => Direction::West,
+        _ => Direction::North,
+    };
+
+    match state.get(PropName::Type)? {
+        PropValue::Left => Some((pos.get_in_direction(clockwise), false)),
+        PropValue::Right => Some((pos.get_in_direction(opposite(clockwise)), true)),
+        _ => None,
+    }
+}
+
+fn opposite(dir: Direction) -> Direction {
+    match dir {
+        Direction::Down => Direction::Up,
+        Direction::Up => Direction::Down,
+        Direction::North => Direction::South,
+        Direction::South => Direction::North,
+        Direction::West => Direction::East,
+        Direction::East => Direction::West,
+    }
+}
+
+/// Returns the inventory entity of the container at `pos`, spawning it if it
+/// doesn't exist yet.
+fn block_inventory(world: &mut World, layer: Entity, pos: BlockPos) -> Option<Entity> {
+    let container = Container::at(world.get::<ChunkLayer>(layer)?, pos)?;
+
+    let existing = world
+        .resource::<BlockInventories>()
+        .get(layer, container.positions[0]);
+
+    // The inventory could be outdated if the block changed this tick.
+    if let Some(entity) = existing {
+        let up_to_date = world
+            .get::<BlockInventory>(entity)
+            .is_some_and(|inv| inv.container == container);
+
+        if up_to_date && world.get::<Despawned>(entity).is_none() {
+            return Some(entity);
+        }
+    }
+
+    let mut inventory = container.inventory();
+
+    let synced: Vec<_> = container
+        .positions
+        .iter()
+        .map(|&pos| {
+            world
+                .get::<ChunkLayer>(layer)
+                .and_then(|l| l.block(pos)?.nbt)
+                .map(ContainerContents::from_block_entity)
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let half_len = inventory.slot_count() / container.positions.len() as u16;
+
+    for (half, contents) in synced.iter().enumerate() {
+        for (slot, stack) in contents.iter() {
+            if u16::from(slot) < half_len {
+                inventory.set_slot(half as u16 * half_len + u16::from(slot), stack.clone());
+            }
+        }
+    }
+
+    let entity = world
+        .spawn((
+            inventory,
+            BlockInventory {
+                layer,
+                container: container.clone(),
+                viewers: vec![],
+                synced,
+            },
+        ))
+        .id();
+
+    let mut inventories = world.resource_mut::<BlockInventories>();
+
+    for &pos in &container.positions {
+        inventories.map.insert((layer, pos), entity);
+    }
+
+    Some(entity)
+}
+
+fn update_block_inventories(
+    mut inventories: Query<(Entity, &mut BlockInventory, &Inventory, Has<Despawned>)>,
+    clients: Query<(Entity, &OpenInventory), Without<Despawned>>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut index: ResMut<BlockInventories>,
+    mut commands: Commands,
+) {
+    let mut viewers = HashMap::<Entity, Vec<Entity>>::new();
+
+    for (client, open) in &clients {
+        viewers.entry(open.entity).or_default().push(client);
+    }
+
+    for (entity, mut inv, inventory, despawned) in &mut inventories {
+        let mut layer = layers.get_mut(inv.layer).ok();
+
+        let valid = layer.as_ref().is_some_and(|l| {
+            Container::at(l, inv.container.positions[0]).as_ref() == Some(&inv.container)
+        });
+
+        if let (Some(layer), true, false) = (&mut layer, valid, despawned) {
+            sync_contents(layer, &mut inv, inventory);
+        }
+
+        let mut new_viewers = viewers.remove(&entity).unwrap_or_default();
+        new_viewers.sort_unstable();
+
+        if !valid || despawned {
+            new_viewers.clear();
+
+            for &pos in &inv.container.positions {
+                if index.map.get(&(inv.layer, pos)) == Some(&entity) {
+                    index.map.remove(&(inv.layer, pos));
+                }
+            }
+
+            if !despawned {
+                commands.entity(entity).insert(Despawned);
+            }
+        }
+
+        if new_viewers == inv.viewers {
+            continue;
+        }
+
+        // Don't animate a block which has been replaced.
+        if let (Some(layer), true) = (&mut layer, valid) {
+            animate(layer, &inv.container, inv.viewers.len(), new_viewers.len());
+        }
+
+        inv.viewers = new_viewers;
+    }
+}
+
+/// Writes the items of the inventory to the block entities of the container
+/// if they changed.
+fn sync_contents(layer: &mut ChunkLayer, inv: &mut BlockInventory, inventory: &Inventory) {
+    let half_len = inventory.slot_count() / inv.container.positions.len() as u16;
+
+    for (half, &pos) in inv.container.positions.iter().enumerate() {
+        let mut contents = ContainerContents::new();
+
+        for slot in 0..half_len {
+            contents.set(
+                slot as u8,
+                inventory.slot(half as u16 * half_len + slot).clone(),
+            );
+        }
+
+        if contents == inv.synced[half] {
+            continue;
+        }
+
+        if let Some(nbt) = layer.block_entity_mut(pos) {
+            contents.write_to_block_entity(nbt);
+        } else if let Some(block) = layer.block(pos) {
+            let mut nbt = Compound::new();
+            contents.write_to_block_entity(&mut nbt);
+
+            let state = block.state;
+            layer.set_block(pos, Block::new(state, Some(nbt)));
+        }
+
+        inv.synced[half] = contents;
+    }
+}
+
+/// Returns the item of the block at `position`, like the item dropped when
+/// the block is broken. Shulker boxes keep their contents in the item.
+pub fn item_from_block(layer: &ChunkLayer, position: impl Into<BlockPos>) -> Option<ItemStack> {
+    let block = layer.block(position)?;
+    let kind = block.state.to_kind().to_item_kind();
+
+    if kind == ItemKind::Air {
+        return None;
+    }
+
+    let mut stack = ItemStack::new(kind, 1, None);
+
+    if let Some(nbt) = block.nbt {
+        ContainerContents::from_block_entity(nbt).write_to_item(&mut stack);
+    }
+
+    Some(stack)
+}
+
+/// Returns the block entity data of the block placed by an item. Placed
+/// shulker boxes keep the contents of the item.
+pub fn block_entity_from_item(stack: &ItemStack) -> Option<Compound> {
+    match stack.nbt.as_ref()?.get("BlockEntityTag") {
+        Some(Value::Compound(tag)) => Some(tag.clone()),
+        _ => None,
+    }
+}
+
+/// Opens or closes the lid of a container and plays its sounds when the
+/// number of viewers changes.
+fn animate(layer: &mut ChunkLayer, container: &Container, old_viewers: usize, new_viewers: usize) {
+    let opened = old_viewers == 0 && new_viewers > 0;
+    let closed = old_viewers > 0 && new_viewers == 0;
+
+    match container.kind {
+        ContainerKind::Chest | ContainerKind::ShulkerBox => {
+            // The lid is open while the count is nonzero.
+            for &pos in &container.positions {
+                layer.view_writer(pos).write_packet(&BlockEventS2c {
+                    position: pos,
+                    action_id: 1,
+                    action_parameter: new_viewers.min(u8::MAX.into()) as u8,
+                    block_type: container.block,
+                });
+            }
+        }
+        ContainerKind::Barrel if opened || closed => {
+            let pos = container.positions[0];
+
+            if let Some(block) = layer.block(pos) {
+                let block = Block {
+                    state: block
+                        .state
+                        .set(PropName::Open, PropValue::from_bool(opened)),
+                    nbt: block.nbt.cloned(),
+                };
+
+                layer.set_block(pos, block);
+            }
+        }
+        ContainerKind::Barrel => {}
+    }
+
+    let sound = match (container.kind, opened, closed) {
+        (ContainerKind::Chest, true, _) => Sound::BlockChestOpen,
+        (ContainerKind::Chest, _, true) => Sound::BlockChestClose,
+        (ContainerKind::Barrel, true, _) => Sound::BlockBarrelOpen,
+        (ContainerKind::Barrel, _, true) => Sound::BlockBarrelClose,
+        (ContainerKind::ShulkerBox, true, _) => Sound::BlockShulkerBoxOpen,
+        (ContainerKind::ShulkerBox, _, true) => Sound::BlockShulkerBoxClose,
+        _ => return,
+    };
+
+    let pitch = valence_server::rand::thread_rng().gen_range(0.9..1.0);
+
+    layer.play_sound(sound, SoundCategory::Block, container.center(), 0.5, pitch);
+}
@@ -0,0 +1,128 @@
+//! Writing and signing books.
+//!
+//! When a client edits a book and quill in its hotbar or off hand, the pages
+//! are checked, written to the item, and sent as a [`BookEditEvent`]. Signing
+//! the book turns it into a written book with the client's username as the
+//! author.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::{Client, Username};
+use valence_server::event_loop::{EventLoopPreUpdate, PacketEvent};
+use valence_server::nbt::{Compound, List};
+use valence_server::protocol::packets::play::BookUpdateC2s;
+use valence_server::protocol_error::ProtocolErrorSink;
+use valence_server::{ItemKind, ItemStack, Text};
+
+use crate::player_inventory::PlayerInventory;
+use crate::Inventory;
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<BookEditEvent>()
+        .add_systems(EventLoopPreUpdate, handle_book_update);
+}
+
+/// The most pages of a book.
+pub const MAX_BOOK_PAGES: usize = 100;
+/// The most characters on a page of a book.
+pub const MAX_BOOK_PAGE_CHARS: usize = 1024;
+/// The most characters in the title of a written book.
+pub const MAX_BOOK_TITLE_CHARS: usize = 32;
+
+/// The slot of the off hand in the book update packet.
+const PACKET_SLOT_OFFHAND: i32 = 40;
+
+/// Sent when a client edited or signed a book and quill. The item is already
+/// updated in the client's inventory.
+#[derive(Event, Clone, PartialEq, Eq, Debug)]
+pub struct BookEditEvent {
+    pub client: Entity,
+    /// The slot of the book in the client's inventory.
+    pub slot: u16,
+    pub pages: Vec<String>,
+    /// The title of the book if it was signed, in which case it is now a
+    /// written book.
+    pub title: Option<String>,
+}
+
+/// Returns the written book made by signing a book and quill.
+fn written_book(pages: &[String], title: &str, author: &str, count: i8) -> ItemStack {
+    let mut nbt = Compound::new();
+
+    nbt.insert("title", title);
+    nbt.insert("author", author);
+    nbt.insert(
+        "pages",
+        List::String(
+            pages
+                .iter()
+                .map(|page| Text::text(page.clone()).into())
+                .collect(),
+        ),
+    );
+    nbt.insert("resolved", true);
+
+    ItemStack::new(ItemKind::WrittenBook, count, Some(nbt))
+}
+
+fn handle_book_update(
+    mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    mut clients: Query<(&mut Inventory, &Username), With<Client>>,
+    mut events: EventWriter<BookEditEvent>,
+) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode_reporting::<BookUpdateC2s>(&errors) else {
+            continue;
+        };
+
+        let Ok((mut inventory, username)) = clients.get_mut(packet.client) else {
+            continue;
+        };
+
+        let slot = match pkt.slot.0 {
+            PACKET_SLOT_OFFHAND => PlayerInventory::SLOT_OFFHAND,
+            hotbar @ 0..=8 => PlayerInventory::hotbar_to_slot(hotbar as u8),
+            _ => continue,
+        };
+
+        let stack = inventory.slot(slot);
+
+        if stack.item != ItemKind::WritableBook {
+            continue;
+        }
+
+        let pages: Vec<String> = pkt.entries.0.iter().map(|page| page.0.to_owned()).collect();
+        let title = pkt.title.map(|title| title.0.to_owned());
+
+        if pages.len() > MAX_BOOK_PAGES
+            || pages
+                .iter()
+                .any(|page| page.chars().count() > MAX_BOOK_PAGE_CHARS)
+            || title
+                .as_ref()
+                .is_some_and(|t| t.trim().is_empty() || t.chars().count() > MAX_BOOK_TITLE_CHARS)
+        {
+            continue;
+        }
+
+        let new_stack = match &title {
+            Some(title) => written_book(&pages, title, &username.0, stack.count),
+            None => {
+                let mut nbt = stack.nbt.clone().unwrap_or_default();
+                nbt.insert("pages", List::String(pages.clone()));
+
+                stack.clone().with_nbt(nbt)
+            }
+        };
+
+        inventory.set_slot(slot, new_stack);
+
+        events.send(BookEditEvent {
+            client: packet.client,
+            slot,
+            pages,
+            title,
+        });
+    }
+}
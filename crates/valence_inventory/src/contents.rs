@@ -0,0 +1,326 @@
+//! The items stored in the NBT data of items and block entities.
+//!
+//! Shulker box items keep their contents in the `BlockEntityTag` of the item,
+//! bundles keep them in their `Items` list, and placed containers keep them
+//! in the `Items` list of their block entity. [`ContainerContents`] reads and
+//! writes all of these. Items inside of the contents can have contents of
+//! their own, which are read the same way.
+//!
+//! ```
+//! # use valence_inventory::contents::ContainerContents;
+//! # use valence_server::{ItemKind, ItemStack};
+//! let mut shulker_box = ItemStack::new(ItemKind::ShulkerBox, 1, None);
+//!
+//! let mut contents = ContainerContents::new();
+//! contents.set(3, ItemStack::new(ItemKind::Diamond, 64, None));
+//! contents.write_to_item(&mut shulker_box);
+//!
+//! let contents = ContainerContents::from_item(&shulker_box).unwrap();
+//! assert_eq!(contents.get(3).unwrap().item, ItemKind::Diamond);
+//! ```
+
+use std::collections::BTreeMap;
+
+use valence_server::nbt::{Compound, List, Value};
+use valence_server::{ItemKind, ItemStack};
+
+use crate::Inventory;
+
+/// The items of a container, indexed by slot. Empty slots are not stored.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct ContainerContents {
+    slots: BTreeMap<u8, ItemStack>,
+}
+
+impl ContainerContents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the contents of a shulker box or bundle item. Returns `None` if
+    /// the item can't hold items.
+    pub fn from_item(stack: &ItemStack) -> Option<Self> {
+        let nbt = stack.nbt.as_ref();
+
+        if is_shulker_box(stack.item) {
+            let items = match nbt.and_then(|nbt| nbt.get("BlockEntityTag")) {
+                Some(Value::Compound(tag)) => tag.get("Items"),
+                _ => None,
+            };
+
+            Some(Self::from_list(items, true))
+        } else if stack.item == ItemKind::Bundle {
+            Some(Self::from_list(nbt.and_then(|nbt| nbt.get("Items")), false))
+        } else {
+            None
+        }
+    }
+
+    /// Writes the contents into a shulker box or bundle item, replacing its
+    /// previous contents. Does nothing if the item can't hold items.
+    pub fn write_to_item(&self, stack: &mut ItemStack) {
+        if is_shulker_box(stack.item) {
+            let nbt = stack.nbt.get_or_insert_with(Compound::new);
+
+            if self.is_empty() {
+                if let Some(Value::Compound(tag)) = nbt.get_mut("BlockEntityTag") {
+                    tag.remove("Items");
+
+                    if tag.is_empty() {
+                        nbt.remove("BlockEntityTag");
+                    }
+                }
+            } else {
+                if !matches!(nbt.get("BlockEntityTag"), Some(Value::Compound(_))) {
+                    nbt.insert("BlockEntityTag", Compound::new());
+                }
+
+                if let Some(Value::Compound(tag)) = nbt.get_mut("BlockEntityTag") {
+                    tag.insert("Items", self.to_list(true));
+                }
+            }
+        } else if stack.item == ItemKind::Bundle {
+            let nbt = stack.nbt.get_or_insert_with(Compound::new);
+
+            if self.is_empty() {
+                nbt.remove("Items");
+            } else {
+                nbt.insert("Items", self.to_list(false));
+            }
+        } else {
+            return;
+        }
+
+        if stack.nbt.as_ref().is_some_and(|nbt| nbt.is_empty()) {
+            stack.nbt = None;
+        }
+    }
+
+    /// Reads the contents of a block entity, like a chest or a placed shulker
+    /// box.
+    pub fn from_block_entity(nbt: &Compound) -> Self {
+        Self::from_list(nbt.get("Items"), true)
+    }
+
+    /// Writes the contents into a block entity, replacing its previous
+    /// contents.
+    pub fn write_to_block_entity(&self, nbt: &mut Compound) {
+        if self.is_empty() {
+            nbt.remove("Items");
+        } else {
+            nbt.insert("Items", self.to_list(true));
+        }
+    }
+
+    /// Copies the slots of an inventory.
+    pub fn from_inventory(inventory: &Inventory) -> Self {
+        let slots = inventory
+            .slots()
+            .enumerate()
+            .filter(|(idx, stack)| !stack.is_empty() && *idx <= u8::MAX.into())
+            .map(|(idx, stack)| (idx as u8, stack.clone()))
+            .collect();
+
+        Self { slots }
+    }
+
+    /// Copies the contents into the slots of an inventory, starting at slot
+    /// `offset`. Slots which don't exist in the inventory are skipped.
+    pub fn copy_to_inventory(&self, inventory: &mut Inventory, offset: u16) {
+        for (&slot, stack) in &self.slots {
+            let idx = offset + u16::from(slot);
+
+            if idx < inventory.slot_count() {
+                inventory.set_slot(idx, stack.clone());
+            }
+        }
+    }
+
+    /// Reads a list of items with `Slot` tags, like the `Items` list of a
+    /// block entity or the `Inventory` list of a player. Slots are kept as
+    /// stored, so negative slots wrap around.
+    pub fn from_slotted_list(items: &Value) -> Self {
+        Self::from_list(Some(items), true)
+    }
+
+    /// Writes the contents as a list of items with `Slot` tags.
+    pub fn to_slotted_list(&self) -> List {
+        self.to_list(true)
+    }
+
+    pub fn get(&self, slot: u8) -> Option<&ItemStack> {
+        self.slots.get(&slot)
+    }
+
+    pub fn get_mut(&mut self, slot: u8) -> Option<&mut ItemStack> {
+        self.slots.get_mut(&slot)
+    }
+
+    /// Sets the item in a slot, returning the previous item. Setting an empty
+    /// stack clears the slot.
+    pub fn set(&mut self, slot: u8, stack: ItemStack) -> Option<ItemStack> {
+        if stack.is_empty() {
+            self.slots.remove(&slot)
+        } else {
+            self.slots.insert(slot, stack)
+        }
+    }
+
+    pub fn remove(&mut self, slot: u8) -> Option<ItemStack> {
+        self.slots.remove(&slot)
+    }
+
+    /// Returns the occupied slots and their items in ascending slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &ItemStack)> + '_ {
+        self.slots.iter().map(|(&slot, stack)| (slot, stack))
+    }
+
+    /// The number of occupied slots.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Reads an `Items` list. Items without a `Slot` tag are put in the order
+    /// of the list.
+    fn from_list(items: Option<&Value>, with_slots: bool) -> Self {
+        let Some(Value::List(List::Compound(items))) = items else {
+            return Self::new();
+        };
+
+        let slots = items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| {
+                let slot = match item.get("Slot") {
+                    Some(&Value::Byte(slot)) if with_slots => slot as u8,
+                    _ => u8::try_from(idx).ok()?,
+                };
+
+                Some((slot, read_stack(item)?))
+            })
+            .collect();
+
+        Self { slots }
+    }
+
+    fn to_list(&self, with_slots: bool) -> List {
+        List::Compound(
+            self.slots
+                .iter()
+                .map(|(&slot, stack)| {
+                    let mut item = Compound::new();
+
+                    if with_slots {
+                        item.insert("Slot", slot as i8);
+                    }
+
+                    item.insert("id", format!("minecraft:{}", stack.item.to_str()));
+                    item.insert("Count", stack.count);
+
+                    if let Some(tag) = &stack.nbt {
+                        item.insert("tag", tag.clone());
+                    }
+
+                    item
+                })
+                .collect(),
+        )
+    }
+}
+
+fn read_stack(item: &Compound) -> Option<ItemStack> {
+    let Some(Value::String(id)) = item.get("id") else {
+        return None;
+    };
+
+    let kind = ItemKind::from_str(id.strip_prefix("minecraft:").unwrap_or(id))?;
+
+    let count = match item.get("Count") {
+        Some(&Value::Byte(count)) => count,
+        _ => 1,
+    };
+
+    let tag = match item.get("tag") {
+        Some(Value::Compound(tag)) => Some(tag.clone()),
+        _ => None,
+    };
+
+    let stack = ItemStack::new(kind, count, tag);
+
+    (!stack.is_empty()).then_some(stack)
+}
+
+/// Returns whether an item is one of the shulker boxes.
+pub fn is_shulker_box(item: ItemKind) -> bool {
+    item.to_str().ends_with("shulker_box")
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::nbt::compound;
+
+    use super::*;
+    use crate::InventoryKind;
+
+    #[test]
+    fn nested_contents() {
+        let mut bundle = ItemStack::new(ItemKind::Bundle, 1, None);
+
+        let mut bundle_contents = ContainerContents::new();
+        bundle_contents.set(0, ItemStack::new(ItemKind::Apple, 3, None));
+        bundle_contents.write_to_item(&mut bundle);
+
+        let mut shulker_box = ItemStack::new(ItemKind::RedShulkerBox, 1, None);
+
+        let mut contents = ContainerContents::new();
+        contents.set(26, bundle);
+        contents.write_to_item(&mut shulker_box);
+
+        let contents = ContainerContents::from_item(&shulker_box).unwrap();
+        let bundle = contents.get(26).unwrap();
+
+        assert_eq!(
+            ContainerContents::from_item(bundle).unwrap(),
+            bundle_contents
+        );
+
+        // Emptying the contents removes the NBT again.
+        ContainerContents::new().write_to_item(&mut shulker_box);
+        assert_eq!(shulker_box.nbt, None);
+
+        assert!(ContainerContents::from_item(&ItemStack::new(ItemKind::Stone, 1, None)).is_none());
+    }
+
+    #[test]
+    fn block_entity_contents() {
+        let nbt = compound! {
+            "Items" => List::Compound(vec![
+                compound! { "Slot" => 2_i8, "id" => "minecraft:stick", "Count" => 5_i8 },
+                compound! { "Slot" => 4_i8, "id" => "minecraft:not_an_item", "Count" => 1_i8 },
+            ]),
+        };
+
+        let contents = ContainerContents::from_block_entity(&nbt);
+        assert_eq!(contents.len(), 1);
+
+        let mut inventory = Inventory::new(InventoryKind::Generic9x6);
+        contents.copy_to_inventory(&mut inventory, 27);
+        assert_eq!(
+            inventory.slot(29),
+            &ItemStack::new(ItemKind::Stick, 5, None)
+        );
+
+        assert_eq!(
+            ContainerContents::from_inventory(&inventory).get(29),
+            Some(&ItemStack::new(ItemKind::Stick, 5, None))
+        );
+
+        let mut nbt = Compound::new();
+        contents.write_to_block_entity(&mut nbt);
+        assert_eq!(ContainerContents::from_block_entity(&nbt), contents);
+    }
+}
@@ -39,9 +39,13 @@ use valence_server::protocol::packets::play::{
     UpdateSelectedSlotS2c,
 };
 use valence_server::protocol::{VarInt, WritePacket};
+use valence_server::protocol_error::ProtocolErrorSink;
 use valence_server::text::IntoText;
 use valence_server::{GameMode, ItemKind, ItemStack, Text};
 
+pub mod block_inventory;
+pub mod book;
+pub mod contents;
 pub mod player_inventory;
 mod validate;
 
@@ -49,6 +53,9 @@ pub struct InventoryPlugin;
 
 impl Plugin for InventoryPlugin {
     fn build(&self, app: &mut bevy_app::App) {
+        block_inventory::build(app);
+        book::build(app);
+
         app.add_systems(
             PreUpdate,
             init_new_client_inventories.after(SpawnClientsSet),
@@ -776,9 +783,16 @@ fn update_open_inventories(
 }
 
 /// Handles clients telling the server that they are closing an inventory.
-fn handle_close_handled_screen(mut packets: EventReader<PacketEvent>, mut commands: Commands) {
+fn handle_close_handled_screen(
+    mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    mut commands: Commands,
+) {
     for packet in packets.read() {
-        if packet.decode::<CloseHandledScreenC2s>().is_some() {
+        if packet
+            .decode_reporting::<CloseHandledScreenC2s>(&errors)
+            .is_some()
+        {
             if let Some(mut entity) = commands.get_entity(packet.client) {
                 entity.remove::<OpenInventory>();
             }
@@ -823,6 +837,7 @@ pub struct DropItemStackEvent {
 
 fn handle_click_slot(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<(
         &mut Client,
         &mut Inventory,
@@ -835,7 +850,7 @@ fn handle_click_slot(
     mut click_slot_events: EventWriter<ClickSlotEvent>,
 ) {
     for packet in packets.read() {
-        let Some(pkt) = packet.decode::<ClickSlotC2s>() else {
+        let Some(pkt) = packet.decode_reporting::<ClickSlotC2s>(&errors) else {
             // Not the packet we're looking for.
             continue;
         };
@@ -1105,11 +1120,12 @@ fn handle_click_slot(
 
 fn handle_player_actions(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<(&mut Inventory, &mut ClientInventoryState, &HeldItem)>,
     mut drop_item_stack_events: EventWriter<DropItemStackEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<PlayerActionC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<PlayerActionC2s>(&errors) {
             match pkt.action {
                 PlayerAction::DropAllItems => {
                     if let Ok((mut inv, mut inv_state, &held)) = clients.get_mut(packet.client) {
@@ -1171,6 +1187,7 @@ pub struct CreativeInventoryActionEvent {
 
 fn handle_creative_inventory_action(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<(
         &mut Client,
         &mut Inventory,
@@ -1181,7 +1198,7 @@ fn handle_creative_inventory_action(
     mut drop_item_stack_events: EventWriter<DropItemStackEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<CreativeInventoryActionC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<CreativeInventoryActionC2s>(&errors) {
             let Ok((mut client, mut inventory, mut inv_state, game_mode)) =
                 clients.get_mut(packet.client)
             else {
@@ -1255,11 +1272,12 @@ fn update_player_selected_slot(mut clients: Query<(&mut Client, &HeldItem), Chan
 /// Client to Server HeldItem Slot
 fn handle_update_selected_slot(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<&mut HeldItem>,
     mut events: EventWriter<UpdateSelectedSlotEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<UpdateSelectedSlotC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<UpdateSelectedSlotC2s>(&errors) {
             if let Ok(mut mut_held) = clients.get_mut(packet.client) {
                 let held = mut_held.bypass_change_detection();
                 if pkt.slot > 8 {
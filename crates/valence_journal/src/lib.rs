@@ -0,0 +1,428 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod sink;
+
+use std::collections::BTreeMap;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+pub use sink::{JournalSink, MemorySink, TeeSink, WriterSink};
+use tracing::warn;
+use valence_server::budget::BudgetedTask;
+use valence_server::layer::chunk::{Block, IntoBlock};
+use valence_server::uuid::Uuid;
+use valence_server::{BlockPos, ChunkLayer, Server};
+
+pub struct JournalPlugin;
+
+impl Plugin for JournalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BlockJournal>();
+    }
+}
+
+/// Who made a block change.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Actor {
+    /// A player, identified by the UUID of their profile.
+    Player(Uuid),
+    /// Something other than a player, such as `"tnt"` or the name of a plugin.
+    Named(String),
+    /// The server itself.
+    Server,
+}
+
+/// A block change recorded by a [`BlockJournal`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct BlockChange {
+    /// The tick the change was made in, from [`Server::current_tick`].
+    pub tick: i64,
+    /// The entity of the chunk layer that was changed.
+    pub layer: Entity,
+    pub pos: BlockPos,
+    pub old: Block,
+    pub new: Block,
+    pub actor: Actor,
+}
+
+/// Selects changes in a [`BlockJournal`]. Fields that are `None` match every
+/// change.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct JournalQuery {
+    pub layer: Option<Entity>,
+    pub actor: Option<Actor>,
+    /// The inclusive corners of the box the changed block must be in.
+    pub area: Option<(BlockPos, BlockPos)>,
+    /// The first tick to include.
+    pub since_tick: Option<i64>,
+    /// The last tick to include.
+    pub until_tick: Option<i64>,
+}
+
+impl JournalQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn layer(mut self, layer: Entity) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    pub fn actor(mut self, actor: Actor) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    /// Only matches changes in the box between the corners `a` and `b`,
+    /// inclusive.
+    pub fn area(mut self, a: impl Into<BlockPos>, b: impl Into<BlockPos>) -> Self {
+        let (a, b) = (a.into(), b.into());
+
+        self.area = Some((
+            BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        ));
+        self
+    }
+
+    pub fn since_tick(mut self, tick: i64) -> Self {
+        self.since_tick = Some(tick);
+        self
+    }
+
+    pub fn until_tick(mut self, tick: i64) -> Self {
+        self.until_tick = Some(tick);
+        self
+    }
+
+    pub fn matches(&self, change: &BlockChange) -> bool {
+        let in_area = |(min, max): (BlockPos, BlockPos)| {
+            let p = change.pos;
+            (min.x..=max.x).contains(&p.x)
+                && (min.y..=max.y).contains(&p.y)
+                && (min.z..=max.z).contains(&p.z)
+        };
+
+        self.layer.is_none_or(|layer| layer == change.layer)
+            && self.actor.as_ref().is_none_or(|a| *a == change.actor)
+            && self.area.is_none_or(in_area)
+            && self.since_tick.is_none_or(|t| change.tick >= t)
+            && self.until_tick.is_none_or(|t| change.tick <= t)
+    }
+}
+
+/// Resource recording block changes made through [`JournaledEdits`] to a
+/// [`JournalSink`]. A [`MemorySink`] without a size limit is used by default.
+#[derive(Resource)]
+pub struct BlockJournal {
+    sink: Box<dyn JournalSink>,
+    /// Whether changes are recorded. Enabled by default.
+    pub enabled: bool,
+}
+
+impl BlockJournal {
+    pub fn new(sink: impl JournalSink) -> Self {
+        Self {
+            sink: Box::new(sink),
+            enabled: true,
+        }
+    }
+
+    /// Replaces the sink changes are recorded to.
+    pub fn set_sink(&mut self, sink: impl JournalSink) {
+        self.sink = Box::new(sink);
+    }
+
+    /// Records a change which was made without [`JournaledEdits`]. Does
+    /// nothing if the journal is disabled.
+    pub fn record(&mut self, change: BlockChange) {
+        if self.enabled {
+            if let Err(e) = self.sink.append(&change) {
+                warn!("failed to record block change: {e:#}");
+            }
+        }
+    }
+
+    /// Returns the recorded changes matching `query`, oldest first.
+    pub fn query(&self, query: &JournalQuery) -> Vec<BlockChange> {
+        self.sink.query(query)
+    }
+
+    /// Like [`JournaledEdits::rollback`], but creates a [`BudgetedTask`] which
+    /// restores one block per item, so that large rollbacks are spread across
+    /// ticks. The changes to undo are selected when the task is created.
+    pub fn rollback_task(&self, query: &JournalQuery, actor: Actor) -> BudgetedTask {
+        BudgetedTask::new(
+            self.rollback_targets(query),
+            move |world, ((layer, pos), (old, new))| {
+                let tick = world.resource::<Server>().current_tick();
+
+                world.resource_scope(|world, mut journal: Mut<BlockJournal>| {
+                    if let Some(mut chunk_layer) = world.get_mut::<ChunkLayer>(layer) {
+                        restore(
+                            &mut journal,
+                            tick,
+                            layer,
+                            &mut chunk_layer,
+                            pos,
+                            old,
+                            &new,
+                            &actor,
+                        );
+                    }
+                });
+            },
+        )
+    }
+
+    /// Returns the earliest old block and the latest new block of every
+    /// position changed by the changes matching `query`.
+    fn rollback_targets(
+        &self,
+        query: &JournalQuery,
+    ) -> BTreeMap<(Entity, BlockPos), (Block, Block)> {
+        let mut targets: BTreeMap<(Entity, BlockPos), (Block, Block)> = BTreeMap::new();
+
+        for change in self.query(query) {
+            targets
+                .entry((change.layer, change.pos))
+                .and_modify(|(_, new)| *new = change.new.clone())
+                .or_insert((change.old, change.new));
+        }
+
+        targets
+    }
+}
+
+/// Sets the block at `pos` back to `old` if it is still `new`, and records the
+/// change. Returns whether the block was restored.
+#[allow(clippy::too_many_arguments)]
+fn restore(
+    journal: &mut BlockJournal,
+    tick: i64,
+    layer: Entity,
+    chunk_layer: &mut ChunkLayer,
+    pos: BlockPos,
+    old: Block,
+    new: &Block,
+    actor: &Actor,
+) -> bool {
+    let is_unchanged = chunk_layer
+        .block(pos)
+        .is_some_and(|b| b.state == new.state && b.nbt == new.nbt.as_ref());
+
+    if !is_unchanged {
+        return false;
+    }
+
+    let Some(current) = chunk_layer.set_block(pos, old.clone()) else {
+        return false;
+    };
+
+    if current != old {
+        journal.record(BlockChange {
+            tick,
+            layer,
+            pos,
+            old: current,
+            new: old,
+            actor: actor.clone(),
+        });
+    }
+
+    true
+}
+
+impl Default for BlockJournal {
+    fn default() -> Self {
+        Self::new(MemorySink::new())
+    }
+}
+
+/// The result of [`JournaledEdits::rollback`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct RollbackResult {
+    /// The number of blocks that were restored.
+    pub restored: usize,
+    /// The number of blocks that were left alone because they were changed
+    /// again by a change not selected by the query, or are no longer loaded.
+    pub skipped: usize,
+}
+
+/// A [`SystemParam`] for changing blocks of chunk layers while recording the
+/// changes in the [`BlockJournal`].
+#[derive(SystemParam)]
+pub struct JournaledEdits<'w, 's> {
+    journal: ResMut<'w, BlockJournal>,
+    server: Res<'w, Server>,
+    layers: Query<'w, 's, &'static mut ChunkLayer>,
+}
+
+impl JournaledEdits<'_, '_> {
+    /// Sets the block at `pos` in `layer` like [`ChunkLayer::set_block`] and
+    /// records the change. Returns the previous block, or `None` if the
+    /// position is not loaded.
+    pub fn set_block(
+        &mut self,
+        layer: Entity,
+        pos: impl Into<BlockPos>,
+        block: impl IntoBlock,
+        actor: &Actor,
+    ) -> Option<Block> {
+        let pos = pos.into();
+        let block = block.into_block();
+
+        let old = self
+            .layers
+            .get_mut(layer)
+            .ok()?
+            .set_block(pos, block.clone())?;
+
+        if old != block {
+            self.journal.record(BlockChange {
+                tick: self.server.current_tick(),
+                layer,
+                pos,
+                old: old.clone(),
+                new: block,
+                actor: actor.clone(),
+            });
+        }
+
+        Some(old)
+    }
+
+    /// Undoes the changes matching `query`, restoring every affected block to
+    /// its state before the earliest matching change. The restorations are
+    /// recorded with `actor`.
+    ///
+    /// A block is only restored if it still has the state set by its latest
+    /// matching change, so that newer changes by others are not overwritten.
+    pub fn rollback(&mut self, query: &JournalQuery, actor: &Actor) -> RollbackResult {
+        let targets = self.journal.rollback_targets(query);
+        let tick = self.server.current_tick();
+
+        let mut result = RollbackResult::default();
+
+        for ((layer, pos), (old, new)) in targets {
+            let restored = self.layers.get_mut(layer).is_ok_and(|mut chunk_layer| {
+                restore(
+                    &mut self.journal,
+                    tick,
+                    layer,
+                    &mut chunk_layer,
+                    pos,
+                    old,
+                    &new,
+                    actor,
+                )
+            });
+
+            if restored {
+                result.restored += 1;
+            } else {
+                result.skipped += 1;
+            }
+        }
+
+        result
+    }
+
+    pub fn journal(&self) -> &BlockJournal {
+        &self.journal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::BlockState;
+
+    use super::*;
+
+    fn change(tick: i64, pos: [i32; 3], actor: Actor) -> BlockChange {
+        BlockChange {
+            tick,
+            layer: Entity::PLACEHOLDER,
+            pos: pos.into(),
+            old: BlockState::STONE.into_block(),
+            new: BlockState::AIR.into_block(),
+            actor,
+        }
+    }
+
+    #[test]
+    fn query_changes() {
+        let alice = Actor::Player(Uuid::from_u128(1));
+        let bob = Actor::Player(Uuid::from_u128(2));
+
+        let mut journal = BlockJournal::new(MemorySink::with_max_len(3));
+
+        journal.record(change(0, [0, 0, 0], alice.clone()));
+        journal.record(change(5, [1, 2, 3], alice.clone()));
+        journal.record(change(10, [10, 0, 0], bob.clone()));
+        journal.record(change(15, [-5, 0, 0], Actor::Server));
+
+        // The oldest change was dropped.
+        assert_eq!(journal.query(&JournalQuery::new()).len(), 3);
+
+        let ticks = |query: JournalQuery| {
+            journal
+                .query(&query)
+                .iter()
+                .map(|c| c.tick)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ticks(JournalQuery::new().actor(alice)), [5]);
+        assert_eq!(
+            ticks(JournalQuery::new().area([10, 5, 5], [0, 0, 0])),
+            [5, 10]
+        );
+        assert_eq!(ticks(JournalQuery::new().since_tick(10)), [10, 15]);
+        assert_eq!(ticks(JournalQuery::new().until_tick(10).actor(bob)), [10]);
+
+        journal.enabled = false;
+        journal.record(change(20, [0, 0, 0], Actor::Server));
+        assert!(journal
+            .query(&JournalQuery::new().since_tick(20))
+            .is_empty());
+    }
+
+    #[test]
+    fn writer_sink() {
+        let mut sink = WriterSink::new(vec![]);
+
+        sink.append(&change(7, [1, -2, 3], Actor::Named("tnt".into())))
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(sink.into_inner()).unwrap(),
+            format!(
+                "7\t{:?}\t1 -2 3\tnamed:tnt\t{}\t{}\n",
+                Entity::PLACEHOLDER,
+                BlockState::STONE,
+                BlockState::AIR
+            )
+        );
+    }
+}
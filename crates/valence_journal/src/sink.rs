@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+use std::io::Write;
+
+use crate::{Actor, BlockChange, JournalQuery};
+
+/// Storage for the changes recorded by a [`BlockJournal`](crate::BlockJournal).
+///
+/// Sinks are append-only: changes are never modified or removed once
+/// recorded, except when a sink drops its oldest changes to bound its size.
+pub trait JournalSink: Send + Sync + 'static {
+    /// Stores `change`. Changes are appended in the order they happened.
+    fn append(&mut self, change: &BlockChange) -> anyhow::Result<()>;
+
+    /// Returns the stored changes matching `query`, oldest first.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns nothing, for sinks which only write changes elsewhere.
+    fn query(&self, query: &JournalQuery) -> Vec<BlockChange> {
+        let _ = query;
+        vec![]
+    }
+}
+
+/// Keeps changes in memory, optionally dropping the oldest changes beyond a
+/// maximum length.
+#[derive(Clone, Default, Debug)]
+pub struct MemorySink {
+    changes: VecDeque<BlockChange>,
+    max_len: Option<usize>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a sink which keeps at most `max_len` changes.
+    pub fn with_max_len(max_len: usize) -> Self {
+        Self {
+            changes: VecDeque::new(),
+            max_len: Some(max_len),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BlockChange> + '_ {
+        self.changes.iter()
+    }
+}
+
+impl JournalSink for MemorySink {
+    fn append(&mut self, change: &BlockChange) -> anyhow::Result<()> {
+        if self.max_len == Some(0) {
+            return Ok(());
+        }
+
+        if Some(self.changes.len()) == self.max_len {
+            self.changes.pop_front();
+        }
+
+        self.changes.push_back(change.clone());
+        Ok(())
+    }
+
+    fn query(&self, query: &JournalQuery) -> Vec<BlockChange> {
+        self.changes
+            .iter()
+            .filter(|change| query.matches(change))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Writes every change as a line of tab separated values to a writer, such as
+/// a log file. The columns are the tick, the layer, the position, the actor,
+/// the old block state and the new block state, such as
+/// `1200 4v0 10 64 -3 player:<uuid> minecraft:stone minecraft:air` with tabs
+/// between the columns.
+///
+/// Block entity data is not written. This sink can't be queried, so wrap a
+/// [`MemorySink`] with [`TeeSink`] to also support rollbacks.
+pub struct WriterSink<W> {
+    writer: W,
+}
+
+impl<W: Write + Send + Sync + 'static> WriterSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send + Sync + 'static> JournalSink for WriterSink<W> {
+    fn append(&mut self, change: &BlockChange) -> anyhow::Result<()> {
+        let actor = match &change.actor {
+            Actor::Player(uuid) => format!("player:{uuid}"),
+            Actor::Named(name) => format!("named:{name}"),
+            Actor::Server => "server".into(),
+        };
+
+        writeln!(
+            self.writer,
+            "{}\t{:?}\t{} {} {}\t{actor}\t{}\t{}",
+            change.tick,
+            change.layer,
+            change.pos.x,
+            change.pos.y,
+            change.pos.z,
+            change.old.state,
+            change.new.state,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Appends changes to two sinks and queries the first one.
+pub struct TeeSink<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A: JournalSink, B: JournalSink> TeeSink<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: JournalSink, B: JournalSink> JournalSink for TeeSink<A, B> {
+    fn append(&mut self, change: &BlockChange) -> anyhow::Result<()> {
+        let first = self.first.append(change);
+        let second = self.second.append(change);
+        first.and(second)
+    }
+
+    fn query(&self, query: &JournalQuery) -> Vec<BlockChange> {
+        self.first.query(query)
+    }
+}
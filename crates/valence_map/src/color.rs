@@ -0,0 +1,222 @@
+//! The colors of map pixels.
+
+/// The RGB values of the base colors of maps, indexed by their ID. Base color
+/// 0 is transparent.
+const BASE_COLORS: [u32; 62] = [
+    0x000000, 0x7fb238, 0xf7e9a3, 0xc7c7c7, 0xff0000, 0xa0a0ff, 0xa7a7a7, 0x007c00, 0xffffff,
+    0xa4a8b8, 0x976d4d, 0x707070, 0x4040ff, 0x8f7748, 0xfffcf5, 0xd87f33, 0xb24cd8, 0x6699d8,
+    0xe5e533, 0x7fcc19, 0xf27fa5, 0x4c4c4c, 0x999999, 0x4c7f99, 0x7f3fb2, 0x334cb2, 0x664c33,
+    0x667f33, 0x993333, 0x191919, 0xfaee4d, 0x5cdbd5, 0x4a80ff, 0x00d93a, 0x815631, 0x700200,
+    0xd1b1a1, 0x9f5224, 0x95576c, 0x706c8a, 0xba8524, 0x677535, 0xa04d4e, 0x392923, 0x876b62,
+    0x575c5c, 0x7a4958, 0x4c3e5c, 0x4c3223, 0x4c522a, 0x8e3c2e, 0x251610, 0xbd3031, 0x943f61,
+    0x5c191d, 0x167e86, 0x3a8e8c, 0x562c3e, 0x14b485, 0x646464, 0xd8af93, 0x7fa796,
+];
+
+/// The color of a map pixel, which is one of the four shades of a base
+/// color. The base colors are the colors of blocks on vanilla maps, such as
+/// 8 for snow (white) or 29 for black wool.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub struct MapColor(pub u8);
+
+/// The shades of a base [`MapColor`], from the multiplier of the base color
+/// in vanilla.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum MapShade {
+    /// 180/255 of the base color.
+    Low,
+    /// 220/255 of the base color.
+    Normal,
+    /// The base color.
+    High,
+    /// 135/255 of the base color. Vanilla maps don't use this shade.
+    Lowest,
+}
+
+impl MapShade {
+    pub const ALL: [Self; 4] = [Self::Low, Self::Normal, Self::High, Self::Lowest];
+
+    const fn multiplier(self) -> u32 {
+        match self {
+            Self::Low => 180,
+            Self::Normal => 220,
+            Self::High => 255,
+            Self::Lowest => 135,
+        }
+    }
+}
+
+impl MapColor {
+    /// Pixels with this color show the background of the map.
+    pub const TRANSPARENT: Self = Self(0);
+
+    /// The number of base colors, including the transparent one.
+    pub const BASE_COUNT: u8 = BASE_COLORS.len() as u8;
+
+    /// Creates the color with the base color `base` and the shade `shade`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base` isn't less than [`Self::BASE_COUNT`].
+    #[track_caller]
+    pub const fn new(base: u8, shade: MapShade) -> Self {
+        assert!(base < Self::BASE_COUNT, "invalid base map color");
+
+        Self(base * 4 + shade as u8)
+    }
+
+    pub const fn base(self) -> u8 {
+        self.0 / 4
+    }
+
+    pub const fn shade(self) -> MapShade {
+        MapShade::ALL[(self.0 % 4) as usize]
+    }
+
+    pub const fn is_transparent(self) -> bool {
+        self.base() == 0
+    }
+
+    /// Returns the RGB value of this color, or `None` if it is transparent or
+    /// not a valid color.
+    pub const fn to_rgb(self) -> Option<[u8; 3]> {
+        if self.is_transparent() || self.base() >= Self::BASE_COUNT {
+            return None;
+        }
+
+        let rgb = BASE_COLORS[self.base() as usize];
+        let mul = self.shade().multiplier();
+
+        Some([
+            ((rgb >> 16 & 0xff) * mul / 255) as u8,
+            ((rgb >> 8 & 0xff) * mul / 255) as u8,
+            ((rgb & 0xff) * mul / 255) as u8,
+        ])
+    }
+
+    /// Returns the opaque color closest to the RGB value `rgb`.
+    pub fn nearest(rgb: [u8; 3]) -> Self {
+        Self::nearest_to([rgb[0].into(), rgb[1].into(), rgb[2].into()]).0
+    }
+
+    /// Returns the opaque color closest to `rgb` and its RGB value. The
+    /// components of `rgb` may be out of range, as happens while dithering.
+    fn nearest_to(rgb: [i32; 3]) -> (Self, [i32; 3]) {
+        (4..Self::BASE_COUNT * 4)
+            .filter_map(|id| {
+                let [r, g, b] = Self(id).to_rgb()?;
+                Some((Self(id), [r.into(), g.into(), b.into()]))
+            })
+            .min_by_key(|(_, c)| {
+                // Weighted by how sensitive eyes are to each component.
+                let [dr, dg, db] = [c[0] - rgb[0], c[1] - rgb[1], c[2] - rgb[2]];
+                2 * dr * dr + 4 * dg * dg + 3 * db * db
+            })
+            .expect("there are opaque map colors")
+    }
+}
+
+/// Converts an RGBA image with the given size to map colors, using
+/// Floyd-Steinberg dithering to approximate colors which maps don't have.
+/// Pixels with an alpha below 128 become transparent. The pixels of `rgba` and
+/// the returned colors are in rows from the top left corner.
+///
+/// # Panics
+///
+/// Panics if the length of `rgba` isn't `width * height * 4`.
+#[track_caller]
+pub fn dither(width: usize, height: usize, rgba: &[u8]) -> Vec<MapColor> {
+    assert_eq!(
+        rgba.len(),
+        width * height * 4,
+        "RGBA data doesn't match the size of the image"
+    );
+
+    let mut errors = vec![[0_i32; 3]; width * height];
+    let mut colors = Vec::with_capacity(width * height);
+
+    for (i, pixel) in rgba.chunks_exact(4).enumerate() {
+        if pixel[3] < 128 {
+            colors.push(MapColor::TRANSPARENT);
+            continue;
+        }
+
+        let wanted: [i32; 3] = array_add(
+            [pixel[0].into(), pixel[1].into(), pixel[2].into()],
+            errors[i],
+        );
+        let (color, actual) = MapColor::nearest_to(wanted);
+        colors.push(color);
+
+        let error = [
+            wanted[0] - actual[0],
+            wanted[1] - actual[1],
+            wanted[2] - actual[2],
+        ];
+
+        let (x, y) = (i % width, i / width);
+        let mut spread = |dx: isize, dy: usize, weight: i32| {
+            let Some(nx) = x.checked_add_signed(dx).filter(|&nx| nx < width) else {
+                return;
+            };
+
+            if y + dy < height {
+                let e = &mut errors[nx + (y + dy) * width];
+                *e = array_add(*e, error.map(|c| c * weight / 16));
+            }
+        };
+
+        spread(1, 0, 7);
+        spread(-1, 1, 3);
+        spread(0, 1, 5);
+        spread(1, 1, 1);
+    }
+
+    colors
+}
+
+fn array_add(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_color_rgb() {
+        assert_eq!(MapColor::new(8, MapShade::High).to_rgb(), Some([255; 3]));
+        assert_eq!(
+            MapColor::new(4, MapShade::Normal).to_rgb(),
+            Some([220, 0, 0])
+        );
+        assert_eq!(MapColor::TRANSPARENT.to_rgb(), None);
+        assert_eq!(MapColor(255).to_rgb(), None);
+
+        assert_eq!(MapColor::nearest([255, 255, 255]), MapColor(8 * 4 + 2));
+        assert_eq!(
+            MapColor::nearest([26, 25, 24]),
+            MapColor::new(29, MapShade::High)
+        );
+        assert_eq!(
+            MapColor::new(12, MapShade::Lowest).shade(),
+            MapShade::Lowest
+        );
+    }
+
+    #[test]
+    fn dither_image() {
+        // Grays which maps don't have are approximated with several colors.
+        let gray: Vec<u8> = [128, 128, 128, 255].repeat(64);
+        let colors = dither(8, 8, &gray);
+
+        assert!(colors.iter().all(|c| !c.is_transparent()));
+        assert!(colors.windows(2).any(|w| w[0] != w[1]));
+
+        // Exact colors aren't changed, and transparent pixels stay transparent.
+        let red = MapColor::new(4, MapShade::High);
+        let [r, g, b] = red.to_rgb().unwrap();
+        let image = [r, g, b, 255, 0, 0, 0, 0, r, g, b, 255];
+
+        assert_eq!(dither(3, 1, &image), [red, MapColor::TRANSPARENT, red]);
+    }
+}
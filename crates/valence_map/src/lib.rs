@@ -0,0 +1,479 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod color;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use color::{dither, MapColor, MapShade};
+use valence_inventory::player_inventory::PlayerInventory;
+use valence_inventory::{HeldItem, Inventory};
+use valence_server::client::{Client, FlushPacketsSet, UpdateClientsSet};
+use valence_server::nbt::{compound, Value};
+pub use valence_server::protocol::packets::play::map_update_s2c::IconType as MapMarkerKind;
+use valence_server::protocol::packets::play::map_update_s2c::{Data, Icon};
+use valence_server::protocol::packets::play::MapUpdateS2c;
+use valence_server::protocol::{VarInt, WritePacket};
+use valence_server::text::IntoText;
+use valence_server::{ItemKind, ItemStack, Text};
+
+/// The width and height of a map in pixels.
+pub const MAP_SIZE: usize = 128;
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (init_clients, apply_deferred, send_map_updates)
+                .chain()
+                .after(UpdateClientsSet)
+                .before(FlushPacketsSet),
+        );
+    }
+}
+
+/// The ID of a map, which is the `map` tag of `filled_map` items showing it.
+#[derive(Component, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct MapId(pub i32);
+
+impl MapId {
+    /// Returns a `filled_map` item which shows this map.
+    pub fn item_stack(self) -> ItemStack {
+        ItemStack::new(ItemKind::FilledMap, 1, Some(compound! { "map" => self.0 }))
+    }
+
+    /// Returns the ID of the map shown by an item, or `None` if it isn't a
+    /// `filled_map`.
+    pub fn from_item_stack(stack: &ItemStack) -> Option<Self> {
+        if stack.item != ItemKind::FilledMap {
+            return None;
+        }
+
+        match stack.nbt.as_ref()?.get("map")? {
+            Value::Int(id) => Some(Self(*id)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Bundle, Clone, Debug)]
+pub struct MapBundle {
+    pub id: MapId,
+    pub canvas: MapCanvas,
+}
+
+/// The pixels and markers of a map. Changes are sent to the clients which
+/// have seen the map at the end of the tick.
+#[derive(Component, Clone, Debug)]
+pub struct MapCanvas {
+    colors: Box<[MapColor]>,
+    markers: Vec<MapMarker>,
+    scale: i8,
+    locked: bool,
+    /// The corners of the part of the canvas changed since the last update, as
+    /// `[min_x, min_y, max_x, max_y]`.
+    changed_area: Option<[usize; 4]>,
+    /// If the markers, scale or lock changed since the last update.
+    changed_markers: bool,
+}
+
+impl MapCanvas {
+    /// Creates a transparent canvas without markers.
+    pub fn new() -> Self {
+        Self {
+            colors: vec![MapColor::TRANSPARENT; MAP_SIZE * MAP_SIZE].into(),
+            markers: vec![],
+            scale: 0,
+            locked: false,
+            // New canvases replace any canvas clients have seen with the same ID.
+            changed_area: Some([0, 0, MAP_SIZE - 1, MAP_SIZE - 1]),
+            changed_markers: true,
+        }
+    }
+
+    /// Gets the color of the pixel at the given position. `x` and `y` are in
+    /// the range `0..128`, starting from the top left corner.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is out of bounds.
+    #[track_caller]
+    pub fn pixel(&self, x: usize, y: usize) -> MapColor {
+        check_pixel_oob(x, y);
+
+        self.colors[x + y * MAP_SIZE]
+    }
+
+    /// Sets the color of the pixel at the given position. `x` and `y` are in
+    /// the range `0..128`, starting from the top left corner.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is out of bounds.
+    #[track_caller]
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: MapColor) {
+        check_pixel_oob(x, y);
+
+        let pixel = &mut self.colors[x + y * MAP_SIZE];
+
+        if *pixel != color {
+            *pixel = color;
+            self.mark_changed(x, y, x, y);
+        }
+    }
+
+    /// Sets every pixel of the canvas to `color`.
+    pub fn fill(&mut self, color: MapColor) {
+        self.fill_rect(0, 0, MAP_SIZE, MAP_SIZE, color);
+    }
+
+    /// Sets the pixels in the rectangle with the top left corner at `x` and
+    /// `y` to `color`. The parts of the rectangle outside of the canvas are
+    /// ignored.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: MapColor) {
+        for py in y..(y + height).min(MAP_SIZE) {
+            for px in x..(x + width).min(MAP_SIZE) {
+                self.set_pixel(px, py, color);
+            }
+        }
+    }
+
+    /// Draws an image of map colors with the top left corner at `x` and `y`.
+    /// The colors are in rows from the top left corner of the image.
+    /// Transparent pixels and the parts of the image outside of the canvas are
+    /// skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `colors` isn't `width * height`.
+    #[track_caller]
+    pub fn draw_colors(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        colors: &[MapColor],
+    ) {
+        assert_eq!(
+            colors.len(),
+            width * height,
+            "colors don't match the size of the image"
+        );
+
+        for (i, &color) in colors.iter().enumerate() {
+            let (px, py) = (x + i % width, y + i / width);
+
+            if !color.is_transparent() && px < MAP_SIZE && py < MAP_SIZE {
+                self.set_pixel(px, py, color);
+            }
+        }
+    }
+
+    /// Draws an RGBA image with the top left corner at `x` and `y`, which is
+    /// [dithered](dither) to map colors. Like [`Self::draw_colors`], mostly
+    /// transparent pixels are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `rgba` isn't `width * height * 4`.
+    #[track_caller]
+    pub fn draw_rgba(&mut self, x: usize, y: usize, width: usize, height: usize, rgba: &[u8]) {
+        let colors = dither(width, height, rgba);
+        self.draw_colors(x, y, width, height, &colors);
+    }
+
+    /// Returns the colors of every pixel, in rows from the top left corner.
+    pub fn colors(&self) -> &[MapColor] {
+        &self.colors
+    }
+
+    pub fn markers(&self) -> &[MapMarker] {
+        &self.markers
+    }
+
+    /// Returns the markers for modification. They are sent to clients again
+    /// even if they aren't modified.
+    pub fn markers_mut(&mut self) -> &mut Vec<MapMarker> {
+        self.changed_markers = true;
+        &mut self.markers
+    }
+
+    pub fn add_marker(&mut self, marker: MapMarker) {
+        self.markers_mut().push(marker);
+    }
+
+    pub fn clear_markers(&mut self) {
+        self.markers_mut().clear();
+    }
+
+    /// The zoom level of the map from 0 to 4, which only changes how big the
+    /// map looks in the client's tooltip.
+    pub fn scale(&self) -> i8 {
+        self.scale
+    }
+
+    pub fn set_scale(&mut self, scale: i8) {
+        self.scale = scale;
+        self.changed_markers = true;
+    }
+
+    /// Whether the map shows a lock in the client's tooltip.
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+        self.changed_markers = true;
+    }
+
+    fn mark_changed(&mut self, min_x: usize, min_y: usize, max_x: usize, max_y: usize) {
+        self.changed_area = Some(match self.changed_area {
+            Some([x0, y0, x1, y1]) => [x0.min(min_x), y0.min(min_y), x1.max(max_x), y1.max(max_y)],
+            None => [min_x, min_y, max_x, max_y],
+        });
+    }
+
+    /// Writes the packet updating the canvas of map `id`. If `full` is false,
+    /// only the changes since the last update are written.
+    fn write_update(&self, id: MapId, full: bool, mut writer: impl WritePacket) {
+        let area = if full {
+            Some([0, 0, MAP_SIZE - 1, MAP_SIZE - 1])
+        } else {
+            self.changed_area
+        };
+
+        let mut data = vec![];
+
+        if let Some([x0, y0, x1, y1]) = area {
+            for y in y0..=y1 {
+                data.extend(
+                    self.colors[x0 + y * MAP_SIZE..=x1 + y * MAP_SIZE]
+                        .iter()
+                        .map(|c| c.0),
+                );
+            }
+        }
+
+        writer.write_packet(&MapUpdateS2c {
+            map_id: VarInt(id.0),
+            scale: self.scale,
+            locked: self.locked,
+            icons: (full || self.changed_markers)
+                .then(|| self.markers.iter().map(MapMarker::to_icon).collect()),
+            data: area.map(|[x0, y0, x1, y1]| Data {
+                columns: (x1 - x0 + 1) as u8,
+                rows: (y1 - y0 + 1) as u8,
+                position: [x0 as i8, y0 as i8],
+                data: &data,
+            }),
+        });
+    }
+}
+
+impl Default for MapCanvas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline]
+#[track_caller]
+fn check_pixel_oob(x: usize, y: usize) {
+    assert!(
+        x < MAP_SIZE && y < MAP_SIZE,
+        "map pixel ({x}, {y}) is out of bounds"
+    );
+}
+
+/// A marker on a map, like the player arrows and banners of vanilla maps.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapMarker {
+    pub kind: MapMarkerKind,
+    /// The position of the marker, from -128 for the top left corner to 127
+    /// for the bottom right corner. Each pixel is two units.
+    pub position: [i8; 2],
+    /// The rotation of the marker in 16ths of a full turn clockwise, where 0
+    /// points up.
+    pub direction: u8,
+    /// The text shown below the marker.
+    pub name: Option<Text>,
+}
+
+impl MapMarker {
+    pub fn new(kind: MapMarkerKind, position: [i8; 2]) -> Self {
+        Self {
+            kind,
+            position,
+            direction: 0,
+            name: None,
+        }
+    }
+
+    /// Creates a banner marker, which is shown for banners marked on vanilla
+    /// maps.
+    pub fn banner(color: BannerColor, position: [i8; 2]) -> Self {
+        Self::new(color.marker_kind(), position)
+    }
+
+    pub fn with_direction(mut self, direction: u8) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_name<'a>(mut self, name: impl IntoText<'a>) -> Self {
+        self.name = Some(name.into_cow_text().into_owned());
+        self
+    }
+
+    fn to_icon(&self) -> Icon<'_> {
+        Icon {
+            icon_type: self.kind,
+            position: self.position,
+            direction: (self.direction % 16) as i8,
+            display_name: self.name.as_ref().map(Cow::Borrowed),
+        }
+    }
+}
+
+/// The colors of banner markers.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum BannerColor {
+    White,
+    Orange,
+    Magenta,
+    LightBlue,
+    Yellow,
+    Lime,
+    Pink,
+    Gray,
+    LightGray,
+    Cyan,
+    Purple,
+    Blue,
+    Brown,
+    Green,
+    Red,
+    Black,
+}
+
+impl BannerColor {
+    pub fn marker_kind(self) -> MapMarkerKind {
+        match self {
+            Self::White => MapMarkerKind::WhiteBanner,
+            Self::Orange => MapMarkerKind::OrangeBanner,
+            Self::Magenta => MapMarkerKind::MagentaBanner,
+            Self::LightBlue => MapMarkerKind::LightBlueBanner,
+            Self::Yellow => MapMarkerKind::YellowBanner,
+            Self::Lime => MapMarkerKind::LimeBanner,
+            Self::Pink => MapMarkerKind::PinkBanner,
+            Self::Gray => MapMarkerKind::GrayBanner,
+            Self::LightGray => MapMarkerKind::LightGrayBanner,
+            Self::Cyan => MapMarkerKind::CyanBanner,
+            Self::Purple => MapMarkerKind::PurpleBanner,
+            Self::Blue => MapMarkerKind::BlueBanner,
+            Self::Brown => MapMarkerKind::BrownBanner,
+            Self::Green => MapMarkerKind::GreenBanner,
+            Self::Red => MapMarkerKind::RedBanner,
+            Self::Black => MapMarkerKind::BlackBanner,
+        }
+    }
+}
+
+/// The maps whose canvas has been sent to a client. Clients keep the canvases
+/// of the maps they've seen, so changes to them are sent even when the client
+/// isn't holding them.
+#[derive(Component, Default, Debug)]
+pub struct KnownMaps(BTreeSet<MapId>);
+
+impl KnownMaps {
+    pub fn contains(&self, id: MapId) -> bool {
+        self.0.contains(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = MapId> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+fn init_clients(clients: Query<Entity, Added<Client>>, mut commands: Commands) {
+    for entity in &clients {
+        commands.entity(entity).insert(KnownMaps::default());
+    }
+}
+
+fn send_map_updates(
+    mut canvases: Query<(&MapId, &mut MapCanvas)>,
+    mut clients: Query<(Entity, &mut Client, &mut KnownMaps, &Inventory, &HeldItem)>,
+) {
+    // Changes are sent first, so clients which see a map for the first time
+    // only get the full canvas.
+    for (&id, mut canvas) in &mut canvases {
+        if canvas.changed_area.is_none() && !canvas.changed_markers {
+            continue;
+        }
+
+        for (_, mut client, known, ..) in &mut clients {
+            if known.contains(id) {
+                canvas.write_update(id, false, &mut *client);
+            }
+        }
+
+        let canvas = canvas.bypass_change_detection();
+        canvas.changed_area = None;
+        canvas.changed_markers = false;
+    }
+
+    let mut held = BTreeMap::<MapId, Vec<Entity>>::new();
+
+    for (entity, _, known, inventory, held_item) in &clients {
+        for slot in [held_item.slot(), PlayerInventory::SLOT_OFFHAND] {
+            if let Some(id) = MapId::from_item_stack(inventory.slot(slot)) {
+                if !known.contains(id) {
+                    held.entry(id).or_default().push(entity);
+                }
+            }
+        }
+    }
+
+    if held.is_empty() {
+        return;
+    }
+
+    for (&id, canvas) in &canvases {
+        let Some(holders) = held.get(&id) else {
+            continue;
+        };
+
+        for &holder in holders {
+            if let Ok((_, mut client, mut known, ..)) = clients.get_mut(holder) {
+                if known.0.insert(id) {
+                    canvas.write_update(id, true, &mut *client);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+#[cfg(feature = "prometheus")]
+mod prometheus;
+mod registry;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+#[cfg(feature = "prometheus")]
+pub use prometheus::{PrometheusExporter, PrometheusSettings};
+pub use registry::{Histogram, MetricFamily, MetricKind, MetricValue, Metrics, DEFAULT_BUCKETS};
+use valence_network::SharedNetworkState;
+use valence_server::client::ClientMarker;
+use valence_server::{ChunkLayer, TickTimings};
+
+/// Collects the metrics listed in the crate documentation and hands them to
+/// the [`MetricsExporters`] at the end of every tick.
+pub struct MetricsPlugin;
+
+/// The systems collecting metrics, in [`Last`]. Systems adding their own
+/// metrics in [`Last`] should run before this set to be exported in the same
+/// tick.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CollectMetricsSet;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Metrics>()
+            .init_resource::<MetricsExporters>()
+            .add_systems(Startup, describe_metrics)
+            .add_systems(
+                Last,
+                (
+                    (
+                        collect_player_metrics,
+                        collect_chunk_metrics,
+                        collect_network_metrics,
+                        collect_tick_metrics,
+                    )
+                        .in_set(CollectMetricsSet),
+                    export_metrics.after(CollectMetricsSet),
+                ),
+            );
+
+        #[cfg(feature = "prometheus")]
+        prometheus::build(app);
+    }
+}
+
+/// Receives the [`Metrics`] at the end of every tick, to send them to a
+/// monitoring system.
+pub trait MetricsExporter: Send + Sync + 'static {
+    fn export(&mut self, metrics: &Metrics);
+}
+
+impl<F: FnMut(&Metrics) + Send + Sync + 'static> MetricsExporter for F {
+    fn export(&mut self, metrics: &Metrics) {
+        self(metrics)
+    }
+}
+
+/// The exporters the [`Metrics`] are handed to.
+#[derive(Resource, Default)]
+pub struct MetricsExporters {
+    exporters: Vec<Box<dyn MetricsExporter>>,
+}
+
+impl MetricsExporters {
+    pub fn add(&mut self, exporter: impl MetricsExporter) {
+        self.exporters.push(Box::new(exporter));
+    }
+
+    pub fn len(&self) -> usize {
+        self.exporters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exporters.is_empty()
+    }
+}
+
+const ONLINE_PLAYERS: &str = "valence_online_players";
+const LOADED_CHUNKS: &str = "valence_loaded_chunks";
+const CACHE_HITS: &str = "valence_chunk_init_packet_cache_hits_total";
+const CACHE_MISSES: &str = "valence_chunk_init_packet_cache_misses_total";
+const CACHE_HIT_RATE: &str = "valence_chunk_init_packet_cache_hit_rate";
+const PACKETS_RECEIVED: &str = "valence_packets_received_total";
+const PACKETS_DROPPED: &str = "valence_packets_dropped_total";
+const CONNECTIONS_KICKED: &str = "valence_connections_kicked_total";
+const BYTES_RECEIVED: &str = "valence_bytes_received_total";
+const BYTES_SENT: &str = "valence_bytes_sent_total";
+const TICK_DURATION: &str = "valence_tick_duration_seconds";
+
+fn describe_metrics(mut metrics: ResMut<Metrics>) {
+    use MetricKind::*;
+
+    for (name, kind, help) in [
+        (ONLINE_PLAYERS, Gauge, "The number of connected clients."),
+        (
+            LOADED_CHUNKS,
+            Gauge,
+            "The number of loaded chunks of a chunk layer.",
+        ),
+        (
+            CACHE_HITS,
+            Counter,
+            "Chunks sent to clients from the packet cache of the chunk.",
+        ),
+        (
+            CACHE_MISSES,
+            Counter,
+            "Chunks whose packets had to be encoded.",
+        ),
+        (
+            CACHE_HIT_RATE,
+            Gauge,
+            "The fraction of chunks sent to clients from the packet cache.",
+        ),
+        (PACKETS_RECEIVED, Counter, "Packets received from clients."),
+        (
+            PACKETS_DROPPED,
+            Counter,
+            "Packets dropped by the rate limits.",
+        ),
+        (
+            CONNECTIONS_KICKED,
+            Counter,
+            "Connections closed by the rate limits.",
+        ),
+        (BYTES_RECEIVED, Counter, "Bytes received from clients."),
+        (BYTES_SENT, Counter, "Bytes sent to clients."),
+    ] {
+        metrics.describe(name, kind, help);
+    }
+
+    metrics.describe_histogram(
+        TICK_DURATION,
+        "How long ticks took to run.",
+        &[0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0],
+    );
+}
+
+fn collect_player_metrics(clients: Query<(), With<ClientMarker>>, mut metrics: ResMut<Metrics>) {
+    metrics.set_gauge(ONLINE_PLAYERS, &[], clients.iter().count() as f64);
+}
+
+fn collect_chunk_metrics(layers: Query<(Entity, &ChunkLayer)>, mut metrics: ResMut<Metrics>) {
+    // Start over so that despawned layers disappear.
+    for name in [LOADED_CHUNKS, CACHE_HITS, CACHE_MISSES, CACHE_HIT_RATE] {
+        metrics.clear(name);
+    }
+
+    for (entity, layer) in &layers {
+        let label = format!("{entity:?}");
+        let labels = &[("layer", label.as_str())];
+
+        metrics.set_gauge(LOADED_CHUNKS, labels, layer.chunks().count() as f64);
+
+        let stats = layer.init_packet_cache_stats();
+
+        metrics.set_counter(CACHE_HITS, labels, stats.hits);
+        metrics.set_counter(CACHE_MISSES, labels, stats.misses);
+
+        if let Some(hit_rate) = stats.hit_rate() {
+            metrics.set_gauge(CACHE_HIT_RATE, labels, hit_rate);
+        }
+    }
+}
+
+fn collect_network_metrics(shared: Option<Res<SharedNetworkState>>, mut metrics: ResMut<Metrics>) {
+    let Some(shared) = shared else {
+        return;
+    };
+
+    let network = shared.metrics();
+
+    metrics.set_counter(PACKETS_RECEIVED, &[], network.packets_received());
+    metrics.set_counter(PACKETS_DROPPED, &[], network.packets_dropped());
+    metrics.set_counter(CONNECTIONS_KICKED, &[], network.connections_kicked());
+    metrics.set_counter(BYTES_RECEIVED, &[], network.bytes_received());
+    metrics.set_counter(BYTES_SENT, &[], network.bytes_sent());
+}
+
+fn collect_tick_metrics(timings: Option<Res<TickTimings>>, mut metrics: ResMut<Metrics>) {
+    // The timings are those of the previous tick, and are zero before the
+    // first tick finishes.
+    if let Some(last_tick) = timings.map(|t| t.last_tick()).filter(|d| !d.is_zero()) {
+        metrics.observe(TICK_DURATION, &[], last_tick.as_secs_f64());
+    }
+}
+
+fn export_metrics(metrics: Res<Metrics>, mut exporters: ResMut<MetricsExporters>) {
+    for exporter in &mut exporters.exporters {
+        exporter.export(&metrics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn collect_and_export() {
+        let mut app = App::new();
+        app.add_plugins(MetricsPlugin);
+
+        let exported = Arc::new(Mutex::new(String::new()));
+        let exported_clone = exported.clone();
+
+        app.world
+            .resource_mut::<MetricsExporters>()
+            .add(move |metrics: &Metrics| {
+                *exported_clone.lock().unwrap() = metrics.encode_prometheus();
+            });
+
+        app.world.spawn(ClientMarker);
+        app.world.spawn(ClientMarker);
+
+        app.update();
+
+        assert_eq!(
+            app.world.resource::<Metrics>().value(ONLINE_PLAYERS, &[]),
+            Some(&MetricValue::Gauge(2.0))
+        );
+
+        let exported = exported.lock().unwrap();
+        assert!(
+            exported.contains("# HELP valence_online_players The number of connected clients.\n")
+        );
+        assert!(exported.contains("\nvalence_online_players 2\n"));
+    }
+}
@@ -0,0 +1,206 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{mem, thread};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use tracing::{error, info, warn};
+
+use crate::{Metrics, MetricsExporter, MetricsExporters};
+
+pub(crate) fn build(app: &mut App) {
+    app.init_resource::<PrometheusSettings>()
+        .add_systems(PostStartup, start_prometheus_endpoint);
+}
+
+/// Settings for the Prometheus endpoint. Changes have no effect after
+/// startup.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct PrometheusSettings {
+    /// The address to serve the metrics on, at `/metrics`, or `None` to
+    /// disable the endpoint.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub address: Option<SocketAddr>,
+}
+
+/// Serves [`Metrics`] over HTTP in the Prometheus text format. Added to the
+/// [`MetricsExporters`] automatically when [`PrometheusSettings::address`] is
+/// set.
+///
+/// Scrapes are answered with the metrics of the next tick, so the metrics are
+/// only encoded when they are scraped.
+pub struct PrometheusExporter {
+    /// The scrapes waiting for the next export.
+    scrapes: Arc<Mutex<Vec<Sender<String>>>>,
+    local_addr: SocketAddr,
+}
+
+impl PrometheusExporter {
+    /// Starts serving metrics on `address` from a new thread.
+    pub fn bind(address: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let local_addr = listener.local_addr()?;
+        let scrapes = Arc::new(Mutex::new(vec![]));
+        let thread_scrapes = scrapes.clone();
+
+        thread::Builder::new()
+            .name("prometheus endpoint".into())
+            .spawn(move || serve(listener, &thread_scrapes))?;
+
+        Ok(Self {
+            scrapes,
+            local_addr,
+        })
+    }
+
+    /// Returns the address metrics are served on, which tells the port the
+    /// system picked if the exporter was bound to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl MetricsExporter for PrometheusExporter {
+    fn export(&mut self, metrics: &Metrics) {
+        let scrapes = mem::take(&mut *self.scrapes.lock().unwrap());
+
+        if scrapes.is_empty() {
+            return;
+        }
+
+        let text = metrics.encode_prometheus();
+
+        for scrape in scrapes {
+            // The scrape may have timed out already.
+            let _ = scrape.send(text.clone());
+        }
+    }
+}
+
+fn start_prometheus_endpoint(
+    settings: Res<PrometheusSettings>,
+    mut exporters: ResMut<MetricsExporters>,
+) {
+    let Some(address) = settings.address else {
+        return;
+    };
+
+    match PrometheusExporter::bind(address) {
+        Ok(exporter) => {
+            info!(
+                "serving metrics on http://{}/metrics",
+                exporter.local_addr()
+            );
+            exporters.add(exporter);
+        }
+        Err(e) => error!("failed to start Prometheus endpoint on {address}: {e}"),
+    }
+}
+
+/// How long a scraper may take to send its request, and how long it waits for
+/// the next tick.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Answers scrapes one at a time, since there are rarely more than a few
+/// scrapers.
+fn serve(listener: TcpListener, scrapes: &Mutex<Vec<Sender<String>>>) {
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| handle_request(stream, scrapes));
+
+        if let Err(e) = result {
+            warn!("failed to answer metrics request: {e}");
+        }
+    }
+}
+
+fn handle_request(mut stream: TcpStream, scrapes: &Mutex<Vec<Sender<String>>>) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Skip the headers.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let (send, recv) = mpsc::channel();
+            scrapes.lock().unwrap().push(send);
+
+            match recv.recv_timeout(REQUEST_TIMEOUT) {
+                Ok(text) => ("200 OK", text),
+                Err(_) => (
+                    "503 Service Unavailable",
+                    "Server is not ticking\n".to_owned(),
+                ),
+            }
+        }
+        (Some("GET"), Some(_)) => ("404 Not Found", "Not Found\n".to_owned()),
+        _ => ("405 Method Not Allowed", "Method Not Allowed\n".to_owned()),
+    };
+
+    let mut response = format!("HTTP/1.1 {status}\r\n");
+    response.push_str("Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n");
+    response.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    response.push_str("Connection: close\r\n\r\n");
+    response.push_str(&body);
+
+    stream.write_all(response.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serve_metrics() {
+        let mut exporter = PrometheusExporter::bind((Ipv4Addr::LOCALHOST, 0).into()).unwrap();
+        let address = exporter.local_addr();
+
+        let mut metrics = Metrics::default();
+        metrics.set_gauge("valence_online_players", &[], 5.0);
+
+        // Nothing is encoded until a scrape arrives.
+        exporter.export(&metrics);
+
+        let scrape = thread::spawn(move || get(address, "/metrics"));
+
+        // Export every "tick" until the scrape is answered.
+        while !scrape.is_finished() {
+            exporter.export(&metrics);
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let response = scrape.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response
+            .ends_with("\r\n\r\n# TYPE valence_online_players gauge\nvalence_online_players 5\n"));
+        assert!(exporter.scrapes.lock().unwrap().is_empty());
+
+        assert!(get(address, "/").starts_with("HTTP/1.1 404"));
+    }
+}
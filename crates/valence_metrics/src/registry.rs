@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+use bevy_ecs::prelude::*;
+
+/// The bucket bounds of histograms which weren't given any with
+/// [`Metrics::describe_histogram`].
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The metrics of the server, by name.
+///
+/// Metrics are grouped in families which share a name, a kind and a help
+/// text. The series of a family are told apart by their labels. Families are
+/// created the first time they are used, but describing them beforehand adds
+/// a help text.
+///
+/// # Panics
+///
+/// Using a family as a kind other than the one it was created with panics.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct Metrics {
+    families: BTreeMap<String, MetricFamily>,
+}
+
+/// A group of series with the same name.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MetricFamily {
+    kind: MetricKind,
+    help: String,
+    buckets: Vec<f64>,
+    series: BTreeMap<Labels, MetricValue>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MetricKind {
+    /// A value which only ever increases, like the number of bytes sent.
+    Counter,
+    /// A value which goes up and down, like the number of players.
+    Gauge,
+    /// The distribution of observed values, like tick durations.
+    Histogram,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum MetricValue {
+    Counter(u64),
+    Gauge(f64),
+    Histogram(Histogram),
+}
+
+/// Observed values counted in buckets.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Histogram {
+    bounds: Vec<f64>,
+    /// The number of observations of every bucket, not including those of
+    /// smaller buckets. The last bucket is unbounded.
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+/// Label names and values, sorted by name.
+type Labels = Vec<(String, String)>;
+
+impl Metrics {
+    /// Adds a help text to a family, creating it if needed.
+    pub fn describe(&mut self, name: &str, kind: MetricKind, help: &str) {
+        self.family(name, kind).help = help.to_owned();
+    }
+
+    /// Adds a help text and the upper bounds of the buckets to a histogram
+    /// family. The bounds only apply to series created afterwards.
+    pub fn describe_histogram(&mut self, name: &str, help: &str, buckets: &[f64]) {
+        let family = self.family(name, MetricKind::Histogram);
+        family.help = help.to_owned();
+        family.buckets = buckets.to_vec();
+    }
+
+    /// Sets a counter, for counters kept elsewhere.
+    pub fn set_counter(&mut self, name: &str, labels: &[(&str, &str)], value: u64) {
+        *self.counter(name, labels) = value;
+    }
+
+    pub fn increment_counter(&mut self, name: &str, labels: &[(&str, &str)], by: u64) {
+        *self.counter(name, labels) += by;
+    }
+
+    pub fn set_gauge(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let family = self.family(name, MetricKind::Gauge);
+
+        family
+            .series
+            .insert(to_labels(labels), MetricValue::Gauge(value));
+    }
+
+    /// Adds an observation to a histogram.
+    pub fn observe(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let family = self.family(name, MetricKind::Histogram);
+        let buckets = &family.buckets;
+
+        let value_mut = family
+            .series
+            .entry(to_labels(labels))
+            .or_insert_with(|| MetricValue::Histogram(Histogram::new(buckets.clone())));
+
+        if let MetricValue::Histogram(histogram) = value_mut {
+            histogram.observe(value);
+        }
+    }
+
+    /// Removes every series of a family, like those of a despawned layer. The
+    /// help text is kept.
+    pub fn clear(&mut self, name: &str) {
+        if let Some(family) = self.families.get_mut(name) {
+            family.series.clear();
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MetricFamily> {
+        self.families.get(name)
+    }
+
+    /// Returns the value of a series.
+    pub fn value(&self, name: &str, labels: &[(&str, &str)]) -> Option<&MetricValue> {
+        self.families.get(name)?.series.get(&to_labels(labels))
+    }
+
+    /// Returns an iterator over the families, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MetricFamily)> + '_ {
+        self.families.iter().map(|(name, f)| (name.as_str(), f))
+    }
+
+    /// Encodes the metrics in the [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+    pub fn encode_prometheus(&self) -> String {
+        let mut out = String::new();
+        self.write_prometheus(&mut out).unwrap();
+        out
+    }
+
+    fn write_prometheus(&self, out: &mut String) -> fmt::Result {
+        for (name, family) in &self.families {
+            if !family.help.is_empty() {
+                let help = family.help.replace('\\', "\\\\").replace('\n', "\\n");
+                writeln!(out, "# HELP {name} {help}")?;
+            }
+
+            let kind = match family.kind {
+                MetricKind::Counter => "counter",
+                MetricKind::Gauge => "gauge",
+                MetricKind::Histogram => "histogram",
+            };
+
+            writeln!(out, "# TYPE {name} {kind}")?;
+
+            for (labels, value) in &family.series {
+                match value {
+                    MetricValue::Counter(v) => {
+                        writeln!(out, "{name}{} {v}", LabelSet(labels, None))?;
+                    }
+                    MetricValue::Gauge(v) => {
+                        writeln!(out, "{name}{} {}", LabelSet(labels, None), Float(*v))?;
+                    }
+                    MetricValue::Histogram(h) => {
+                        let mut cumulative = 0;
+
+                        for (i, count) in h.counts.iter().enumerate() {
+                            cumulative += count;
+                            let le = h.bounds.get(i).copied().unwrap_or(f64::INFINITY);
+
+                            writeln!(
+                                out,
+                                "{name}_bucket{} {cumulative}",
+                                LabelSet(labels, Some(le))
+                            )?;
+                        }
+
+                        let labels = LabelSet(labels, None);
+                        writeln!(out, "{name}_sum{labels} {}", Float(h.sum))?;
+                        writeln!(out, "{name}_count{labels} {cumulative}")?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn family(&mut self, name: &str, kind: MetricKind) -> &mut MetricFamily {
+        let family = self
+            .families
+            .entry(name.to_owned())
+            .or_insert_with(|| MetricFamily {
+                kind,
+                help: String::new(),
+                buckets: DEFAULT_BUCKETS.to_vec(),
+                series: BTreeMap::new(),
+            });
+
+        assert_eq!(
+            family.kind, kind,
+            "metric `{name}` was used as a {kind:?} but is a {:?}",
+            family.kind
+        );
+
+        family
+    }
+
+    fn counter(&mut self, name: &str, labels: &[(&str, &str)]) -> &mut u64 {
+        let family = self.family(name, MetricKind::Counter);
+
+        match family
+            .series
+            .entry(to_labels(labels))
+            .or_insert(MetricValue::Counter(0))
+        {
+            MetricValue::Counter(v) => v,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl MetricFamily {
+    pub fn kind(&self) -> MetricKind {
+        self.kind
+    }
+
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+
+    /// Returns an iterator over the labels and values of the series.
+    pub fn series(&self) -> impl Iterator<Item = (&[(String, String)], &MetricValue)> + '_ {
+        self.series
+            .iter()
+            .map(|(labels, value)| (labels.as_slice(), value))
+    }
+}
+
+impl Histogram {
+    fn new(bounds: Vec<f64>) -> Self {
+        Self {
+            counts: vec![0; bounds.len() + 1],
+            bounds,
+            sum: 0.0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.partition_point(|&bound| bound < value);
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+
+    /// The upper bounds of the buckets, excluding the unbounded last bucket.
+    pub fn bounds(&self) -> &[f64] {
+        &self.bounds
+    }
+
+    /// Returns the number of observations of every bucket, not including
+    /// those of smaller buckets.
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// The number of observations.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+fn to_labels(labels: &[(&str, &str)]) -> Labels {
+    let mut labels: Labels = labels
+        .iter()
+        .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+        .collect();
+
+    labels.sort();
+    labels
+}
+
+/// Formats labels like `{layer="3v0"}`, with an optional `le` label for
+/// histogram buckets.
+struct LabelSet<'a>(&'a [(String, String)], Option<f64>);
+
+impl fmt::Display for LabelSet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() && self.1.is_none() {
+            return Ok(());
+        }
+
+        f.write_char('{')?;
+
+        let le = self.1.map(|le| ("le".to_owned(), Float(le).to_string()));
+
+        for (i, (name, value)) in self.0.iter().chain(le.as_ref()).enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+
+            write!(f, "{name}=\"")?;
+
+            for c in value.chars() {
+                match c {
+                    '\\' => f.write_str("\\\\")?,
+                    '"' => f.write_str("\\\"")?,
+                    '\n' => f.write_str("\\n")?,
+                    c => f.write_char(c)?,
+                }
+            }
+
+            f.write_char('"')?;
+        }
+
+        f.write_char('}')
+    }
+}
+
+/// Formats floats the way Prometheus expects.
+struct Float(f64);
+
+impl fmt::Display for Float {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_nan() {
+            f.write_str("NaN")
+        } else if self.0 == f64::INFINITY {
+            f.write_str("+Inf")
+        } else if self.0 == f64::NEG_INFINITY {
+            f.write_str("-Inf")
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_prometheus_text() {
+        let mut metrics = Metrics::default();
+
+        metrics.describe("players", MetricKind::Gauge, "Players online.");
+        metrics.set_gauge("players", &[], 3.0);
+
+        metrics.increment_counter("bytes_total", &[("layer", "a\"b")], 5);
+        metrics.increment_counter("bytes_total", &[("layer", "a\"b")], 2);
+
+        metrics.describe_histogram("tick_seconds", "Tick durations.", &[0.01, 0.05]);
+        metrics.observe("tick_seconds", &[], 0.005);
+        metrics.observe("tick_seconds", &[], 0.01);
+        metrics.observe("tick_seconds", &[], 0.5);
+
+        assert_eq!(
+            metrics.encode_prometheus(),
+            "# TYPE bytes_total counter\nbytes_total{layer=\"a\\\"b\"} 7\n# HELP players Players \
+             online.\n# TYPE players gauge\nplayers 3\n# HELP tick_seconds Tick durations.\n# \
+             TYPE tick_seconds histogram\ntick_seconds_bucket{le=\"0.01\"} \
+             2\ntick_seconds_bucket{le=\"0.05\"} 2\ntick_seconds_bucket{le=\"+Inf\"} \
+             3\ntick_seconds_sum 0.515\ntick_seconds_count 3\n"
+        );
+    }
+
+    #[test]
+    fn labels_are_order_independent() {
+        let mut metrics = Metrics::default();
+
+        metrics.set_counter("c", &[("a", "1"), ("b", "2")], 4);
+
+        assert_eq!(
+            metrics.value("c", &[("b", "2"), ("a", "1")]),
+            Some(&MetricValue::Counter(4))
+        );
+
+        metrics.clear("c");
+        assert!(metrics.value("c", &[("a", "1"), ("b", "2")]).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn kind_mismatch_panics() {
+        let mut metrics = Metrics::default();
+
+        metrics.set_gauge("m", &[], 1.0);
+        metrics.increment_counter("m", &[], 1);
+    }
+}
@@ -0,0 +1,102 @@
+//! Helpers for the lifecycle of per-game arena layers.
+//!
+//! A typical game creates an arena with [`spawn_arena`], or copies a prepared
+//! map with [`clone_arena`], moves its players in
+//! with [`ArenaPlayerQuery`], and finally removes it with [`TearDownArena`]
+//! which sends every remaining player back to a fallback layer.
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::WorldQuery;
+use bevy_ecs::system::Command;
+use valence_server::client::{Client, VisibleChunkLayer, VisibleEntityLayers};
+use valence_server::entity::{EntityLayerId, Position};
+use valence_server::layer::clone::CloneLayer;
+use valence_server::math::DVec3;
+use valence_server::{Despawned, LayerBundle};
+
+/// Marker component for layers created with [`spawn_arena`] or
+/// [`clone_arena`].
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Arena;
+
+/// Spawns `layer` as an arena and returns the layer entity.
+pub fn spawn_arena(commands: &mut Commands, layer: LayerBundle) -> Entity {
+    commands.spawn((layer, Arena)).id()
+}
+
+/// Spawns a copy of the layer entity `template` and its entities as an arena,
+/// and returns the new layer entity. The chunks of the copy share their data
+/// with the template until they are modified, so every match can have its
+/// own copy of a large map. See [`CloneLayer`].
+pub fn clone_arena(commands: &mut Commands, template: Entity) -> Entity {
+    let arena = commands.spawn(Arena).id();
+    commands.add(CloneLayer::new(template, arena).with_entities());
+    arena
+}
+
+/// Query for moving a client between layers.
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct ArenaPlayerQuery {
+    pub layer: &'static mut EntityLayerId,
+    pub visible_chunk_layer: &'static mut VisibleChunkLayer,
+    pub visible_entity_layers: &'static mut VisibleEntityLayers,
+    pub position: &'static mut Position,
+}
+
+impl ArenaPlayerQueryItem<'_> {
+    /// Moves the client to `position` in the layer entity `layer`, which must
+    /// have both a chunk layer and an entity layer.
+    pub fn move_to(&mut self, layer: Entity, position: impl Into<DVec3>) {
+        self.layer.0 = layer;
+        self.visible_chunk_layer.0 = layer;
+        self.visible_entity_layers.0.clear();
+        self.visible_entity_layers.0.insert(layer);
+        self.position.set(position);
+    }
+
+    /// Returns whether the client is in the layer entity `layer`.
+    pub fn is_in(&self, layer: Entity) -> bool {
+        self.layer.0 == layer
+    }
+}
+
+/// [`Command`] that despawns an arena layer after moving the clients still
+/// inside of it to `fallback_position` in `fallback_layer`. The other
+/// entities on the arena's entity layer are despawned with it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TearDownArena {
+    pub arena: Entity,
+    pub fallback_layer: Entity,
+    pub fallback_position: DVec3,
+}
+
+impl Command for TearDownArena {
+    fn apply(self, world: &mut World) {
+        let mut players = world.query_filtered::<ArenaPlayerQuery, With<Client>>();
+
+        for mut player in players.iter_mut(world) {
+            if player.is_in(self.arena) {
+                player.move_to(self.fallback_layer, self.fallback_position);
+            }
+        }
+
+        let mut entities = world.query_filtered::<(Entity, &EntityLayerId), Without<Client>>();
+
+        let leftover: Vec<_> = entities
+            .iter(world)
+            .filter(|(entity, layer)| layer.0 == self.arena && *entity != self.arena)
+            .map(|(entity, _)| entity)
+            .collect();
+
+        for entity in leftover {
+            world.entity_mut(entity).insert(Despawned);
+        }
+
+        if let Some(mut arena) = world.get_entity_mut(self.arena) {
+            // The layer is removed at the end of the tick so that despawn
+            // packets can still be sent.
+            arena.insert(Despawned);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use bevy_ecs::prelude::*;
+use valence_server::client::{Client, VisibleChunkLayer};
+use valence_server::entity::Position;
+use valence_server::math::DVec3;
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::text::{Color, IntoText};
+use valence_server::title::SetTitle;
+
+/// Component which counts down the remaining ticks of a timer. Insert this on
+/// a layer entity to announce the remaining seconds to all clients viewing
+/// that layer.
+///
+/// The component is removed and a [`CountdownFinishedEvent`] is sent once the
+/// countdown reaches zero.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct Countdown {
+    /// The number of ticks left.
+    pub remaining_ticks: u64,
+    /// Whether the remaining seconds should be shown to the clients viewing
+    /// the layer.
+    pub announce: bool,
+}
+
+impl Countdown {
+    pub fn from_seconds(seconds: u64) -> Self {
+        Self {
+            remaining_ticks: seconds * 20,
+            announce: true,
+        }
+    }
+}
+
+/// Sent when a [`Countdown`] reaches zero.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CountdownFinishedEvent {
+    /// The entity the countdown was on.
+    pub entity: Entity,
+}
+
+pub(crate) fn tick_countdowns(
+    mut countdowns: Query<(Entity, &mut Countdown)>,
+    mut clients: Query<(&mut Client, &Position, &VisibleChunkLayer)>,
+    mut finished: EventWriter<CountdownFinishedEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut countdown) in &mut countdowns {
+        if countdown.remaining_ticks == 0 {
+            commands.entity(entity).remove::<Countdown>();
+            finished.send(CountdownFinishedEvent { entity });
+            continue;
+        }
+
+        if countdown.announce {
+            for (mut client, pos, visible_layer) in &mut clients {
+                if visible_layer.0 == entity {
+                    announce(&mut client, pos.0, countdown.remaining_ticks);
+                }
+            }
+        }
+
+        countdown.remaining_ticks -= 1;
+    }
+}
+
+/// Shows the number of seconds left on whole seconds. The last few seconds are
+/// shown as a title with a tick sound, the rest in the action bar.
+pub(crate) fn announce(client: &mut Client, position: DVec3, remaining_ticks: u64) {
+    if !remaining_ticks.is_multiple_of(20) {
+        return;
+    }
+
+    let seconds = remaining_ticks / 20;
+
+    if seconds <= 5 {
+        let color = match seconds {
+            3.. => Color::GREEN,
+            2 => Color::YELLOW,
+            _ => Color::RED,
+        };
+
+        client.set_title_times(0, 25, 5);
+        client.set_title(seconds.to_string().color(color));
+        client.play_sound(
+            Sound::BlockNoteBlockHat,
+            SoundCategory::Master,
+            position,
+            1.0,
+            1.0,
+        );
+    } else if seconds.is_multiple_of(10) || seconds < 10 {
+        client.set_action_bar(format!("Starting in {seconds} seconds"));
+    }
+}
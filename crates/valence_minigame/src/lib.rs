@@ -0,0 +1,171 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+pub mod arena;
+mod countdown;
+mod team;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use countdown::{Countdown, CountdownFinishedEvent};
+pub use team::balance_teams;
+use valence_server::client::Client;
+use valence_server::entity::Position;
+use valence_server::Despawned;
+
+pub struct MinigamePlugin;
+
+impl Plugin for MinigamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameState>()
+            .init_resource::<LobbySettings>()
+            .add_event::<GamePhaseChangeEvent>()
+            .add_event::<CountdownFinishedEvent>()
+            .add_systems(
+                Update,
+                (
+                    auto_advance_game_state,
+                    countdown::tick_countdowns,
+                    tick_game_state.after(auto_advance_game_state),
+                ),
+            );
+    }
+}
+
+/// The phases a game goes through.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub enum GamePhase {
+    /// Waiting for enough players to join.
+    #[default]
+    Waiting,
+    /// Enough players are present and the game is about to start.
+    Starting,
+    /// The game is in progress.
+    Running,
+    /// The game is over and the results are being shown.
+    Ending,
+}
+
+/// Resource containing the current [`GamePhase`] of the game.
+///
+/// Changing the phase with [`GameState::set_phase`] sends a
+/// [`GamePhaseChangeEvent`].
+#[derive(Resource, Default, Debug)]
+pub struct GameState {
+    phase: GamePhase,
+    ticks_in_phase: u64,
+    transitions: Vec<(GamePhase, GamePhase)>,
+}
+
+impl GameState {
+    pub fn phase(&self) -> GamePhase {
+        self.phase
+    }
+
+    /// The number of ticks since the current phase was entered.
+    pub fn ticks_in_phase(&self) -> u64 {
+        self.ticks_in_phase
+    }
+
+    /// Enters `phase`. Does nothing if the game is already in `phase`.
+    pub fn set_phase(&mut self, phase: GamePhase) {
+        if self.phase != phase {
+            self.transitions.push((self.phase, phase));
+            self.phase = phase;
+            self.ticks_in_phase = 0;
+        }
+    }
+}
+
+/// Sent when the [`GamePhase`] of the [`GameState`] changes.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GamePhaseChangeEvent {
+    pub from: GamePhase,
+    pub to: GamePhase,
+}
+
+/// Settings for automatically advancing the [`GameState`] based on the number
+/// of connected players.
+#[derive(Resource, Clone, Debug)]
+pub struct LobbySettings {
+    /// Whether the game should advance from [`GamePhase::Waiting`] to
+    /// [`GamePhase::Starting`] to [`GamePhase::Running`] on its own. If the
+    /// player count drops below `min_players` while starting, the game goes
+    /// back to waiting.
+    pub auto_start: bool,
+    /// The number of players needed to start the game.
+    pub min_players: usize,
+    /// The number of ticks spent in [`GamePhase::Starting`].
+    pub countdown_ticks: u32,
+}
+
+impl Default for LobbySettings {
+    fn default() -> Self {
+        Self {
+            auto_start: false,
+            min_players: 2,
+            countdown_ticks: 10 * 20,
+        }
+    }
+}
+
+fn auto_advance_game_state(
+    mut state: ResMut<GameState>,
+    settings: Res<LobbySettings>,
+    mut clients: Query<(&mut Client, &Position), Without<Despawned>>,
+) {
+    if !settings.auto_start {
+        return;
+    }
+
+    let players = clients.iter().len();
+
+    match state.phase() {
+        GamePhase::Waiting if players >= settings.min_players => {
+            state.set_phase(GamePhase::Starting);
+        }
+        GamePhase::Starting if players < settings.min_players => {
+            state.set_phase(GamePhase::Waiting);
+        }
+        GamePhase::Starting => {
+            let elapsed = state.ticks_in_phase();
+            let total = settings.countdown_ticks as u64;
+
+            if elapsed >= total {
+                state.set_phase(GamePhase::Running);
+            } else {
+                for (mut client, pos) in &mut clients {
+                    countdown::announce(&mut client, pos.0, total - elapsed);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn tick_game_state(mut state: ResMut<GameState>, mut events: EventWriter<GamePhaseChangeEvent>) {
+    let state = &mut *state;
+
+    for (from, to) in state.transitions.drain(..) {
+        events.send(GamePhaseChangeEvent { from, to });
+    }
+
+    state.ticks_in_phase += 1;
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Assigns every unassigned player to one of `teams` so that the teams stay
+/// as even as possible.
+///
+/// `players` contains every player along with the team they are already on,
+/// if any. Players that already have a team keep it. Ties are broken by the
+/// order of `teams`.
+///
+/// Returns the new assignments for the players that did not have a team.
+///
+/// ```
+/// # use valence_minigame::balance_teams;
+/// let players = [(1, Some("red")), (2, None), (3, None), (4, Some("red"))];
+/// let assignments = balance_teams(players, &["red", "blue"]);
+///
+/// assert_eq!(assignments, [(2, "blue"), (3, "blue")]);
+/// ```
+pub fn balance_teams<P, T>(
+    players: impl IntoIterator<Item = (P, Option<T>)>,
+    teams: &[T],
+) -> Vec<(P, T)>
+where
+    T: Clone + Eq + Hash,
+{
+    let mut counts: HashMap<T, usize> = teams.iter().map(|t| (t.clone(), 0)).collect();
+    let mut unassigned = vec![];
+
+    for (player, team) in players {
+        match team {
+            Some(team) => {
+                if let Some(count) = counts.get_mut(&team) {
+                    *count += 1;
+                }
+            }
+            None => unassigned.push(player),
+        }
+    }
+
+    let mut assignments = Vec::with_capacity(unassigned.len());
+
+    for player in unassigned {
+        let Some(team) = teams.iter().min_by_key(|t| counts[*t]) else {
+            break;
+        };
+
+        *counts.get_mut(team).unwrap() += 1;
+        assignments.push((player, team.clone()));
+    }
+
+    assignments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balance_empty_teams() {
+        let players = (0..5).map(|p| (p, None));
+        let assignments = balance_teams(players, &['a', 'b', 'c']);
+
+        assert_eq!(
+            assignments,
+            [(0, 'a'), (1, 'b'), (2, 'c'), (3, 'a'), (4, 'b')]
+        );
+    }
+
+    #[test]
+    fn balance_without_teams() {
+        let players = [(0, None::<u8>)];
+
+        assert!(balance_teams(players, &[]).is_empty());
+    }
+}
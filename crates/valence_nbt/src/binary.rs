@@ -42,18 +42,20 @@ mod decode;
 mod encode;
 mod error;
 mod modified_utf8;
+mod stream;
 #[cfg(test)]
 mod tests;
 
 pub use decode::{from_binary, FromModifiedUtf8, FromModifiedUtf8Error};
 pub use encode::{to_binary, written_size, ToModifiedUtf8};
 pub use error::*;
+pub use stream::StreamReader;
 
 use crate::Tag;
 
 impl Tag {
     /// Returns the name of this tag for error reporting purposes.
-    const fn name(self) -> &'static str {
+    pub(crate) const fn name(self) -> &'static str {
         match self {
             Tag::End => "end",
             Tag::Byte => "byte",
@@ -36,12 +36,12 @@ where
 }
 
 /// Maximum recursion depth to prevent overflowing the call stack.
-const MAX_DEPTH: usize = 512;
+pub(super) const MAX_DEPTH: usize = 512;
 
-struct DecodeState<'a, 'de> {
-    slice: &'a mut &'de [u8],
+pub(super) struct DecodeState<'a, 'de> {
+    pub(super) slice: &'a mut &'de [u8],
     /// Current recursion depth.
-    depth: usize,
+    pub(super) depth: usize,
 }
 
 impl<'de> DecodeState<'_, 'de> {
@@ -57,7 +57,7 @@ impl<'de> DecodeState<'_, 'de> {
         res
     }
 
-    fn read_tag(&mut self) -> Result<Tag> {
+    pub(super) fn read_tag(&mut self) -> Result<Tag> {
         match self.slice.read_u8()? {
             0 => Ok(Tag::End),
             1 => Ok(Tag::Byte),
@@ -76,7 +76,7 @@ impl<'de> DecodeState<'_, 'de> {
         }
     }
 
-    fn read_value<S>(&mut self, tag: Tag) -> Result<Value<S>>
+    pub(super) fn read_value<S>(&mut self, tag: Tag) -> Result<Value<S>>
     where
         S: FromModifiedUtf8<'de> + Hash + Ord,
     {
@@ -105,7 +105,7 @@ impl<'de> DecodeState<'_, 'de> {
         Ok(self.slice.read_i16::<BigEndian>()?)
     }
 
-    fn read_int(&mut self) -> Result<i32> {
+    pub(super) fn read_int(&mut self) -> Result<i32> {
         Ok(self.slice.read_i32::<BigEndian>()?)
     }
 
@@ -144,7 +144,7 @@ impl<'de> DecodeState<'_, 'de> {
         Ok(array)
     }
 
-    fn read_string<S>(&mut self) -> Result<S>
+    pub(super) fn read_string<S>(&mut self) -> Result<S>
     where
         S: FromModifiedUtf8<'de>,
     {
@@ -267,6 +267,91 @@ impl<'de> DecodeState<'_, 'de> {
         }
     }
 
+    /// Skips over a value without decoding it.
+    pub(super) fn skip_value(&mut self, tag: Tag) -> Result<()> {
+        match tag {
+            Tag::End => unreachable!("illegal TAG_End argument"),
+            Tag::Byte => self.skip_bytes(1, tag),
+            Tag::Short => self.skip_bytes(2, tag),
+            Tag::Int | Tag::Float => self.skip_bytes(4, tag),
+            Tag::Long | Tag::Double => self.skip_bytes(8, tag),
+            Tag::ByteArray => self.skip_array(1, tag),
+            Tag::String => {
+                let len = self.slice.read_u16::<BigEndian>()?.into();
+                self.skip_bytes(len, tag)
+            }
+            Tag::List => self.check_depth(|st| {
+                let elem_type = st.read_tag()?;
+                let len = st.read_int()?;
+
+                if len.is_negative() {
+                    return Err(Error::new_owned(format!(
+                        "negative {} list length of {len}",
+                        elem_type.name()
+                    )));
+                }
+
+                let elem_size = match elem_type {
+                    Tag::End if len != 0 => {
+                        return Err(Error::new_owned(format!(
+                            "TAG_End list with nonzero length of {len}"
+                        )))
+                    }
+                    Tag::End => return Ok(()),
+                    Tag::Byte => 1,
+                    Tag::Short => 2,
+                    Tag::Int | Tag::Float => 4,
+                    Tag::Long | Tag::Double => 8,
+                    _ => {
+                        for _ in 0..len {
+                            st.skip_value(elem_type)?;
+                        }
+
+                        return Ok(());
+                    }
+                };
+
+                st.skip_bytes(len as usize * elem_size, Tag::List)
+            }),
+            Tag::Compound => self.check_depth(|st| loop {
+                let tag = st.read_tag()?;
+                if tag == Tag::End {
+                    return Ok(());
+                }
+
+                st.skip_value(Tag::String)?;
+                st.skip_value(tag)?;
+            }),
+            Tag::IntArray => self.skip_array(4, tag),
+            Tag::LongArray => self.skip_array(8, tag),
+        }
+    }
+
+    fn skip_array(&mut self, elem_size: usize, tag: Tag) -> Result<()> {
+        let len = self.read_int()?;
+
+        if len.is_negative() {
+            return Err(Error::new_owned(format!(
+                "negative {} length of {len}",
+                tag.name()
+            )));
+        }
+
+        self.skip_bytes(len as usize * elem_size, tag)
+    }
+
+    fn skip_bytes(&mut self, len: usize, tag: Tag) -> Result<()> {
+        if len > self.slice.len() {
+            return Err(Error::new_owned(format!(
+                "{} of {len} bytes exceeds remainder of input",
+                tag.name()
+            )));
+        }
+
+        *self.slice = &self.slice[len..];
+        Ok(())
+    }
+
     fn read_int_array(&mut self) -> Result<Vec<i32>> {
         let len = self.read_int()?;
 
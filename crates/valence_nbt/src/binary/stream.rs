@@ -0,0 +1,222 @@
+use std::borrow::Cow;
+use std::hash::Hash;
+
+use super::decode::{DecodeState, MAX_DEPTH};
+use super::{Error, FromModifiedUtf8, Result};
+use crate::tag::Tag;
+use crate::Value;
+
+/// A pull-based reader over uncompressed NBT binary data, which reads one tag
+/// at a time instead of building the whole [`Compound`](crate::Compound) in
+/// memory.
+///
+/// [`next_entry`](Self::next_entry) returns the type and name of the next tag
+/// in the compound or list the reader is in. Its value can then be read,
+/// [entered](Self::enter) to read its tags one at a time, or ignored, in which
+/// case it is skipped without being decoded.
+///
+/// # Examples
+///
+/// Reading only the heightmaps of a chunk:
+///
+/// ```
+/// use valence_nbt::binary::StreamReader;
+/// use valence_nbt::{compound, to_binary, Compound, Tag, Value};
+///
+/// let chunk = compound! {
+///     "sections" => valence_nbt::List::Compound(vec![Compound::new(); 24]),
+///     "Heightmaps" => compound! {
+///         "MOTION_BLOCKING" => vec![0_i64; 37],
+///     },
+///     "Status" => "minecraft:full",
+/// };
+///
+/// let mut buf = vec![];
+/// to_binary(&chunk, &mut buf, "").unwrap();
+///
+/// let mut slice = buf.as_slice();
+/// let (mut reader, _) = StreamReader::new(&mut slice).unwrap();
+/// let mut heightmaps = None;
+///
+/// while let Some((tag, name)) = reader.next_entry().unwrap() {
+///     if tag == Tag::Compound && name == "Heightmaps" {
+///         heightmaps = Some(reader.read_value::<String>().unwrap());
+///     }
+/// }
+///
+/// assert!(matches!(heightmaps, Some(Value::Compound(_))));
+/// ```
+pub struct StreamReader<'a, 'de> {
+    slice: &'a mut &'de [u8],
+    /// The compounds and lists the reader is in, innermost last.
+    stack: Vec<Frame>,
+    /// The type of the value of the last entry, if it hasn't been read yet.
+    pending: Option<Tag>,
+}
+
+enum Frame {
+    Compound,
+    List { elem_type: Tag, remaining: u32 },
+}
+
+impl<'a, 'de> StreamReader<'a, 'de> {
+    /// Starts reading the root compound at the start of `slice`. The slice is
+    /// advanced as tags are read.
+    ///
+    /// The string returned in the tuple is the name of the root compound
+    /// (typically the empty string).
+    pub fn new(slice: &'a mut &'de [u8]) -> Result<(Self, Cow<'de, str>)> {
+        let mut state = DecodeState { slice, depth: 0 };
+
+        let root_tag = state.read_tag()?;
+
+        if root_tag != Tag::Compound {
+            return Err(Error::new_owned(format!(
+                "expected root tag for compound (got {})",
+                root_tag.name(),
+            )));
+        }
+
+        let root_name = state.read_string::<Cow<str>>()?;
+
+        let reader = Self {
+            slice: state.slice,
+            stack: vec![Frame::Compound],
+            pending: None,
+        };
+
+        Ok((reader, root_name))
+    }
+
+    fn state(&mut self) -> DecodeState<'_, 'de> {
+        DecodeState {
+            slice: self.slice,
+            depth: self.stack.len(),
+        }
+    }
+
+    /// Returns the type and name of the next tag in the current compound, or
+    /// the type of the next element of the current list with an empty name.
+    /// The value of the previous entry is skipped if it wasn't read.
+    ///
+    /// Returns `None` at the end of the current compound or list, after which
+    /// the reader continues in its parent. Once the root compound has ended,
+    /// `None` is always returned.
+    pub fn next_entry(&mut self) -> Result<Option<(Tag, Cow<'de, str>)>> {
+        self.skip_value()?;
+
+        match self.stack.last_mut() {
+            None => Ok(None),
+            Some(Frame::Compound) => {
+                let mut state = self.state();
+                let tag = state.read_tag()?;
+
+                if tag == Tag::End {
+                    self.stack.pop();
+                    return Ok(None);
+                }
+
+                let name = state.read_string::<Cow<str>>()?;
+
+                self.pending = Some(tag);
+                Ok(Some((tag, name)))
+            }
+            Some(Frame::List { remaining: 0, .. }) => {
+                self.stack.pop();
+                Ok(None)
+            }
+            Some(Frame::List {
+                elem_type,
+                remaining,
+            }) => {
+                *remaining -= 1;
+
+                self.pending = Some(*elem_type);
+                Ok(Some((*elem_type, Cow::Borrowed(""))))
+            }
+        }
+    }
+
+    fn take_pending(&mut self) -> Result<Tag> {
+        self.pending
+            .take()
+            .ok_or_else(|| Error::new_static("no entry value to read"))
+    }
+
+    /// Decodes the value of the last entry returned by
+    /// [`next_entry`](Self::next_entry).
+    pub fn read_value<S>(&mut self) -> Result<Value<S>>
+    where
+        S: FromModifiedUtf8<'de> + Hash + Ord,
+    {
+        let tag = self.take_pending()?;
+        self.state().read_value(tag)
+    }
+
+    /// Skips the value of the last entry returned by
+    /// [`next_entry`](Self::next_entry) without decoding it. Does nothing if
+    /// the value was already read or skipped.
+    pub fn skip_value(&mut self) -> Result<()> {
+        match self.pending.take() {
+            Some(tag) => self.state().skip_value(tag),
+            None => Ok(()),
+        }
+    }
+
+    /// Enters the compound or list of the last entry returned by
+    /// [`next_entry`](Self::next_entry), so that `next_entry` returns its
+    /// tags. Returns the number of elements if it is a list.
+    pub fn enter(&mut self) -> Result<Option<usize>> {
+        let tag = self.take_pending()?;
+
+        if self.stack.len() >= MAX_DEPTH {
+            return Err(Error::new_static("reached maximum recursion depth"));
+        }
+
+        match tag {
+            Tag::Compound => {
+                self.stack.push(Frame::Compound);
+                Ok(None)
+            }
+            Tag::List => {
+                let mut state = self.state();
+                let elem_type = state.read_tag()?;
+                let len = state.read_int()?;
+
+                if len.is_negative() {
+                    return Err(Error::new_owned(format!(
+                        "negative {} list length of {len}",
+                        elem_type.name()
+                    )));
+                }
+
+                if elem_type == Tag::End && len != 0 {
+                    return Err(Error::new_owned(format!(
+                        "TAG_End list with nonzero length of {len}"
+                    )));
+                }
+
+                self.stack.push(Frame::List {
+                    elem_type,
+                    remaining: len as u32,
+                });
+
+                Ok(Some(len as usize))
+            }
+            tag => {
+                self.pending = Some(tag);
+
+                Err(Error::new_owned(format!(
+                    "cannot enter {}, only compounds and lists",
+                    tag.name()
+                )))
+            }
+        }
+    }
+
+    /// Returns how many compounds and lists the reader is in, including the
+    /// root compound.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::binary::written_size;
+use crate::binary::{written_size, StreamReader};
 use crate::tag::Tag;
 use crate::{compound, from_binary, to_binary, Compound, List, Value};
 
@@ -98,6 +98,113 @@ fn correct_length() {
     assert_eq!(written_size(&c, "abc"), buf.len());
 }
 
+#[test]
+fn stream_read_and_skip() {
+    let c = example_compound();
+
+    let mut buf = vec![];
+    to_binary(&c, &mut buf, ROOT_NAME).unwrap();
+
+    // Reading every value gives back the compound.
+    let mut slice = buf.as_slice();
+    let (mut reader, root_name) = StreamReader::new(&mut slice).unwrap();
+    let mut decoded = Compound::new();
+
+    assert_eq!(root_name, ROOT_NAME);
+
+    while let Some((tag, name)) = reader.next_entry().unwrap() {
+        let value = reader.read_value::<String>().unwrap();
+        assert_eq!(value.tag(), tag);
+        decoded.insert(name, value);
+    }
+
+    assert_eq!(decoded, c);
+    assert!(reader.next_entry().unwrap().is_none());
+    assert!(slice.is_empty());
+
+    // Skipping every value ends in the same place.
+    let mut slice = buf.as_slice();
+    let (mut reader, _) = StreamReader::new(&mut slice).unwrap();
+
+    while reader.next_entry().unwrap().is_some() {}
+
+    assert_eq!(reader.depth(), 0);
+    assert!(slice.is_empty());
+}
+
+#[test]
+fn stream_enter() {
+    let mut buf = vec![];
+    to_binary(&example_compound(), &mut buf, "").unwrap();
+
+    let mut slice = buf.as_slice();
+    let (mut reader, _) = StreamReader::new(&mut slice).unwrap();
+    let mut longs = vec![];
+    let mut names = vec![];
+
+    while let Some((_, name)) = reader.next_entry().unwrap() {
+        names.push(name.into_owned());
+
+        if names.last().unwrap() != "list_of_compound" {
+            continue;
+        }
+
+        assert_eq!(reader.enter().unwrap(), Some(3));
+
+        while let Some((tag, name)) = reader.next_entry().unwrap() {
+            assert_eq!(tag, Tag::Compound);
+            assert_eq!(name, "");
+            assert_eq!(reader.enter().unwrap(), None);
+            assert_eq!(reader.depth(), 3);
+
+            while let Some((_, name)) = reader.next_entry().unwrap() {
+                if name == "long" {
+                    longs.push(reader.read_value::<String>().unwrap());
+                }
+            }
+        }
+
+        assert_eq!(reader.depth(), 1);
+    }
+
+    assert_eq!(longs, vec![Value::Long(i64::MAX); 3]);
+    assert_eq!(names.len(), example_compound().len());
+    assert!(slice.is_empty());
+}
+
+#[test]
+fn stream_errors() {
+    let mut buf = vec![];
+    to_binary(&compound!("byte" => 1_i8), &mut buf, "").unwrap();
+
+    let mut slice = buf.as_slice();
+    let (mut reader, _) = StreamReader::new(&mut slice).unwrap();
+
+    assert!(reader.read_value::<String>().is_err());
+
+    reader.next_entry().unwrap();
+    assert!(reader.enter().is_err());
+    assert_eq!(reader.read_value::<String>().unwrap(), Value::Byte(1));
+
+    // Truncated input.
+    let mut slice = &buf[..buf.len() - 2];
+    let (mut reader, _) = StreamReader::new(&mut slice).unwrap();
+    assert!(reader.next_entry().unwrap().is_some());
+    assert!(reader.next_entry().is_err());
+
+    // Deeply nested compounds are skipped without overflowing the stack.
+    let mut buf = vec![Tag::Compound as u8, 0, 0];
+
+    for _ in 0..10_000 {
+        buf.extend([Tag::Compound as u8, 0, 0]);
+    }
+
+    let mut slice = buf.as_slice();
+    let (mut reader, _) = StreamReader::new(&mut slice).unwrap();
+    assert!(reader.next_entry().unwrap().is_some());
+    assert!(reader.next_entry().is_err());
+}
+
 fn example_compound() -> Compound {
     fn inner() -> Compound {
         compound! {
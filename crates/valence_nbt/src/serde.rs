@@ -1,8 +1,12 @@
 use std::fmt;
 
+#[cfg(feature = "binary")]
+pub use binary::from_binary_borrowed;
 pub use ser::*;
 use thiserror::Error;
 
+#[cfg(feature = "binary")]
+mod binary;
 mod de;
 mod ser;
 #[cfg(test)]
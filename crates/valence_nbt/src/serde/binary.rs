@@ -0,0 +1,415 @@
+use std::borrow::Cow;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use serde::de::value::{BorrowedStrDeserializer, StringDeserializer};
+use serde::de::{DeserializeSeed, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer};
+
+use super::Error;
+use crate::Tag;
+
+/// Deserializes a `T` directly from uncompressed NBT binary data, without
+/// decoding a [`Compound`](crate::Compound) first.
+///
+/// Strings and byte arrays are borrowed from `slice` when `T` asks for them,
+/// such as with `&'de str`, `&'de [u8]` or `Cow<'de, str>` fields marked with
+/// `#[serde(borrow)]`. Strings are only borrowed if their modified UTF-8
+/// encoding is also valid UTF-8, which is true unless they contain null
+/// characters or characters outside the Basic Multilingual Plane. Use
+/// `Cow<'de, str>` for strings which could contain those. Byte arrays can also
+/// be deserialized as sequences, like `Vec<i8>`.
+///
+/// The string returned in the tuple is the name of the root compound
+/// (typically the empty string).
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use serde::Deserialize;
+/// use valence_nbt::serde::from_binary_borrowed;
+/// use valence_nbt::{compound, to_binary};
+///
+/// #[derive(Deserialize)]
+/// struct Sign<'a> {
+///     id: &'a str,
+///     #[serde(borrow)]
+///     text: Cow<'a, str>,
+///     data: &'a [u8],
+/// }
+///
+/// let mut buf = vec![];
+///
+/// let c = compound! {
+///     "id" => "minecraft:sign",
+///     "text" => "hello",
+///     "data" => vec![1_i8, 2, 3],
+/// };
+///
+/// to_binary(&c, &mut buf, "").unwrap();
+///
+/// let (sign, _) = from_binary_borrowed::<Sign>(&mut buf.as_slice()).unwrap();
+///
+/// assert_eq!(sign.id, "minecraft:sign");
+/// assert!(matches!(sign.text, Cow::Borrowed("hello")));
+/// assert_eq!(sign.data, [1, 2, 3]);
+/// ```
+pub fn from_binary_borrowed<'de, T>(slice: &mut &'de [u8]) -> Result<(T, Cow<'de, str>), Error>
+where
+    T: Deserialize<'de>,
+{
+    let mut reader = Reader { slice, depth: 0 };
+
+    let root_tag = reader.read_tag()?;
+
+    if root_tag != Tag::Compound {
+        return Err(Error::new(format!(
+            "expected root tag for compound (got {})",
+            root_tag.name()
+        )));
+    }
+
+    let root_name = reader.read_string()?;
+
+    let value = T::deserialize(ValueDeserializer {
+        reader: &mut reader,
+        tag: Tag::Compound,
+    })?;
+
+    Ok((value, root_name))
+}
+
+/// Maximum recursion depth to prevent overflowing the call stack.
+const MAX_DEPTH: usize = 512;
+
+struct Reader<'a, 'de> {
+    slice: &'a mut &'de [u8],
+    /// Current recursion depth.
+    depth: usize,
+}
+
+impl<'de> Reader<'_, 'de> {
+    fn read_tag(&mut self) -> Result<Tag, Error> {
+        match self.slice.read_u8().map_err(eof)? {
+            0 => Ok(Tag::End),
+            1 => Ok(Tag::Byte),
+            2 => Ok(Tag::Short),
+            3 => Ok(Tag::Int),
+            4 => Ok(Tag::Long),
+            5 => Ok(Tag::Float),
+            6 => Ok(Tag::Double),
+            7 => Ok(Tag::ByteArray),
+            8 => Ok(Tag::String),
+            9 => Ok(Tag::List),
+            10 => Ok(Tag::Compound),
+            11 => Ok(Tag::IntArray),
+            12 => Ok(Tag::LongArray),
+            byte => Err(Error::new(format!("invalid tag byte of {byte:#x}"))),
+        }
+    }
+
+    /// Reads the length of an array or list whose elements are at least
+    /// `elem_size` bytes long. `what` names the array or list for errors.
+    fn read_len(&mut self, elem_size: usize, what: impl Fn() -> String) -> Result<usize, Error> {
+        let len = self.slice.read_i32::<BigEndian>().map_err(eof)?;
+
+        if len.is_negative() {
+            return Err(Error::new(format!("negative {} length of {len}", what())));
+        }
+
+        if len as u64 * elem_size as u64 > self.slice.len() as u64 {
+            return Err(Error::new(format!(
+                "{} of length {len} exceeds remainder of input",
+                what()
+            )));
+        }
+
+        Ok(len as usize)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> &'de [u8] {
+        let (left, right) = self.slice.split_at(len);
+        *self.slice = right;
+        left
+    }
+
+    fn read_string(&mut self) -> Result<Cow<'de, str>, Error> {
+        let len = self.slice.read_u16::<BigEndian>().map_err(eof)?.into();
+
+        if len > self.slice.len() {
+            return Err(Error::new(format!(
+                "string of length {len} exceeds remainder of input"
+            )));
+        }
+
+        cesu8::from_java_cesu8(self.read_bytes(len))
+            .map_err(|_| Error::new("could not decode modified UTF-8 data"))
+    }
+
+    /// Calls `f` one level deeper into nested lists and compounds.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::new("reached maximum recursion depth"));
+        }
+
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+        res
+    }
+}
+
+fn eof(_: std::io::Error) -> Error {
+    Error::new("unexpected end of input")
+}
+
+/// Deserializes the payload of a tag which was already read.
+struct ValueDeserializer<'r, 'a, 'de> {
+    reader: &'r mut Reader<'a, 'de>,
+    tag: Tag,
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'_, '_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let r = self.reader;
+
+        match self.tag {
+            Tag::End => Err(Error::new("unexpected end tag")),
+            Tag::Byte => visitor.visit_i8(r.slice.read_i8().map_err(eof)?),
+            Tag::Short => visitor.visit_i16(r.slice.read_i16::<BigEndian>().map_err(eof)?),
+            Tag::Int => visitor.visit_i32(r.slice.read_i32::<BigEndian>().map_err(eof)?),
+            Tag::Long => visitor.visit_i64(r.slice.read_i64::<BigEndian>().map_err(eof)?),
+            Tag::Float => visitor.visit_f32(r.slice.read_f32::<BigEndian>().map_err(eof)?),
+            Tag::Double => visitor.visit_f64(r.slice.read_f64::<BigEndian>().map_err(eof)?),
+            Tag::ByteArray => {
+                let len = r.read_len(1, || "byte array".into())?;
+                visitor.visit_borrowed_bytes(r.read_bytes(len))
+            }
+            Tag::String => match r.read_string()? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            Tag::List => r.nested(|r| {
+                let elem_tag = r.read_tag()?;
+
+                let elem_size = match elem_tag {
+                    Tag::Byte => 1,
+                    Tag::Short => 2,
+                    Tag::Int | Tag::Float => 4,
+                    Tag::Long | Tag::Double => 8,
+                    _ => 0,
+                };
+
+                let len = r.read_len(elem_size, || format!("{} list", elem_tag.name()))?;
+
+                if elem_tag == Tag::End && len != 0 {
+                    return Err(Error::new(format!(
+                        "TAG_End list with nonzero length of {len}"
+                    )));
+                }
+
+                visit_seq(r, visitor, elem_tag, len)
+            }),
+            Tag::Compound => r.nested(|r| {
+                let mut access = CompoundAccess {
+                    reader: r,
+                    tag: Tag::End,
+                    done: false,
+                };
+
+                let value = visitor.visit_map(&mut access)?;
+
+                // Skip the entries the visitor didn't ask for, if it stopped early.
+                while !access.done && access.next_key::<IgnoredAny>()?.is_some() {
+                    access.next_value::<IgnoredAny>()?;
+                }
+
+                Ok(value)
+            }),
+            Tag::IntArray => {
+                let len = r.read_len(4, || "int array".into())?;
+                visit_seq(r, visitor, Tag::Int, len)
+            }
+            Tag::LongArray => {
+                let len = r.read_len(8, || "long array".into())?;
+                visit_seq(r, visitor, Tag::Long, len)
+            }
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.tag {
+            Tag::Byte => visitor.visit_bool(self.reader.slice.read_i8().map_err(eof)? != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.tag {
+            Tag::ByteArray => {
+                let len = self.reader.read_len(1, || "byte array".into())?;
+                visit_seq(self.reader, visitor, Tag::Byte, len)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.tag {
+            // Unit variant.
+            Tag::String => match self.reader.read_string()? {
+                Cow::Borrowed(s) => visitor.visit_enum(BorrowedStrDeserializer::new(s)),
+                Cow::Owned(s) => visitor.visit_enum(s.into_deserializer()),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Visits `len` elements with the tag `elem_tag` as a sequence, and checks
+/// that all of them were deserialized.
+fn visit_seq<'de, V>(
+    reader: &mut Reader<'_, 'de>,
+    visitor: V,
+    elem_tag: Tag,
+    len: usize,
+) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let mut access = ListAccess {
+        reader,
+        elem_tag,
+        remaining: len,
+    };
+
+    let value = visitor.visit_seq(&mut access)?;
+
+    if access.remaining > 0 {
+        return Err(Error::new(format!(
+            "{} of {len} list elements were not deserialized",
+            access.remaining
+        )));
+    }
+
+    Ok(value)
+}
+
+struct ListAccess<'r, 'a, 'de> {
+    reader: &'r mut Reader<'a, 'de>,
+    elem_tag: Tag,
+    remaining: usize,
+}
+
+impl<'de> SeqAccess<'de> for ListAccess<'_, '_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+
+        seed.deserialize(ValueDeserializer {
+            reader: self.reader,
+            tag: self.elem_tag,
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct CompoundAccess<'r, 'a, 'de> {
+    reader: &'r mut Reader<'a, 'de>,
+    /// The tag of the value after the last key.
+    tag: Tag,
+    /// Whether the end of the compound was reached.
+    done: bool,
+}
+
+impl<'de> MapAccess<'de> for CompoundAccess<'_, '_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.done {
+            return Ok(None);
+        }
+
+        self.tag = self.reader.read_tag()?;
+
+        if self.tag == Tag::End {
+            self.done = true;
+            return Ok(None);
+        }
+
+        match self.reader.read_string()? {
+            Cow::Borrowed(s) => seed.deserialize(BorrowedStrDeserializer::new(s)),
+            Cow::Owned(s) => seed.deserialize(StringDeserializer::new(s)),
+        }
+        .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(ValueDeserializer {
+            reader: self.reader,
+            tag: self.tag,
+        })
+    }
+}
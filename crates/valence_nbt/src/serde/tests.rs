@@ -96,6 +96,18 @@ fn compound_to_struct() {
     assert_eq!(s, make_struct());
 }
 
+#[test]
+fn byte_array_to_vec() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Bytes {
+        bytes: Vec<i8>,
+    }
+
+    let c = compound! { "bytes" => vec![1_i8, -1] };
+
+    assert_eq!(Bytes::deserialize(c).unwrap(), Bytes { bytes: vec![1, -1] });
+}
+
 #[test]
 fn compound_to_json() {
     let mut j = serde_json::to_value(make_compound()).unwrap();
@@ -107,3 +119,70 @@ fn compound_to_json() {
 
     assert_eq!(j, make_json());
 }
+
+#[cfg(feature = "binary")]
+#[test]
+fn binary_to_struct() {
+    let mut buf = vec![];
+    crate::to_binary(&make_compound(), &mut buf, "root").unwrap();
+
+    let (s, root_name) = from_binary_borrowed::<Struct>(&mut buf.as_slice()).unwrap();
+
+    assert_eq!(s, make_struct());
+    assert_eq!(root_name, "root");
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn binary_to_borrowed_struct() {
+    use std::borrow::Cow;
+
+    #[derive(Deserialize, Debug)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        #[serde(borrow)]
+        emoji: Cow<'a, str>,
+        bytes: &'a [u8],
+        byte_vec: Vec<i8>,
+        inner: BorrowedInner<'a>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct BorrowedInner<'a> {
+        #[serde(borrow)]
+        lines: Vec<&'a str>,
+    }
+
+    let c = compound! {
+        "name" => "valence",
+        "emoji" => "🤨",
+        "bytes" => vec![1_i8, -1],
+        "byte_vec" => vec![1_i8, -1],
+        "ignored" => compound! { "a" => List::Int(vec![1, 2]) },
+        "inner" => compound! {
+            "lines" => List::String(vec!["a".into(), "b".into()]),
+        },
+    };
+
+    let mut buf = vec![];
+    crate::to_binary(&c, &mut buf, "").unwrap();
+
+    let mut slice = buf.as_slice();
+    let (b, _) = from_binary_borrowed::<Borrowed>(&mut slice).unwrap();
+
+    assert!(slice.is_empty());
+    assert_eq!(b.name, "valence");
+    // Characters outside the BMP are encoded differently in modified UTF-8.
+    assert!(matches!(b.emoji, Cow::Owned(ref s) if s == "🤨"));
+    assert_eq!(b.bytes, [1, 255]);
+    assert_eq!(b.byte_vec, [1, -1]);
+    assert_eq!(b.inner.lines, ["a", "b"]);
+
+    #[derive(Deserialize, Debug)]
+    struct NotBorrowable<'a> {
+        #[allow(dead_code)]
+        emoji: &'a str,
+    }
+
+    assert!(from_binary_borrowed::<NotBorrowable>(&mut buf.as_slice()).is_err());
+}
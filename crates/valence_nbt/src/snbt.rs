@@ -22,6 +22,7 @@ pub enum SnbtErrorKind {
     LongString,
     TrailingData,
     DepthLimitExceeded,
+    ExpectCompound,
 }
 
 impl Display for SnbtErrorKind {
@@ -39,6 +40,7 @@ impl Display for SnbtErrorKind {
             LongString => write!(f, "long string"),
             TrailingData => write!(f, "extra data after end"),
             DepthLimitExceeded => write!(f, "depth limit exceeded"),
+            ExpectCompound => write!(f, "expect compound"),
         }
     }
 }
@@ -237,11 +239,13 @@ impl<'a> SnbtReader<'a> {
 
         let mut cpd = Compound::new();
         while self.peek()? != '}' {
+            // Empty keys are allowed if they are quoted.
+            let quoted = matches!(self.peek()?, '"' | '\'');
             let key = self.read_string()?;
 
             self.skip_whitespace();
 
-            if key.is_empty() {
+            if key.is_empty() && !quoted {
                 return Err(self.make_error(SnbtErrorKind::EmptyKeyInCompound));
             }
 
@@ -353,39 +357,15 @@ impl<'a> SnbtReader<'a> {
     }
 
     fn parse_primitive(&mut self) -> Result<Value> {
-        macro_rules! try_ret {
-            // Try possible solution until one works
-            ($v:expr) => {{
-                match $v {
-                    Ok(v) => return Ok(v.into()),
-                    Err(_) => (),
-                }
-            }};
-        }
-
         let target = self.read_unquoted_string()?;
 
-        match target
-            .bytes()
-            .last()
-            .ok_or_else(|| self.make_error(SnbtErrorKind::ExpectValue))?
-        {
-            b'b' | b'B' => try_ret!(target[..target.len() - 1].parse::<i8>()),
-            b's' | b'S' => try_ret!(target[..target.len() - 1].parse::<i16>()),
-            b'l' | b'L' => try_ret!(target[..target.len() - 1].parse::<i64>()),
-            b'f' | b'F' => try_ret!(target[..target.len() - 1].parse::<f32>()),
-            b'd' | b'D' => try_ret!(target[..target.len() - 1].parse::<f64>()),
-            _ => (),
-        }
-
-        match target.as_str() {
-            "true" => return Ok(Value::Byte(1)),
-            "false" => return Ok(Value::Byte(0)),
-            _ => {
-                try_ret!(target.parse::<i32>());
-                try_ret!(target.parse::<f64>());
-            }
-        };
+        if target.is_empty() {
+            return Err(self.make_error(SnbtErrorKind::ExpectValue));
+        }
+
+        if let Some(value) = parse_unquoted_value(&target) {
+            return Ok(value);
+        }
 
         if target.len() > STRING_MAX_LEN {
             return Err(self.make_error(SnbtErrorKind::LongString));
@@ -426,6 +406,78 @@ impl<'a> SnbtReader<'a> {
         self.index
     }
 }
+
+/// Returns the number or boolean an unquoted value stands for, like vanilla,
+/// or `None` if it is a string. Numbers which are out of range for their type
+/// are strings too.
+fn parse_unquoted_value(s: &str) -> Option<Value> {
+    let (body, suffix) = s.split_at(s.len() - 1);
+
+    match suffix {
+        "f" | "F" if is_decimal(body, false) => return body.parse::<f32>().ok().map(Value::Float),
+        "b" | "B" if is_integer(body) => return body.parse::<i8>().ok().map(Value::Byte),
+        "l" | "L" if is_integer(body) => return body.parse::<i64>().ok().map(Value::Long),
+        "s" | "S" if is_integer(body) => return body.parse::<i16>().ok().map(Value::Short),
+        "d" | "D" if is_decimal(body, false) => return body.parse::<f64>().ok().map(Value::Double),
+        _ => {}
+    }
+
+    if is_integer(s) {
+        return s.parse::<i32>().ok().map(Value::Int);
+    }
+
+    if is_decimal(s, true) {
+        return s.parse::<f64>().ok().map(Value::Double);
+    }
+
+    match s {
+        "true" => Some(Value::Byte(1)),
+        "false" => Some(Value::Byte(0)),
+        _ => None,
+    }
+}
+
+fn strip_sign(s: &str) -> &str {
+    s.strip_prefix(['-', '+']).unwrap_or(s)
+}
+
+fn is_digits(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Matches `[-+]?(?:0|[1-9][0-9]*)`.
+fn is_integer(s: &str) -> bool {
+    let digits = strip_sign(s);
+
+    !digits.is_empty() && is_digits(digits) && (digits == "0" || !digits.starts_with('0'))
+}
+
+/// Matches `[-+]?(?:[0-9]+[.]|[0-9]*[.][0-9]+)(?:e[-+]?[0-9]+)?`, where the dot
+/// is optional unless `dot_required`.
+fn is_decimal(s: &str, dot_required: bool) -> bool {
+    let s = strip_sign(s);
+
+    let mantissa = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => {
+            let exponent = strip_sign(exponent);
+
+            if exponent.is_empty() || !is_digits(exponent) {
+                return false;
+            }
+
+            mantissa
+        }
+        None => s,
+    };
+
+    match mantissa.split_once('.') {
+        Some((int, frac)) => {
+            is_digits(int) && is_digits(frac) && !(int.is_empty() && frac.is_empty())
+        }
+        None => !dot_required && !mantissa.is_empty() && is_digits(mantissa),
+    }
+}
+
 /// Parse a string in SNBT format into a `Value`.
 /// Assert that the string has no trailing data.
 /// SNBT is quite similar to JSON, but with some differences.
@@ -444,123 +496,196 @@ pub fn from_snbt_str(snbt: &str) -> Result<Value> {
     SnbtReader::new(snbt).read()
 }
 
+/// Writes values in SNBT format, the same way vanilla does.
+///
+/// String values are always quoted so they are read back as strings, while
+/// compound keys are only quoted when they need to be.
 pub struct SnbtWriter<'a> {
     output: &'a mut String,
+    /// The indentation of pretty output, or `None` for compact output.
+    indent: Option<&'a str>,
+    depth: usize,
 }
 
 impl<'a> SnbtWriter<'a> {
     pub fn new(output: &'a mut String) -> Self {
-        Self { output }
+        Self {
+            output,
+            indent: None,
+            depth: 0,
+        }
+    }
+
+    /// Creates a writer which puts the entries of compounds and lists on
+    /// their own lines, indented by `indent` per level. Arrays and lists of
+    /// numbers stay on one line.
+    pub fn pretty(output: &'a mut String, indent: &'a str) -> Self {
+        Self {
+            output,
+            indent: Some(indent),
+            depth: 0,
+        }
     }
 
+    fn write_key(&mut self, key: &str) {
+        let simple = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '+' | '.'));
+
+        if simple {
+            self.output.push_str(key);
+        } else {
+            self.write_string(key);
+        }
+    }
+
+    /// Writes a quoted string. Like vanilla, single quotes are used if a
+    /// double quote comes before any single quote in the string.
     fn write_string(&mut self, s: &str) {
-        let mut need_quote = false;
+        let quote = match s.chars().find(|&c| c == '"' || c == '\'') {
+            Some('"') => '\'',
+            _ => '"',
+        };
+
+        self.output.push(quote);
+
         for c in s.chars() {
-            if !matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '+' | '.') {
-                need_quote = true;
-                break;
+            if c == quote || c == '\\' {
+                self.output.push('\\');
             }
+            self.output.push(c);
         }
 
-        if need_quote {
-            self.output.push('"');
-            for c in s.chars() {
-                match c {
-                    '"' => self.output.push_str("\\\""),
-                    '\\' => self.output.push_str("\\\\"),
-                    _ => self.output.push(c),
-                }
+        self.output.push(quote);
+    }
+
+    fn write_primitive(&mut self, postfix: &str, value: impl ToString) {
+        self.output.push_str(&value.to_string());
+        self.output.push_str(postfix);
+    }
+
+    fn write_separator(&mut self, multiline: bool) {
+        self.output.push(',');
+
+        if multiline {
+            self.write_newline();
+        } else if self.indent.is_some() {
+            self.output.push(' ');
+        }
+    }
+
+    fn write_newline(&mut self) {
+        if let Some(indent) = self.indent {
+            self.output.push('\n');
+
+            for _ in 0..self.depth {
+                self.output.push_str(indent);
             }
-            self.output.push('"');
-        } else {
-            self.output.push_str(s);
         }
     }
 
-    fn write_primitive_array<'b>(
+    /// Writes the elements of a list or array between brackets, putting them
+    /// on their own lines if `multiline` and the output is pretty.
+    fn write_sequence<T>(
         &mut self,
         prefix: &str,
-        iter: impl Iterator<Item = &'b (impl Into<Value> + 'b + Copy)>,
+        items: &[T],
+        multiline: bool,
+        mut write: impl FnMut(&mut Self, &T),
     ) {
+        let multiline = multiline && self.indent.is_some() && !items.is_empty();
+
         self.output.push('[');
-        self.output.push_str(prefix);
 
-        let mut first = true;
+        if !prefix.is_empty() {
+            self.output.push_str(prefix);
+            self.output.push(';');
 
-        for v in iter {
-            if !first {
-                self.output.push(',');
+            if self.indent.is_some() && !items.is_empty() {
+                self.output.push(' ');
             }
-            first = false;
+        }
 
-            self.write_element(&(*v).into());
+        if multiline {
+            self.depth += 1;
+            self.write_newline();
+        }
+
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.write_separator(multiline);
+            }
+            write(self, item);
+        }
+
+        if multiline {
+            self.depth -= 1;
+            self.write_newline();
         }
 
         self.output.push(']');
     }
 
-    fn write_primitive(&mut self, postfix: &str, value: impl ToString) {
-        self.output.push_str(&value.to_string());
-        self.output.push_str(postfix);
+    fn write_byte_array(&mut self, v: &[i8]) {
+        self.write_sequence("B", v, false, |w, v| w.write_primitive("B", v));
     }
 
-    fn write_list(&mut self, list: &List) {
-        macro_rules! variant_impl {
-            ($v:expr, $handle:expr) => {{
-                self.output.push('[');
-
-                let mut first = true;
-                for v in $v.iter() {
-                    if !first {
-                        self.output.push(',');
-                    }
-                    first = false;
-                    $handle(v);
-                }
+    fn write_int_array(&mut self, v: &[i32]) {
+        self.write_sequence("I", v, false, |w, v| w.write_primitive("", v));
+    }
 
-                self.output.push(']');
-            }};
-        }
-        #[allow(clippy::redundant_closure_call)]
+    fn write_long_array(&mut self, v: &[i64]) {
+        self.write_sequence("L", v, false, |w, v| w.write_primitive("L", v));
+    }
+
+    fn write_list(&mut self, list: &List) {
         match list {
-            List::Byte(v) => variant_impl!(v, |v| self.write_primitive("b", v)),
-            List::Short(v) => variant_impl!(v, |v| self.write_primitive("s", v)),
-            List::Int(v) => variant_impl!(v, |v| self.write_primitive("", v)),
-            List::Long(v) => variant_impl!(v, |v| self.write_primitive("l", v)),
-            List::Float(v) => variant_impl!(v, |v| self.write_primitive("f", v)),
-            List::Double(v) => variant_impl!(v, |v| self.write_primitive("d", v)),
-            List::ByteArray(v) => {
-                variant_impl!(v, |v: &Vec<i8>| self.write_primitive_array("B", v.iter()))
-            }
-            List::IntArray(v) => {
-                variant_impl!(v, |v: &Vec<i32>| self.write_primitive_array("", v.iter()))
-            }
-            List::LongArray(v) => {
-                variant_impl!(v, |v: &Vec<i64>| self.write_primitive_array("L", v.iter()))
-            }
-            List::String(v) => variant_impl!(v, |v| self.write_string(v)),
-            List::List(v) => variant_impl!(v, |v| self.write_list(v)),
-            List::Compound(v) => variant_impl!(v, |v| self.write_compound(v)),
             List::End => self.output.push_str("[]"),
+            List::Byte(v) => self.write_sequence("", v, false, |w, v| w.write_primitive("b", v)),
+            List::Short(v) => self.write_sequence("", v, false, |w, v| w.write_primitive("s", v)),
+            List::Int(v) => self.write_sequence("", v, false, |w, v| w.write_primitive("", v)),
+            List::Long(v) => self.write_sequence("", v, false, |w, v| w.write_primitive("L", v)),
+            List::Float(v) => self.write_sequence("", v, false, |w, v| w.write_primitive("f", v)),
+            List::Double(v) => self.write_sequence("", v, false, |w, v| w.write_primitive("d", v)),
+            List::ByteArray(v) => self.write_sequence("", v, true, |w, v| w.write_byte_array(v)),
+            List::IntArray(v) => self.write_sequence("", v, true, |w, v| w.write_int_array(v)),
+            List::LongArray(v) => self.write_sequence("", v, true, |w, v| w.write_long_array(v)),
+            List::String(v) => self.write_sequence("", v, true, |w, v| w.write_string(v)),
+            List::List(v) => self.write_sequence("", v, true, |w, v| w.write_list(v)),
+            List::Compound(v) => self.write_sequence("", v, true, |w, v| w.write_compound(v)),
         }
     }
 
     fn write_compound(&mut self, compound: &Compound) {
         self.output.push('{');
 
-        let mut first = true;
-        for (k, v) in compound.iter() {
-            if !first {
-                self.output.push(',');
-            }
+        if compound.is_empty() {
+            self.output.push('}');
+            return;
+        }
 
-            first = false;
+        self.depth += 1;
+        self.write_newline();
 
-            self.write_string(k);
+        for (i, (k, v)) in compound.iter().enumerate() {
+            if i > 0 {
+                self.write_separator(true);
+            }
+
+            self.write_key(k);
             self.output.push(':');
+
+            if self.indent.is_some() {
+                self.output.push(' ');
+            }
+
             self.write_element(v);
         }
 
+        self.depth -= 1;
+        self.write_newline();
+
         self.output.push('}');
     }
 
@@ -571,12 +696,12 @@ impl<'a> SnbtWriter<'a> {
             Byte(v) => self.write_primitive("b", v),
             Short(v) => self.write_primitive("s", v),
             Int(v) => self.write_primitive("", v),
-            Long(v) => self.write_primitive("l", v),
+            Long(v) => self.write_primitive("L", v),
             Float(v) => self.write_primitive("f", v),
             Double(v) => self.write_primitive("d", v),
-            ByteArray(v) => self.write_primitive_array("B;", v.iter()),
-            IntArray(v) => self.write_primitive_array("I;", v.iter()),
-            LongArray(v) => self.write_primitive_array("L;", v.iter()),
+            ByteArray(v) => self.write_byte_array(v),
+            IntArray(v) => self.write_int_array(v),
+            LongArray(v) => self.write_long_array(v),
             String(v) => self.write_string(v),
             List(v) => self.write_list(v),
             Compound(v) => self.write_compound(v),
@@ -594,6 +719,67 @@ pub fn to_snbt_string(value: &Value) -> String {
     output
 }
 
+/// Convert a value to a string in SNBT format which is indented by four
+/// spaces per level, for config files and output meant for people.
+///
+/// ```
+/// use valence_nbt::snbt::to_snbt_pretty;
+/// use valence_nbt::{compound, List, Value};
+///
+/// let value = Value::Compound(compound! {
+///     "name" => "Steve",
+///     "pos" => List::Double(vec![1.0, 64.0, -3.5]),
+/// });
+///
+/// assert_eq!(
+///     to_snbt_pretty(&value),
+///     "{\n    name: \"Steve\",\n    pos: [1d, 64d, -3.5d]\n}"
+/// );
+/// ```
+pub fn to_snbt_pretty(value: &Value) -> String {
+    let mut output = String::new();
+    let mut writer = SnbtWriter::pretty(&mut output, "    ");
+
+    writer.write_element(value);
+
+    output
+}
+
+impl Compound {
+    /// Parses a compound in SNBT format, like
+    /// `{name: "Steve", pos: [1d, 64d, -3.5d]}`.
+    pub fn from_snbt(snbt: &str) -> Result<Compound> {
+        let mut reader = SnbtReader::new(snbt);
+
+        reader.skip_whitespace();
+
+        if reader.peek()? != '{' {
+            return Err(reader.make_error(SnbtErrorKind::ExpectCompound));
+        }
+
+        match reader.read()? {
+            Value::Compound(compound) => Ok(compound),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Converts the compound to a string in SNBT format. See
+    /// [`to_snbt_string`].
+    pub fn to_snbt(&self) -> String {
+        let mut output = String::new();
+        SnbtWriter::new(&mut output).write_compound(self);
+        output
+    }
+
+    /// Converts the compound to an indented string in SNBT format. See
+    /// [`to_snbt_pretty`].
+    pub fn to_snbt_pretty(&self) -> String {
+        let mut output = String::new();
+        SnbtWriter::pretty(&mut output, "    ").write_compound(self);
+        output
+    }
+}
+
 impl Display for SnbtWriter<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.output)
@@ -602,8 +788,8 @@ impl Display for SnbtWriter<'_> {
 
 #[cfg(test)]
 mod tests {
-
     use super::*;
+    use crate::compound;
 
     #[test]
     fn test_parse() {
@@ -720,7 +906,104 @@ mod tests {
         #[cfg(feature = "preserve_order")]
         assert_eq!(
             to_snbt_string(&value),
-            r#"{foo:1,bar:1d,baz:1f,"hello'":"hello world",world:"hello\"world",1.5f:1.5d,3b:2f,bool:0b,more:{iarr:[I;1,2,3],larr:[L;1l,2l,3l]},empty:[Bibabo]}"#
+            r#"{foo:1,bar:1d,baz:1f,"hello'":"hello world",world:'hello"world',1.5f:1.5d,3b:2f,bool:0b,more:{iarr:[I;1,2,3],larr:[L;1L,2L,3L]},empty:["Bibabo"]}"#
         );
     }
+
+    #[test]
+    fn infer_number_types() {
+        let cases: [(&str, Value); 22] = [
+            ("1b", Value::Byte(1)),
+            ("-128B", Value::Byte(-128)),
+            ("128b", "128b".into()),
+            ("+5s", Value::Short(5)),
+            ("7", Value::Int(7)),
+            ("0", Value::Int(0)),
+            ("3000000000", "3000000000".into()),
+            ("3000000000L", Value::Long(3_000_000_000)),
+            ("1.5", Value::Double(1.5)),
+            (".5", Value::Double(0.5)),
+            ("2.", Value::Double(2.0)),
+            ("1e5", "1e5".into()),
+            ("1.5e2", Value::Double(150.0)),
+            ("1e5d", Value::Double(1e5)),
+            ("3f", Value::Float(3.0)),
+            (".25F", Value::Float(0.25)),
+            ("01", "01".into()),
+            ("1.2.3", "1.2.3".into()),
+            ("true", Value::Byte(1)),
+            ("false", Value::Byte(0)),
+            ("True", "True".into()),
+            ("minecraft.stone", "minecraft.stone".into()),
+        ];
+
+        for (snbt, value) in cases {
+            assert_eq!(from_snbt_str(snbt).unwrap(), value, "{snbt}");
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let compound = compound! {
+            "byte" => 1_i8,
+            "short" => -2_i16,
+            "long" => i64::MAX,
+            "number string" => "123",
+            "bool string" => "true",
+            "quotes" => r#"'a' and "b""#,
+            "" => "empty key",
+            "bytes" => vec![1_i8, -2],
+            "arrays" => List::IntArray(vec![vec![1, 2], vec![]]),
+            "longs" => List::LongArray(vec![vec![3]]),
+            "nested" => List::List(vec![List::End, List::Float(vec![0.5])]),
+            "compounds" => List::Compound(vec![compound! { "a" => "b" }, Compound::new()]),
+        };
+
+        let compact = compound.to_snbt();
+        assert_eq!(Compound::from_snbt(&compact).unwrap(), compound);
+
+        let pretty = compound.to_snbt_pretty();
+        assert_eq!(Compound::from_snbt(&pretty).unwrap(), compound);
+
+        assert_eq!(
+            to_snbt_string(&List::ByteArray(vec![vec![1]]).into()),
+            "[[B;1B]]"
+        );
+
+        assert_eq!(
+            Compound::from_snbt("[1, 2]").unwrap_err().error_type,
+            SnbtErrorKind::ExpectCompound
+        );
+    }
+
+    #[test]
+    fn pretty_print() {
+        let compound = compound! {
+            "name" => "Steve",
+            "pos" => List::Double(vec![1.0, 64.0]),
+            "tags" => List::String(vec!["a".into(), "b".into()]),
+            "data" => compound! {
+                "ints" => vec![1, 2, 3],
+                "empty" => Compound::new(),
+            },
+        };
+
+        let expected = r#"{
+    name: "Steve",
+    pos: [1d, 64d],
+    tags: [
+        "a",
+        "b"
+    ],
+    data: {
+        ints: [I; 1, 2, 3],
+        empty: {}
+    }
+}"#;
+
+        #[cfg(feature = "preserve_order")]
+        assert_eq!(compound.to_snbt_pretty(), expected);
+
+        assert_eq!(Compound::from_snbt(expected).unwrap(), compound);
+    }
 }
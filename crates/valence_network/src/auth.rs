@@ -0,0 +1,376 @@
+//! Pluggable authentication of clients in online mode.
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use uuid::Uuid;
+use valence_protocol::profile::Property;
+
+use crate::SharedNetworkState;
+
+/// A game profile of an authenticated player, as returned by the session
+/// server.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<Property>,
+}
+
+/// Verifies the identity of clients joining in [online mode].
+///
+/// The backend in use is set with [`NetworkSettings::auth`]. Backends can be
+/// layered, such as [`CachedAuth`] wrapping [`MojangAuth`].
+///
+/// This trait uses [`mod@async_trait`].
+///
+/// [online mode]: crate::ConnectionMode::Online
+/// [`NetworkSettings::auth`]: crate::NetworkSettings::auth
+#[async_trait]
+pub trait AuthBackend: Send + Sync + 'static {
+    /// Checks that the client with `username` has joined the server with the
+    /// Mojang session server. The arguments are described in the
+    /// [wiki](https://wiki.vg/Protocol_Encryption#Server).
+    ///
+    /// - If `Ok(Some(profile))` is returned, the client is authenticated.
+    /// - If `Ok(None)` is returned, the client is disconnected with the
+    ///   "unverified username" message.
+    /// - If `Err(_)` is returned, the connection is closed.
+    async fn has_joined(
+        &self,
+        shared: &SharedNetworkState,
+        username: &str,
+        auth_digest: &str,
+        player_ip: IpAddr,
+    ) -> anyhow::Result<Option<GameProfile>>;
+
+    /// Returns whether the client with `username` may join without
+    /// authentication, as if the server was in offline mode. Offline clients
+    /// skip encryption and get an offline UUID derived from their username.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `false`.
+    async fn allows_offline(
+        &self,
+        shared: &SharedNetworkState,
+        username: &str,
+        player_ip: IpAddr,
+    ) -> bool {
+        #![allow(unused_variables)]
+
+        false
+    }
+}
+
+/// Authenticates clients with the session server URL from
+/// [`NetworkCallbacks::session_server`]. This is the default backend.
+///
+/// [`NetworkCallbacks::session_server`]: crate::NetworkCallbacks::session_server
+#[derive(Copy, Clone, Default, Debug)]
+pub struct MojangAuth;
+
+#[async_trait]
+impl AuthBackend for MojangAuth {
+    async fn has_joined(
+        &self,
+        shared: &SharedNetworkState,
+        username: &str,
+        auth_digest: &str,
+        player_ip: IpAddr,
+    ) -> anyhow::Result<Option<GameProfile>> {
+        let url = shared
+            .0
+            .callbacks
+            .inner
+            .session_server(shared, username, auth_digest, &player_ip)
+            .await;
+
+        let resp = shared.0.http_client.get(url).send().await?;
+
+        match resp.status() {
+            StatusCode::OK => Ok(Some(resp.json().await?)),
+            StatusCode::NO_CONTENT => Ok(None),
+            status => bail!("session server GET request failed (status code {status})"),
+        }
+    }
+}
+
+/// Remembers the profiles returned by another backend, and falls back on
+/// them while that backend is failing.
+///
+/// Every login is checked with the wrapped backend first, so the cache never
+/// lets anyone in while the session server is reachable. Only when the
+/// wrapped backend returns an error, such as when the session server is down
+/// or rate limits the server, is a client let in with the profile of its last
+/// successful login. This requires the client to connect from the same IP
+/// address as that login and with the same username, and the login to be
+/// less than [`ttl`](Self::ttl) old.
+///
+/// **Note:** the session server is the only thing which can prove that a
+/// client owns an account. While falling back, the IP address the
+/// connection comes from is all that backs up a client's identity, so keep
+/// the TTL short if clients may share an IP address.
+pub struct CachedAuth<B> {
+    inner: B,
+    ttl: Duration,
+    cache: Mutex<HashMap<(String, IpAddr), (Instant, GameProfile)>>,
+}
+
+impl<B: AuthBackend> CachedAuth<B> {
+    /// Wraps `inner` with a TTL of five minutes.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            ttl: Duration::from_secs(5 * 60),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets how long after a successful login its profile may be used as a
+    /// fallback.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Forgets all cached profiles.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Returns the number of cached profiles, including expired ones which
+    /// haven't been pruned yet.
+    pub fn len(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// Returns whether there are no cached profiles.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl<B: AuthBackend> AuthBackend for CachedAuth<B> {
+    async fn has_joined(
+        &self,
+        shared: &SharedNetworkState,
+        username: &str,
+        auth_digest: &str,
+        player_ip: IpAddr,
+    ) -> anyhow::Result<Option<GameProfile>> {
+        let res = self
+            .inner
+            .has_joined(shared, username, auth_digest, player_ip)
+            .await;
+
+        let mut cache = self.cache.lock().unwrap();
+
+        // Drop expired entries so the cache doesn't grow forever.
+        cache.retain(|_, (time, _)| time.elapsed() < self.ttl);
+
+        let key = (username.to_owned(), player_ip);
+
+        match res {
+            Ok(Some(profile)) => {
+                cache.insert(key, (Instant::now(), profile.clone()));
+                Ok(Some(profile))
+            }
+            Ok(None) => {
+                cache.remove(&key);
+                Ok(None)
+            }
+            Err(e) => match cache.get(&key) {
+                Some((_, profile)) => {
+                    tracing::warn!("using cached profile of {username}: {e:#}");
+                    Ok(Some(profile.clone()))
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn allows_offline(
+        &self,
+        shared: &SharedNetworkState,
+        username: &str,
+        player_ip: IpAddr,
+    ) -> bool {
+        self.inner.allows_offline(shared, username, player_ip).await
+    }
+}
+
+/// Lets a fixed set of usernames join without authentication, while
+/// everyone else is authenticated by another backend.
+pub struct OfflineUsers<B> {
+    inner: B,
+    usernames: HashSet<String>,
+}
+
+impl<B: AuthBackend> OfflineUsers<B> {
+    pub fn new<S: Into<String>>(inner: B, usernames: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            inner,
+            usernames: usernames.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl<B: AuthBackend> AuthBackend for OfflineUsers<B> {
+    async fn has_joined(
+        &self,
+        shared: &SharedNetworkState,
+        username: &str,
+        auth_digest: &str,
+        player_ip: IpAddr,
+    ) -> anyhow::Result<Option<GameProfile>> {
+        self.inner
+            .has_joined(shared, username, auth_digest, player_ip)
+            .await
+    }
+
+    async fn allows_offline(
+        &self,
+        shared: &SharedNetworkState,
+        username: &str,
+        player_ip: IpAddr,
+    ) -> bool {
+        self.usernames.contains(username)
+            || self.inner.allows_offline(shared, username, player_ip).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{test_shared_state, NetworkSettings};
+
+    /// Answers logins with the result it is set to.
+    #[derive(Default)]
+    struct MockAuth {
+        result: Mutex<Option<anyhow::Result<Option<GameProfile>>>>,
+        calls: AtomicUsize,
+    }
+
+    impl MockAuth {
+        fn set(&self, result: anyhow::Result<Option<GameProfile>>) {
+            *self.result.lock().unwrap() = Some(result);
+        }
+    }
+
+    #[async_trait]
+    impl AuthBackend for &'static MockAuth {
+        async fn has_joined(
+            &self,
+            _shared: &SharedNetworkState,
+            _username: &str,
+            _auth_digest: &str,
+            _player_ip: IpAddr,
+        ) -> anyhow::Result<Option<GameProfile>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.result.lock().unwrap().take().expect("no result set")
+        }
+    }
+
+    fn profile(name: &str) -> GameProfile {
+        GameProfile {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            properties: vec![],
+        }
+    }
+
+    fn mock() -> &'static MockAuth {
+        Box::leak(Box::default())
+    }
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    #[tokio::test]
+    async fn cached_auth_always_asks_inner_backend() {
+        let shared = test_shared_state(NetworkSettings::default());
+        let inner = mock();
+        let auth = CachedAuth::new(inner);
+        let steve = profile("Steve");
+
+        inner.set(Ok(Some(steve.clone())));
+        let res = auth.has_joined(&shared, "Steve", "", IP).await.unwrap();
+        assert_eq!(res, Some(steve));
+
+        // A fresh cached profile doesn't let the client skip authentication.
+        inner.set(Ok(None));
+        let res = auth.has_joined(&shared, "Steve", "", IP).await.unwrap();
+        assert_eq!(res, None);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        // Rejected logins forget the profile.
+        inner.set(Err(anyhow::anyhow!("session server is down")));
+        assert!(auth.has_joined(&shared, "Steve", "", IP).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn cached_auth_falls_back_on_error() {
+        let shared = test_shared_state(NetworkSettings::default());
+        let inner = mock();
+        let auth = CachedAuth::new(inner);
+        let steve = profile("Steve");
+
+        inner.set(Ok(Some(steve.clone())));
+        auth.has_joined(&shared, "Steve", "", IP).await.unwrap();
+
+        inner.set(Err(anyhow::anyhow!("session server is down")));
+        let res = auth.has_joined(&shared, "Steve", "", IP).await.unwrap();
+        assert_eq!(res, Some(steve));
+
+        // Other IP addresses and usernames don't get the cached profile.
+        let other_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        inner.set(Err(anyhow::anyhow!("session server is down")));
+        assert!(auth
+            .has_joined(&shared, "Steve", "", other_ip)
+            .await
+            .is_err());
+
+        inner.set(Err(anyhow::anyhow!("session server is down")));
+        assert!(auth.has_joined(&shared, "Alex", "", IP).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn cached_auth_expires_and_prunes() {
+        let shared = test_shared_state(NetworkSettings::default());
+        let inner = mock();
+        let auth = CachedAuth::new(inner).ttl(Duration::from_millis(50));
+
+        inner.set(Ok(Some(profile("Steve"))));
+        auth.has_joined(&shared, "Steve", "", IP).await.unwrap();
+        assert_eq!(auth.len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Expired profiles aren't used as a fallback.
+        inner.set(Err(anyhow::anyhow!("session server is down")));
+        assert!(auth.has_joined(&shared, "Steve", "", IP).await.is_err());
+        assert!(auth.is_empty());
+
+        inner.set(Ok(Some(profile("Alex"))));
+        auth.has_joined(&shared, "Alex", "", IP).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Any login prunes the expired profiles of other clients.
+        inner.set(Ok(Some(profile("Steve"))));
+        auth.has_joined(&shared, "Steve", "", IP).await.unwrap();
+        assert_eq!(auth.len(), 1);
+    }
+}
@@ -9,9 +9,7 @@ use base64::prelude::*;
 use hmac::digest::Update;
 use hmac::{Hmac, Mac};
 use num_bigint::BigInt;
-use reqwest::StatusCode;
 use rsa::Pkcs1v15Encrypt;
-use serde::Deserialize;
 use serde_json::{json, Value};
 use sha1::Sha1;
 use sha2::{Digest, Sha256};
@@ -20,7 +18,7 @@ use tracing::{error, info, trace, warn};
 use uuid::Uuid;
 use valence_lang::keys;
 use valence_protocol::profile::Property;
-use valence_protocol::Decode;
+use valence_protocol::{Decode, Encode, Packet, PacketSide, PacketState};
 use valence_server::client::Properties;
 use valence_server::protocol::packets::handshaking::handshake_c2s::HandshakeNextState;
 use valence_server::protocol::packets::handshaking::HandshakeC2s;
@@ -33,9 +31,10 @@ use valence_server::protocol::packets::status::{
 };
 use valence_server::protocol::{PacketDecoder, PacketEncoder, RawBytes, VarInt};
 use valence_server::text::{Color, IntoText};
-use valence_server::{ident, Text, MINECRAFT_VERSION, PROTOCOL_VERSION};
+use valence_server::{ident, Text};
 
 use crate::legacy_ping::try_handle_legacy_ping;
+use crate::login_query::LoginQueries;
 use crate::packet_io::PacketIo;
 use crate::{CleanupOnDrop, ConnectionMode, NewClientInfo, ServerListPing, SharedNetworkState};
 
@@ -100,7 +99,13 @@ async fn handle_connection(
         }
     }
 
-    let io = PacketIo::new(stream, PacketEncoder::new(), PacketDecoder::new());
+    let io = PacketIo::new(
+        stream,
+        PacketEncoder::new(),
+        PacketDecoder::new(),
+        &shared,
+        remote_addr,
+    );
 
     if let Err(e) = handle_handshake(shared, io, remote_addr).await {
         // EOF can happen if the client disconnects while joining, which isn't
@@ -126,44 +131,75 @@ pub struct HandshakeData {
     pub server_port: u16,
 }
 
+/// The handshake of a client connecting through BungeeCord. BungeeCord
+/// appends the forwarded player data to the server address, separated by null
+/// bytes, which makes the address longer than [`HandshakeC2s`] allows.
+#[derive(Debug, Encode, Decode)]
+struct BungeeCordHandshakeC2s<'a> {
+    protocol_version: VarInt,
+    server_address: &'a str,
+    server_port: u16,
+    next_state: HandshakeNextState,
+}
+
+impl Packet for BungeeCordHandshakeC2s<'_> {
+    const ID: i32 = HandshakeC2s::ID;
+    const NAME: &'static str = "BungeeCordHandshakeC2s";
+    const SIDE: PacketSide = PacketSide::Serverbound;
+    const STATE: PacketState = PacketState::Handshaking;
+}
+
 async fn handle_handshake(
     shared: SharedNetworkState,
     mut io: PacketIo,
     remote_addr: SocketAddr,
 ) -> anyhow::Result<()> {
-    let handshake = io.recv_packet::<HandshakeC2s>().await?;
-
-    let next_state = handshake.next_state;
-
-    let handshake = HandshakeData {
-        protocol_version: handshake.protocol_version.0,
-        server_address: handshake.server_address.0.to_owned(),
-        server_port: handshake.server_port,
-    };
-
-    // TODO: this is borked.
-    ensure!(
-        shared.0.connection_mode == ConnectionMode::BungeeCord
-            || handshake.server_address.encode_utf16().count() <= 255,
-        "handshake server address is too long"
-    );
+    let (handshake, next_state, bungeecord_data) =
+        if shared.0.connection_mode == ConnectionMode::BungeeCord {
+            let handshake = io.recv_packet::<BungeeCordHandshakeC2s>().await?;
+
+            let (server_address, data) = match handshake.server_address.split_once('\0') {
+                Some((address, data)) => (address, Some(data.to_owned())),
+                None => (handshake.server_address, None),
+            };
+
+            ensure!(
+                server_address.encode_utf16().count() <= 255,
+                "handshake server address is too long"
+            );
+
+            let handshake_data = HandshakeData {
+                protocol_version: handshake.protocol_version.0,
+                server_address: server_address.to_owned(),
+                server_port: handshake.server_port,
+            };
+
+            (handshake_data, handshake.next_state, data)
+        } else {
+            let handshake = io.recv_packet::<HandshakeC2s>().await?;
+
+            let handshake_data = HandshakeData {
+                protocol_version: handshake.protocol_version.0,
+                server_address: handshake.server_address.0.to_owned(),
+                server_port: handshake.server_port,
+            };
+
+            (handshake_data, handshake.next_state, None)
+        };
 
     match next_state {
         HandshakeNextState::Status => handle_status(shared, io, remote_addr, handshake)
             .await
             .context("handling status"),
         HandshakeNextState::Login => {
-            match handle_login(&shared, &mut io, remote_addr, handshake)
+            let server_address = handshake.server_address.clone();
+
+            match handle_login(&shared, &mut io, remote_addr, handshake, bungeecord_data)
                 .await
                 .context("handling login")?
             {
                 Some((info, cleanup)) => {
-                    let client = io.into_client_args(
-                        info,
-                        shared.0.incoming_byte_limit,
-                        shared.0.outgoing_byte_limit,
-                        cleanup,
-                    );
+                    let client = io.into_client_args(info, server_address, &shared, cleanup);
 
                     let _ = shared.0.new_clients_send.send_async(client).await;
 
@@ -228,6 +264,16 @@ async fn handle_status(
                 "description": description,
             });
 
+            let template_favicon;
+            let favicon_png = if favicon_png.is_empty() {
+                template_favicon = shared
+                    .status_template_for(&handshake.server_address)
+                    .favicon_png;
+                template_favicon.as_deref().unwrap_or_default()
+            } else {
+                favicon_png
+            };
+
             if !favicon_png.is_empty() {
                 let mut buf = "data:image/png;base64,".to_owned();
                 BASE64_STANDARD.encode_string(favicon_png, &mut buf);
@@ -255,12 +301,14 @@ async fn handle_login(
     io: &mut PacketIo,
     remote_addr: SocketAddr,
     handshake: HandshakeData,
+    bungeecord_data: Option<String>,
 ) -> anyhow::Result<Option<(NewClientInfo, CleanupOnDrop)>> {
-    if handshake.protocol_version != PROTOCOL_VERSION {
+    let callbacks = &shared.0.callbacks.inner;
+
+    if !callbacks.supports_protocol(shared, handshake.protocol_version) {
         io.send_packet(&LoginDisconnectS2c {
-            // TODO: use correct translation key.
-            reason: format!("Mismatched Minecraft version (server is on {MINECRAFT_VERSION})")
-                .color(Color::RED)
+            reason: callbacks
+                .version_mismatch_message(shared, &handshake)
                 .into(),
         })
         .await?;
@@ -268,6 +316,13 @@ async fn handle_login(
         return Ok(None);
     }
 
+    if let Some(translator) = shared
+        .version_policy()
+        .translator(handshake.protocol_version)
+    {
+        io.set_translator(translator);
+    }
+
     let LoginHelloC2s {
         username,
         profile_id: _, // TODO
@@ -276,12 +331,33 @@ async fn handle_login(
     let username = username.0.to_owned();
 
     let info = match shared.connection_mode() {
-        ConnectionMode::Online { .. } => login_online(shared, io, remote_addr, username).await?,
-        ConnectionMode::Offline => login_offline(remote_addr, username)?,
-        ConnectionMode::BungeeCord => {
-            login_bungeecord(remote_addr, &handshake.server_address, username)?
+        ConnectionMode::Online { .. } => {
+            if shared
+                .0
+                .auth
+                .allows_offline(shared, &username, remote_addr.ip())
+                .await
+            {
+                login_offline(remote_addr, username)?
+            } else {
+                login_online(shared, io, remote_addr, username).await?
+            }
         }
-        ConnectionMode::Velocity { secret } => login_velocity(io, username, secret).await?,
+        ConnectionMode::Offline => login_offline(remote_addr, username)?,
+        ConnectionMode::BungeeCord => match bungeecord_data {
+            Some(data) => login_bungeecord(&data, username)?,
+            None => {
+                disconnect_unforwarded(io, "BungeeCord").await?;
+                return Ok(None);
+            }
+        },
+        ConnectionMode::Velocity { secret } => match login_velocity(io, username, secret).await? {
+            Some(info) => info,
+            None => {
+                disconnect_unforwarded(io, "Velocity").await?;
+                return Ok(None);
+            }
+        },
     };
 
     if shared.0.threshold.0 > 0 {
@@ -293,6 +369,22 @@ async fn handle_login(
         io.set_compression(shared.0.threshold);
     }
 
+    let queries = shared
+        .0
+        .callbacks
+        .inner
+        .login_queries(shared, &info, &mut LoginQueries::new(io))
+        .await;
+
+    if let Err(reason) = queries {
+        info!("disconnect at login: \"{reason}\"");
+        io.send_packet(&LoginDisconnectS2c {
+            reason: reason.into(),
+        })
+        .await?;
+        return Ok(None);
+    }
+
     let cleanup = match shared.0.callbacks.inner.login(shared, &info).await {
         Ok(f) => CleanupOnDrop(Some(f)),
         Err(reason) => {
@@ -365,43 +457,20 @@ async fn login_online(
         .chain(&shared.0.public_key_der)
         .finalize();
 
-    let url = shared
+    let profile = shared
         .0
-        .callbacks
-        .inner
-        .session_server(
-            shared,
-            username.as_str(),
-            &auth_digest(&hash),
-            &remote_addr.ip(),
-        )
-        .await;
-
-    let resp = shared.0.http_client.get(url).send().await?;
-
-    match resp.status() {
-        StatusCode::OK => {}
-        StatusCode::NO_CONTENT => {
-            let reason = Text::translate(keys::MULTIPLAYER_DISCONNECT_UNVERIFIED_USERNAME, []);
-            io.send_packet(&LoginDisconnectS2c {
-                reason: reason.into(),
-            })
-            .await?;
-            bail!("session server could not verify username");
-        }
-        status => {
-            bail!("session server GET request failed (status code {status})");
-        }
-    }
-
-    #[derive(Deserialize)]
-    struct GameProfile {
-        id: Uuid,
-        name: String,
-        properties: Vec<Property>,
-    }
+        .auth
+        .has_joined(shared, &username, &auth_digest(&hash), remote_addr.ip())
+        .await?;
 
-    let profile: GameProfile = resp.json().await.context("parsing game profile")?;
+    let Some(profile) = profile else {
+        let reason = Text::translate(keys::MULTIPLAYER_DISCONNECT_UNVERIFIED_USERNAME, []);
+        io.send_packet(&LoginDisconnectS2c {
+            reason: reason.into(),
+        })
+        .await?;
+        bail!("session server could not verify username");
+    };
 
     ensure!(profile.name == username, "usernames do not match");
 
@@ -432,31 +501,26 @@ fn login_offline(remote_addr: SocketAddr, username: String) -> anyhow::Result<Ne
     })
 }
 
-/// Login procedure for BungeeCord.
-fn login_bungeecord(
-    remote_addr: SocketAddr,
-    server_address: &str,
-    username: String,
-) -> anyhow::Result<NewClientInfo> {
-    // Get data from server_address field of the handshake
-    let data = server_address.split('\0').take(4).collect::<Vec<_>>();
-
-    // Ip of player, only given if ip_forward on bungee is true
-    let ip = match data.get(1) {
-        Some(ip) => ip.parse()?,
-        None => remote_addr.ip(),
-    };
-
-    // Uuid of player, only given if ip_forward on bungee is true
-    let uuid = match data.get(2) {
-        Some(uuid) => uuid.parse()?,
-        None => offline_uuid(username.as_str())?,
-    };
-
-    // Read properties and get textures
-    // Properties of player's game profile, only given if ip_forward and online_mode
-    // on bungee both are true
-    let properties: Vec<Property> = match data.get(3) {
+/// Login procedure for BungeeCord. `data` is the player data appended to the
+/// server address of the handshake.
+fn login_bungeecord(data: &str, username: String) -> anyhow::Result<NewClientInfo> {
+    // The data is the IP and UUID of the player, followed by the properties of
+    // the player's game profile if the proxy is in online mode.
+    let mut fields = data.split('\0');
+
+    let ip = fields
+        .next()
+        .context("missing BungeeCord player IP")?
+        .parse()
+        .context("failed to parse BungeeCord player IP")?;
+
+    let uuid = fields
+        .next()
+        .context("missing BungeeCord player UUID")?
+        .parse()
+        .context("failed to parse BungeeCord player UUID")?;
+
+    let properties: Vec<Property> = match fields.next() {
         Some(properties) => serde_json::from_str(properties)
             .context("failed to parse BungeeCord player properties")?,
         None => vec![],
@@ -470,22 +534,24 @@ fn login_bungeecord(
     })
 }
 
-/// Login procedure for Velocity.
+/// Login procedure for Velocity. Returns `None` if the client didn't connect
+/// through Velocity.
 async fn login_velocity(
     io: &mut PacketIo,
     username: String,
     velocity_secret: &str,
-) -> anyhow::Result<NewClientInfo> {
-    const VELOCITY_MIN_SUPPORTED_VERSION: u8 = 1;
-    const VELOCITY_MODERN_FORWARDING_WITH_KEY_V2: i32 = 3;
+) -> anyhow::Result<Option<NewClientInfo>> {
+    /// The forwarding version without the player's chat session, which
+    /// 1.20.1 clients send after login instead.
+    const VELOCITY_MODERN_FORWARDING_DEFAULT: u8 = 1;
 
-    let message_id: i32 = 0; // TODO: make this random?
+    let message_id: i32 = rand::random();
 
     // Send Player Info Request into the Plugin Channel
     io.send_packet(&LoginQueryRequestS2c {
         message_id: VarInt(message_id),
         channel: ident!("velocity:player_info").into(),
-        data: RawBytes(&[VELOCITY_MIN_SUPPORTED_VERSION]).into(),
+        data: RawBytes(&[VELOCITY_MODERN_FORWARDING_DEFAULT]).into(),
     })
     .await?;
 
@@ -498,10 +564,12 @@ async fn login_velocity(
         plugin_response.message_id.0,
     );
 
-    let data = plugin_response
-        .data
-        .context("missing plugin response data")?
-        .0;
+    // Clients which didn't connect through Velocity don't understand the
+    // request.
+    let Some(data) = plugin_response.data else {
+        return Ok(None);
+    };
+    let data = data.0;
 
     ensure!(data.len() >= 32, "invalid plugin response data length");
     let (signature, mut data_without_signature) = data.split_at(32);
@@ -509,13 +577,19 @@ async fn login_velocity(
     // Verify signature
     let mut mac = Hmac::<Sha256>::new_from_slice(velocity_secret.as_bytes())?;
     Mac::update(&mut mac, data_without_signature);
-    mac.verify_slice(signature)?;
+    mac.verify_slice(signature)
+        .context("invalid Velocity forwarding signature")?;
 
     // Check Velocity version
     let version = VarInt::decode(&mut data_without_signature)
         .context("failed to decode velocity version")?
         .0;
 
+    ensure!(
+        version >= VELOCITY_MODERN_FORWARDING_DEFAULT as i32,
+        "unsupported Velocity forwarding version {version}"
+    );
+
     // Get client address
     let remote_addr = String::decode(&mut data_without_signature)?.parse()?;
 
@@ -532,23 +606,43 @@ async fn login_velocity(
     let properties = Vec::<Property>::decode(&mut data_without_signature)
         .context("decoding velocity game profile properties")?;
 
-    if version >= VELOCITY_MODERN_FORWARDING_WITH_KEY_V2 {
-        // TODO
-    }
-
-    Ok(NewClientInfo {
+    Ok(Some(NewClientInfo {
         uuid,
         username,
         properties: Properties(properties),
         ip: remote_addr,
+    }))
+}
+
+/// Disconnects a client which connected without going through the `proxy` the
+/// server is configured for.
+async fn disconnect_unforwarded(io: &mut PacketIo, proxy: &str) -> anyhow::Result<()> {
+    info!("disconnecting client which didn't connect through {proxy}");
+
+    io.send_packet(&LoginDisconnectS2c {
+        reason: format!("This server requires you to connect through {proxy}.")
+            .color(Color::RED)
+            .into(),
     })
+    .await
 }
 
 #[cfg(test)]
 mod tests {
+    use std::net::IpAddr;
+    use std::sync::Arc;
+
     use sha1::Digest;
+    use valence_server::client::ClientBundleArgs;
+    use valence_server::protocol::decode::PacketFrame;
+    use valence_server::protocol::CompressionThreshold;
+    use valence_server::PROTOCOL_VERSION;
 
     use super::*;
+    use crate::packet_io::TestClient;
+    use crate::{test_shared_state, NetworkSettings};
+
+    const NOTCH_UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
 
     #[test]
     fn auth_digest_usernames() {
@@ -565,4 +659,239 @@ mod tests {
             "88e16a1019277b15d58faf0541e11910eb756f6"
         );
     }
+
+    #[test]
+    fn bungeecord_player_data() {
+        let info = login_bungeecord(
+            "203.0.113.7\x00069a79f444e94726a5befca90e38aaf5\0[{\"name\":\"textures\",\"value\":\"\
+             abc\",\"signature\":\"def\"}]",
+            "Notch".into(),
+        )
+        .unwrap();
+
+        assert_eq!(info.ip, "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(info.uuid, NOTCH_UUID.parse::<Uuid>().unwrap());
+        assert_eq!(info.properties.0.len(), 1);
+        assert_eq!(info.properties.0[0].name, "textures");
+
+        // The properties are only forwarded by proxies in online mode.
+        let info = login_bungeecord(&format!("203.0.113.7\0{NOTCH_UUID}"), "Notch".into());
+        assert!(info.unwrap().properties.0.is_empty());
+
+        assert!(login_bungeecord(&format!("not an ip\0{NOTCH_UUID}"), "Notch".into()).is_err());
+        assert!(login_bungeecord("203.0.113.7", "Notch".into()).is_err());
+        assert!(login_bungeecord("203.0.113.7\0not a uuid", "Notch".into()).is_err());
+        assert!(login_bungeecord(
+            &format!("203.0.113.7\0{NOTCH_UUID}\0not json"),
+            "Notch".into()
+        )
+        .is_err());
+    }
+
+    /// Starts logging in a client with `server_address` in its handshake.
+    async fn start_login(
+        shared: &SharedNetworkState,
+        server_address: &str,
+    ) -> (TestClient, tokio::task::JoinHandle<anyhow::Result<()>>) {
+        let (mut client, io) = TestClient::connect(shared).await;
+        let remote_addr = "127.0.0.1:25565".parse().unwrap();
+        let task = tokio::spawn(handle_handshake(shared.clone(), io, remote_addr));
+
+        client
+            .send(&BungeeCordHandshakeC2s {
+                protocol_version: VarInt(PROTOCOL_VERSION),
+                server_address,
+                server_port: 25565,
+                next_state: HandshakeNextState::Login,
+            })
+            .await;
+
+        client
+            .send(&LoginHelloC2s {
+                username: "Notch".into(),
+                profile_id: None,
+            })
+            .await;
+
+        (client, task)
+    }
+
+    /// Receives packets until the login succeeds, and returns the arguments of
+    /// the new client.
+    async fn finish_login(
+        shared: &SharedNetworkState,
+        client: &mut TestClient,
+    ) -> ClientBundleArgs {
+        let mut frame = client.recv_frame().await.unwrap();
+
+        if frame.id == LoginCompressionS2c::ID {
+            let LoginCompressionS2c { threshold } = frame.decode().unwrap();
+            client.set_compression(CompressionThreshold(threshold.0));
+            frame = client.recv_frame().await.unwrap();
+        }
+
+        let success: LoginSuccessS2c = frame.decode().unwrap();
+        assert_eq!(success.username.0, "Notch");
+
+        shared.0.new_clients_recv.recv_async().await.unwrap()
+    }
+
+    fn assert_disconnected(frame: &PacketFrame) {
+        assert_eq!(frame.id, LoginDisconnectS2c::ID);
+    }
+
+    #[tokio::test]
+    async fn bungeecord_login() {
+        let shared = test_shared_state(NetworkSettings {
+            connection_mode: ConnectionMode::BungeeCord,
+            ..Default::default()
+        });
+
+        let address = format!("example.com\0203.0.113.7\0{}", NOTCH_UUID.replace('-', ""));
+        let (mut client, task) = start_login(&shared, &address).await;
+        let args = finish_login(&shared, &mut client).await;
+
+        task.await.unwrap().unwrap();
+        assert_eq!(args.server_address, "example.com");
+        assert_eq!(args.ip, "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(args.uuid, NOTCH_UUID.parse::<Uuid>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn bungeecord_unforwarded() {
+        let shared = test_shared_state(NetworkSettings {
+            connection_mode: ConnectionMode::BungeeCord,
+            ..Default::default()
+        });
+
+        let (mut client, task) = start_login(&shared, "example.com").await;
+
+        assert_disconnected(&client.recv_frame().await.unwrap());
+        task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn bungeecord_malformed() {
+        let shared = test_shared_state(NetworkSettings {
+            connection_mode: ConnectionMode::BungeeCord,
+            ..Default::default()
+        });
+
+        let (mut client, task) = start_login(&shared, "example.com\0not an ip\0nope").await;
+
+        assert!(task.await.unwrap().is_err());
+        assert!(client.recv_frame().await.is_err());
+
+        // The server address itself is still limited to 255 characters.
+        let address = format!("{}\0203.0.113.7\0{NOTCH_UUID}", "a".repeat(256));
+        let (_client, task) = start_login(&shared, &address).await;
+
+        assert!(task.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn long_address_without_bungeecord() {
+        let shared = test_shared_state(NetworkSettings {
+            connection_mode: ConnectionMode::Offline,
+            ..Default::default()
+        });
+
+        let address = format!("example.com\0203.0.113.7\0{}", "a".repeat(256));
+        let (_client, task) = start_login(&shared, &address).await;
+
+        assert!(task.await.unwrap().is_err());
+    }
+
+    /// Answers the Velocity player info request of the server, with the
+    /// forwarded data signed with `secret`.
+    async fn answer_velocity(client: &mut TestClient, secret: &str, data: Option<Vec<u8>>) {
+        let frame = client.recv_frame().await.unwrap();
+        let request: LoginQueryRequestS2c = frame.decode().unwrap();
+        assert_eq!(request.channel.as_str(), "velocity:player_info");
+
+        let data = data.map(|data| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+            Mac::update(&mut mac, &data);
+
+            let mut signed = mac.finalize().into_bytes().to_vec();
+            signed.extend(data);
+            signed
+        });
+
+        client
+            .send(&LoginQueryResponseC2s {
+                message_id: request.message_id,
+                data: data.as_deref().map(|data| RawBytes(data).into()),
+            })
+            .await;
+    }
+
+    fn velocity_data(username: &str) -> Vec<u8> {
+        let mut data = vec![];
+
+        VarInt(1).encode(&mut data).unwrap();
+        "203.0.113.7".encode(&mut data).unwrap();
+        NOTCH_UUID
+            .parse::<Uuid>()
+            .unwrap()
+            .encode(&mut data)
+            .unwrap();
+        username.encode(&mut data).unwrap();
+        Vec::<Property>::new().encode(&mut data).unwrap();
+
+        data
+    }
+
+    fn velocity_settings() -> NetworkSettings {
+        NetworkSettings {
+            connection_mode: ConnectionMode::Velocity {
+                secret: Arc::from("secret"),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn velocity_login() {
+        let shared = test_shared_state(velocity_settings());
+
+        let (mut client, task) = start_login(&shared, "example.com").await;
+        answer_velocity(&mut client, "secret", Some(velocity_data("Notch"))).await;
+        let args = finish_login(&shared, &mut client).await;
+
+        task.await.unwrap().unwrap();
+        assert_eq!(args.ip, "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(args.uuid, NOTCH_UUID.parse::<Uuid>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn velocity_unforwarded() {
+        let shared = test_shared_state(velocity_settings());
+
+        let (mut client, task) = start_login(&shared, "example.com").await;
+        answer_velocity(&mut client, "secret", None).await;
+
+        assert_disconnected(&client.recv_frame().await.unwrap());
+        task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn velocity_malformed() {
+        let shared = test_shared_state(velocity_settings());
+
+        // Signed with the wrong secret.
+        let (mut client, task) = start_login(&shared, "example.com").await;
+        answer_velocity(&mut client, "wrong", Some(velocity_data("Notch"))).await;
+        assert!(task.await.unwrap().is_err());
+
+        // Forwarded for another player.
+        let (mut client, task) = start_login(&shared, "example.com").await;
+        answer_velocity(&mut client, "secret", Some(velocity_data("jeb_"))).await;
+        assert!(task.await.unwrap().is_err());
+
+        // Truncated.
+        let (mut client, task) = start_login(&shared, "example.com").await;
+        answer_velocity(&mut client, "secret", Some(vec![1])).await;
+        assert!(task.await.unwrap().is_err());
+    }
 }
@@ -17,29 +17,42 @@
     clippy::dbg_macro
 )]
 
+pub mod auth;
 mod byte_channel;
 mod connect;
 mod legacy_ping;
+pub mod login_query;
 mod packet_io;
+pub mod rate_limit;
+pub mod status;
+pub mod tap;
+pub mod version;
+pub mod virtual_host;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use anyhow::Context;
 pub use async_trait::async_trait;
+use auth::{AuthBackend, MojangAuth};
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use connect::do_accept_loop;
 pub use connect::HandshakeData;
 use flume::{Receiver, Sender};
 pub use legacy_ping::{ServerListLegacyPingPayload, ServerListLegacyPingResponse};
+use login_query::LoginQueries;
 use rand::rngs::OsRng;
+use rate_limit::{NetworkMetrics, RateLimits};
 use rsa::traits::PublicKeyParts;
 use rsa::RsaPrivateKey;
 use serde::Serialize;
+use status::{StatusTemplate, StatusVariables};
+use tap::PacketTap;
 use tokio::net::UdpSocket;
 use tokio::runtime::{Handle, Runtime};
 use tokio::sync::Semaphore;
@@ -48,7 +61,8 @@ use tracing::error;
 use uuid::Uuid;
 use valence_protocol::text::IntoText;
 use valence_server::client::{ClientBundle, ClientBundleArgs, Properties, SpawnClientsSet};
-use valence_server::{CompressionThreshold, Server, Text, MINECRAFT_VERSION, PROTOCOL_VERSION};
+use valence_server::{CompressionThreshold, Server, Text, PROTOCOL_VERSION};
+use version::VersionPolicy;
 
 pub struct NetworkPlugin;
 
@@ -92,9 +106,23 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
 
     let shared = SharedNetworkState(Arc::new(SharedNetworkStateInner {
         callbacks: settings.callbacks.clone(),
+        auth: settings.auth.clone(),
         address: settings.address,
         incoming_byte_limit: settings.incoming_byte_limit,
         outgoing_byte_limit: settings.outgoing_byte_limit,
+        rate_limits: settings.rate_limits.clone(),
+        metrics: Arc::new(NetworkMetrics::default()),
+        packet_taps: RwLock::new(vec![]),
+        status_template: RwLock::new(settings.status_template.clone()),
+        host_status_templates: RwLock::new(
+            settings
+                .host_status_templates
+                .iter()
+                .map(|(host, template)| (status::normalize_host(host), template.clone()))
+                .collect(),
+        ),
+        status_variables: StatusVariables::default(),
+        version_policy: RwLock::new(settings.version_policy.clone()),
         connection_sema: Arc::new(Semaphore::new(
             settings.max_connections.min(Semaphore::MAX_PERMITS),
         )),
@@ -147,6 +175,10 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
     // Spawn new clients before the event loop starts.
     app.add_systems(PreUpdate, spawn_new_clients.in_set(SpawnClientsSet));
 
+    app.add_systems(PostUpdate, status::publish_layer_player_counts);
+
+    virtual_host::build(app);
+
     Ok(())
 }
 
@@ -165,12 +197,48 @@ impl SharedNetworkState {
     pub fn max_players(&self) -> usize {
         self.0.max_players
     }
+
+    /// Returns the traffic counters of all connections.
+    pub fn metrics(&self) -> &NetworkMetrics {
+        &self.0.metrics
+    }
+
+    /// Registers a [`PacketTap`] to observe the packets of connections
+    /// accepted from now on. See the [`tap`] module for more information.
+    pub fn add_packet_tap(&self, tap: impl PacketTap) {
+        self.0.packet_taps.write().unwrap().push(Arc::new(tap));
+    }
+}
+
+/// Builds the shared state of the network plugin with `settings` on the
+/// current tokio runtime, without accepting connections.
+#[cfg(test)]
+pub(crate) fn test_shared_state(mut settings: NetworkSettings) -> SharedNetworkState {
+    settings.tokio_handle = Some(Handle::current());
+
+    let mut app = App::new();
+    app.add_plugins(valence_server::ServerPlugin)
+        .insert_resource(settings);
+
+    build_plugin(&mut app).unwrap();
+
+    app.world.resource::<SharedNetworkState>().clone()
 }
+
 struct SharedNetworkStateInner {
     callbacks: ErasedNetworkCallbacks,
+    auth: Arc<dyn AuthBackend>,
     address: SocketAddr,
     incoming_byte_limit: usize,
     outgoing_byte_limit: usize,
+    rate_limits: RateLimits,
+    metrics: Arc<NetworkMetrics>,
+    packet_taps: RwLock<Vec<Arc<dyn PacketTap>>>,
+    status_template: RwLock<StatusTemplate>,
+    /// The status templates of host names, which are normalized.
+    host_status_templates: RwLock<HashMap<String, StatusTemplate>>,
+    status_variables: StatusVariables,
+    version_policy: RwLock<VersionPolicy>,
     /// Limits the number of simultaneous connections to the server before the
     /// play state.
     connection_sema: Arc<Semaphore>,
@@ -216,6 +284,15 @@ pub struct NewClientInfo {
 #[derive(Resource, Clone)]
 pub struct NetworkSettings {
     pub callbacks: ErasedNetworkCallbacks,
+    /// Authenticates clients when the [`connection_mode`] is
+    /// [`ConnectionMode::Online`].
+    ///
+    /// # Default Value
+    ///
+    /// [`MojangAuth`]
+    ///
+    /// [`connection_mode`]: Self::connection_mode
+    pub auth: Arc<dyn AuthBackend>,
     /// The [`Handle`] to the tokio runtime the server will use. If `None` is
     /// provided, the server will create its own tokio runtime at startup.
     ///
@@ -274,12 +351,45 @@ pub struct NetworkSettings {
     ///
     /// The default value is left unspecified and may change in future versions.
     pub outgoing_byte_limit: usize,
+    /// Limits on the packets received from and bytes sent to each
+    /// connection, protecting the server from malicious or broken clients.
+    ///
+    /// # Default Value
+    ///
+    /// See [`RateLimits`].
+    pub rate_limits: RateLimits,
+    /// The MOTDs and player sample shown in the server list by the default
+    /// [`NetworkCallbacks::server_list_ping`]. Can be changed after the
+    /// plugin is built with [`SharedNetworkState::set_status_template`].
+    ///
+    /// # Default Value
+    ///
+    /// `StatusTemplate::new("A Valence Server")`
+    pub status_template: StatusTemplate,
+    /// The status templates used instead of
+    /// [`status_template`](Self::status_template) for clients connecting with
+    /// a host name in the map. Can be changed after the plugin is built with
+    /// [`SharedNetworkState::set_host_status_template`].
+    ///
+    /// # Default Value
+    ///
+    /// An empty map.
+    pub host_status_templates: HashMap<String, StatusTemplate>,
+    /// How clients with a different protocol version than the server are
+    /// treated. Can be changed after the plugin is built with
+    /// [`SharedNetworkState::set_version_policy`].
+    ///
+    /// # Default Value
+    ///
+    /// `VersionPolicy::new()`
+    pub version_policy: VersionPolicy,
 }
 
 impl Default for NetworkSettings {
     fn default() -> Self {
         Self {
             callbacks: ErasedNetworkCallbacks::default(),
+            auth: Arc::new(MojangAuth),
             tokio_handle: None,
             max_connections: 1024,
             max_players: 20,
@@ -289,6 +399,10 @@ impl Default for NetworkSettings {
             },
             incoming_byte_limit: 2097152, // 2 MiB
             outgoing_byte_limit: 8388608, // 8 MiB
+            rate_limits: RateLimits::default(),
+            status_template: StatusTemplate::default(),
+            host_status_templates: HashMap::new(),
+            version_policy: VersionPolicy::default(),
         }
     }
 }
@@ -332,7 +446,14 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
     ///
     /// # Default Implementation
     ///
-    /// A default placeholder response is returned.
+    /// The response is rendered from the [status template] of the host name
+    /// the client connected with. Unless the template overrides the version,
+    /// clients with a protocol version which isn't
+    /// [supported](Self::supports_protocol) are shown the mismatch label of
+    /// the [version policy].
+    ///
+    /// [status template]: SharedNetworkState::status_template_for
+    /// [version policy]: SharedNetworkState::version_policy
     async fn server_list_ping(
         &self,
         shared: &SharedNetworkState,
@@ -341,17 +462,69 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
     ) -> ServerListPing {
         #![allow(unused_variables)]
 
+        let template = shared.status_template_for(&handshake_data.server_address);
+        let client_protocol = handshake_data.protocol_version;
+
+        let (version_name, protocol) = if let Some(version) = template.version.clone() {
+            version
+        } else if self.supports_protocol(shared, client_protocol) {
+            (
+                shared.version_policy().version_name(client_protocol),
+                client_protocol,
+            )
+        } else {
+            (
+                shared
+                    .version_policy()
+                    .render_mismatch_label(shared, client_protocol),
+                PROTOCOL_VERSION,
+            )
+        };
+
         ServerListPing::Respond {
-            online_players: shared.player_count().load(Ordering::Relaxed) as i32,
-            max_players: shared.max_players() as i32,
-            player_sample: vec![],
-            description: "A Valence Server".into_text(),
+            online_players: template.render_online_players(shared),
+            max_players: template.render_max_players(shared),
+            player_sample: template.render_player_sample(shared),
+            description: template.render_motd(shared).into_text(),
+            // Filled in from the template when the response is sent.
             favicon_png: &[],
-            version_name: MINECRAFT_VERSION.to_owned(),
-            protocol: PROTOCOL_VERSION,
+            version_name,
+            protocol,
         }
     }
 
+    /// Called to check if clients with the protocol version `protocol` may
+    /// join. Clients with other versions are disconnected with
+    /// [`version_mismatch_message`] when they try to log in.
+    ///
+    /// # Default Implementation
+    ///
+    /// Uses [`VersionPolicy::supports`] of the [version policy].
+    ///
+    /// [`version_mismatch_message`]: Self::version_mismatch_message
+    /// [version policy]: SharedNetworkState::version_policy
+    fn supports_protocol(&self, shared: &SharedNetworkState, protocol: i32) -> bool {
+        shared.version_policy().supports(protocol)
+    }
+
+    /// Called when a client with an unsupported protocol version tries to log
+    /// in, to get the reason it is disconnected with.
+    ///
+    /// # Default Implementation
+    ///
+    /// The mismatch message of the [version policy].
+    ///
+    /// [version policy]: SharedNetworkState::version_policy
+    fn version_mismatch_message(
+        &self,
+        shared: &SharedNetworkState,
+        handshake_data: &HandshakeData,
+    ) -> Text {
+        #![allow(unused_variables)]
+
+        shared.version_policy().mismatch_message
+    }
+
     /// Called when the server receives a Server List Legacy Ping query.
     /// Data for the response can be provided or the query can be ignored.
     ///
@@ -471,9 +644,36 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
         }
     }
 
+    /// Called after a client is authenticated and before [`login`] to exchange
+    /// login plugin messages with the client, such as to negotiate with
+    /// modded clients or proxies. See the [`login_query`] module for more
+    /// information.
+    ///
+    /// If an error is returned, the client is disconnected with the error as
+    /// the reason.
+    ///
+    /// This method is called from within a tokio runtime.
+    ///
+    /// # Default Implementation
+    ///
+    /// No requests are sent.
+    ///
+    /// [`login`]: Self::login
+    async fn login_queries(
+        &self,
+        shared: &SharedNetworkState,
+        info: &NewClientInfo,
+        queries: &mut LoginQueries<'_>,
+    ) -> Result<(), Text> {
+        #![allow(unused_variables)]
+
+        Ok(())
+    }
+
     /// Called upon every client login to obtain the full URL to use for session
     /// server requests. This is done to authenticate player accounts. This
-    /// method is not called unless [online mode] is enabled.
+    /// method is not called unless [online mode] is enabled and the
+    /// [`auth::MojangAuth`] backend is in use.
     ///
     /// It is assumed that upon successful request, a structure matching the
     /// description in the [wiki](https://wiki.vg/Protocol_Encryption#Server) was obtained.
@@ -563,7 +763,8 @@ pub enum ConnectionMode {
     ///   forwarding mode.
     ///
     /// All player data (username, UUID, and properties) is fetched from the
-    /// proxy, but no attempt is made to stop connections originating from
+    /// proxy. Connections without forwarded player data are disconnected, but
+    /// no attempt is made to stop forged connections originating from
     /// elsewhere. As a result, you must ensure clients connect through the
     /// proxy and are unable to connect to the server directly. Otherwise,
     /// clients can use any username or UUID they choose similar to
@@ -580,8 +781,9 @@ pub enum ConnectionMode {
     /// configured with the forwarding mode `modern`.
     ///
     /// All player data (username, UUID, and properties) is fetched from the
-    /// proxy and all connections originating from outside Velocity are
-    /// blocked.
+    /// proxy, and all connections originating from outside Velocity are
+    /// disconnected because they can't sign the forwarded data with the
+    /// secret key.
     ///
     /// [Velocity]: https://velocitypowered.com/
     Velocity {
@@ -612,7 +814,11 @@ pub enum ServerListPing<'a> {
         /// The server's icon as the bytes of a PNG image.
         /// The image must be 64x64 pixels.
         ///
-        /// No icon is used if the slice is empty.
+        /// If the slice is empty, the [favicon of the status template] for
+        /// the address the client connected with is used. No icon is shown if
+        /// that is `None`.
+        ///
+        /// [favicon of the status template]: crate::status::StatusTemplate::favicon_png
         favicon_png: &'a [u8],
         /// The version name of the server. Displayed when client is using a
         /// different protocol.
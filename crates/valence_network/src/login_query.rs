@@ -0,0 +1,267 @@
+//! Exchanging login plugin messages with clients before they spawn.
+//!
+//! During login, the server may send requests on plugin channels which the
+//! client, or a proxy in front of it, answers before the player joins the
+//! game. Modded clients use this to negotiate features, such as the networking
+//! handshake of Fabric, and proxies use it to forward player information.
+//!
+//! Requests are made from [`NetworkCallbacks::login_queries`] through the
+//! [`LoginQueries`] of the connection. Clients which don't understand a
+//! channel respond without data.
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_network::login_query::{LoginQueries, LoginQuery};
+//! use valence_network::{async_trait, NetworkCallbacks, NewClientInfo, SharedNetworkState};
+//! use valence_server::ident::{ident, Ident};
+//! use valence_server::Text;
+//!
+//! struct VersionQuery;
+//!
+//! impl LoginQuery for VersionQuery {
+//!     const CHANNEL: Ident<&'static str> = ident!("my_mod:version");
+//!     type Response = u8;
+//!
+//!     fn encode(&self, _buf: &mut Vec<u8>) {}
+//!
+//!     fn decode_response(data: &[u8]) -> anyhow::Result<u8> {
+//!         data.first()
+//!             .copied()
+//!             .ok_or_else(|| anyhow::anyhow!("empty response"))
+//!     }
+//! }
+//!
+//! struct MyCallbacks;
+//!
+//! #[async_trait]
+//! impl NetworkCallbacks for MyCallbacks {
+//!     async fn login_queries(
+//!         &self,
+//!         _shared: &SharedNetworkState,
+//!         _info: &NewClientInfo,
+//!         queries: &mut LoginQueries<'_>,
+//!     ) -> Result<(), Text> {
+//!         match queries.query(&VersionQuery).await {
+//!             Ok(Some(version)) if version >= 2 => Ok(()),
+//!             Ok(Some(_)) => Err("Please update my_mod".into()),
+//!             Ok(None) => Err("This server requires my_mod".into()),
+//!             Err(_) => Err("Failed to negotiate with my_mod".into()),
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! [`NetworkCallbacks::login_queries`]: crate::NetworkCallbacks::login_queries
+
+use std::time::Duration;
+
+use anyhow::{anyhow, ensure};
+use valence_server::ident::Ident;
+use valence_server::protocol::packets::login::{LoginQueryRequestS2c, LoginQueryResponseC2s};
+use valence_server::protocol::{RawBytes, VarInt};
+
+use crate::packet_io::PacketIo;
+
+/// A request sent on a login plugin channel with a typed response.
+pub trait LoginQuery {
+    /// The channel the request is sent on.
+    const CHANNEL: Ident<&'static str>;
+
+    type Response;
+
+    /// Encodes this request as the payload of the login plugin request.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Decodes the payload of the response to this request.
+    fn decode_response(data: &[u8]) -> anyhow::Result<Self::Response>;
+}
+
+/// The login plugin message exchange of a connection which is logging in.
+pub struct LoginQueries<'a> {
+    io: &'a mut PacketIo,
+    next_message_id: i32,
+    timeout: Duration,
+}
+
+impl<'a> LoginQueries<'a> {
+    pub(crate) fn new(io: &'a mut PacketIo) -> Self {
+        Self {
+            io,
+            next_message_id: 0,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// How long to wait for the response to a request before failing.
+    ///
+    /// # Default Value
+    ///
+    /// 10 seconds
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Sends `data` on `channel` and waits for the response. Returns `None` if
+    /// the client doesn't understand the channel.
+    ///
+    /// An error is returned if the connection fails, the response doesn't
+    /// arrive within the [timeout](Self::timeout), or the client responds to
+    /// a different request. The connection can't be used after an error, so
+    /// the client should be disconnected.
+    pub async fn request(
+        &mut self,
+        channel: Ident<&str>,
+        data: &[u8],
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+
+        self.io
+            .send_packet(&LoginQueryRequestS2c {
+                message_id: VarInt(message_id),
+                channel: channel.into(),
+                data: RawBytes(data).into(),
+            })
+            .await?;
+
+        let response =
+            tokio::time::timeout(self.timeout, self.io.recv_packet::<LoginQueryResponseC2s>())
+                .await
+                .map_err(|_| anyhow!("timed out waiting for response on channel {channel}"))??;
+
+        ensure!(
+            response.message_id.0 == message_id,
+            "mismatched login plugin response ID (got {}, expected {message_id})",
+            response.message_id.0
+        );
+
+        Ok(response.data.map(|data| data.0.to_vec()))
+    }
+
+    /// Sends the typed request `query` and decodes the response. Returns `None`
+    /// if the client doesn't understand the channel of `Q`.
+    pub async fn query<Q: LoginQuery>(&mut self, query: &Q) -> anyhow::Result<Option<Q::Response>> {
+        let mut buf = vec![];
+        query.encode(&mut buf);
+
+        match self.request(Q::CHANNEL, &buf).await? {
+            Some(data) => Q::decode_response(&data).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::ident::ident;
+    use valence_server::protocol::Packet;
+
+    use super::*;
+    use crate::packet_io::TestClient;
+    use crate::{test_shared_state, NetworkSettings};
+
+    struct VersionQuery;
+
+    impl LoginQuery for VersionQuery {
+        const CHANNEL: Ident<&'static str> = ident!("test:version");
+        type Response = u8;
+
+        fn encode(&self, buf: &mut Vec<u8>) {
+            buf.push(42);
+        }
+
+        fn decode_response(data: &[u8]) -> anyhow::Result<u8> {
+            data.first()
+                .copied()
+                .ok_or_else(|| anyhow!("empty response"))
+        }
+    }
+
+    /// Answers the next request with `data`, offsetting its message ID by
+    /// `id_offset`.
+    async fn answer(client: &mut TestClient, id_offset: i32, data: Option<&[u8]>) {
+        let frame = client.recv_frame().await.unwrap();
+        let request: LoginQueryRequestS2c = frame.decode().unwrap();
+
+        assert_eq!(request.channel.as_str(), "test:version");
+        assert_eq!(request.data.0 .0, [42]);
+
+        client
+            .send(&LoginQueryResponseC2s {
+                message_id: VarInt(request.message_id.0 + id_offset),
+                data: data.map(|data| RawBytes(data).into()),
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn matching_response() {
+        let shared = test_shared_state(NetworkSettings::default());
+        let (mut client, mut io) = TestClient::connect(&shared).await;
+        let mut queries = LoginQueries::new(&mut io);
+
+        let (res, ()) = tokio::join!(
+            queries.query(&VersionQuery),
+            answer(&mut client, 0, Some(&[3]))
+        );
+        assert_eq!(res.unwrap(), Some(3));
+
+        // Message IDs aren't reused.
+        let (res, ()) = tokio::join!(
+            queries.query(&VersionQuery),
+            answer(&mut client, 0, Some(&[4]))
+        );
+        assert_eq!(res.unwrap(), Some(4));
+    }
+
+    #[tokio::test]
+    async fn mismatched_response_id() {
+        let shared = test_shared_state(NetworkSettings::default());
+        let (mut client, mut io) = TestClient::connect(&shared).await;
+        let mut queries = LoginQueries::new(&mut io);
+
+        let (res, ()) = tokio::join!(
+            queries.query(&VersionQuery),
+            answer(&mut client, 1, Some(&[3]))
+        );
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn response_timeout() {
+        let shared = test_shared_state(NetworkSettings::default());
+        let (mut client, mut io) = TestClient::connect(&shared).await;
+        let mut queries = LoginQueries::new(&mut io);
+        queries.set_timeout(Duration::from_millis(50));
+
+        // The client receives the request but never answers.
+        let (res, frame) = tokio::join!(queries.query(&VersionQuery), client.recv_frame());
+        assert_eq!(frame.unwrap().id, LoginQueryRequestS2c::ID);
+
+        let err = res.unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err:#}");
+    }
+
+    #[tokio::test]
+    async fn empty_response() {
+        let shared = test_shared_state(NetworkSettings::default());
+        let (mut client, mut io) = TestClient::connect(&shared).await;
+        let mut queries = LoginQueries::new(&mut io);
+
+        // Clients which don't understand the channel respond without data.
+        let (res, ()) = tokio::join!(queries.query(&VersionQuery), answer(&mut client, 0, None));
+        assert_eq!(res.unwrap(), None);
+
+        // An empty payload is passed on to the query to decode.
+        let (res, ()) = tokio::join!(
+            queries.query(&VersionQuery),
+            answer(&mut client, 0, Some(&[]))
+        );
+        assert!(res.is_err());
+    }
+}
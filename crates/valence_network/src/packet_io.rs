@@ -1,6 +1,7 @@
 use std::io::ErrorKind;
-use std::sync::Arc;
-use std::time::Instant;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{io, mem};
 
 use anyhow::bail;
@@ -13,22 +14,43 @@ use tracing::{debug, warn};
 use valence_protocol::CompressionThreshold;
 use valence_server::client::{ClientBundleArgs, ClientConnection, ReceivedPacket};
 use valence_server::protocol::decode::PacketFrame;
-use valence_server::protocol::{Decode, Encode, Packet, PacketDecoder, PacketEncoder};
+use valence_server::protocol::translate::PacketTranslator;
+use valence_server::protocol::{
+    Decode, Encode, Packet, PacketDecoder, PacketEncoder, PacketSide, PacketState,
+};
+use valence_server::protocol_error::ProtocolError;
 
 use crate::byte_channel::{byte_channel, ByteSender, TrySendError};
-use crate::{CleanupOnDrop, NewClientInfo};
+use crate::rate_limit::{NetworkMetrics, PacketLimiter, TokenBucket, Verdict};
+use crate::tap::{Observers, OutboundTap, TappedPacket};
+use crate::{CleanupOnDrop, NewClientInfo, SharedNetworkState};
 
 pub(crate) struct PacketIo {
     stream: TcpStream,
     enc: PacketEncoder,
     dec: PacketDecoder,
     frame: PacketFrame,
+    /// Limits the packets received before the play state.
+    limiter: PacketLimiter,
+    metrics: Arc<NetworkMetrics>,
+    /// Observes the packets of this connection if any packet tap is
+    /// interested in it.
+    tap: Option<OutboundTap>,
 }
 
 const READ_BUF_SIZE: usize = 4096;
 
 impl PacketIo {
-    pub(crate) fn new(stream: TcpStream, enc: PacketEncoder, dec: PacketDecoder) -> Self {
+    pub(crate) fn new(
+        stream: TcpStream,
+        enc: PacketEncoder,
+        dec: PacketDecoder,
+        shared: &SharedNetworkState,
+        remote_addr: SocketAddr,
+    ) -> Self {
+        let limits = &shared.0.rate_limits;
+        let taps = shared.0.packet_taps.read().unwrap();
+
         Self {
             stream,
             enc,
@@ -37,6 +59,9 @@ impl PacketIo {
                 id: -1,
                 body: BytesMut::new(),
             },
+            limiter: PacketLimiter::new(limits.login_packets_per_second, limits.policy),
+            metrics: shared.0.metrics.clone(),
+            tap: Observers::open(&taps, remote_addr).map(OutboundTap::new),
         }
     }
 
@@ -47,6 +72,11 @@ impl PacketIo {
         self.enc.append_packet(pkt)?;
         let bytes = self.enc.take();
         self.stream.write_all(&bytes).await?;
+
+        if let Some(tap) = &mut self.tap {
+            tap.written(&bytes, P::STATE);
+        }
+
         Ok(())
     }
 
@@ -55,7 +85,34 @@ impl PacketIo {
         P: Packet + Decode<'a>,
     {
         loop {
-            if let Some(frame) = self.dec.try_next_packet()? {
+            if let Some(mut frame) = self.dec.try_next_packet()? {
+                match self.limiter.check(Instant::now()) {
+                    Verdict::Accept => {}
+                    // Every packet before the play state is expected by the
+                    // login sequence, so dropping one would leave the
+                    // connection stuck. Close it even with the drop policy.
+                    Verdict::Drop | Verdict::Kick => {
+                        self.metrics.add_kicked();
+                        bail!("exceeded the packet rate limit");
+                    }
+                }
+
+                if let Some(translator) = self.enc.translator() {
+                    if !translator.serverbound(P::STATE, &mut frame)? {
+                        continue;
+                    }
+                }
+
+                if let Some(tap) = &self.tap {
+                    tap.observers().inbound(&TappedPacket {
+                        side: PacketSide::Serverbound,
+                        state: P::STATE,
+                        id: frame.id,
+                        body: &frame.body,
+                        timestamp: Instant::now(),
+                    });
+                }
+
                 self.frame = frame;
 
                 return self.frame.decode();
@@ -74,38 +131,75 @@ impl PacketIo {
         }
     }
 
+    /// Translates the packets of this connection from now on. The connection
+    /// must be in the login state.
+    pub(crate) fn set_translator(&mut self, translator: Arc<dyn PacketTranslator>) {
+        self.enc
+            .set_translator(Some(translator), PacketState::Login);
+    }
+
     #[allow(dead_code)]
     pub(crate) fn set_compression(&mut self, threshold: CompressionThreshold) {
         self.enc.set_compression(threshold);
         self.dec.set_compression(threshold);
+
+        if let Some(tap) = &mut self.tap {
+            tap.set_compression(threshold);
+        }
     }
 
     pub(crate) fn enable_encryption(&mut self, key: &[u8; 16]) {
         self.enc.enable_encryption(key);
         self.dec.enable_encryption(key);
+
+        if let Some(tap) = &mut self.tap {
+            tap.enable_encryption(key);
+        }
     }
 
     pub(crate) fn into_client_args(
         mut self,
         info: NewClientInfo,
-        incoming_byte_limit: usize,
-        outgoing_byte_limit: usize,
+        server_address: String,
+        shared: &SharedNetworkState,
         cleanup: CleanupOnDrop,
     ) -> ClientBundleArgs {
         let (incoming_sender, incoming_receiver) = flume::unbounded();
 
-        let incoming_byte_limit = incoming_byte_limit.min(Semaphore::MAX_PERMITS);
+        let incoming_byte_limit = shared.0.incoming_byte_limit.min(Semaphore::MAX_PERMITS);
+        let outgoing_byte_limit = shared.0.outgoing_byte_limit;
+        let limits = &shared.0.rate_limits;
+
+        let mut limiter = PacketLimiter::new(limits.play_packets_per_second, limits.policy);
+        let mut outgoing_budget = limits.outgoing_bytes_per_second.map(TokenBucket::new);
+
+        let metrics = self.metrics.clone();
+        let writer_metrics = self.metrics.clone();
+
+        let mut tap = self.tap.take();
+        let observers = tap.as_ref().map(|tap| tap.observers().clone());
+
+        if let Some(observers) = &observers {
+            observers.logged_in(&info);
+        }
 
         let recv_sem = Arc::new(Semaphore::new(incoming_byte_limit));
         let recv_sem_clone = recv_sem.clone();
 
+        let frame_error = Arc::new(Mutex::new(None));
+        let frame_error_clone = frame_error.clone();
+
         let (mut reader, mut writer) = self.stream.into_split();
 
+        // The client is in the play state from now on.
+        self.enc.set_state(PacketState::Play);
+        let translator = self.enc.translator().cloned();
+
         let reader_task = tokio::spawn(async move {
             let mut buf = BytesMut::new();
 
             loop {
-                let frame = match self.dec.try_next_packet() {
+                let mut frame = match self.dec.try_next_packet() {
                     Ok(Some(frame)) => frame,
                     Ok(None) => {
                         // Incomplete packet. Need more data.
@@ -113,7 +207,7 @@ impl PacketIo {
                         buf.reserve(READ_BUF_SIZE);
                         match reader.read_buf(&mut buf).await {
                             Ok(0) => break, // Reader is at EOF.
-                            Ok(_) => {}
+                            Ok(n) => metrics.add_received_bytes(n),
                             Err(e) => {
                                 debug!("error reading data from stream: {e}");
                                 break;
@@ -126,12 +220,40 @@ impl PacketIo {
                     }
                     Err(e) => {
                         warn!("error decoding packet frame: {e:#}");
+                        *frame_error.lock().unwrap() = Some(ProtocolError::frame(e));
                         break;
                     }
                 };
 
                 let timestamp = Instant::now();
 
+                metrics.add_received();
+
+                match limiter.check(timestamp) {
+                    Verdict::Accept => {}
+                    Verdict::Drop => {
+                        metrics.add_dropped();
+                        continue;
+                    }
+                    Verdict::Kick => {
+                        debug!("client exceeded the packet rate limit");
+                        metrics.add_kicked();
+                        break;
+                    }
+                }
+
+                if let Some(translator) = &translator {
+                    match translator.serverbound(PacketState::Play, &mut frame) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(e) => {
+                            warn!("error translating packet: {e:#}");
+                            *frame_error.lock().unwrap() = Some(ProtocolError::frame(e));
+                            break;
+                        }
+                    }
+                }
+
                 // Estimate memory usage of this packet.
                 let cost = mem::size_of::<ReceivedPacket>() + frame.body.len();
 
@@ -155,6 +277,16 @@ impl PacketIo {
                 // The permits will be added back on the other side of the channel.
                 permits.forget();
 
+                if let Some(observers) = &observers {
+                    observers.inbound(&TappedPacket {
+                        side: PacketSide::Serverbound,
+                        state: PacketState::Play,
+                        id: frame.id,
+                        body: &frame.body,
+                        timestamp,
+                    });
+                }
+
                 let packet = ReceivedPacket {
                     timestamp,
                     id: frame.id,
@@ -180,9 +312,26 @@ impl PacketIo {
                     }
                 };
 
+                // Hold back data over the byte budget. Further data is queued in the
+                // meantime, up to the outgoing byte limit.
+                let delay = match &mut outgoing_budget {
+                    Some(budget) => budget.take_debt(bytes.len(), Instant::now()),
+                    None => Duration::ZERO,
+                };
+
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+
+                writer_metrics.add_sent(bytes.len(), delay);
+
                 if let Err(e) = writer.write_all(&bytes).await {
                     debug!("error writing data to stream: {e}");
                 }
+
+                if let Some(tap) = &mut tap {
+                    tap.written(&bytes, PacketState::Play);
+                }
             }
         });
 
@@ -190,11 +339,13 @@ impl PacketIo {
             username: info.username,
             uuid: info.uuid,
             ip: info.ip,
+            server_address,
             properties: info.properties.0,
             conn: Box::new(RealClientConnection {
                 send: outgoing_sender,
                 recv: incoming_receiver,
                 recv_sem: recv_sem_clone,
+                frame_error: frame_error_clone,
                 reader_task,
                 writer_task,
                 _cleanup: cleanup,
@@ -210,6 +361,9 @@ struct RealClientConnection {
     /// Limits the amount of data queued in the `recv` channel. Each permit
     /// represents one byte.
     recv_sem: Arc<Semaphore>,
+    /// The error which stopped the reader task, if the client sent a
+    /// malformed packet frame.
+    frame_error: Arc<Mutex<Option<ProtocolError>>>,
     _cleanup: CleanupOnDrop,
     reader_task: JoinHandle<()>,
     writer_task: JoinHandle<()>,
@@ -238,7 +392,13 @@ impl ClientConnection for RealClientConnection {
                 Ok(Some(packet))
             }
             Err(flume::TryRecvError::Empty) => Ok(None),
-            Err(flume::TryRecvError::Disconnected) => bail!("client disconnected"),
+            Err(flume::TryRecvError::Disconnected) => {
+                if let Some(error) = self.frame_error.lock().unwrap().take() {
+                    return Err(error.into());
+                }
+
+                bail!("client disconnected")
+            }
         }
     }
 
@@ -253,3 +413,108 @@ impl Drop for RealClientConnection {
         self.reader_task.abort();
     }
 }
+
+/// The client end of a local connection to a [`PacketIo`], for tests.
+#[cfg(test)]
+pub(crate) struct TestClient {
+    stream: TcpStream,
+    enc: PacketEncoder,
+    dec: PacketDecoder,
+}
+
+#[cfg(test)]
+impl TestClient {
+    /// Connects a new client to a new [`PacketIo`] over a local TCP
+    /// connection.
+    pub(crate) async fn connect(shared: &SharedNetworkState) -> (Self, PacketIo) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (stream, accepted) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        let (server_stream, remote_addr) = accepted.unwrap();
+
+        let io = PacketIo::new(
+            server_stream,
+            PacketEncoder::new(),
+            PacketDecoder::new(),
+            shared,
+            remote_addr,
+        );
+
+        let client = Self {
+            stream: stream.unwrap(),
+            enc: PacketEncoder::new(),
+            dec: PacketDecoder::new(),
+        };
+
+        (client, io)
+    }
+
+    pub(crate) async fn send<P>(&mut self, pkt: &P)
+    where
+        P: Packet + Encode,
+    {
+        self.enc.append_packet(pkt).unwrap();
+        self.stream.write_all(&self.enc.take()).await.unwrap();
+    }
+
+    /// Receives the next packet from the server, or returns an error if the
+    /// server closed the connection.
+    pub(crate) async fn recv_frame(&mut self) -> anyhow::Result<PacketFrame> {
+        loop {
+            if let Some(frame) = self.dec.try_next_packet()? {
+                return Ok(frame);
+            }
+
+            self.dec.reserve(READ_BUF_SIZE);
+            let mut buf = self.dec.take_capacity();
+
+            if self.stream.read_buf(&mut buf).await? == 0 {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+
+            self.dec.queue_bytes(buf);
+        }
+    }
+
+    pub(crate) fn set_compression(&mut self, threshold: CompressionThreshold) {
+        self.enc.set_compression(threshold);
+        self.dec.set_compression(threshold);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::protocol::packets::status::QueryPingC2s;
+
+    use super::*;
+    use crate::rate_limit::{LimitPolicy, RateLimits};
+    use crate::{test_shared_state, NetworkSettings};
+
+    #[tokio::test]
+    async fn login_rate_limit_always_kicks() {
+        let shared = test_shared_state(NetworkSettings {
+            rate_limits: RateLimits {
+                login_packets_per_second: Some(1),
+                policy: LimitPolicy::Drop,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let (mut client, mut io) = TestClient::connect(&shared).await;
+
+        for payload in 0..3 {
+            client.send(&QueryPingC2s { payload }).await;
+        }
+
+        let QueryPingC2s { payload } = io.recv_packet().await.unwrap();
+        assert_eq!(payload, 0);
+
+        // The second packet isn't dropped silently, which would leave the
+        // connection waiting for it.
+        assert!(io.recv_packet::<QueryPingC2s>().await.is_err());
+        assert_eq!(shared.metrics().connections_kicked(), 1);
+        assert_eq!(shared.metrics().packets_dropped(), 0);
+    }
+}
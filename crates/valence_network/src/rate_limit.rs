@@ -0,0 +1,293 @@
+//! Limits on the rate of packets and bytes exchanged with each connection.
+//!
+//! All limits are disabled by default. Outgoing packets are never reordered
+//! by priority, see
+//! [`outgoing_bytes_per_second`](RateLimits::outgoing_bytes_per_second).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Rate limits applied to every connection. Set with
+/// [`NetworkSettings::rate_limits`](crate::NetworkSettings::rate_limits).
+///
+/// Incoming packets are limited with a token bucket: a connection may send
+/// bursts of up to one second worth of packets, after which it is held to the
+/// configured rate.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RateLimits {
+    /// The maximum number of packets per second a connection may send in the
+    /// handshaking, status, and login states. `None` disables the limit.
+    ///
+    /// A vanilla client sends fewer than ten packets before it is in the play
+    /// state.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub login_packets_per_second: Option<u32>,
+    /// The maximum number of packets per second a client may send in the play
+    /// state. `None` disables the limit.
+    ///
+    /// Vanilla clients send about 20 movement packets per second, and more
+    /// while interacting with inventories or blocks. Clients catching up after
+    /// lag send their packets in bursts, so leave plenty of headroom.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub play_packets_per_second: Option<u32>,
+    /// What happens to connections exceeding the packet limits.
+    ///
+    /// # Default Value
+    ///
+    /// [`LimitPolicy::Kick`]
+    pub policy: LimitPolicy,
+    /// The maximum number of bytes per second sent to a client in the play
+    /// state. Outgoing data over the budget is delayed, and clients which
+    /// fall behind by more than
+    /// [`outgoing_byte_limit`](crate::NetworkSettings::outgoing_byte_limit)
+    /// are disconnected. `None` disables the limit.
+    ///
+    /// Outgoing packets are not prioritized. They are sent in the order they
+    /// were written since the stream is compressed and encrypted as a whole,
+    /// so keepalives and other small packets queued behind chunk data are
+    /// delayed too. Lower the view distance of clients that can't keep up
+    /// instead of setting this too low.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub outgoing_bytes_per_second: Option<u32>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            login_packets_per_second: None,
+            play_packets_per_second: None,
+            policy: LimitPolicy::Kick,
+            outgoing_bytes_per_second: None,
+        }
+    }
+}
+
+/// What happens to connections exceeding a packet limit in [`RateLimits`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum LimitPolicy {
+    /// The connection is closed.
+    #[default]
+    Kick,
+    /// Packets over the limit are discarded and the connection stays open.
+    /// This only applies in the play state. Connections exceeding
+    /// [`login_packets_per_second`](RateLimits::login_packets_per_second)
+    /// are always closed, since the login can't continue without the
+    /// discarded packets.
+    ///
+    /// Discarding packets can confuse the state of well-behaved clients, such
+    /// as when a teleport confirmation is lost.
+    Drop,
+}
+
+/// Counters for the traffic of all connections, obtained with
+/// [`SharedNetworkState::metrics`](crate::SharedNetworkState::metrics).
+///
+/// The counters are updated from the network threads and only ever increase.
+#[derive(Default, Debug)]
+pub struct NetworkMetrics {
+    packets_received: AtomicU64,
+    bytes_received: AtomicU64,
+    packets_dropped: AtomicU64,
+    connections_kicked: AtomicU64,
+    bytes_sent: AtomicU64,
+    send_delay_micros: AtomicU64,
+}
+
+impl NetworkMetrics {
+    /// The number of packets received from clients in the play state,
+    /// including dropped packets.
+    pub fn packets_received(&self) -> u64 {
+        self.packets_received.load(Ordering::Relaxed)
+    }
+
+    /// The number of bytes received from clients in the play state.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// The number of packets discarded by [`LimitPolicy::Drop`].
+    pub fn packets_dropped(&self) -> u64 {
+        self.packets_dropped.load(Ordering::Relaxed)
+    }
+
+    /// The number of connections closed by [`LimitPolicy::Kick`].
+    pub fn connections_kicked(&self) -> u64 {
+        self.connections_kicked.load(Ordering::Relaxed)
+    }
+
+    /// The number of bytes sent to clients in the play state.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// The total time outgoing data was held back by
+    /// [`RateLimits::outgoing_bytes_per_second`].
+    pub fn send_delay(&self) -> Duration {
+        Duration::from_micros(self.send_delay_micros.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn add_received(&self) {
+        self.packets_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_received_bytes(&self, bytes: usize) {
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_dropped(&self) {
+        self.packets_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_kicked(&self) {
+        self.connections_kicked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_sent(&self, bytes: usize, delay: Duration) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.send_delay_micros
+            .fetch_add(delay.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// A token bucket holding up to one second worth of tokens.
+#[derive(Clone, Debug)]
+pub(crate) struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(rate: u32) -> Self {
+        Self {
+            rate: rate.max(1) as f64,
+            tokens: rate.max(1) as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.rate);
+        self.last_refill = now;
+    }
+
+    /// Takes one token, returning `false` if the bucket is empty.
+    pub(crate) fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes `amount` tokens, going into debt if there aren't enough. Returns
+    /// how long to wait until the debt is paid off.
+    pub(crate) fn take_debt(&mut self, amount: usize, now: Instant) -> Duration {
+        self.refill(now);
+
+        self.tokens -= amount as f64;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Limits the packets received on one connection.
+pub(crate) struct PacketLimiter {
+    bucket: Option<TokenBucket>,
+    policy: LimitPolicy,
+}
+
+/// What to do with a received packet.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Verdict {
+    Accept,
+    Drop,
+    Kick,
+}
+
+impl PacketLimiter {
+    pub(crate) fn new(packets_per_second: Option<u32>, policy: LimitPolicy) -> Self {
+        Self {
+            bucket: packets_per_second.map(TokenBucket::new),
+            policy,
+        }
+    }
+
+    pub(crate) fn check(&mut self, now: Instant) -> Verdict {
+        let limited = self
+            .bucket
+            .as_mut()
+            .is_some_and(|bucket| !bucket.try_take(now));
+
+        match (limited, self.policy) {
+            (false, _) => Verdict::Accept,
+            (true, LimitPolicy::Kick) => Verdict::Kick,
+            (true, LimitPolicy::Drop) => Verdict::Drop,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_limiter() {
+        let start = Instant::now();
+
+        let mut limiter = PacketLimiter::new(Some(10), LimitPolicy::Drop);
+
+        for _ in 0..10 {
+            assert_eq!(limiter.check(start), Verdict::Accept);
+        }
+        assert_eq!(limiter.check(start), Verdict::Drop);
+
+        // A tenth of a second refills one token.
+        let later = start + Duration::from_millis(100);
+        assert_eq!(limiter.check(later), Verdict::Accept);
+        assert_eq!(limiter.check(later), Verdict::Drop);
+
+        // The bucket never holds more than a second worth of tokens.
+        let much_later = start + Duration::from_secs(60);
+        for _ in 0..10 {
+            assert_eq!(limiter.check(much_later), Verdict::Accept);
+        }
+        assert_eq!(limiter.check(much_later), Verdict::Drop);
+
+        let mut limiter = PacketLimiter::new(None, LimitPolicy::Kick);
+        for _ in 0..1000 {
+            assert_eq!(limiter.check(start), Verdict::Accept);
+        }
+    }
+
+    #[test]
+    fn byte_budget() {
+        let start = Instant::now();
+
+        let mut bucket = TokenBucket::new(1000);
+
+        assert_eq!(bucket.take_debt(500, start), Duration::ZERO);
+        assert_eq!(bucket.take_debt(1000, start), Duration::from_millis(500));
+
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.take_debt(0, later), Duration::ZERO);
+    }
+}
@@ -0,0 +1,407 @@
+//! Templates for the server list status response.
+//!
+//! The response to server list pings is built from a [`StatusTemplate`] when
+//! the default [`NetworkCallbacks::server_list_ping`] is used. Templates
+//! reference [`StatusVariables`] by name, such as `{online}` or `{event}`.
+//! Variables are published by systems during the tick and read by the network
+//! threads at ping time, so the world never has to be accessed while
+//! responding.
+//!
+//! The following variables are always available:
+//!
+//! - `online`: the number of players on the server.
+//! - `max`: the maximum number of players.
+//! - `players.<label>`: the number of clients viewing the chunk layer with the
+//!   [`StatusLabel`] `<label>`.
+//!
+//! Servers reachable under several host names, like `lobby.example.com` and
+//! `pvp.example.com`, can show a different template for each with
+//! [`SharedNetworkState::set_host_status_template`], or with the status of a
+//! [virtual host](crate::virtual_host). Everything in the
+//! response, including the player counts and the version, can be computed per
+//! ping by implementing [`NetworkCallbacks::server_list_ping`] instead.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_network::status::StatusTemplate;
+//! use valence_network::SharedNetworkState;
+//!
+//! let template = StatusTemplate::new("§6{event}§r - {online}/{max} online")
+//!     .with_motd("§a{queue} players waiting")
+//!     .with_sample_line("Lobby: {players.lobby}");
+//!
+//! # #[derive(Resource)]
+//! # struct Queue(Vec<()>);
+//! fn publish_queue(queue: Res<Queue>, shared: Res<SharedNetworkState>) {
+//!     shared.status_variables().set("queue", queue.0.len());
+//! }
+//! # let _ = (template, publish_queue);
+//! ```
+//!
+//! [`NetworkCallbacks::server_list_ping`]: crate::NetworkCallbacks::server_list_ping
+
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy_ecs::prelude::*;
+use uuid::Uuid;
+use valence_server::client::{ClientMarker, VisibleChunkLayer};
+
+use crate::{PlayerSampleEntry, SharedNetworkState};
+
+/// Templates for the parts of the status response shown in the server list.
+///
+/// In templates, `{name}` is replaced with the variable `name`, and `{{` and
+/// `}}` are replaced with literal braces. References to unknown variables are
+/// left as they are. Templates may contain
+/// [legacy formatting codes](https://minecraft.wiki/w/Formatting_codes).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StatusTemplate {
+    /// The descriptions shown below the server name, rotated every
+    /// [`rotation_period`](Self::rotation_period).
+    pub motds: Vec<String>,
+    /// How long each MOTD is shown for.
+    ///
+    /// # Default Value
+    ///
+    /// 10 seconds
+    pub rotation_period: Duration,
+    /// Lines shown when hovering over the player count. Nothing is shown if
+    /// this is empty.
+    pub player_sample: Vec<String>,
+    /// The server icon as the bytes of a 64x64 PNG image. No icon is shown
+    /// if this is `None`.
+    pub favicon_png: Option<Arc<[u8]>>,
+    /// Shown as the number of online players instead of the real number. The
+    /// real number is shown if this is `None` or doesn't render to a number.
+    pub online_players: Option<String>,
+    /// Shown as the maximum number of players instead of the real maximum.
+    /// The real maximum is shown if this is `None` or doesn't render to a
+    /// number.
+    pub max_players: Option<String>,
+    /// The version name and protocol version reported to every client,
+    /// regardless of the [version policy]. Clients show the name instead of
+    /// the player count if the protocol differs from their own.
+    ///
+    /// [version policy]: SharedNetworkState::version_policy
+    pub version: Option<(String, i32)>,
+}
+
+impl StatusTemplate {
+    pub fn new(motd: impl Into<String>) -> Self {
+        Self {
+            motds: vec![motd.into()],
+            rotation_period: Duration::from_secs(10),
+            player_sample: vec![],
+            favicon_png: None,
+            online_players: None,
+            max_players: None,
+            version: None,
+        }
+    }
+
+    /// Adds another MOTD to the rotation.
+    pub fn with_motd(mut self, motd: impl Into<String>) -> Self {
+        self.motds.push(motd.into());
+        self
+    }
+
+    pub fn with_rotation_period(mut self, period: Duration) -> Self {
+        self.rotation_period = period;
+        self
+    }
+
+    pub fn with_sample_line(mut self, line: impl Into<String>) -> Self {
+        self.player_sample.push(line.into());
+        self
+    }
+
+    pub fn with_favicon_png(mut self, png: impl Into<Arc<[u8]>>) -> Self {
+        self.favicon_png = Some(png.into());
+        self
+    }
+
+    pub fn with_online_players(mut self, online_players: impl Into<String>) -> Self {
+        self.online_players = Some(online_players.into());
+        self
+    }
+
+    pub fn with_max_players(mut self, max_players: impl Into<String>) -> Self {
+        self.max_players = Some(max_players.into());
+        self
+    }
+
+    pub fn with_version(mut self, name: impl Into<String>, protocol: i32) -> Self {
+        self.version = Some((name.into(), protocol));
+        self
+    }
+
+    /// Returns the MOTD to show at `time`. All servers with the same
+    /// templates show the same MOTD at the same time.
+    pub fn motd_at(&self, time: SystemTime) -> Option<&str> {
+        let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let period = self.rotation_period.as_millis().max(1);
+        let index = (elapsed.as_millis() / period) % self.motds.len().max(1) as u128;
+
+        self.motds.get(index as usize).map(String::as_str)
+    }
+
+    /// Renders the current MOTD with the variables of `shared`.
+    pub fn render_motd(&self, shared: &SharedNetworkState) -> String {
+        self.motd_at(SystemTime::now())
+            .map(|motd| render(motd, |name| shared.status_variable(name)))
+            .unwrap_or_default()
+    }
+
+    /// Renders the number of online players with the variables of `shared`.
+    pub fn render_online_players(&self, shared: &SharedNetworkState) -> i32 {
+        let online = shared.player_count().load(Ordering::Relaxed) as i32;
+
+        render_count(self.online_players.as_deref(), online, |name| {
+            shared.status_variable(name)
+        })
+    }
+
+    /// Renders the maximum number of players with the variables of `shared`.
+    pub fn render_max_players(&self, shared: &SharedNetworkState) -> i32 {
+        render_count(
+            self.max_players.as_deref(),
+            shared.max_players() as i32,
+            |name| shared.status_variable(name),
+        )
+    }
+
+    /// Renders the lines of the player sample with the variables of `shared`.
+    pub fn render_player_sample(&self, shared: &SharedNetworkState) -> Vec<PlayerSampleEntry> {
+        self.player_sample
+            .iter()
+            .map(|line| PlayerSampleEntry {
+                name: render(line, |name| shared.status_variable(name)),
+                id: Uuid::nil(),
+            })
+            .collect()
+    }
+}
+
+impl Default for StatusTemplate {
+    fn default() -> Self {
+        Self::new("A Valence Server")
+    }
+}
+
+/// Values referenced by [`StatusTemplate`]s. Cloning is cheap and the clones
+/// share the same values.
+#[derive(Clone, Default, Debug)]
+pub struct StatusVariables(Arc<RwLock<HashMap<String, String>>>);
+
+impl StatusVariables {
+    /// Sets the variable `name` to `value`.
+    pub fn set(&self, name: impl Into<String>, value: impl ToString) {
+        let value = value.to_string();
+        let mut vars = self.0.write().unwrap();
+        let name = name.into();
+
+        if vars.get(&name) != Some(&value) {
+            vars.insert(name, value);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.0.read().unwrap().get(name).cloned()
+    }
+
+    pub fn remove(&self, name: &str) -> Option<String> {
+        self.0.write().unwrap().remove(name)
+    }
+}
+
+impl SharedNetworkState {
+    /// The variables referenced by status templates. See the [`status`]
+    /// module for more information.
+    ///
+    /// [`status`]: crate::status
+    pub fn status_variables(&self) -> &StatusVariables {
+        &self.0.status_variables
+    }
+
+    /// Returns the status template used to respond to server list pings.
+    pub fn status_template(&self) -> StatusTemplate {
+        self.0.status_template.read().unwrap().clone()
+    }
+
+    /// Replaces the status template, taking effect for the next ping.
+    pub fn set_status_template(&self, template: StatusTemplate) {
+        *self.0.status_template.write().unwrap() = template;
+    }
+
+    /// Sets the status template used to respond to server list pings of
+    /// clients connecting with the host name `host`, taking effect for the
+    /// next ping. Host names are compared without case.
+    pub fn set_host_status_template(&self, host: &str, template: StatusTemplate) {
+        self.0
+            .host_status_templates
+            .write()
+            .unwrap()
+            .insert(normalize_host(host), template);
+    }
+
+    /// Removes the status template of the host name `host`, so the default
+    /// status template is used for it again.
+    pub fn remove_host_status_template(&self, host: &str) -> Option<StatusTemplate> {
+        self.0
+            .host_status_templates
+            .write()
+            .unwrap()
+            .remove(&normalize_host(host))
+    }
+
+    /// Returns the status template used to respond to server list pings of
+    /// clients which connected to the server address `address` in their
+    /// handshake. This is the template of the host, or the default status
+    /// template if the host has none.
+    pub fn status_template_for(&self, address: &str) -> StatusTemplate {
+        let templates = self.0.host_status_templates.read().unwrap();
+
+        match templates.get(&normalize_host(address)) {
+            Some(template) => template.clone(),
+            None => self.status_template(),
+        }
+    }
+
+    /// Returns the value of a status variable, including the built-in ones.
+    pub fn status_variable(&self, name: &str) -> Option<String> {
+        match name {
+            "online" => Some(self.player_count().load(Ordering::Relaxed).to_string()),
+            "max" => Some(self.max_players().to_string()),
+            _ => self.status_variables().get(name),
+        }
+    }
+}
+
+/// Names a chunk layer in status templates. The number of clients viewing
+/// the layer is published as the variable `players.<label>`.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct StatusLabel(pub String);
+
+pub(crate) fn publish_layer_player_counts(
+    layers: Query<(Entity, &StatusLabel)>,
+    clients: Query<&VisibleChunkLayer, With<ClientMarker>>,
+    shared: Res<SharedNetworkState>,
+) {
+    if layers.is_empty() {
+        return;
+    }
+
+    let mut counts = HashMap::<Entity, usize>::new();
+
+    for layer in &clients {
+        *counts.entry(layer.0).or_default() += 1;
+    }
+
+    for (layer, label) in &layers {
+        shared.status_variables().set(
+            format!("players.{}", label.0),
+            counts.get(&layer).copied().unwrap_or(0),
+        );
+    }
+}
+
+/// Returns the host name of a server address sent in a handshake. Modded
+/// clients append data after a null byte, and addresses may end in a dot.
+pub(crate) fn normalize_host(address: &str) -> String {
+    let host = address.split('\0').next().unwrap_or_default();
+
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Renders a player count, or returns `default` if there is no template or it
+/// doesn't render to a number.
+fn render_count(template: Option<&str>, default: i32, get: impl Fn(&str) -> Option<String>) -> i32 {
+    template
+        .and_then(|template| render(template, get).trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Replaces the variables in `template` with the values returned by `get`.
+pub(crate) fn render(template: &str, get: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+        } else if let Some(end) = rest.strip_prefix('{').and_then(|r| r.find('}')) {
+            let name = &rest[1..end + 1];
+
+            match get(name) {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[..end + 2]),
+            }
+
+            rest = &rest[end + 2..];
+        } else {
+            out.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_template() {
+        let get = |name: &str| match name {
+            "online" => Some("5".into()),
+            "players.lobby" => Some("3".into()),
+            _ => None,
+        };
+
+        assert_eq!(render("{online} online", get), "5 online");
+        assert_eq!(render("Lobby: {players.lobby}!", get), "Lobby: 3!");
+        assert_eq!(render("{{online}} {missing}", get), "{online} {missing}");
+        assert_eq!(render("a } b { c", get), "a } b { c");
+    }
+
+    #[test]
+    fn render_counts() {
+        let get = |name: &str| (name == "queue").then(|| "12".to_owned());
+
+        assert_eq!(render_count(Some("{queue}"), 3, get), 12);
+        assert_eq!(render_count(Some("{missing}"), 3, get), 3);
+        assert_eq!(render_count(None, 3, get), 3);
+    }
+
+    #[test]
+    fn normalize_hosts() {
+        assert_eq!(normalize_host("Lobby.Example.com."), "lobby.example.com");
+        assert_eq!(normalize_host("pvp.example.com\0FML3\0"), "pvp.example.com");
+        assert_eq!(normalize_host(""), "");
+    }
+
+    #[test]
+    fn rotate_motds() {
+        let template = StatusTemplate::new("a")
+            .with_motd("b")
+            .with_rotation_period(Duration::from_secs(5));
+
+        let at = |secs| template.motd_at(UNIX_EPOCH + Duration::from_secs(secs));
+
+        assert_eq!(at(0), Some("a"));
+        assert_eq!(at(4), Some("a"));
+        assert_eq!(at(5), Some("b"));
+        assert_eq!(at(10), Some("a"));
+    }
+}
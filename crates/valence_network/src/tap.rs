@@ -0,0 +1,285 @@
+//! Observing the packets exchanged with connections.
+//!
+//! A [`PacketTap`] is registered with [`SharedNetworkState::add_packet_tap`]
+//! and is asked for a [`PacketObserver`] whenever a new connection is
+//! accepted. The observer then sees every packet sent and received on that
+//! connection, in every state, which is useful for packet logging, capturing
+//! replays, and debugging the protocol.
+//!
+//! Observers are called from the network threads. Long running work should
+//! be sent elsewhere, such as through a channel, since it delays the
+//! connection.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::net::SocketAddr;
+//!
+//! use valence_network::tap::{PacketObserver, TappedPacket};
+//! use valence_server::protocol::packets::play::ChatMessageC2s;
+//!
+//! struct ChatLogger(SocketAddr);
+//!
+//! impl PacketObserver for ChatLogger {
+//!     fn inbound(&mut self, packet: &TappedPacket) {
+//!         if let Some(pkt) = packet.decode::<ChatMessageC2s>() {
+//!             println!("{}: {}", self.0, pkt.message);
+//!         }
+//!     }
+//! }
+//!
+//! fn tap(remote_addr: SocketAddr) -> Option<Box<dyn PacketObserver>> {
+//!     Some(Box::new(ChatLogger(remote_addr)))
+//! }
+//!
+//! // In a startup system:
+//! // shared.add_packet_tap(tap);
+//! # let _ = tap;
+//! ```
+//!
+//! [`SharedNetworkState::add_packet_tap`]: crate::SharedNetworkState::add_packet_tap
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tracing::warn;
+use valence_server::protocol::{Decode, Packet, PacketDecoder, PacketSide, PacketState};
+use valence_server::CompressionThreshold;
+
+use crate::NewClientInfo;
+
+/// Creates [`PacketObserver`]s for new connections.
+pub trait PacketTap: Send + Sync + 'static {
+    /// Called when a connection from `remote_addr` is accepted. Returns the
+    /// observer for the packets of the connection, or `None` to not observe
+    /// it.
+    fn open(&self, remote_addr: SocketAddr) -> Option<Box<dyn PacketObserver>>;
+}
+
+impl<F> PacketTap for F
+where
+    F: Fn(SocketAddr) -> Option<Box<dyn PacketObserver>> + Send + Sync + 'static,
+{
+    fn open(&self, remote_addr: SocketAddr) -> Option<Box<dyn PacketObserver>> {
+        self(remote_addr)
+    }
+}
+
+/// Observes the packets of a single connection. The observer is dropped when
+/// the connection is closed.
+pub trait PacketObserver: Send + 'static {
+    /// Called for every packet received from the client.
+    fn inbound(&mut self, packet: &TappedPacket) {
+        #![allow(unused_variables)]
+    }
+
+    /// Called for every packet sent to the client, after it is written to the
+    /// connection.
+    ///
+    /// Packets sent in the play state are observed in the order they are
+    /// sent, which is usually once per tick.
+    fn outbound(&mut self, packet: &TappedPacket) {
+        #![allow(unused_variables)]
+    }
+
+    /// Called when the connection finished logging in and is about to be
+    /// spawned as a client.
+    fn logged_in(&mut self, info: &NewClientInfo) {
+        #![allow(unused_variables)]
+    }
+}
+
+/// A packet seen by a [`PacketObserver`].
+#[derive(Clone, Debug)]
+pub struct TappedPacket<'a> {
+    pub side: PacketSide,
+    pub state: PacketState,
+    /// The ID of the packet.
+    pub id: i32,
+    /// The contents of the packet after the leading VarInt ID, uncompressed
+    /// and unencrypted.
+    pub body: &'a [u8],
+    pub timestamp: Instant,
+}
+
+impl<'a> TappedPacket<'a> {
+    /// Attempts to decode this packet as type `P`. Returns `None` if the
+    /// packet is not a `P` or fails to decode.
+    pub fn decode<P>(&self) -> Option<P>
+    where
+        P: Packet + Decode<'a>,
+    {
+        if P::ID != self.id || P::SIDE != self.side || P::STATE != self.state {
+            return None;
+        }
+
+        let mut r = self.body;
+
+        match P::decode(&mut r) {
+            Ok(pkt) if r.is_empty() => Some(pkt),
+            Ok(_) => {
+                warn!("missed {} bytes while decoding '{}'", r.len(), P::NAME);
+                None
+            }
+            Err(e) => {
+                warn!("failed to decode packet '{}': {e:#}", P::NAME);
+                None
+            }
+        }
+    }
+}
+
+/// The observers of one connection, shared by its reader and writer.
+#[derive(Clone)]
+pub(crate) struct Observers(Arc<Mutex<Vec<Box<dyn PacketObserver>>>>);
+
+impl Observers {
+    /// Opens observers for a new connection, or returns `None` if no tap is
+    /// interested in it.
+    pub(crate) fn open(taps: &[Arc<dyn PacketTap>], remote_addr: SocketAddr) -> Option<Self> {
+        let observers: Vec<_> = taps
+            .iter()
+            .filter_map(|tap| tap.open(remote_addr))
+            .collect();
+
+        if observers.is_empty() {
+            None
+        } else {
+            Some(Self(Arc::new(Mutex::new(observers))))
+        }
+    }
+
+    pub(crate) fn inbound(&self, packet: &TappedPacket) {
+        for observer in self.0.lock().unwrap().iter_mut() {
+            observer.inbound(packet);
+        }
+    }
+
+    pub(crate) fn outbound(&self, packet: &TappedPacket) {
+        for observer in self.0.lock().unwrap().iter_mut() {
+            observer.outbound(packet);
+        }
+    }
+
+    pub(crate) fn logged_in(&self, info: &NewClientInfo) {
+        for observer in self.0.lock().unwrap().iter_mut() {
+            observer.logged_in(info);
+        }
+    }
+}
+
+/// Recovers the outgoing packets of a connection from the bytes written to
+/// it, which are compressed and encrypted.
+pub(crate) struct OutboundTap {
+    observers: Observers,
+    /// `None` if decoding failed, after which the rest of the stream can't be
+    /// decoded either.
+    dec: Option<PacketDecoder>,
+}
+
+impl OutboundTap {
+    pub(crate) fn new(observers: Observers) -> Self {
+        Self {
+            observers,
+            dec: Some(PacketDecoder::new()),
+        }
+    }
+
+    pub(crate) fn observers(&self) -> &Observers {
+        &self.observers
+    }
+
+    pub(crate) fn set_compression(&mut self, threshold: CompressionThreshold) {
+        if let Some(dec) = &mut self.dec {
+            dec.set_compression(threshold);
+        }
+    }
+
+    pub(crate) fn enable_encryption(&mut self, key: &[u8; 16]) {
+        if let Some(dec) = &mut self.dec {
+            dec.enable_encryption(key);
+        }
+    }
+
+    /// Observes the packets in `bytes`, which were written to the connection.
+    pub(crate) fn written(&mut self, bytes: &[u8], state: PacketState) {
+        let Some(dec) = &mut self.dec else {
+            return;
+        };
+
+        dec.queue_slice(bytes);
+
+        let timestamp = Instant::now();
+
+        loop {
+            match dec.try_next_packet() {
+                Ok(Some(frame)) => self.observers.outbound(&TappedPacket {
+                    side: PacketSide::Clientbound,
+                    state,
+                    id: frame.id,
+                    body: &frame.body,
+                    timestamp,
+                }),
+                Ok(None) => break,
+                Err(e) => {
+                    // Only happens if the encoder and our decoder are out of
+                    // sync, so there's no point in continuing.
+                    warn!("error decoding outgoing packet for tap: {e:#}");
+                    self.dec = None;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::protocol::packets::play::KeepAliveS2c;
+    use valence_server::protocol::PacketEncoder;
+
+    use super::*;
+
+    struct Collect(Arc<Mutex<Vec<i32>>>);
+
+    impl PacketObserver for Collect {
+        fn outbound(&mut self, packet: &TappedPacket) {
+            assert_eq!(packet.decode::<KeepAliveS2c>().unwrap().id, 42);
+            self.0.lock().unwrap().push(packet.id);
+        }
+    }
+
+    #[test]
+    fn outbound_tap_decodes_written_packets() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let seen_clone = seen.clone();
+
+        let taps: Vec<Arc<dyn PacketTap>> =
+            vec![Arc::new(move |_| -> Option<Box<dyn PacketObserver>> {
+                Some(Box::new(Collect(seen_clone.clone())))
+            })];
+
+        let observers = Observers::open(&taps, ([127, 0, 0, 1], 25565).into()).unwrap();
+        let mut tap = OutboundTap::new(observers);
+
+        let key = [7; 16];
+        let mut enc = PacketEncoder::new();
+
+        enc.set_compression(CompressionThreshold(0));
+        tap.set_compression(CompressionThreshold(0));
+        enc.enable_encryption(&key);
+        tap.enable_encryption(&key);
+
+        enc.append_packet(&KeepAliveS2c { id: 42 }).unwrap();
+        enc.append_packet(&KeepAliveS2c { id: 42 }).unwrap();
+        let bytes = enc.take();
+
+        // Packets split across writes are observed once complete.
+        let (first, second) = bytes.split_at(bytes.len() / 2 + 1);
+        tap.written(first, PacketState::Play);
+        tap.written(second, PacketState::Play);
+
+        assert_eq!(*seen.lock().unwrap(), [KeepAliveS2c::ID; 2]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Handling clients with a different protocol version than the server.
+//!
+//! Clients with a version which has a [`PacketTranslator`] in the
+//! [`VersionPolicy`] may join, and their packets are translated to and from
+//! the version of the server. Clients with an unsupported version see the
+//! version label of the [`VersionPolicy`] in red in the server list, and are
+//! disconnected with its mismatch message if they try to join anyway. Both can
+//! be tailored further per client with [`NetworkCallbacks::supports_protocol`]
+//! and [`NetworkCallbacks::version_mismatch_message`].
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_network::version::VersionPolicy;
+//! use valence_server::text::{Color, IntoText};
+//!
+//! let policy = VersionPolicy::new()
+//!     .with_mismatch_label("Please use {version}")
+//!     .with_mismatch_message("Please join with Minecraft 1.20.1".color(Color::RED));
+//! # let _ = policy;
+//! ```
+//!
+//! [`NetworkCallbacks::supports_protocol`]: crate::NetworkCallbacks::supports_protocol
+//! [`NetworkCallbacks::version_mismatch_message`]: crate::NetworkCallbacks::version_mismatch_message
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use valence_server::protocol::translate::{PacketTranslator, PacketTranslators};
+use valence_server::text::{Color, IntoText};
+use valence_server::{Text, MINECRAFT_VERSION, PROTOCOL_VERSION};
+
+use crate::status::render;
+use crate::SharedNetworkState;
+
+/// Decides which protocol versions may join and what other clients are told.
+#[derive(Clone, PartialEq, Debug)]
+pub struct VersionPolicy {
+    /// Protocol versions accepted in addition to [`PROTOCOL_VERSION`].
+    ///
+    /// Clients with these versions see the server as compatible and are
+    /// allowed to log in, but packets are still encoded for
+    /// [`PROTOCOL_VERSION`]. This is only useful with a proxy which
+    /// translates between versions. See [`translators`](Self::translators)
+    /// otherwise.
+    pub extra_protocols: BTreeSet<i32>,
+    /// Translators for clients with other protocol versions. Clients with
+    /// these versions are allowed to log in, and their packets are
+    /// translated.
+    pub translators: PacketTranslators,
+    /// The version name shown in red in the server list of clients with an
+    /// unsupported version. `{version}` is replaced with the Minecraft
+    /// version of the server, `{protocol}` with the protocol version of the
+    /// client, and other names with [status variables].
+    ///
+    /// # Default Value
+    ///
+    /// `"{version}"`
+    ///
+    /// [status variables]: crate::status::StatusVariables
+    pub mismatch_label: String,
+    /// The reason shown to clients with an unsupported version when they are
+    /// disconnected.
+    pub mismatch_message: Text,
+}
+
+impl VersionPolicy {
+    pub fn new() -> Self {
+        Self {
+            extra_protocols: BTreeSet::new(),
+            translators: PacketTranslators::new(),
+            mismatch_label: "{version}".into(),
+            // TODO: use correct translation key.
+            mismatch_message: format!(
+                "Mismatched Minecraft version (server is on {MINECRAFT_VERSION})"
+            )
+            .color(Color::RED),
+        }
+    }
+
+    /// Accepts clients with the protocol version `protocol`.
+    pub fn with_extra_protocol(mut self, protocol: i32) -> Self {
+        self.extra_protocols.insert(protocol);
+        self
+    }
+
+    /// Accepts clients with the protocol version of `translator` and
+    /// translates their packets.
+    pub fn with_translator(mut self, translator: impl PacketTranslator) -> Self {
+        self.translators.insert(translator);
+        self
+    }
+
+    pub fn with_mismatch_label(mut self, label: impl Into<String>) -> Self {
+        self.mismatch_label = label.into();
+        self
+    }
+
+    pub fn with_mismatch_message(mut self, message: impl Into<Text>) -> Self {
+        self.mismatch_message = message.into();
+        self
+    }
+
+    /// Returns whether clients with the protocol version `protocol` may join.
+    pub fn supports(&self, protocol: i32) -> bool {
+        protocol == PROTOCOL_VERSION
+            || self.extra_protocols.contains(&protocol)
+            || self.translators.contains(protocol)
+    }
+
+    /// Returns the translator for clients with the protocol version
+    /// `protocol`, if their packets need translating.
+    pub fn translator(&self, protocol: i32) -> Option<Arc<dyn PacketTranslator>> {
+        self.translators.get(protocol).cloned()
+    }
+
+    /// Returns the Minecraft version shown to supported clients with the
+    /// protocol version `protocol` in the server list.
+    pub fn version_name(&self, protocol: i32) -> String {
+        match self.translators.get(protocol) {
+            Some(translator) => translator.version_name().to_owned(),
+            None => MINECRAFT_VERSION.to_owned(),
+        }
+    }
+
+    /// Renders the [mismatch label](Self::mismatch_label) for a client with
+    /// the protocol version `protocol`.
+    pub fn render_mismatch_label(&self, shared: &SharedNetworkState, protocol: i32) -> String {
+        render(&self.mismatch_label, |name| match name {
+            "version" => Some(MINECRAFT_VERSION.to_owned()),
+            "protocol" => Some(protocol.to_string()),
+            _ => shared.status_variable(name),
+        })
+    }
+}
+
+impl Default for VersionPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedNetworkState {
+    /// Returns the policy for clients with a different protocol version.
+    pub fn version_policy(&self) -> VersionPolicy {
+        self.0.version_policy.read().unwrap().clone()
+    }
+
+    /// Replaces the version policy, taking effect for the next connection.
+    pub fn set_version_policy(&self, policy: VersionPolicy) {
+        *self.0.version_policy.write().unwrap() = policy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_protocols() {
+        let policy = VersionPolicy::new().with_extra_protocol(PROTOCOL_VERSION + 1);
+
+        assert!(policy.supports(PROTOCOL_VERSION));
+        assert!(policy.supports(PROTOCOL_VERSION + 1));
+        assert!(!policy.supports(PROTOCOL_VERSION - 1));
+    }
+
+    struct OldVersion;
+
+    impl PacketTranslator for OldVersion {
+        fn protocol_version(&self) -> i32 {
+            PROTOCOL_VERSION - 1
+        }
+
+        fn version_name(&self) -> &str {
+            "old"
+        }
+    }
+
+    #[test]
+    fn translated_protocols() {
+        let policy = VersionPolicy::new().with_translator(OldVersion);
+
+        assert!(policy.supports(PROTOCOL_VERSION - 1));
+        assert!(policy.translator(PROTOCOL_VERSION - 1).is_some());
+        assert!(policy.translator(PROTOCOL_VERSION).is_none());
+
+        assert_eq!(policy.version_name(PROTOCOL_VERSION - 1), "old");
+        assert_eq!(policy.version_name(PROTOCOL_VERSION), MINECRAFT_VERSION);
+    }
+}
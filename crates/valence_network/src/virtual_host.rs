@@ -0,0 +1,236 @@
+//! Serving several logical servers on one listener.
+//!
+//! Clients send the host name they connect with, like `lobby.example.com` or
+//! `pvp.example.com`, in their handshake. [`VirtualHosts`] maps host names to
+//! a [`VirtualHost`], which decides the layer new clients join, the status
+//! shown in the server list and the resource pack clients must use.
+//!
+//! Clients are routed in [`PreUpdate`] when they join, so systems in
+//! [`Update`] see them in the layer of their host and can still move them
+//! elsewhere. Clients connecting with a host name without a virtual host are
+//! left alone.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_network::status::StatusTemplate;
+//! use valence_network::virtual_host::{VirtualHost, VirtualHosts};
+//!
+//! fn setup(mut hosts: ResMut<VirtualHosts>) {
+//!     # let (lobby, pvp) = (Entity::PLACEHOLDER, Entity::PLACEHOLDER);
+//!     hosts.insert(
+//!         "lobby.example.com",
+//!         VirtualHost::new(lobby).with_status(StatusTemplate::new("§aThe lobby")),
+//!     );
+//!     hosts.insert(
+//!         "pvp.example.com",
+//!         VirtualHost::new(pvp).with_status(StatusTemplate::new("§cThe arena")),
+//!     );
+//! }
+//! # let _ = setup;
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::{
+    ServerAddress, SpawnClientsSet, VisibleChunkLayer, VisibleEntityLayers,
+};
+use valence_server::entity::EntityLayerId;
+use valence_server::resource_pack::RequiredResourcePack;
+
+use crate::status::{normalize_host, StatusTemplate};
+use crate::SharedNetworkState;
+
+pub(crate) fn build(app: &mut App) {
+    app.init_resource::<VirtualHosts>()
+        .add_systems(PreUpdate, route_new_clients.after(SpawnClientsSet))
+        .add_systems(PostUpdate, publish_status_templates);
+}
+
+/// The logical servers of the listener by host name. Host names are compared
+/// without case.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct VirtualHosts {
+    hosts: HashMap<String, VirtualHost>,
+}
+
+/// A logical server reachable under a host name.
+#[derive(Clone, PartialEq, Debug)]
+pub struct VirtualHost {
+    /// The layer new clients join. It is used as the visible chunk layer, the
+    /// visible entity layer and the layer of the player entity.
+    pub layer: Entity,
+    /// The status shown in the server list, or the default status template if
+    /// this is `None`.
+    pub status: Option<StatusTemplate>,
+    /// The resource pack new clients must use.
+    pub resource_pack: Option<RequiredResourcePack>,
+}
+
+impl VirtualHost {
+    pub fn new(layer: Entity) -> Self {
+        Self {
+            layer,
+            status: None,
+            resource_pack: None,
+        }
+    }
+
+    pub fn with_status(mut self, status: StatusTemplate) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_resource_pack(mut self, pack: RequiredResourcePack) -> Self {
+        self.resource_pack = Some(pack);
+        self
+    }
+}
+
+impl VirtualHosts {
+    /// Adds a virtual host for the host name `host`. The previous virtual
+    /// host of the name is returned.
+    pub fn insert(&mut self, host: &str, virtual_host: VirtualHost) -> Option<VirtualHost> {
+        self.hosts.insert(normalize_host(host), virtual_host)
+    }
+
+    pub fn remove(&mut self, host: &str) -> Option<VirtualHost> {
+        self.hosts.remove(&normalize_host(host))
+    }
+
+    /// Returns the virtual host of a server address sent in a handshake.
+    pub fn get(&self, address: &str) -> Option<&VirtualHost> {
+        self.hosts.get(&normalize_host(address))
+    }
+
+    pub fn get_mut(&mut self, address: &str) -> Option<&mut VirtualHost> {
+        self.hosts.get_mut(&normalize_host(address))
+    }
+
+    /// Returns an iterator over the host names and their virtual hosts.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &VirtualHost)> + '_ {
+        self.hosts.iter().map(|(host, vh)| (host.as_str(), vh))
+    }
+}
+
+/// The host name a client was routed by. Added to clients which joined
+/// through a [`VirtualHost`].
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct VirtualHostName(pub String);
+
+fn route_new_clients(
+    mut clients: Query<
+        (
+            Entity,
+            &ServerAddress,
+            &mut EntityLayerId,
+            &mut VisibleChunkLayer,
+            &mut VisibleEntityLayers,
+        ),
+        Added<ServerAddress>,
+    >,
+    hosts: Res<VirtualHosts>,
+    mut commands: Commands,
+) {
+    for (entity, address, mut layer_id, mut visible_chunk_layer, mut visible_entity_layers) in
+        &mut clients
+    {
+        let Some(host) = hosts.get(&address.0) else {
+            continue;
+        };
+
+        layer_id.0 = host.layer;
+        visible_chunk_layer.0 = host.layer;
+        visible_entity_layers.0.insert(host.layer);
+
+        let mut entity = commands.entity(entity);
+
+        entity.insert(VirtualHostName(normalize_host(&address.0)));
+
+        if let Some(pack) = &host.resource_pack {
+            entity.insert(pack.clone());
+        }
+    }
+}
+
+/// Keeps the host status templates of the [`SharedNetworkState`] in sync with
+/// the status of the virtual hosts.
+fn publish_status_templates(
+    hosts: Res<VirtualHosts>,
+    shared: Res<SharedNetworkState>,
+    mut published: Local<HashSet<String>>,
+) {
+    if !hosts.is_changed() {
+        return;
+    }
+
+    for host in published.drain() {
+        if hosts.get(&host).and_then(|h| h.status.as_ref()).is_none() {
+            shared.remove_host_status_template(&host);
+        }
+    }
+
+    for (host, vh) in hosts.iter() {
+        if let Some(status) = &vh.status {
+            shared.set_host_status_template(host, status.clone());
+            published.insert(host.to_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_clients_by_host() {
+        let mut app = App::new();
+        app.init_resource::<VirtualHosts>()
+            .add_systems(Update, route_new_clients);
+
+        let lobby = app.world.spawn_empty().id();
+        let pack = RequiredResourcePack::new("https://example.com/pack.zip", "0".repeat(40));
+
+        app.world.resource_mut::<VirtualHosts>().insert(
+            "Lobby.Example.com",
+            VirtualHost::new(lobby).with_resource_pack(pack.clone()),
+        );
+
+        let mut spawn_client = |address: &str| {
+            app.world
+                .spawn((
+                    ServerAddress(address.into()),
+                    EntityLayerId(Entity::PLACEHOLDER),
+                    VisibleChunkLayer::default(),
+                    VisibleEntityLayers::default(),
+                ))
+                .id()
+        };
+
+        let routed = spawn_client("lobby.example.com.\0FML3\0");
+        let unrouted = spawn_client("pvp.example.com");
+
+        app.update();
+
+        let routed = app.world.entity(routed);
+        assert_eq!(routed.get::<VisibleChunkLayer>().unwrap().0, lobby);
+        assert_eq!(routed.get::<EntityLayerId>().unwrap().0, lobby);
+        assert!(routed
+            .get::<VisibleEntityLayers>()
+            .unwrap()
+            .0
+            .contains(&lobby));
+        assert_eq!(routed.get::<RequiredResourcePack>(), Some(&pack));
+        assert_eq!(
+            routed.get::<VirtualHostName>().unwrap().0,
+            "lobby.example.com"
+        );
+
+        let unrouted = app.world.entity(unrouted);
+        assert_ne!(unrouted.get::<VisibleChunkLayer>().unwrap().0, lobby);
+        assert!(unrouted.get::<VirtualHostName>().is_none());
+    }
+}
@@ -0,0 +1,447 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use valence_command::scopes::CommandScopes;
+use valence_command::CommandRegistry;
+
+pub struct PermissionPlugin;
+
+impl Plugin for PermissionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PermissionGroups>()
+            .add_event::<PermissionsChangedEvent>()
+            .add_systems(
+                PostUpdate,
+                (send_permission_changes, sync_command_scopes).chain(),
+            );
+    }
+}
+
+/// The permission nodes granted to or denied from an entity, and the groups
+/// the entity is in.
+///
+/// Groups earlier in the list take priority over later ones. The entity's own
+/// nodes take priority over all groups.
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct PermissionSet {
+    /// Maps nodes to whether they are granted (`true`) or denied (`false`).
+    nodes: BTreeMap<String, bool>,
+    groups: Vec<String>,
+}
+
+impl PermissionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grants `node`, replacing a denial of the same node.
+    pub fn grant(&mut self, node: impl Into<String>) -> &mut Self {
+        self.nodes.insert(node.into(), true);
+        self
+    }
+
+    /// Denies `node`, replacing a grant of the same node.
+    pub fn deny(&mut self, node: impl Into<String>) -> &mut Self {
+        self.nodes.insert(node.into(), false);
+        self
+    }
+
+    /// Removes the grant or denial of `node`.
+    pub fn unset(&mut self, node: &str) -> &mut Self {
+        self.nodes.remove(node);
+        self
+    }
+
+    /// Returns an iterator over the nodes in this set and whether they are
+    /// granted.
+    pub fn nodes(&self) -> impl Iterator<Item = (&str, bool)> + '_ {
+        self.nodes
+            .iter()
+            .map(|(node, &granted)| (node.as_str(), granted))
+    }
+
+    /// Adds the set to the group named `group` with the lowest priority.
+    pub fn add_group(&mut self, group: impl Into<String>) -> &mut Self {
+        let group = group.into();
+        if !self.groups.contains(&group) {
+            self.groups.push(group);
+        }
+        self
+    }
+
+    pub fn remove_group(&mut self, group: &str) -> &mut Self {
+        self.groups.retain(|g| g != group);
+        self
+    }
+
+    pub fn in_group(&self, group: &str) -> bool {
+        self.groups.iter().any(|g| g == group)
+    }
+
+    /// Returns the groups of this set, highest priority first.
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    /// Returns whether the nodes of this set grant or deny `node`, ignoring
+    /// groups. Returns `None` if no node matches.
+    pub fn get(&self, node: &str) -> Option<bool> {
+        self.nodes
+            .iter()
+            .filter_map(|(pattern, &granted)| {
+                specificity(pattern, node).map(|specificity| (specificity, !granted))
+            })
+            // Denials win over grants of equal specificity.
+            .max()
+            .map(|(_, denied)| !denied)
+    }
+}
+
+/// Returns how specific `pattern` is if it matches `node`.
+fn specificity(pattern: &str, node: &str) -> Option<usize> {
+    if pattern == node {
+        // Exact matches are more specific than any wildcard.
+        Some(usize::MAX)
+    } else if let Some(prefix) = pattern.strip_suffix('*') {
+        let is_wildcard = prefix.is_empty() || prefix.ends_with('.');
+        (is_wildcard && node.starts_with(prefix)).then_some(prefix.len())
+    } else {
+        None
+    }
+}
+
+/// The named groups of permissions. A group is a [`PermissionSet`] which
+/// inherits the permissions of the groups it is in.
+///
+/// Every entity is implicitly in the [default group] with the lowest
+/// priority.
+///
+/// [default group]: PermissionGroups::DEFAULT
+#[derive(Resource, Clone, Default, Debug)]
+pub struct PermissionGroups {
+    groups: BTreeMap<String, PermissionSet>,
+}
+
+impl PermissionGroups {
+    /// The name of the group every entity is in.
+    pub const DEFAULT: &'static str = "default";
+
+    /// Inserts a group, returning the previous group with the same name.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        permissions: PermissionSet,
+    ) -> Option<PermissionSet> {
+        self.groups.insert(name.into(), permissions)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PermissionSet> {
+        self.groups.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut PermissionSet> {
+        self.groups.get_mut(name)
+    }
+
+    /// Returns the group named `name`, inserting an empty group if it doesn't
+    /// exist.
+    pub fn get_or_insert(&mut self, name: &str) -> &mut PermissionSet {
+        self.groups.entry(name.into()).or_default()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PermissionSet> {
+        self.groups.remove(name)
+    }
+
+    /// Returns an iterator over the names of the groups and their
+    /// permissions.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PermissionSet)> + '_ {
+        self.groups.iter().map(|(name, set)| (name.as_str(), set))
+    }
+
+    /// Returns whether `set` has the permission `node`, taking its groups and
+    /// the default group into account.
+    pub fn has(&self, set: &PermissionSet, node: &str) -> bool {
+        let mut visited = BTreeSet::new();
+
+        self.resolve(set, node, &mut visited)
+            .or_else(|| {
+                let default = self.groups.get(Self::DEFAULT)?;
+                if visited.insert(Self::DEFAULT) {
+                    self.resolve(default, node, &mut visited)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(false)
+    }
+
+    fn resolve<'a>(
+        &'a self,
+        set: &'a PermissionSet,
+        node: &str,
+        visited: &mut BTreeSet<&'a str>,
+    ) -> Option<bool> {
+        if let Some(granted) = set.get(node) {
+            return Some(granted);
+        }
+
+        for name in &set.groups {
+            // Skip groups already checked so inheritance cycles terminate.
+            if !visited.insert(name.as_str()) {
+                continue;
+            }
+
+            if let Some(group) = self.groups.get(name) {
+                if let Some(granted) = self.resolve(group, node, visited) {
+                    return Some(granted);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Sent when the permissions of an entity may have changed, either because
+/// its [`PermissionSet`] or the [`PermissionGroups`] were modified.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PermissionsChangedEvent {
+    pub entity: Entity,
+}
+
+/// A [`SystemParam`] for checking the permissions of entities.
+#[derive(SystemParam)]
+pub struct Permissions<'w, 's> {
+    groups: Res<'w, PermissionGroups>,
+    sets: Query<'w, 's, &'static PermissionSet>,
+}
+
+impl Permissions<'_, '_> {
+    /// Returns whether `entity` has the permission `node`. Entities without a
+    /// [`PermissionSet`] only have the permissions of the default group.
+    pub fn has(&self, entity: Entity, node: &str) -> bool {
+        match self.sets.get(entity) {
+            Ok(set) => self.groups.has(set, node),
+            Err(_) => self.groups.has(&PermissionSet::new(), node),
+        }
+    }
+
+    pub fn groups(&self) -> &PermissionGroups {
+        &self.groups
+    }
+}
+
+fn send_permission_changes(
+    groups: Res<PermissionGroups>,
+    all: Query<Entity, With<PermissionSet>>,
+    changed: Query<Entity, Changed<PermissionSet>>,
+    mut removed: RemovedComponents<PermissionSet>,
+    mut events: EventWriter<PermissionsChangedEvent>,
+) {
+    let mut entities = if groups.is_changed() {
+        all.iter().collect::<Vec<_>>()
+    } else {
+        changed.iter().collect()
+    };
+
+    // Entities without a set fall back to the default group.
+    entities.extend(removed.read());
+
+    events.send_batch(
+        entities
+            .into_iter()
+            .map(|entity| PermissionsChangedEvent { entity }),
+    );
+}
+
+/// Gives entities the command scopes used in the command graph which they have
+/// the permissions for, which updates the command tree sent to clients.
+/// Entities without a [`PermissionSet`] get the scopes of the default group.
+/// Scopes which aren't used by any command are left alone.
+fn sync_command_scopes(
+    mut events: EventReader<PermissionsChangedEvent>,
+    registry: Res<CommandRegistry>,
+    groups: Res<PermissionGroups>,
+    mut entities: Query<(Entity, Option<&PermissionSet>, &mut CommandScopes)>,
+) {
+    // Commands with new scopes may have been registered, and the default group
+    // may have changed for entities without a set, so every entity is updated.
+    let changed = if registry.is_changed() || groups.is_changed() {
+        events.clear();
+        entities
+            .iter()
+            .map(|(entity, _, _)| entity)
+            .collect::<BTreeSet<_>>()
+    } else {
+        events
+            .read()
+            .map(|event| event.entity)
+            .chain(
+                entities
+                    .iter_mut()
+                    .filter(|(_, _, scopes)| scopes.is_added())
+                    .map(|(entity, _, _)| entity),
+            )
+            .collect()
+    };
+
+    if changed.is_empty() {
+        return;
+    }
+
+    let required_scopes = registry
+        .graph
+        .graph
+        .node_weights()
+        .flat_map(|node| node.scopes.iter())
+        .collect::<BTreeSet<_>>();
+
+    let no_permissions = PermissionSet::new();
+
+    for entity in changed {
+        let Ok((_, set, mut scopes)) = entities.get_mut(entity) else {
+            continue;
+        };
+
+        let set = set.unwrap_or(&no_permissions);
+
+        for scope in &required_scopes {
+            let granted = groups.has(set, scope);
+
+            // Avoid triggering change detection, which resends the command tree.
+            if granted != scopes.contains(scope.as_str()) {
+                if granted {
+                    scopes.add(scope);
+                } else {
+                    scopes.remove(scope.as_str());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_resolution() {
+        let mut groups = PermissionGroups::default();
+
+        groups
+            .get_or_insert(PermissionGroups::DEFAULT)
+            .grant("valence.command.help");
+
+        groups
+            .get_or_insert("moderator")
+            .grant("valence.command.*")
+            .deny("valence.command.stop");
+
+        groups
+            .get_or_insert("admin")
+            .grant("valence.command.stop")
+            .add_group("moderator");
+
+        // Inheritance cycles don't loop forever.
+        groups.get_or_insert("moderator").add_group("admin");
+
+        let mut player = PermissionSet::new();
+        assert!(groups.has(&player, "valence.command.help"));
+        assert!(!groups.has(&player, "valence.command.kick"));
+
+        player.add_group("moderator");
+        assert!(groups.has(&player, "valence.command.kick"));
+        assert!(!groups.has(&player, "valence.command.stop"));
+        assert!(!groups.has(&player, "valence.commands"));
+
+        player.remove_group("moderator").add_group("admin");
+        assert!(groups.has(&player, "valence.command.stop"));
+
+        // The entity's own nodes take priority over its groups.
+        player.deny("valence.command.kick");
+        assert!(!groups.has(&player, "valence.command.kick"));
+
+        // Denials win over grants of equal specificity, and more specific nodes win.
+        let mut set = PermissionSet::new();
+        set.grant("*").deny("a.*").grant("a.b.*");
+        assert!(set.get("x.y").unwrap());
+        assert!(!set.get("a.c").unwrap());
+        assert!(set.get("a.b.c").unwrap());
+        assert_eq!(set.get("a"), Some(true));
+    }
+
+    #[test]
+    fn command_scopes_follow_permissions() {
+        let mut app = App::new();
+        app.init_resource::<CommandRegistry>()
+            .add_plugins(PermissionPlugin);
+
+        let mut registry = app.world.resource_mut::<CommandRegistry>();
+        let graph = &mut registry.graph;
+        for scope in ["valence.command.help", "valence.command.kick"] {
+            let mut node = graph.graph[graph.root].clone();
+            node.scopes = vec![scope.into()];
+            graph.graph.add_node(node);
+        }
+
+        app.world
+            .resource_mut::<PermissionGroups>()
+            .get_or_insert(PermissionGroups::DEFAULT)
+            .grant("valence.command.help");
+
+        let mut custom = CommandScopes::new();
+        custom.add("custom.scope");
+        custom.add("valence.command.kick");
+        let player = app.world.spawn(custom).id();
+
+        app.update();
+
+        // Entities without a set get the default group, and scopes which
+        // aren't in the command graph are kept.
+        let scopes = app.world.get::<CommandScopes>(player).unwrap();
+        assert!(scopes.contains("valence.command.help"));
+        assert!(!scopes.contains("valence.command.kick"));
+        assert!(scopes.contains("custom.scope"));
+
+        let mut set = PermissionSet::new();
+        set.grant("valence.command.kick");
+        app.world.entity_mut(player).insert(set);
+
+        app.update();
+
+        let scopes = app.world.get::<CommandScopes>(player).unwrap();
+        assert!(scopes.contains("valence.command.kick"));
+        assert!(scopes.contains("custom.scope"));
+
+        app.world.entity_mut(player).remove::<PermissionSet>();
+
+        app.update();
+
+        let scopes = app.world.get::<CommandScopes>(player).unwrap();
+        assert!(!scopes.contains("valence.command.kick"));
+        assert!(scopes.contains("valence.command.help"));
+    }
+}
@@ -0,0 +1,265 @@
+use valence_inventory::contents::ContainerContents;
+use valence_inventory::player_inventory::PlayerInventory;
+use valence_server::ident::Ident;
+use valence_server::math::DVec3;
+use valence_server::nbt::{Compound, List, Value};
+use valence_server::{ident, GameMode};
+
+/// The data version of Minecraft 1.20.1, written to saved player data.
+const DATA_VERSION: i32 = 3465;
+
+/// The key custom data is stored under. Vanilla ignores it.
+const CUSTOM_DATA_KEY: &str = "ValenceData";
+
+/// The state of a player which is saved when they leave and restored when
+/// they join again.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerData {
+    pub position: DVec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// The name of the dimension the player was in, such as
+    /// `minecraft:overworld`.
+    pub dimension: Ident<String>,
+    pub game_mode: GameMode,
+    pub health: f32,
+    pub food: i32,
+    pub saturation: f32,
+    /// The items in the player's inventory, indexed by the slots of
+    /// [`PlayerInventory`].
+    pub inventory: ContainerContents,
+    /// The selected hotbar slot, from 0 to 8.
+    pub selected_slot: u8,
+    /// Data of the server's own. Kept in the player's
+    /// [`CustomPlayerData`](crate::CustomPlayerData).
+    pub custom: Compound,
+}
+
+impl Default for PlayerData {
+    fn default() -> Self {
+        Self {
+            position: DVec3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+            dimension: ident!("overworld").into(),
+            game_mode: GameMode::default(),
+            health: 20.0,
+            food: 20,
+            saturation: 5.0,
+            inventory: ContainerContents::new(),
+            selected_slot: 0,
+            custom: Compound::new(),
+        }
+    }
+}
+
+impl PlayerData {
+    /// Reads player data in the format of the `playerdata` files of vanilla
+    /// worlds. Missing and malformed values are left at their defaults.
+    pub fn from_nbt(nbt: &Compound) -> Self {
+        let mut data = Self::default();
+
+        if let Some(Value::List(List::Double(pos))) = nbt.get("Pos") {
+            if let &[x, y, z] = pos.as_slice() {
+                data.position = DVec3::new(x, y, z);
+            }
+        }
+
+        if let Some(Value::List(List::Float(rotation))) = nbt.get("Rotation") {
+            if let &[yaw, pitch] = rotation.as_slice() {
+                data.yaw = yaw;
+                data.pitch = pitch;
+            }
+        }
+
+        if let Some(Value::String(dimension)) = nbt.get("Dimension") {
+            if let Ok(dimension) = Ident::new(dimension.clone()) {
+                data.dimension = dimension.into();
+            }
+        }
+
+        if let Some(&Value::Int(game_mode)) = nbt.get("playerGameType") {
+            data.game_mode = match game_mode {
+                1 => GameMode::Creative,
+                2 => GameMode::Adventure,
+                3 => GameMode::Spectator,
+                _ => GameMode::Survival,
+            };
+        }
+
+        if let Some(&Value::Float(health)) = nbt.get("Health") {
+            data.health = health;
+        }
+
+        if let Some(&Value::Int(food)) = nbt.get("foodLevel") {
+            data.food = food;
+        }
+
+        if let Some(&Value::Float(saturation)) = nbt.get("foodSaturationLevel") {
+            data.saturation = saturation;
+        }
+
+        if let Some(items) = nbt.get("Inventory") {
+            for (slot, stack) in ContainerContents::from_slotted_list(items).iter() {
+                if let Some(slot) = from_vanilla_slot(slot as i8) {
+                    data.inventory.set(slot as u8, stack.clone());
+                }
+            }
+        }
+
+        if let Some(&Value::Int(slot)) = nbt.get("SelectedItemSlot") {
+            data.selected_slot = slot.clamp(0, 8) as u8;
+        }
+
+        if let Some(Value::Compound(custom)) = nbt.get(CUSTOM_DATA_KEY) {
+            data.custom = custom.clone();
+        }
+
+        data
+    }
+
+    /// Writes the data in the format of the `playerdata` files of vanilla
+    /// worlds.
+    pub fn to_nbt(&self) -> Compound {
+        let mut inventory = ContainerContents::new();
+
+        for (slot, stack) in self.inventory.iter() {
+            if let Some(slot) = to_vanilla_slot(slot.into()) {
+                inventory.set(slot as u8, stack.clone());
+            }
+        }
+
+        let game_mode = match self.game_mode {
+            GameMode::Survival => 0,
+            GameMode::Creative => 1,
+            GameMode::Adventure => 2,
+            GameMode::Spectator => 3,
+        };
+
+        let mut nbt = Compound::new();
+
+        nbt.insert("DataVersion", DATA_VERSION);
+        nbt.insert(
+            "Pos",
+            List::Double(vec![self.position.x, self.position.y, self.position.z]),
+        );
+        nbt.insert("Rotation", List::Float(vec![self.yaw, self.pitch]));
+        nbt.insert("Dimension", self.dimension.as_str());
+        nbt.insert("playerGameType", game_mode);
+        nbt.insert("Health", self.health);
+        nbt.insert("foodLevel", self.food);
+        nbt.insert("foodSaturationLevel", self.saturation);
+        nbt.insert("Inventory", inventory.to_slotted_list());
+        nbt.insert("SelectedItemSlot", i32::from(self.selected_slot));
+
+        if !self.custom.is_empty() {
+            nbt.insert(CUSTOM_DATA_KEY, self.custom.clone());
+        }
+
+        nbt
+    }
+}
+
+/// Converts a slot of the `Inventory` list of vanilla player data to a slot
+/// of [`PlayerInventory`].
+fn from_vanilla_slot(slot: i8) -> Option<u16> {
+    match slot {
+        0..=8 => Some(PlayerInventory::hotbar_to_slot(slot as u8)),
+        9..=35 => Some(slot as u16),
+        100 => Some(PlayerInventory::SLOT_FEET),
+        101 => Some(PlayerInventory::SLOT_LEGS),
+        102 => Some(PlayerInventory::SLOT_CHEST),
+        103 => Some(PlayerInventory::SLOT_HEAD),
+        -106 => Some(PlayerInventory::SLOT_OFFHAND),
+        _ => None,
+    }
+}
+
+/// Converts a slot of [`PlayerInventory`] to a slot of the `Inventory` list of
+/// vanilla player data. The crafting grid isn't saved.
+fn to_vanilla_slot(slot: u16) -> Option<i8> {
+    match slot {
+        PlayerInventory::SLOT_HEAD => Some(103),
+        PlayerInventory::SLOT_CHEST => Some(102),
+        PlayerInventory::SLOT_LEGS => Some(101),
+        PlayerInventory::SLOT_FEET => Some(100),
+        PlayerInventory::SLOT_OFFHAND => Some(-106),
+        9..=35 => Some(slot as i8),
+        36..=44 => Some(PlayerInventory::slot_to_hotbar(slot) as i8),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::nbt::compound;
+    use valence_server::{ItemKind, ItemStack};
+
+    use super::*;
+
+    #[test]
+    fn vanilla_round_trip() {
+        let mut data = PlayerData {
+            position: DVec3::new(1.5, 64.0, -3.5),
+            yaw: 90.0,
+            pitch: -10.0,
+            dimension: ident!("the_nether").into(),
+            game_mode: GameMode::Creative,
+            health: 7.5,
+            food: 12,
+            saturation: 1.0,
+            selected_slot: 4,
+            custom: compound! { "coins" => 100 },
+            ..Default::default()
+        };
+
+        data.inventory
+            .set(36, ItemStack::new(ItemKind::DiamondSword, 1, None));
+        data.inventory
+            .set(20, ItemStack::new(ItemKind::Cobblestone, 64, None));
+        data.inventory.set(
+            PlayerInventory::SLOT_HEAD as u8,
+            ItemStack::new(ItemKind::IronHelmet, 1, None),
+        );
+        data.inventory.set(
+            PlayerInventory::SLOT_OFFHAND as u8,
+            ItemStack::new(ItemKind::Shield, 1, None),
+        );
+
+        let nbt = data.to_nbt();
+
+        // The slots are stored the way vanilla stores them.
+        let Some(Value::List(List::Compound(items))) = nbt.get("Inventory") else {
+            panic!("missing inventory");
+        };
+
+        let slots: Vec<_> = items
+            .iter()
+            .map(|item| match item.get("Slot") {
+                Some(&Value::Byte(slot)) => slot,
+                _ => panic!("missing slot"),
+            })
+            .collect();
+
+        assert_eq!(slots, [0, 20, 103, -106]);
+        assert_eq!(nbt.get("playerGameType"), Some(&Value::Int(1)));
+
+        assert_eq!(PlayerData::from_nbt(&nbt), data);
+    }
+
+    #[test]
+    fn missing_values_are_defaults() {
+        let data = PlayerData::from_nbt(&compound! {
+            "Pos" => List::Double(vec![1.0, 2.0]),
+            "Health" => 5.0_f32,
+        });
+
+        assert_eq!(
+            data,
+            PlayerData {
+                health: 5.0,
+                ..Default::default()
+            }
+        );
+    }
+}
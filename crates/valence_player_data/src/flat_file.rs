@@ -0,0 +1,141 @@
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use uuid::Uuid;
+use valence_server::nbt::{from_binary, to_binary, Compound};
+
+use crate::{PlayerData, PlayerDataProvider};
+
+/// Stores player data in a directory of gzipped NBT files named after the
+/// UUIDs of the players, like the `playerdata` directory of vanilla worlds.
+/// Pointing it at the `playerdata` directory of a vanilla world shares the
+/// player data with the world.
+#[derive(Clone, Debug)]
+pub struct FlatFileProvider {
+    dir: PathBuf,
+}
+
+impl FlatFileProvider {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The path of the file the data of the player is stored in.
+    pub fn path(&self, uuid: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.dat", uuid.hyphenated()))
+    }
+
+    /// Reads the data of a player, blocking the current thread.
+    pub fn load_blocking(&self, uuid: Uuid) -> anyhow::Result<Option<PlayerData>> {
+        let path = self.path(uuid);
+
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("failed to open {}", path.display())),
+        };
+
+        let mut buf = vec![];
+        GzDecoder::new(file)
+            .read_to_end(&mut buf)
+            .with_context(|| format!("failed to decompress {}", path.display()))?;
+
+        let (nbt, _) = from_binary::<String>(&mut buf.as_slice())
+            .with_context(|| format!("failed to decode {}", path.display()))?;
+
+        Ok(Some(PlayerData::from_nbt(&nbt)))
+    }
+
+    /// Writes the data of a player, blocking the current thread.
+    ///
+    /// The data is written to a temporary file first, which then replaces the
+    /// previous file, so a crash while saving doesn't lose the previous data.
+    pub fn save_blocking(&self, uuid: Uuid, data: &PlayerData) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("failed to create {}", self.dir.display()))?;
+
+        let path = self.path(uuid);
+        let tmp_path = path.with_extension("dat_tmp");
+
+        let nbt: Compound = data.to_nbt();
+
+        let mut buf = vec![];
+        to_binary(&nbt, &mut buf, "")?;
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&buf)?;
+
+        fs::write(&tmp_path, encoder.finish()?)
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("failed to replace {}", path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// Stores files in the `playerdata` directory.
+impl Default for FlatFileProvider {
+    fn default() -> Self {
+        Self::new("playerdata")
+    }
+}
+
+#[async_trait]
+impl PlayerDataProvider for FlatFileProvider {
+    async fn load(&self, uuid: Uuid) -> anyhow::Result<Option<PlayerData>> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.load_blocking(uuid)).await?
+    }
+
+    async fn save(&self, uuid: Uuid, data: PlayerData) -> anyhow::Result<()> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.save_blocking(uuid, &data)).await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use valence_server::{ItemKind, ItemStack};
+
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let dir = env::temp_dir().join(format!("valence_player_data_test_{}", std::process::id()));
+        let provider = FlatFileProvider::new(&dir);
+
+        let uuid = Uuid::from_u128(0x1234);
+
+        assert_eq!(provider.load_blocking(uuid).unwrap(), None);
+
+        let mut data = PlayerData {
+            health: 3.0,
+            ..Default::default()
+        };
+        data.inventory
+            .set(40, ItemStack::new(ItemKind::Bread, 16, None));
+
+        provider.save_blocking(uuid, &data).unwrap();
+
+        assert!(dir
+            .join("00000000-0000-0000-0000-000000001234.dat")
+            .exists());
+        assert_eq!(provider.load_blocking(uuid).unwrap(), Some(data));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
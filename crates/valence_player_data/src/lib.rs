@@ -0,0 +1,366 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod data;
+mod flat_file;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::WorldQuery;
+pub use data::PlayerData;
+use derive_more::{Deref, DerefMut};
+pub use flat_file::FlatFileProvider;
+use tokio::runtime::{Builder, Handle, Runtime};
+use tracing::warn;
+use uuid::Uuid;
+use valence_inventory::contents::ContainerContents;
+use valence_inventory::{HeldItem, Inventory};
+use valence_server::client::{Client, VisibleChunkLayer};
+use valence_server::entity::living::Health;
+use valence_server::entity::player::{Food, Saturation};
+use valence_server::entity::{HeadYaw, Look, Position};
+use valence_server::ident::Ident;
+use valence_server::nbt::Compound;
+use valence_server::{ident, ChunkLayer, GameMode, UniqueId};
+
+/// Loads the data of clients when they join and saves it when they leave.
+pub struct PlayerDataPlugin;
+
+impl Plugin for PlayerDataPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerDataSettings>()
+            .insert_resource(PlayerDataStorage::new())
+            .add_event::<PlayerDataLoadedEvent>()
+            .add_systems(
+                Update,
+                (
+                    load_joined_players,
+                    apply_deferred, // So clients have `CustomPlayerData` when data is applied.
+                    apply_loaded_player_data,
+                )
+                    .chain(),
+            )
+            .add_systems(Last, save_disconnected_players);
+    }
+}
+
+/// Loads and saves player data, such as from files or a database.
+///
+/// The methods are called on a tokio runtime. Operations are run one at a
+/// time in the order they were requested, so a player who rejoins quickly
+/// always gets the data saved when they left.
+#[async_trait]
+pub trait PlayerDataProvider: Send + Sync + 'static {
+    /// Loads the data of the player with the given UUID. Returns `None` if
+    /// nothing was saved for the player yet.
+    async fn load(&self, uuid: Uuid) -> anyhow::Result<Option<PlayerData>>;
+
+    /// Saves the data of the player with the given UUID, replacing the
+    /// previously saved data.
+    async fn save(&self, uuid: Uuid, data: PlayerData) -> anyhow::Result<()>;
+}
+
+/// Settings for [`PlayerDataPlugin`]. Changes apply to the loads and saves
+/// requested after them.
+#[derive(Resource, Clone)]
+pub struct PlayerDataSettings {
+    /// Where player data is loaded from and saved to. Defaults to a
+    /// [`FlatFileProvider`] for the `playerdata` directory.
+    pub provider: Arc<dyn PlayerDataProvider>,
+    /// The tokio runtime the provider is called on. If `None`, a runtime is
+    /// created when it is first needed.
+    pub tokio_handle: Option<Handle>,
+}
+
+impl Default for PlayerDataSettings {
+    fn default() -> Self {
+        Self {
+            provider: Arc::new(FlatFileProvider::default()),
+            tokio_handle: None,
+        }
+    }
+}
+
+/// Custom data saved along with the rest of the data of a player. Inserted
+/// on clients when they join, and set from the loaded data.
+#[derive(Component, Clone, PartialEq, Default, Debug, Deref, DerefMut)]
+pub struct CustomPlayerData(pub Compound);
+
+/// Marks clients whose data has been loaded.
+///
+/// Data is only saved for clients with this component, so that clients which
+/// leave before their data is loaded, or whose data failed to load, don't
+/// overwrite their saved data.
+#[derive(Component, Copy, Clone, Default, Debug)]
+pub struct PlayerDataLoaded;
+
+/// Sent when the data of a client has been loaded and applied to it.
+///
+/// The position, look, game mode, health, food, inventory and held item of
+/// the client are set from the data. Moving the client to the layer of its
+/// [`dimension`](PlayerData::dimension) is up to the server.
+#[derive(Event, Clone, Debug)]
+pub struct PlayerDataLoadedEvent {
+    pub client: Entity,
+    /// The loaded data, or `None` if the client has no saved data.
+    pub data: Option<PlayerData>,
+}
+
+/// The components player data is taken from and applied to.
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct PlayerDataQuery {
+    pub position: &'static mut Position,
+    pub look: &'static mut Look,
+    pub head_yaw: &'static mut HeadYaw,
+    pub game_mode: &'static mut GameMode,
+    pub health: &'static mut Health,
+    pub food: &'static mut Food,
+    pub saturation: &'static mut Saturation,
+    pub inventory: &'static mut Inventory,
+    pub held_item: &'static mut HeldItem,
+    pub custom: Option<&'static mut CustomPlayerData>,
+}
+
+impl PlayerDataQueryReadOnlyItem<'_> {
+    /// Collects the data of the player. `dimension` is the name of the
+    /// dimension the player is in.
+    pub fn to_player_data(&self, dimension: Ident<&str>) -> PlayerData {
+        PlayerData {
+            position: self.position.0,
+            yaw: self.look.yaw,
+            pitch: self.look.pitch,
+            dimension: dimension.into(),
+            game_mode: *self.game_mode,
+            health: self.health.0,
+            food: self.food.0,
+            saturation: self.saturation.0,
+            inventory: ContainerContents::from_inventory(self.inventory),
+            selected_slot: self.held_item.hotbar_idx(),
+            custom: self.custom.map(|c| c.0.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+impl PlayerDataQueryItem<'_> {
+    /// Sets the components of the player from `data`.
+    pub fn apply(&mut self, data: &PlayerData) {
+        self.position.0 = data.position;
+        self.look.yaw = data.yaw;
+        self.look.pitch = data.pitch;
+        self.head_yaw.0 = data.yaw;
+        self.game_mode.set_if_neq(data.game_mode);
+        self.health.0 = data.health;
+        self.food.0 = data.food;
+        self.saturation.0 = data.saturation;
+
+        for slot in 0..self.inventory.slot_count() {
+            let stack = data.inventory.get(slot as u8).cloned().unwrap_or_default();
+
+            if *self.inventory.slot(slot) != stack {
+                self.inventory.set_slot(slot, stack);
+            }
+        }
+
+        self.held_item.set_hotbar_idx(data.selected_slot.min(8));
+
+        if let Some(custom) = &mut self.custom {
+            custom.0.clone_from(&data.custom);
+        }
+    }
+}
+
+enum Op {
+    Load {
+        client: Entity,
+        uuid: Uuid,
+        provider: Arc<dyn PlayerDataProvider>,
+    },
+    Save {
+        uuid: Uuid,
+        data: PlayerData,
+        provider: Arc<dyn PlayerDataProvider>,
+    },
+}
+
+/// Runs the loads and saves of player data.
+#[derive(Resource)]
+pub struct PlayerDataStorage {
+    ops_send: flume::Sender<Op>,
+    ops_recv: flume::Receiver<Op>,
+    loaded_send: flume::Sender<(Entity, Option<PlayerData>)>,
+    loaded_recv: flume::Receiver<(Entity, Option<PlayerData>)>,
+    /// Whether the worker task has been spawned.
+    started: bool,
+    // Holding a runtime handle is not enough to keep tokio working. We need
+    // to store the runtime here so we don't drop it.
+    runtime: Option<Runtime>,
+}
+
+impl PlayerDataStorage {
+    fn new() -> Self {
+        let (ops_send, ops_recv) = flume::unbounded();
+        let (loaded_send, loaded_recv) = flume::unbounded();
+
+        Self {
+            ops_send,
+            ops_recv,
+            loaded_send,
+            loaded_recv,
+            started: false,
+            runtime: None,
+        }
+    }
+
+    /// Saves the data of a player with the provider in `settings`, such as
+    /// for saving the data of every client periodically. The data is saved
+    /// after all loads and saves requested before.
+    pub fn save(&mut self, settings: &PlayerDataSettings, uuid: Uuid, data: PlayerData) {
+        self.send(
+            settings,
+            Op::Save {
+                uuid,
+                data,
+                provider: settings.provider.clone(),
+            },
+        );
+    }
+
+    fn send(&mut self, settings: &PlayerDataSettings, op: Op) {
+        if !self.started {
+            let handle = match &settings.tokio_handle {
+                Some(handle) => handle.clone(),
+                None => match Builder::new_multi_thread()
+                    .worker_threads(1)
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => {
+                        let handle = runtime.handle().clone();
+                        self.runtime = Some(runtime);
+                        handle
+                    }
+                    Err(e) => {
+                        warn!("failed to create tokio runtime for player data: {e}");
+                        return;
+                    }
+                },
+            };
+
+            handle.spawn(run_ops(self.ops_recv.clone(), self.loaded_send.clone()));
+            self.started = true;
+        }
+
+        let _ = self.ops_send.send(op);
+    }
+}
+
+async fn run_ops(ops: flume::Receiver<Op>, loaded: flume::Sender<(Entity, Option<PlayerData>)>) {
+    while let Ok(op) = ops.recv_async().await {
+        match op {
+            Op::Load {
+                client,
+                uuid,
+                provider,
+            } => match provider.load(uuid).await {
+                Ok(data) => {
+                    let _ = loaded.send((client, data));
+                }
+                Err(e) => warn!("failed to load player data of {uuid}: {e:#}"),
+            },
+            Op::Save {
+                uuid,
+                data,
+                provider,
+            } => {
+                if let Err(e) = provider.save(uuid, data).await {
+                    warn!("failed to save player data of {uuid}: {e:#}");
+                }
+            }
+        }
+    }
+}
+
+fn load_joined_players(
+    clients: Query<(Entity, &UniqueId), Added<Client>>,
+    settings: Res<PlayerDataSettings>,
+    mut storage: ResMut<PlayerDataStorage>,
+    mut commands: Commands,
+) {
+    for (client, uuid) in &clients {
+        commands.entity(client).insert(CustomPlayerData::default());
+
+        storage.send(
+            &settings,
+            Op::Load {
+                client,
+                uuid: uuid.0,
+                provider: settings.provider.clone(),
+            },
+        );
+    }
+}
+
+fn apply_loaded_player_data(
+    storage: Res<PlayerDataStorage>,
+    mut clients: Query<PlayerDataQuery, With<Client>>,
+    mut events: EventWriter<PlayerDataLoadedEvent>,
+    mut commands: Commands,
+) {
+    for (client, data) in storage.loaded_recv.try_iter() {
+        // The client left while its data was being loaded.
+        let Ok(mut player) = clients.get_mut(client) else {
+            continue;
+        };
+
+        if let Some(data) = &data {
+            player.apply(data);
+        }
+
+        commands.entity(client).insert(PlayerDataLoaded);
+
+        events.send(PlayerDataLoadedEvent { client, data });
+    }
+}
+
+fn save_disconnected_players(
+    mut removed: RemovedComponents<Client>,
+    players: Query<(&UniqueId, PlayerDataQuery, &VisibleChunkLayer), With<PlayerDataLoaded>>,
+    layers: Query<&ChunkLayer>,
+    settings: Res<PlayerDataSettings>,
+    mut storage: ResMut<PlayerDataStorage>,
+) {
+    for client in removed.read() {
+        let Ok((uuid, player, visible_chunk_layer)) = players.get(client) else {
+            continue;
+        };
+
+        let dimension = layers
+            .get(visible_chunk_layer.0)
+            .map_or(ident!("overworld"), |layer| layer.dimension_type_name());
+
+        let data = player.to_player_data(dimension);
+
+        storage.save(&settings, uuid.0, data);
+    }
+}
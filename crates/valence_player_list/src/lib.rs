@@ -18,7 +18,10 @@
 )]
 #![allow(clippy::type_complexity)]
 
+pub mod npc;
+
 use std::borrow::Cow;
+use std::collections::BTreeSet;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
@@ -33,7 +36,7 @@ use valence_server::protocol::packets::play::{
 use valence_server::protocol::WritePacket;
 use valence_server::text::IntoText;
 use valence_server::uuid::Uuid;
-use valence_server::{Despawned, GameMode, Server, Text, UniqueId};
+use valence_server::{Despawned, EventLoopUpdate, GameMode, Server, Text, UniqueId};
 
 pub struct PlayerListPlugin;
 
@@ -57,18 +60,25 @@ impl Plugin for PlayerListPlugin {
                     apply_deferred, // So new clients get the packets for their own entry.
                     update_entries,
                     init_player_list_for_clients,
+                    update_hidden_entries,
                     remove_despawned_entries,
                     write_player_list_changes,
                 )
                     .in_set(PlayerListSet)
                     .chain(),
-            );
+            )
+            .add_event::<npc::NpcInteractEvent>()
+            .add_systems(EventLoopUpdate, npc::emit_npc_interactions)
+            .add_systems(Update, npc::look_at_players);
     }
 }
 
 #[derive(Resource)]
 pub struct PlayerList {
     cached_update_packets: Vec<u8>,
+    /// Entries added by `cached_update_packets`, which need to be removed again
+    /// for clients hiding them.
+    cached_added_entries: Vec<Uuid>,
     header: Text,
     footer: Text,
     changed_header_or_footer: bool,
@@ -81,6 +91,7 @@ impl PlayerList {
     fn new() -> Self {
         Self {
             cached_update_packets: vec![],
+            cached_added_entries: vec![],
             header: Text::default(),
             footer: Text::default(),
             changed_header_or_footer: false,
@@ -115,6 +126,17 @@ impl PlayerList {
 
         self.footer = txt;
     }
+
+    /// Returns a username for a fake entry which makes the entry appear at
+    /// `position` in the player list.
+    ///
+    /// Clients sort the player list by team and then by username, so entries
+    /// without a team are ordered by the usernames returned from this
+    /// function. Set the [`DisplayName`] of the entry to control the text
+    /// that is shown.
+    pub fn ordered_username(position: u16) -> Username {
+        Username(format!("~{position:05}"))
+    }
 }
 
 /// Bundle for spawning new player list entries. All components are required
@@ -154,6 +176,45 @@ impl Default for Listed {
     }
 }
 
+/// Component for clients which hides player list entries from that client
+/// only.
+///
+/// Unlike [`Listed`], hidden entries are removed from the client entirely.
+/// Clients don't render player entities which have no entry, so hiding a
+/// player also makes them invisible to the client if they are spawned after
+/// being hidden.
+#[derive(Component, Clone, Default, Debug)]
+pub struct HiddenEntries {
+    hidden: BTreeSet<Uuid>,
+    sent: BTreeSet<Uuid>,
+}
+
+impl HiddenEntries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hides the entry with the [`UniqueId`] `uuid`. Returns `false` if it
+    /// was already hidden.
+    pub fn hide(&mut self, uuid: Uuid) -> bool {
+        self.hidden.insert(uuid)
+    }
+
+    /// Shows the entry with the [`UniqueId`] `uuid` again. Returns `false` if
+    /// it wasn't hidden.
+    pub fn show(&mut self, uuid: Uuid) -> bool {
+        self.hidden.remove(&uuid)
+    }
+
+    pub fn is_hidden(&self, uuid: Uuid) -> bool {
+        self.hidden.contains(&uuid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Uuid> + '_ {
+        self.hidden.iter().copied()
+    }
+}
+
 fn update_header_footer(player_list: ResMut<PlayerList>, server: Res<Server>) {
     if player_list.changed_header_or_footer {
         let player_list = player_list.into_inner();
@@ -189,7 +250,7 @@ fn add_new_clients_to_player_list(
 }
 
 fn init_player_list_for_clients(
-    mut clients: Query<&mut Client, (Added<Client>, Without<Despawned>)>,
+    mut clients: Query<(&mut Client, Option<&HiddenEntries>), (Added<Client>, Without<Despawned>)>,
     player_list: Res<PlayerList>,
     entries: Query<
         (
@@ -205,7 +266,7 @@ fn init_player_list_for_clients(
     >,
 ) {
     if player_list.manage_clients {
-        for mut client in &mut clients {
+        for (mut client, hidden) in &mut clients {
             let actions = packet::PlayerListActions::new()
                 .with_add_player(true)
                 .with_update_game_mode(true)
@@ -215,6 +276,7 @@ fn init_player_list_for_clients(
 
             let entries: Vec<_> = entries
                 .iter()
+                .filter(|(uuid, ..)| !hidden.is_some_and(|h| h.is_hidden(uuid.0)))
                 .map(
                     |(uuid, username, props, game_mode, ping, display_name, listed)| {
                         packet::PlayerListEntry {
@@ -317,6 +379,7 @@ fn update_entries(
         // new entries.
         if uuid.is_changed() || username.is_changed() || props.is_changed() {
             actions.set_add_player(true);
+            player_list.cached_added_entries.push(uuid.0);
 
             if *game_mode != GameMode::default() {
                 actions.set_update_game_mode(true);
@@ -371,17 +434,101 @@ fn update_entries(
     }
 }
 
+fn update_hidden_entries(
+    mut clients: Query<(&mut Client, &mut HiddenEntries), Changed<HiddenEntries>>,
+    entries: Query<
+        (
+            &UniqueId,
+            &Username,
+            &Properties,
+            &GameMode,
+            &Ping,
+            &DisplayName,
+            &Listed,
+        ),
+        (With<PlayerListEntry>, Without<Despawned>),
+    >,
+) {
+    for (mut client, mut hidden) in &mut clients {
+        let hidden = hidden.bypass_change_detection();
+
+        // New clients have only been sent the entries that are not hidden.
+        if !client.is_added() {
+            let removed: Vec<_> = hidden.hidden.difference(&hidden.sent).copied().collect();
+
+            if !removed.is_empty() {
+                client.write_packet(&PlayerRemoveS2c {
+                    uuids: Cow::Owned(removed),
+                });
+            }
+
+            let shown: Vec<_> = entries
+                .iter()
+                .filter(|(uuid, ..)| {
+                    hidden.sent.contains(&uuid.0) && !hidden.hidden.contains(&uuid.0)
+                })
+                .map(
+                    |(uuid, username, props, game_mode, ping, display_name, listed)| {
+                        packet::PlayerListEntry {
+                            player_uuid: uuid.0,
+                            username: &username.0,
+                            properties: Cow::Borrowed(&props.0),
+                            chat_data: None,
+                            listed: listed.0,
+                            ping: ping.0,
+                            game_mode: *game_mode,
+                            display_name: display_name.0.as_ref().map(Cow::Borrowed),
+                        }
+                    },
+                )
+                .collect();
+
+            if !shown.is_empty() {
+                client.write_packet(&PlayerListS2c {
+                    actions: packet::PlayerListActions::new()
+                        .with_add_player(true)
+                        .with_update_game_mode(true)
+                        .with_update_listed(true)
+                        .with_update_latency(true)
+                        .with_update_display_name(true),
+                    entries: Cow::Owned(shown),
+                });
+            }
+        }
+
+        hidden.sent.clone_from(&hidden.hidden);
+    }
+}
+
 fn write_player_list_changes(
     mut player_list: ResMut<PlayerList>,
-    mut clients: Query<&mut Client, Without<Despawned>>,
+    mut clients: Query<(&mut Client, Option<&HiddenEntries>), Without<Despawned>>,
 ) {
     if !player_list.cached_update_packets.is_empty() {
-        for mut client in &mut clients {
+        for (mut client, hidden) in &mut clients {
             if !client.is_added() {
                 client.write_packet_bytes(&player_list.cached_update_packets);
+
+                // Remove the hidden entries which were just added again.
+                if let Some(hidden) = hidden {
+                    let readded: Vec<_> = player_list
+                        .cached_added_entries
+                        .iter()
+                        .copied()
+                        .filter(|&uuid| hidden.is_hidden(uuid))
+                        .collect();
+
+                    if !readded.is_empty() {
+                        client.write_packet(&PlayerRemoveS2c {
+                            uuids: Cow::Owned(readded),
+                        });
+                    }
+                }
             }
         }
 
         player_list.cached_update_packets.clear();
     }
+
+    player_list.cached_added_entries.clear();
 }
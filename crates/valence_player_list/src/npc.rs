@@ -0,0 +1,215 @@
+//! Player-shaped non-player characters.
+//!
+//! Player entities are only rendered by clients which have an entry for the
+//! entity's UUID in their player list, and the skin of the entity is taken
+//! from the entry's textures. Older versions of the game required adding the
+//! entry, spawning the entity, and then removing the entry again to keep NPCs
+//! out of the tab list. Since 1.19.3, the entry can instead be kept and marked
+//! as unlisted, which [`NpcBundle`] does by default. The entry is removed
+//! along with the entity when the NPC is despawned.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_player_list::npc::{LookAtPlayers, NpcBundle, NpcInteractEvent};
+//! use valence_server::EntityLayer;
+//!
+//! fn spawn_npc(mut commands: Commands, layers: Query<Entity, With<EntityLayer>>) {
+//!     for layer in &layers {
+//!         commands.spawn((
+//!             NpcBundle::new(layer, "Shopkeeper")
+//!                 .with_position([0.0, 65.0, 0.0])
+//!                 .with_skin("<base64 textures>", "<signature>"),
+//!             LookAtPlayers::default(),
+//!         ));
+//!     }
+//! }
+//!
+//! fn open_shop(mut events: EventReader<NpcInteractEvent>) {
+//!     for event in events.read() {
+//!         println!("{:?} clicked on {:?}", event.client, event.npc);
+//!     }
+//! }
+//! # let _ = (spawn_npc, open_shop);
+//! ```
+
+use bevy_ecs::prelude::*;
+use valence_server::client::{Client, Properties, Username};
+use valence_server::entity::player::{PlayerEntityBundle, PlayerModelParts};
+use valence_server::entity::{EntityLayerId, HeadYaw, Look, Position};
+use valence_server::interact_entity::{EntityInteraction, InteractEntityEvent};
+use valence_server::keepalive::Ping;
+use valence_server::math::{DVec3, Vec3};
+use valence_server::{Despawned, GameMode, Text};
+
+use crate::{DisplayName, Listed, PlayerListEntry};
+
+/// Marker component for entities spawned with [`NpcBundle`].
+#[derive(Component, Copy, Clone, Default, Debug)]
+pub struct Npc;
+
+/// Bundle for spawning a player entity together with the player list entry
+/// which provides its name and skin.
+///
+/// # Despawning NPCs
+///
+/// The [`Despawned`] component must be used to despawn NPCs, so that the
+/// player list entry is removed as well.
+///
+/// [`Despawned`]: valence_server::Despawned
+#[derive(Bundle, Default, Debug)]
+pub struct NpcBundle {
+    pub npc: Npc,
+    /// The entity. Its [`UniqueId`](valence_server::UniqueId) is shared with
+    /// the player list entry.
+    pub player: PlayerEntityBundle,
+    pub player_list_entry: PlayerListEntry,
+    /// The name shown above the entity. Must be at most 16 characters long.
+    pub username: Username,
+    /// The textures of the entity's skin.
+    pub properties: Properties,
+    pub game_mode: GameMode,
+    pub ping: Ping,
+    pub display_name: DisplayName,
+    /// Whether the NPC appears in the player list.
+    ///
+    /// # Default Value
+    ///
+    /// `false`
+    pub listed: Listed,
+}
+
+impl NpcBundle {
+    /// Creates an NPC named `username` with the default skin in the entity
+    /// layer `layer`.
+    pub fn new(layer: Entity, username: impl Into<String>) -> Self {
+        Self {
+            player: PlayerEntityBundle {
+                layer: EntityLayerId(layer),
+                ..Default::default()
+            },
+            username: Username(username.into()),
+            listed: Listed(false),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_position(mut self, pos: impl Into<DVec3>) -> Self {
+        self.player.position = Position::new(pos);
+        self
+    }
+
+    pub fn with_look(mut self, yaw: f32, pitch: f32) -> Self {
+        self.player.look = Look::new(yaw, pitch);
+        self.player.head_yaw = HeadYaw(yaw);
+        self
+    }
+
+    /// Sets the skin to the base64 encoded `textures` property of a profile
+    /// and its signature, as returned by the session server.
+    pub fn with_skin(mut self, value: impl Into<String>, signature: impl Into<String>) -> Self {
+        self.properties.set_skin(value, signature);
+        self
+    }
+
+    /// Sets the parts of the skin which are shown, such as the hat layer and
+    /// the cape. Use [`PlayerModelParts::ALL`] to show everything.
+    pub fn with_model_parts(mut self, parts: PlayerModelParts) -> Self {
+        self.player.player_player_model_parts = parts;
+        self
+    }
+
+    /// Sets the name shown in the player list, if the NPC is
+    /// [listed](Self::with_listed).
+    pub fn with_display_name(mut self, name: impl Into<Text>) -> Self {
+        self.display_name = DisplayName(Some(name.into()));
+        self
+    }
+
+    pub fn with_listed(mut self, listed: bool) -> Self {
+        self.listed = Listed(listed);
+        self
+    }
+}
+
+/// Makes an [`Npc`] turn its head towards the closest client within
+/// `radius` blocks. The NPC keeps its last direction when no client is close
+/// enough.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct LookAtPlayers {
+    pub radius: f64,
+}
+
+/// Returns a radius of 8 blocks.
+impl Default for LookAtPlayers {
+    fn default() -> Self {
+        Self { radius: 8.0 }
+    }
+}
+
+/// Sent when a client attacks or interacts with an [`Npc`].
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct NpcInteractEvent {
+    pub client: Entity,
+    pub npc: Entity,
+    /// If the client was sneaking during the interaction.
+    pub sneaking: bool,
+    pub interact: EntityInteraction,
+}
+
+pub(crate) fn emit_npc_interactions(
+    npcs: Query<(), With<Npc>>,
+    mut interactions: EventReader<InteractEntityEvent>,
+    mut events: EventWriter<NpcInteractEvent>,
+) {
+    for event in interactions.read() {
+        if npcs.contains(event.entity) {
+            events.send(NpcInteractEvent {
+                client: event.client,
+                npc: event.entity,
+                sneaking: event.sneaking,
+                interact: event.interact,
+            });
+        }
+    }
+}
+
+pub(crate) fn look_at_players(
+    mut npcs: Query<
+        (
+            &Position,
+            &EntityLayerId,
+            &LookAtPlayers,
+            &mut Look,
+            &mut HeadYaw,
+        ),
+        (With<Npc>, Without<Despawned>),
+    >,
+    clients: Query<(&Position, &EntityLayerId), (With<Client>, Without<Npc>)>,
+) {
+    for (npc_pos, npc_layer, look_at, mut look, mut head_yaw) in &mut npcs {
+        let closest = clients
+            .iter()
+            .filter(|(_, layer)| *layer == npc_layer)
+            .map(|(pos, _)| pos.0 - npc_pos.0)
+            .filter(|offset| offset.length_squared() <= look_at.radius * look_at.radius)
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+
+        let Some(offset) = closest else {
+            continue;
+        };
+
+        // Both the NPC and the client are assumed to be standing.
+        let dir = Vec3::new(offset.x as f32, offset.y as f32, offset.z as f32);
+
+        if let Some(dir) = dir.try_normalize() {
+            let mut new_look = *look;
+            new_look.set_vec(dir);
+
+            // Avoid marking the look as changed when nothing moved.
+            look.set_if_neq(new_look);
+            head_yaw.set_if_neq(HeadYaw(new_look.yaw));
+        }
+    }
+}
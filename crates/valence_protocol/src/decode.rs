@@ -99,12 +99,8 @@ impl PacketDecoder {
             } else {
                 debug_assert_eq!(data_len, 0);
 
-                ensure!(
-                    r.len() <= self.threshold.0 as usize,
-                    "uncompressed packet length of {} exceeds compression threshold of {}",
-                    r.len(),
-                    self.threshold.0
-                );
+                // Uncompressed packets may exceed the threshold, since the
+                // encoder can decide to skip compression for any packet.
 
                 let remaining_len = r.len();
 
@@ -200,6 +196,11 @@ impl PacketDecoder {
     pub fn reserve(&mut self, additional: usize) {
         self.buf.reserve(additional);
     }
+
+    /// Returns the length of the queued data which wasn't decoded yet.
+    pub(crate) fn queued_len(&self) -> usize {
+        self.buf.len()
+    }
 }
 
 #[derive(Clone, Debug)]
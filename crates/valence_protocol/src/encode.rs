@@ -1,4 +1,7 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(feature = "encryption")]
 use aes::cipher::generic_array::GenericArray;
@@ -8,8 +11,10 @@ use anyhow::ensure;
 use bytes::{BufMut, BytesMut};
 use tracing::warn;
 
+use crate::decode::{PacketDecoder, PacketFrame};
+use crate::translate::PacketTranslator;
 use crate::var_int::VarInt;
-use crate::{CompressionThreshold, Encode, Packet, MAX_PACKET_SIZE};
+use crate::{CompressionThreshold, Encode, Packet, PacketState, MAX_PACKET_SIZE, PROTOCOL_VERSION};
 
 /// The AES block cipher with a 128 bit key, using the CFB-8 mode of
 /// operation.
@@ -23,8 +28,21 @@ pub struct PacketEncoder {
     compress_buf: Vec<u8>,
     #[cfg(feature = "compression")]
     threshold: CompressionThreshold,
+    /// Packets with data at most this long are never compressed, even when
+    /// they exceed the threshold.
+    min_compressed_len: usize,
+    /// IDs of packets which are never compressed.
+    uncompressed_ids: BTreeSet<i32>,
+    stats: Option<CompressionStats>,
     #[cfg(feature = "encryption")]
     cipher: Option<Cipher>,
+    translation: Option<Translation>,
+}
+
+/// The translator of a [`PacketEncoder`] and the state of its connection.
+struct Translation {
+    translator: Arc<dyn PacketTranslator>,
+    state: PacketState,
 }
 
 impl PacketEncoder {
@@ -32,9 +50,19 @@ impl PacketEncoder {
         Self::default()
     }
 
+    /// Appends raw packet data.
+    ///
+    /// If a [translator](Self::set_translator) is set, the data is decoded,
+    /// translated and encoded again, so it must consist of whole packets
+    /// encoded with the compression threshold of this encoder. Packets which
+    /// fail to translate are logged and discarded.
     #[inline]
     pub fn append_bytes(&mut self, bytes: &[u8]) {
-        self.buf.extend_from_slice(bytes)
+        if self.translation.is_none() {
+            self.buf.extend_from_slice(bytes);
+        } else if let Err(e) = self.append_translated_bytes(bytes) {
+            warn!("failed to translate packet data: {e:#}");
+        }
     }
 
     pub fn prepend_packet<P>(&mut self, pkt: &P) -> anyhow::Result<()>
@@ -62,20 +90,129 @@ impl PacketEncoder {
     where
         P: Packet + Encode,
     {
+        if let Some(translation) = &mut self.translation {
+            translation.state = P::STATE;
+
+            let mut body = BytesMut::new();
+            pkt.encode((&mut body).writer())?;
+
+            return self.append_translated(P::ID, body);
+        }
+
         let start_len = self.buf.len();
 
         pkt.encode_with_id((&mut self.buf).writer())?;
 
+        self.frame_packet(start_len, P::ID)
+    }
+
+    /// Appends a packet which was already encoded, such as one from a
+    /// [`PacketFrame`]. `body` is the content of the packet after the leading
+    /// VarInt ID.
+    pub fn append_packet_frame(&mut self, id: i32, body: &[u8]) -> anyhow::Result<()> {
+        if self.translation.is_some() {
+            return self.append_translated(id, BytesMut::from(body));
+        }
+
+        self.append_untranslated_frame(id, body)
+    }
+
+    /// Returns the translator of the clientbound packets, if any.
+    pub fn translator(&self) -> Option<&Arc<dyn PacketTranslator>> {
+        self.translation.as_ref().map(|t| &t.translator)
+    }
+
+    /// Sets the translator of all packets appended from now on, or removes
+    /// it. `state` is the state of the connection, which is updated whenever
+    /// a packet is appended with [`append_packet`](Self::append_packet).
+    pub fn set_translator(
+        &mut self,
+        translator: Option<Arc<dyn PacketTranslator>>,
+        state: PacketState,
+    ) {
+        self.translation = translator.map(|translator| Translation { translator, state });
+    }
+
+    /// Sets the state of the connection that raw packet data and packet frames
+    /// are translated in. Only needed when switching states without appending
+    /// a packet of the new state, such as after login.
+    pub fn set_state(&mut self, state: PacketState) {
+        if let Some(translation) = &mut self.translation {
+            translation.state = state;
+        }
+    }
+
+    /// Returns the protocol version the packets are encoded for.
+    pub fn protocol_version(&self) -> i32 {
+        self.translator()
+            .map_or(PROTOCOL_VERSION, |t| t.protocol_version())
+    }
+
+    fn append_translated(&mut self, id: i32, body: BytesMut) -> anyhow::Result<()> {
+        let Some(translation) = &self.translation else {
+            return self.append_untranslated_frame(id, &body);
+        };
+
+        let mut frame = PacketFrame { id, body };
+
+        if translation
+            .translator
+            .clientbound(translation.state, &mut frame)?
+        {
+            self.append_untranslated_frame(frame.id, &frame.body)?;
+        }
+
+        Ok(())
+    }
+
+    fn append_translated_bytes(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        let mut dec = PacketDecoder::new();
+
+        #[cfg(feature = "compression")]
+        dec.set_compression(self.threshold);
+
+        dec.queue_slice(bytes);
+
+        while let Some(frame) = dec.try_next_packet()? {
+            self.append_translated(frame.id, frame.body)?;
+        }
+
+        ensure!(dec.queued_len() == 0, "incomplete packet data");
+
+        Ok(())
+    }
+
+    fn append_untranslated_frame(&mut self, id: i32, body: &[u8]) -> anyhow::Result<()> {
+        let start_len = self.buf.len();
+
+        VarInt(id).encode((&mut self.buf).writer())?;
+        self.buf.extend_from_slice(body);
+
+        self.frame_packet(start_len, id)
+    }
+
+    /// Adds the length prefix to the packet data after `start_len` and
+    /// compresses it if necessary.
+    fn frame_packet(&mut self, start_len: usize, id: i32) -> anyhow::Result<()> {
         let data_len = self.buf.len() - start_len;
 
+        #[cfg(not(feature = "compression"))]
+        let _ = id;
+
         #[cfg(feature = "compression")]
         if self.threshold.0 >= 0 {
             use std::io::Read;
+            use std::time::Instant;
 
             use flate2::bufread::ZlibEncoder;
             use flate2::Compression;
 
-            if data_len > self.threshold.0 as usize {
+            if data_len > self.threshold.0 as usize
+                && data_len > self.min_compressed_len
+                && !self.uncompressed_ids.contains(&id)
+            {
+                let start = self.stats.is_some().then(Instant::now);
+
                 let mut z = ZlibEncoder::new(&self.buf[start_len..], Compression::new(4));
 
                 self.compress_buf.clear();
@@ -91,6 +228,10 @@ impl PacketEncoder {
 
                 drop(z);
 
+                if let (Some(stats), Some(start)) = (&mut self.stats, start) {
+                    stats.record_compressed(id, data_len, self.compress_buf.len(), start.elapsed());
+                }
+
                 self.buf.truncate(start_len);
 
                 let mut writer = (&mut self.buf).writer();
@@ -120,6 +261,10 @@ impl PacketEncoder {
                 VarInt(packet_len as i32).encode(&mut front)?;
                 // Zero for no compression on this packet.
                 VarInt(0).encode(front)?;
+
+                if let Some(stats) = &mut self.stats {
+                    stats.record_uncompressed(id, data_len);
+                }
             }
 
             return Ok(());
@@ -167,6 +312,57 @@ impl PacketEncoder {
         self.threshold = threshold;
     }
 
+    /// Returns the length of packet data at and below which packets are sent
+    /// uncompressed even if they exceed the compression threshold.
+    pub fn min_compressed_len(&self) -> usize {
+        self.min_compressed_len
+    }
+
+    /// Sets the length of packet data at and below which packets are sent
+    /// uncompressed even if they exceed the compression threshold. Since
+    /// uncompressed packets of any size are valid, this can be changed at any
+    /// time without telling the client.
+    pub fn set_min_compressed_len(&mut self, len: usize) {
+        self.min_compressed_len = len;
+    }
+
+    /// Returns whether packets with the given ID are compressed when they
+    /// exceed the threshold.
+    pub fn compresses_packet(&self, id: i32) -> bool {
+        !self.uncompressed_ids.contains(&id)
+    }
+
+    /// Sets whether packets with the given ID are compressed when they exceed
+    /// the threshold. Useful for packets whose data doesn't compress well.
+    pub fn set_compresses_packet(&mut self, id: i32, compress: bool) {
+        if compress {
+            self.uncompressed_ids.remove(&id);
+        } else {
+            self.uncompressed_ids.insert(id);
+        }
+    }
+
+    /// Starts recording [`CompressionStats`] for the packets appended to this
+    /// encoder. Raw bytes added with [`append_bytes`](Self::append_bytes) are
+    /// not recorded.
+    pub fn enable_compression_stats(&mut self) {
+        if self.stats.is_none() {
+            self.stats = Some(CompressionStats::default());
+        }
+    }
+
+    /// Returns the stats recorded since they were last taken, or `None` if
+    /// recording isn't enabled.
+    pub fn compression_stats(&self) -> Option<&CompressionStats> {
+        self.stats.as_ref()
+    }
+
+    /// Takes the stats recorded so far and starts recording anew. Returns
+    /// `None` if recording isn't enabled.
+    pub fn take_compression_stats(&mut self) -> Option<CompressionStats> {
+        self.stats.as_mut().map(std::mem::take)
+    }
+
     /// Initializes the cipher with the given key. All future packets **and any
     /// that have not been [taken] yet** are encrypted.
     ///
@@ -182,6 +378,122 @@ impl PacketEncoder {
     }
 }
 
+/// Statistics on the packets written by a [`PacketEncoder`], grouped by packet
+/// ID. Only packets written while compression is enabled are recorded.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct CompressionStats {
+    classes: BTreeMap<i32, PacketClassStats>,
+}
+
+/// Statistics on the packets with a single ID. See [`CompressionStats`].
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct PacketClassStats {
+    /// The number of packets written.
+    pub packets: u64,
+    /// The total length of the data of all packets, before compression.
+    pub bytes: u64,
+    /// The number of packets which were compressed.
+    pub compressed_packets: u64,
+    /// The total length of the data of the compressed packets before
+    /// compression.
+    pub compressed_input_bytes: u64,
+    /// The total length of the data of the compressed packets after
+    /// compression.
+    pub compressed_output_bytes: u64,
+    /// The time spent compressing.
+    pub compress_time: Duration,
+}
+
+impl PacketClassStats {
+    /// Returns the size of the compressed packets after compression relative to
+    /// their size before, or `None` if no packets were compressed. Lower is
+    /// better, and values close to or above one mean compression is a waste.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.compressed_input_bytes > 0)
+            .then(|| self.compressed_output_bytes as f64 / self.compressed_input_bytes as f64)
+    }
+
+    /// Returns the average time spent compressing one byte of packet data, or
+    /// `None` if no packets were compressed.
+    pub fn time_per_byte(&self) -> Option<Duration> {
+        (self.compressed_input_bytes > 0).then(|| {
+            Duration::from_secs_f64(
+                self.compress_time.as_secs_f64() / self.compressed_input_bytes as f64,
+            )
+        })
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+        self.compressed_packets += other.compressed_packets;
+        self.compressed_input_bytes += other.compressed_input_bytes;
+        self.compressed_output_bytes += other.compressed_output_bytes;
+        self.compress_time += other.compress_time;
+    }
+}
+
+impl CompressionStats {
+    /// Returns the stats of the packets with the given ID.
+    pub fn get(&self, id: i32) -> Option<&PacketClassStats> {
+        self.classes.get(&id)
+    }
+
+    /// Returns an iterator over the packet IDs and their stats, ordered by ID.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, &PacketClassStats)> + '_ {
+        self.classes.iter().map(|(id, stats)| (*id, stats))
+    }
+
+    /// Returns the stats of all packets combined.
+    pub fn total(&self) -> PacketClassStats {
+        let mut total = PacketClassStats::default();
+
+        for stats in self.classes.values() {
+            total.merge(stats);
+        }
+
+        total
+    }
+
+    /// Adds `stats` to the stats of the packets with the given ID.
+    pub fn add(&mut self, id: i32, stats: &PacketClassStats) {
+        self.classes.entry(id).or_default().merge(stats);
+    }
+
+    /// Adds the stats in `other` to these.
+    pub fn merge(&mut self, other: &Self) {
+        for (id, stats) in &other.classes {
+            self.classes.entry(*id).or_default().merge(stats);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.classes.clear();
+    }
+
+    #[cfg(feature = "compression")]
+    fn record_uncompressed(&mut self, id: i32, len: usize) {
+        let stats = self.classes.entry(id).or_default();
+        stats.packets += 1;
+        stats.bytes += len as u64;
+    }
+
+    #[cfg(feature = "compression")]
+    fn record_compressed(&mut self, id: i32, len: usize, compressed_len: usize, time: Duration) {
+        let stats = self.classes.entry(id).or_default();
+        stats.packets += 1;
+        stats.bytes += len as u64;
+        stats.compressed_packets += 1;
+        stats.compressed_input_bytes += len as u64;
+        stats.compressed_output_bytes += compressed_len as u64;
+        stats.compress_time += time;
+    }
+}
+
 /// Types that can have packets written to them.
 pub trait WritePacket {
     /// Writes a packet to this object. Encoding errors are typically logged and
@@ -50,12 +50,14 @@ pub mod packets;
 pub mod profile;
 mod raw;
 pub mod sound;
+pub mod translate;
 pub mod var_int;
 mod var_long;
 mod velocity;
 
 use std::io::Write;
 
+pub use anyhow;
 use anyhow::Context;
 pub use array::FixedArray;
 pub use biome_pos::BiomePos;
@@ -64,6 +66,7 @@ pub use block::{BlockKind, BlockState};
 pub use block_pos::BlockPos;
 pub use bounded::Bounded;
 pub use byte_angle::ByteAngle;
+pub use bytes;
 pub use chunk_pos::ChunkPos;
 pub use chunk_section_pos::ChunkSectionPos;
 pub use decode::PacketDecoder;
@@ -81,16 +84,17 @@ pub use raw::RawBytes;
 use serde::{Deserialize, Serialize};
 pub use sound::Sound;
 pub use text::Text;
+pub use uuid;
 pub use valence_generated::{block, packet_id, status_effects};
+pub use valence_ident as ident;
 pub use valence_ident::Ident;
+pub use valence_math as math;
+pub use valence_nbt as nbt;
 pub use valence_protocol_macros::{Decode, Encode, Packet};
+pub use valence_text as text;
 pub use var_int::VarInt;
 pub use var_long::VarLong;
 pub use velocity::Velocity;
-pub use {
-    anyhow, bytes, uuid, valence_ident as ident, valence_math as math, valence_nbt as nbt,
-    valence_text as text,
-};
 
 /// The maximum number of bytes in a single Minecraft packet.
 pub const MAX_PACKET_SIZE: i32 = 2097152;
@@ -470,4 +474,40 @@ mod tests {
         check_test_packet(&mut dec, "fourth");
         check_test_packet(&mut dec, "third");
     }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compression_stats_and_overrides() {
+        let mut enc = PacketEncoder::new();
+        enc.set_compression(0.into());
+        enc.enable_compression_stats();
+
+        enc.append_packet(&TestPacket::new("compressed")).unwrap();
+
+        enc.set_compresses_packet(TestPacket::ID, false);
+        enc.append_packet(&TestPacket::new("not compressed"))
+            .unwrap();
+
+        enc.set_compresses_packet(TestPacket::ID, true);
+        enc.set_min_compressed_len(usize::MAX);
+        enc.append_packet(&TestPacket::new("also not compressed"))
+            .unwrap();
+
+        let stats = enc.take_compression_stats().unwrap();
+        let class = stats.get(TestPacket::ID).unwrap();
+
+        assert_eq!(class.packets, 3);
+        assert_eq!(class.compressed_packets, 1);
+        assert!(class.ratio().unwrap() > 0.0);
+        assert_eq!(stats.total(), *class);
+        assert!(enc.compression_stats().unwrap().is_empty());
+
+        let mut dec = PacketDecoder::new();
+        dec.set_compression(0.into());
+        dec.queue_bytes(enc.take());
+
+        check_test_packet(&mut dec, "compressed");
+        check_test_packet(&mut dec, "not compressed");
+        check_test_packet(&mut dec, "also not compressed");
+    }
 }
@@ -14,15 +14,45 @@ pub struct Property<S = String> {
 
 /// Contains URLs to the skin and cape of a player.
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
 pub struct PlayerTextures {
     /// URL to the player's skin texture.
     pub skin: Url,
     /// URL to the player's cape texture. May be absent if the player does not
     /// have a cape.
     pub cape: Option<Url>,
+    /// The arm width the skin is drawn for.
+    pub model: SkinModel,
+}
+
+/// The player model a skin is drawn for, which differs in the width of the
+/// arms.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum SkinModel {
+    /// The "Steve" model with 4 pixel wide arms.
+    #[default]
+    Classic,
+    /// The "Alex" model with 3 pixel wide arms.
+    Slim,
 }
 
 impl PlayerTextures {
+    /// Creates textures with the given skin and cape, with the skin drawn for
+    /// the [classic](SkinModel::Classic) model.
+    pub fn new(skin: Url, cape: Option<Url>) -> Self {
+        Self {
+            skin,
+            cape,
+            model: SkinModel::Classic,
+        }
+    }
+
+    /// Sets the model the skin is drawn for.
+    pub fn with_model(mut self, model: SkinModel) -> Self {
+        self.model = model;
+        self
+    }
+
     /// Constructs player textures from the "textures" property of the game
     /// profile.
     ///
@@ -44,15 +74,28 @@ impl PlayerTextures {
         #[derive(Debug, Deserialize)]
         struct TextureUrl {
             url: Url,
+            #[serde(default)]
+            metadata: Option<TextureMetadata>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct TextureMetadata {
+            model: String,
         }
 
         let decoded = BASE64_STANDARD.decode(textures.as_bytes())?;
 
         let Textures { textures } = serde_json::from_slice(&decoded)?;
 
+        let model = match textures.skin.metadata {
+            Some(metadata) if metadata.model == "slim" => SkinModel::Slim,
+            _ => SkinModel::Classic,
+        };
+
         Ok(Self {
             skin: textures.skin.url,
             cape: textures.cape.map(|t| t.url),
+            model,
         })
     }
 }
@@ -0,0 +1,371 @@
+//! Translating packets between [`PROTOCOL_VERSION`] and the protocol versions
+//! of other clients.
+//!
+//! A [`PacketTranslator`] rewrites the packets of a single other protocol
+//! version. Once it is [set](PacketEncoder::set_translator) on the encoder of
+//! a connection, every clientbound packet is translated before it is framed,
+//! including raw packet bytes shared between clients. Serverbound packets are
+//! translated by whoever decodes them, since only they know the state of the
+//! connection.
+//!
+//! Translators are responsible for everything that differs between the
+//! versions: packet IDs (see [`PacketIdMap`]), packet layouts, the registry
+//! codec, and the IDs of blocks, items and entity data in packet bodies.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use valence_nbt::Compound;
+
+use crate::decode::PacketFrame;
+#[cfg(doc)]
+use crate::encode::PacketEncoder;
+use crate::{PacketState, PROTOCOL_VERSION};
+
+/// Translates packets between [`PROTOCOL_VERSION`] and another protocol
+/// version.
+pub trait PacketTranslator: Send + Sync + 'static {
+    /// The protocol version of the clients this translates for.
+    fn protocol_version(&self) -> i32;
+
+    /// The Minecraft version of [`protocol_version`], like `"1.19.4"`.
+    ///
+    /// [`protocol_version`]: Self::protocol_version
+    fn version_name(&self) -> &str;
+
+    /// Rewrites a clientbound packet of [`PROTOCOL_VERSION`] in place for the
+    /// client. Returns whether the packet should be sent at all.
+    ///
+    /// # Default Implementation
+    ///
+    /// The packet is sent unchanged.
+    fn clientbound(&self, state: PacketState, frame: &mut PacketFrame) -> anyhow::Result<bool> {
+        #![allow(unused_variables)]
+
+        Ok(true)
+    }
+
+    /// Rewrites a serverbound packet of the client in place to the packet of
+    /// [`PROTOCOL_VERSION`]. Returns whether the packet should be received at
+    /// all.
+    ///
+    /// # Default Implementation
+    ///
+    /// The packet is received unchanged.
+    fn serverbound(&self, state: PacketState, frame: &mut PacketFrame) -> anyhow::Result<bool> {
+        #![allow(unused_variables)]
+
+        Ok(true)
+    }
+
+    /// Changes the registry codec sent to the client when it joins, such as
+    /// to remove entries its version doesn't know.
+    ///
+    /// # Default Implementation
+    ///
+    /// The codec is sent unchanged.
+    fn registry_codec(&self, codec: &mut Compound) {
+        #![allow(unused_variables)]
+    }
+}
+
+/// The packet IDs which differ between [`PROTOCOL_VERSION`] and another
+/// protocol version. Packets without a mapping keep their ID.
+///
+/// # Examples
+///
+/// ```
+/// use valence_protocol::translate::PacketIdMap;
+/// use valence_protocol::PacketState;
+///
+/// let ids = PacketIdMap::new()
+///     .with_clientbound(PacketState::Play, 0x10, 0x11)
+///     .without_clientbound(PacketState::Play, 0x12)
+///     .with_serverbound(PacketState::Play, 0x05, 0x04);
+///
+/// assert_eq!(ids.clientbound(PacketState::Play, 0x10), Some(0x11));
+/// assert_eq!(ids.clientbound(PacketState::Play, 0x12), None);
+/// assert_eq!(ids.clientbound(PacketState::Login, 0x10), Some(0x10));
+/// assert_eq!(ids.serverbound(PacketState::Play, 0x05), Some(0x04));
+/// ```
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct PacketIdMap {
+    clientbound: HashMap<(PacketState, i32), Option<i32>>,
+    serverbound: HashMap<(PacketState, i32), Option<i32>>,
+}
+
+impl PacketIdMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the clientbound packet with the server's ID `id` to the client
+    /// with the ID `client_id`.
+    pub fn with_clientbound(mut self, state: PacketState, id: i32, client_id: i32) -> Self {
+        self.clientbound.insert((state, id), Some(client_id));
+        self
+    }
+
+    /// Drops the clientbound packet with the server's ID `id`, for packets
+    /// the client doesn't know.
+    pub fn without_clientbound(mut self, state: PacketState, id: i32) -> Self {
+        self.clientbound.insert((state, id), None);
+        self
+    }
+
+    /// Receives the serverbound packet with the client's ID `client_id` as
+    /// the packet with the server's ID `id`.
+    pub fn with_serverbound(mut self, state: PacketState, client_id: i32, id: i32) -> Self {
+        self.serverbound.insert((state, client_id), Some(id));
+        self
+    }
+
+    /// Drops the serverbound packet with the client's ID `client_id`, for
+    /// packets the server doesn't know.
+    pub fn without_serverbound(mut self, state: PacketState, client_id: i32) -> Self {
+        self.serverbound.insert((state, client_id), None);
+        self
+    }
+
+    /// Returns the client's ID of the clientbound packet with the server's ID
+    /// `id`, or `None` if it is dropped.
+    pub fn clientbound(&self, state: PacketState, id: i32) -> Option<i32> {
+        self.clientbound
+            .get(&(state, id))
+            .copied()
+            .unwrap_or(Some(id))
+    }
+
+    /// Returns the server's ID of the serverbound packet with the client's ID
+    /// `client_id`, or `None` if it is dropped.
+    pub fn serverbound(&self, state: PacketState, client_id: i32) -> Option<i32> {
+        self.serverbound
+            .get(&(state, client_id))
+            .copied()
+            .unwrap_or(Some(client_id))
+    }
+
+    /// Maps the ID of a clientbound packet in place. Returns whether the
+    /// packet should be sent.
+    pub fn translate_clientbound(&self, state: PacketState, frame: &mut PacketFrame) -> bool {
+        match self.clientbound(state, frame.id) {
+            Some(id) => {
+                frame.id = id;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Maps the ID of a serverbound packet in place. Returns whether the
+    /// packet should be received.
+    pub fn translate_serverbound(&self, state: PacketState, frame: &mut PacketFrame) -> bool {
+        match self.serverbound(state, frame.id) {
+            Some(id) => {
+                frame.id = id;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The [`PacketTranslator`]s of a server, by protocol version.
+#[derive(Clone, Default)]
+pub struct PacketTranslators {
+    by_protocol: BTreeMap<i32, Arc<dyn PacketTranslator>>,
+}
+
+impl PacketTranslators {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a translator, replacing the previous translator for the same
+    /// protocol version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the translator is for [`PROTOCOL_VERSION`].
+    pub fn insert(&mut self, translator: impl PacketTranslator) {
+        self.insert_arc(Arc::new(translator));
+    }
+
+    /// Like [`insert`](Self::insert), for translators which are already
+    /// shared.
+    pub fn insert_arc(&mut self, translator: Arc<dyn PacketTranslator>) {
+        let protocol = translator.protocol_version();
+
+        assert_ne!(
+            protocol, PROTOCOL_VERSION,
+            "packets of protocol {PROTOCOL_VERSION} don't need translating"
+        );
+
+        self.by_protocol.insert(protocol, translator);
+    }
+
+    pub fn remove(&mut self, protocol: i32) -> Option<Arc<dyn PacketTranslator>> {
+        self.by_protocol.remove(&protocol)
+    }
+
+    /// Returns the translator for clients with the protocol version
+    /// `protocol`.
+    pub fn get(&self, protocol: i32) -> Option<&Arc<dyn PacketTranslator>> {
+        self.by_protocol.get(&protocol)
+    }
+
+    pub fn contains(&self, protocol: i32) -> bool {
+        self.by_protocol.contains_key(&protocol)
+    }
+
+    /// Returns an iterator over the protocol versions with a translator, in
+    /// ascending order.
+    pub fn protocols(&self) -> impl Iterator<Item = i32> + '_ {
+        self.by_protocol.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_protocol.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_protocol.is_empty()
+    }
+}
+
+/// Translators are equal if they are the same translators for the same
+/// protocol versions.
+impl PartialEq for PacketTranslators {
+    fn eq(&self, other: &Self) -> bool {
+        self.by_protocol.len() == other.by_protocol.len()
+            && self
+                .by_protocol
+                .iter()
+                .zip(&other.by_protocol)
+                .all(|((a, a_t), (b, b_t))| a == b && Arc::ptr_eq(a_t, b_t))
+    }
+}
+
+impl fmt::Debug for PacketTranslators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.by_protocol
+                    .iter()
+                    .map(|(protocol, t)| (protocol, t.version_name())),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BufMut;
+
+    use super::*;
+    use crate::decode::PacketDecoder;
+    use crate::encode::PacketEncoder;
+    use crate::packets::play::{KeepAliveS2c, PlayPingS2c};
+    use crate::Packet;
+
+    /// Moves keep alive packets to another ID and adds a byte to them, and
+    /// drops ping packets.
+    struct TestTranslator {
+        ids: PacketIdMap,
+    }
+
+    impl TestTranslator {
+        fn new() -> Self {
+            Self {
+                ids: PacketIdMap::new()
+                    .with_clientbound(PacketState::Play, KeepAliveS2c::ID, 0x7f)
+                    .without_clientbound(PacketState::Play, PlayPingS2c::ID),
+            }
+        }
+    }
+
+    impl PacketTranslator for TestTranslator {
+        fn protocol_version(&self) -> i32 {
+            PROTOCOL_VERSION - 1
+        }
+
+        fn version_name(&self) -> &str {
+            "test"
+        }
+
+        fn clientbound(&self, state: PacketState, frame: &mut PacketFrame) -> anyhow::Result<bool> {
+            if !self.ids.translate_clientbound(state, frame) {
+                return Ok(false);
+            }
+
+            if frame.id == 0x7f {
+                frame.body.put_u8(42);
+            }
+
+            Ok(true)
+        }
+    }
+
+    fn decode_all(enc: &mut PacketEncoder) -> Vec<PacketFrame> {
+        let mut dec = PacketDecoder::new();
+        dec.queue_bytes(enc.take());
+
+        let mut frames = vec![];
+
+        while let Some(frame) = dec.try_next_packet().unwrap() {
+            frames.push(frame);
+        }
+
+        frames
+    }
+
+    #[test]
+    fn translate_clientbound_packets() {
+        let mut raw = PacketEncoder::new();
+        raw.append_packet(&KeepAliveS2c { id: 5 }).unwrap();
+        raw.append_packet(&PlayPingS2c { id: 6 }).unwrap();
+        let raw = raw.take();
+
+        let mut enc = PacketEncoder::new();
+        enc.set_translator(Some(Arc::new(TestTranslator::new())), PacketState::Play);
+
+        assert_eq!(enc.protocol_version(), PROTOCOL_VERSION - 1);
+
+        enc.append_packet(&KeepAliveS2c { id: 1 }).unwrap();
+        enc.append_packet(&PlayPingS2c { id: 2 }).unwrap();
+        enc.append_bytes(&raw);
+
+        let frames = decode_all(&mut enc);
+
+        assert_eq!(frames.len(), 2);
+
+        for (frame, id) in frames.iter().zip([1_u64, 5]) {
+            assert_eq!(frame.id, 0x7f);
+            assert_eq!(&frame.body[..8], id.to_be_bytes());
+            assert_eq!(&frame.body[8..], [42]);
+        }
+
+        // Removing the translator sends packets unchanged.
+        enc.set_translator(None, PacketState::Play);
+        enc.append_bytes(&raw);
+
+        assert_eq!(decode_all(&mut enc).len(), 2);
+        assert_eq!(enc.protocol_version(), PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn translators_by_protocol() {
+        let mut translators = PacketTranslators::new();
+        translators.insert(TestTranslator::new());
+
+        assert!(translators.contains(PROTOCOL_VERSION - 1));
+        assert!(translators.get(PROTOCOL_VERSION).is_none());
+        assert_eq!(
+            translators.protocols().collect::<Vec<_>>(),
+            [PROTOCOL_VERSION - 1]
+        );
+        assert_eq!(translators, translators.clone());
+        assert_ne!(translators, PacketTranslators::new());
+    }
+}
@@ -9,14 +9,23 @@ use crate::{Decode, Encode};
 pub struct Velocity(pub [i16; 3]);
 
 impl Velocity {
+    /// The fastest velocity in any direction, in blocks per tick. Faster
+    /// velocities are clamped to it like in vanilla.
+    pub const MAX_BLOCKS_PER_TICK: f64 = 3.9;
+
     /// From meters/second.
     pub fn from_ms_f32(ms: [f32; 3]) -> Self {
-        Self(ms.map(|v| (8000.0 / 20.0 * v) as i16))
+        Self(ms.map(|v| quantize((8000.0 / 20.0 * v) as f64)))
     }
 
     /// From meters/second.
     pub fn from_ms_f64(ms: [f64; 3]) -> Self {
-        Self(ms.map(|v| (8000.0 / 20.0 * v) as i16))
+        Self(ms.map(|v| quantize(8000.0 / 20.0 * v)))
+    }
+
+    /// From blocks per tick, the unit of velocity in vanilla.
+    pub fn from_blocks_per_tick(bpt: [f64; 3]) -> Self {
+        Self(bpt.map(|v| quantize(8000.0 * v)))
     }
 
     /// To meters/second.
@@ -28,6 +37,17 @@ impl Velocity {
     pub fn to_ms_f64(self) -> [f64; 3] {
         self.0.map(|v| v as f64 / (8000.0 / 20.0))
     }
+
+    /// To blocks per tick.
+    pub fn to_blocks_per_tick(self) -> [f64; 3] {
+        self.0.map(|v| v as f64 / 8000.0)
+    }
+}
+
+/// Converts a velocity in 1/8000 blocks per tick to the packet encoding.
+fn quantize(units: f64) -> i16 {
+    let max = Velocity::MAX_BLOCKS_PER_TICK * 8000.0;
+    units.clamp(-max, max) as i16
 }
 
 impl fmt::Debug for Velocity {
@@ -52,3 +72,19 @@ fn velocity_from_ms() {
     assert_eq!(val_1, val_2);
     assert_eq!(val_1, -1343);
 }
+
+#[cfg(test)]
+#[test]
+fn velocity_is_clamped() {
+    let max = (Velocity::MAX_BLOCKS_PER_TICK * 8000.0) as i16;
+
+    assert_eq!(
+        Velocity::from_blocks_per_tick([10.0, -10.0, f64::NAN]).0,
+        [max, -max, 0]
+    );
+    assert_eq!(Velocity::from_ms_f32([20.0, 0.0, 0.0]).0, [8000, 0, 0]);
+    assert_eq!(
+        Velocity::from_blocks_per_tick([0.5, 0.0, 0.0]).to_blocks_per_tick(),
+        [0.5, 0.0, 0.0]
+    );
+}
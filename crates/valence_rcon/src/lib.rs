@@ -0,0 +1,52 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod query;
+mod rcon;
+
+pub use query::{QueryPlugin, QuerySettings};
+pub use rcon::{RconExecutor, RconPlugin, RconSettings};
+
+/// Removes the [legacy formatting codes](https://minecraft.wiki/w/Formatting_codes)
+/// from a string, since RCON and query clients show text as it is.
+fn strip_formatting(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_legacy_formatting() {
+        assert_eq!(strip_formatting("§6Gold§r and §lbold"), "Gold and bold");
+        assert_eq!(strip_formatting("trailing §"), "trailing ");
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use tracing::{error, info};
+use valence_server::client::{ClientMarker, Username};
+use valence_server::MINECRAFT_VERSION;
+
+use crate::strip_formatting;
+
+/// Answers requests of the UDP query protocol. See the crate documentation for
+/// more information.
+pub struct QueryPlugin;
+
+impl Plugin for QueryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QuerySettings>()
+            .add_systems(PostStartup, start_query_listener)
+            .add_systems(
+                PostUpdate,
+                publish_query_status.run_if(resource_exists::<SharedQueryStatus>()),
+            );
+    }
+}
+
+/// Settings for [`QueryPlugin`]. Changes to the address have no effect after
+/// startup, but the other settings can be changed at any time.
+#[derive(Resource, Clone, Debug)]
+pub struct QuerySettings {
+    /// The address to answer query requests on, or `None` to disable the
+    /// query protocol. This is usually the address of the server.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub address: Option<SocketAddr>,
+    /// The message of the day. Formatting codes are removed.
+    pub motd: String,
+    /// The name of the world.
+    ///
+    /// # Default Value
+    ///
+    /// `"world"`
+    pub map: String,
+    /// The maximum number of players.
+    ///
+    /// # Default Value
+    ///
+    /// `20`
+    pub max_players: usize,
+}
+
+impl Default for QuerySettings {
+    fn default() -> Self {
+        Self {
+            address: None,
+            motd: "A Valence Server".into(),
+            map: "world".into(),
+            max_players: 20,
+        }
+    }
+}
+
+/// What query responses are built from. Published every tick, since the
+/// world can't be accessed from the query thread.
+#[derive(Clone, Default, Debug)]
+struct QueryStatus {
+    motd: String,
+    map: String,
+    max_players: usize,
+    players: Vec<String>,
+}
+
+#[derive(Resource, Clone)]
+struct SharedQueryStatus(Arc<RwLock<QueryStatus>>);
+
+const TYPE_STAT: u8 = 0;
+const TYPE_HANDSHAKE: u8 = 9;
+
+/// How long challenge tokens are valid for, like in vanilla.
+const CHALLENGE_LIFETIME: Duration = Duration::from_secs(30);
+
+fn start_query_listener(settings: Res<QuerySettings>, mut commands: Commands) {
+    let Some(address) = settings.address else {
+        return;
+    };
+
+    let socket = match UdpSocket::bind(address) {
+        Ok(socket) => socket,
+        Err(e) => {
+            error!("failed to start query listener on {address}: {e}");
+            return;
+        }
+    };
+
+    info!("query listening on {address}");
+
+    let status = SharedQueryStatus(Arc::default());
+    let thread_status = status.0.clone();
+
+    thread::Builder::new()
+        .name("query listener".into())
+        .spawn(move || query_loop(socket, address, &thread_status))
+        .expect("failed to spawn query listener thread");
+
+    commands.insert_resource(status);
+}
+
+fn publish_query_status(
+    settings: Res<QuerySettings>,
+    clients: Query<&Username, With<ClientMarker>>,
+    status: Res<SharedQueryStatus>,
+) {
+    let mut status = status.0.write().unwrap();
+
+    status.motd = strip_formatting(&settings.motd);
+    status.map.clone_from(&settings.map);
+    status.max_players = settings.max_players;
+    status.players.clear();
+    status
+        .players
+        .extend(clients.iter().map(|name| name.0.clone()));
+}
+
+fn query_loop(socket: UdpSocket, address: SocketAddr, status: &RwLock<QueryStatus>) {
+    let mut challenges = HashMap::<SocketAddr, (i32, Instant)>::new();
+    let mut buf = [0; 1460];
+
+    loop {
+        let Ok((len, from)) = socket.recv_from(&mut buf) else {
+            continue;
+        };
+
+        challenges.retain(|_, (_, issued)| issued.elapsed() < CHALLENGE_LIFETIME);
+
+        let response = handle_request(&buf[..len], from, &mut challenges, || {
+            (status.read().unwrap().clone(), address)
+        });
+
+        if let Some(response) = response {
+            let _ = socket.send_to(&response, from);
+        }
+    }
+}
+
+/// Returns the response to a query request, or `None` if the request is
+/// invalid.
+fn handle_request(
+    request: &[u8],
+    from: SocketAddr,
+    challenges: &mut HashMap<SocketAddr, (i32, Instant)>,
+    status: impl FnOnce() -> (QueryStatus, SocketAddr),
+) -> Option<Vec<u8>> {
+    let [0xfe, 0xfd, kind, s0, s1, s2, s3, ref rest @ ..] = *request else {
+        return None;
+    };
+
+    let session = [s0, s1, s2, s3];
+
+    let mut response = vec![kind];
+    response.extend_from_slice(&session);
+
+    match kind {
+        TYPE_HANDSHAKE => {
+            let token = rand::random::<i32>() & 0x7fff_ffff;
+            challenges.insert(from, (token, Instant::now()));
+
+            write_str(&mut response, &token.to_string());
+        }
+        TYPE_STAT => {
+            let token = i32::from_be_bytes(rest.get(..4)?.try_into().unwrap());
+
+            if challenges.get(&from).map(|&(t, _)| t) != Some(token) {
+                return None;
+            }
+
+            let (status, address) = status();
+
+            // Full stat requests are padded to 8 bytes after the session ID.
+            if rest.len() >= 8 {
+                write_full_stat(&mut response, &status, address);
+            } else {
+                write_basic_stat(&mut response, &status, address);
+            }
+        }
+        _ => return None,
+    }
+
+    Some(response)
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+}
+
+fn write_basic_stat(buf: &mut Vec<u8>, status: &QueryStatus, address: SocketAddr) {
+    write_str(buf, &status.motd);
+    write_str(buf, "SMP");
+    write_str(buf, &status.map);
+    write_str(buf, &status.players.len().to_string());
+    write_str(buf, &status.max_players.to_string());
+    buf.extend_from_slice(&address.port().to_le_bytes());
+    write_str(buf, &address.ip().to_string());
+}
+
+fn write_full_stat(buf: &mut Vec<u8>, status: &QueryStatus, address: SocketAddr) {
+    buf.extend_from_slice(b"splitnum\0\x80\0");
+
+    for (key, value) in [
+        ("hostname", status.motd.clone()),
+        ("gametype", "SMP".into()),
+        ("game_id", "MINECRAFT".into()),
+        ("version", MINECRAFT_VERSION.into()),
+        ("plugins", "".into()),
+        ("map", status.map.clone()),
+        ("numplayers", status.players.len().to_string()),
+        ("maxplayers", status.max_players.to_string()),
+        ("hostport", address.port().to_string()),
+        ("hostip", address.ip().to_string()),
+    ] {
+        write_str(buf, key);
+        write_str(buf, &value);
+    }
+
+    buf.push(0);
+    buf.extend_from_slice(b"\x01player_\0\0");
+
+    for player in &status.players {
+        write_str(buf, player);
+    }
+
+    buf.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> (QueryStatus, SocketAddr) {
+        let status = QueryStatus {
+            motd: "Hello".into(),
+            map: "world".into(),
+            max_players: 10,
+            players: vec!["alice".into(), "bob".into()],
+        };
+
+        (status, "127.0.0.1:25565".parse().unwrap())
+    }
+
+    #[test]
+    fn query_handshake_and_stats() {
+        let from = "10.0.0.1:5000".parse().unwrap();
+        let mut challenges = HashMap::new();
+
+        // Stat requests without a challenge token are ignored.
+        let request = [0xfe, 0xfd, TYPE_STAT, 0, 0, 0, 1, 0, 0, 0, 0];
+        assert!(handle_request(&request, from, &mut challenges, status).is_none());
+
+        let request = [0xfe, 0xfd, TYPE_HANDSHAKE, 0, 0, 0, 1];
+        let response = handle_request(&request, from, &mut challenges, status).unwrap();
+        assert_eq!(&response[..5], &[TYPE_HANDSHAKE, 0, 0, 0, 1]);
+
+        let token: i32 = std::str::from_utf8(&response[5..response.len() - 1])
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        let mut request = vec![0xfe, 0xfd, TYPE_STAT, 0, 0, 0, 1];
+        request.extend_from_slice(&token.to_be_bytes());
+
+        let basic = handle_request(&request, from, &mut challenges, status).unwrap();
+        let mut expected = vec![TYPE_STAT, 0, 0, 0, 1];
+        expected.extend_from_slice(b"Hello\0SMP\0world\x002\x0010\0");
+        expected.extend_from_slice(&25565u16.to_le_bytes());
+        expected.extend_from_slice(b"127.0.0.1\0");
+        assert_eq!(basic, expected);
+
+        request.extend_from_slice(&[0; 4]);
+
+        let full = handle_request(&request, from, &mut challenges, status).unwrap();
+        assert!(full.starts_with(&[TYPE_STAT, 0, 0, 0, 1]));
+        assert!(full.ends_with(b"\x01player_\0\0alice\0bob\0\0"));
+
+        let full = String::from_utf8_lossy(&full);
+        assert!(full.contains("numplayers\x002\0"));
+        assert!(full.contains(&format!("version\0{MINECRAFT_VERSION}\0")));
+
+        // Tokens are tied to the address they were issued to.
+        let other = "10.0.0.2:5000".parse().unwrap();
+        assert!(handle_request(&request, other, &mut challenges, status).is_none());
+    }
+}
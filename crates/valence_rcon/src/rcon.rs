@@ -0,0 +1,390 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use flume::{Receiver, Sender};
+use tracing::{error, info, warn};
+use valence_command::scopes::CommandScopes;
+use valence_command::{CommandExecutionEvent, CommandProcessedEvent};
+use valence_server::text::IntoText;
+
+use crate::strip_formatting;
+
+/// Accepts RCON connections and executes their commands. See the crate
+/// documentation for more information.
+pub struct RconPlugin;
+
+impl Plugin for RconPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RconSettings>()
+            .add_systems(PostStartup, start_listener)
+            .add_systems(
+                PreUpdate,
+                execute_requests.run_if(resource_exists::<RconRequests>()),
+            )
+            .add_systems(PostUpdate, reply_to_requests);
+    }
+}
+
+/// Settings for [`RconPlugin`]. Changes have no effect after startup.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct RconSettings {
+    /// The address to accept RCON connections on, or `None` to disable RCON.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub address: Option<SocketAddr>,
+    /// The password RCON clients must log in with. RCON is disabled if the
+    /// password is empty, like in vanilla.
+    pub password: String,
+}
+
+/// The executor of a command sent by an RCON client. Messages sent to it are
+/// returned to the client once the tick is over, and the entity is despawned.
+///
+/// The executor has the `root` [`CommandScopes`], so it may execute every
+/// command.
+#[derive(Component, Debug)]
+pub struct RconExecutor {
+    output: String,
+    reply: Sender<String>,
+}
+
+impl RconExecutor {
+    /// Adds a line to the output returned to the RCON client. Formatting is
+    /// removed.
+    pub fn send_message<'a>(&mut self, msg: impl IntoText<'a>) {
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        self.output
+            .push_str(&strip_formatting(&msg.into_text().to_legacy_lossy()));
+    }
+
+    /// Returns the output so far.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+}
+
+/// A command received by a connection thread.
+struct RconRequest {
+    command: String,
+    reply: Sender<String>,
+}
+
+#[derive(Resource)]
+struct RconRequests(Receiver<RconRequest>);
+
+const TYPE_RESPONSE: i32 = 0;
+const TYPE_EXEC_COMMAND: i32 = 2;
+const TYPE_AUTH_RESPONSE: i32 = 2;
+const TYPE_LOGIN: i32 = 3;
+
+/// The largest packet accepted from clients, like in vanilla.
+const MAX_INCOMING_PACKET: usize = 1460;
+/// The largest body of a response packet. Longer responses are split.
+const MAX_RESPONSE_BODY: usize = 4096;
+
+fn start_listener(settings: Res<RconSettings>, mut commands: Commands) {
+    let Some(address) = settings.address else {
+        return;
+    };
+
+    if settings.password.is_empty() {
+        warn!("RCON is disabled because the password is empty");
+        return;
+    }
+
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("failed to start RCON listener on {address}: {e}");
+            return;
+        }
+    };
+
+    info!("RCON listening on {address}");
+
+    let (send, recv) = flume::unbounded();
+    let password = settings.password.clone();
+
+    thread::Builder::new()
+        .name("rcon listener".into())
+        .spawn(move || accept_loop(listener, password, send))
+        .expect("failed to spawn RCON listener thread");
+
+    commands.insert_resource(RconRequests(recv));
+}
+
+fn accept_loop(listener: TcpListener, password: String, requests: Sender<RconRequest>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("failed to accept RCON connection: {e}");
+                continue;
+            }
+        };
+
+        if requests.is_disconnected() {
+            return;
+        }
+
+        let password = password.clone();
+        let requests = requests.clone();
+
+        let _ = thread::Builder::new()
+            .name("rcon connection".into())
+            .spawn(move || {
+                let addr = stream.peer_addr().ok();
+
+                if let Err(e) = handle_connection(stream, &password, &requests) {
+                    if e.kind() != io::ErrorKind::UnexpectedEof {
+                        warn!("RCON connection from {addr:?} ended with error: {e}");
+                    }
+                }
+            });
+    }
+}
+
+/// Handles the packets of an RCON connection until it is closed.
+fn handle_connection(
+    mut stream: TcpStream,
+    password: &str,
+    requests: &Sender<RconRequest>,
+) -> io::Result<()> {
+    let mut authenticated = false;
+
+    loop {
+        let (id, kind, body) = read_packet(&mut stream)?;
+
+        match kind {
+            TYPE_LOGIN => {
+                authenticated = !password.is_empty() && body == password;
+
+                let id = if authenticated { id } else { -1 };
+                write_packet(&mut stream, id, TYPE_AUTH_RESPONSE, "")?;
+            }
+            TYPE_EXEC_COMMAND if authenticated => {
+                let (reply_send, reply_recv) = flume::bounded(1);
+
+                let request = RconRequest {
+                    command: body.strip_prefix('/').unwrap_or(&body).to_owned(),
+                    reply: reply_send,
+                };
+
+                if requests.send(request).is_err() {
+                    return Ok(());
+                }
+
+                let Ok(output) = reply_recv.recv() else {
+                    return Ok(());
+                };
+
+                write_response(&mut stream, id, &output)?;
+            }
+            TYPE_EXEC_COMMAND => write_packet(&mut stream, -1, TYPE_AUTH_RESPONSE, "")?,
+            _ => write_packet(
+                &mut stream,
+                id,
+                TYPE_RESPONSE,
+                &format!("Unknown request {kind:x}"),
+            )?,
+        }
+    }
+}
+
+/// Reads a packet and returns its request ID, type and body.
+fn read_packet(r: &mut impl Read) -> io::Result<(i32, i32, String)> {
+    let mut len = [0; 4];
+    r.read_exact(&mut len)?;
+    let len = i32::from_le_bytes(len);
+
+    if !(10..=MAX_INCOMING_PACKET as i32).contains(&len) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid RCON packet length of {len}"),
+        ));
+    }
+
+    let mut buf = vec![0; len as usize];
+    r.read_exact(&mut buf)?;
+
+    let id = i32::from_le_bytes(buf[0..4].try_into().unwrap());
+    let kind = i32::from_le_bytes(buf[4..8].try_into().unwrap());
+
+    // The body ends with a null byte, followed by another null byte.
+    let body = &buf[8..];
+    let body = &body[..body.iter().position(|&b| b == 0).unwrap_or(body.len())];
+
+    Ok((id, kind, String::from_utf8_lossy(body).into_owned()))
+}
+
+fn write_packet(w: &mut impl Write, id: i32, kind: i32, body: &str) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(14 + body.len());
+
+    buf.extend_from_slice(&(body.len() as i32 + 10).to_le_bytes());
+    buf.extend_from_slice(&id.to_le_bytes());
+    buf.extend_from_slice(&kind.to_le_bytes());
+    buf.extend_from_slice(body.as_bytes());
+    buf.extend_from_slice(&[0, 0]);
+
+    w.write_all(&buf)
+}
+
+/// Writes the output of a command, split into several packets if it is long.
+fn write_response(w: &mut impl Write, id: i32, output: &str) -> io::Result<()> {
+    let mut rest = output;
+
+    loop {
+        let mut split = rest.len().min(MAX_RESPONSE_BODY);
+
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+
+        let (body, tail) = rest.split_at(split);
+        write_packet(w, id, TYPE_RESPONSE, body)?;
+
+        if tail.is_empty() {
+            return Ok(());
+        }
+
+        rest = tail;
+    }
+}
+
+/// Sends the commands of RCON clients as [`CommandExecutionEvent`]s, executed
+/// by a new [`RconExecutor`].
+fn execute_requests(
+    requests: Res<RconRequests>,
+    mut events: EventWriter<CommandExecutionEvent>,
+    mut commands: Commands,
+) {
+    for request in requests.0.try_iter() {
+        let mut scopes = CommandScopes::new();
+        scopes.add("root");
+
+        let executor = commands
+            .spawn((
+                RconExecutor {
+                    output: String::new(),
+                    reply: request.reply,
+                },
+                scopes,
+            ))
+            .id();
+
+        events.send(CommandExecutionEvent {
+            command: request.command,
+            executor,
+        });
+    }
+}
+
+fn reply_to_requests(
+    executors: Query<(Entity, &RconExecutor)>,
+    mut processed: EventReader<CommandProcessedEvent>,
+    mut commands: Commands,
+) {
+    let processed: Vec<_> = processed.read().map(|event| event.executor).collect();
+
+    for (entity, executor) in &executors {
+        let output = if executor.output.is_empty() && !processed.contains(&entity) {
+            "Unknown or incomplete command".to_owned()
+        } else {
+            executor.output.clone()
+        };
+
+        let _ = executor.reply.send(output);
+
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn rcon_packet_round_trip() {
+        let mut buf = vec![];
+        write_packet(&mut buf, 7, TYPE_EXEC_COMMAND, "list").unwrap();
+
+        assert_eq!(buf.len(), 4 + 10 + 4);
+        assert_eq!(
+            read_packet(&mut buf.as_slice()).unwrap(),
+            (7, TYPE_EXEC_COMMAND, "list".into())
+        );
+    }
+
+    #[test]
+    fn long_responses_are_split() {
+        let output = "a".repeat(MAX_RESPONSE_BODY + 10);
+
+        let mut buf = vec![];
+        write_response(&mut buf, 1, &output).unwrap();
+
+        let mut r = buf.as_slice();
+        assert_eq!(read_packet_unbounded(&mut r).len(), MAX_RESPONSE_BODY);
+        assert_eq!(read_packet_unbounded(&mut r).len(), 10);
+        assert!(r.is_empty());
+    }
+
+    /// Reads the body of a response packet, which may be longer than the
+    /// packets clients are allowed to send.
+    fn read_packet_unbounded(r: &mut &[u8]) -> String {
+        let len = i32::from_le_bytes(r[0..4].try_into().unwrap()) as usize;
+        let body = String::from_utf8(r[12..len + 2].to_vec()).unwrap();
+        *r = &r[len + 4..];
+        body
+    }
+
+    #[test]
+    fn login_and_execute() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (send, recv) = flume::unbounded();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let _ = handle_connection(stream, "secret", &send);
+        });
+
+        // Answers the request like the server would at the end of the tick.
+        thread::spawn(move || {
+            let request: RconRequest = recv.recv().unwrap();
+            request
+                .reply
+                .send(format!("ran {}", request.command))
+                .unwrap();
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+
+        write_packet(&mut client, 1, TYPE_EXEC_COMMAND, "list").unwrap();
+        assert_eq!(read_packet(&mut client).unwrap().0, -1);
+
+        write_packet(&mut client, 2, TYPE_LOGIN, "wrong").unwrap();
+        assert_eq!(read_packet(&mut client).unwrap().0, -1);
+
+        write_packet(&mut client, 3, TYPE_LOGIN, "secret").unwrap();
+        assert_eq!(
+            read_packet(&mut client).unwrap(),
+            (3, TYPE_AUTH_RESPONSE, String::new())
+        );
+
+        write_packet(&mut client, 4, TYPE_EXEC_COMMAND, "/list").unwrap();
+        assert_eq!(
+            read_packet(&mut client).unwrap(),
+            (4, TYPE_RESPONSE, "ran list".into())
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use bevy_ecs::prelude::*;
+use valence_inventory::Inventory;
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::layer::chunk::BlockRef;
+use valence_server::nbt::Value;
+use valence_server::{BlockPos, ChunkLayer, Direction, ItemKind};
+
+/// Component for entities holding the [`Inventory`] of a container block,
+/// such as a chest or a hopper. Comparators reading from the container are
+/// updated when the inventory changes.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ContainerBlock {
+    /// The chunk layer the container block is in.
+    pub layer: Entity,
+    /// The position of the container block.
+    pub position: BlockPos,
+}
+
+/// Send this event after changing a block whose signal is computed by
+/// [`block_signal`] to update the comparators reading from it.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct UpdateComparatorsEvent {
+    pub layer: Entity,
+    pub position: BlockPos,
+}
+
+/// Sent when a comparator reads a new signal.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ComparatorSignalEvent {
+    pub layer: Entity,
+    /// The position of the comparator.
+    pub comparator: BlockPos,
+    /// The signal strength from 0 to 15.
+    pub signal: u8,
+}
+
+/// Returns the signal strength a comparator reads from a container with the
+/// contents of `inventory`.
+pub fn container_signal(inventory: &Inventory) -> u8 {
+    if inventory.slot_count() == 0 {
+        return 0;
+    }
+
+    let mut fullness = 0.0;
+    let mut empty = true;
+
+    for stack in inventory.slots() {
+        if !stack.is_empty() {
+            fullness += stack.count as f32 / stack.item.max_stack() as f32;
+            empty = false;
+        }
+    }
+
+    if empty {
+        return 0;
+    }
+
+    fullness /= inventory.slot_count() as f32;
+
+    (fullness * 14.0) as u8 + 1
+}
+
+/// Returns the signal strength a comparator reads from `block`, or `None` if
+/// the signal doesn't depend on the block itself.
+pub fn block_signal(block: BlockRef) -> Option<u8> {
+    let state = block.state;
+    let prop_u16 = |name| state.get(name).and_then(PropValue::to_u16);
+
+    let signal = match state.to_kind() {
+        BlockKind::Composter => prop_u16(PropName::Level)?,
+        BlockKind::WaterCauldron | BlockKind::PowderSnowCauldron => prop_u16(PropName::Level)?,
+        BlockKind::LavaCauldron => 3,
+        BlockKind::Cauldron => 0,
+        BlockKind::Cake => (7 - prop_u16(PropName::Bites)?) * 2,
+        BlockKind::EndPortalFrame => {
+            if state.get(PropName::Eye) == Some(PropValue::True) {
+                15
+            } else {
+                0
+            }
+        }
+        BlockKind::RespawnAnchor => prop_u16(PropName::Charges)? * 15 / 4,
+        BlockKind::Jukebox => {
+            if state.get(PropName::HasRecord) != Some(PropValue::True) {
+                return Some(0);
+            }
+
+            let Some(Value::Compound(record)) = block.nbt.and_then(|nbt| nbt.get("RecordItem"))
+            else {
+                return Some(0);
+            };
+
+            let Some(Value::String(id)) = record.get("id") else {
+                return Some(0);
+            };
+
+            let id = id.strip_prefix("minecraft:").unwrap_or(id);
+
+            return Some(ItemKind::from_str(id).map_or(0, record_signal));
+        }
+        _ => return None,
+    };
+
+    Some(signal as u8)
+}
+
+/// Returns the signal strength a comparator reads from a jukebox playing
+/// `record`.
+pub fn record_signal(record: ItemKind) -> u8 {
+    match record {
+        ItemKind::MusicDisc13 => 1,
+        ItemKind::MusicDiscCat => 2,
+        ItemKind::MusicDiscBlocks => 3,
+        ItemKind::MusicDiscChirp => 4,
+        ItemKind::MusicDiscFar => 5,
+        ItemKind::MusicDiscMall => 6,
+        ItemKind::MusicDiscMellohi => 7,
+        ItemKind::MusicDiscStal => 8,
+        ItemKind::MusicDiscStrad => 9,
+        ItemKind::MusicDiscWard => 10,
+        ItemKind::MusicDisc11 => 11,
+        ItemKind::MusicDiscWait => 12,
+        ItemKind::MusicDiscPigstep => 13,
+        ItemKind::MusicDiscOtherside | ItemKind::MusicDiscRelic => 14,
+        ItemKind::MusicDisc5 => 15,
+        _ => 0,
+    }
+}
+
+pub(crate) fn update_comparators(
+    containers: Query<(&ContainerBlock, &Inventory), Changed<Inventory>>,
+    mut update_events: EventReader<UpdateComparatorsEvent>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut signal_events: EventWriter<ComparatorSignalEvent>,
+) {
+    let container_signals = containers.iter().map(|(container, inv)| {
+        (
+            container.layer,
+            container.position,
+            Some(container_signal(inv)),
+        )
+    });
+
+    let block_signals = update_events
+        .read()
+        .map(|event| (event.layer, event.position, None));
+
+    // Collected first because `layers` is needed to compute block signals.
+    let sources: Vec<_> = container_signals.chain(block_signals).collect();
+
+    for (layer_entity, source, signal) in sources {
+        let Ok(mut layer) = layers.get_mut(layer_entity) else {
+            continue;
+        };
+
+        let signal = match signal {
+            Some(signal) => signal,
+            None => match layer.block(source).and_then(block_signal) {
+                Some(signal) => signal,
+                None => continue,
+            },
+        };
+
+        for dir in [
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+        ] {
+            let pos = source.get_in_direction(dir);
+
+            let Some(state) = layer.block(pos).map(|b| b.state) else {
+                continue;
+            };
+
+            if state.to_kind() != BlockKind::Comparator {
+                continue;
+            }
+
+            // Comparators read from the block behind them, which is in the
+            // direction they are facing.
+            let facing = match state.get(PropName::Facing) {
+                Some(PropValue::North) => Direction::North,
+                Some(PropValue::South) => Direction::South,
+                Some(PropValue::West) => Direction::West,
+                Some(PropValue::East) => Direction::East,
+                _ => continue,
+            };
+
+            if pos.get_in_direction(facing) != source {
+                continue;
+            }
+
+            let powered = if signal > 0 {
+                PropValue::True
+            } else {
+                PropValue::False
+            };
+
+            let new_state = state.set(PropName::Powered, powered);
+
+            if new_state != state {
+                layer.set_block(pos, new_state);
+            }
+
+            signal_events.send(ComparatorSignalEvent {
+                layer: layer_entity,
+                comparator: pos,
+                signal,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_inventory::InventoryKind;
+    use valence_server::ItemStack;
+
+    use super::*;
+
+    #[test]
+    fn container_signal_strength() {
+        let mut inv = Inventory::new(InventoryKind::Generic9x3);
+        assert_eq!(container_signal(&inv), 0);
+
+        inv.set_slot(0, ItemStack::new(ItemKind::Stone, 1, None));
+        assert_eq!(container_signal(&inv), 1);
+
+        for slot in 0..27 {
+            inv.set_slot(slot, ItemStack::new(ItemKind::Stone, 64, None));
+        }
+        assert_eq!(container_signal(&inv), 15);
+
+        // A single unstackable item fills a whole slot.
+        let mut hopper = Inventory::new(InventoryKind::Hopper);
+        hopper.set_slot(0, ItemStack::new(ItemKind::DiamondSword, 1, None));
+        assert_eq!(container_signal(&hopper), 3);
+    }
+}
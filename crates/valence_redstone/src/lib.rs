@@ -0,0 +1,42 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod comparator;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use comparator::{
+    block_signal, container_signal, record_signal, ComparatorSignalEvent, ContainerBlock,
+    UpdateComparatorsEvent,
+};
+use valence_server::layer::UpdateLayersPreClientSet;
+
+pub struct RedstonePlugin;
+
+impl Plugin for RedstonePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UpdateComparatorsEvent>()
+            .add_event::<ComparatorSignalEvent>()
+            .add_systems(
+                PostUpdate,
+                comparator::update_comparators.before(UpdateLayersPreClientSet),
+            );
+    }
+}
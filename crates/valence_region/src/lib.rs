@@ -0,0 +1,444 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod region;
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use region::{Region, RegionFlag, RegionFlags, RegionShape};
+use valence_inventory::player_inventory::PlayerInventory;
+use valence_inventory::{HeldItem, Inventory};
+use valence_server::block::BlockKind;
+use valence_server::client::{Client, UpdateClientsSet, VisibleChunkLayer};
+use valence_server::entity::{EntityManager, OldPosition, Position};
+use valence_server::event_loop::{
+    CancelledPackets, EventLoopPreUpdate, HandleActionPacketsSet, PacketEvent,
+};
+use valence_server::interact_entity::EntityInteraction;
+use valence_server::math::DVec3;
+use valence_server::protocol::packets::play::player_action_c2s::PlayerAction;
+use valence_server::protocol::packets::play::{
+    BlockUpdateS2c, PlayerActionC2s, PlayerInteractBlockC2s, PlayerInteractEntityC2s,
+};
+use valence_server::protocol::{Decode, Packet, WritePacket};
+use valence_server::uuid::Uuid;
+use valence_server::{BlockPos, ChunkLayer, Hand, UniqueId};
+
+pub struct RegionPlugin;
+
+impl Plugin for RegionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RegionEnterEvent>()
+            .add_event::<RegionLeaveEvent>()
+            .add_event::<RegionDeniedEvent>()
+            .add_systems(
+                EventLoopPreUpdate,
+                (enforce_block_regions, enforce_pvp_regions).before(HandleActionPacketsSet),
+            )
+            .add_systems(
+                PostUpdate,
+                (init_current_regions, track_current_regions)
+                    .chain()
+                    .before(UpdateClientsSet),
+            );
+    }
+}
+
+/// Component containing the protected regions of a layer. Insert this on an
+/// entity with a [`ChunkLayer`] to protect parts of it.
+#[derive(Component, Clone, Default, Debug)]
+pub struct Regions {
+    regions: BTreeMap<String, Region>,
+}
+
+impl Regions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a region with the given name, returning the region that was
+    /// previously stored under that name.
+    pub fn insert(&mut self, name: impl Into<String>, region: Region) -> Option<Region> {
+        self.regions.insert(name.into(), region)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Region> {
+        self.regions.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Region> {
+        self.regions.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Region> {
+        self.regions.get_mut(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Region)> + '_ {
+        self.regions.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Returns all regions containing the given position, ordered from highest
+    /// to lowest priority.
+    pub fn at(&self, pos: impl Into<DVec3>) -> Vec<(&str, &Region)> {
+        let pos = pos.into();
+
+        let mut res: Vec<_> = self.iter().filter(|(_, r)| r.contains(pos)).collect();
+        res.sort_by_key(|(_, r)| Reverse(r.priority));
+        res
+    }
+
+    /// Like [`Self::at`], but for the block at `pos`.
+    pub fn at_block(&self, pos: impl Into<BlockPos>) -> Vec<(&str, &Region)> {
+        let pos = pos.into();
+
+        let mut res: Vec<_> = self.iter().filter(|(_, r)| r.contains_block(pos)).collect();
+        res.sort_by_key(|(_, r)| Reverse(r.priority));
+        res
+    }
+
+    /// Returns the region that decides the value of `flag` for the block at
+    /// `pos`, which is the region with the highest priority that sets the flag.
+    pub fn deciding_region(
+        &self,
+        pos: impl Into<BlockPos>,
+        flag: RegionFlag,
+    ) -> Option<(&str, &Region)> {
+        self.at_block(pos)
+            .into_iter()
+            .find(|(_, r)| r.flags.get(flag).is_some())
+    }
+
+    /// Returns whether `flag` is allowed for the block at `pos` when performed
+    /// by the player with the UUID `actor`. Members of the deciding region
+    /// are always allowed. Actions outside of any region that sets the flag
+    /// are allowed.
+    pub fn allows(&self, pos: impl Into<BlockPos>, flag: RegionFlag, actor: Option<Uuid>) -> bool {
+        match self.deciding_region(pos, flag) {
+            Some((_, region)) => {
+                actor.is_some_and(|uuid| region.is_member(uuid))
+                    || region.flags.get(flag).unwrap_or(true)
+            }
+            None => true,
+        }
+    }
+
+    /// Removes the blocks which are protected from explosions from the list of
+    /// blocks affected by an explosion.
+    pub fn filter_explosion(&self, blocks: &mut Vec<BlockPos>) {
+        blocks.retain(|&pos| self.allows(pos, RegionFlag::Explosions, None));
+    }
+}
+
+/// The names of the regions a client is currently inside of. This is
+/// inserted on clients automatically and updated every tick.
+#[derive(Component, Clone, Default, Debug)]
+pub struct CurrentRegions(BTreeSet<String>);
+
+impl CurrentRegions {
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.iter().map(|s| s.as_str())
+    }
+}
+
+/// Sent when a client enters a region.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct RegionEnterEvent {
+    pub client: Entity,
+    /// The layer containing the region.
+    pub layer: Entity,
+    pub region: String,
+}
+
+/// Sent when a client leaves a region or when the region it was in is
+/// removed.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct RegionLeaveEvent {
+    pub client: Entity,
+    /// The layer containing the region.
+    pub layer: Entity,
+    pub region: String,
+}
+
+/// Sent when a client attempted an action that was denied by a region. The
+/// action is cancelled before any digging, block interaction, or entity
+/// interaction events are sent for it.
+///
+/// Block placements are inferred from block interactions, so a
+/// [`RegionFlag::Build`] denial is also reported when a client holding a block
+/// uses a block next to a position where building is denied.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct RegionDeniedEvent {
+    pub client: Entity,
+    /// The layer containing the region.
+    pub layer: Entity,
+    /// The name of the region which denied the action.
+    pub region: String,
+    pub flag: RegionFlag,
+    /// The block the action was performed on. For [`RegionFlag::Pvp`], this is
+    /// the block position of the player who isn't allowed to fight there.
+    pub position: BlockPos,
+}
+
+fn enforce_block_regions(
+    mut packets: EventReader<PacketEvent>,
+    mut cancelled: ResMut<CancelledPackets>,
+    mut clients: Query<(
+        &mut Client,
+        &VisibleChunkLayer,
+        &UniqueId,
+        Option<&Inventory>,
+        Option<&HeldItem>,
+    )>,
+    layers: Query<(&ChunkLayer, &Regions)>,
+    mut denied: EventWriter<RegionDeniedEvent>,
+) {
+    for (packet, id) in packets.read_with_id() {
+        let Ok((mut client, visible_layer, uuid, inventory, held_item)) =
+            clients.get_mut(packet.client)
+        else {
+            continue;
+        };
+
+        let Ok((layer, regions)) = layers.get(visible_layer.0) else {
+            continue;
+        };
+
+        // The positions whose blocks the client may have predicted a change
+        // for, and the denial of the action if there is one.
+        let (positions, denial) = if let Some(pkt) = peek::<PlayerActionC2s>(packet) {
+            if !matches!(
+                pkt.action,
+                PlayerAction::StartDestroyBlock | PlayerAction::StopDestroyBlock
+            ) {
+                continue;
+            }
+
+            let denial = denying_region(regions, pkt.position, RegionFlag::Break, uuid.0)
+                .map(|name| (name, RegionFlag::Break, pkt.position));
+
+            (vec![pkt.position], denial)
+        } else if let Some(pkt) = peek::<PlayerInteractBlockC2s>(packet) {
+            let placed = pkt.position.get_in_direction(pkt.face);
+
+            let holds_block = match (inventory, held_item) {
+                (Some(inventory), Some(held_item)) => {
+                    let slot = match pkt.hand {
+                        Hand::Main => held_item.slot(),
+                        Hand::Off => PlayerInventory::SLOT_OFFHAND,
+                    };
+
+                    BlockKind::from_item_kind(inventory.slot(slot).item).is_some()
+                }
+                // Without an inventory we can't tell, so assume the client
+                // could be placing a block.
+                _ => true,
+            };
+
+            let denial = denying_region(regions, pkt.position, RegionFlag::Interact, uuid.0)
+                .map(|name| (name, RegionFlag::Interact, pkt.position))
+                .or_else(|| {
+                    holds_block
+                        .then(|| denying_region(regions, placed, RegionFlag::Build, uuid.0))
+                        .flatten()
+                        .map(|name| (name, RegionFlag::Build, placed))
+                });
+
+            (vec![pkt.position, placed], denial)
+        } else {
+            continue;
+        };
+
+        let Some((name, flag, position)) = denial else {
+            continue;
+        };
+
+        cancelled.cancel(id);
+
+        denied.send(RegionDeniedEvent {
+            client: packet.client,
+            layer: visible_layer.0,
+            region: name.to_owned(),
+            flag,
+            position,
+        });
+
+        // Undo the client's prediction.
+        for pos in positions {
+            if let Some(block) = layer.block(pos) {
+                client.write_packet(&BlockUpdateS2c {
+                    position: pos,
+                    block_id: block.state,
+                });
+            }
+        }
+    }
+}
+
+fn enforce_pvp_regions(
+    mut packets: EventReader<PacketEvent>,
+    mut cancelled: ResMut<CancelledPackets>,
+    entities: Res<EntityManager>,
+    clients: Query<(&Position, &VisibleChunkLayer, &UniqueId), With<Client>>,
+    layers: Query<&Regions>,
+    mut denied: EventWriter<RegionDeniedEvent>,
+) {
+    for (packet, id) in packets.read_with_id() {
+        let Some(pkt) = peek::<PlayerInteractEntityC2s>(packet) else {
+            continue;
+        };
+
+        if pkt.interact != EntityInteraction::Attack {
+            continue;
+        }
+
+        let Some(victim) = entities.get_by_id(pkt.entity_id.0) else {
+            continue;
+        };
+
+        let (Ok(attacker), Ok(victim)) = (clients.get(packet.client), clients.get(victim)) else {
+            continue;
+        };
+
+        let (_, layer, _) = attacker;
+
+        let Ok(regions) = layers.get(layer.0) else {
+            continue;
+        };
+
+        // Both players must be allowed to fight where they are standing.
+        let denial = [attacker, victim].into_iter().find_map(|(pos, _, uuid)| {
+            let pos = BlockPos::from(pos.0);
+
+            denying_region(regions, pos, RegionFlag::Pvp, uuid.0).map(|name| (name, pos))
+        });
+
+        if let Some((name, position)) = denial {
+            cancelled.cancel(id);
+
+            denied.send(RegionDeniedEvent {
+                client: packet.client,
+                layer: layer.0,
+                region: name.to_owned(),
+                flag: RegionFlag::Pvp,
+                position,
+            });
+        }
+    }
+}
+
+/// Returns the name of the region denying `flag` at `pos` for the player
+/// `actor`, if it is denied.
+fn denying_region(regions: &Regions, pos: BlockPos, flag: RegionFlag, actor: Uuid) -> Option<&str> {
+    if regions.allows(pos, flag, Some(actor)) {
+        None
+    } else {
+        regions.deciding_region(pos, flag).map(|(name, _)| name)
+    }
+}
+
+/// Decodes a packet without reporting errors. Malformed packets are reported
+/// by the systems which handle them.
+fn peek<'a, P: Packet + Decode<'a>>(packet: &'a PacketEvent) -> Option<P> {
+    if packet.id == P::ID {
+        P::decode(&mut &packet.data[..]).ok()
+    } else {
+        None
+    }
+}
+
+fn init_current_regions(
+    mut commands: Commands,
+    clients: Query<Entity, (With<Client>, Without<CurrentRegions>)>,
+) {
+    for entity in &clients {
+        commands.entity(entity).insert(CurrentRegions::default());
+    }
+}
+
+fn track_current_regions(
+    mut clients: Query<(
+        Entity,
+        &mut CurrentRegions,
+        &Position,
+        &OldPosition,
+        &VisibleChunkLayer,
+    )>,
+    layers: Query<Ref<Regions>>,
+    mut enter: EventWriter<RegionEnterEvent>,
+    mut leave: EventWriter<RegionLeaveEvent>,
+) {
+    for (client, mut current, pos, old_pos, layer) in &mut clients {
+        let regions = layers.get(layer.0).ok();
+
+        let layer_changed = regions.as_ref().is_some_and(|r| r.is_changed());
+
+        if *pos == *old_pos && !layer_changed && !current.is_changed() {
+            continue;
+        }
+
+        let now: BTreeSet<String> = regions
+            .as_ref()
+            .map(|r| {
+                r.at(pos.0)
+                    .into_iter()
+                    .map(|(name, _)| name.to_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if now == current.0 {
+            continue;
+        }
+
+        for name in current.0.difference(&now) {
+            leave.send(RegionLeaveEvent {
+                client,
+                layer: layer.0,
+                region: name.clone(),
+            });
+        }
+
+        for name in now.difference(&current.0) {
+            enter.send(RegionEnterEvent {
+                client,
+                layer: layer.0,
+                region: name.clone(),
+            });
+        }
+
+        current.0 = now;
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use valence_server::math::{DVec2, DVec3};
+use valence_server::uuid::Uuid;
+use valence_server::BlockPos;
+
+/// A protected volume of a layer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Region {
+    /// The volume covered by this region.
+    pub shape: RegionShape,
+    /// Regions with a higher priority take precedence over overlapping regions
+    /// with a lower priority.
+    pub priority: i32,
+    /// The flags set by this region. Flags which are not set are inherited from
+    /// overlapping regions with a lower priority.
+    pub flags: RegionFlags,
+    /// UUIDs of the players that are exempt from the flags of this region,
+    /// such as the owners of a land claim.
+    pub members: BTreeSet<Uuid>,
+}
+
+impl Region {
+    pub fn new(shape: RegionShape) -> Self {
+        Self {
+            shape,
+            priority: 0,
+            flags: RegionFlags::default(),
+            members: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_flag(mut self, flag: RegionFlag, value: bool) -> Self {
+        self.flags.set(flag, value);
+        self
+    }
+
+    pub fn with_member(mut self, uuid: Uuid) -> Self {
+        self.members.insert(uuid);
+        self
+    }
+
+    /// Returns whether the player with the given UUID is a member of this
+    /// region.
+    pub fn is_member(&self, uuid: Uuid) -> bool {
+        self.members.contains(&uuid)
+    }
+
+    /// Returns whether the given position is inside of this region.
+    pub fn contains(&self, pos: impl Into<DVec3>) -> bool {
+        self.shape.contains(pos.into())
+    }
+
+    /// Returns whether the given block is inside of this region. The block is
+    /// considered inside if its center is.
+    pub fn contains_block(&self, pos: impl Into<BlockPos>) -> bool {
+        let pos = pos.into();
+        self.shape.contains(DVec3::new(
+            pos.x as f64 + 0.5,
+            pos.y as f64 + 0.5,
+            pos.z as f64 + 0.5,
+        ))
+    }
+}
+
+/// The volume of a [`Region`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum RegionShape {
+    /// An axis-aligned box of blocks. Both corners are inclusive.
+    Cuboid { min: BlockPos, max: BlockPos },
+    /// A polygon on the XZ plane extruded from `min_y` to `max_y` (both
+    /// inclusive). The points are the vertices of the polygon in order and the
+    /// polygon is implicitly closed.
+    Polygon {
+        points: Vec<DVec2>,
+        min_y: i32,
+        max_y: i32,
+    },
+}
+
+impl RegionShape {
+    /// Creates a cuboid from two opposite corners in any order.
+    pub fn cuboid(a: impl Into<BlockPos>, b: impl Into<BlockPos>) -> Self {
+        let a = a.into();
+        let b = b.into();
+
+        Self::Cuboid {
+            min: BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// Creates an extruded polygon from its vertices on the XZ plane.
+    pub fn polygon(
+        points: impl IntoIterator<Item = impl Into<DVec2>>,
+        min_y: i32,
+        max_y: i32,
+    ) -> Self {
+        Self::Polygon {
+            points: points.into_iter().map(Into::into).collect(),
+            min_y: min_y.min(max_y),
+            max_y: min_y.max(max_y),
+        }
+    }
+
+    pub fn contains(&self, pos: DVec3) -> bool {
+        match self {
+            RegionShape::Cuboid { min, max } => {
+                pos.x >= min.x as f64
+                    && pos.y >= min.y as f64
+                    && pos.z >= min.z as f64
+                    && pos.x < max.x as f64 + 1.0
+                    && pos.y < max.y as f64 + 1.0
+                    && pos.z < max.z as f64 + 1.0
+            }
+            RegionShape::Polygon {
+                points,
+                min_y,
+                max_y,
+            } => {
+                if pos.y < *min_y as f64 || pos.y >= *max_y as f64 + 1.0 || points.len() < 3 {
+                    return false;
+                }
+
+                // Even-odd rule.
+                let mut inside = false;
+                let mut j = points.len() - 1;
+
+                for i in 0..points.len() {
+                    let a = points[i];
+                    let b = points[j];
+
+                    if (a.y > pos.z) != (b.y > pos.z)
+                        && pos.x < (b.x - a.x) * (pos.z - a.y) / (b.y - a.y) + a.x
+                    {
+                        inside = !inside;
+                    }
+
+                    j = i;
+                }
+
+                inside
+            }
+        }
+    }
+}
+
+/// The actions which can be allowed or denied by a [`Region`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum RegionFlag {
+    /// Placing blocks.
+    Build,
+    /// Breaking blocks.
+    Break,
+    /// Using blocks such as doors, buttons and containers.
+    Interact,
+    /// Blocks being destroyed by explosions.
+    Explosions,
+    /// Players attacking other players.
+    Pvp,
+}
+
+/// The set of flags of a [`Region`]. Flags that are absent are inherited.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct RegionFlags(BTreeMap<RegionFlag, bool>);
+
+impl RegionFlags {
+    pub fn get(&self, flag: RegionFlag) -> Option<bool> {
+        self.0.get(&flag).copied()
+    }
+
+    pub fn set(&mut self, flag: RegionFlag, value: bool) -> Option<bool> {
+        self.0.insert(flag, value)
+    }
+
+    /// Removes the flag so that it is inherited again.
+    pub fn unset(&mut self, flag: RegionFlag) -> Option<bool> {
+        self.0.remove(&flag)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (RegionFlag, bool)> + '_ {
+        self.0.iter().map(|(k, v)| (*k, *v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuboid_contains() {
+        let shape = RegionShape::cuboid([5, 5, 5], [-5, -5, -5]);
+
+        assert!(shape.contains(DVec3::new(0.0, 0.0, 0.0)));
+        assert!(shape.contains(DVec3::new(-5.0, -5.0, -5.0)));
+        assert!(shape.contains(DVec3::new(5.9, 5.9, 5.9)));
+        assert!(!shape.contains(DVec3::new(6.0, 0.0, 0.0)));
+        assert!(!shape.contains(DVec3::new(0.0, -5.1, 0.0)));
+    }
+
+    #[test]
+    fn polygon_contains() {
+        // An L shaped region.
+        let shape = RegionShape::polygon(
+            [
+                [0.0, 0.0],
+                [10.0, 0.0],
+                [10.0, 4.0],
+                [4.0, 4.0],
+                [4.0, 10.0],
+                [0.0, 10.0],
+            ],
+            0,
+            10,
+        );
+
+        assert!(shape.contains(DVec3::new(2.0, 5.0, 2.0)));
+        assert!(shape.contains(DVec3::new(8.0, 5.0, 2.0)));
+        assert!(shape.contains(DVec3::new(2.0, 5.0, 8.0)));
+        assert!(!shape.contains(DVec3::new(8.0, 5.0, 8.0)));
+        assert!(!shape.contains(DVec3::new(2.0, 11.0, 2.0)));
+        assert!(!shape.contains(DVec3::new(-1.0, 5.0, 2.0)));
+    }
+}
@@ -1,6 +1,28 @@
 //! Contains dimension types and the dimension type registry. Minecraft's
 //! default dimensions are added to the registry by default.
 //!
+//! Custom dimension types can be added with
+//! [`DimensionTypeRegistry::register`], which checks them against the same
+//! limits as vanilla so that clients accept them.
+//!
+//! ```
+//! # use valence_registry::dimension_type::*;
+//! # use valence_ident::ident;
+//! # let mut dimensions = DimensionTypeRegistry::default();
+//! dimensions
+//!     .register(
+//!         ident!("my_plugin:tall"),
+//!         DimensionType {
+//!             min_y: -256,
+//!             height: 768,
+//!             logical_height: 768,
+//!             effects: DimensionEffects::TheEnd,
+//!             ..Default::default()
+//!         },
+//!     )
+//!     .unwrap();
+//! ```
+//!
 //! ### **NOTE:**
 //! - Modifying the dimension type registry after the server has started can
 //! break invariants within instances and clients! Make sure there are no
@@ -11,12 +33,14 @@ use std::ops::{Deref, DerefMut};
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use tracing::error;
 use valence_ident::{ident, Ident};
 use valence_nbt::serde::CompoundSerializer;
 
 use crate::codec::{RegistryCodec, RegistryValue};
 use crate::{Registry, RegistryIdx, RegistrySet};
+
 pub struct DimensionTypePlugin;
 
 impl Plugin for DimensionTypePlugin {
@@ -58,6 +82,12 @@ fn update_dimension_type_registry(
     mut codec: ResMut<RegistryCodec>,
 ) {
     if reg.is_changed() {
+        for (_, name, dim) in reg.iter() {
+            if let Err(e) = dim.validate() {
+                error!("dimension type `{name}` will be rejected by clients: {e}");
+            }
+        }
+
         let dimension_types = codec.registry_mut(DimensionTypeRegistry::KEY);
 
         dimension_types.clear();
@@ -80,6 +110,64 @@ pub struct DimensionTypeRegistry {
 
 impl DimensionTypeRegistry {
     pub const KEY: Ident<&'static str> = ident!("dimension_type");
+
+    /// Adds a custom dimension type after [validating](DimensionType::validate)
+    /// it. Unlike [`Registry::insert`], existing dimension types are not
+    /// replaced.
+    pub fn register(
+        &mut self,
+        name: impl Into<Ident<String>>,
+        dimension_type: DimensionType,
+    ) -> Result<DimensionTypeId, DimensionTypeError> {
+        let name = name.into();
+
+        dimension_type.validate()?;
+
+        if self.reg.get(name.as_str_ident()).is_some() {
+            return Err(DimensionTypeError::AlreadyRegistered(name));
+        }
+
+        self.reg
+            .insert(name, dimension_type)
+            .ok_or(DimensionTypeError::RegistryFull)
+    }
+
+    /// Returns the dimension type with the given name if it is registered and
+    /// valid.
+    pub fn validated(&self, name: Ident<&str>) -> Result<&DimensionType, DimensionTypeError> {
+        let dimension_type = self
+            .reg
+            .get(name)
+            .ok_or_else(|| DimensionTypeError::NotRegistered(name.into()))?;
+
+        dimension_type.validate()?;
+
+        Ok(dimension_type)
+    }
+}
+
+/// An error from registering or looking up a [`DimensionType`].
+#[derive(Clone, PartialEq, Debug, Error)]
+pub enum DimensionTypeError {
+    #[error("dimension type `{0}` is already registered")]
+    AlreadyRegistered(Ident<String>),
+    #[error("dimension type `{0}` is not registered")]
+    NotRegistered(Ident<String>),
+    #[error("the dimension type registry is full")]
+    RegistryFull,
+    #[error(
+        "dimension with min_y of {min_y} and height of {height} must be a multiple of 16 between \
+         y = {} and y = {}",
+        DimensionType::MIN_Y,
+        DimensionType::MAX_Y
+    )]
+    Height { min_y: i32, height: i32 },
+    #[error("logical height of {logical_height} must be between 0 and the height of {height}")]
+    LogicalHeight { logical_height: i32, height: i32 },
+    #[error("coordinate scale of {0} must be between 0.00001 and 30000000")]
+    CoordinateScale(f64),
+    #[error("monster spawn light levels must be between 0 and 15")]
+    MonsterSpawnLight,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
@@ -135,6 +223,65 @@ pub struct DimensionType {
     pub ultrawarm: bool,
 }
 
+impl DimensionType {
+    /// The lowest `min_y` of dimensions.
+    pub const MIN_Y: i32 = -2032;
+    /// The highest block Y coordinate in dimensions.
+    pub const MAX_Y: i32 = 2031;
+    /// The lowest height of dimensions.
+    pub const MIN_HEIGHT: i32 = 16;
+    /// The greatest height of dimensions.
+    pub const MAX_HEIGHT: i32 = Self::MAX_Y - Self::MIN_Y + 1;
+
+    /// Checks that the dimension type is within the limits of vanilla clients,
+    /// which refuse to join servers with invalid dimension types.
+    pub fn validate(&self) -> Result<(), DimensionTypeError> {
+        let Self {
+            min_y,
+            height,
+            logical_height,
+            coordinate_scale,
+            ..
+        } = *self;
+
+        if min_y % 16 != 0
+            || height % 16 != 0
+            || !(Self::MIN_HEIGHT..=Self::MAX_HEIGHT).contains(&height)
+            || min_y < Self::MIN_Y
+            || min_y + height > Self::MAX_Y + 1
+        {
+            return Err(DimensionTypeError::Height { min_y, height });
+        }
+
+        if !(0..=height).contains(&logical_height) {
+            return Err(DimensionTypeError::LogicalHeight {
+                logical_height,
+                height,
+            });
+        }
+
+        if !(1e-5..=3e7).contains(&coordinate_scale) {
+            return Err(DimensionTypeError::CoordinateScale(coordinate_scale));
+        }
+
+        let light_levels_valid = match self.monster_spawn_light_level {
+            MonsterSpawnLightLevel::Int(level) => (0..=15).contains(&level),
+            MonsterSpawnLightLevel::Tagged(MonsterSpawnLightLevelTagged::Uniform {
+                min_inclusive,
+                max_inclusive,
+            }) => {
+                (0..=15).contains(&min_inclusive) && (min_inclusive..=15).contains(&max_inclusive)
+            }
+        };
+
+        if !light_levels_valid || !(0..=15).contains(&self.monster_spawn_block_light_limit) {
+            return Err(DimensionTypeError::MonsterSpawnLight);
+        }
+
+        Ok(())
+    }
+}
+
 impl Default for DimensionType {
     fn default() -> Self {
         Self {
@@ -194,3 +341,103 @@ impl From<i32> for MonsterSpawnLightLevel {
         Self::Int(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_dimension_types() {
+        assert_eq!(DimensionType::default().validate(), Ok(()));
+
+        let tallest = DimensionType {
+            min_y: DimensionType::MIN_Y,
+            height: DimensionType::MAX_HEIGHT,
+            ..Default::default()
+        };
+
+        assert_eq!(tallest.validate(), Ok(()));
+
+        for (min_y, height) in [(-64, 100), (-60, 384), (0, 0), (2000, 64), (-2048, 384)] {
+            let dim = DimensionType {
+                min_y,
+                height,
+                logical_height: 0,
+                ..Default::default()
+            };
+
+            assert_eq!(
+                dim.validate(),
+                Err(DimensionTypeError::Height { min_y, height })
+            );
+        }
+
+        let dim = DimensionType {
+            logical_height: 400,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            dim.validate(),
+            Err(DimensionTypeError::LogicalHeight { .. })
+        ));
+
+        let dim = DimensionType {
+            coordinate_scale: 0.0,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            dim.validate(),
+            Err(DimensionTypeError::CoordinateScale(0.0))
+        );
+
+        let dim = DimensionType {
+            monster_spawn_light_level: MonsterSpawnLightLevel::Tagged(
+                MonsterSpawnLightLevelTagged::Uniform {
+                    min_inclusive: 8,
+                    max_inclusive: 4,
+                },
+            ),
+            ..Default::default()
+        };
+
+        assert_eq!(dim.validate(), Err(DimensionTypeError::MonsterSpawnLight));
+    }
+
+    #[test]
+    fn register_dimension_types() {
+        let mut reg = DimensionTypeRegistry::default();
+
+        let id = reg
+            .register(ident!("tall"), DimensionType::default())
+            .unwrap();
+
+        assert_eq!(reg.index_of(ident!("tall")), Some(id));
+        assert!(reg.validated(ident!("tall")).is_ok());
+
+        assert_eq!(
+            reg.register(ident!("tall"), DimensionType::default()),
+            Err(DimensionTypeError::AlreadyRegistered(ident!("tall").into()))
+        );
+
+        assert_eq!(
+            reg.register(
+                ident!("short"),
+                DimensionType {
+                    height: 8,
+                    ..Default::default()
+                }
+            ),
+            Err(DimensionTypeError::Height {
+                min_y: -64,
+                height: 8
+            })
+        );
+
+        assert_eq!(
+            reg.validated(ident!("short")),
+            Err(DimensionTypeError::NotRegistered(ident!("short").into()))
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, ensure, Context};
+use bytes::Bytes;
+use valence_server::protocol::{Decode, Encode, VarInt, MAX_PACKET_SIZE, PROTOCOL_VERSION};
+
+const MAGIC: &[u8; 8] = b"VLCRPLY1";
+
+/// A packet in a [`Replay`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReplayFrame {
+    /// The time since the start of the recording the packet was sent at.
+    pub time: Duration,
+    /// The ID of the packet.
+    pub id: i32,
+    /// The contents of the packet after the leading VarInt ID.
+    pub body: Bytes,
+}
+
+/// A recording of the packets sent to a client in the play state, loaded in
+/// memory.
+///
+/// Replays are stored as a header followed by the packets, each prefixed with
+/// the milliseconds since the start of the recording and its length. The
+/// packets are not compressed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Replay {
+    protocol_version: i32,
+    frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    /// Creates a replay from its packets, which must be ordered by time.
+    pub fn from_frames(frames: Vec<ReplayFrame>) -> Self {
+        debug_assert!(frames.windows(2).all(|w| w[0].time <= w[1].time));
+
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            frames,
+        }
+    }
+
+    /// Loads a replay from a file created by a [`ReplayWriter`].
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+        Self::read(BufReader::new(file))
+    }
+
+    /// Reads a replay written by a [`ReplayWriter`]. A recording which was cut
+    /// off in the middle of a packet, such as when the server crashed, is
+    /// read up to the last complete packet.
+    pub fn read(mut r: impl Read) -> anyhow::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        r.read_exact(&mut magic)?;
+        ensure!(&magic == MAGIC, "not a replay");
+
+        let mut version = [0; 4];
+        r.read_exact(&mut version)?;
+        let protocol_version = i32::from_be_bytes(version);
+
+        let mut data = vec![];
+        r.read_to_end(&mut data)?;
+
+        let mut frames = vec![];
+        let mut data = &data[..];
+
+        while data.len() >= 8 {
+            let millis = u32::from_be_bytes(data[..4].try_into().unwrap());
+            let len = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+
+            ensure!(len <= MAX_PACKET_SIZE as usize, "packet is too large");
+
+            let Some(mut packet) = data[8..].get(..len) else {
+                break;
+            };
+
+            data = &data[8 + len..];
+
+            let id = VarInt::decode(&mut packet)?.0;
+
+            frames.push(ReplayFrame {
+                time: Duration::from_millis(millis.into()),
+                id,
+                body: Bytes::copy_from_slice(packet),
+            });
+        }
+
+        if frames.windows(2).any(|w| w[0].time > w[1].time) {
+            bail!("packets are out of order");
+        }
+
+        Ok(Self {
+            protocol_version,
+            frames,
+        })
+    }
+
+    /// The version of the protocol the packets were recorded with. Replays
+    /// from other versions can't be played back.
+    pub fn protocol_version(&self) -> i32 {
+        self.protocol_version
+    }
+
+    pub fn frames(&self) -> &[ReplayFrame] {
+        &self.frames
+    }
+
+    /// The time of the last packet.
+    pub fn duration(&self) -> Duration {
+        self.frames.last().map_or(Duration::ZERO, |f| f.time)
+    }
+}
+
+/// Writes packets in the format read by [`Replay::read`].
+pub struct ReplayWriter<W: Write> {
+    writer: W,
+    start: Instant,
+    buf: Vec<u8>,
+}
+
+impl ReplayWriter<BufWriter<File>> {
+    /// Creates a new replay file at `path`, replacing any existing file.
+    pub fn create(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+
+        Self::new(BufWriter::new(file))
+    }
+}
+
+impl<W: Write> ReplayWriter<W> {
+    /// Writes the header to `writer`. The recording starts now.
+    pub fn new(mut writer: W) -> anyhow::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&PROTOCOL_VERSION.to_be_bytes())?;
+
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+            buf: vec![],
+        })
+    }
+
+    /// Appends a packet sent at `timestamp`.
+    pub fn write(&mut self, timestamp: Instant, id: i32, body: &[u8]) -> anyhow::Result<()> {
+        let time = timestamp.saturating_duration_since(self.start);
+
+        self.buf.clear();
+        VarInt(id).encode(&mut self.buf)?;
+        self.buf.extend_from_slice(body);
+
+        let millis = u32::try_from(time.as_millis()).context("recording is too long")?;
+
+        self.writer.write_all(&millis.to_be_bytes())?;
+        self.writer
+            .write_all(&(self.buf.len() as u32).to_be_bytes())?;
+        self.writer.write_all(&self.buf)?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub(crate) fn boxed(self) -> ReplayWriter<Box<dyn Write + Send>>
+    where
+        W: Send + 'static,
+    {
+        ReplayWriter {
+            writer: Box::new(self.writer),
+            start: self.start,
+            buf: self.buf,
+        }
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn finish(mut self) -> anyhow::Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read() {
+        let mut writer = ReplayWriter::new(vec![]).unwrap();
+        let start = writer.start;
+
+        writer.write(start, 1, &[1, 2, 3]).unwrap();
+        writer
+            .write(start + Duration::from_millis(1500), 300, &[])
+            .unwrap();
+
+        let mut bytes = writer.finish().unwrap();
+
+        let replay = Replay::read(&bytes[..]).unwrap();
+
+        assert_eq!(replay.protocol_version(), PROTOCOL_VERSION);
+        assert_eq!(replay.duration(), Duration::from_millis(1500));
+        assert_eq!(
+            replay.frames(),
+            [
+                ReplayFrame {
+                    time: Duration::ZERO,
+                    id: 1,
+                    body: Bytes::from_static(&[1, 2, 3]),
+                },
+                ReplayFrame {
+                    time: Duration::from_millis(1500),
+                    id: 300,
+                    body: Bytes::new(),
+                }
+            ]
+        );
+
+        // An incomplete packet at the end is ignored.
+        bytes.pop();
+        assert_eq!(Replay::read(&bytes[..]).unwrap().frames().len(), 1);
+
+        assert!(Replay::read(&b"not a replay"[..]).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod file;
+mod playback;
+mod record;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use file::{Replay, ReplayFrame, ReplayWriter};
+pub use playback::ReplayPlayback;
+pub use record::ReplayRecorder;
+use valence_network::SharedNetworkState;
+use valence_server::client::{FlushPacketsSet, UpdateClientsSet};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>()
+            .add_systems(Startup, add_recorder_tap)
+            .add_systems(
+                PostUpdate,
+                (
+                    (playback::stop_playback, playback::start_playback)
+                        .chain()
+                        .before(UpdateClientsSet),
+                    // Sent after the client is removed from its layers, so the
+                    // replay's chunks aren't unloaded again.
+                    playback::play_replays
+                        .after(UpdateClientsSet)
+                        .before(FlushPacketsSet),
+                ),
+            );
+    }
+}
+
+fn add_recorder_tap(shared: Option<Res<SharedNetworkState>>, recorder: Res<ReplayRecorder>) {
+    if let Some(shared) = shared {
+        shared.add_packet_tap(recorder.clone());
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy_ecs::prelude::*;
+use tracing::warn;
+use valence_server::client::{Client, VisibleChunkLayer, VisibleEntityLayers};
+use valence_server::entity::EntityId;
+use valence_server::protocol::packets::play::{
+    ChunkDataS2c, DisconnectS2c, EntitiesDestroyS2c, EntitySpawnS2c, ExperienceOrbSpawnS2c,
+    GameJoinS2c, KeepAliveS2c, PlayerPositionLookS2c, PlayerRespawnS2c, PlayerSpawnS2c,
+    UnloadChunkS2c,
+};
+use valence_server::protocol::{
+    Decode, Packet, PacketEncoder, VarInt, WritePacket, PROTOCOL_VERSION,
+};
+use valence_server::{ChunkPos, Server};
+
+use crate::{Replay, ReplayFrame};
+
+/// Plays a [`Replay`] back to the client this component is inserted on. Remove
+/// the component to stop the playback and return the client to the world.
+///
+/// While watching, the client is removed from its layers so that only the
+/// replay is visible, and it should be in spectator mode to fly around
+/// freely. Packets which affect the client's own connection, such as
+/// keepalives, teleports, and respawns, are not played back.
+///
+/// The server keeps updating the chunk the client's view is centered on as it
+/// moves, so parts of the replay far away from the client may be unloaded.
+#[derive(Component, Clone, Debug)]
+pub struct ReplayPlayback {
+    replay: Arc<Replay>,
+    position: Duration,
+    /// The index of the next frame to send.
+    next_frame: usize,
+    /// Whether the replay needs to be sent again from the start.
+    rewind: bool,
+    /// How fast the replay is played, where `1.0` is the speed it was recorded
+    /// at.
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl ReplayPlayback {
+    /// Plays `replay` from the start at normal speed.
+    pub fn new(replay: impl Into<Arc<Replay>>) -> Self {
+        Self {
+            replay: replay.into(),
+            position: Duration::ZERO,
+            next_frame: 0,
+            rewind: false,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn replay(&self) -> &Arc<Replay> {
+        &self.replay
+    }
+
+    /// The current time in the replay.
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    /// Jumps to `position` in the replay. The packets up to that time are sent
+    /// in the next tick, which can take a while for long recordings.
+    pub fn seek(&mut self, position: Duration) {
+        let position = position.min(self.replay.duration());
+
+        if position < self.position {
+            self.rewind = true;
+        }
+
+        self.position = position;
+    }
+
+    /// Whether every packet of the replay was sent.
+    pub fn is_finished(&self) -> bool {
+        self.next_frame == self.replay.frames().len()
+    }
+}
+
+/// The state of a client watching a replay.
+#[derive(Component)]
+pub(crate) struct ReplayViewer {
+    /// The layers to return the client to when the playback stops.
+    chunk_layer: Entity,
+    entity_layers: BTreeSet<Entity>,
+    /// The chunks and entities the replay has loaded on the client.
+    chunks: BTreeSet<ChunkPos>,
+    entities: BTreeSet<i32>,
+}
+
+impl ReplayViewer {
+    /// Updates the loaded chunks and entities with `frame`. Returns whether the
+    /// frame should be sent to the client with the entity ID `self_id`.
+    fn track(&mut self, frame: &ReplayFrame, self_id: i32) -> bool {
+        let mut body = &frame.body[..];
+
+        match frame.id {
+            KeepAliveS2c::ID
+            | GameJoinS2c::ID
+            | DisconnectS2c::ID
+            | PlayerRespawnS2c::ID
+            | PlayerPositionLookS2c::ID => false,
+            ChunkDataS2c::ID => {
+                if let Ok(pos) = ChunkPos::decode(&mut body) {
+                    self.chunks.insert(pos);
+                }
+                true
+            }
+            UnloadChunkS2c::ID => {
+                if let Ok(pos) = ChunkPos::decode(&mut body) {
+                    self.chunks.remove(&pos);
+                }
+                true
+            }
+            EntitySpawnS2c::ID | PlayerSpawnS2c::ID | ExperienceOrbSpawnS2c::ID => {
+                match VarInt::decode(&mut body) {
+                    Ok(VarInt(id)) if id == self_id => false,
+                    Ok(VarInt(id)) => {
+                        self.entities.insert(id);
+                        true
+                    }
+                    Err(_) => true,
+                }
+            }
+            EntitiesDestroyS2c::ID => {
+                if let Ok(pkt) = EntitiesDestroyS2c::decode(&mut body) {
+                    for id in pkt.entity_ids.iter() {
+                        self.entities.remove(&id.0);
+                    }
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    /// Unloads everything the replay loaded on the client.
+    fn clear(&mut self, client: &mut Client) {
+        for pos in std::mem::take(&mut self.chunks) {
+            client.write_packet(&UnloadChunkS2c { pos });
+        }
+
+        if !self.entities.is_empty() {
+            let entity_ids: Vec<_> = std::mem::take(&mut self.entities)
+                .into_iter()
+                .map(VarInt)
+                .collect();
+
+            client.write_packet(&EntitiesDestroyS2c {
+                entity_ids: entity_ids.into(),
+            });
+        }
+    }
+}
+
+pub(crate) fn start_playback(
+    mut clients: Query<
+        (
+            Entity,
+            &mut Client,
+            &mut VisibleChunkLayer,
+            &mut VisibleEntityLayers,
+            Option<&mut ReplayViewer>,
+            &ReplayPlayback,
+        ),
+        Added<ReplayPlayback>,
+    >,
+    mut commands: Commands,
+) {
+    for (entity, mut client, mut chunk_layer, mut entity_layers, viewer, playback) in &mut clients {
+        if playback.replay.protocol_version() != PROTOCOL_VERSION {
+            warn!(
+                "can't play back replay from protocol version {}",
+                playback.replay.protocol_version()
+            );
+            commands.entity(entity).remove::<ReplayPlayback>();
+            continue;
+        }
+
+        match viewer {
+            // The client was already watching another replay.
+            Some(mut viewer) => viewer.clear(&mut client),
+            None => {
+                commands.entity(entity).insert(ReplayViewer {
+                    chunk_layer: chunk_layer.0,
+                    entity_layers: std::mem::take(&mut entity_layers.0),
+                    chunks: BTreeSet::new(),
+                    entities: BTreeSet::new(),
+                });
+
+                chunk_layer.0 = Entity::PLACEHOLDER;
+            }
+        }
+    }
+}
+
+pub(crate) fn stop_playback(
+    mut removed: RemovedComponents<ReplayPlayback>,
+    mut clients: Query<
+        (
+            &mut Client,
+            &mut VisibleChunkLayer,
+            &mut VisibleEntityLayers,
+            &mut ReplayViewer,
+        ),
+        Without<ReplayPlayback>,
+    >,
+    mut commands: Commands,
+) {
+    for entity in removed.read() {
+        let Ok((mut client, mut chunk_layer, mut entity_layers, mut viewer)) =
+            clients.get_mut(entity)
+        else {
+            continue;
+        };
+
+        viewer.clear(&mut client);
+
+        chunk_layer.0 = viewer.chunk_layer;
+        entity_layers.0 = std::mem::take(&mut viewer.entity_layers);
+
+        commands.entity(entity).remove::<ReplayViewer>();
+    }
+}
+
+pub(crate) fn play_replays(
+    mut clients: Query<(
+        &mut Client,
+        &mut ReplayPlayback,
+        &mut ReplayViewer,
+        &EntityId,
+    )>,
+    server: Res<Server>,
+) {
+    let tick = Duration::from_secs(1) / server.tick_rate().get();
+
+    let mut enc = PacketEncoder::new();
+    enc.set_compression(server.compression_threshold());
+
+    for (mut client, mut playback, mut viewer, self_id) in &mut clients {
+        let playback = &mut *playback;
+
+        if playback.rewind {
+            viewer.clear(&mut client);
+            playback.next_frame = 0;
+            playback.rewind = false;
+        }
+
+        if !playback.paused {
+            playback.position = (playback.position + tick.mul_f32(playback.speed.max(0.0)))
+                .min(playback.replay.duration());
+        }
+
+        let frames = &playback.replay.frames()[playback.next_frame..];
+        let count = frames.partition_point(|f| f.time <= playback.position);
+
+        for frame in &frames[..count] {
+            if viewer.track(frame, self_id.get()) {
+                if let Err(e) = enc.append_packet_frame(frame.id, &frame.body) {
+                    warn!("failed to play back packet: {e:#}");
+                }
+            }
+        }
+
+        playback.next_frame += count;
+
+        client.write_packet_bytes(&enc.take());
+    }
+}
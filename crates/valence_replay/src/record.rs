@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use bevy_ecs::prelude::*;
+use tracing::warn;
+use valence_network::tap::{PacketObserver, PacketTap, TappedPacket};
+use valence_network::NewClientInfo;
+use valence_server::protocol::PacketState;
+use valence_server::uuid::Uuid;
+
+use crate::ReplayWriter;
+
+type BoxedWriter = ReplayWriter<Box<dyn Write + Send>>;
+
+/// The recording of a connection, shared by its observer and the recorder.
+type Slot = Arc<Mutex<Option<BoxedWriter>>>;
+
+/// Resource for recording the packets sent to clients into replays.
+///
+/// Recording only works with the network plugin, since the packets are
+/// captured with a [packet tap](valence_network::tap) as they are written to
+/// the connection. Packets are recorded from the play state onwards.
+///
+/// A recording only contains the packets sent after it started, so it should
+/// be started when the client joins, such as in the system that sets up new
+/// clients, for the recording to include the world around the client.
+/// Changing the client's `VisibleChunkLayer` also makes the server resend the
+/// chunks and entities in view.
+#[derive(Resource, Clone, Default)]
+pub struct ReplayRecorder {
+    connections: Arc<Mutex<HashMap<Uuid, Slot>>>,
+}
+
+impl ReplayRecorder {
+    /// Starts recording the packets sent to the client with the given UUID
+    /// to `writer`, replacing any recording in progress. Returns `false` if
+    /// the client is not connected.
+    pub fn start<W: Write + Send + 'static>(&self, uuid: Uuid, writer: ReplayWriter<W>) -> bool {
+        let Some(slot) = self.connections.lock().unwrap().get(&uuid).cloned() else {
+            return false;
+        };
+
+        if let Some(old) = slot.lock().unwrap().replace(writer.boxed()) {
+            finish(old);
+        }
+
+        true
+    }
+
+    /// Stops the recording of the client with the given UUID and flushes the
+    /// replay. Returns `false` if the client was not being recorded.
+    pub fn stop(&self, uuid: Uuid) -> bool {
+        let slot = self.connections.lock().unwrap().get(&uuid).cloned();
+
+        match slot.and_then(|slot| slot.lock().unwrap().take()) {
+            Some(writer) => {
+                finish(writer);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_recording(&self, uuid: Uuid) -> bool {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(&uuid)
+            .is_some_and(|slot| slot.lock().unwrap().is_some())
+    }
+}
+
+impl PacketTap for ReplayRecorder {
+    fn open(&self, _remote_addr: SocketAddr) -> Option<Box<dyn PacketObserver>> {
+        Some(Box::new(RecordingObserver {
+            connections: self.connections.clone(),
+            client: None,
+        }))
+    }
+}
+
+fn finish(writer: BoxedWriter) {
+    if let Err(e) = writer.finish() {
+        warn!("failed to finish replay: {e:#}");
+    }
+}
+
+struct RecordingObserver {
+    connections: Arc<Mutex<HashMap<Uuid, Slot>>>,
+    /// Set once the connection has logged in.
+    client: Option<(Uuid, Slot)>,
+}
+
+impl PacketObserver for RecordingObserver {
+    fn outbound(&mut self, packet: &TappedPacket) {
+        if packet.state != PacketState::Play {
+            return;
+        }
+
+        let Some((_, slot)) = &self.client else {
+            return;
+        };
+
+        let mut slot = slot.lock().unwrap();
+
+        if let Some(writer) = slot.as_mut() {
+            if let Err(e) = writer.write(packet.timestamp, packet.id, packet.body) {
+                warn!("failed to record packet, stopping replay: {e:#}");
+                slot.take();
+            }
+        }
+    }
+
+    fn logged_in(&mut self, info: &NewClientInfo) {
+        let slot = Slot::default();
+
+        self.connections
+            .lock()
+            .unwrap()
+            .insert(info.uuid, slot.clone());
+
+        self.client = Some((info.uuid, slot));
+    }
+}
+
+impl Drop for RecordingObserver {
+    fn drop(&mut self) {
+        let Some((uuid, slot)) = self.client.take() else {
+            return;
+        };
+
+        let mut connections = self.connections.lock().unwrap();
+
+        // The same player may have reconnected in the meantime.
+        if connections
+            .get(&uuid)
+            .is_some_and(|s| Arc::ptr_eq(s, &slot))
+        {
+            connections.remove(&uuid);
+        }
+
+        drop(connections);
+
+        let writer = slot.lock().unwrap().take();
+
+        if let Some(writer) = writer {
+            finish(writer);
+        }
+    }
+}
@@ -19,11 +19,14 @@
 #![allow(clippy::type_complexity)]
 
 mod components;
+mod team;
+
 use std::collections::BTreeSet;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 pub use components::*;
+pub use team::*;
 use tracing::{debug, warn};
 use valence_server::client::{Client, OldVisibleEntityLayers, VisibleEntityLayers};
 use valence_server::entity::EntityLayerId;
@@ -66,6 +69,16 @@ impl Plugin for ScoreboardPlugin {
                 .after(create_or_update_objectives)
                 .after(handle_new_clients)
                 .in_set(ScoreboardSet),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                team::create_or_update_teams,
+                team::update_team_members.after(team::create_or_update_teams),
+                team::remove_despawned_teams,
+                team::send_teams_to_clients,
+            )
+                .in_set(ScoreboardSet),
         );
     }
 }
@@ -0,0 +1,270 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+use bevy_ecs::prelude::*;
+use derive_more::{Deref, DerefMut};
+use tracing::warn;
+use valence_server::client::{Client, OldVisibleEntityLayers, VisibleEntityLayers};
+use valence_server::entity::EntityLayerId;
+pub use valence_server::protocol::packets::play::team_s2c::{
+    CollisionRule, NameTagVisibility, TeamColor,
+};
+use valence_server::protocol::packets::play::team_s2c::{Mode, TeamFlags};
+use valence_server::protocol::packets::play::TeamS2c;
+use valence_server::protocol::WritePacket;
+use valence_server::text::IntoText;
+use valence_server::{Despawned, EntityLayer, Text};
+
+/// A string that identifies a team. It's generally not safe to modify this
+/// after it's been created. Limited to 16 characters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Component, Deref)]
+pub struct Team(pub(crate) String);
+
+impl Team {
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        debug_assert!(
+            name.len() <= 16,
+            "Team name {} is too long ({} > 16)",
+            name,
+            name.len()
+        );
+        Self(name)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+/// How the members of a team are shown to clients and how they interact with
+/// each other.
+#[derive(Debug, Clone, PartialEq, Component)]
+pub struct TeamSettings {
+    pub display_name: Text,
+    /// Colors the names of the members in the player list, above their heads,
+    /// and in chat.
+    pub color: TeamColor,
+    /// Text shown before the names of the members.
+    pub prefix: Text,
+    /// Text shown after the names of the members.
+    pub suffix: Text,
+    pub friendly_fire: bool,
+    pub see_invisible_teammates: bool,
+    pub name_tag_visibility: NameTagVisibility,
+    pub collision_rule: CollisionRule,
+}
+
+impl Default for TeamSettings {
+    fn default() -> Self {
+        Self {
+            display_name: "".into_text(),
+            color: TeamColor::Reset,
+            prefix: "".into_text(),
+            suffix: "".into_text(),
+            friendly_fire: true,
+            see_invisible_teammates: true,
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+        }
+    }
+}
+
+impl TeamSettings {
+    fn flags(&self) -> TeamFlags {
+        TeamFlags::new()
+            .with_friendly_fire(self.friendly_fire)
+            .with_see_invisible_teammates(self.see_invisible_teammates)
+    }
+}
+
+/// The names of the entities on a team. For players this is their username,
+/// for other entities their UUID as a string.
+#[derive(Debug, Clone, PartialEq, Eq, Component, Default, Deref, DerefMut)]
+pub struct TeamMembers(pub BTreeSet<String>);
+
+impl TeamMembers {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Component, Default)]
+pub struct OldTeamMembers(pub(crate) BTreeSet<String>);
+
+#[derive(Bundle)]
+pub struct TeamBundle {
+    pub name: Team,
+    pub settings: TeamSettings,
+    pub members: TeamMembers,
+    pub old_members: OldTeamMembers,
+    pub layer: EntityLayerId,
+}
+
+impl Default for TeamBundle {
+    fn default() -> Self {
+        Self {
+            name: Team::new(""),
+            settings: Default::default(),
+            members: Default::default(),
+            old_members: Default::default(),
+            layer: Default::default(),
+        }
+    }
+}
+
+fn create_mode<'a>(settings: &'a TeamSettings, entities: Vec<&'a str>) -> Mode<'a> {
+    Mode::CreateTeam {
+        team_display_name: Cow::Borrowed(&settings.display_name),
+        friendly_flags: settings.flags(),
+        name_tag_visibility: settings.name_tag_visibility,
+        collision_rule: settings.collision_rule,
+        team_color: settings.color,
+        team_prefix: Cow::Borrowed(&settings.prefix),
+        team_suffix: Cow::Borrowed(&settings.suffix),
+        entities,
+    }
+}
+
+/// Creates new teams without members, which are added by
+/// `update_team_members`.
+pub(crate) fn create_or_update_teams(
+    teams: Query<(Ref<Team>, Ref<TeamSettings>, &EntityLayerId), Changed<TeamSettings>>,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (team, settings, entity_layer) in &teams {
+        if team.name().is_empty() {
+            warn!("Team name is empty");
+        }
+
+        let mode = if team.is_added() {
+            create_mode(&settings, vec![])
+        } else {
+            Mode::UpdateTeamInfo {
+                team_display_name: Cow::Borrowed(&settings.display_name),
+                friendly_flags: settings.flags(),
+                name_tag_visibility: settings.name_tag_visibility,
+                collision_rule: settings.collision_rule,
+                team_color: settings.color,
+                team_prefix: Cow::Borrowed(&settings.prefix),
+                team_suffix: Cow::Borrowed(&settings.suffix),
+            }
+        };
+
+        let Ok(mut layer) = layers.get_mut(entity_layer.0) else {
+            warn!(
+                "No layer found for entity layer ID {:?}, can't update team",
+                entity_layer
+            );
+            continue;
+        };
+
+        layer.write_packet(&TeamS2c {
+            team_name: &team.0,
+            mode,
+        });
+    }
+}
+
+/// Must occur after `create_or_update_teams`.
+pub(crate) fn update_team_members(
+    mut teams: Query<
+        (&Team, &TeamMembers, &mut OldTeamMembers, &EntityLayerId),
+        (Changed<TeamMembers>, Without<Despawned>),
+    >,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (team, members, mut old_members, entity_layer) in &mut teams {
+        let Ok(mut layer) = layers.get_mut(entity_layer.0) else {
+            warn!(
+                "No layer found for entity layer ID {:?}, can't update team members",
+                entity_layer
+            );
+            continue;
+        };
+
+        let removed: Vec<_> = old_members
+            .0
+            .difference(&members.0)
+            .map(String::as_str)
+            .collect();
+
+        if !removed.is_empty() {
+            layer.write_packet(&TeamS2c {
+                team_name: &team.0,
+                mode: Mode::RemoveEntities { entities: removed },
+            });
+        }
+
+        let added: Vec<_> = members
+            .0
+            .difference(&old_members.0)
+            .map(String::as_str)
+            .collect();
+
+        if !added.is_empty() {
+            layer.write_packet(&TeamS2c {
+                team_name: &team.0,
+                mode: Mode::AddEntities { entities: added },
+            });
+        }
+
+        old_members.0 = members.0.clone();
+    }
+}
+
+pub(crate) fn remove_despawned_teams(
+    mut commands: Commands,
+    teams: Query<(Entity, &Team, &EntityLayerId), With<Despawned>>,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (entity, team, entity_layer) in &teams {
+        commands.entity(entity).despawn();
+
+        let Ok(mut layer) = layers.get_mut(entity_layer.0) else {
+            warn!(
+                "No layer found for entity layer ID {:?}, can't remove team",
+                entity_layer
+            );
+            continue;
+        };
+
+        layer.write_packet(&TeamS2c {
+            team_name: &team.0,
+            mode: Mode::RemoveTeam,
+        });
+    }
+}
+
+/// Sends the teams of newly visible layers to clients and removes the teams of
+/// layers that are no longer visible.
+pub(crate) fn send_teams_to_clients(
+    mut clients: Query<
+        (&mut Client, &VisibleEntityLayers, &OldVisibleEntityLayers),
+        Or<(Added<Client>, Changed<VisibleEntityLayers>)>,
+    >,
+    teams: Query<(&Team, &TeamSettings, &TeamMembers, &EntityLayerId), Without<Despawned>>,
+) {
+    for (mut client, visible_layers, old_visible_layers) in &mut clients {
+        let is_new = client.is_added();
+
+        for (team, settings, members, layer) in &teams {
+            let visible = visible_layers.0.contains(&layer.0);
+            let was_visible = !is_new && old_visible_layers.get().contains(&layer.0);
+
+            if was_visible && !visible {
+                client.write_packet(&TeamS2c {
+                    team_name: &team.0,
+                    mode: Mode::RemoveTeam,
+                });
+            } else if visible && !was_visible {
+                let entities = members.iter().map(String::as_str).collect();
+
+                client.write_packet(&TeamS2c {
+                    team_name: &team.0,
+                    mode: create_mode(settings, entities),
+                });
+            }
+        }
+    }
+}
@@ -7,11 +7,12 @@ use valence_protocol::{GameMode, WritePacket};
 
 use crate::client::{update_game_mode, Client, UpdateClientsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 /// [`Component`] that stores the player's flying speed ability.
 ///
 /// [`Default`] value: `0.05`.
-#[derive(Component, Deref, DerefMut)]
+#[derive(Component, Copy, Clone, PartialEq, Debug, Deref, DerefMut)]
 pub struct FlyingSpeed(pub f32);
 
 impl Default for FlyingSpeed {
@@ -24,7 +25,7 @@ impl Default for FlyingSpeed {
 /// The lower the value, the higher the field of view.
 ///
 /// [`Default`] value: `0.1`.
-#[derive(Component, Deref, DerefMut)]
+#[derive(Component, Copy, Clone, PartialEq, Debug, Deref, DerefMut)]
 pub struct FovModifier(pub f32);
 
 impl Default for FovModifier {
@@ -33,39 +34,49 @@ impl Default for FovModifier {
     }
 }
 
-/// Send if the client sends [`UpdatePlayerAbilitiesC2s::StartFlying`]
+/// Sent when a client starts flying, either because it asked to or because it
+/// was put in [`GameMode::Spectator`].
 #[derive(Event)]
 pub struct PlayerStartFlyingEvent {
     pub client: Entity,
 }
 
-/// Send if the client sends [`UpdatePlayerAbilitiesC2s::StopFlying`]
+/// Sent when a client stops flying, either because it asked to or because it
+/// was put in a game mode without flight.
 #[derive(Event)]
 pub struct PlayerStopFlyingEvent {
     pub client: Entity,
 }
 
+/// Sent when a client asks to start flying without the
+/// [`allow_flying`](PlayerAbilitiesFlags::allow_flying) ability. The client is
+/// told its abilities again, which puts it back on the ground.
+#[derive(Event)]
+pub struct PlayerFlyingRejectedEvent {
+    pub client: Entity,
+}
+
 /// Order of execution:
 /// 1. `update_game_mode`: Watch [`GameMode`] changes => Send
-/// `GameStateChangeS2c` to update the client's gamemode
+///    `GameStateChangeS2c` to update the client's gamemode
 ///
-/// 2. `update_client_player_abilities`: Watch [`PlayerAbilitiesFlags`],
-/// [`FlyingSpeed`] and [`FovModifier`] changes => Send [`PlayerAbilitiesS2c`]
-/// to update the client's abilities
+/// 2. `update_player_abilities`: Watch [`GameMode`] changes => Update
+///    [`PlayerAbilitiesFlags`] according to the [`GameMode`] with
+///    [`game_mode_abilities`]
 ///
-/// 3. `update_player_abilities`: Watch
-/// [`GameMode`] changes => Update [`PlayerAbilitiesFlags`] according to the
-/// [`GameMode`]
+/// 3. `update_client_player_abilities`: Watch [`PlayerAbilitiesFlags`],
+///    [`FlyingSpeed`] and [`FovModifier`] changes => Send
+///    [`PlayerAbilitiesS2c`] to update the client's abilities
 ///
-/// 4. `update_server_player_abilities`: Watch
-/// [`UpdatePlayerAbilitiesC2s`] packets => Update [`PlayerAbilitiesFlags`]
-/// according to the packet
+/// 4. `update_server_player_abilities`: Watch [`UpdatePlayerAbilitiesC2s`]
+///    packets => Update [`PlayerAbilitiesFlags`] according to the packet
 pub struct AbilitiesPlugin;
 
 impl Plugin for AbilitiesPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<PlayerStartFlyingEvent>()
             .add_event::<PlayerStopFlyingEvent>()
+            .add_event::<PlayerFlyingRejectedEvent>()
             .add_systems(
                 PostUpdate,
                 (
@@ -79,6 +90,31 @@ impl Plugin for AbilitiesPlugin {
     }
 }
 
+/// Returns `flags` with the abilities of `game_mode`, like the vanilla server
+/// does when the game mode of a player changes. Players in creative mode keep
+/// flying if they were.
+pub fn game_mode_abilities(
+    game_mode: GameMode,
+    flags: PlayerAbilitiesFlags,
+) -> PlayerAbilitiesFlags {
+    match game_mode {
+        GameMode::Creative => flags
+            .with_invulnerable(true)
+            .with_allow_flying(true)
+            .with_instant_break(true),
+        GameMode::Spectator => flags
+            .with_invulnerable(true)
+            .with_allow_flying(true)
+            .with_instant_break(false)
+            .with_flying(true),
+        GameMode::Survival | GameMode::Adventure => flags
+            .with_invulnerable(false)
+            .with_allow_flying(false)
+            .with_instant_break(false)
+            .with_flying(false),
+    }
+}
+
 fn update_client_player_abilities(
     mut clients_query: Query<
         (
@@ -103,67 +139,59 @@ fn update_client_player_abilities(
     }
 }
 
-/// /!\ This system does not trigger change detection on
-/// [`PlayerAbilitiesFlags`]
 fn update_player_abilities(
     mut player_start_flying_event_writer: EventWriter<PlayerStartFlyingEvent>,
     mut player_stop_flying_event_writer: EventWriter<PlayerStopFlyingEvent>,
     mut client_query: Query<(Entity, &mut PlayerAbilitiesFlags, &GameMode), Changed<GameMode>>,
 ) {
-    for (entity, mut mut_flags, gamemode) in client_query.iter_mut() {
-        let flags = mut_flags.bypass_change_detection();
-        match gamemode {
-            GameMode::Creative => {
-                flags.set_invulnerable(true);
-                flags.set_allow_flying(true);
-                flags.set_instant_break(true);
-            }
-            GameMode::Spectator => {
-                flags.set_invulnerable(true);
-                flags.set_allow_flying(true);
-                flags.set_instant_break(false);
-                flags.set_flying(true);
-                player_start_flying_event_writer.send(PlayerStartFlyingEvent { client: entity });
-            }
-            GameMode::Survival => {
-                flags.set_invulnerable(false);
-                flags.set_allow_flying(false);
-                flags.set_instant_break(false);
-                flags.set_flying(false);
-                player_stop_flying_event_writer.send(PlayerStopFlyingEvent { client: entity });
+    for (entity, mut flags, game_mode) in client_query.iter_mut() {
+        let was_flying = flags.flying();
+
+        // Changing the flags sends them to the client, which resets its
+        // abilities when its game mode changes.
+        flags.set_if_neq(game_mode_abilities(*game_mode, *flags));
+
+        match (was_flying, flags.flying()) {
+            (false, true) => {
+                player_start_flying_event_writer.send(PlayerStartFlyingEvent { client: entity })
             }
-            GameMode::Adventure => {
-                flags.set_invulnerable(false);
-                flags.set_allow_flying(false);
-                flags.set_instant_break(false);
-                flags.set_flying(false);
-                player_stop_flying_event_writer.send(PlayerStopFlyingEvent { client: entity });
+            (true, false) => {
+                player_stop_flying_event_writer.send(PlayerStopFlyingEvent { client: entity })
             }
+            _ => {}
         }
     }
 }
 
 /// /!\ This system does not trigger change detection on
-/// [`PlayerAbilitiesFlags`]
+/// [`PlayerAbilitiesFlags`], unless the client isn't allowed to fly.
 fn update_server_player_abilities(
     mut packet_events: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut player_start_flying_event_writer: EventWriter<PlayerStartFlyingEvent>,
     mut player_stop_flying_event_writer: EventWriter<PlayerStopFlyingEvent>,
+    mut player_flying_rejected_event_writer: EventWriter<PlayerFlyingRejectedEvent>,
     mut client_query: Query<&mut PlayerAbilitiesFlags>,
 ) {
     for packets in packet_events.read() {
-        if let Some(pkt) = packets.decode::<UpdatePlayerAbilitiesC2s>() {
+        if let Some(pkt) = packets.decode_reporting::<UpdatePlayerAbilitiesC2s>(&errors) {
             if let Ok(mut mut_flags) = client_query.get_mut(packets.client) {
-                let flags = mut_flags.bypass_change_detection();
                 match pkt {
+                    UpdatePlayerAbilitiesC2s::StartFlying if !mut_flags.allow_flying() => {
+                        // Send the abilities again to correct the client.
+                        mut_flags.set_changed();
+                        player_flying_rejected_event_writer.send(PlayerFlyingRejectedEvent {
+                            client: packets.client,
+                        });
+                    }
                     UpdatePlayerAbilitiesC2s::StartFlying => {
-                        flags.set_flying(true);
+                        mut_flags.bypass_change_detection().set_flying(true);
                         player_start_flying_event_writer.send(PlayerStartFlyingEvent {
                             client: packets.client,
                         });
                     }
                     UpdatePlayerAbilitiesC2s::StopFlying => {
-                        flags.set_flying(false);
+                        mut_flags.bypass_change_detection().set_flying(false);
                         player_stop_flying_event_writer.send(PlayerStopFlyingEvent {
                             client: packets.client,
                         });
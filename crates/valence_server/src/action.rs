@@ -6,14 +6,20 @@ use valence_protocol::packets::play::{PlayerActionC2s, PlayerActionResponseS2c};
 use valence_protocol::{BlockPos, Direction, VarInt, WritePacket};
 
 use crate::client::{Client, UpdateClientsSet};
-use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::event_loop::{
+    CancelledPackets, EventLoopPreUpdate, HandleActionPacketsSet, PacketEvent,
+};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct ActionPlugin;
 
 impl Plugin for ActionPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<DiggingEvent>()
-            .add_systems(EventLoopPreUpdate, handle_player_action)
+            .add_systems(
+                EventLoopPreUpdate,
+                handle_player_action.in_set(HandleActionPacketsSet),
+            )
             .add_systems(
                 PostUpdate,
                 acknowledge_player_actions.in_set(UpdateClientsSet),
@@ -52,14 +58,20 @@ impl ActionSequence {
 fn handle_player_action(
     mut clients: Query<&mut ActionSequence>,
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    cancelled: Res<CancelledPackets>,
     mut digging_events: EventWriter<DiggingEvent>,
 ) {
-    for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<PlayerActionC2s>() {
+    for (packet, id) in packets.read_with_id() {
+        if let Some(pkt) = packet.decode_reporting::<PlayerActionC2s>(&errors) {
             if let Ok(mut seq) = clients.get_mut(packet.client) {
                 seq.update(pkt.sequence.0);
             }
 
+            if cancelled.is_cancelled(id) {
+                continue;
+            }
+
             // TODO: check that digging is happening within configurable distance to client.
             // TODO: check that blocks are being broken at the appropriate speeds.
 
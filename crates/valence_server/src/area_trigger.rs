@@ -0,0 +1,156 @@
+//! Events for clients entering and leaving areas of a layer.
+//!
+//! Spawn an entity with an [`AreaTrigger`] to be notified with an
+//! [`AreaEnterEvent`] when a client's hitbox starts overlapping the area, and
+//! with an [`AreaLeaveEvent`] when it stops overlapping, such as for portals,
+//! shop zones and minigame triggers. Clients count as inside a trigger while
+//! they are in its layer, so switching layers also leaves a trigger.
+//!
+//! If the trigger entity has a [`Position`], the area is relative to it. This
+//! allows attaching triggers to entities, such as the zone around an NPC or
+//! an interaction entity, which then move along with it.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_math::{Aabb, DVec3};
+//! use valence_server::area_trigger::{AreaEnterEvent, AreaTrigger};
+//!
+//! fn spawn_portal(layer: Entity, mut commands: Commands) {
+//!     let area = Aabb::new(DVec3::new(0.0, 64.0, 0.0), DVec3::new(2.0, 67.0, 1.0));
+//!     commands.spawn(AreaTrigger::new(layer, area));
+//! }
+//!
+//! fn use_portals(mut events: EventReader<AreaEnterEvent>) {
+//!     for event in events.read() {
+//!         println!("{:?} entered portal {:?}", event.client, event.trigger);
+//!     }
+//! }
+//! # let _ = (spawn_portal, use_portals);
+//! ```
+
+use std::collections::BTreeSet;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::hitbox::{Hitbox, HitboxUpdateSet};
+use valence_entity::{EntityLayerId, Position};
+use valence_math::Aabb;
+use valence_server_common::Despawned;
+
+use crate::client::{Client, UpdateClientsSet};
+
+pub struct AreaTriggerPlugin;
+
+impl Plugin for AreaTriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AreaEnterEvent>()
+            .add_event::<AreaLeaveEvent>()
+            .add_systems(
+                PostUpdate,
+                update_area_triggers
+                    .after(HitboxUpdateSet)
+                    .before(UpdateClientsSet),
+            );
+    }
+}
+
+/// An area of a layer which sends events when clients enter and leave it.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct AreaTrigger {
+    /// The entity layer the area is in.
+    pub layer: Entity,
+    /// The area, relative to the [`Position`] of the trigger entity if it has
+    /// one.
+    pub area: Aabb,
+    occupants: BTreeSet<Entity>,
+}
+
+impl AreaTrigger {
+    pub fn new(layer: Entity, area: Aabb) -> Self {
+        Self {
+            layer,
+            area,
+            occupants: BTreeSet::new(),
+        }
+    }
+
+    /// Returns whether the client was inside the area as of the last update.
+    pub fn contains(&self, client: Entity) -> bool {
+        self.occupants.contains(&client)
+    }
+
+    /// Returns the clients which were inside the area as of the last update.
+    pub fn occupants(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.occupants.iter().copied()
+    }
+}
+
+/// Sent when a client enters an [`AreaTrigger`].
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AreaEnterEvent {
+    pub trigger: Entity,
+    pub client: Entity,
+}
+
+/// Sent when a client leaves an [`AreaTrigger`], including when the client
+/// disconnects or the trigger is marked [`Despawned`].
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AreaLeaveEvent {
+    pub trigger: Entity,
+    pub client: Entity,
+}
+
+fn update_area_triggers(
+    mut triggers: Query<(Entity, &mut AreaTrigger, Option<&Position>, Has<Despawned>)>,
+    clients: Query<
+        (Entity, &EntityLayerId, &Position, Option<&Hitbox>),
+        (With<Client>, Without<Despawned>),
+    >,
+    mut enter_events: EventWriter<AreaEnterEvent>,
+    mut leave_events: EventWriter<AreaLeaveEvent>,
+) {
+    for (trigger_id, mut trigger, trigger_pos, despawned) in &mut triggers {
+        let area = match trigger_pos {
+            Some(pos) => trigger.area + pos.0,
+            None => trigger.area,
+        };
+
+        let inside: BTreeSet<Entity> = if despawned {
+            BTreeSet::new()
+        } else {
+            clients
+                .iter()
+                .filter(|(_, layer, pos, hitbox)| {
+                    layer.0 == trigger.layer
+                        && match hitbox {
+                            Some(hitbox) => hitbox.get().intersects(area),
+                            None => area.contains_point(pos.0),
+                        }
+                })
+                .map(|(client, ..)| client)
+                .collect()
+        };
+
+        if inside == trigger.occupants {
+            continue;
+        }
+
+        for &client in trigger.occupants.difference(&inside) {
+            leave_events.send(AreaLeaveEvent {
+                trigger: trigger_id,
+                client,
+            });
+        }
+
+        for &client in inside.difference(&trigger.occupants) {
+            enter_events.send(AreaEnterEvent {
+                trigger: trigger_id,
+                client,
+            });
+        }
+
+        trigger.occupants = inside;
+    }
+}
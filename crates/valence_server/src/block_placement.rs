@@ -0,0 +1,431 @@
+//! Server-side validation of block placements.
+//!
+//! Valence does not place blocks on its own. Systems that place blocks in
+//! response to [`InteractBlockEvent`]s should use [`BlockPlacement`] to
+//! validate the placement, and [`resync_block`] to undo the client's
+//! prediction when the placement is rejected. This prevents ghost blocks and
+//! placements that would be impossible in vanilla.
+//!
+//! [`InteractBlockEvent`]: crate::interact_block::InteractBlockEvent
+
+use std::sync::OnceLock;
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use valence_entity::hitbox::Hitbox;
+use valence_entity::living::LivingEntity;
+use valence_math::{Aabb, DVec3};
+use valence_protocol::block::{BlockTag, PropName, PropValue};
+use valence_protocol::packets::play::BlockUpdateS2c;
+use valence_protocol::{BlockKind, BlockPos, BlockState, ChunkPos, Direction, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::client::Client;
+use crate::{ChunkLayer, EntityLayer, GameMode};
+
+/// The reason a block placement was rejected.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PlacementError {
+    /// The clicked block is air, which clients cannot place against.
+    ClickedAir,
+    /// The target position is outside of the loaded chunks of the layer.
+    Unloaded,
+    /// The target position is occupied by a block that cannot be replaced.
+    Occupied,
+    /// The block would intersect the hitbox of this entity.
+    Obstructed(Entity),
+    /// The block needs to be attached to a sturdy face of a neighboring block
+    /// but there is none.
+    Unsupported,
+}
+
+/// [`SystemParam`] for validating block placements against the blocks and
+/// entities of a layer.
+#[derive(SystemParam)]
+pub struct BlockPlacement<'w, 's> {
+    entity_layers: Query<'w, 's, &'static EntityLayer>,
+    obstacles: Query<
+        'w,
+        's,
+        (&'static Hitbox, Option<&'static GameMode>),
+        (With<LivingEntity>, Without<Despawned>),
+    >,
+}
+
+impl BlockPlacement<'_, '_> {
+    /// Validates placing `state` against the `face` of the block at `clicked`
+    /// in `chunk_layer`. The entities of the entity layer `entity_layer` are
+    /// checked for obstruction.
+    ///
+    /// On success, returns the position the block should be placed at. This
+    /// is `clicked` itself if the clicked block is replaceable (like tall
+    /// grass), and the neighbor in the direction of `face` otherwise.
+    pub fn validate(
+        &self,
+        chunk_layer: &ChunkLayer,
+        entity_layer: Entity,
+        clicked: BlockPos,
+        face: Direction,
+        state: BlockState,
+    ) -> Result<BlockPos, PlacementError> {
+        let pos = placement_target(chunk_layer, clicked, face)?;
+
+        if let Some(entity) = self.obstructing_entity(entity_layer, pos, state) {
+            return Err(PlacementError::Obstructed(entity));
+        }
+
+        if !has_support(chunk_layer, pos, state) {
+            return Err(PlacementError::Unsupported);
+        }
+
+        Ok(pos)
+    }
+
+    /// Returns an entity whose hitbox intersects the collision shapes of
+    /// `state` placed at `pos`, if any. Spectators never obstruct placements.
+    pub fn obstructing_entity(
+        &self,
+        entity_layer: Entity,
+        pos: BlockPos,
+        state: BlockState,
+    ) -> Option<Entity> {
+        let layer = self.entity_layers.get(entity_layer).ok()?;
+        let offset = DVec3::new(pos.x as f64, pos.y as f64, pos.z as f64);
+        let center = ChunkPos::from(pos);
+
+        // Hitboxes can extend into neighboring chunks.
+        for cz in center.z - 1..=center.z + 1 {
+            for cx in center.x - 1..=center.x + 1 {
+                for entity in layer.entities_at(ChunkPos::new(cx, cz)) {
+                    let Ok((hitbox, mode)) = self.obstacles.get(entity) else {
+                        continue;
+                    };
+
+                    if mode == Some(&GameMode::Spectator) {
+                        continue;
+                    }
+
+                    if state
+                        .collision_shapes()
+                        .any(|shape| overlaps(shape + offset, hitbox.get()))
+                    {
+                        return Some(entity);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Returns the position a block placed against the `face` of `clicked` ends up
+/// at, checking that the clicked block is not air and the target can be
+/// replaced.
+pub fn placement_target(
+    chunk_layer: &ChunkLayer,
+    clicked: BlockPos,
+    face: Direction,
+) -> Result<BlockPos, PlacementError> {
+    let clicked_state = chunk_layer
+        .block(clicked)
+        .ok_or(PlacementError::Unloaded)?
+        .state;
+
+    if clicked_state.is_air() {
+        return Err(PlacementError::ClickedAir);
+    }
+
+    if clicked_state.is_replaceable() {
+        return Ok(clicked);
+    }
+
+    let pos = clicked.get_in_direction(face);
+
+    let target = chunk_layer.block(pos).ok_or(PlacementError::Unloaded)?;
+
+    if !target.state.is_replaceable() {
+        return Err(PlacementError::Occupied);
+    }
+
+    Ok(pos)
+}
+
+/// Returns whether `state` placed at `pos` is attached to a block that can
+/// hold it. Blocks which do not need support always return `true`.
+pub fn has_support(chunk_layer: &ChunkLayer, pos: BlockPos, state: BlockState) -> bool {
+    let Some(dir) = support_direction(state) else {
+        return true;
+    };
+
+    chunk_layer
+        .block(pos.get_in_direction(dir))
+        .is_some_and(|b| is_face_sturdy(b.state, opposite(dir)))
+}
+
+/// Returns the state to place for `kind` when placing against `face`, picking
+/// the wall variant and orientation the way vanilla clients predict it.
+pub fn oriented_state(kind: BlockKind, face: Direction) -> BlockState {
+    let state = kind.to_state();
+
+    let facing = match face {
+        Direction::North => PropValue::North,
+        Direction::South => PropValue::South,
+        Direction::West => PropValue::West,
+        Direction::East => PropValue::East,
+        Direction::Down | Direction::Up => {
+            let face_value = if face == Direction::Up {
+                PropValue::Floor
+            } else {
+                PropValue::Ceiling
+            };
+
+            return state
+                .set(PropName::Face, face_value)
+                .set(PropName::Axis, PropValue::Y);
+        }
+    };
+
+    if let Some(wall) = state.wall_block_id() {
+        return wall.set(PropName::Facing, facing);
+    }
+
+    let axis = match face {
+        Direction::North | Direction::South => PropValue::Z,
+        _ => PropValue::X,
+    };
+
+    state
+        .set(PropName::Face, PropValue::Wall)
+        .set(PropName::Facing, facing)
+        .set(PropName::Axis, axis)
+}
+
+/// Sends the true state of the block at `pos` to `client`, undoing any
+/// prediction the client made about it.
+pub fn resync_block(client: &mut Client, chunk_layer: &ChunkLayer, pos: BlockPos) {
+    if let Some(block) = chunk_layer.block(pos) {
+        client.write_packet(&BlockUpdateS2c {
+            position: pos,
+            block_id: block.state,
+        });
+    }
+}
+
+/// Returns the direction from a block to the neighbor it needs to be attached
+/// to, or `None` if it doesn't need support.
+///
+/// This is derived from the block's properties, its floor and wall variants,
+/// and vanilla block tags. Blocks which aren't covered by any of these, like
+/// redstone wire, are assumed not to need support.
+fn support_direction(state: BlockState) -> Option<Direction> {
+    let facing = || match state.get(PropName::Facing)? {
+        PropValue::North => Some(Direction::North),
+        PropValue::South => Some(Direction::South),
+        PropValue::West => Some(Direction::West),
+        PropValue::East => Some(Direction::East),
+        _ => None,
+    };
+
+    // Buttons, levers, and grindstones.
+    match state.get(PropName::Face) {
+        Some(PropValue::Floor) => return Some(Direction::Down),
+        Some(PropValue::Ceiling) => return Some(Direction::Up),
+        Some(PropValue::Wall) => return facing().map(opposite),
+        _ => {}
+    }
+
+    let kind = state.to_kind();
+
+    if kind.is_in(BlockTag::CEILING_HANGING_SIGNS) {
+        return Some(Direction::Up);
+    }
+
+    // Wall hanging signs hang between the blocks to their sides rather than on
+    // the block behind them.
+    if kind.is_in(BlockTag::WALL_HANGING_SIGNS) {
+        return None;
+    }
+
+    // Torches, signs, banners, and coral fans have no collision and stand on
+    // the floor, while their wall variants hang on the block behind them.
+    // Heads have the same variants but don't need support.
+    if state.collision_shapes().next().is_none() {
+        if state.wall_block_id().is_some() {
+            return Some(Direction::Down);
+        }
+
+        if wall_variants().binary_search(&kind).is_ok() {
+            return facing().map(opposite);
+        }
+    }
+
+    // Ladders. Other climbable blocks like vines have no facing.
+    if kind.is_in(BlockTag::CLIMBABLE) {
+        return facing().map(opposite);
+    }
+
+    const FLOOR_TAGS: [BlockTag; 7] = [
+        BlockTag::RAILS,
+        BlockTag::WOOL_CARPETS,
+        BlockTag::PRESSURE_PLATES,
+        BlockTag::DOORS,
+        BlockTag::SAPLINGS,
+        BlockTag::SMALL_FLOWERS,
+        BlockTag::TALL_FLOWERS,
+    ];
+
+    if FLOOR_TAGS.iter().any(|&tag| kind.is_in(tag)) {
+        return Some(Direction::Down);
+    }
+
+    None
+}
+
+/// Returns the block kinds which are the wall variant of another block kind,
+/// sorted.
+fn wall_variants() -> &'static [BlockKind] {
+    static WALL_VARIANTS: OnceLock<Vec<BlockKind>> = OnceLock::new();
+
+    WALL_VARIANTS.get_or_init(|| {
+        let mut kinds: Vec<_> = BlockKind::ALL
+            .iter()
+            .filter_map(|kind| Some(kind.to_state().wall_block_id()?.to_kind()))
+            .collect();
+
+        kinds.sort_unstable();
+        kinds.dedup();
+        kinds
+    })
+}
+
+/// Returns whether the side `face` of `state` is fully covered by one of its
+/// collision shapes.
+fn is_face_sturdy(state: BlockState, face: Direction) -> bool {
+    state.collision_shapes().any(|shape| {
+        let (min, max) = (shape.min(), shape.max());
+
+        let covers =
+            |a: usize, b: usize| min[a] <= 0.0 && max[a] >= 1.0 && min[b] <= 0.0 && max[b] >= 1.0;
+
+        match face {
+            Direction::Down => min.y <= 0.0 && covers(0, 2),
+            Direction::Up => max.y >= 1.0 && covers(0, 2),
+            Direction::North => min.z <= 0.0 && covers(0, 1),
+            Direction::South => max.z >= 1.0 && covers(0, 1),
+            Direction::West => min.x <= 0.0 && covers(1, 2),
+            Direction::East => max.x >= 1.0 && covers(1, 2),
+        }
+    })
+}
+
+/// Like [`Aabb::intersects`], but boxes that only touch do not overlap.
+fn overlaps(a: Aabb, b: Aabb) -> bool {
+    const EPSILON: f64 = 1e-7;
+
+    a.max().x - EPSILON > b.min().x
+        && b.max().x - EPSILON > a.min().x
+        && a.max().y - EPSILON > b.min().y
+        && b.max().y - EPSILON > a.min().y
+        && a.max().z - EPSILON > b.min().z
+        && b.max().z - EPSILON > a.min().z
+}
+
+fn opposite(dir: Direction) -> Direction {
+    match dir {
+        Direction::Down => Direction::Up,
+        Direction::Up => Direction::Down,
+        Direction::North => Direction::South,
+        Direction::South => Direction::North,
+        Direction::West => Direction::East,
+        Direction::East => Direction::West,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(state: BlockState, facing: PropValue) -> BlockState {
+        state.set(PropName::Facing, facing)
+    }
+
+    #[test]
+    fn floor_blocks_need_support_below() {
+        for state in [
+            BlockState::TORCH,
+            BlockState::OAK_SIGN,
+            BlockState::WHITE_BANNER,
+            BlockState::TUBE_CORAL_FAN,
+            BlockState::RAIL,
+            BlockState::WHITE_CARPET,
+            BlockState::STONE_PRESSURE_PLATE,
+            BlockState::OAK_DOOR,
+            BlockState::OAK_SAPLING,
+            BlockState::POPPY,
+            BlockState::SUNFLOWER,
+        ] {
+            assert_eq!(support_direction(state), Some(Direction::Down), "{state:?}");
+        }
+    }
+
+    #[test]
+    fn wall_blocks_need_support_behind() {
+        for state in [
+            BlockState::WALL_TORCH,
+            BlockState::OAK_WALL_SIGN,
+            BlockState::WHITE_WALL_BANNER,
+            BlockState::TUBE_CORAL_WALL_FAN,
+            BlockState::LADDER,
+        ] {
+            assert_eq!(
+                support_direction(wall(state, PropValue::North)),
+                Some(Direction::South),
+                "{state:?}"
+            );
+        }
+
+        let button = BlockState::STONE_BUTTON
+            .set(PropName::Face, PropValue::Wall)
+            .set(PropName::Facing, PropValue::East);
+        assert_eq!(support_direction(button), Some(Direction::West));
+    }
+
+    #[test]
+    fn ceiling_blocks_need_support_above() {
+        let lever = BlockState::LEVER.set(PropName::Face, PropValue::Ceiling);
+
+        assert_eq!(support_direction(lever), Some(Direction::Up));
+        assert_eq!(
+            support_direction(BlockState::OAK_HANGING_SIGN),
+            Some(Direction::Up)
+        );
+    }
+
+    #[test]
+    fn free_standing_blocks_need_no_support() {
+        for state in [
+            BlockState::STONE,
+            BlockState::SKELETON_SKULL,
+            BlockState::SKELETON_WALL_SKULL,
+            BlockState::OAK_WALL_HANGING_SIGN,
+            BlockState::VINE,
+            BlockState::OAK_STAIRS,
+        ] {
+            assert_eq!(support_direction(state), None, "{state:?}");
+        }
+    }
+
+    #[test]
+    fn sturdy_faces() {
+        assert!(is_face_sturdy(BlockState::STONE, Direction::Up));
+
+        let bottom_slab = BlockState::OAK_SLAB.set(PropName::Type, PropValue::Bottom);
+        assert!(is_face_sturdy(bottom_slab, Direction::Down));
+        assert!(!is_face_sturdy(bottom_slab, Direction::Up));
+        assert!(!is_face_sturdy(bottom_slab, Direction::North));
+
+        assert!(!is_face_sturdy(BlockState::AIR, Direction::Up));
+        assert!(!is_face_sturdy(BlockState::OAK_FENCE, Direction::Up));
+    }
+}
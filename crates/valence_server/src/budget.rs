@@ -0,0 +1,365 @@
+//! Spreading long-running operations across ticks.
+//!
+//! Operations like filling a large region, generating chunks ahead of time,
+//! or rolling back many block changes can take longer than a tick. Such
+//! operations are split into many small items of work and spawned as a
+//! [`BudgetedTask`]. Every tick, the tasks get a share of the [`WorkBudget`]
+//! and run items until their share is used up, continuing on the next tick.
+//!
+//! A [`TaskProgressEvent`] is sent for every task that made progress in a
+//! tick, and a [`TaskCompletedEvent`] when a task has run all of its items.
+//! Despawning the entity of a task cancels it.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::budget::TaskCompletedEvent;
+//! use valence_server::layer::chunk::region::{self, Region};
+//! use valence_server::BlockState;
+//!
+//! #[derive(Resource)]
+//! struct ArenaReset(Entity);
+//!
+//! fn reset_arena(layer: Entity, mut commands: Commands) {
+//!     let arena = Region::new([-500, 0, -500], [500, 100, 500]);
+//!
+//!     let task = commands
+//!         .spawn(region::fill_task(layer, arena, BlockState::AIR))
+//!         .id();
+//!
+//!     commands.insert_resource(ArenaReset(task));
+//! }
+//!
+//! fn arena_reset(mut events: EventReader<TaskCompletedEvent>, reset: Res<ArenaReset>) {
+//!     for event in events.read() {
+//!         if event.task == reset.0 {
+//!             println!("the arena is ready");
+//!         }
+//!     }
+//! }
+//! # let _ = (reset_arena, arena_reset);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server_common::Server;
+
+use crate::ChunkLayer;
+
+pub struct WorkBudgetPlugin;
+
+impl Plugin for WorkBudgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorkBudget>()
+            .insert_resource(TickStart(Instant::now()))
+            .add_event::<TaskProgressEvent>()
+            .add_event::<TaskCompletedEvent>()
+            .add_systems(First, record_tick_start)
+            .add_systems(Update, run_budgeted_tasks);
+    }
+}
+
+/// How much time [`BudgetedTask`]s may take per tick.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct WorkBudget {
+    /// The most time spent on all tasks together in a tick.
+    ///
+    /// # Default Value
+    ///
+    /// 10 milliseconds
+    pub per_tick: Duration,
+    /// The fraction of the tick period after which no more time is given to
+    /// tasks, counted from the start of the tick. Ticks which are already
+    /// slow because of other work leave less time for tasks.
+    ///
+    /// # Default Value
+    ///
+    /// `0.8`
+    pub max_tick_usage: f64,
+}
+
+impl Default for WorkBudget {
+    fn default() -> Self {
+        Self {
+            per_tick: Duration::from_millis(10),
+            max_tick_usage: 0.8,
+        }
+    }
+}
+
+impl WorkBudget {
+    /// Returns the time tasks may take in the current tick, given when the
+    /// tick started.
+    fn available(&self, server: &Server, tick_start: Instant) -> Duration {
+        let tick_period = Duration::from_secs_f64((server.tick_rate().get() as f64).recip());
+        let tick_limit = tick_period.mul_f64(self.max_tick_usage.clamp(0.0, 1.0));
+
+        tick_limit
+            .saturating_sub(tick_start.elapsed())
+            .min(self.per_tick)
+    }
+}
+
+/// An iterator which is consumed in slices of limited time, keeping track of
+/// how many items were taken.
+#[derive(Clone, Debug)]
+pub struct TimeSliced<I> {
+    iter: I,
+    done: u64,
+    total: Option<u64>,
+    finished: bool,
+}
+
+impl<I: Iterator> TimeSliced<I> {
+    pub fn new(iter: impl IntoIterator<IntoIter = I>) -> Self {
+        let iter = iter.into_iter();
+
+        let total = match iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(lower as u64),
+            _ => None,
+        };
+
+        Self {
+            iter,
+            done: 0,
+            total,
+            finished: false,
+        }
+    }
+
+    /// Calls `f` with the next items until `deadline` has passed or there are
+    /// no items left. At least one item is taken, so the iterator always
+    /// makes progress. Returns whether there are no items left.
+    pub fn run_until(&mut self, deadline: Instant, mut f: impl FnMut(I::Item)) -> bool {
+        while !self.finished {
+            match self.iter.next() {
+                Some(item) => {
+                    f(item);
+                    self.done += 1;
+
+                    // Notice the end right away if the iterator knows it.
+                    if self.iter.size_hint().1 == Some(0) {
+                        self.finished = true;
+                    } else if Instant::now() >= deadline {
+                        break;
+                    }
+                }
+                None => self.finished = true,
+            }
+        }
+
+        self.finished
+    }
+
+    /// The number of items taken so far.
+    pub fn done(&self) -> u64 {
+        self.done
+    }
+
+    /// The total number of items, if the iterator knows its exact length.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Whether there are no items left.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+/// A long-running operation, split into items of work which are run across
+/// ticks within the [`WorkBudget`]. Spawn it as an entity to start it. The
+/// entity is despawned when the task is completed.
+///
+/// Every task runs at least one item per tick, so items should be small. For
+/// example, a task editing blocks could handle one chunk per item.
+#[derive(Component)]
+pub struct BudgetedTask {
+    steps: Box<dyn TaskSteps>,
+}
+
+impl BudgetedTask {
+    /// Creates a task which calls `f` with the world and every item of
+    /// `items`.
+    pub fn new<I, F>(items: I, f: F) -> Self
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + Sync + 'static,
+        F: FnMut(&mut World, I::Item) + Send + Sync + 'static,
+    {
+        Self {
+            steps: Box::new(Steps {
+                items: TimeSliced::new(items),
+                f,
+            }),
+        }
+    }
+
+    /// Creates a task which calls `f` with the [`ChunkLayer`] of the entity
+    /// `layer` and every item of `items`. Items are skipped if the layer does
+    /// not exist.
+    pub fn for_layer<I, F>(layer: Entity, items: I, mut f: F) -> Self
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + Sync + 'static,
+        F: FnMut(&mut ChunkLayer, I::Item) + Send + Sync + 'static,
+    {
+        Self::new(items, move |world, item| {
+            if let Some(mut layer) = world.get_mut::<ChunkLayer>(layer) {
+                f(&mut layer, item);
+            }
+        })
+    }
+
+    /// The number of items run so far.
+    pub fn done(&self) -> u64 {
+        self.steps.done()
+    }
+
+    /// The total number of items, if known.
+    pub fn total(&self) -> Option<u64> {
+        self.steps.total()
+    }
+}
+
+trait TaskSteps: Send + Sync + 'static {
+    fn run_until(&mut self, world: &mut World, deadline: Instant) -> bool;
+
+    fn done(&self) -> u64;
+
+    fn total(&self) -> Option<u64>;
+}
+
+struct Steps<I, F> {
+    items: TimeSliced<I>,
+    f: F,
+}
+
+impl<I, F> TaskSteps for Steps<I, F>
+where
+    I: Iterator + Send + Sync + 'static,
+    F: FnMut(&mut World, I::Item) + Send + Sync + 'static,
+{
+    fn run_until(&mut self, world: &mut World, deadline: Instant) -> bool {
+        let f = &mut self.f;
+        self.items.run_until(deadline, |item| f(world, item))
+    }
+
+    fn done(&self) -> u64 {
+        self.items.done()
+    }
+
+    fn total(&self) -> Option<u64> {
+        self.items.total()
+    }
+}
+
+/// Sent when a [`BudgetedTask`] ran some of its items in a tick.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TaskProgressEvent {
+    pub task: Entity,
+    /// The number of items run so far.
+    pub done: u64,
+    /// The total number of items, if known.
+    pub total: Option<u64>,
+}
+
+/// Sent when a [`BudgetedTask`] has run all of its items. The entity of the
+/// task is despawned in the same tick.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TaskCompletedEvent {
+    pub task: Entity,
+    /// The number of items that were run.
+    pub done: u64,
+}
+
+#[derive(Resource)]
+struct TickStart(Instant);
+
+fn record_tick_start(mut tick_start: ResMut<TickStart>) {
+    tick_start.0 = Instant::now();
+}
+
+fn run_budgeted_tasks(world: &mut World, mut tasks: Local<Vec<Entity>>) {
+    tasks.clear();
+    tasks.extend(
+        world
+            .query_filtered::<Entity, With<BudgetedTask>>()
+            .iter(world),
+    );
+
+    if tasks.is_empty() {
+        return;
+    }
+
+    let start = Instant::now();
+    let budget = world
+        .resource::<WorkBudget>()
+        .available(world.resource::<Server>(), world.resource::<TickStart>().0);
+
+    for (i, &entity) in tasks.iter().enumerate() {
+        // Split the remaining time evenly between the remaining tasks.
+        let remaining = budget.saturating_sub(start.elapsed());
+        let deadline = Instant::now() + remaining / (tasks.len() - i) as u32;
+
+        // Take the task out of the world while it runs, so it can access the
+        // whole world.
+        let Some(mut task) = world
+            .get_entity_mut(entity)
+            .and_then(|mut entity| entity.take::<BudgetedTask>())
+        else {
+            continue;
+        };
+
+        let finished = task.steps.run_until(world, deadline);
+
+        world.send_event(TaskProgressEvent {
+            task: entity,
+            done: task.done(),
+            total: task.total(),
+        });
+
+        if finished {
+            world.send_event(TaskCompletedEvent {
+                task: entity,
+                done: task.done(),
+            });
+
+            world.despawn(entity);
+        } else if let Some(mut entity) = world.get_entity_mut(entity) {
+            // The task may have despawned its own entity.
+            entity.insert(task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_sliced_makes_progress() {
+        let mut sliced = TimeSliced::new(0..10);
+        let mut seen = vec![];
+
+        assert_eq!(sliced.total(), Some(10));
+
+        // The deadline has passed, but one item is still taken.
+        assert!(!sliced.run_until(Instant::now(), |n| seen.push(n)));
+        assert_eq!(seen, [0]);
+        assert_eq!(sliced.done(), 1);
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert!(sliced.run_until(deadline, |n| seen.push(n)));
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+        assert!(sliced.is_finished());
+
+        assert_eq!(
+            TimeSliced::new((0..10).filter(|n| n % 2 == 0)).total(),
+            None
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Debounced clicks on entities and blocks.
+//!
+//! Vanilla clients send several packets for a single click. Right clicking an
+//! entity sends an [`EntityInteraction::InteractAt`] and an
+//! [`EntityInteraction::Interact`] for the main hand, and the same again for
+//! the off hand if the main hand did nothing. Right clicking a block sends an
+//! interaction for each hand in the same way. Players also tend to click
+//! twice in quick succession.
+//!
+//! [`ClickEvent`]s are sent for [`InteractEntityEvent`]s and
+//! [`InteractBlockEvent`]s, but clicks of the same type on the same target
+//! within [`ClickDebounce::debounce_ticks`] of the previous one are dropped.
+//! Menus and buttons which should only activate once per click should use
+//! [`ClickEvent`]s instead of the raw interaction events.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_protocol::packets::play::player_interact_entity_c2s::EntityInteraction;
+use valence_protocol::{BlockPos, Hand};
+use valence_server_common::Server;
+
+use crate::client::Client;
+use crate::event_loop::EventLoopUpdate;
+use crate::interact_block::InteractBlockEvent;
+use crate::interact_entity::InteractEntityEvent;
+
+pub struct ClickPlugin;
+
+impl Plugin for ClickPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ClickEvent>()
+            .add_systems(PreUpdate, init_click_debounce)
+            .add_systems(EventLoopUpdate, debounce_clicks);
+    }
+}
+
+/// Whether a click was a left or a right click.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ClickType {
+    /// An attack on an entity.
+    Left,
+    /// An interaction with an entity or a block.
+    Right,
+}
+
+/// What was clicked.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ClickTarget {
+    Entity(Entity),
+    Block(BlockPos),
+}
+
+/// Sent for the first of the interaction packets of a click, unless it is
+/// debounced by the [`ClickDebounce`] of the client.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ClickEvent {
+    pub client: Entity,
+    pub target: ClickTarget,
+    pub click: ClickType,
+    /// The hand of the first interaction of the click. Always
+    /// [`Hand::Main`] for left clicks.
+    pub hand: Hand,
+    /// If the client was sneaking while clicking an entity. Always `false`
+    /// for blocks.
+    pub sneaking: bool,
+}
+
+/// The debouncing of the [`ClickEvent`]s of a client. Added to clients
+/// automatically.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct ClickDebounce {
+    /// Clicks of the same type on the same target less than this many ticks
+    /// after the previous one are dropped. Interactions in the same tick are
+    /// always considered parts of the same click, so zero still removes the
+    /// duplicates sent by vanilla clients.
+    ///
+    /// # Default Value
+    ///
+    /// 4
+    pub debounce_ticks: u32,
+    /// Clicks of the same type on any target less than this many ticks after
+    /// the previous one are dropped, like a cooldown on clicking.
+    ///
+    /// # Default Value
+    ///
+    /// 0
+    pub cooldown_ticks: u32,
+    /// The type, target, and tick of recently accepted clicks.
+    last: Vec<(ClickType, ClickTarget, i64)>,
+}
+
+impl Default for ClickDebounce {
+    fn default() -> Self {
+        Self {
+            debounce_ticks: 4,
+            cooldown_ticks: 0,
+            last: vec![],
+        }
+    }
+}
+
+impl ClickDebounce {
+    pub fn new(debounce_ticks: u32, cooldown_ticks: u32) -> Self {
+        Self {
+            debounce_ticks,
+            cooldown_ticks,
+            last: vec![],
+        }
+    }
+
+    /// Returns whether a click in tick `tick` is accepted, and remembers it
+    /// if it is.
+    fn accept(&mut self, click: ClickType, target: ClickTarget, tick: i64) -> bool {
+        let debounce = i64::from(self.debounce_ticks.max(1));
+        let cooldown = i64::from(self.cooldown_ticks);
+
+        let rejected = self
+            .last
+            .iter()
+            .any(|&(last_click, last_target, last_tick)| {
+                last_click == click
+                    && (tick - last_tick < cooldown
+                        || (last_target == target && tick - last_tick < debounce))
+            });
+
+        if rejected {
+            return false;
+        }
+
+        self.last
+            .retain(|&(_, _, last_tick)| tick - last_tick < debounce.max(cooldown));
+        self.last.push((click, target, tick));
+
+        true
+    }
+}
+
+fn init_click_debounce(
+    clients: Query<Entity, (Added<Client>, Without<ClickDebounce>)>,
+    mut commands: Commands,
+) {
+    for client in &clients {
+        commands.entity(client).insert(ClickDebounce::default());
+    }
+}
+
+fn debounce_clicks(
+    mut clients: Query<&mut ClickDebounce>,
+    server: Res<Server>,
+    mut entity_interactions: EventReader<InteractEntityEvent>,
+    mut block_interactions: EventReader<InteractBlockEvent>,
+    mut events: EventWriter<ClickEvent>,
+) {
+    let tick = server.current_tick();
+
+    let clicks = entity_interactions
+        .read()
+        .map(|event| {
+            let (click, hand) = match event.interact {
+                EntityInteraction::Attack => (ClickType::Left, Hand::Main),
+                EntityInteraction::Interact(hand) | EntityInteraction::InteractAt { hand, .. } => {
+                    (ClickType::Right, hand)
+                }
+            };
+
+            ClickEvent {
+                client: event.client,
+                target: ClickTarget::Entity(event.entity),
+                click,
+                hand,
+                sneaking: event.sneaking,
+            }
+        })
+        .chain(block_interactions.read().map(|event| ClickEvent {
+            client: event.client,
+            target: ClickTarget::Block(event.position),
+            click: ClickType::Right,
+            hand: event.hand,
+            sneaking: false,
+        }));
+
+    for click in clicks {
+        let accepted = clients.get_mut(click.client).map_or(true, |mut debounce| {
+            debounce.accept(click.click, click.target, tick)
+        });
+
+        if accepted {
+            events.send(click);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debounce_same_target() {
+        let mut debounce = ClickDebounce::new(4, 0);
+        let a = ClickTarget::Entity(Entity::from_raw(1));
+        let b = ClickTarget::Block(BlockPos::new(0, 0, 0));
+
+        assert!(debounce.accept(ClickType::Right, a, 0));
+        assert!(!debounce.accept(ClickType::Right, a, 0));
+        assert!(!debounce.accept(ClickType::Right, a, 3));
+        assert!(debounce.accept(ClickType::Left, a, 3));
+        assert!(debounce.accept(ClickType::Right, b, 3));
+        assert!(debounce.accept(ClickType::Right, a, 4));
+    }
+
+    #[test]
+    fn cooldown_any_target() {
+        let mut debounce = ClickDebounce::new(0, 10);
+        let a = ClickTarget::Entity(Entity::from_raw(1));
+        let b = ClickTarget::Entity(Entity::from_raw(2));
+
+        assert!(debounce.accept(ClickType::Left, a, 0));
+        assert!(!debounce.accept(ClickType::Left, b, 5));
+        assert!(debounce.accept(ClickType::Right, b, 5));
+        assert!(debounce.accept(ClickType::Left, b, 10));
+    }
+}
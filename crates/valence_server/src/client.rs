@@ -21,7 +21,7 @@ use valence_entity::tracked_data::TrackedData;
 use valence_entity::{
     ClearEntityChangesSet, EntityId, EntityStatus, OldPosition, Position, Velocity,
 };
-use valence_math::{DVec3, Vec3};
+use valence_math::{DVec3, Vec2, Vec3};
 use valence_protocol::encode::{PacketEncoder, WritePacket};
 use valence_protocol::packets::play::chunk_biome_data_s2c::ChunkBiome;
 use valence_protocol::packets::play::game_state_change_s2c::GameEventKind;
@@ -30,9 +30,9 @@ use valence_protocol::packets::play::{
     ChunkBiomeDataS2c, ChunkLoadDistanceS2c, ChunkRenderDistanceCenterS2c, DeathMessageS2c,
     DisconnectS2c, EntitiesDestroyS2c, EntityAttributesS2c, EntityStatusS2c,
     EntityTrackerUpdateS2c, EntityVelocityUpdateS2c, GameStateChangeS2c, HealthUpdateS2c,
-    ParticleS2c, PlaySoundS2c, UnloadChunkS2c,
+    ParticleS2c, PlaySoundS2c, SetCameraEntityS2c, UnloadChunkS2c,
 };
-use valence_protocol::profile::Property;
+use valence_protocol::profile::{PlayerTextures, Property};
 use valence_protocol::sound::{Sound, SoundCategory, SoundId};
 use valence_protocol::text::{IntoText, Text};
 use valence_protocol::var_int::VarInt;
@@ -40,7 +40,9 @@ use valence_protocol::{BlockPos, ChunkPos, Encode, GameMode, Packet};
 use valence_registry::RegistrySet;
 use valence_server_common::{Despawned, UniqueId};
 
+use crate::layer::chunk::{upgrade_partial_chunks, PartialChunks};
 use crate::layer::{ChunkLayer, EntityLayer, UpdateLayersPostClientSet, UpdateLayersPreClientSet};
+use crate::visibility::HiddenEntities;
 use crate::ChunkView;
 
 pub struct ClientPlugin;
@@ -74,10 +76,17 @@ impl Plugin for ClientPlugin {
                     update_view_and_layers
                         .after(crate::spawn::initial_join)
                         .after(handle_layer_messages),
+                    // The respawn packet clears the chunks of the client, so it
+                    // must be written before the chunks of the new layer.
+                    crate::spawn::respawn
+                        .after(crate::spawn::initial_join)
+                        .before(update_view_and_layers),
                     cleanup_chunks_after_client_despawn.after(update_view_and_layers),
                     crate::spawn::update_respawn_position.after(update_view_and_layers),
-                    crate::spawn::respawn.after(crate::spawn::update_respawn_position),
                     update_old_view_dist.after(update_view_and_layers),
+                    upgrade_partial_chunks
+                        .after(update_view_and_layers)
+                        .after(handle_layer_messages),
                     update_game_mode,
                     update_food_saturation_health,
                     update_tracked_data,
@@ -114,6 +123,7 @@ pub struct ClientBundle {
     pub entity_remove_buf: EntityRemoveBuf,
     pub username: Username,
     pub ip: Ip,
+    pub server_address: ServerAddress,
     pub properties: Properties,
     pub respawn_pos: crate::spawn::RespawnPosition,
     pub op_level: crate::op_level::OpLevel,
@@ -137,9 +147,15 @@ pub struct ClientBundle {
     pub is_debug: crate::spawn::IsDebug,
     pub is_flat: crate::spawn::IsFlat,
     pub portal_cooldown: crate::spawn::PortalCooldown,
+    pub enabled_features: crate::spawn::EnabledFeatures,
     pub flying_speed: crate::abilities::FlyingSpeed,
     pub fov_modifier: crate::abilities::FovModifier,
     pub player_abilities_flags: crate::abilities::PlayerAbilitiesFlags,
+    pub experience_level: crate::experience::ExperienceLevel,
+    pub experience_points: crate::experience::ExperiencePoints,
+    pub resource_pack_state: crate::resource_pack::ResourcePackState,
+    pub hidden_entities: HiddenEntities,
+    pub partial_chunks: PartialChunks,
     pub player: PlayerEntityBundle,
 }
 
@@ -155,6 +171,7 @@ impl ClientBundle {
             entity_remove_buf: Default::default(),
             username: Username(args.username),
             ip: Ip(args.ip),
+            server_address: ServerAddress(args.server_address),
             properties: Properties(args.properties),
             respawn_pos: Default::default(),
             op_level: Default::default(),
@@ -178,9 +195,15 @@ impl ClientBundle {
             reduced_debug_info: Default::default(),
             is_debug: Default::default(),
             portal_cooldown: Default::default(),
+            enabled_features: Default::default(),
             flying_speed: Default::default(),
             fov_modifier: Default::default(),
             player_abilities_flags: Default::default(),
+            experience_level: Default::default(),
+            experience_points: Default::default(),
+            resource_pack_state: Default::default(),
+            hidden_entities: Default::default(),
+            partial_chunks: Default::default(),
             player: PlayerEntityBundle {
                 uuid: UniqueId(args.uuid),
                 ..Default::default()
@@ -197,6 +220,8 @@ pub struct ClientBundleArgs {
     pub uuid: Uuid,
     /// IP address of the client.
     pub ip: IpAddr,
+    /// The server address the client connected with in its handshake.
+    pub server_address: String,
     /// Properties of this client from the game profile.
     pub properties: Vec<Property>,
     /// The abstract socket connection.
@@ -280,6 +305,14 @@ impl Client {
         self.conn.as_mut()
     }
 
+    /// Returns the protocol version of the client. Packets are translated to
+    /// and from it if it isn't [`PROTOCOL_VERSION`].
+    ///
+    /// [`PROTOCOL_VERSION`]: valence_protocol::PROTOCOL_VERSION
+    pub fn protocol_version(&self) -> i32 {
+        self.enc.protocol_version()
+    }
+
     /// Flushes the packet queue to the underlying connection.
     ///
     /// This is called automatically at the end of the tick and when the client
@@ -365,6 +398,19 @@ impl Client {
         });
     }
 
+    /// Knocks the client back in `direction` like
+    /// [`knockback_velocity`](crate::velocity::knockback_velocity), as if it
+    /// was standing still on the ground. Use
+    /// [`ApplyKnockback`](crate::velocity::ApplyKnockback) to take its
+    /// current velocity into account.
+    ///
+    /// `direction` is the horizontal direction as `(x, z)` and `strength` is
+    /// in blocks per tick.
+    pub fn apply_knockback(&mut self, direction: Vec2, strength: f32) {
+        let velocity = crate::velocity::knockback_velocity(Vec3::ZERO, true, direction, strength);
+        self.set_velocity(velocity * 20.0);
+    }
+
     /// Triggers an [`EntityStatus`].
     ///
     /// The status is only visible to this client.
@@ -374,6 +420,25 @@ impl Client {
             entity_status: status as u8,
         });
     }
+
+    /// Makes the client see through the eyes of the entity with the protocol
+    /// ID `entity_id`, which the client must have spawned. The client leaves
+    /// the entity's view on its own in spectator mode by sneaking.
+    ///
+    /// See [`Spectate`](crate::spectate::Spectate) for a camera which follows
+    /// the entity across chunks and layers.
+    pub fn set_camera(&mut self, entity_id: i32) {
+        self.write_packet(&SetCameraEntityS2c {
+            entity_id: VarInt(entity_id),
+        });
+    }
+
+    /// Makes the client see through its own eyes again after
+    /// [`set_camera`](Self::set_camera).
+    pub fn reset_camera(&mut self) {
+        // Clients know themselves by the reserved entity ID 0.
+        self.set_camera(0);
+    }
 }
 
 /// A [`Command`] to disconnect a [`Client`] with a displayed reason.
@@ -443,9 +508,35 @@ impl fmt::Display for Username {
 pub struct Properties(pub Vec<Property>);
 
 impl Properties {
+    /// Finds the property named `name`.
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.0.iter().find(|p| p.name == name)
+    }
+
+    /// Sets the value and signature of the property named `name`, or adds it
+    /// if it does not exist.
+    pub fn set(&mut self, name: &str, value: impl Into<String>, signature: Option<String>) {
+        if let Some(prop) = self.0.iter_mut().find(|p| p.name == name) {
+            prop.value = value.into();
+            prop.signature = signature;
+        } else {
+            self.0.push(Property {
+                name: name.to_owned(),
+                value: value.into(),
+                signature,
+            });
+        }
+    }
+
+    /// Removes the property named `name` and returns it.
+    pub fn remove(&mut self, name: &str) -> Option<Property> {
+        let idx = self.0.iter().position(|p| p.name == name)?;
+        Some(self.0.remove(idx))
+    }
+
     /// Finds the property with the name "textures".
     pub fn textures(&self) -> Option<&Property> {
-        self.0.iter().find(|p| p.name == "textures")
+        self.get("textures")
     }
 
     /// Finds the property with the name "textures" mutably.
@@ -467,16 +558,20 @@ impl Properties {
     /// clients. You can't sign skins yourself, so you'll have to get it from
     /// Mojang.
     pub fn set_skin(&mut self, skin: impl Into<String>, signature: impl Into<String>) {
-        if let Some(prop) = self.textures_mut() {
-            prop.value = skin.into();
-            prop.signature = Some(signature.into());
-        } else {
-            self.0.push(Property {
-                name: "textures".to_owned(),
-                value: skin.into(),
-                signature: Some(signature.into()),
-            });
-        }
+        self.set("textures", skin, Some(signature.into()));
+    }
+
+    /// Removes the "textures" property, which makes clients show the default
+    /// skin for the player's UUID.
+    pub fn remove_skin(&mut self) -> Option<Property> {
+        self.remove("textures")
+    }
+
+    /// Decodes the skin and cape URLs and the skin model from the "textures"
+    /// property. Returns `None` if there is no such property or it is
+    /// malformed.
+    pub fn player_textures(&self) -> Option<PlayerTextures> {
+        PlayerTextures::try_from_textures(self.skin()?).ok()
     }
 }
 
@@ -489,6 +584,12 @@ pub struct PropertyValue {
 #[derive(Component, Clone, PartialEq, Eq, Debug, Deref)]
 pub struct Ip(pub IpAddr);
 
+/// The server address the client connected with in its handshake, such as
+/// `play.example.com`. Useful to tell apart clients which joined through
+/// different host names.
+#[derive(Component, Clone, PartialEq, Eq, Debug, Deref)]
+pub struct ServerAddress(pub String);
+
 #[derive(Component, Clone, PartialEq, Eq, Debug, Deref)]
 pub struct ViewDistance(u8);
 
@@ -635,6 +736,8 @@ fn handle_layer_messages(
         &OldVisibleChunkLayer,
         &mut VisibleEntityLayers,
         &OldVisibleEntityLayers,
+        &HiddenEntities,
+        &mut PartialChunks,
     )>,
     chunk_layers: Query<&ChunkLayer>,
     entity_layers: Query<&EntityLayer>,
@@ -650,6 +753,8 @@ fn handle_layer_messages(
             old_visible_chunk_layer,
             mut visible_entity_layers,
             old_visible_entity_layers,
+            hidden_entities,
+            mut partial_chunks,
         )| {
             let block_pos = BlockPos::from(old_view.old_pos.get());
             let old_view = old_view.get();
@@ -725,7 +830,13 @@ fn handle_layer_messages(
                             [.., ChunkLayer::LOAD | ChunkLayer::OVERWRITE] => {
                                 // Load chunk.
                                 let chunk = chunk_layer.chunk(pos).expect("chunk must exist");
-                                chunk.write_init_packets(&mut *client, pos, chunk_layer.info());
+                                partial_chunks.write_init_packets(
+                                    &mut client,
+                                    chunk_layer,
+                                    chunk,
+                                    pos,
+                                    block_pos.y.into(),
+                                );
                                 chunk.inc_viewer_count();
                             }
                             [.., ChunkLayer::UNLOAD] => {
@@ -805,7 +916,8 @@ fn handle_layer_messages(
                                 while let Ok(u64) = bytes.read_u64::<NativeEndian>() {
                                     let entity = Entity::from_bits(u64);
 
-                                    if self_entity != entity {
+                                    if self_entity != entity && !hidden_entities.suppresses(entity)
+                                    {
                                         if let Ok((init, old_pos)) = entities.get(entity) {
                                             remove_buf.send_and_clear(&mut *client);
 
@@ -828,7 +940,8 @@ fn handle_layer_messages(
                                 while let Ok(u64) = bytes.read_u64::<NativeEndian>() {
                                     let entity = Entity::from_bits(u64);
 
-                                    if self_entity != entity {
+                                    if self_entity != entity && !hidden_entities.suppresses(entity)
+                                    {
                                         if let Ok((init, old_pos)) = entities.get(entity) {
                                             remove_buf.send_and_clear(&mut *client);
 
@@ -841,6 +954,11 @@ fn handle_layer_messages(
                                 }
                             }
                         }
+                        crate::layer::entity::LocalMsg::EntityPacketAt { pos: _, entity } => {
+                            if self_entity != entity && !hidden_entities.suppresses(entity) {
+                                client.write_packet_bytes(&bytes[range]);
+                            }
+                        }
                         crate::layer::entity::LocalMsg::PacketAt { pos: _ } => {
                             client.write_packet_bytes(&bytes[range]);
                         }
@@ -890,6 +1008,8 @@ pub(crate) fn update_view_and_layers(
             &OldPosition,
             &ViewDistance,
             &OldViewDistance,
+            &HiddenEntities,
+            &mut PartialChunks,
         ),
         Or<(
             Changed<VisibleChunkLayer>,
@@ -916,13 +1036,15 @@ pub(crate) fn update_view_and_layers(
             old_pos,
             view_dist,
             old_view_dist,
+            hidden_entities,
+            mut partial_chunks,
         )| {
             let view = ChunkView::new(ChunkPos::from(pos.0), view_dist.0);
             let old_view = ChunkView::new(ChunkPos::from(old_pos.get()), old_view_dist.0);
 
             // Make sure the center chunk is set before loading chunks! Otherwise the client
-            // may ignore the chunk.
-            if old_view.pos != view.pos {
+            // may ignore the chunk. Respawning into another dimension resets the center.
+            if old_view.pos != view.pos || old_chunk_layer.0 != chunk_layer.0 {
                 client.write_packet(&ChunkRenderDistanceCenterS2c {
                     chunk_x: VarInt(view.pos.x),
                     chunk_z: VarInt(view.pos.z),
@@ -943,10 +1065,18 @@ pub(crate) fn update_view_and_layers(
                 }
 
                 // Load all chunks in the new view.
+                partial_chunks.clear();
+
                 if let Ok(layer) = chunk_layers.get(chunk_layer.0) {
-                    for pos in view.iter() {
-                        if let Some(chunk) = layer.chunk(pos) {
-                            chunk.write_init_packets(&mut *client, pos, layer.info());
+                    for chunk_pos in view.iter() {
+                        if let Some(chunk) = layer.chunk(chunk_pos) {
+                            partial_chunks.write_init_packets(
+                                &mut client,
+                                layer,
+                                chunk,
+                                chunk_pos,
+                                pos.0.y,
+                            );
                             chunk.inc_viewer_count();
                         }
                     }
@@ -975,7 +1105,7 @@ pub(crate) fn update_view_and_layers(
                     if let Ok(layer) = entity_layers.get(layer) {
                         for pos in view.iter() {
                             for entity in layer.entities_at(pos) {
-                                if self_entity != entity {
+                                if self_entity != entity && !hidden_entities.suppresses(entity) {
                                     if let Ok((init, pos)) = entity_init.get(entity) {
                                         init.write_init_packets(pos.get(), &mut *client);
                                     }
@@ -1015,7 +1145,8 @@ pub(crate) fn update_view_and_layers(
                         if let Ok(layer) = entity_layers.get(layer) {
                             for pos in old_view.iter() {
                                 for entity in layer.entities_at(pos) {
-                                    if self_entity != entity {
+                                    if self_entity != entity && !hidden_entities.suppresses(entity)
+                                    {
                                         if let Ok((init, pos)) = entity_init.get(entity) {
                                             init.write_init_packets(pos.get(), &mut *client);
                                         }
@@ -1044,9 +1175,15 @@ pub(crate) fn update_view_and_layers(
 
                     // Load chunks in the new view.
                     if let Ok(layer) = chunk_layers.get(chunk_layer.0) {
-                        for pos in view.diff(old_view) {
-                            if let Some(chunk) = layer.chunk(pos) {
-                                chunk.write_init_packets(&mut *client, pos, layer.info());
+                        for chunk_pos in view.diff(old_view) {
+                            if let Some(chunk) = layer.chunk(chunk_pos) {
+                                partial_chunks.write_init_packets(
+                                    &mut client,
+                                    layer,
+                                    chunk,
+                                    chunk_pos,
+                                    pos.0.y,
+                                );
                                 chunk.inc_viewer_count();
                             }
                         }
@@ -1072,7 +1209,8 @@ pub(crate) fn update_view_and_layers(
                         if let Ok(layer) = entity_layers.get(layer) {
                             for pos in view.diff(old_view) {
                                 for entity in layer.entities_at(pos) {
-                                    if self_entity != entity {
+                                    if self_entity != entity && !hidden_entities.suppresses(entity)
+                                    {
                                         if let Ok((init, pos)) = entity_init.get(entity) {
                                             init.write_init_packets(pos.get(), &mut *client);
                                         }
@@ -6,6 +6,7 @@ pub use valence_protocol::packets::play::client_command_c2s::ClientCommand;
 use valence_protocol::packets::play::ClientCommandC2s;
 
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct ClientCommandPlugin;
 
@@ -65,6 +66,7 @@ pub struct LeaveBedEvent {
 
 fn handle_client_command(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<(&mut entity::Pose, &mut Flags)>,
     mut sprinting_events: EventWriter<SprintEvent>,
     mut sneaking_events: EventWriter<SneakEvent>,
@@ -72,7 +74,7 @@ fn handle_client_command(
     mut leave_bed_events: EventWriter<LeaveBedEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<ClientCommandC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<ClientCommandC2s>(&errors) {
             match pkt.action {
                 ClientCommand::StartSneaking => {
                     if let Ok((mut pose, mut flags)) = clients.get_mut(packet.client) {
@@ -6,6 +6,7 @@ use valence_protocol::packets::play::ClientSettingsC2s;
 
 use crate::client::ViewDistance;
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct ClientSettingsPlugin;
 
@@ -27,6 +28,7 @@ pub struct ClientSettings {
 
 fn handle_client_settings(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<(
         &mut ViewDistance,
         &mut ClientSettings,
@@ -35,7 +37,7 @@ fn handle_client_settings(
     )>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<ClientSettingsC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<ClientSettingsC2s>(&errors) {
             if let Ok((mut view_dist, mut settings, mut model_parts, mut main_arm)) =
                 clients.get_mut(packet.client)
             {
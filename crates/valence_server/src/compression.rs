@@ -0,0 +1,255 @@
+//! Measuring and tuning packet compression.
+//!
+//! Compressing packets saves bandwidth but costs CPU time, and some packets
+//! barely get smaller no matter what. When
+//! [enabled](CompressionTuningSettings::enabled), the compression ratio and
+//! time of every packet ID is recorded for each client. With
+//! [`adjust`](CompressionTuningSettings::adjust), the recorded stats are used
+//! to stop compressing packets which don't compress well and to raise the
+//! size at which packets are compressed while compression takes too long.
+//!
+//! The negotiated compression threshold of a connection never changes. Only
+//! packets above it which would otherwise be compressed are sent
+//! uncompressed, which clients accept.
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_protocol::encode::CompressionStats;
+use valence_server_common::Server;
+
+use crate::client::{Client, FlushPacketsSet};
+
+pub struct CompressionTuningPlugin;
+
+impl Plugin for CompressionTuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CompressionTuningSettings>()
+            .init_resource::<ServerCompressionStats>()
+            .add_systems(
+                PostUpdate,
+                (init_compression_tuning, apply_deferred, tune_compression)
+                    .chain()
+                    .after(FlushPacketsSet)
+                    .run_if(|settings: Res<CompressionTuningSettings>| settings.enabled),
+            );
+    }
+}
+
+/// The smallest minimum length of compressed packets the controller sets. Any
+/// lower and raising the limit wouldn't save a meaningful amount of time.
+const MIN_STEP: usize = 512;
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct CompressionTuningSettings {
+    /// Whether compression stats are recorded. Clients are left alone
+    /// unless this is set.
+    pub enabled: bool,
+    /// Whether the compression of clients is adjusted based on their stats.
+    /// Without this, stats are only recorded.
+    pub adjust: bool,
+    /// The number of ticks between adjustments. Stats are collected over this
+    /// many ticks.
+    pub interval: u32,
+    /// Packets whose compressed size is above this fraction of their
+    /// uncompressed size are no longer compressed.
+    pub max_ratio: f64,
+    /// The number of compressed packets with an ID which must be recorded
+    /// before compression can be disabled for it.
+    pub min_samples: u64,
+    /// The average time per tick a single client may spend compressing
+    /// packets. Above this, larger packets are required for compression.
+    /// Below half of it, the requirement is relaxed again.
+    pub time_budget: Duration,
+    /// The largest the minimum length of compressed packets gets.
+    pub max_min_compressed_len: usize,
+}
+
+impl Default for CompressionTuningSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            adjust: true,
+            interval: 100,
+            max_ratio: 0.9,
+            min_samples: 16,
+            time_budget: Duration::from_micros(250),
+            max_min_compressed_len: 16384,
+        }
+    }
+}
+
+/// The compression stats of all clients combined over the last interval.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct ServerCompressionStats(pub CompressionStats);
+
+/// The compression state of a client. Added to clients automatically while
+/// tuning is [enabled](CompressionTuningSettings::enabled).
+#[derive(Component, Clone, Default, Debug)]
+pub struct CompressionTuning {
+    stats: CompressionStats,
+    min_compressed_len: usize,
+    uncompressed_packets: BTreeSet<i32>,
+}
+
+impl CompressionTuning {
+    /// The stats of this client over the last interval.
+    pub fn stats(&self) -> &CompressionStats {
+        &self.stats
+    }
+
+    /// The length of packet data at and below which packets aren't
+    /// compressed for this client, in addition to the compression threshold.
+    pub fn min_compressed_len(&self) -> usize {
+        self.min_compressed_len
+    }
+
+    /// The IDs of the packets which are no longer compressed for this client.
+    pub fn uncompressed_packets(&self) -> &BTreeSet<i32> {
+        &self.uncompressed_packets
+    }
+
+    /// Updates the limits from the stats of the last interval.
+    fn adjust(&mut self, settings: &CompressionTuningSettings) {
+        for (id, class) in self.stats.iter() {
+            if class.compressed_packets >= settings.min_samples
+                && class
+                    .ratio()
+                    .is_some_and(|ratio| ratio > settings.max_ratio)
+            {
+                self.uncompressed_packets.insert(id);
+            }
+        }
+
+        let time_per_tick = self.stats.total().compress_time / settings.interval.max(1);
+
+        if time_per_tick > settings.time_budget {
+            self.min_compressed_len = (self.min_compressed_len * 2)
+                .max(MIN_STEP)
+                .min(settings.max_min_compressed_len);
+        } else if time_per_tick < settings.time_budget / 2 {
+            self.min_compressed_len /= 2;
+
+            if self.min_compressed_len < MIN_STEP {
+                self.min_compressed_len = 0;
+            }
+        }
+    }
+}
+
+fn init_compression_tuning(
+    clients: Query<Entity, (With<Client>, Without<CompressionTuning>)>,
+    mut commands: Commands,
+) {
+    for entity in &clients {
+        commands.entity(entity).insert(CompressionTuning::default());
+    }
+}
+
+fn tune_compression(
+    mut clients: Query<(&mut Client, &mut CompressionTuning)>,
+    settings: Res<CompressionTuningSettings>,
+    server: Res<Server>,
+    mut server_stats: ResMut<ServerCompressionStats>,
+) {
+    let end_of_interval = server.current_tick() % settings.interval.max(1) as i64 == 0;
+
+    if end_of_interval {
+        server_stats.0.clear();
+    }
+
+    for (mut client, mut tuning) in &mut clients {
+        let enc = &mut client.enc;
+
+        enc.enable_compression_stats();
+
+        if !end_of_interval {
+            continue;
+        }
+
+        tuning.stats = enc.take_compression_stats().unwrap_or_default();
+        server_stats.0.merge(&tuning.stats);
+
+        if !settings.adjust {
+            continue;
+        }
+
+        tuning.adjust(&settings);
+
+        enc.set_min_compressed_len(tuning.min_compressed_len);
+
+        for &id in &tuning.uncompressed_packets {
+            enc.set_compresses_packet(id, false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::encode::PacketClassStats;
+
+    use super::*;
+
+    #[test]
+    fn adjust_from_stats() {
+        let settings = CompressionTuningSettings {
+            interval: 10,
+            ..Default::default()
+        };
+
+        let mut tuning = CompressionTuning::default();
+
+        // Packet 1 compresses well, packet 2 doesn't, and there are too few
+        // samples of packet 3 to tell.
+        tuning.stats.add(
+            1,
+            &PacketClassStats {
+                packets: 20,
+                compressed_packets: 20,
+                compressed_input_bytes: 10000,
+                compressed_output_bytes: 2000,
+                compress_time: Duration::from_millis(10),
+                ..Default::default()
+            },
+        );
+        tuning.stats.add(
+            2,
+            &PacketClassStats {
+                packets: 20,
+                compressed_packets: 20,
+                compressed_input_bytes: 10000,
+                compressed_output_bytes: 9800,
+                ..Default::default()
+            },
+        );
+        tuning.stats.add(
+            3,
+            &PacketClassStats {
+                packets: 2,
+                compressed_packets: 2,
+                compressed_input_bytes: 1000,
+                compressed_output_bytes: 1000,
+                ..Default::default()
+            },
+        );
+
+        tuning.adjust(&settings);
+
+        assert_eq!(tuning.uncompressed_packets(), &BTreeSet::from([2]));
+        // 1 ms per tick is over the budget.
+        assert_eq!(tuning.min_compressed_len(), MIN_STEP);
+
+        tuning.adjust(&settings);
+        assert_eq!(tuning.min_compressed_len(), MIN_STEP * 2);
+
+        tuning.stats.clear();
+
+        tuning.adjust(&settings);
+        assert_eq!(tuning.min_compressed_len(), MIN_STEP);
+
+        tuning.adjust(&settings);
+        assert_eq!(tuning.min_compressed_len(), 0);
+    }
+}
@@ -1,17 +1,32 @@
+//! Custom payloads, also known as plugin messages.
+//!
+//! All custom payloads sent by clients are sent as [`CustomPayloadEvent`]s.
+//! Channels can also be registered in the [`PluginChannelRegistry`], which
+//! announces them to clients with the `minecraft:register` channel. Messages
+//! implementing [`PluginMessage`] added with
+//! [`AddPluginChannel::add_plugin_channel`] are decoded and sent as
+//! [`PluginMessageEvent`]s.
+
+use std::collections::BTreeSet;
+
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use tracing::debug;
 use valence_protocol::packets::play::{CustomPayloadC2s, CustomPayloadS2c};
-use valence_protocol::{Bounded, Ident, WritePacket};
+use valence_protocol::{ident, Bounded, Ident, WritePacket};
 
-use crate::client::Client;
+use crate::client::{Client, ClientMarker, FlushPacketsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct CustomPayloadPlugin;
 
 impl Plugin for CustomPayloadPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<CustomPayloadEvent>()
-            .add_systems(EventLoopPreUpdate, handle_custom_payload);
+            .init_resource::<PluginChannelRegistry>()
+            .add_systems(EventLoopPreUpdate, handle_custom_payload)
+            .add_systems(PostUpdate, announce_channels.before(FlushPacketsSet));
     }
 }
 
@@ -29,14 +44,106 @@ impl Client {
             data: Bounded(data.into()),
         });
     }
+
+    /// Sends the raw bytes of a plugin message on `channel`. Same as
+    /// [`Client::send_custom_payload`].
+    pub fn send_plugin_message(&mut self, channel: Ident<&str>, data: &[u8]) {
+        self.send_custom_payload(channel, data);
+    }
+
+    /// Encodes `msg` and sends it on its channel.
+    pub fn send_typed_plugin_message<M: PluginMessage>(&mut self, msg: &M) {
+        let mut buf = vec![];
+        msg.encode(&mut buf);
+        self.send_custom_payload(M::CHANNEL, &buf);
+    }
+}
+
+/// The plugin channels the server listens on. The registered channels are
+/// sent to clients when they join, and changes to the registry are sent to
+/// all clients.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct PluginChannelRegistry {
+    channels: BTreeSet<Ident<String>>,
+    /// Channels unregistered since the last announcement.
+    removed: BTreeSet<Ident<String>>,
+}
+
+impl PluginChannelRegistry {
+    /// Registers `channel`. Returns whether the channel was not already
+    /// registered.
+    pub fn register(&mut self, channel: impl Into<Ident<String>>) -> bool {
+        let channel = channel.into();
+        self.removed.remove(&channel);
+        self.channels.insert(channel)
+    }
+
+    /// Unregisters `channel`. Returns whether the channel was registered.
+    pub fn unregister(&mut self, channel: Ident<&str>) -> bool {
+        let channel = Ident::<String>::from(channel);
+
+        if self.channels.remove(&channel) {
+            self.removed.insert(channel);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_registered(&self, channel: Ident<&str>) -> bool {
+        self.channels.contains(&Ident::<String>::from(channel))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Ident<&str>> + '_ {
+        self.channels.iter().map(|c| c.as_str_ident())
+    }
+}
+
+/// A message sent on a plugin channel which can be converted to and from
+/// bytes.
+pub trait PluginMessage: Sized + Send + Sync + 'static {
+    /// The channel this message is sent on.
+    const CHANNEL: Ident<&'static str>;
+
+    /// Decodes a message from the payload of a custom payload packet.
+    fn decode(data: &[u8]) -> anyhow::Result<Self>;
+
+    /// Encodes this message as the payload of a custom payload packet.
+    fn encode(&self, buf: &mut Vec<u8>);
+}
+
+/// Sent when a client sends a message on the channel of `M`. Messages which
+/// fail to decode are skipped.
+#[derive(Event, Clone, Debug)]
+pub struct PluginMessageEvent<M> {
+    pub client: Entity,
+    pub message: M,
+}
+
+pub trait AddPluginChannel {
+    /// Registers the channel of `M` and sends a [`PluginMessageEvent<M>`] for
+    /// every message received on it.
+    fn add_plugin_channel<M: PluginMessage>(&mut self) -> &mut Self;
+}
+
+impl AddPluginChannel for App {
+    fn add_plugin_channel<M: PluginMessage>(&mut self) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(PluginChannelRegistry::default)
+            .register(M::CHANNEL);
+
+        self.add_event::<PluginMessageEvent<M>>()
+            .add_systems(EventLoopPreUpdate, decode_plugin_messages::<M>)
+    }
 }
 
 fn handle_custom_payload(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut events: EventWriter<CustomPayloadEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<CustomPayloadC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<CustomPayloadC2s>(&errors) {
             events.send(CustomPayloadEvent {
                 client: packet.client,
                 channel: pkt.channel.into(),
@@ -45,3 +152,109 @@ fn handle_custom_payload(
         }
     }
 }
+
+fn decode_plugin_messages<M: PluginMessage>(
+    mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    mut events: EventWriter<PluginMessageEvent<M>>,
+) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode_reporting::<CustomPayloadC2s>(&errors) else {
+            continue;
+        };
+
+        if pkt.channel.as_str_ident() != M::CHANNEL {
+            continue;
+        }
+
+        match M::decode(pkt.data.0 .0) {
+            Ok(message) => events.send(PluginMessageEvent {
+                client: packet.client,
+                message,
+            }),
+            Err(e) => debug!(
+                "failed to decode plugin message on channel {}: {e:#}",
+                M::CHANNEL
+            ),
+        }
+    }
+}
+
+/// Sends the registered channels to new clients, and changes to the registry
+/// to all clients.
+fn announce_channels(
+    mut registry: ResMut<PluginChannelRegistry>,
+    mut clients: Query<(&mut Client, Ref<ClientMarker>)>,
+    added: Query<(), Added<ClientMarker>>,
+) {
+    let changed = registry.is_changed();
+
+    if !changed && added.is_empty() {
+        return;
+    }
+
+    let registered = channel_list(registry.channels.iter());
+    let removed = channel_list(registry.removed.iter());
+
+    for (mut client, marker) in &mut clients {
+        if (changed || marker.is_added()) && !registered.is_empty() {
+            client.send_custom_payload(ident!("minecraft:register"), &registered);
+        }
+
+        if changed && !marker.is_added() && !removed.is_empty() {
+            client.send_custom_payload(ident!("minecraft:unregister"), &removed);
+        }
+    }
+
+    registry.bypass_change_detection().removed.clear();
+}
+
+/// Encodes channels as the payload of `minecraft:register` and
+/// `minecraft:unregister`, which is a list of null-separated channel names.
+fn channel_list<'a>(channels: impl Iterator<Item = &'a Ident<String>>) -> Vec<u8> {
+    let mut buf = vec![];
+
+    for channel in channels {
+        if !buf.is_empty() {
+            buf.push(0);
+        }
+        buf.extend_from_slice(channel.as_str().as_bytes());
+    }
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_tracks_removed_channels() {
+        let mut registry = PluginChannelRegistry::default();
+
+        assert!(registry.register(ident!("valence:a")));
+        assert!(!registry.register(ident!("valence:a")));
+        assert!(registry.register(ident!("valence:b")));
+        assert!(registry.is_registered(ident!("valence:a")));
+
+        assert!(registry.unregister(ident!("valence:a")));
+        assert!(!registry.unregister(ident!("valence:a")));
+        assert!(!registry.is_registered(ident!("valence:a")));
+        assert_eq!(registry.iter().collect::<Vec<_>>(), [ident!("valence:b")]);
+        assert!(registry
+            .removed
+            .contains(&Ident::<String>::from(ident!("valence:a"))));
+
+        // Registering a channel again cancels its removal.
+        registry.register(ident!("valence:a"));
+        assert!(registry.removed.is_empty());
+    }
+
+    #[test]
+    fn channel_list_is_null_separated() {
+        let channels = [ident!("valence:a").into(), ident!("valence:b").into()];
+
+        assert_eq!(channel_list(channels.iter()), b"valence:a\0valence:b");
+        assert_eq!(channel_list([].iter()), b"");
+    }
+}
@@ -0,0 +1,283 @@
+//! Components which repeatedly play particles and sounds.
+//!
+//! An emitter is played at the [`Position`] of its entity to the clients
+//! viewing the entity layer in its [`EntityLayerId`]. Emitters can be
+//! inserted on any entity with these components, or spawned on their own:
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::emitter::{ParticleEmitter, SoundEmitter};
+//! use valence_server::entity::{EntityLayerId, Position};
+//! use valence_server::protocol::sound::{Sound, SoundCategory};
+//! use valence_server::protocol::Particle;
+//!
+//! fn spawn_campfire(mut commands: Commands, layer: Entity) {
+//!     commands.spawn((
+//!         Position::new([0.5, 65.0, 0.5]),
+//!         EntityLayerId(layer),
+//!         ParticleEmitter::new(Particle::CampfireCosySmoke)
+//!             .with_interval(5)
+//!             .with_spread([0.2, 0.5, 0.2]),
+//!         SoundEmitter::new(Sound::BlockCampfireCrackle, SoundCategory::Block).with_interval(40),
+//!     ));
+//! }
+//! # let _ = spawn_campfire;
+//! ```
+
+use std::borrow::Cow;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::{EntityLayerId, Position};
+use valence_math::{DVec3, Vec3};
+use valence_protocol::packets::play::{ParticleS2c, PlaySoundS2c};
+use valence_protocol::sound::{Sound, SoundCategory, SoundId};
+use valence_protocol::{Encode, Packet, Particle, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::layer::{EntityLayer, UpdateLayersPreClientSet};
+use crate::Layer;
+
+pub struct EmitterPlugin;
+
+impl Plugin for EmitterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (play_particle_emitters, play_sound_emitters).before(UpdateLayersPreClientSet),
+        );
+    }
+}
+
+/// Plays a particle effect every [`interval`](Self::interval) ticks.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct ParticleEmitter {
+    pub particle: Particle,
+    /// The number of ticks between two effects. The first effect is played
+    /// the tick the emitter is added.
+    ///
+    /// # Default Value
+    ///
+    /// 1
+    pub interval: u32,
+    /// Offset of the effect from the entity's position.
+    pub offset: DVec3,
+    /// The spread of the particles along each axis.
+    pub spread: Vec3,
+    pub max_speed: f32,
+    /// The number of particles per effect.
+    pub count: i32,
+    /// Whether clients render the particles from further away than usual.
+    pub long_distance: bool,
+    /// A client which doesn't receive the effect, like the player carrying
+    /// the emitter.
+    pub except: Option<Entity>,
+    /// Ticks until the next effect is played.
+    cooldown: u32,
+}
+
+impl ParticleEmitter {
+    pub fn new(particle: Particle) -> Self {
+        Self {
+            particle,
+            interval: 1,
+            offset: DVec3::ZERO,
+            spread: Vec3::ZERO,
+            max_speed: 0.0,
+            count: 1,
+            long_distance: false,
+            except: None,
+            cooldown: 0,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: u32) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: impl Into<DVec3>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    pub fn with_spread(mut self, spread: impl Into<Vec3>) -> Self {
+        self.spread = spread.into();
+        self
+    }
+
+    pub fn with_max_speed(mut self, max_speed: f32) -> Self {
+        self.max_speed = max_speed;
+        self
+    }
+
+    pub fn with_count(mut self, count: i32) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn with_long_distance(mut self, long_distance: bool) -> Self {
+        self.long_distance = long_distance;
+        self
+    }
+
+    pub fn with_except(mut self, client: Entity) -> Self {
+        self.except = Some(client);
+        self
+    }
+}
+
+/// Plays a sound every [`interval`](Self::interval) ticks.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct SoundEmitter {
+    pub sound: Sound,
+    pub category: SoundCategory,
+    /// The number of ticks between two sounds. The first sound is played the
+    /// tick the emitter is added.
+    ///
+    /// # Default Value
+    ///
+    /// 20
+    pub interval: u32,
+    /// Offset of the sound from the entity's position.
+    pub offset: DVec3,
+    pub volume: f32,
+    pub pitch: f32,
+    /// A client which doesn't receive the sound, like the player carrying
+    /// the emitter.
+    pub except: Option<Entity>,
+    /// Ticks until the next sound is played.
+    cooldown: u32,
+}
+
+impl SoundEmitter {
+    pub fn new(sound: Sound, category: SoundCategory) -> Self {
+        Self {
+            sound,
+            category,
+            interval: 20,
+            offset: DVec3::ZERO,
+            volume: 1.0,
+            pitch: 1.0,
+            except: None,
+            cooldown: 0,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: u32) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: impl Into<DVec3>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn with_except(mut self, client: Entity) -> Self {
+        self.except = Some(client);
+        self
+    }
+}
+
+/// Counts down `cooldown` and returns whether the emitter plays this tick.
+fn tick_cooldown(cooldown: &mut u32, interval: u32) -> bool {
+    if *cooldown == 0 {
+        *cooldown = interval.max(1) - 1;
+        true
+    } else {
+        *cooldown -= 1;
+        false
+    }
+}
+
+/// Writes `pkt` to the viewers of `pos` in `layer`, except `except`.
+fn write_to_viewers<P>(layer: &mut EntityLayer, pos: DVec3, except: Option<Entity>, pkt: &P)
+where
+    P: Packet + Encode,
+{
+    match except {
+        Some(except) => layer.view_except_writer(pos, except).write_packet(pkt),
+        None => layer.view_writer(pos).write_packet(pkt),
+    }
+}
+
+fn play_particle_emitters(
+    mut emitters: Query<(&mut ParticleEmitter, &Position, &EntityLayerId), Without<Despawned>>,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (mut emitter, pos, layer_id) in &mut emitters {
+        // Bypass change detection, since the cooldown changes every tick.
+        let emitter = emitter.bypass_change_detection();
+
+        if !tick_cooldown(&mut emitter.cooldown, emitter.interval) {
+            continue;
+        }
+
+        let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+            continue;
+        };
+
+        let position = pos.0 + emitter.offset;
+
+        write_to_viewers(
+            &mut layer,
+            position,
+            emitter.except,
+            &ParticleS2c {
+                particle: Cow::Borrowed(&emitter.particle),
+                long_distance: emitter.long_distance,
+                position,
+                offset: emitter.spread,
+                max_speed: emitter.max_speed,
+                count: emitter.count,
+            },
+        );
+    }
+}
+
+fn play_sound_emitters(
+    mut emitters: Query<(&mut SoundEmitter, &Position, &EntityLayerId), Without<Despawned>>,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (mut emitter, pos, layer_id) in &mut emitters {
+        let emitter = emitter.bypass_change_detection();
+
+        if !tick_cooldown(&mut emitter.cooldown, emitter.interval) {
+            continue;
+        }
+
+        let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+            continue;
+        };
+
+        let position = pos.0 + emitter.offset;
+
+        write_to_viewers(
+            &mut layer,
+            position,
+            emitter.except,
+            &PlaySoundS2c {
+                id: SoundId::Direct {
+                    id: emitter.sound.to_ident().into(),
+                    range: None,
+                },
+                category: emitter.category,
+                position: (position * 8.0).as_ivec3(),
+                volume: emitter.volume,
+                pitch: emitter.pitch,
+                seed: rand::random(),
+            },
+        );
+    }
+}
@@ -0,0 +1,622 @@
+//! Ambient, hurt, and death sounds of entities.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::living::Health;
+use valence_entity::{EntityId, EntityKind, EntityLayerId, Position};
+use valence_protocol::packets::play::PlaySoundFromEntityS2c;
+use valence_protocol::sound::{Sound, SoundCategory};
+use valence_protocol::{VarInt, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::layer::{EntityLayer, UpdateLayersPreClientSet};
+use crate::Layer;
+
+pub struct EntitySoundPlugin;
+
+impl Plugin for EntitySoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EntitySoundEvent>().add_systems(
+            PostUpdate,
+            (
+                init_entity_sounds,
+                apply_deferred,
+                (emit_health_sounds, emit_ambient_sounds),
+                play_entity_sounds,
+            )
+                .chain()
+                .before(UpdateLayersPreClientSet),
+        );
+    }
+}
+
+/// The minimum number of ticks between two ambient sounds of an entity.
+const MIN_AMBIENT_SOUND_DELAY: i32 = 80;
+
+/// The sounds an entity makes.
+///
+/// Living entities are given the vanilla sounds of their kind if they don't
+/// have this component when they are spawned. Insert it to give an entity
+/// different sounds, or remove it to silence the entity.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct EntitySounds {
+    /// Played randomly while the entity is alive.
+    pub ambient: Option<Sound>,
+    pub hurt: Option<Sound>,
+    pub death: Option<Sound>,
+    pub category: SoundCategory,
+    pub volume: f32,
+    /// The pitch the random pitch variation is centered on.
+    pub pitch: f32,
+}
+
+impl EntitySounds {
+    /// Creates a sound set without any sounds in `category`.
+    pub fn new(category: SoundCategory) -> Self {
+        Self {
+            ambient: None,
+            hurt: None,
+            death: None,
+            category,
+            volume: 1.0,
+            pitch: 1.0,
+        }
+    }
+
+    /// Returns the vanilla sounds of entities of `kind`, or `None` if the
+    /// entity doesn't make any of these sounds.
+    pub fn for_kind(kind: EntityKind) -> Option<Self> {
+        let with = |category, ambient, hurt, death| Self {
+            ambient,
+            hurt: Some(hurt),
+            death: Some(death),
+            ..Self::new(category)
+        };
+        let hostile = |ambient, hurt, death| with(SoundCategory::Hostile, ambient, hurt, death);
+        let neutral = |ambient, hurt, death| with(SoundCategory::Neutral, ambient, hurt, death);
+        let player = |ambient, hurt, death| with(SoundCategory::Player, ambient, hurt, death);
+
+        Some(match kind {
+            EntityKind::ZOMBIE => hostile(
+                Some(Sound::EntityZombieAmbient),
+                Sound::EntityZombieHurt,
+                Sound::EntityZombieDeath,
+            ),
+            EntityKind::HUSK => hostile(
+                Some(Sound::EntityHuskAmbient),
+                Sound::EntityHuskHurt,
+                Sound::EntityHuskDeath,
+            ),
+            EntityKind::DROWNED => hostile(
+                Some(Sound::EntityDrownedAmbient),
+                Sound::EntityDrownedHurt,
+                Sound::EntityDrownedDeath,
+            ),
+            EntityKind::SKELETON => hostile(
+                Some(Sound::EntitySkeletonAmbient),
+                Sound::EntitySkeletonHurt,
+                Sound::EntitySkeletonDeath,
+            ),
+            EntityKind::STRAY => hostile(
+                Some(Sound::EntityStrayAmbient),
+                Sound::EntityStrayHurt,
+                Sound::EntityStrayDeath,
+            ),
+            EntityKind::WITHER_SKELETON => hostile(
+                Some(Sound::EntityWitherSkeletonAmbient),
+                Sound::EntityWitherSkeletonHurt,
+                Sound::EntityWitherSkeletonDeath,
+            ),
+            EntityKind::CREEPER => {
+                hostile(None, Sound::EntityCreeperHurt, Sound::EntityCreeperDeath)
+            }
+            EntityKind::SPIDER => hostile(
+                Some(Sound::EntitySpiderAmbient),
+                Sound::EntitySpiderHurt,
+                Sound::EntitySpiderDeath,
+            ),
+            EntityKind::CAVE_SPIDER => hostile(
+                Some(Sound::EntitySpiderAmbient),
+                Sound::EntitySpiderHurt,
+                Sound::EntitySpiderDeath,
+            ),
+            EntityKind::ENDERMAN => hostile(
+                Some(Sound::EntityEndermanAmbient),
+                Sound::EntityEndermanHurt,
+                Sound::EntityEndermanDeath,
+            ),
+            EntityKind::WITCH => hostile(
+                Some(Sound::EntityWitchAmbient),
+                Sound::EntityWitchHurt,
+                Sound::EntityWitchDeath,
+            ),
+            EntityKind::BLAZE => hostile(
+                Some(Sound::EntityBlazeAmbient),
+                Sound::EntityBlazeHurt,
+                Sound::EntityBlazeDeath,
+            ),
+            EntityKind::GHAST => hostile(
+                Some(Sound::EntityGhastAmbient),
+                Sound::EntityGhastHurt,
+                Sound::EntityGhastDeath,
+            ),
+            EntityKind::SLIME => hostile(None, Sound::EntitySlimeHurt, Sound::EntitySlimeDeath),
+            EntityKind::MAGMA_CUBE => hostile(
+                None,
+                Sound::EntityMagmaCubeHurt,
+                Sound::EntityMagmaCubeDeath,
+            ),
+            EntityKind::PHANTOM => hostile(
+                Some(Sound::EntityPhantomAmbient),
+                Sound::EntityPhantomHurt,
+                Sound::EntityPhantomDeath,
+            ),
+            EntityKind::PILLAGER => hostile(
+                Some(Sound::EntityPillagerAmbient),
+                Sound::EntityPillagerHurt,
+                Sound::EntityPillagerDeath,
+            ),
+            EntityKind::VINDICATOR => hostile(
+                Some(Sound::EntityVindicatorAmbient),
+                Sound::EntityVindicatorHurt,
+                Sound::EntityVindicatorDeath,
+            ),
+            EntityKind::EVOKER => hostile(
+                Some(Sound::EntityEvokerAmbient),
+                Sound::EntityEvokerHurt,
+                Sound::EntityEvokerDeath,
+            ),
+            EntityKind::ILLUSIONER => hostile(
+                Some(Sound::EntityIllusionerAmbient),
+                Sound::EntityIllusionerHurt,
+                Sound::EntityIllusionerDeath,
+            ),
+            EntityKind::PIGLIN => hostile(
+                Some(Sound::EntityPiglinAmbient),
+                Sound::EntityPiglinHurt,
+                Sound::EntityPiglinDeath,
+            ),
+            EntityKind::PIGLIN_BRUTE => hostile(
+                Some(Sound::EntityPiglinBruteAmbient),
+                Sound::EntityPiglinBruteHurt,
+                Sound::EntityPiglinBruteDeath,
+            ),
+            EntityKind::ZOMBIFIED_PIGLIN => hostile(
+                Some(Sound::EntityZombifiedPiglinAmbient),
+                Sound::EntityZombifiedPiglinHurt,
+                Sound::EntityZombifiedPiglinDeath,
+            ),
+            EntityKind::HOGLIN => hostile(
+                Some(Sound::EntityHoglinAmbient),
+                Sound::EntityHoglinHurt,
+                Sound::EntityHoglinDeath,
+            ),
+            EntityKind::ZOGLIN => hostile(
+                Some(Sound::EntityZoglinAmbient),
+                Sound::EntityZoglinHurt,
+                Sound::EntityZoglinDeath,
+            ),
+            EntityKind::GUARDIAN => hostile(
+                Some(Sound::EntityGuardianAmbient),
+                Sound::EntityGuardianHurt,
+                Sound::EntityGuardianDeath,
+            ),
+            EntityKind::ELDER_GUARDIAN => hostile(
+                Some(Sound::EntityElderGuardianAmbient),
+                Sound::EntityElderGuardianHurt,
+                Sound::EntityElderGuardianDeath,
+            ),
+            EntityKind::SILVERFISH => hostile(
+                Some(Sound::EntitySilverfishAmbient),
+                Sound::EntitySilverfishHurt,
+                Sound::EntitySilverfishDeath,
+            ),
+            EntityKind::ENDERMITE => hostile(
+                Some(Sound::EntityEndermiteAmbient),
+                Sound::EntityEndermiteHurt,
+                Sound::EntityEndermiteDeath,
+            ),
+            EntityKind::SHULKER => hostile(
+                Some(Sound::EntityShulkerAmbient),
+                Sound::EntityShulkerHurt,
+                Sound::EntityShulkerDeath,
+            ),
+            EntityKind::VEX => hostile(
+                Some(Sound::EntityVexAmbient),
+                Sound::EntityVexHurt,
+                Sound::EntityVexDeath,
+            ),
+            EntityKind::RAVAGER => hostile(
+                Some(Sound::EntityRavagerAmbient),
+                Sound::EntityRavagerHurt,
+                Sound::EntityRavagerDeath,
+            ),
+            EntityKind::WARDEN => hostile(
+                Some(Sound::EntityWardenAmbient),
+                Sound::EntityWardenHurt,
+                Sound::EntityWardenDeath,
+            ),
+            EntityKind::WITHER => hostile(
+                Some(Sound::EntityWitherAmbient),
+                Sound::EntityWitherHurt,
+                Sound::EntityWitherDeath,
+            ),
+            EntityKind::ENDER_DRAGON => hostile(
+                Some(Sound::EntityEnderDragonAmbient),
+                Sound::EntityEnderDragonHurt,
+                Sound::EntityEnderDragonDeath,
+            ),
+            EntityKind::ZOMBIE_VILLAGER => hostile(
+                Some(Sound::EntityZombieVillagerAmbient),
+                Sound::EntityZombieVillagerHurt,
+                Sound::EntityZombieVillagerDeath,
+            ),
+            EntityKind::COW => neutral(
+                Some(Sound::EntityCowAmbient),
+                Sound::EntityCowHurt,
+                Sound::EntityCowDeath,
+            ),
+            EntityKind::MOOSHROOM => neutral(
+                Some(Sound::EntityCowAmbient),
+                Sound::EntityCowHurt,
+                Sound::EntityCowDeath,
+            ),
+            EntityKind::PIG => neutral(
+                Some(Sound::EntityPigAmbient),
+                Sound::EntityPigHurt,
+                Sound::EntityPigDeath,
+            ),
+            EntityKind::SHEEP => neutral(
+                Some(Sound::EntitySheepAmbient),
+                Sound::EntitySheepHurt,
+                Sound::EntitySheepDeath,
+            ),
+            EntityKind::CHICKEN => neutral(
+                Some(Sound::EntityChickenAmbient),
+                Sound::EntityChickenHurt,
+                Sound::EntityChickenDeath,
+            ),
+            EntityKind::HORSE => neutral(
+                Some(Sound::EntityHorseAmbient),
+                Sound::EntityHorseHurt,
+                Sound::EntityHorseDeath,
+            ),
+            EntityKind::SKELETON_HORSE => neutral(
+                Some(Sound::EntitySkeletonHorseAmbient),
+                Sound::EntitySkeletonHorseHurt,
+                Sound::EntitySkeletonHorseDeath,
+            ),
+            EntityKind::ZOMBIE_HORSE => neutral(
+                Some(Sound::EntityZombieHorseAmbient),
+                Sound::EntityZombieHorseHurt,
+                Sound::EntityZombieHorseDeath,
+            ),
+            EntityKind::DONKEY => neutral(
+                Some(Sound::EntityDonkeyAmbient),
+                Sound::EntityDonkeyHurt,
+                Sound::EntityDonkeyDeath,
+            ),
+            EntityKind::MULE => neutral(
+                Some(Sound::EntityMuleAmbient),
+                Sound::EntityMuleHurt,
+                Sound::EntityMuleDeath,
+            ),
+            EntityKind::LLAMA => neutral(
+                Some(Sound::EntityLlamaAmbient),
+                Sound::EntityLlamaHurt,
+                Sound::EntityLlamaDeath,
+            ),
+            EntityKind::TRADER_LLAMA => neutral(
+                Some(Sound::EntityLlamaAmbient),
+                Sound::EntityLlamaHurt,
+                Sound::EntityLlamaDeath,
+            ),
+            EntityKind::WOLF => neutral(
+                Some(Sound::EntityWolfAmbient),
+                Sound::EntityWolfHurt,
+                Sound::EntityWolfDeath,
+            ),
+            EntityKind::CAT => neutral(
+                Some(Sound::EntityCatAmbient),
+                Sound::EntityCatHurt,
+                Sound::EntityCatDeath,
+            ),
+            EntityKind::OCELOT => neutral(
+                Some(Sound::EntityOcelotAmbient),
+                Sound::EntityOcelotHurt,
+                Sound::EntityOcelotDeath,
+            ),
+            EntityKind::FOX => neutral(
+                Some(Sound::EntityFoxAmbient),
+                Sound::EntityFoxHurt,
+                Sound::EntityFoxDeath,
+            ),
+            EntityKind::RABBIT => neutral(
+                Some(Sound::EntityRabbitAmbient),
+                Sound::EntityRabbitHurt,
+                Sound::EntityRabbitDeath,
+            ),
+            EntityKind::PARROT => neutral(
+                Some(Sound::EntityParrotAmbient),
+                Sound::EntityParrotHurt,
+                Sound::EntityParrotDeath,
+            ),
+            EntityKind::BEE => neutral(None, Sound::EntityBeeHurt, Sound::EntityBeeDeath),
+            EntityKind::TURTLE => neutral(None, Sound::EntityTurtleHurt, Sound::EntityTurtleDeath),
+            EntityKind::PANDA => neutral(
+                Some(Sound::EntityPandaAmbient),
+                Sound::EntityPandaHurt,
+                Sound::EntityPandaDeath,
+            ),
+            EntityKind::POLAR_BEAR => neutral(
+                Some(Sound::EntityPolarBearAmbient),
+                Sound::EntityPolarBearHurt,
+                Sound::EntityPolarBearDeath,
+            ),
+            EntityKind::GOAT => neutral(
+                Some(Sound::EntityGoatAmbient),
+                Sound::EntityGoatHurt,
+                Sound::EntityGoatDeath,
+            ),
+            EntityKind::AXOLOTL => {
+                neutral(None, Sound::EntityAxolotlHurt, Sound::EntityAxolotlDeath)
+            }
+            EntityKind::FROG => neutral(
+                Some(Sound::EntityFrogAmbient),
+                Sound::EntityFrogHurt,
+                Sound::EntityFrogDeath,
+            ),
+            EntityKind::TADPOLE => {
+                neutral(None, Sound::EntityTadpoleHurt, Sound::EntityTadpoleDeath)
+            }
+            EntityKind::CAMEL => neutral(
+                Some(Sound::EntityCamelAmbient),
+                Sound::EntityCamelHurt,
+                Sound::EntityCamelDeath,
+            ),
+            EntityKind::SNIFFER => {
+                neutral(None, Sound::EntitySnifferHurt, Sound::EntitySnifferDeath)
+            }
+            EntityKind::IRON_GOLEM => neutral(
+                None,
+                Sound::EntityIronGolemHurt,
+                Sound::EntityIronGolemDeath,
+            ),
+            EntityKind::SNOW_GOLEM => neutral(
+                Some(Sound::EntitySnowGolemAmbient),
+                Sound::EntitySnowGolemHurt,
+                Sound::EntitySnowGolemDeath,
+            ),
+            EntityKind::VILLAGER => neutral(
+                Some(Sound::EntityVillagerAmbient),
+                Sound::EntityVillagerHurt,
+                Sound::EntityVillagerDeath,
+            ),
+            EntityKind::WANDERING_TRADER => neutral(
+                Some(Sound::EntityWanderingTraderAmbient),
+                Sound::EntityWanderingTraderHurt,
+                Sound::EntityWanderingTraderDeath,
+            ),
+            EntityKind::BAT => neutral(
+                Some(Sound::EntityBatAmbient),
+                Sound::EntityBatHurt,
+                Sound::EntityBatDeath,
+            ),
+            EntityKind::SQUID => neutral(
+                Some(Sound::EntitySquidAmbient),
+                Sound::EntitySquidHurt,
+                Sound::EntitySquidDeath,
+            ),
+            EntityKind::GLOW_SQUID => neutral(
+                Some(Sound::EntityGlowSquidAmbient),
+                Sound::EntityGlowSquidHurt,
+                Sound::EntityGlowSquidDeath,
+            ),
+            EntityKind::DOLPHIN => neutral(
+                Some(Sound::EntityDolphinAmbient),
+                Sound::EntityDolphinHurt,
+                Sound::EntityDolphinDeath,
+            ),
+            EntityKind::COD => neutral(
+                Some(Sound::EntityCodAmbient),
+                Sound::EntityCodHurt,
+                Sound::EntityCodDeath,
+            ),
+            EntityKind::SALMON => neutral(
+                Some(Sound::EntitySalmonAmbient),
+                Sound::EntitySalmonHurt,
+                Sound::EntitySalmonDeath,
+            ),
+            EntityKind::TROPICAL_FISH => neutral(
+                Some(Sound::EntityTropicalFishAmbient),
+                Sound::EntityTropicalFishHurt,
+                Sound::EntityTropicalFishDeath,
+            ),
+            EntityKind::PUFFERFISH => neutral(
+                Some(Sound::EntityPufferFishAmbient),
+                Sound::EntityPufferFishHurt,
+                Sound::EntityPufferFishDeath,
+            ),
+            EntityKind::ALLAY => neutral(None, Sound::EntityAllayHurt, Sound::EntityAllayDeath),
+            EntityKind::STRIDER => neutral(
+                Some(Sound::EntityStriderAmbient),
+                Sound::EntityStriderHurt,
+                Sound::EntityStriderDeath,
+            ),
+            EntityKind::PLAYER => player(None, Sound::EntityPlayerHurt, Sound::EntityPlayerDeath),
+            _ => return None,
+        })
+    }
+
+    pub fn get(&self, kind: EntitySoundKind) -> Option<Sound> {
+        match kind {
+            EntitySoundKind::Ambient => self.ambient,
+            EntitySoundKind::Hurt => self.hurt,
+            EntitySoundKind::Death => self.death,
+        }
+    }
+
+    /// Returns the pitch with the random variation applied, like vanilla
+    /// does for every sound an entity makes.
+    pub fn random_pitch(&self) -> f32 {
+        self.pitch + (rand::random::<f32>() - rand::random::<f32>()) * 0.2
+    }
+}
+
+/// The kinds of sounds in [`EntitySounds`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum EntitySoundKind {
+    Ambient,
+    Hurt,
+    Death,
+}
+
+/// Plays one of the [`EntitySounds`] of `entity` to the clients viewing it.
+///
+/// Hurt and death sounds are sent automatically when the [`Health`] of an
+/// entity decreases, and ambient sounds at random intervals. Combat or AI
+/// code can send this event to play them at other times.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct EntitySoundEvent {
+    pub entity: Entity,
+    pub kind: EntitySoundKind,
+}
+
+/// Tracks when an entity last made a sound.
+#[derive(Component, Clone, Debug)]
+pub struct EntitySoundState {
+    last_health: Option<f32>,
+    /// Counts up every tick. The higher it gets, the more likely an ambient
+    /// sound is played.
+    ambient_chance: i32,
+}
+
+impl Default for EntitySoundState {
+    fn default() -> Self {
+        Self {
+            last_health: None,
+            ambient_chance: -MIN_AMBIENT_SOUND_DELAY,
+        }
+    }
+}
+
+fn init_entity_sounds(
+    entities: Query<
+        (Entity, &EntityKind, Has<EntitySounds>),
+        (Added<EntityKind>, With<Health>, Without<Despawned>),
+    >,
+    mut commands: Commands,
+) {
+    for (entity, kind, has_sounds) in &entities {
+        let mut entity = commands.entity(entity);
+
+        entity.insert(EntitySoundState::default());
+
+        if !has_sounds {
+            if let Some(sounds) = EntitySounds::for_kind(*kind) {
+                entity.insert(sounds);
+            }
+        }
+    }
+}
+
+fn emit_health_sounds(
+    mut entities: Query<(Entity, &Health, &mut EntitySoundState), Changed<Health>>,
+    mut events: EventWriter<EntitySoundEvent>,
+) {
+    for (entity, health, mut state) in &mut entities {
+        if let Some(last_health) = state.last_health {
+            if health.0 < last_health && last_health > 0.0 {
+                let kind = if health.0 <= 0.0 {
+                    EntitySoundKind::Death
+                } else {
+                    EntitySoundKind::Hurt
+                };
+
+                events.send(EntitySoundEvent { entity, kind });
+            }
+        }
+
+        state.last_health = Some(health.0);
+    }
+}
+
+fn emit_ambient_sounds(
+    mut entities: Query<(Entity, &EntitySounds, &Health, &mut EntitySoundState)>,
+    mut events: EventWriter<EntitySoundEvent>,
+) {
+    for (entity, sounds, health, mut state) in &mut entities {
+        if sounds.ambient.is_none() || health.0 <= 0.0 {
+            continue;
+        }
+
+        // Same odds as vanilla: the chance increases each tick until a sound
+        // is played.
+        let chance = state.ambient_chance;
+        state.ambient_chance += 1;
+
+        if rand::random::<u32>() % 1000 < chance.max(0) as u32 {
+            state.ambient_chance = -MIN_AMBIENT_SOUND_DELAY;
+
+            events.send(EntitySoundEvent {
+                entity,
+                kind: EntitySoundKind::Ambient,
+            });
+        }
+    }
+}
+
+fn play_entity_sounds(
+    mut events: EventReader<EntitySoundEvent>,
+    entities: Query<(&EntityId, &EntityLayerId, &Position, &EntitySounds)>,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for event in events.read() {
+        let Ok((id, layer_id, pos, sounds)) = entities.get(event.entity) else {
+            continue;
+        };
+
+        let Some(sound) = sounds.get(event.kind) else {
+            continue;
+        };
+
+        let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+            continue;
+        };
+
+        layer
+            .view_writer(pos.0)
+            .write_packet(&PlaySoundFromEntityS2c {
+                // Sound IDs are offset by one, because zero means the sound is given
+                // inline.
+                id: VarInt(sound.to_raw() as i32 + 1),
+                category: sounds.category,
+                entity_id: VarInt(id.get()),
+                volume: sounds.volume,
+                pitch: sounds.random_pitch(),
+                seed: rand::random(),
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_entity_sounds() {
+        let zombie = EntitySounds::for_kind(EntityKind::ZOMBIE).unwrap();
+        assert_eq!(zombie.category, SoundCategory::Hostile);
+        assert_eq!(zombie.ambient, Some(Sound::EntityZombieAmbient));
+        assert_eq!(
+            zombie.get(EntitySoundKind::Death),
+            Some(Sound::EntityZombieDeath)
+        );
+
+        let player = EntitySounds::for_kind(EntityKind::PLAYER).unwrap();
+        assert_eq!(player.category, SoundCategory::Player);
+        assert_eq!(player.ambient, None);
+
+        assert!(EntitySounds::for_kind(EntityKind::ITEM).is_none());
+    }
+}
@@ -1,7 +1,9 @@
+use std::collections::HashSet;
 use std::time::Instant;
 
 use bevy_app::prelude::*;
 use bevy_app::MainScheduleOrder;
+use bevy_ecs::event::EventId;
 use bevy_ecs::prelude::*;
 use bevy_ecs::schedule::ScheduleLabel;
 use bevy_ecs::system::SystemState;
@@ -10,17 +12,26 @@ use tracing::{debug, warn};
 use valence_protocol::{Decode, Packet};
 
 use crate::client::Client;
+use crate::protocol_error::{
+    send_protocol_error, send_protocol_error_events, ProtocolError, ProtocolErrorEvent,
+    ProtocolErrorKind, ProtocolErrorSink, ProtocolErrorStats,
+};
 
 pub struct EventLoopPlugin;
 
 impl Plugin for EventLoopPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<PacketEvent>()
+            .add_event::<ProtocolErrorEvent>()
+            .init_resource::<CancelledPackets>()
+            .init_resource::<ProtocolErrorSink>()
+            .init_resource::<ProtocolErrorStats>()
             .add_schedule(Schedule::new(RunEventLoop))
             .add_schedule(Schedule::new(EventLoopPreUpdate))
             .add_schedule(Schedule::new(EventLoopUpdate))
             .add_schedule(Schedule::new(EventLoopPostUpdate))
-            .add_systems(RunEventLoop, run_event_loop);
+            .add_systems(RunEventLoop, run_event_loop)
+            .add_systems(Last, send_protocol_error_events);
 
         app.world
             .resource_mut::<MainScheduleOrder>()
@@ -44,6 +55,33 @@ pub struct EventLoopUpdate;
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EventLoopPostUpdate;
 
+/// The systems in [`EventLoopPreUpdate`] which turn digging, block
+/// interaction, and entity interaction packets into events. They skip the
+/// packets in [`CancelledPackets`].
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct HandleActionPacketsSet;
+
+/// [`PacketEvent`]s which should not be turned into events. Systems in
+/// [`EventLoopPreUpdate`] which run before [`HandleActionPacketsSet`] can
+/// cancel a packet to deny the action of a client, so that the systems
+/// handling the action never see it.
+///
+/// Only the packets handled in [`HandleActionPacketsSet`] can be cancelled.
+/// The sequence numbers of cancelled packets are still acknowledged, but the
+/// client's prediction of the action is not undone.
+#[derive(Resource, Default, Debug)]
+pub struct CancelledPackets(HashSet<EventId<PacketEvent>>);
+
+impl CancelledPackets {
+    pub fn cancel(&mut self, packet: EventId<PacketEvent>) {
+        self.0.insert(packet);
+    }
+
+    pub fn is_cancelled(&self, packet: EventId<PacketEvent>) -> bool {
+        self.0.contains(&packet)
+    }
+}
+
 #[derive(Event, Clone, Debug)]
 pub struct PacketEvent {
     /// The client this packet originated from.
@@ -61,8 +99,29 @@ impl PacketEvent {
     ///
     /// If the packet ID is mismatched or an error occurs, `None` is returned.
     /// Otherwise, `Some` is returned containing the decoded packet.
+    ///
+    /// Errors are only logged. Use [`PacketEvent::decode_reporting`] to also
+    /// send them as [`ProtocolErrorEvent`]s.
     #[inline]
     pub fn decode<'a, P>(&'a self) -> Option<P>
+    where
+        P: Packet + Decode<'a>,
+    {
+        self.decode_inner(None)
+    }
+
+    /// Like [`PacketEvent::decode`], but errors are also pushed to `errors`
+    /// and later sent as [`ProtocolErrorEvent`]s.
+    #[inline]
+    pub fn decode_reporting<'a, P>(&'a self, errors: &ProtocolErrorSink) -> Option<P>
+    where
+        P: Packet + Decode<'a>,
+    {
+        self.decode_inner(Some(errors))
+    }
+
+    #[inline]
+    fn decode_inner<'a, P>(&'a self, errors: Option<&ProtocolErrorSink>) -> Option<P>
     where
         P: Packet + Decode<'a>,
     {
@@ -82,15 +141,48 @@ impl PacketEvent {
                         P::ID
                     );
                     debug!("complete packet after partial decode: {pkt:?}");
+
+                    if let Some(errors) = errors {
+                        self.report_error(
+                            errors,
+                            ProtocolErrorKind::TrailingBytes,
+                            P::NAME,
+                            format!("{} bytes left over", r.len()),
+                        );
+                    }
                 }
                 Err(e) => {
                     warn!("failed to decode packet with ID of {}: {e:#}", P::ID);
+
+                    if let Some(errors) = errors {
+                        self.report_error(
+                            errors,
+                            ProtocolErrorKind::Decode,
+                            P::NAME,
+                            format!("{e:#}"),
+                        );
+                    }
                 }
             }
         }
 
         None
     }
+
+    #[cold]
+    fn report_error(
+        &self,
+        errors: &ProtocolErrorSink,
+        kind: ProtocolErrorKind,
+        packet_name: &'static str,
+        message: String,
+    ) {
+        errors.push(
+            self.client,
+            self.timestamp,
+            ProtocolError::packet(kind, self.id, packet_name, message, &self.data),
+        );
+    }
 }
 
 fn run_event_loop_schedules(world: &mut World) {
@@ -107,12 +199,17 @@ fn run_event_loop(
         Query<(Entity, &mut Client)>,
         EventWriter<PacketEvent>,
         Commands,
+        ResMut<ProtocolErrorStats>,
+        EventWriter<ProtocolErrorEvent>,
     )>,
     mut check_again: Local<Vec<(Entity, usize)>>,
 ) {
     debug_assert!(check_again.is_empty());
 
-    let (mut clients, mut event_writer, mut commands) = state.get_mut(world);
+    world.resource_mut::<CancelledPackets>().0.clear();
+
+    let (mut clients, mut event_writer, mut commands, mut stats, mut error_writer) =
+        state.get_mut(world);
 
     for (entity, mut client) in &mut clients {
         match client.connection_mut().try_recv() {
@@ -134,6 +231,11 @@ fn run_event_loop(
             Err(e) => {
                 // Client is disconnected.
                 debug!("disconnecting client: {e:#}");
+
+                if let Some(error) = e.downcast_ref::<ProtocolError>() {
+                    send_protocol_error(&mut stats, &mut error_writer, entity, error.clone());
+                }
+
                 commands.entity(entity).remove::<Client>();
             }
         }
@@ -143,7 +245,8 @@ fn run_event_loop(
     run_event_loop_schedules(world);
 
     while !check_again.is_empty() {
-        let (mut clients, mut event_writer, mut commands) = state.get_mut(world);
+        let (mut clients, mut event_writer, mut commands, mut stats, mut error_writer) =
+            state.get_mut(world);
 
         check_again.retain_mut(|(entity, remaining)| {
             debug_assert!(*remaining > 0);
@@ -165,6 +268,16 @@ fn run_event_loop(
                     Err(e) => {
                         // Client is disconnected.
                         debug!("disconnecting client: {e:#}");
+
+                        if let Some(error) = e.downcast_ref::<ProtocolError>() {
+                            send_protocol_error(
+                                &mut stats,
+                                &mut error_writer,
+                                *entity,
+                                error.clone(),
+                            );
+                        }
+
                         commands.entity(*entity).remove::<Client>();
                         false
                     }
@@ -0,0 +1,359 @@
+//! Experience points, levels, and experience orbs.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::WorldQuery;
+use derive_more::Deref;
+use valence_entity::experience_orb::{ExperienceOrbEntity, ExperienceOrbEntityBundle};
+use valence_entity::{EntityId, EntityLayerId, ObjectData, Position};
+use valence_math::DVec3;
+use valence_protocol::packets::play::{ExperienceBarUpdateS2c, ItemPickupAnimationS2c};
+use valence_protocol::{GameMode, VarInt, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::client::{Client, UpdateClientsSet};
+use crate::layer::{EntityLayer, UpdateLayersPreClientSet};
+use crate::Layer;
+
+pub struct ExperiencePlugin;
+
+impl Plugin for ExperiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (
+                attract_experience_orbs.before(UpdateLayersPreClientSet),
+                update_experience_bar.in_set(UpdateClientsSet),
+            ),
+        );
+    }
+}
+
+/// The distance in blocks at which experience orbs start moving towards
+/// players.
+pub const ORB_ATTRACT_DISTANCE: f64 = 8.0;
+
+/// The distance in blocks at which experience orbs are absorbed by players.
+pub const ORB_PICKUP_DISTANCE: f64 = 1.0;
+
+/// The experience level of a client, as displayed above the experience bar.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug, Deref)]
+pub struct ExperienceLevel(pub u32);
+
+/// The total number of experience points a client has collected.
+///
+/// The progress of the experience bar is derived from this and the
+/// [`ExperienceLevel`]. Use [`ExperienceQuery`] to modify both at once.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug, Deref)]
+pub struct ExperiencePoints(pub u32);
+
+/// Returns the number of experience points needed to go from `level` to the
+/// next level.
+pub const fn xp_to_next_level(level: u32) -> u32 {
+    match level {
+        0..=15 => 2 * level + 7,
+        16..=30 => 5 * level - 38,
+        _ => level.saturating_mul(9) - 158,
+    }
+}
+
+/// Returns the total number of experience points needed to reach `level` from
+/// zero. Saturates at [`u32::MAX`].
+pub const fn total_xp_for_level(level: u32) -> u32 {
+    let xp = total_xp_for_level_u64(level);
+
+    if xp > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        xp as u32
+    }
+}
+
+const fn total_xp_for_level_u64(level: u32) -> u64 {
+    let l = level as u64;
+
+    match level {
+        0..=16 => l * l + 6 * l,
+        // 2.5 * l^2 - 40.5 * l + 360
+        17..=31 => (5 * l * l + 720 - 81 * l) / 2,
+        // 4.5 * l^2 - 162.5 * l + 2220, saturating for levels far above
+        // `MAX_LEVEL`.
+        _ => (l.saturating_mul(l).saturating_mul(9).saturating_add(4440) - 325 * l) / 2,
+    }
+}
+
+/// The highest level whose total experience fits in [`ExperiencePoints`].
+pub const MAX_LEVEL: u32 = level_for_total_xp(u32::MAX);
+
+/// Returns the level reached after collecting `total_xp` experience points
+/// from zero.
+pub const fn level_for_total_xp(total_xp: u32) -> u32 {
+    // Binary search for the highest level whose total is within `total_xp`.
+    // Level 2^16 is already out of range for any `u32` total.
+    let mut lo: u32 = 0;
+    let mut hi: u32 = 1 << 16;
+
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+
+        if total_xp_for_level_u64(mid) <= total_xp as u64 {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+
+    lo
+}
+
+/// Query for modifying the experience of a client while keeping the level and
+/// points consistent.
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct ExperienceQuery {
+    pub level: &'static mut ExperienceLevel,
+    pub points: &'static mut ExperiencePoints,
+}
+
+impl ExperienceQueryItem<'_> {
+    /// Adds experience points, leveling up (or down if `amount` is negative) as
+    /// needed.
+    pub fn add_xp(&mut self, amount: i32) {
+        let total = self.points.0.saturating_add_signed(amount);
+
+        self.points.0 = total;
+        self.level.0 = level_for_total_xp(total);
+    }
+
+    /// Adds levels while preserving the progress towards the next level. The
+    /// level stops at [`MAX_LEVEL`].
+    pub fn add_levels(&mut self, levels: i32) {
+        let progress = self.progress();
+        let level = self.level.0.saturating_add_signed(levels).min(MAX_LEVEL);
+        let progress_xp = (progress * xp_to_next_level(level) as f32).round() as u32;
+
+        self.level.0 = level;
+        self.points.0 = total_xp_for_level(level).saturating_add(progress_xp);
+    }
+
+    /// Sets the experience to exactly `level` with no progress. The level
+    /// stops at [`MAX_LEVEL`].
+    pub fn set_level(&mut self, level: u32) {
+        let level = level.min(MAX_LEVEL);
+
+        self.level.0 = level;
+        self.points.0 = total_xp_for_level(level);
+    }
+
+    /// Returns the fill of the experience bar in `0.0..1.0`.
+    pub fn progress(&self) -> f32 {
+        experience_bar_progress(*self.level, *self.points)
+    }
+}
+
+fn experience_bar_progress(level: ExperienceLevel, points: ExperiencePoints) -> f32 {
+    let base = total_xp_for_level(level.0);
+
+    if points.0 < base {
+        return 0.0;
+    }
+
+    ((points.0 - base) as f32 / xp_to_next_level(level.0) as f32).clamp(0.0, 1.0)
+}
+
+/// Splits `amount` into the orb sizes used by the vanilla server.
+pub fn split_into_orbs(mut amount: u32) -> Vec<u32> {
+    const SIZES: [u32; 11] = [2477, 1237, 617, 307, 149, 73, 37, 17, 7, 3, 1];
+
+    let mut orbs = vec![];
+
+    while amount > 0 {
+        let size = SIZES
+            .into_iter()
+            .find(|&s| s <= amount)
+            .expect("size of 1 always fits");
+
+        orbs.push(size);
+        amount -= size;
+    }
+
+    orbs
+}
+
+/// Spawns experience orbs worth a total of `amount` points at `position`.
+pub fn spawn_experience_orbs(
+    commands: &mut Commands,
+    layer: Entity,
+    position: impl Into<DVec3>,
+    amount: u32,
+) {
+    let position = position.into();
+
+    for size in split_into_orbs(amount) {
+        commands.spawn(ExperienceOrbEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position(position),
+            object_data: ObjectData(size as i32),
+            ..Default::default()
+        });
+    }
+}
+
+fn attract_experience_orbs(
+    mut orbs: Query<
+        (
+            Entity,
+            &EntityId,
+            &mut Position,
+            &EntityLayerId,
+            &ObjectData,
+        ),
+        (
+            With<ExperienceOrbEntity>,
+            Without<Despawned>,
+            Without<Client>,
+        ),
+    >,
+    mut clients: Query<
+        (
+            Entity,
+            &mut Client,
+            &EntityId,
+            &Position,
+            &EntityLayerId,
+            &GameMode,
+            ExperienceQuery,
+        ),
+        Without<ExperienceOrbEntity>,
+    >,
+    mut layers: Query<&mut EntityLayer>,
+    mut commands: Commands,
+) {
+    for (orb, orb_id, mut orb_pos, orb_layer, value) in &mut orbs {
+        let nearest = clients
+            .iter_mut()
+            .filter(|(_, _, _, pos, layer, mode, _)| {
+                layer.0 == orb_layer.0
+                    && **mode != GameMode::Spectator
+                    && pos.0.distance(orb_pos.0) < ORB_ATTRACT_DISTANCE
+            })
+            .min_by(|a, b| {
+                a.3 .0
+                    .distance_squared(orb_pos.0)
+                    .total_cmp(&b.3 .0.distance_squared(orb_pos.0))
+            });
+
+        let Some((client_entity, mut client, client_id, client_pos, _, _, mut xp)) = nearest else {
+            continue;
+        };
+
+        // Aim at the player's waist.
+        let target = client_pos.0 + DVec3::new(0.0, 0.9, 0.0);
+        let dist = target.distance(orb_pos.0);
+
+        if dist < ORB_PICKUP_DISTANCE {
+            xp.add_xp(value.0);
+
+            client.write_packet(&ItemPickupAnimationS2c {
+                collected_entity_id: VarInt(orb_id.get()),
+                collector_entity_id: VarInt(0), // The client always sees itself with ID 0.
+                pickup_item_count: VarInt(1),
+            });
+
+            if let Ok(mut layer) = layers.get_mut(orb_layer.0) {
+                layer
+                    .view_except_writer(orb_pos.0, client_entity)
+                    .write_packet(&ItemPickupAnimationS2c {
+                        collected_entity_id: VarInt(orb_id.get()),
+                        collector_entity_id: VarInt(client_id.get()),
+                        pickup_item_count: VarInt(1),
+                    });
+            }
+
+            commands.entity(orb).insert(Despawned);
+        } else {
+            // Orbs speed up as they get closer.
+            let closeness = 1.0 - dist / ORB_ATTRACT_DISTANCE;
+            let step = (0.1 + closeness * closeness * 0.8).min(dist);
+
+            let pos = orb_pos.0;
+            orb_pos.0 = pos + (target - pos) / dist * step;
+        }
+    }
+}
+
+fn update_experience_bar(
+    mut clients: Query<
+        (&mut Client, &ExperienceLevel, &ExperiencePoints),
+        Or<(Changed<ExperienceLevel>, Changed<ExperiencePoints>)>,
+    >,
+) {
+    for (mut client, level, points) in &mut clients {
+        client.write_packet(&ExperienceBarUpdateS2c {
+            bar: experience_bar_progress(*level, *points),
+            level: VarInt(level.0.min(i32::MAX as u32) as i32),
+            total_xp: VarInt(points.0.min(i32::MAX as u32) as i32),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_curve() {
+        assert_eq!(total_xp_for_level(0), 0);
+        assert_eq!(total_xp_for_level(u32::MAX), u32::MAX);
+        assert_eq!(total_xp_for_level(16), 352);
+        assert_eq!(total_xp_for_level(17), 394);
+        assert_eq!(total_xp_for_level(30), 1395);
+        assert_eq!(total_xp_for_level(31), 1507);
+        assert_eq!(total_xp_for_level(32), 1628);
+
+        for level in 0..100 {
+            assert_eq!(
+                total_xp_for_level(level) + xp_to_next_level(level),
+                total_xp_for_level(level + 1),
+                "level {level}"
+            );
+            assert_eq!(level_for_total_xp(total_xp_for_level(level)), level);
+        }
+    }
+
+    #[test]
+    fn add_levels_saturates() {
+        let mut world = World::new();
+        let entity = world
+            .spawn((
+                ExperienceLevel(10),
+                ExperiencePoints(total_xp_for_level(10) + 10),
+            ))
+            .id();
+
+        let mut query = world.query::<ExperienceQuery>();
+        let mut xp = query.get_mut(&mut world, entity).unwrap();
+
+        xp.add_levels(i32::MAX);
+        assert_eq!(xp.level.0, MAX_LEVEL);
+        assert_eq!(level_for_total_xp(xp.points.0), MAX_LEVEL);
+
+        xp.add_levels(i32::MAX);
+        assert_eq!(xp.level.0, MAX_LEVEL);
+
+        xp.add_levels(i32::MIN);
+        assert_eq!(xp.level.0, 0);
+        assert_eq!(level_for_total_xp(xp.points.0), 0);
+
+        xp.set_level(u32::MAX);
+        assert_eq!(xp.level.0, MAX_LEVEL);
+        assert_eq!(xp.points.0, total_xp_for_level(MAX_LEVEL));
+    }
+
+    #[test]
+    fn orb_sizes() {
+        assert_eq!(split_into_orbs(0), Vec::<u32>::new());
+        assert_eq!(split_into_orbs(11), vec![7, 3, 1]);
+        assert_eq!(split_into_orbs(20).iter().sum::<u32>(), 20);
+    }
+}
@@ -0,0 +1,277 @@
+//! Finding entities and clients by layer, position, and view.
+//!
+//! Many systems need "the entities near this point" or "the clients which can
+//! see this chunk". Instead of joining [`Position`], [`EntityLayerId`], and
+//! view components by hand, use the [`LayerEntities`] and [`Viewers`] system
+//! parameters. Entities are narrowed down with the runtime
+//! [`EntityFilter`]s [`InLayer`] and [`WithinDistance`], which can be
+//! combined with tuples, and with regular query filters like
+//! `With<LivingEntity>`.
+//!
+//! When the filter limits the search to one layer and a bounded area, only
+//! the chunks of that area are visited, so the cost doesn't depend on the
+//! total number of entities. The chunks of entities are updated in
+//! [`PostUpdate`], so entities spawned or moved into the area this tick may
+//! be missed until the next tick.
+//!
+//! [`PostUpdate`]: bevy_app::PostUpdate
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::entity::{EntityLayerId, Position};
+//! use valence_server::filter::{InLayer, LayerEntities, LivingEntity, WithinDistance};
+//!
+//! #[derive(Component)]
+//! struct Turret;
+//!
+//! fn find_targets(
+//!     turrets: Query<(Entity, &Position, &EntityLayerId), With<Turret>>,
+//!     living: LayerEntities<With<LivingEntity>>,
+//! ) {
+//!     for (turret, pos, layer) in &turrets {
+//!         for target in living.iter((InLayer(layer.0), WithinDistance::new(pos.0, 16.0))) {
+//!             if target != turret {
+//!                 println!("{turret:?} can shoot at {target:?}");
+//!             }
+//!         }
+//!     }
+//! }
+//! # let _ = find_targets;
+//! ```
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::ReadOnlyWorldQuery;
+use bevy_ecs::system::SystemParam;
+pub use valence_entity::living::LivingEntity;
+use valence_entity::{EntityLayerId, Position};
+use valence_math::DVec3;
+use valence_protocol::ChunkPos;
+use valence_server_common::Despawned;
+
+use crate::client::{ClientMarker, View, VisibleChunkLayer, VisibleEntityLayers};
+use crate::layer::EntityLayer;
+
+/// A condition on the layer and position of an entity, checked at runtime.
+///
+/// Filters are combined with tuples, which match entities matching every
+/// filter of the tuple.
+pub trait EntityFilter {
+    fn matches(&self, layer: Entity, pos: DVec3) -> bool;
+
+    /// Returns the layer all matching entities are in, if there is one.
+    fn layer(&self) -> Option<Entity> {
+        None
+    }
+
+    /// Returns the corners of the smallest area of chunks containing all
+    /// matching entities, if it is bounded.
+    fn chunk_bounds(&self) -> Option<(ChunkPos, ChunkPos)> {
+        None
+    }
+}
+
+/// Matches every entity.
+impl EntityFilter for () {
+    fn matches(&self, _layer: Entity, _pos: DVec3) -> bool {
+        true
+    }
+}
+
+impl<A: EntityFilter, B: EntityFilter> EntityFilter for (A, B) {
+    fn matches(&self, layer: Entity, pos: DVec3) -> bool {
+        self.0.matches(layer, pos) && self.1.matches(layer, pos)
+    }
+
+    fn layer(&self) -> Option<Entity> {
+        self.0.layer().or_else(|| self.1.layer())
+    }
+
+    fn chunk_bounds(&self) -> Option<(ChunkPos, ChunkPos)> {
+        match (self.0.chunk_bounds(), self.1.chunk_bounds()) {
+            (Some((min_a, max_a)), Some((min_b, max_b))) => Some((
+                ChunkPos::new(min_a.x.max(min_b.x), min_a.z.max(min_b.z)),
+                ChunkPos::new(max_a.x.min(max_b.x), max_a.z.min(max_b.z)),
+            )),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+impl<A: EntityFilter, B: EntityFilter, C: EntityFilter> EntityFilter for (A, B, C) {
+    fn matches(&self, layer: Entity, pos: DVec3) -> bool {
+        ((&self.0, &self.1), &self.2).matches(layer, pos)
+    }
+
+    fn layer(&self) -> Option<Entity> {
+        ((&self.0, &self.1), &self.2).layer()
+    }
+
+    fn chunk_bounds(&self) -> Option<(ChunkPos, ChunkPos)> {
+        ((&self.0, &self.1), &self.2).chunk_bounds()
+    }
+}
+
+impl<F: EntityFilter + ?Sized> EntityFilter for &F {
+    fn matches(&self, layer: Entity, pos: DVec3) -> bool {
+        (**self).matches(layer, pos)
+    }
+
+    fn layer(&self) -> Option<Entity> {
+        (**self).layer()
+    }
+
+    fn chunk_bounds(&self) -> Option<(ChunkPos, ChunkPos)> {
+        (**self).chunk_bounds()
+    }
+}
+
+/// Matches entities in the given entity layer.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InLayer(pub Entity);
+
+impl EntityFilter for InLayer {
+    fn matches(&self, layer: Entity, _pos: DVec3) -> bool {
+        layer == self.0
+    }
+
+    fn layer(&self) -> Option<Entity> {
+        Some(self.0)
+    }
+}
+
+/// Matches entities within `radius` blocks of `center`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct WithinDistance {
+    pub center: DVec3,
+    pub radius: f64,
+}
+
+impl WithinDistance {
+    pub fn new(center: impl Into<DVec3>, radius: f64) -> Self {
+        Self {
+            center: center.into(),
+            radius,
+        }
+    }
+}
+
+impl EntityFilter for WithinDistance {
+    fn matches(&self, _layer: Entity, pos: DVec3) -> bool {
+        pos.distance_squared(self.center) <= self.radius * self.radius
+    }
+
+    fn chunk_bounds(&self) -> Option<(ChunkPos, ChunkPos)> {
+        let r = DVec3::new(self.radius, 0.0, self.radius);
+
+        Some((
+            ChunkPos::from(self.center - r),
+            ChunkPos::from(self.center + r),
+        ))
+    }
+}
+
+/// [`SystemParam`] for finding the entities matching an [`EntityFilter`].
+/// Only entities matching the query filter `F` are returned. Despawned
+/// entities are never returned.
+#[derive(SystemParam)]
+pub struct LayerEntities<'w, 's, F: ReadOnlyWorldQuery + 'static = ()> {
+    entities:
+        Query<'w, 's, (Entity, &'static EntityLayerId, &'static Position), (F, Without<Despawned>)>,
+    layers: Query<'w, 's, &'static EntityLayer>,
+}
+
+impl<F: ReadOnlyWorldQuery + 'static> LayerEntities<'_, '_, F> {
+    /// Returns the entities matching `filter` in an unspecified order.
+    pub fn iter<'a>(&'a self, filter: impl EntityFilter + 'a) -> impl Iterator<Item = Entity> + 'a {
+        let area = filter
+            .layer()
+            .zip(filter.chunk_bounds())
+            .and_then(|(layer, bounds)| Some((self.layers.get(layer).ok()?, bounds)));
+
+        // Visit only the chunks of the area if possible.
+        let candidates: Box<dyn Iterator<Item = Entity> + 'a> = match area {
+            Some((layer, (min, max))) => Box::new(
+                (min.z..=max.z)
+                    .flat_map(move |z| (min.x..=max.x).map(move |x| ChunkPos::new(x, z)))
+                    .flat_map(move |pos| layer.entities_at(pos)),
+            ),
+            None => Box::new(self.entities.iter().map(|(entity, _, _)| entity)),
+        };
+
+        candidates.filter(move |&entity| {
+            self.entities
+                .get(entity)
+                .is_ok_and(|(_, layer, pos)| filter.matches(layer.0, pos.0))
+        })
+    }
+
+    /// Returns the entity matching `filter` which is closest to `pos`, and
+    /// its distance to `pos`.
+    pub fn closest(
+        &self,
+        pos: impl Into<DVec3>,
+        filter: impl EntityFilter,
+    ) -> Option<(Entity, f64)> {
+        let pos = pos.into();
+
+        self.iter(filter)
+            .filter_map(|entity| {
+                let (_, _, entity_pos) = self.entities.get(entity).ok()?;
+                Some((entity, entity_pos.0.distance(pos)))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+}
+
+/// [`SystemParam`] for finding the clients which can see a chunk or the
+/// entities in it.
+#[derive(SystemParam)]
+pub struct Viewers<'w, 's> {
+    clients: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static VisibleChunkLayer,
+            &'static VisibleEntityLayers,
+            View,
+        ),
+        With<ClientMarker>,
+    >,
+}
+
+impl Viewers<'_, '_> {
+    /// Returns the clients viewing the chunk at `pos` of the chunk layer
+    /// `layer`.
+    pub fn of_chunk(
+        &self,
+        layer: Entity,
+        pos: impl Into<ChunkPos>,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        let pos = pos.into();
+
+        self.clients
+            .iter()
+            .filter(move |(_, visible, _, view)| visible.0 == layer && view.get().contains(pos))
+            .map(|(entity, _, _, _)| entity)
+    }
+
+    /// Returns the clients which see the entities at `pos` of the entity
+    /// layer `layer`.
+    pub fn of_entities_at(
+        &self,
+        layer: Entity,
+        pos: impl Into<ChunkPos>,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        let pos = pos.into();
+
+        self.clients
+            .iter()
+            .filter(move |(_, _, visible, view)| {
+                visible.0.contains(&layer) && view.get().contains(pos)
+            })
+            .map(|(entity, _, _, _)| entity)
+    }
+}
@@ -5,6 +5,7 @@ use valence_protocol::packets::play::HandSwingC2s;
 use valence_protocol::Hand;
 
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct HandSwingPlugin;
 
@@ -23,11 +24,12 @@ pub struct HandSwingEvent {
 
 fn handle_hand_swing(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<&mut EntityAnimations>,
     mut events: EventWriter<HandSwingEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<HandSwingC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<HandSwingC2s>(&errors) {
             if let Ok(mut anim) = clients.get_mut(packet.client) {
                 anim.trigger(match pkt.hand {
                     Hand::Main => EntityAnimation::SwingMainHand,
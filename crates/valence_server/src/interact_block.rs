@@ -5,14 +5,19 @@ use valence_protocol::packets::play::PlayerInteractBlockC2s;
 use valence_protocol::{BlockPos, Direction, Hand};
 
 use crate::action::ActionSequence;
-use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::event_loop::{
+    CancelledPackets, EventLoopPreUpdate, HandleActionPacketsSet, PacketEvent,
+};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct InteractBlockPlugin;
 
 impl Plugin for InteractBlockPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<InteractBlockEvent>()
-            .add_systems(EventLoopPreUpdate, handle_interact_block);
+        app.add_event::<InteractBlockEvent>().add_systems(
+            EventLoopPreUpdate,
+            handle_interact_block.in_set(HandleActionPacketsSet),
+        );
     }
 }
 
@@ -35,15 +40,21 @@ pub struct InteractBlockEvent {
 
 fn handle_interact_block(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    cancelled: Res<CancelledPackets>,
     mut clients: Query<&mut ActionSequence>,
     mut events: EventWriter<InteractBlockEvent>,
 ) {
-    for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<PlayerInteractBlockC2s>() {
+    for (packet, id) in packets.read_with_id() {
+        if let Some(pkt) = packet.decode_reporting::<PlayerInteractBlockC2s>(&errors) {
             if let Ok(mut action_seq) = clients.get_mut(packet.client) {
                 action_seq.update(pkt.sequence.0);
             }
 
+            if cancelled.is_cancelled(id) {
+                continue;
+            }
+
             // TODO: check that the block interaction is valid.
 
             events.send(InteractBlockEvent {
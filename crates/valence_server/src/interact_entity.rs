@@ -1,17 +1,53 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
-use valence_entity::EntityManager;
+use valence_entity::hitbox::{player_eye_height, Hitbox};
+use valence_entity::{entity, EntityLayerId, EntityManager, Position};
+use valence_math::DVec3;
 pub use valence_protocol::packets::play::player_interact_entity_c2s::EntityInteraction;
 use valence_protocol::packets::play::PlayerInteractEntityC2s;
+use valence_protocol::BlockPos;
 
-use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::client::{VisibleChunkLayer, VisibleEntityLayers};
+use crate::event_loop::{
+    CancelledPackets, EventLoopPreUpdate, HandleActionPacketsSet, PacketEvent,
+};
+use crate::layer::ChunkLayer;
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct InteractEntityPlugin;
 
 impl Plugin for InteractEntityPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<InteractEntityEvent>()
-            .add_systems(EventLoopPreUpdate, handle_interact_entity);
+        app.init_resource::<InteractEntitySettings>()
+            .add_event::<InteractEntityEvent>()
+            .add_event::<RejectedInteractEvent>()
+            .add_systems(
+                EventLoopPreUpdate,
+                handle_interact_entity.in_set(HandleActionPacketsSet),
+            );
+    }
+}
+
+/// Controls which entity interactions from clients are accepted.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct InteractEntitySettings {
+    /// The maximum distance from the eyes of the client to the [`Hitbox`] of
+    /// the entity for an interaction to be accepted, or `None` to accept
+    /// interactions from any distance. Defaults to 6, which is what the
+    /// vanilla server accepts.
+    pub max_reach: Option<f64>,
+    /// Whether interactions through solid blocks are rejected. The line of
+    /// sight is checked from the eyes of the client to the closest point on
+    /// the hitbox of the entity.
+    pub require_line_of_sight: bool,
+}
+
+impl Default for InteractEntitySettings {
+    fn default() -> Self {
+        Self {
+            max_reach: Some(6.0),
+            require_line_of_sight: false,
+        }
     }
 }
 
@@ -26,25 +62,117 @@ pub struct InteractEntityEvent {
     pub interact: EntityInteraction,
 }
 
+/// Sent instead of [`InteractEntityEvent`] when an interaction fails the
+/// checks configured in [`InteractEntitySettings`].
+#[derive(Event, Copy, Clone, Debug)]
+pub struct RejectedInteractEvent {
+    pub client: Entity,
+    /// The entity the client tried to interact with.
+    pub entity: Entity,
+    /// The kind of interaction that was attempted.
+    pub interact: EntityInteraction,
+    pub reason: InteractRejection,
+}
+
+/// Why an entity interaction was rejected.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum InteractRejection {
+    /// The entity is not in any of the client's [`VisibleEntityLayers`].
+    OtherLayer,
+    /// The entity is further away than
+    /// [`InteractEntitySettings::max_reach`].
+    OutOfReach {
+        /// The distance from the eyes of the client to the hitbox of the
+        /// entity.
+        distance: f64,
+    },
+    /// A solid block is between the client and the entity.
+    Obstructed {
+        /// The position of the first block in the way.
+        block: BlockPos,
+    },
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_interact_entity(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    cancelled: Res<CancelledPackets>,
     entities: Res<EntityManager>,
+    settings: Res<InteractEntitySettings>,
+    clients: Query<(
+        &Position,
+        Option<&entity::Pose>,
+        &VisibleEntityLayers,
+        &VisibleChunkLayer,
+    )>,
+    targets: Query<(&Position, Option<&Hitbox>, Option<&EntityLayerId>)>,
+    layers: Query<&ChunkLayer>,
     mut events: EventWriter<InteractEntityEvent>,
+    mut rejected: EventWriter<RejectedInteractEvent>,
 ) {
-    for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<PlayerInteractEntityC2s>() {
-            // TODO: check that the entity is in the same instance as the player.
-            // TODO: check that the distance between the player and the interacted entity is
-            // within some configurable tolerance level.
-
-            if let Some(entity) = entities.get_by_id(pkt.entity_id.0) {
-                events.send(InteractEntityEvent {
-                    client: packet.client,
-                    entity,
-                    sneaking: pkt.sneaking,
-                    interact: pkt.interact,
-                })
+    for (packet, id) in packets.read_with_id() {
+        if cancelled.is_cancelled(id) {
+            continue;
+        }
+
+        if let Some(pkt) = packet.decode_reporting::<PlayerInteractEntityC2s>(&errors) {
+            let Some(entity) = entities.get_by_id(pkt.entity_id.0) else {
+                continue;
+            };
+
+            let reject = |reason| RejectedInteractEvent {
+                client: packet.client,
+                entity,
+                interact: pkt.interact,
+                reason,
+            };
+
+            if let (Ok((pos, pose, visible_entity_layers, visible_chunk_layer)), Ok(target)) =
+                (clients.get(packet.client), targets.get(entity))
+            {
+                let (target_pos, target_hitbox, target_layer) = target;
+
+                if let Some(layer) = target_layer {
+                    if !visible_entity_layers.0.contains(&layer.0) {
+                        rejected.send(reject(InteractRejection::OtherLayer));
+                        continue;
+                    }
+                }
+
+                let eye_height = player_eye_height(pose.map_or(Default::default(), |pose| pose.0));
+                let eyes = pos.0 + DVec3::new(0.0, eye_height, 0.0);
+
+                let closest = match target_hitbox {
+                    Some(hitbox) => hitbox.projected_point(eyes),
+                    None => target_pos.0,
+                };
+
+                let distance = closest.distance(eyes);
+
+                if settings.max_reach.is_some_and(|max| distance > max) {
+                    rejected.send(reject(InteractRejection::OutOfReach { distance }));
+                    continue;
+                }
+
+                if settings.require_line_of_sight {
+                    if let Some(hit) = layers
+                        .get(visible_chunk_layer.0)
+                        .ok()
+                        .and_then(|layer| layer.raycast(eyes, closest - eyes, distance))
+                    {
+                        rejected.send(reject(InteractRejection::Obstructed { block: hit.pos }));
+                        continue;
+                    }
+                }
             }
+
+            events.send(InteractEntityEvent {
+                client: packet.client,
+                entity,
+                sneaking: pkt.sneaking,
+                interact: pkt.interact,
+            });
         }
     }
 }
@@ -5,6 +5,7 @@ use valence_protocol::Hand;
 
 use crate::action::ActionSequence;
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct InteractItemPlugin;
 
@@ -24,11 +25,12 @@ pub struct InteractItemEvent {
 
 fn handle_player_interact_item(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<&mut ActionSequence>,
     mut events: EventWriter<InteractItemEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<PlayerInteractItemC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<PlayerInteractItemC2s>(&errors) {
             if let Ok(mut action_seq) = clients.get_mut(packet.client) {
                 action_seq.update(pkt.sequence.0);
             }
@@ -9,6 +9,7 @@ use valence_protocol::WritePacket;
 
 use crate::client::{Client, UpdateClientsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct KeepalivePlugin;
 
@@ -93,11 +94,12 @@ fn send_keepalive(
 
 fn handle_keepalive_response(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<(Entity, &mut KeepaliveState, &mut Ping)>,
     mut commands: Commands,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<KeepAliveC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<KeepAliveC2s>(&errors) {
             if let Ok((entity, mut state, mut ping)) = clients.get_mut(packet.client) {
                 if state.got_keepalive {
                     warn!("unexpected keepalive from client {entity:?}");
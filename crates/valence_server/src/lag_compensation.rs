@@ -0,0 +1,283 @@
+//! Lag compensation for attacks and hit-scan weapons.
+//!
+//! Clients see other entities where they were some time ago because of
+//! network latency. To make hits fair at higher pings, the positions of
+//! entities are recorded every tick and hits are checked against where the
+//! target was when the attacker saw it.
+
+use std::collections::VecDeque;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use valence_entity::entity::Pose;
+use valence_entity::hitbox::{player_eye_height, HitboxShape};
+use valence_entity::Position;
+use valence_math::{Aabb, DVec3};
+use valence_protocol::packets::play::player_interact_entity_c2s::EntityInteraction;
+use valence_server_common::Server;
+
+use crate::event_loop::EventLoopUpdate;
+use crate::interact_entity::InteractEntityEvent;
+use crate::keepalive::Ping;
+
+pub struct LagCompensationPlugin;
+
+impl Plugin for LagCompensationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LagCompensationSettings>()
+            .add_event::<CompensatedAttackEvent>()
+            .add_systems(EventLoopUpdate, compensate_attacks)
+            .add_systems(
+                PostUpdate,
+                (init_position_history, apply_deferred, record_positions).chain(),
+            );
+    }
+}
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct LagCompensationSettings {
+    /// The number of ticks of positions kept for every entity. This is the
+    /// furthest hits can be rewound. Set this to zero to disable lag
+    /// compensation.
+    pub max_rewind_ticks: u32,
+    /// The number of ticks the client renders entities behind their latest
+    /// position because of interpolation. This is added to the rewind of
+    /// every hit.
+    pub interpolation_ticks: u32,
+    /// The maximum distance from the attacker's eyes to the rewound hitbox of
+    /// the target for an attack to be in reach.
+    pub max_reach: f64,
+}
+
+impl Default for LagCompensationSettings {
+    fn default() -> Self {
+        Self {
+            max_rewind_ticks: 10,
+            interpolation_ticks: 1,
+            max_reach: 3.0,
+        }
+    }
+}
+
+/// The position and hitbox of an entity at the end of a tick.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PositionSnapshot {
+    /// The [tick](Server::current_tick) the snapshot was taken in.
+    pub tick: i64,
+    pub position: DVec3,
+    /// The hitbox of the entity in world coordinates.
+    pub hitbox: Aabb,
+}
+
+/// The recent positions of an entity, oldest first. Added automatically to
+/// entities with a [`HitboxShape`].
+#[derive(Component, Clone, Default, Debug)]
+pub struct PositionHistory {
+    snapshots: VecDeque<PositionSnapshot>,
+}
+
+impl PositionHistory {
+    /// Returns the latest snapshot.
+    pub fn latest(&self) -> Option<&PositionSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Returns the latest snapshot taken at or before `tick`. If the history
+    /// doesn't reach back that far, the oldest snapshot is returned.
+    pub fn at_tick(&self, tick: i64) -> Option<&PositionSnapshot> {
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.tick <= tick)
+            .or_else(|| self.snapshots.front())
+    }
+
+    /// Returns the snapshot from `ticks` ticks before the latest one.
+    pub fn rewind(&self, ticks: u32) -> Option<&PositionSnapshot> {
+        let latest = self.latest()?;
+        self.at_tick(latest.tick - ticks as i64)
+    }
+
+    /// Returns an iterator over the snapshots, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &PositionSnapshot> + '_ {
+        self.snapshots.iter()
+    }
+
+    fn push(&mut self, snapshot: PositionSnapshot, capacity: usize) {
+        while self.snapshots.len() >= capacity.max(1) {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+}
+
+/// Sent when a client attacks an entity, with the attack checked against the
+/// position the target had when the client saw it.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct CompensatedAttackEvent {
+    pub client: Entity,
+    pub target: Entity,
+    /// The number of ticks the target was rewound.
+    pub rewind_ticks: u32,
+    /// The hitbox of the target the attack was checked against.
+    pub hitbox: Aabb,
+    /// The distance from the eyes of the client to `hitbox`.
+    pub distance: f64,
+    /// Whether `distance` is within [`LagCompensationSettings::max_reach`].
+    pub in_reach: bool,
+}
+
+/// A [`SystemParam`] for checking hits against the past positions of
+/// entities.
+#[derive(SystemParam)]
+pub struct LagCompensation<'w, 's> {
+    settings: Res<'w, LagCompensationSettings>,
+    server: Res<'w, Server>,
+    pings: Query<'w, 's, &'static Ping>,
+    histories: Query<'w, 's, (Entity, &'static PositionHistory)>,
+}
+
+impl LagCompensation<'_, '_> {
+    /// Returns the number of ticks entities are rewound by for hits made by
+    /// `client`. This is half of the client's ping plus the interpolation
+    /// delay, limited to [`LagCompensationSettings::max_rewind_ticks`].
+    pub fn rewind_ticks(&self, client: Entity) -> u32 {
+        let ping = self.pings.get(client).map_or(-1, |ping| ping.0);
+        let tick_millis = 1000.0 / self.server.tick_rate().get() as f64;
+
+        let latency_ticks = if ping > 0 {
+            (ping as f64 / 2.0 / tick_millis).round() as u32
+        } else {
+            0
+        };
+
+        (latency_ticks + self.settings.interpolation_ticks).min(self.settings.max_rewind_ticks)
+    }
+
+    /// Returns the hitbox `entity` had `ticks` ticks ago.
+    pub fn hitbox_at(&self, entity: Entity, ticks: u32) -> Option<Aabb> {
+        let (_, history) = self.histories.get(entity).ok()?;
+        history.rewind(ticks).map(|snapshot| snapshot.hitbox)
+    }
+
+    /// Casts a ray from `origin` in `direction` against the hitboxes entities
+    /// had when `shooter` fired, and returns the closest entity hit and the
+    /// distance to it. The shooter itself is never hit.
+    pub fn raycast(
+        &self,
+        shooter: Entity,
+        origin: DVec3,
+        direction: DVec3,
+        max_distance: f64,
+    ) -> Option<(Entity, f64)> {
+        let ticks = self.rewind_ticks(shooter);
+        let direction = direction.normalize_or_zero();
+
+        self.histories
+            .iter()
+            .filter(|&(entity, _)| entity != shooter)
+            .filter_map(|(entity, history)| {
+                let hitbox = history.rewind(ticks)?.hitbox;
+                let [near, _] = hitbox.ray_intersection(origin, direction)?;
+                (near <= max_distance).then_some((entity, near))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+}
+
+fn init_position_history(
+    entities: Query<Entity, (With<HitboxShape>, Without<PositionHistory>)>,
+    mut commands: Commands,
+) {
+    for entity in &entities {
+        commands.entity(entity).insert(PositionHistory::default());
+    }
+}
+
+fn record_positions(
+    mut entities: Query<(&Position, &HitboxShape, &mut PositionHistory)>,
+    settings: Res<LagCompensationSettings>,
+    server: Res<Server>,
+) {
+    let capacity = settings.max_rewind_ticks as usize + 1;
+
+    for (pos, shape, mut history) in &mut entities {
+        history.push(
+            PositionSnapshot {
+                tick: server.current_tick(),
+                position: pos.0,
+                hitbox: shape.get() + pos.0,
+            },
+            capacity,
+        );
+    }
+}
+
+fn compensate_attacks(
+    mut interactions: EventReader<InteractEntityEvent>,
+    mut attacks: EventWriter<CompensatedAttackEvent>,
+    attackers: Query<(&Position, Option<&Pose>)>,
+    lag_compensation: LagCompensation,
+) {
+    for event in interactions.read() {
+        if event.interact != EntityInteraction::Attack {
+            continue;
+        }
+
+        let Ok((attacker_pos, pose)) = attackers.get(event.client) else {
+            continue;
+        };
+
+        let rewind_ticks = lag_compensation.rewind_ticks(event.client);
+
+        let Some(hitbox) = lag_compensation.hitbox_at(event.entity, rewind_ticks) else {
+            continue;
+        };
+
+        let eye_height = player_eye_height(pose.map_or(Default::default(), |pose| pose.0));
+        let eyes = attacker_pos.0 + DVec3::new(0.0, eye_height, 0.0);
+        let distance = hitbox.distance_to_point(eyes);
+
+        attacks.send(CompensatedAttackEvent {
+            client: event.client,
+            target: event.entity,
+            rewind_ticks,
+            hitbox,
+            distance,
+            in_reach: distance <= lag_compensation.settings.max_reach,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(tick: i64) -> PositionSnapshot {
+        let position = DVec3::new(tick as f64, 0.0, 0.0);
+        PositionSnapshot {
+            tick,
+            position,
+            hitbox: Aabb::new_point(position),
+        }
+    }
+
+    #[test]
+    fn position_history_rewind() {
+        let mut history = PositionHistory::default();
+
+        for tick in 0..20 {
+            history.push(snapshot(tick), 5);
+        }
+
+        assert_eq!(history.iter().count(), 5);
+        assert_eq!(history.latest().unwrap().tick, 19);
+        assert_eq!(history.rewind(0).unwrap().tick, 19);
+        assert_eq!(history.rewind(3).unwrap().tick, 16);
+
+        // Rewinding past the history clamps to the oldest snapshot.
+        assert_eq!(history.rewind(100).unwrap().tick, 15);
+        assert_eq!(history.at_tick(17).unwrap().position.x, 17.0);
+    }
+}
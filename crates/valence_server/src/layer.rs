@@ -6,6 +6,7 @@
 
 pub mod bvh;
 pub mod chunk;
+pub mod clone;
 pub mod entity;
 pub mod message;
 
@@ -16,6 +17,7 @@ pub use entity::EntityLayer;
 use valence_entity::{InitEntitiesSet, UpdateTrackedDataSet};
 use valence_protocol::encode::WritePacket;
 use valence_protocol::{BlockPos, ChunkPos, Ident};
+use valence_registry::dimension_type::DimensionTypeError;
 use valence_registry::{BiomeRegistry, DimensionTypeRegistry};
 use valence_server_common::Server;
 
@@ -44,6 +46,8 @@ impl Plugin for LayerPlugin {
             ),
         );
 
+        app.init_resource::<clone::CloneComponents>();
+
         chunk::build(app);
         entity::build(app);
     }
@@ -125,6 +129,12 @@ pub struct LayerBundle {
 
 impl LayerBundle {
     /// Returns a new layer bundle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimension type isn't registered or is invalid. See
+    /// [`ChunkLayer::new`].
+    #[track_caller]
     pub fn new(
         dimension_type_name: impl Into<Ident<String>>,
         dimensions: &DimensionTypeRegistry,
@@ -136,4 +146,18 @@ impl LayerBundle {
             entity: EntityLayer::new(server),
         }
     }
+
+    /// Returns a new layer bundle, or an error if the dimension type isn't
+    /// registered or is invalid. See [`ChunkLayer::try_new`].
+    pub fn try_new(
+        dimension_type_name: impl Into<Ident<String>>,
+        dimensions: &DimensionTypeRegistry,
+        biomes: &BiomeRegistry,
+        server: &Server,
+    ) -> Result<Self, DimensionTypeError> {
+        Ok(Self {
+            chunk: ChunkLayer::try_new(dimension_type_name, dimensions, biomes, server)?,
+            entity: EntityLayer::new(server),
+        })
+    }
 }
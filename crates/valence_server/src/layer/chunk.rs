@@ -1,18 +1,33 @@
+mod anti_xray;
 #[allow(clippy::module_inception)]
 mod chunk;
+mod collision;
 pub mod loaded;
 mod paletted_container;
+mod raycast;
+pub mod region;
+mod search;
+pub mod shape;
+pub mod storage;
+pub mod template;
 pub mod unloaded;
+mod vertical_streaming;
 
 use std::borrow::Cow;
 use std::collections::hash_map::{Entry, OccupiedEntry, VacantEntry};
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+pub use anti_xray::{AntiXray, AntiXrayMode};
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 pub use chunk::{MAX_HEIGHT, *};
+pub use collision::SolidMask;
 pub use loaded::LoadedChunk;
+pub use raycast::BlockRaycastHit;
 use rustc_hash::FxHashMap;
+pub use search::{BlockKindSet, BlockPredicate};
+pub use template::ChunkTemplate;
 pub use unloaded::UnloadedChunk;
 use valence_math::{DVec3, Vec3};
 use valence_nbt::Compound;
@@ -22,8 +37,13 @@ use valence_protocol::packets::play::{ParticleS2c, PlaySoundS2c};
 use valence_protocol::sound::{Sound, SoundCategory, SoundId};
 use valence_protocol::{BiomePos, BlockPos, ChunkPos, CompressionThreshold, Encode, Ident, Packet};
 use valence_registry::biome::{BiomeId, BiomeRegistry};
+#[cfg(doc)]
+use valence_registry::dimension_type::DimensionType;
+use valence_registry::dimension_type::DimensionTypeError;
 use valence_registry::DimensionTypeRegistry;
 use valence_server_common::Server;
+pub(crate) use vertical_streaming::upgrade_partial_chunks;
+pub use vertical_streaming::{FarSections, PartialChunks, VerticalStreaming};
 
 use super::bvh::GetChunkPos;
 use super::message::Messages;
@@ -40,12 +60,16 @@ pub struct ChunkLayer {
 }
 
 /// Chunk layer information.
+#[derive(Clone)]
 pub(crate) struct ChunkLayerInfo {
     dimension_type_name: Ident<String>,
     height: u32,
     min_y: i32,
     biome_registry_len: usize,
     threshold: CompressionThreshold,
+    anti_xray: Option<AntiXray>,
+    vertical_streaming: Option<VerticalStreaming>,
+    init_packet_cache: InitPacketCacheCounters,
 }
 
 impl fmt::Debug for ChunkLayerInfo {
@@ -56,11 +80,61 @@ impl fmt::Debug for ChunkLayerInfo {
             .field("min_y", &self.min_y)
             .field("biome_registry_len", &self.biome_registry_len)
             .field("threshold", &self.threshold)
+            .field("anti_xray", &self.anti_xray)
+            .field("vertical_streaming", &self.vertical_streaming)
+            .field("init_packet_cache", &self.init_packet_cache)
             // Ignore sky light mask and array.
             .finish()
     }
 }
 
+/// How often the packets initializing chunks were taken from the cache of
+/// the chunks instead of being encoded. Obtained with
+/// [`ChunkLayer::init_packet_cache_stats`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct InitPacketCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl InitPacketCacheStats {
+    /// Returns the fraction of chunk initializations served from the cache,
+    /// or `None` if no chunks were initialized yet.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+/// The counters behind [`InitPacketCacheStats`]. Chunks are initialized from
+/// several threads, so the counters are atomic.
+#[derive(Default, Debug)]
+pub(crate) struct InitPacketCacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl InitPacketCacheCounters {
+    pub(crate) fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> InitPacketCacheStats {
+        InitPacketCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Copies of a layer count their own cache hits, so cloning starts from zero.
+impl Clone for InitPacketCacheCounters {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
 type ChunkLayerMessages = Messages<GlobalMsg, LocalMsg>;
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -125,6 +199,11 @@ impl ChunkLayer {
     pub(crate) const OVERWRITE: u8 = 2;
 
     /// Creates a new chunk layer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimension type isn't registered or is invalid. See
+    /// [`ChunkLayer::try_new`].
     #[track_caller]
     pub fn new(
         dimension_type_name: impl Into<Ident<String>>,
@@ -132,17 +211,27 @@ impl ChunkLayer {
         biomes: &BiomeRegistry,
         server: &Server,
     ) -> Self {
+        match Self::try_new(dimension_type_name, dimensions, biomes, server) {
+            Ok(layer) => layer,
+            Err(e) => panic!("failed to create chunk layer: {e}"),
+        }
+    }
+
+    /// Creates a new chunk layer, or returns an error if the dimension type
+    /// isn't registered or is [invalid](DimensionType::validate).
+    pub fn try_new(
+        dimension_type_name: impl Into<Ident<String>>,
+        dimensions: &DimensionTypeRegistry,
+        biomes: &BiomeRegistry,
+        server: &Server,
+    ) -> Result<Self, DimensionTypeError> {
         let dimension_type_name = dimension_type_name.into();
 
-        let dim = &dimensions[dimension_type_name.as_str_ident()];
+        let dim = dimensions.validated(dimension_type_name.as_str_ident())?;
 
-        assert!(
-            (0..MAX_HEIGHT as i32).contains(&dim.height),
-            "invalid dimension height of {}",
-            dim.height
-        );
+        debug_assert!(dim.height as u32 <= MAX_HEIGHT);
 
-        Self {
+        Ok(Self {
             messages: Messages::new(),
             chunks: Default::default(),
             info: ChunkLayerInfo {
@@ -151,8 +240,29 @@ impl ChunkLayer {
                 min_y: dim.min_y,
                 biome_registry_len: biomes.iter().len(),
                 threshold: server.compression_threshold(),
+                anti_xray: None,
+                vertical_streaming: None,
+                init_packet_cache: InitPacketCacheCounters::default(),
             },
+        })
+    }
+
+    /// Creates a new chunk layer with the same dimension as this one and a
+    /// copy of every chunk in it. The copies share their block and biome data
+    /// with this layer until either of them modifies it, so duplicating a
+    /// layer is cheap.
+    pub fn duplicate(&self) -> Self {
+        let mut layer = Self {
+            messages: Messages::new(),
+            chunks: Default::default(),
+            info: self.info.clone(),
+        };
+
+        for (pos, chunk) in self.chunks() {
+            layer.insert_chunk(pos, chunk.to_unloaded());
         }
+
+        layer
     }
 
     /// The name of the dimension this chunk layer is using.
@@ -170,6 +280,35 @@ impl ChunkLayer {
         self.info.min_y
     }
 
+    /// The anti-xray settings of this layer, if enabled.
+    pub fn anti_xray(&self) -> Option<&AntiXray> {
+        self.info.anti_xray.as_ref()
+    }
+
+    /// Enables or disables anti-xray for this layer. The setting applies to
+    /// all clients viewing the layer; there is no per-client override. Chunks
+    /// which were already sent to clients are not sent again, so this should
+    /// be set before clients join the layer.
+    pub fn set_anti_xray(&mut self, anti_xray: Option<AntiXray>) {
+        self.info.anti_xray = anti_xray;
+
+        for chunk in self.chunks.values_mut() {
+            chunk.clear_init_packets_cache();
+        }
+    }
+
+    /// The vertical streaming settings of this layer, if enabled.
+    pub fn vertical_streaming(&self) -> Option<&VerticalStreaming> {
+        self.info.vertical_streaming.as_ref()
+    }
+
+    /// Enables or disables vertical streaming for this layer. Chunks which
+    /// were sent to clients in part are sent again in full when it is
+    /// disabled.
+    pub fn set_vertical_streaming(&mut self, vertical_streaming: Option<VerticalStreaming>) {
+        self.info.vertical_streaming = vertical_streaming;
+    }
+
     /// Get a reference to the chunk at the given position, if it is loaded.
     pub fn chunk(&self, pos: impl Into<ChunkPos>) -> Option<&LoadedChunk> {
         self.chunks.get(&pos.into())
@@ -197,6 +336,28 @@ impl ChunkLayer {
         }
     }
 
+    /// Insert an [empty placeholder](LoadedChunk::is_empty_placeholder) chunk
+    /// at the given position, which is all air and takes almost no memory
+    /// until it is modified. The previous chunk data is returned.
+    pub fn insert_empty_chunk(&mut self, pos: impl Into<ChunkPos>) -> Option<UnloadedChunk> {
+        match self.chunk_entry(pos) {
+            ChunkEntry::Occupied(mut oe) => Some(oe.insert_empty()),
+            ChunkEntry::Vacant(ve) => {
+                ve.insert_empty();
+                None
+            }
+        }
+    }
+
+    /// Inserts a copy of every chunk in `template` into this layer, replacing
+    /// the chunks at the same positions. The copies share their block and
+    /// biome data with the template until they are modified.
+    pub fn insert_template(&mut self, template: &ChunkTemplate) {
+        for (pos, chunk) in template.chunks() {
+            self.insert_chunk(pos, chunk.clone());
+        }
+    }
+
     /// Unload the chunk at the given position, if it is loaded. Returns the
     /// chunk if it was loaded.
     pub fn remove_chunk(&mut self, pos: impl Into<ChunkPos>) -> Option<UnloadedChunk> {
@@ -287,6 +448,36 @@ impl ChunkLayer {
         Some(chunk.block(x, y, z))
     }
 
+    /// Returns whether the block at `pos` is solid, i.e. whether it has any
+    /// collision shapes. Unloaded and out of bounds blocks are not solid.
+    ///
+    /// This uses the [`SolidMask`] cached for each chunk section and is much
+    /// faster than looking up the collision shapes of the block.
+    pub fn is_solid(&self, pos: impl Into<BlockPos>) -> bool {
+        let pos = pos.into();
+
+        let Some(y) = pos
+            .y
+            .checked_sub(self.info.min_y)
+            .and_then(|y| u32::try_from(y).ok())
+        else {
+            return false;
+        };
+
+        if y >= self.info.height {
+            return false;
+        }
+
+        let Some(chunk) = self.chunk(pos) else {
+            return false;
+        };
+
+        let x = pos.x.rem_euclid(16) as u32;
+        let z = pos.z.rem_euclid(16) as u32;
+
+        chunk.is_solid(x, y, z)
+    }
+
     pub fn set_block(&mut self, pos: impl Into<BlockPos>, block: impl IntoBlock) -> Option<Block> {
         let pos = pos.into();
 
@@ -307,6 +498,23 @@ impl ChunkLayer {
         Some(chunk.set_block(x, y, z, block))
     }
 
+    /// Sets every position in `positions` to `block`. Positions outside of
+    /// loaded chunks are skipped. Returns the number of blocks set.
+    ///
+    /// See [`shape`] for functions returning the positions of common shapes.
+    pub fn set_blocks(
+        &mut self,
+        positions: impl IntoIterator<Item = BlockPos>,
+        block: impl IntoBlock,
+    ) -> usize {
+        let block = block.into_block();
+
+        positions
+            .into_iter()
+            .filter(|&pos| self.set_block(pos, block.clone()).is_some())
+            .count()
+    }
+
     pub fn block_entity_mut(&mut self, pos: impl Into<BlockPos>) -> Option<&mut Compound> {
         let pos = pos.into();
 
@@ -367,6 +575,12 @@ impl ChunkLayer {
         Some(chunk.set_biome(x, y, z, biome))
     }
 
+    /// Returns how often the packets initializing chunks for clients were
+    /// taken from the cache of the chunks since the layer was created.
+    pub fn init_packet_cache_stats(&self) -> InitPacketCacheStats {
+        self.info.init_packet_cache.stats()
+    }
+
     pub(crate) fn info(&self) -> &ChunkLayerInfo {
         &self.info
     }
@@ -699,6 +913,20 @@ impl<'a> OccupiedChunkEntry<'a> {
         self.entry.get_mut().insert(chunk)
     }
 
+    /// Replaces the chunk with an [empty
+    /// placeholder](LoadedChunk::is_empty_placeholder). The previous chunk
+    /// data is returned.
+    pub fn insert_empty(&mut self) -> UnloadedChunk {
+        self.messages.send_local_infallible(
+            LocalMsg::ChangeChunkState {
+                pos: *self.entry.key(),
+            },
+            |b| b.push(ChunkLayer::OVERWRITE),
+        );
+
+        self.entry.get_mut().insert_empty()
+    }
+
     pub fn into_mut(self) -> &'a mut LoadedChunk {
         self.entry.into_mut()
     }
@@ -755,6 +983,19 @@ impl<'a> VacantChunkEntry<'a> {
         self.entry.insert(loaded)
     }
 
+    /// Inserts an [empty placeholder](LoadedChunk::is_empty_placeholder)
+    /// chunk.
+    pub fn insert_empty(self) -> &'a mut LoadedChunk {
+        self.messages.send_local_infallible(
+            LocalMsg::ChangeChunkState {
+                pos: *self.entry.key(),
+            },
+            |b| b.push(ChunkLayer::LOAD),
+        );
+
+        self.entry.insert(LoadedChunk::new_empty(self.height))
+    }
+
     pub fn into_key(self) -> ChunkPos {
         *self.entry.key()
     }
@@ -0,0 +1,219 @@
+//! Ore obfuscation to make X-ray clients and texture packs useless.
+
+use rustc_hash::FxHashSet;
+use valence_protocol::{BlockKind, BlockPos, BlockState};
+
+use super::chunk::{BlockStateContainer, Chunk, SECTION_BLOCK_COUNT};
+use super::loaded::LoadedChunk;
+use super::paletted_container::PalettedContainer;
+
+/// Settings for hiding blocks that aren't exposed to air or other
+/// non-opaque blocks from clients.
+///
+/// Hidden blocks with only opaque neighbors are sent to clients as a
+/// replacement block. When a block next to a hidden block becomes non-opaque,
+/// for instance because it was mined, the true block is sent to the clients
+/// viewing it.
+///
+/// Blocks on the edges of a chunk are always considered exposed,
+/// so chunks can be obfuscated without looking at their neighbors.
+///
+/// Anti-xray is a per-layer setting, enabled with
+/// [`ChunkLayer::set_anti_xray`](super::ChunkLayer::set_anti_xray). Every
+/// client viewing the layer receives the same obfuscated chunk data, which is
+/// encoded once per chunk and cached. Clients which should see the true
+/// blocks, like spectators or moderators, need to view a layer without
+/// anti-xray.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AntiXray {
+    hidden: FxHashSet<BlockKind>,
+    mode: AntiXrayMode,
+}
+
+/// What hidden blocks are replaced with.
+#[derive(Clone, PartialEq, Debug)]
+pub enum AntiXrayMode {
+    /// Replace every hidden block with the same block.
+    Replace(BlockState),
+    /// Replace every hidden block with one of the given blocks chosen
+    /// randomly by position. Including the hidden blocks themselves makes
+    /// fake ores appear, so it's harder to tell where the real ores are.
+    Noise(Vec<BlockState>),
+}
+
+/// The ores hidden by default.
+const DEFAULT_HIDDEN: [BlockKind; 19] = [
+    BlockKind::CoalOre,
+    BlockKind::DeepslateCoalOre,
+    BlockKind::IronOre,
+    BlockKind::DeepslateIronOre,
+    BlockKind::CopperOre,
+    BlockKind::DeepslateCopperOre,
+    BlockKind::GoldOre,
+    BlockKind::DeepslateGoldOre,
+    BlockKind::RedstoneOre,
+    BlockKind::DeepslateRedstoneOre,
+    BlockKind::EmeraldOre,
+    BlockKind::DeepslateEmeraldOre,
+    BlockKind::LapisOre,
+    BlockKind::DeepslateLapisOre,
+    BlockKind::DiamondOre,
+    BlockKind::DeepslateDiamondOre,
+    BlockKind::NetherGoldOre,
+    BlockKind::NetherQuartzOre,
+    BlockKind::AncientDebris,
+];
+
+impl Default for AntiXray {
+    fn default() -> Self {
+        Self {
+            hidden: DEFAULT_HIDDEN.into_iter().collect(),
+            mode: AntiXrayMode::Replace(BlockState::STONE),
+        }
+    }
+}
+
+impl AntiXray {
+    /// Creates settings which replace the vanilla ores with stone.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_mode(mut self, mode: AntiXrayMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Replaces the set of hidden blocks.
+    pub fn with_hidden(mut self, kinds: impl IntoIterator<Item = BlockKind>) -> Self {
+        self.hidden = kinds.into_iter().collect();
+        self
+    }
+
+    /// Adds `kind` to the set of hidden blocks.
+    pub fn hide(mut self, kind: BlockKind) -> Self {
+        self.hidden.insert(kind);
+        self
+    }
+
+    pub fn mode(&self) -> &AntiXrayMode {
+        &self.mode
+    }
+
+    pub fn is_hidden(&self, state: BlockState) -> bool {
+        self.hidden.contains(&state.to_kind())
+    }
+
+    /// Returns the block sent to clients in place of a hidden block at `pos`.
+    pub fn replacement(&self, pos: BlockPos) -> BlockState {
+        match &self.mode {
+            AntiXrayMode::Replace(state) => *state,
+            AntiXrayMode::Noise(states) if states.is_empty() => BlockState::STONE,
+            AntiXrayMode::Noise(states) => {
+                // The choice must be stable so the cached chunk packets and block
+                // updates agree.
+                let hash = (pos.x as u32).wrapping_mul(73_856_093)
+                    ^ (pos.y as u32).wrapping_mul(19_349_663)
+                    ^ (pos.z as u32).wrapping_mul(83_492_791);
+
+                states[hash as usize % states.len()]
+            }
+        }
+    }
+
+    /// Returns whether the block at the chunk coordinates `x`, `y`, `z` has a
+    /// non-opaque neighbor or is on the edge of the chunk.
+    pub(super) fn is_exposed(&self, chunk: &LoadedChunk, x: u32, y: u32, z: u32) -> bool {
+        if x == 0 || x == 15 || z == 0 || z == 15 || y == 0 || y + 1 >= chunk.height() {
+            return true;
+        }
+
+        [
+            [x - 1, y, z],
+            [x + 1, y, z],
+            [x, y - 1, z],
+            [x, y + 1, z],
+            [x, y, z - 1],
+            [x, y, z + 1],
+        ]
+        .into_iter()
+        .any(|[x, y, z]| !chunk.block_state(x, y, z).is_opaque())
+    }
+
+    /// Returns a copy of the block states of section `sect_y` of `chunk` with
+    /// the unexposed hidden blocks replaced. `origin` is the position of the
+    /// lowest corner of the section.
+    pub(super) fn obfuscate_section(
+        &self,
+        chunk: &LoadedChunk,
+        sect_y: u32,
+        origin: BlockPos,
+    ) -> BlockStateContainer {
+        let blocks = chunk.section_block_states(sect_y);
+        // Cloning is cheap because the data is shared until it's modified.
+        let mut obfuscated = blocks.clone();
+
+        if let PalettedContainer::Single(state) = blocks {
+            if !self.is_hidden(*state) {
+                return obfuscated;
+            }
+        }
+
+        for idx in 0..SECTION_BLOCK_COUNT {
+            let state = blocks.get(idx);
+
+            if !self.is_hidden(state) {
+                continue;
+            }
+
+            let x = idx as u32 % 16;
+            let z = idx as u32 / 16 % 16;
+            let y = idx as u32 / 256;
+
+            if !self.is_exposed(chunk, x, sect_y * 16 + y, z) {
+                let pos = BlockPos::new(
+                    origin.x + x as i32,
+                    origin.y + y as i32,
+                    origin.z + z as i32,
+                );
+
+                obfuscated.set(idx, self.replacement(pos));
+            }
+        }
+
+        obfuscated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn obfuscate_unexposed_ores() {
+        let anti_xray = AntiXray::new();
+        let mut chunk = LoadedChunk::new(32);
+
+        chunk.fill_block_states(BlockState::STONE);
+        // Surrounded by stone.
+        chunk.set_block_state(5, 5, 5, BlockState::DIAMOND_ORE);
+        // Next to air.
+        chunk.set_block_state(8, 5, 8, BlockState::GOLD_ORE);
+        chunk.set_block_state(8, 6, 8, BlockState::AIR);
+        // On the edge of the chunk.
+        chunk.set_block_state(0, 5, 5, BlockState::IRON_ORE);
+
+        assert!(!anti_xray.is_exposed(&chunk, 5, 5, 5));
+        assert!(anti_xray.is_exposed(&chunk, 8, 5, 8));
+        assert!(anti_xray.is_exposed(&chunk, 0, 5, 5));
+
+        let obfuscated = anti_xray.obfuscate_section(&chunk, 0, BlockPos::new(0, 0, 0));
+
+        assert_eq!(obfuscated.get(5 + 5 * 16 + 5 * 256), BlockState::STONE);
+        assert_eq!(obfuscated.get(8 + 8 * 16 + 5 * 256), BlockState::GOLD_ORE);
+        assert_eq!(obfuscated.get(5 * 16 + 5 * 256), BlockState::IRON_ORE);
+
+        // The real blocks are untouched.
+        assert_eq!(chunk.block_state(5, 5, 5), BlockState::DIAMOND_ORE);
+    }
+}
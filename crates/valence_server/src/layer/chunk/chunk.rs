@@ -3,6 +3,7 @@ use valence_protocol::BlockState;
 use valence_registry::biome::BiomeId;
 
 use super::paletted_container::PalettedContainer;
+use super::search::BlockPredicate;
 
 /// Common operations on chunks. Notable implementors are
 /// [`LoadedChunk`](super::loaded::LoadedChunk) and
@@ -203,6 +204,69 @@ pub trait Chunk {
         self.clear_block_entities();
     }
 
+    /// Returns the number of blocks in this chunk matching `pred`.
+    ///
+    /// The default implementation tests every block with
+    /// [`Self::block_state`].
+    fn count_blocks(&self, pred: impl BlockPredicate) -> u32 {
+        let mut count = 0;
+
+        for y in 0..self.height() {
+            for z in 0..16 {
+                for x in 0..16 {
+                    if pred.matches(self.block_state(x, y, z)) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Returns whether any block in this chunk matches `pred`, such as a
+    /// [`BlockKindSet`](super::BlockKindSet) made from a block tag.
+    ///
+    /// The default implementation tests every block with
+    /// [`Self::block_state`] until one matches.
+    fn contains_any(&self, pred: impl BlockPredicate) -> bool {
+        (0..self.height())
+            .any(|y| (0..16).any(|z| (0..16).any(|x| pred.matches(self.block_state(x, y, z)))))
+    }
+
+    /// Returns an iterator over the offsets `[x, y, z]` of the blocks in this
+    /// chunk which aren't air, ordered by Y, Z, and X.
+    ///
+    /// The default implementation tests every block with
+    /// [`Self::block_state`].
+    fn iter_non_air(&self) -> Box<dyn Iterator<Item = [u32; 3]> + '_> {
+        Box::new(
+            (0..self.height())
+                .flat_map(|y| (0..16).flat_map(move |z| (0..16).map(move |x| [x, y, z])))
+                .filter(|&[x, y, z]| !self.block_state(x, y, z).is_air()),
+        )
+    }
+
+    /// Replaces every block state in this chunk with the result of `f`.
+    /// Implementations may call `f` only once per distinct state, so `f`
+    /// should be a pure function of the state.
+    ///
+    /// The default implementation calls `f` once per block and sets the
+    /// result with [`Self::set_block_state`].
+    ///
+    /// **NOTE:** This is a low-level function which may break expected
+    /// invariants for block entities, like [`Self::set_block_state`].
+    fn map_states(&mut self, mut f: impl FnMut(BlockState) -> BlockState) {
+        for y in 0..self.height() {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let state = self.block_state(x, y, z);
+                    self.set_block_state(x, y, z, f(state));
+                }
+            }
+        }
+    }
+
     /// Attempts to optimize this chunk by reducing its memory usage or other
     /// characteristics. This may be a relatively expensive operation.
     ///
@@ -308,6 +372,17 @@ pub(super) fn check_section_oob(chunk: &impl Chunk, sect_y: u32) {
     );
 }
 
+/// Returns the offsets `[x, y, z]` in a chunk of the block at index `idx` of
+/// the section `sect_y`.
+#[inline]
+pub(super) fn section_block_offsets(sect_y: usize, idx: usize) -> [u32; 3] {
+    [
+        (idx % 16) as u32,
+        (sect_y * 16 + idx / 256) as u32,
+        (idx / 16 % 16) as u32,
+    ]
+}
+
 /// Returns the minimum number of bits needed to represent the integer `n`.
 pub(super) const fn bit_width(n: usize) -> usize {
     (usize::BITS - n.leading_zeros()) as _
@@ -341,6 +416,50 @@ mod tests {
         check(loaded);
     }
 
+    #[test]
+    fn chunk_bulk_queries() {
+        fn check(mut chunk: impl Chunk) {
+            let ores =
+                |state: BlockState| matches!(state, BlockState::COAL_ORE | BlockState::DIAMOND_ORE);
+
+            assert!(!chunk.contains_any(BlockState::STONE));
+            assert_eq!(chunk.iter_non_air().count(), 0);
+
+            chunk.fill_block_state_section(1, BlockState::STONE);
+            chunk.set_block_state(1, 2, 3, BlockState::DIAMOND_ORE);
+            chunk.set_block_state(4, 20, 5, BlockState::COAL_ORE);
+            chunk.set_block_state(4, 20, 5, BlockState::STONE);
+
+            assert_eq!(chunk.count_blocks(BlockState::STONE), 16 * 16 * 16);
+            assert_eq!(chunk.count_blocks(ores), 1);
+            // The palette of section 1 still contains coal ore.
+            assert!(!chunk.contains_any(BlockState::COAL_ORE));
+            assert!(chunk.contains_any(ores));
+
+            let non_air: Vec<_> = chunk.iter_non_air().collect();
+            assert_eq!(non_air.len(), 16 * 16 * 16 + 1);
+            assert_eq!(non_air[0], [1, 2, 3]);
+            assert_eq!(non_air[1], [0, 16, 0]);
+            assert_eq!(non_air[2], [1, 16, 0]);
+            assert_eq!(non_air[17], [0, 16, 1]);
+            assert_eq!(non_air.last(), Some(&[15, 31, 15]));
+
+            chunk.map_states(|state| match state {
+                BlockState::STONE => BlockState::DEEPSLATE,
+                BlockState::DIAMOND_ORE => BlockState::AIR,
+                state => state,
+            });
+
+            assert_eq!(chunk.block_state(1, 2, 3), BlockState::AIR);
+            assert_eq!(chunk.block_state(4, 20, 5), BlockState::DEEPSLATE);
+            assert_eq!(chunk.count_blocks(BlockState::DEEPSLATE), 16 * 16 * 16);
+            assert!(!chunk.contains_any(ores));
+        }
+
+        check(UnloadedChunk::with_height(64));
+        check(LoadedChunk::new(64));
+    }
+
     #[cfg(debug_assertions)]
     #[test]
     #[should_panic]
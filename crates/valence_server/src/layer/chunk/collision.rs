@@ -0,0 +1,112 @@
+use valence_protocol::BlockState;
+
+use super::chunk::{BlockStateContainer, SECTION_BLOCK_COUNT};
+use super::paletted_container::PalettedContainer;
+
+/// Returns whether entities collide with `state`, which is the case if it has
+/// any collision shapes.
+pub(super) fn is_solid(state: BlockState) -> bool {
+    state.collision_shapes().len() > 0
+}
+
+/// The solid blocks of a chunk section, with one bit per block. Blocks are
+/// solid if they have any collision shapes.
+///
+/// Masks are cached by [`LoadedChunk`](super::LoadedChunk), so looking up
+/// whether a block is solid doesn't need to go through the palette of the
+/// section and the collision shapes of the block.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SolidMask([u64; SECTION_BLOCK_COUNT / 64]);
+
+impl SolidMask {
+    pub(super) fn new(states: &BlockStateContainer) -> Self {
+        match states {
+            PalettedContainer::Single(state) => {
+                if is_solid(*state) {
+                    Self([u64::MAX; SECTION_BLOCK_COUNT / 64])
+                } else {
+                    Self([0; SECTION_BLOCK_COUNT / 64])
+                }
+            }
+            _ => {
+                let mut mask = Self([0; SECTION_BLOCK_COUNT / 64]);
+
+                // Neighboring blocks are often the same, so remember the last
+                // block to avoid looking up its collision shapes again.
+                let mut last = None;
+
+                for idx in 0..SECTION_BLOCK_COUNT {
+                    let state = states.get(idx);
+
+                    let solid = match last {
+                        Some((last_state, solid)) if last_state == state => solid,
+                        _ => {
+                            let solid = is_solid(state);
+                            last = Some((state, solid));
+                            solid
+                        }
+                    };
+
+                    mask.set_idx(idx, solid);
+                }
+
+                mask
+            }
+        }
+    }
+
+    /// Returns whether the block at the given offset in the section is solid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x`, `y` or `z` is not less than 16.
+    #[inline]
+    pub fn get(&self, x: u32, y: u32, z: u32) -> bool {
+        assert!(x < 16 && y < 16 && z < 16, "offset out of bounds");
+
+        self.get_idx((x + z * 16 + y * 16 * 16) as usize)
+    }
+
+    /// The number of solid blocks in the section.
+    pub fn count(&self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    #[inline]
+    pub(super) fn get_idx(&self, idx: usize) -> bool {
+        self.0[idx / 64] >> (idx % 64) & 1 == 1
+    }
+
+    #[inline]
+    pub(super) fn set_idx(&mut self, idx: usize, solid: bool) {
+        if solid {
+            self.0[idx / 64] |= 1 << (idx % 64);
+        } else {
+            self.0[idx / 64] &= !(1 << (idx % 64));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_matches_blocks() {
+        let mut states = BlockStateContainer::Single(BlockState::AIR);
+        states.set(0, BlockState::STONE);
+        states.set(17, BlockState::OAK_SLAB);
+        states.set(300, BlockState::TORCH);
+
+        let mask = SolidMask::new(&states);
+
+        assert!(mask.get(0, 0, 0));
+        assert!(mask.get(1, 0, 1));
+        assert!(!mask.get(12, 1, 2));
+        assert!(!mask.get(5, 5, 5));
+        assert_eq!(mask.count(), 2);
+
+        let full = SolidMask::new(&BlockStateContainer::Single(BlockState::STONE));
+        assert_eq!(full.count(), SECTION_BLOCK_COUNT as u32);
+    }
+}
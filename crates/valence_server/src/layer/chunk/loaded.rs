@@ -1,7 +1,9 @@
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet};
 use std::mem;
+use std::ops::Range;
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
 
 use parking_lot::Mutex; // Using nonstandard mutex to avoid poisoning API.
 use valence_generated::block::{PropName, PropValue};
@@ -16,12 +18,16 @@ use valence_protocol::{BlockPos, BlockState, ChunkPos, ChunkSectionPos, Encode};
 use valence_registry::biome::BiomeId;
 use valence_registry::RegistryIdx;
 
+use super::anti_xray::AntiXray;
 use super::chunk::{
-    bit_width, check_biome_oob, check_block_oob, check_section_oob, BiomeContainer,
-    BlockStateContainer, Chunk, SECTION_BLOCK_COUNT,
+    bit_width, check_biome_oob, check_block_oob, check_section_oob, section_block_offsets,
+    BiomeContainer, BlockStateContainer, Chunk, SECTION_BLOCK_COUNT,
 };
+use super::collision::{is_solid, SolidMask};
 use super::paletted_container::PalettedContainer;
+use super::search::BlockPredicate;
 use super::unloaded::{self, UnloadedChunk};
+use super::vertical_streaming::{far_section_block, FarSections};
 use super::{ChunkLayerInfo, ChunkLayerMessages, LocalMsg};
 
 #[derive(Debug)]
@@ -30,7 +36,10 @@ pub struct LoadedChunk {
     /// necessary to record changes, since no client would be in view to receive
     /// the changes if this were zero.
     viewer_count: AtomicU32,
-    /// Block and biome data for the chunk.
+    /// The height of this chunk in blocks.
+    height: u32,
+    /// Block and biome data for the chunk. Empty if the chunk is an
+    /// [empty placeholder](Self::is_empty_placeholder).
     sections: Box<[Section]>,
     /// The block entities in this chunk.
     block_entities: BTreeMap<u32, Compound>,
@@ -51,8 +60,20 @@ struct Section {
     /// Contains modifications for the update section packet. (Or the regular
     /// block update packet if len == 1).
     section_updates: Vec<ChunkDeltaUpdateEntry>,
+    /// Cached solid blocks of the section. Built on first use and updated in
+    /// place when single blocks change.
+    solid: OnceLock<Box<SolidMask>>,
 }
 
+/// The section of every [empty placeholder](LoadedChunk::is_empty_placeholder)
+/// chunk.
+static EMPTY_SECTION: Section = Section {
+    block_states: PalettedContainer::Single(BlockState::AIR),
+    biomes: PalettedContainer::Single(BiomeId::DEFAULT),
+    section_updates: vec![],
+    solid: OnceLock::new(),
+};
+
 impl Section {
     fn count_non_air_blocks(&self) -> u16 {
         let mut count = 0;
@@ -86,6 +107,7 @@ impl LoadedChunk {
     pub(crate) fn new(height: u32) -> Self {
         Self {
             viewer_count: AtomicU32::new(0),
+            height,
             sections: vec![Section::default(); height as usize / 16].into(),
             block_entities: BTreeMap::new(),
             changed_block_entities: BTreeSet::new(),
@@ -94,6 +116,47 @@ impl LoadedChunk {
         }
     }
 
+    /// Creates an [empty placeholder](Self::is_empty_placeholder) chunk.
+    pub(crate) fn new_empty(height: u32) -> Self {
+        Self {
+            viewer_count: AtomicU32::new(0),
+            height,
+            sections: Box::new([]),
+            block_entities: BTreeMap::new(),
+            changed_block_entities: BTreeSet::new(),
+            changed_biomes: false,
+            cached_init_packets: Mutex::new(vec![]),
+        }
+    }
+
+    /// Returns whether this chunk is an empty placeholder, which is all air
+    /// with the default biome and doesn't allocate any sections. Placeholders
+    /// are useful to pad the view distance around small builds.
+    ///
+    /// The chunk stops being a placeholder when it is first modified.
+    pub fn is_empty_placeholder(&self) -> bool {
+        self.sections.is_empty() && self.height > 0
+    }
+
+    /// Returns the section at index `sect_y`, which is the shared empty
+    /// section if this chunk is a placeholder.
+    fn section(&self, sect_y: usize) -> &Section {
+        self.sections.get(sect_y).unwrap_or(&EMPTY_SECTION)
+    }
+
+    /// Returns an iterator over the sections of this chunk from the bottom up.
+    fn sections(&self) -> impl Iterator<Item = &Section> + '_ {
+        (0..self.height as usize / 16).map(|sect_y| self.section(sect_y))
+    }
+
+    /// Allocates the sections of this chunk if it is a placeholder, so they
+    /// can be modified.
+    fn materialize(&mut self) {
+        if self.is_empty_placeholder() {
+            self.sections = vec![Section::default(); self.height as usize / 16].into();
+        }
+    }
+
     /// Sets the content of this chunk to the supplied [`UnloadedChunk`]. The
     /// given unloaded chunk is [resized] to match the height of this loaded
     /// chunk prior to insertion.
@@ -103,6 +166,7 @@ impl LoadedChunk {
     /// [resized]: UnloadedChunk::set_height
     pub(crate) fn insert(&mut self, mut chunk: UnloadedChunk) -> UnloadedChunk {
         chunk.set_height(self.height());
+        self.materialize();
 
         let old_sections = self
             .sections
@@ -110,6 +174,7 @@ impl LoadedChunk {
             .zip(chunk.sections)
             .map(|(sect, other_sect)| {
                 sect.section_updates.clear();
+                sect.solid.take();
 
                 unloaded::Section {
                     block_states: mem::replace(&mut sect.block_states, other_sect.block_states),
@@ -130,11 +195,14 @@ impl LoadedChunk {
     }
 
     pub(crate) fn remove(&mut self) -> UnloadedChunk {
+        self.materialize();
+
         let old_sections = self
             .sections
             .iter_mut()
             .map(|sect| {
                 sect.section_updates.clear();
+                sect.solid.take();
 
                 unloaded::Section {
                     block_states: mem::take(&mut sect.block_states),
@@ -155,6 +223,31 @@ impl LoadedChunk {
         }
     }
 
+    /// Turns this chunk into an empty placeholder. The previous chunk data is
+    /// returned.
+    pub(crate) fn insert_empty(&mut self) -> UnloadedChunk {
+        let old = self.remove();
+        self.sections = Box::new([]);
+        old
+    }
+
+    /// Returns a copy of the blocks, biomes and block entities in this chunk.
+    ///
+    /// Block and biome data is shared with this chunk until either copy
+    /// modifies it, so this is cheap.
+    pub fn to_unloaded(&self) -> UnloadedChunk {
+        UnloadedChunk {
+            sections: self
+                .sections()
+                .map(|sect| unloaded::Section {
+                    block_states: sect.block_states.clone(),
+                    biomes: sect.biomes.clone(),
+                })
+                .collect(),
+            block_entities: self.block_entities.clone(),
+        }
+    }
+
     /// Returns the number of clients in view of this chunk.
     pub fn viewer_count(&self) -> u32 {
         self.viewer_count.load(Ordering::Relaxed)
@@ -194,6 +287,10 @@ impl LoadedChunk {
             return;
         }
 
+        if let Some(anti_xray) = &info.anti_xray {
+            self.apply_anti_xray(anti_xray, pos, info, messages);
+        }
+
         // Block states
         for (sect_y, sect) in self.sections.iter_mut().enumerate() {
             match sect.section_updates.as_slice() {
@@ -243,7 +340,8 @@ impl LoadedChunk {
             let z = (idx / 16) % 16;
             let y = idx / 16 / 16;
 
-            let state = self.sections[y as usize / 16]
+            let state = self
+                .section(y as usize / 16)
                 .block_states
                 .get(idx as usize % SECTION_BLOCK_COUNT);
 
@@ -312,6 +410,10 @@ impl LoadedChunk {
     fn motion_blocking(&self) -> Vec<Vec<u32>> {
         let mut heightmap: Vec<Vec<u32>> = vec![vec![0; 16]; 16];
 
+        if self.is_empty_placeholder() {
+            return heightmap;
+        }
+
         for z in 0..16 {
             for x in 0..16 {
                 for y in (0..self.height()).rev() {
@@ -372,6 +474,143 @@ impl LoadedChunk {
         Value::LongArray(encoded)
     }
 
+    /// Hides the blocks changed this tick which are hidden by `anti_xray`,
+    /// and reveals the hidden blocks next to blocks which became non-opaque.
+    fn apply_anti_xray(
+        &mut self,
+        anti_xray: &AntiXray,
+        pos: ChunkPos,
+        info: &ChunkLayerInfo,
+        messages: &mut ChunkLayerMessages,
+    ) {
+        let height = self.height();
+        let mut hide = vec![];
+        let mut reveal = BTreeSet::new();
+
+        for (sect_y, sect) in self.sections.iter().enumerate() {
+            for (i, entry) in sect.section_updates.iter().enumerate() {
+                let x = entry.off_x() as u32;
+                let y = sect_y as u32 * 16 + entry.off_y() as u32;
+                let z = entry.off_z() as u32;
+                let state = BlockState::from_raw(entry.block_state() as u16).unwrap();
+
+                if anti_xray.is_hidden(state) && !anti_xray.is_exposed(self, x, y, z) {
+                    let global = BlockPos::new(
+                        pos.x * 16 + x as i32,
+                        info.min_y + y as i32,
+                        pos.z * 16 + z as i32,
+                    );
+                    hide.push((sect_y, i, anti_xray.replacement(global)));
+                }
+
+                if !state.is_opaque() {
+                    // Neighbors outside the chunk are on its edge and never hidden.
+                    let neighbors = [
+                        [x.wrapping_sub(1), y, z],
+                        [x + 1, y, z],
+                        [x, y.wrapping_sub(1), z],
+                        [x, y + 1, z],
+                        [x, y, z.wrapping_sub(1)],
+                        [x, y, z + 1],
+                    ];
+
+                    for [x, y, z] in neighbors {
+                        if x < 16 && z < 16 && y < height {
+                            let state = self.block_state(x, y, z);
+                            if anti_xray.is_hidden(state) {
+                                reveal.insert((x, y, z));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for (sect_y, i, state) in hide {
+            let entry = &mut self.sections[sect_y].section_updates[i];
+            *entry = entry.with_block_state(state.to_raw() as u32);
+        }
+
+        for (x, y, z) in reveal {
+            let block_id = self.block_state(x, y, z);
+            let position = BlockPos::new(
+                pos.x * 16 + x as i32,
+                info.min_y + y as i32,
+                pos.z * 16 + z as i32,
+            );
+
+            messages.send_local_infallible(LocalMsg::PacketAt { pos }, |buf| {
+                PacketWriter::new(buf, info.threshold)
+                    .write_packet(&BlockUpdateS2c { position, block_id });
+            });
+        }
+    }
+
+    /// Returns the [`SolidMask`] of the section at index `sect_y`. The mask is
+    /// built the first time it is needed after the section was filled or
+    /// replaced, and kept up to date when single blocks are set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sect_y` is out of bounds.
+    pub fn solid_mask(&self, sect_y: u32) -> &SolidMask {
+        check_section_oob(self, sect_y);
+
+        let sect = self.section(sect_y as usize);
+        sect.solid
+            .get_or_init(|| Box::new(SolidMask::new(&sect.block_states)))
+    }
+
+    /// Returns whether the block at the given offsets is solid, using the
+    /// cached [`SolidMask`] of its section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offsets are out of bounds.
+    pub fn is_solid(&self, x: u32, y: u32, z: u32) -> bool {
+        check_block_oob(self, x, y, z);
+
+        self.solid_mask(y / 16).get(x, y % 16, z)
+    }
+
+    /// Returns the block states of the section at index `sect_y`.
+    pub(super) fn section_block_states(&self, sect_y: u32) -> &BlockStateContainer {
+        &self.section(sect_y as usize).block_states
+    }
+
+    /// Returns the number of blocks of every block state in this chunk.
+    pub fn block_histogram(&self) -> BTreeMap<BlockState, u32> {
+        let mut histogram = BTreeMap::new();
+
+        for sect in self.sections() {
+            sect.block_states.for_each_count(|state, count| {
+                *histogram.entry(state).or_default() += count as u32;
+            });
+        }
+
+        histogram
+    }
+
+    /// Returns the number of biome cells of every biome in this chunk. Each
+    /// biome cell is 4x4x4 blocks.
+    pub fn biome_histogram(&self) -> BTreeMap<BiomeId, u32> {
+        let mut histogram = BTreeMap::new();
+
+        for sect in self.sections() {
+            sect.biomes.for_each_count(|biome, count| {
+                *histogram.entry(biome).or_default() += count as u32;
+            });
+        }
+
+        histogram
+    }
+
+    /// Clears the cached initialization packets, so they are rebuilt the next
+    /// time they are needed.
+    pub(super) fn clear_init_packets_cache(&mut self) {
+        self.cached_init_packets.get_mut().clear();
+    }
+
     /// Writes the packet data needed to initialize this chunk.
     pub(crate) fn write_init_packets(
         &self,
@@ -379,80 +618,148 @@ impl LoadedChunk {
         pos: ChunkPos,
         info: &ChunkLayerInfo,
     ) {
+        // Placeholders are cheap to encode, so their packets aren't cached to
+        // keep them small.
+        if self.is_empty_placeholder() {
+            let mut init_packets = vec![];
+            self.encode_init_packets(&mut init_packets, pos, info, None);
+            writer.write_packet_bytes(&init_packets);
+            return;
+        }
+
         let mut init_packets = self.cached_init_packets.lock();
 
+        info.init_packet_cache.record(!init_packets.is_empty());
+
         if init_packets.is_empty() {
-            let heightmaps = compound! {
-                "MOTION_BLOCKING" => LoadedChunk::encode_heightmap(self.motion_blocking()),
-                // TODO Implement `WORLD_SURFACE` (or explain why we don't need it)
-                // "WORLD_SURFACE" => self.encode_heightmap(self.world_surface()),
+            self.encode_init_packets(&mut init_packets, pos, info, None);
+        }
+
+        writer.write_packet_bytes(&init_packets);
+    }
+
+    /// Writes the packet data needed to initialize this chunk, with only the
+    /// sections in `full` sent in full. The data isn't cached.
+    pub(crate) fn write_partial_init_packets(
+        &self,
+        mut writer: impl WritePacket,
+        pos: ChunkPos,
+        info: &ChunkLayerInfo,
+        full: Range<u32>,
+        far_sections: FarSections,
+    ) {
+        let mut init_packets = vec![];
+        self.encode_init_packets(&mut init_packets, pos, info, Some((full, far_sections)));
+        writer.write_packet_bytes(&init_packets);
+    }
+
+    fn encode_init_packets(
+        &self,
+        buf: &mut Vec<u8>,
+        pos: ChunkPos,
+        info: &ChunkLayerInfo,
+        partial: Option<(Range<u32>, FarSections)>,
+    ) {
+        let heightmaps = compound! {
+            "MOTION_BLOCKING" => LoadedChunk::encode_heightmap(self.motion_blocking()),
+            // TODO Implement `WORLD_SURFACE` (or explain why we don't need it)
+            // "WORLD_SURFACE" => self.encode_heightmap(self.world_surface()),
+        };
+
+        let mut blocks_and_biomes: Vec<u8> = vec![];
+
+        for (sect_y, sect) in self.sections().enumerate() {
+            // Sections far from the client are sent as a single block.
+            let far_block = partial
+                .as_ref()
+                .filter(|(full, _)| !full.contains(&(sect_y as u32)))
+                .map(|&(_, far_sections)| far_section_block(far_sections, &sect.block_states));
+
+            let non_air_blocks = match far_block {
+                Some(block) if block.is_air() => 0,
+                Some(_) => SECTION_BLOCK_COUNT as u16,
+                None => sect.count_non_air_blocks(),
             };
 
-            let mut blocks_and_biomes: Vec<u8> = vec![];
+            non_air_blocks.encode(&mut blocks_and_biomes).unwrap();
 
-            for sect in self.sections.iter() {
-                sect.count_non_air_blocks()
-                    .encode(&mut blocks_and_biomes)
-                    .unwrap();
+            let replaced;
+            let block_states = match (far_block, &info.anti_xray) {
+                (Some(block), _) => {
+                    replaced = BlockStateContainer::Single(block);
+                    &replaced
+                }
+                (None, Some(anti_xray)) => {
+                    let origin =
+                        BlockPos::new(pos.x * 16, info.min_y + sect_y as i32 * 16, pos.z * 16);
+                    replaced = anti_xray.obfuscate_section(self, sect_y as u32, origin);
+                    &replaced
+                }
+                (None, None) => &sect.block_states,
+            };
 
-                sect.block_states
-                    .encode_mc_format(
-                        &mut blocks_and_biomes,
-                        |b| b.to_raw().into(),
-                        4,
-                        8,
-                        bit_width(BlockState::max_raw().into()),
-                    )
-                    .expect("paletted container encode should always succeed");
-
-                sect.biomes
-                    .encode_mc_format(
-                        &mut blocks_and_biomes,
-                        |b| b.to_index() as _,
-                        0,
-                        3,
-                        bit_width(info.biome_registry_len - 1),
-                    )
-                    .expect("paletted container encode should always succeed");
-            }
+            block_states
+                .encode_mc_format(
+                    &mut blocks_and_biomes,
+                    |b| b.to_raw().into(),
+                    4,
+                    8,
+                    bit_width(BlockState::max_raw().into()),
+                )
+                .expect("paletted container encode should always succeed");
+
+            sect.biomes
+                .encode_mc_format(
+                    &mut blocks_and_biomes,
+                    |b| b.to_index() as _,
+                    0,
+                    3,
+                    bit_width(info.biome_registry_len - 1),
+                )
+                .expect("paletted container encode should always succeed");
+        }
+
+        let block_entities: Vec<_> = self
+            .block_entities
+            .iter()
+            .filter_map(|(&idx, nbt)| {
+                let x = idx % 16;
+                let z = idx / 16 % 16;
+                let y = idx / 16 / 16;
+
+                if let Some((full, _)) = &partial {
+                    if !full.contains(&(y / 16)) {
+                        return None;
+                    }
+                }
+
+                let kind = self
+                    .section(y as usize / 16)
+                    .block_states
+                    .get(idx as usize % SECTION_BLOCK_COUNT)
+                    .block_entity_kind();
 
-            let block_entities: Vec<_> = self
-                .block_entities
-                .iter()
-                .filter_map(|(&idx, nbt)| {
-                    let x = idx % 16;
-                    let z = idx / 16 % 16;
-                    let y = idx / 16 / 16;
-
-                    let kind = self.sections[y as usize / 16]
-                        .block_states
-                        .get(idx as usize % SECTION_BLOCK_COUNT)
-                        .block_entity_kind();
-
-                    kind.map(|kind| ChunkDataBlockEntity {
-                        packed_xz: ((x << 4) | z) as i8,
-                        y: y as i16 + info.min_y as i16,
-                        kind,
-                        data: Cow::Borrowed(nbt),
-                    })
+                kind.map(|kind| ChunkDataBlockEntity {
+                    packed_xz: ((x << 4) | z) as i8,
+                    y: y as i16 + info.min_y as i16,
+                    kind,
+                    data: Cow::Borrowed(nbt),
                 })
-                .collect();
-
-            PacketWriter::new(&mut init_packets, info.threshold).write_packet(&ChunkDataS2c {
-                pos,
-                heightmaps: Cow::Owned(heightmaps),
-                blocks_and_biomes: &blocks_and_biomes,
-                block_entities: Cow::Owned(block_entities),
-                sky_light_mask: Cow::Borrowed(&[]),
-                block_light_mask: Cow::Borrowed(&[]),
-                empty_sky_light_mask: Cow::Borrowed(&[]),
-                empty_block_light_mask: Cow::Borrowed(&[]),
-                sky_light_arrays: Cow::Borrowed(&[]),
-                block_light_arrays: Cow::Borrowed(&[]),
             })
-        }
+            .collect();
 
-        writer.write_packet_bytes(&init_packets);
+        PacketWriter::new(buf, info.threshold).write_packet(&ChunkDataS2c {
+            pos,
+            heightmaps: Cow::Owned(heightmaps),
+            blocks_and_biomes: &blocks_and_biomes,
+            block_entities: Cow::Owned(block_entities),
+            sky_light_mask: Cow::Borrowed(&[]),
+            block_light_mask: Cow::Borrowed(&[]),
+            empty_sky_light_mask: Cow::Borrowed(&[]),
+            empty_block_light_mask: Cow::Borrowed(&[]),
+            sky_light_arrays: Cow::Borrowed(&[]),
+            block_light_arrays: Cow::Borrowed(&[]),
+        })
     }
 
     /// Asserts that no changes to this chunk are currently recorded.
@@ -472,21 +779,25 @@ impl LoadedChunk {
 
 impl Chunk for LoadedChunk {
     fn height(&self) -> u32 {
-        self.sections.len() as u32 * 16
+        self.height
     }
 
     fn block_state(&self, x: u32, y: u32, z: u32) -> BlockState {
         check_block_oob(self, x, y, z);
 
         let idx = x + z * 16 + y % 16 * 16 * 16;
-        self.sections[y as usize / 16]
-            .block_states
-            .get(idx as usize)
+        self.section(y as usize / 16).block_states.get(idx as usize)
     }
 
     fn set_block_state(&mut self, x: u32, y: u32, z: u32, block: BlockState) -> BlockState {
         check_block_oob(self, x, y, z);
 
+        if self.is_empty_placeholder() && block == BlockState::AIR {
+            return block;
+        }
+
+        self.materialize();
+
         let sect_y = y / 16;
         let sect = &mut self.sections[sect_y as usize];
         let idx = x + z * 16 + y % 16 * 16 * 16;
@@ -496,6 +807,10 @@ impl Chunk for LoadedChunk {
         if block != old_block {
             self.cached_init_packets.get_mut().clear();
 
+            if let Some(solid) = sect.solid.get_mut() {
+                solid.set_idx(idx as usize, is_solid(block));
+            }
+
             if *self.viewer_count.get_mut() > 0 {
                 sect.section_updates.push(
                     ChunkDeltaUpdateEntry::new()
@@ -513,6 +828,12 @@ impl Chunk for LoadedChunk {
     fn fill_block_state_section(&mut self, sect_y: u32, block: BlockState) {
         check_section_oob(self, sect_y);
 
+        if self.is_empty_placeholder() && block == BlockState::AIR {
+            return;
+        }
+
+        self.materialize();
+
         let sect = &mut self.sections[sect_y as usize];
 
         if let PalettedContainer::Single(b) = &sect.block_states {
@@ -545,7 +866,7 @@ impl Chunk for LoadedChunk {
             for z in 0..16 {
                 for x in 0..16 {
                     for y in 0..16 {
-                        let idx = x + z * 16 + (sect_y * 16 + y) * (16 * 16);
+                        let idx = x + z * 16 + y * 16 * 16;
 
                         if block != sect.block_states.get(idx as usize) {
                             self.cached_init_packets.get_mut().clear();
@@ -566,6 +887,7 @@ impl Chunk for LoadedChunk {
         }
 
         sect.block_states.fill(block);
+        sect.solid.take();
     }
 
     fn block_entity(&self, x: u32, y: u32, z: u32) -> Option<&Compound> {
@@ -643,12 +965,18 @@ impl Chunk for LoadedChunk {
         check_biome_oob(self, x, y, z);
 
         let idx = x + z * 4 + y % 4 * 4 * 4;
-        self.sections[y as usize / 4].biomes.get(idx as usize)
+        self.section(y as usize / 4).biomes.get(idx as usize)
     }
 
     fn set_biome(&mut self, x: u32, y: u32, z: u32, biome: BiomeId) -> BiomeId {
         check_biome_oob(self, x, y, z);
 
+        if self.is_empty_placeholder() && biome == BiomeId::DEFAULT {
+            return biome;
+        }
+
+        self.materialize();
+
         let idx = x + z * 4 + y % 4 * 4 * 4;
         let old_biome = self.sections[y as usize / 4]
             .biomes
@@ -668,6 +996,12 @@ impl Chunk for LoadedChunk {
     fn fill_biome_section(&mut self, sect_y: u32, biome: BiomeId) {
         check_section_oob(self, sect_y);
 
+        if self.is_empty_placeholder() && biome == BiomeId::DEFAULT {
+            return;
+        }
+
+        self.materialize();
+
         let sect = &mut self.sections[sect_y as usize];
 
         if let PalettedContainer::Single(b) = &sect.biomes {
@@ -683,6 +1017,74 @@ impl Chunk for LoadedChunk {
         sect.biomes.fill(biome);
     }
 
+    /// Sections without a matching state in their palette are skipped.
+    fn count_blocks(&self, pred: impl BlockPredicate) -> u32 {
+        self.sections()
+            .map(|sect| {
+                sect.block_states
+                    .count_matching(|state| pred.matches(state)) as u32
+            })
+            .sum()
+    }
+
+    /// Sections without a matching state in their palette are skipped.
+    fn contains_any(&self, pred: impl BlockPredicate) -> bool {
+        self.sections()
+            .any(|sect| sect.block_states.any_matching(|state| pred.matches(state)))
+    }
+
+    /// Sections containing only air are skipped.
+    fn iter_non_air(&self) -> Box<dyn Iterator<Item = [u32; 3]> + '_> {
+        Box::new(self.sections().enumerate().flat_map(|(sect_y, sect)| {
+            sect.block_states
+                .indices_matching(|state| !state.is_air())
+                .map(move |idx| section_block_offsets(sect_y, idx))
+        }))
+    }
+
+    /// Unless a section has too many distinct states to be paletted, `f` is
+    /// only called once per state in the section's palette instead of once
+    /// per block.
+    fn map_states(&mut self, mut f: impl FnMut(BlockState) -> BlockState) {
+        if self.is_empty_placeholder() {
+            if f(BlockState::AIR) == BlockState::AIR {
+                return;
+            }
+
+            self.materialize();
+        }
+
+        let viewed = *self.viewer_count.get_mut() > 0;
+
+        for sect in self.sections.iter_mut() {
+            // Cloning only shares the data, which is copied if it changes.
+            let old = viewed.then(|| sect.block_states.clone());
+
+            if !sect.block_states.map(&mut f) {
+                continue;
+            }
+
+            self.cached_init_packets.get_mut().clear();
+            sect.solid.take();
+
+            if let Some(old) = old {
+                for idx in 0..SECTION_BLOCK_COUNT {
+                    let block = sect.block_states.get(idx);
+
+                    if block != old.get(idx) {
+                        sect.section_updates.push(
+                            ChunkDeltaUpdateEntry::new()
+                                .with_off_x((idx % 16) as u8)
+                                .with_off_y((idx / 256) as u8)
+                                .with_off_z((idx / 16 % 16) as u8)
+                                .with_block_state(block.to_raw().into()),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     fn shrink_to_fit(&mut self) {
         self.cached_init_packets.get_mut().shrink_to_fit();
 
@@ -696,6 +1098,7 @@ impl Chunk for LoadedChunk {
 
 #[cfg(test)]
 mod tests {
+    use valence_generated::block::BlockKind;
     use valence_protocol::{ident, CompressionThreshold};
 
     use super::*;
@@ -727,6 +1130,9 @@ mod tests {
                 min_y: -16,
                 biome_registry_len: 200,
                 threshold: CompressionThreshold(-1),
+                anti_xray: None,
+                vertical_streaming: None,
+                init_packet_cache: Default::default(),
             };
 
             let mut buf = vec![];
@@ -745,6 +1151,12 @@ mod tests {
             // Rebuild cache again.
             chunk.write_init_packets(&mut writer, ChunkPos::new(3, 4), &info);
             assert!(!chunk.cached_init_packets.get_mut().is_empty());
+
+            // Writing again should be a cache hit.
+            let before = info.init_packet_cache.stats();
+            chunk.write_init_packets(&mut writer, ChunkPos::new(3, 4), &info);
+            assert_eq!(info.init_packet_cache.stats().hits, before.hits + 1);
+            assert_eq!(info.init_packet_cache.stats().misses, before.misses);
         }
 
         let mut chunk = LoadedChunk::new(512);
@@ -772,4 +1184,175 @@ mod tests {
 
         assert!(!chunk.cached_init_packets.get_mut().is_empty());
     }
+
+    #[test]
+    fn loaded_chunk_solid_mask_follows_changes() {
+        let mut chunk = LoadedChunk::new(32);
+
+        assert!(!chunk.is_solid(3, 20, 4));
+        assert_eq!(chunk.solid_mask(1).count(), 0);
+
+        // Single blocks update the cached mask in place.
+        chunk.set_block(3, 20, 4, BlockState::STONE);
+        assert!(chunk.is_solid(3, 20, 4));
+        assert_eq!(chunk.solid_mask(1).count(), 1);
+
+        chunk.set_block(3, 20, 4, BlockState::TORCH);
+        assert!(!chunk.is_solid(3, 20, 4));
+
+        // Filling a section rebuilds the mask.
+        chunk.fill_block_state_section(1, BlockState::DIRT);
+        assert_eq!(chunk.solid_mask(1).count(), SECTION_BLOCK_COUNT as u32);
+        assert_eq!(chunk.solid_mask(0).count(), 0);
+
+        let mut unloaded = UnloadedChunk::with_height(32);
+        unloaded.set_block(0, 0, 0, BlockState::OAK_SLAB);
+        chunk.insert(unloaded);
+
+        assert!(chunk.is_solid(0, 0, 0));
+        assert_eq!(chunk.solid_mask(0).count(), 1);
+        assert_eq!(chunk.solid_mask(1).count(), 0);
+    }
+
+    #[test]
+    fn count_blocks() {
+        let mut chunk = LoadedChunk::new(32);
+
+        chunk.fill_block_state_section(0, BlockState::STONE);
+        chunk.set_block(1, 2, 3, BlockState::DIAMOND_ORE);
+        chunk.set_block(1, 20, 3, BlockState::DIAMOND_ORE);
+        chunk.set_block(2, 20, 3, BlockState::DEEPSLATE_DIAMOND_ORE);
+
+        assert_eq!(chunk.count_blocks(BlockState::DIAMOND_ORE), 2);
+        assert_eq!(
+            chunk.count_blocks(|state: BlockState| state.to_kind() == BlockKind::DeepslateDiamondOre),
+            1
+        );
+        assert_eq!(chunk.count_blocks(BlockKind::Stone), 4095);
+
+        let histogram = chunk.block_histogram();
+        assert_eq!(histogram[&BlockState::AIR], 4094);
+        assert_eq!(histogram[&BlockState::STONE], 4095);
+        assert_eq!(histogram[&BlockState::DIAMOND_ORE], 2);
+
+        assert_eq!(chunk.biome_histogram()[&BiomeId::default()], 128);
+    }
+
+    #[test]
+    fn fill_paletted_section_above_bottom() {
+        let mut chunk = LoadedChunk::new(32);
+        chunk.set_block(1, 20, 3, BlockState::STONE);
+        chunk.inc_viewer_count();
+
+        // Blocks are indexed within the section, not the whole chunk.
+        chunk.fill_block_state_section(1, BlockState::STONE);
+
+        assert_eq!(chunk.count_blocks(BlockState::STONE), 4096);
+        assert!(chunk.sections[0].section_updates.is_empty());
+
+        let updates = &chunk.sections[1].section_updates;
+        assert_eq!(updates.len(), 4095);
+        assert!(!updates
+            .iter()
+            .any(|u| [u.off_x(), u.off_y(), u.off_z()] == [1, 4, 3]));
+    }
+
+    #[test]
+    fn map_states_records_changes() {
+        let mut chunk = LoadedChunk::new(32);
+        chunk.set_block(1, 2, 3, BlockState::DIAMOND_ORE);
+        chunk.set_block(4, 5, 6, BlockState::STONE);
+        chunk.inc_viewer_count();
+
+        assert!(chunk.solid_mask(0).get(1, 2, 3));
+        chunk.cached_init_packets.get_mut().push(0);
+
+        chunk.map_states(|state| {
+            if state == BlockState::DIAMOND_ORE {
+                BlockState::AIR
+            } else {
+                state
+            }
+        });
+
+        assert_eq!(chunk.block_state(1, 2, 3), BlockState::AIR);
+        assert!(!chunk.solid_mask(0).get(1, 2, 3));
+        assert!(chunk.cached_init_packets.get_mut().is_empty());
+
+        let updates = &chunk.sections[0].section_updates;
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            [updates[0].off_x(), updates[0].off_y(), updates[0].off_z()],
+            [1, 2, 3]
+        );
+        assert!(chunk.sections[1].section_updates.is_empty());
+
+        // Placeholders stay empty if air isn't changed.
+        let mut chunk = LoadedChunk::new_empty(32);
+        chunk.map_states(|state| {
+            if state == BlockState::STONE {
+                BlockState::DIRT
+            } else {
+                state
+            }
+        });
+        assert!(chunk.is_empty_placeholder());
+
+        chunk.map_states(|_| BlockState::STONE);
+        assert_eq!(chunk.count_blocks(BlockState::STONE), 32 * 16 * 16);
+    }
+
+    #[test]
+    fn empty_placeholder_materializes_on_write() {
+        let info = ChunkLayerInfo {
+            dimension_type_name: ident!("whatever").into(),
+            height: 64,
+            min_y: 0,
+            biome_registry_len: 200,
+            threshold: CompressionThreshold(-1),
+            anti_xray: None,
+            vertical_streaming: None,
+            init_packet_cache: Default::default(),
+        };
+
+        let mut chunk = LoadedChunk::new_empty(64);
+        let full = LoadedChunk::new(64);
+
+        assert!(chunk.is_empty_placeholder());
+        assert_eq!(chunk.height(), 64);
+        assert_eq!(chunk.block_state(3, 40, 5), BlockState::AIR);
+        assert_eq!(chunk.biome(1, 2, 3), BiomeId::DEFAULT);
+        assert_eq!(chunk.count_blocks(BlockState::AIR), 64 * 16 * 16);
+        assert_eq!(chunk.solid_mask(2).count(), 0);
+
+        // Placeholders encode like an all-air chunk, without caching the packets.
+        let mut placeholder_buf = vec![];
+        let mut full_buf = vec![];
+        chunk.write_init_packets(
+            PacketWriter::new(&mut placeholder_buf, info.threshold),
+            ChunkPos::new(1, 2),
+            &info,
+        );
+        full.write_init_packets(
+            PacketWriter::new(&mut full_buf, info.threshold),
+            ChunkPos::new(1, 2),
+            &info,
+        );
+        assert_eq!(placeholder_buf, full_buf);
+        assert!(chunk.cached_init_packets.get_mut().is_empty());
+
+        // Writing what is already there keeps the placeholder.
+        chunk.set_block_state(0, 0, 0, BlockState::AIR);
+        chunk.fill_biomes(BiomeId::DEFAULT);
+        assert!(chunk.is_empty_placeholder());
+
+        chunk.set_block_state(0, 20, 0, BlockState::STONE);
+        assert!(!chunk.is_empty_placeholder());
+        assert_eq!(chunk.block_state(0, 20, 0), BlockState::STONE);
+        assert_eq!(chunk.count_blocks(BlockState::STONE), 1);
+
+        let old = chunk.insert_empty();
+        assert!(chunk.is_empty_placeholder());
+        assert_eq!(old.block_state(0, 20, 0), BlockState::STONE);
+    }
 }
@@ -1,17 +1,22 @@
 use std::array;
 use std::io::Write;
+use std::sync::Arc;
 
 use arrayvec::ArrayVec;
 use valence_protocol::{Encode, VarInt};
 
 use super::chunk::bit_width;
+use super::storage::{read_u8, read_var_u32, write_var_u32, ChunkDecodeError};
 
 /// `HALF_LEN` must be equal to `ceil(LEN / 2)`.
+///
+/// The data of indirect and direct containers is reference counted so clones
+/// share it until one of them is modified.
 #[derive(Clone, Debug)]
 pub(super) enum PalettedContainer<T, const LEN: usize, const HALF_LEN: usize> {
     Single(T),
-    Indirect(Box<Indirect<T, LEN, HALF_LEN>>),
-    Direct(Box<[T; LEN]>),
+    Indirect(Arc<Indirect<T, LEN, HALF_LEN>>),
+    Direct(Arc<[T; LEN]>),
 }
 
 #[derive(Clone, Debug)]
@@ -59,35 +64,221 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
                 } else {
                     // Upgrade to indirect.
                     let old = *old_val;
-                    let mut ind = Box::new(Indirect {
+                    let mut ind = Indirect {
                         palette: ArrayVec::from_iter([old, val]),
                         // All indices are initialized to index 0 (the old element).
                         indices: [0; HALF_LEN],
-                    });
+                    };
 
                     ind.indices[idx / 2] = 1 << (idx % 2 * 4);
-                    *self = Self::Indirect(ind);
+                    *self = Self::Indirect(Arc::new(ind));
                     old
                 }
             }
             Self::Indirect(ind) => {
-                if let Some(old) = ind.set(idx, val) {
+                if ind.get(idx) == val {
+                    val
+                } else if let Some(old) = Arc::make_mut(ind).set(idx, val) {
                     old
                 } else {
                     // Upgrade to direct.
-                    *self = Self::Direct(Box::new(array::from_fn(|i| ind.get(i))));
+                    *self = Self::Direct(Arc::new(array::from_fn(|i| ind.get(i))));
                     self.set(idx, val)
                 }
             }
             Self::Direct(vals) => {
                 let old = vals[idx];
-                vals[idx] = val;
+                if old != val {
+                    Arc::make_mut(vals)[idx] = val;
+                }
                 old
             }
         }
     }
 
+    /// Returns whether `val` could be an element of this container. This is
+    /// false if the palette doesn't contain `val`, without checking every
+    /// element.
+    pub(super) fn may_contain(&self, val: T) -> bool {
+        match self {
+            Self::Single(elem) => *elem == val,
+            Self::Indirect(ind) => ind.palette.contains(&val),
+            Self::Direct(_) => true,
+        }
+    }
+
+    /// Returns whether `pred` could be true for an element of this container,
+    /// by only checking the palette.
+    pub(super) fn may_contain_matching(&self, mut pred: impl FnMut(T) -> bool) -> bool {
+        match self {
+            Self::Single(elem) => pred(*elem),
+            Self::Indirect(ind) => ind.palette.iter().any(|&elem| pred(elem)),
+            Self::Direct(_) => true,
+        }
+    }
+
+    /// Returns the number of elements for which `pred` is true. Unless the
+    /// container is direct, `pred` is only called once per palette entry.
+    pub(super) fn count_matching(&self, mut pred: impl FnMut(T) -> bool) -> usize {
+        match self {
+            Self::Single(elem) => {
+                if pred(*elem) {
+                    LEN
+                } else {
+                    0
+                }
+            }
+            Self::Indirect(ind) => {
+                let matches: ArrayVec<bool, 16> = ind.palette.iter().map(|&e| pred(e)).collect();
+
+                if !matches.contains(&true) {
+                    return 0;
+                }
+
+                (0..LEN).filter(|&i| matches[ind.palette_idx(i)]).count()
+            }
+            Self::Direct(elems) => elems.iter().filter(|&&elem| pred(elem)).count(),
+        }
+    }
+
+    /// Returns whether `pred` is true for any element of this container.
+    /// Unless the container is direct, `pred` is only called once per palette
+    /// entry.
+    pub(super) fn any_matching(&self, pred: impl FnMut(T) -> bool) -> bool {
+        self.indices_matching(pred).next().is_some()
+    }
+
+    /// Returns an iterator over the indices of the elements for which `pred`
+    /// is true. Unless the container is direct, `pred` is only called once
+    /// per palette entry.
+    pub(super) fn indices_matching<'a>(
+        &'a self,
+        mut pred: impl FnMut(T) -> bool + 'a,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let matches: ArrayVec<bool, 16> = match self {
+            Self::Single(elem) => [pred(*elem)].into_iter().collect(),
+            Self::Indirect(ind) => ind.palette.iter().map(|&e| pred(e)).collect(),
+            Self::Direct(_) => ArrayVec::new(),
+        };
+
+        let len = if matches!(self, Self::Direct(_)) || matches.contains(&true) {
+            LEN
+        } else {
+            0
+        };
+
+        (0..len).filter(move |&i| match self {
+            Self::Single(_) => true,
+            Self::Indirect(ind) => matches[ind.palette_idx(i)],
+            Self::Direct(elems) => pred(elems[i]),
+        })
+    }
+
+    /// Replaces every element with the result of `f`. Unless the container
+    /// is direct, `f` is only called once per palette entry. Returns whether
+    /// any palette entry or element was changed.
+    pub(super) fn map(&mut self, mut f: impl FnMut(T) -> T) -> bool {
+        match self {
+            Self::Single(elem) => {
+                let new = f(*elem);
+                let changed = new != *elem;
+                *elem = new;
+                changed
+            }
+            Self::Indirect(ind) => {
+                let palette: ArrayVec<T, 16> = ind.palette.iter().map(|&e| f(e)).collect();
+
+                if palette == ind.palette {
+                    return false;
+                }
+
+                let unique = palette
+                    .iter()
+                    .enumerate()
+                    .all(|(i, e)| !palette[..i].contains(e));
+
+                if unique {
+                    Arc::make_mut(ind).palette = palette;
+                } else {
+                    // Entries were merged, so the palette is rebuilt to keep
+                    // its elements unique.
+                    let mut new_ind = Indirect {
+                        palette: ArrayVec::new(),
+                        indices: [0; HALF_LEN],
+                    };
+
+                    for i in 0..LEN {
+                        new_ind.set(i, palette[ind.palette_idx(i)]);
+                    }
+
+                    *self = if new_ind.palette.len() == 1 {
+                        Self::Single(new_ind.palette[0])
+                    } else {
+                        Self::Indirect(Arc::new(new_ind))
+                    };
+                }
+
+                true
+            }
+            Self::Direct(elems) => {
+                let mut changed = false;
+
+                // Only copy shared data if an element actually changes.
+                for i in 0..LEN {
+                    let new = f(elems[i]);
+
+                    if new != elems[i] {
+                        Arc::make_mut(elems)[i] = new;
+                        changed = true;
+                    }
+                }
+
+                changed
+            }
+        }
+    }
+
+    /// Calls `f` with every distinct element and the number of times it
+    /// occurs. Elements of direct containers are passed once per occurrence.
+    pub(super) fn for_each_count(&self, mut f: impl FnMut(T, usize)) {
+        match self {
+            Self::Single(elem) => f(*elem, LEN),
+            Self::Indirect(ind) => {
+                let mut counts = [0; 16];
+
+                for i in 0..LEN {
+                    counts[ind.palette_idx(i)] += 1;
+                }
+
+                for (&elem, &count) in ind.palette.iter().zip(&counts) {
+                    if count > 0 {
+                        f(elem, count);
+                    }
+                }
+            }
+            Self::Direct(elems) => {
+                for &elem in elems.iter() {
+                    f(elem, 1);
+                }
+            }
+        }
+    }
+
+    /// Returns whether this container's data is shared with a clone of it.
+    pub(super) fn is_shared(&self) -> bool {
+        match self {
+            Self::Single(_) => false,
+            Self::Indirect(ind) => Arc::strong_count(ind) > 1,
+            Self::Direct(dir) => Arc::strong_count(dir) > 1,
+        }
+    }
+
     pub(super) fn shrink_to_fit(&mut self) {
+        // Shrinking shared data would copy it and use more memory, not less.
+        if self.is_shared() {
+            return;
+        }
+
         match self {
             Self::Single(_) => {}
             Self::Indirect(ind) => {
@@ -103,7 +294,7 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
                 if new_ind.palette.len() == 1 {
                     *self = Self::Single(new_ind.palette[0]);
                 } else {
-                    **ind = new_ind;
+                    *ind = Arc::new(new_ind);
                 }
             }
             Self::Direct(dir) => {
@@ -121,7 +312,7 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
                 *self = if ind.palette.len() == 1 {
                     Self::Single(ind.palette[0])
                 } else {
-                    Self::Indirect(Box::new(ind))
+                    Self::Indirect(Arc::new(ind))
                 };
             }
         }
@@ -225,6 +416,105 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
 
         Ok(())
     }
+
+    /// Encodes the paletted container in the compact format of
+    /// [`storage`](super::storage), which keeps the palette and the
+    /// representation of the container.
+    pub(super) fn write_compact<F>(&self, buf: &mut Vec<u8>, mut to_u32: F)
+    where
+        F: FnMut(T) -> u32,
+    {
+        match self {
+            Self::Single(val) => {
+                buf.push(0);
+                write_var_u32(buf, to_u32(*val));
+            }
+            Self::Indirect(ind) => {
+                buf.push(1);
+                buf.push(ind.palette.len() as u8);
+                for val in &ind.palette {
+                    write_var_u32(buf, to_u32(*val));
+                }
+                buf.extend_from_slice(&ind.indices);
+            }
+            Self::Direct(dir) => {
+                buf.push(2);
+                for val in dir.iter() {
+                    write_var_u32(buf, to_u32(*val));
+                }
+            }
+        }
+    }
+
+    /// Decodes a paletted container written by
+    /// [`write_compact`](Self::write_compact). `from_u32` returns `None` for
+    /// values which don't map to an element.
+    pub(super) fn read_compact<F>(r: &mut &[u8], mut from_u32: F) -> Result<Self, ChunkDecodeError>
+    where
+        F: FnMut(u32) -> Option<T>,
+    {
+        let mut read_val = |r: &mut &[u8]| {
+            let raw = read_var_u32(r)?;
+            from_u32(raw).ok_or(ChunkDecodeError::InvalidValue(raw))
+        };
+
+        match read_u8(r)? {
+            0 => Ok(Self::Single(read_val(r)?)),
+            1 => {
+                let len = read_u8(r)? as usize;
+
+                if !(2..=16).contains(&len) {
+                    return Err(ChunkDecodeError::Malformed("invalid palette length"));
+                }
+
+                let mut palette = ArrayVec::new();
+
+                for _ in 0..len {
+                    let val = read_val(r)?;
+
+                    if palette.contains(&val) {
+                        return Err(ChunkDecodeError::Malformed("duplicate palette entry"));
+                    }
+
+                    palette.push(val);
+                }
+
+                if r.len() < HALF_LEN {
+                    return Err(ChunkDecodeError::UnexpectedEof);
+                }
+
+                let (indices, rest) = r.split_at(HALF_LEN);
+                *r = rest;
+
+                let ind = Indirect {
+                    palette,
+                    indices: indices.try_into().unwrap(),
+                };
+
+                if ind
+                    .indices
+                    .iter()
+                    .flat_map(|byte| [byte & 0b1111, byte >> 4])
+                    .take(LEN)
+                    .any(|idx| idx as usize >= len)
+                {
+                    return Err(ChunkDecodeError::Malformed("palette index out of bounds"));
+                }
+
+                Ok(Self::Indirect(Arc::new(ind)))
+            }
+            2 => {
+                let mut vals = Vec::with_capacity(LEN);
+
+                for _ in 0..LEN {
+                    vals.push(read_val(r)?);
+                }
+
+                Ok(Self::Direct(Arc::new(array::from_fn(|i| vals[i]))))
+            }
+            _ => Err(ChunkDecodeError::Malformed("unknown container kind")),
+        }
+    }
 }
 
 impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize> Default
@@ -237,8 +527,11 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize> Default
 
 impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize> Indirect<T, LEN, HALF_LEN> {
     pub(super) fn get(&self, idx: usize) -> T {
-        let palette_idx = self.indices[idx / 2] >> (idx % 2 * 4) & 0b1111;
-        self.palette[palette_idx as usize]
+        self.palette[self.palette_idx(idx)]
+    }
+
+    fn palette_idx(&self, idx: usize) -> usize {
+        (self.indices[idx / 2] >> (idx % 2 * 4) & 0b1111) as usize
     }
 
     pub(super) fn set(&mut self, idx: usize, val: T) -> Option<T> {
@@ -336,4 +629,104 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn count_elements() {
+        const LEN: usize = 100;
+
+        let mut p = PalettedContainer::<u32, LEN, { LEN / 2 }>::new();
+        assert_eq!(p.count_matching(|e| e == 0), LEN);
+        assert!(!p.may_contain_matching(|e| e == 1));
+
+        for i in 0..LEN {
+            p.set(i, i as u32 % 4);
+        }
+
+        assert_eq!(p.count_matching(|e| e >= 2), LEN / 2);
+        assert!(!p.may_contain_matching(|e| e > 3));
+
+        let mut counts = [0; 4];
+        p.for_each_count(|e, count| counts[e as usize] += count);
+        assert_eq!(counts, [LEN / 4; 4]);
+
+        // Direct containers are counted element by element.
+        for i in 0..LEN {
+            p.set(i, i as u32);
+        }
+
+        assert!(matches!(p, PalettedContainer::Direct(_)));
+        assert_eq!(p.count_matching(|e| e < 10), 10);
+    }
+
+    #[test]
+    fn match_and_map_elements() {
+        const LEN: usize = 100;
+
+        let mut p = PalettedContainer::<u32, LEN, { LEN / 2 }>::new();
+        assert!(p.any_matching(|e| e == 0));
+        assert_eq!(p.indices_matching(|e| e == 0).count(), LEN);
+
+        for i in 0..LEN {
+            p.set(i, i as u32 % 4);
+        }
+
+        // The palette still contains 3 after it's overwritten.
+        p.set(3, 0);
+        assert!(p.may_contain(3));
+        assert_eq!(
+            p.indices_matching(|e| e == 3).take(2).collect::<Vec<_>>(),
+            [7, 11]
+        );
+
+        for i in (3..LEN).step_by(4) {
+            p.set(i, 0);
+        }
+
+        assert!(!p.any_matching(|e| e == 3));
+
+        // Merging palette entries keeps the palette unique.
+        assert!(p.map(|e| e / 2));
+        assert!(check(
+            &p,
+            &array::from_fn::<_, LEN, _>(|i| [0, 0, 1, 0][i % 4])
+        ));
+        assert!(!p.map(|e| e));
+
+        assert!(p.map(|_| 5));
+        assert!(matches!(p, PalettedContainer::Single(5)));
+
+        for i in 0..LEN {
+            p.set(i, i as u32);
+        }
+
+        assert!(p.map(|e| e % 2));
+        assert_eq!(p.indices_matching(|e| e == 1).count(), LEN / 2);
+    }
+
+    #[test]
+    fn copy_on_write() {
+        const LEN: usize = 100;
+
+        let mut p = PalettedContainer::<u32, LEN, { LEN / 2 }>::new();
+        for i in 0..LEN {
+            p.set(i, i as u32 % 4);
+        }
+
+        let mut clone = p.clone();
+        assert!(p.is_shared() && clone.is_shared());
+
+        // Setting an element to its current value doesn't copy.
+        clone.set(1, 1);
+        assert!(clone.is_shared());
+
+        clone.set(1, 3);
+        assert!(!p.is_shared() && !clone.is_shared());
+        assert_eq!(p.get(1), 1);
+        assert_eq!(clone.get(1), 3);
+
+        // Shared containers aren't shrunk.
+        let mut clone = p.clone();
+        clone.shrink_to_fit();
+        assert!(clone.is_shared());
+    }
 }
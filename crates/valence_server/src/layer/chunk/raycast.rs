@@ -0,0 +1,175 @@
+use valence_math::DVec3;
+use valence_protocol::{BlockPos, BlockState, Direction};
+
+use super::ChunkLayer;
+
+/// The first block hit by a ray cast with [`ChunkLayer::raycast`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BlockRaycastHit {
+    /// The position of the block that was hit.
+    pub pos: BlockPos,
+    /// The state of the block that was hit.
+    pub state: BlockState,
+    /// The face of the block's collision shape the ray entered through. If the
+    /// ray starts inside the block, this is the face it would have entered
+    /// through.
+    pub face: Direction,
+    /// The distance from the origin of the ray to the hit point.
+    pub distance: f64,
+}
+
+impl BlockRaycastHit {
+    /// Returns the point where the ray hit the block, given the origin and
+    /// direction of the ray.
+    pub fn point(&self, origin: DVec3, direction: DVec3) -> DVec3 {
+        origin + direction.normalize_or_zero() * self.distance
+    }
+}
+
+impl ChunkLayer {
+    /// Casts a ray from `origin` in `direction` and returns the first block
+    /// whose collision shapes it hits within `max_distance`, or `None` if it
+    /// hits nothing. Blocks without collision shapes (air, grass, torches,
+    /// etc.) are passed through, as are unloaded chunks.
+    ///
+    /// `direction` doesn't need to be normalized. `None` is returned if it is
+    /// zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_distance` is not finite.
+    pub fn raycast(
+        &self,
+        origin: DVec3,
+        direction: DVec3,
+        max_distance: f64,
+    ) -> Option<BlockRaycastHit> {
+        assert!(max_distance.is_finite(), "max distance must be finite");
+
+        let dir = direction.normalize_or_zero();
+
+        if dir == DVec3::ZERO {
+            return None;
+        }
+
+        // Walk through the blocks along the ray, one block boundary at a time.
+        let mut pos = [
+            origin.x.floor() as i32,
+            origin.y.floor() as i32,
+            origin.z.floor() as i32,
+        ];
+
+        let mut step = [0; 3];
+        // The distance along the ray to the next block boundary on each axis.
+        let mut t_max = [f64::INFINITY; 3];
+        // The distance along the ray between block boundaries on each axis.
+        let mut t_delta = [f64::INFINITY; 3];
+
+        for i in 0..3 {
+            if dir[i] > 0.0 {
+                step[i] = 1;
+                t_max[i] = (pos[i] as f64 + 1.0 - origin[i]) / dir[i];
+                t_delta[i] = 1.0 / dir[i];
+            } else if dir[i] < 0.0 {
+                step[i] = -1;
+                t_max[i] = (pos[i] as f64 - origin[i]) / dir[i];
+                t_delta[i] = -1.0 / dir[i];
+            }
+        }
+
+        let mut t = 0.0;
+
+        while t <= max_distance {
+            let block_pos = BlockPos::new(pos[0], pos[1], pos[2]);
+
+            if self.is_solid(block_pos) {
+                if let Some(hit) = self.raycast_block(block_pos, origin, dir, max_distance) {
+                    return Some(hit);
+                }
+            }
+
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+
+            t = t_max[axis];
+            t_max[axis] += t_delta[axis];
+            pos[axis] += step[axis];
+        }
+
+        None
+    }
+
+    /// Returns the nearest hit of the normalized ray on the collision shapes of
+    /// the block at `pos`.
+    fn raycast_block(
+        &self,
+        pos: BlockPos,
+        origin: DVec3,
+        dir: DVec3,
+        max_distance: f64,
+    ) -> Option<BlockRaycastHit> {
+        let state = self.block(pos)?.state;
+        let offset = DVec3::new(pos.x as f64, pos.y as f64, pos.z as f64);
+
+        let mut nearest: Option<BlockRaycastHit> = None;
+
+        for shape in state.collision_shapes() {
+            let shape = shape + offset;
+
+            let Some([near, _]) = shape.ray_intersection(origin, dir) else {
+                continue;
+            };
+
+            if near > max_distance || nearest.is_some_and(|hit| hit.distance <= near) {
+                continue;
+            }
+
+            // The face the ray entered through is on the axis whose slab it
+            // entered last.
+            let mut axis = 0;
+            let mut axis_t = f64::NEG_INFINITY;
+
+            for i in 0..3 {
+                let plane = if dir[i] >= 0.0 {
+                    shape.min()[i]
+                } else {
+                    shape.max()[i]
+                };
+
+                let t = (plane - origin[i]) / dir[i];
+
+                if t.is_finite() && t > axis_t {
+                    axis = i;
+                    axis_t = t;
+                }
+            }
+
+            let face = match (axis, dir[axis] >= 0.0) {
+                (0, true) => Direction::West,
+                (0, false) => Direction::East,
+                (1, true) => Direction::Down,
+                (1, false) => Direction::Up,
+                (_, true) => Direction::North,
+                (_, false) => Direction::South,
+            };
+
+            nearest = Some(BlockRaycastHit {
+                pos,
+                state,
+                face,
+                distance: near,
+            });
+        }
+
+        nearest
+    }
+}
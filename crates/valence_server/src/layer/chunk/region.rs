@@ -0,0 +1,602 @@
+//! Bulk operations on cuboid regions of a [`ChunkLayer`], similar to the
+//! commands of WorldEdit.
+//!
+//! The operations work a chunk at a time instead of a block at a time, and
+//! sections which are completely covered by a region are filled at once.
+//! Changes are sent to the viewers of each chunk section in a single
+//! packet, like any other change to a [`ChunkLayer`].
+//!
+//! [`count_blocks`] and [`find_blocks`] search a region for blocks, skipping
+//! chunk sections which can't contain a match according to their palette.
+//!
+//! Positions outside of loaded chunks and outside of the height of the layer
+//! are skipped by every operation.
+//!
+//! Operations on very large regions can take longer than a tick. The `_task`
+//! variants of the operations spread the work across ticks instead.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use bevy_ecs::entity::Entity;
+use valence_nbt::Compound;
+use valence_protocol::{BlockPos, BlockState, ChunkPos};
+
+use super::{Block, BlockPredicate, BlockRef, Chunk, ChunkLayer, IntoBlock, LoadedChunk};
+use crate::budget::BudgetedTask;
+
+/// A box of block positions, including both corners. The region is empty if
+/// a coordinate of `min` is greater than the same coordinate of `max`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Region {
+    pub min: BlockPos,
+    pub max: BlockPos,
+}
+
+impl Region {
+    /// Creates the region with the corners `a` and `b`.
+    pub fn new(a: impl Into<BlockPos>, b: impl Into<BlockPos>) -> Self {
+        let (a, b) = (a.into(), b.into());
+
+        Self {
+            min: BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// Creates the region starting at `min` with the given size along the X,
+    /// Y, and Z axes.
+    pub fn with_size(min: impl Into<BlockPos>, size: [u32; 3]) -> Self {
+        let min = min.into();
+
+        Self {
+            min,
+            max: BlockPos::new(
+                (min.x as i64 + size[0] as i64 - 1) as i32,
+                (min.y as i64 + size[1] as i64 - 1) as i32,
+                (min.z as i64 + size[2] as i64 - 1) as i32,
+            ),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn contains(&self, pos: impl Into<BlockPos>) -> bool {
+        let pos = pos.into();
+
+        (self.min.x..=self.max.x).contains(&pos.x)
+            && (self.min.y..=self.max.y).contains(&pos.y)
+            && (self.min.z..=self.max.z).contains(&pos.z)
+    }
+
+    /// The number of blocks along the X, Y, and Z axes.
+    pub fn size(&self) -> [u32; 3] {
+        if self.is_empty() {
+            return [0; 3];
+        }
+
+        [
+            self.max.x.abs_diff(self.min.x) + 1,
+            self.max.y.abs_diff(self.min.y) + 1,
+            self.max.z.abs_diff(self.min.z) + 1,
+        ]
+    }
+
+    /// The number of blocks in the region.
+    pub fn volume(&self) -> u64 {
+        self.size().iter().map(|&n| n as u64).product()
+    }
+
+    /// Returns every position in the region.
+    pub fn positions(&self) -> impl Iterator<Item = BlockPos> {
+        let Self { min, max } = *self;
+
+        (min.y..=max.y).flat_map(move |y| {
+            (min.z..=max.z).flat_map(move |z| (min.x..=max.x).map(move |x| BlockPos::new(x, y, z)))
+        })
+    }
+
+    /// Splits the region into its parts inside of each chunk column, in the
+    /// same order as the chunks are visited by the operations of this module.
+    /// Useful for spreading large operations across ticks with a
+    /// [`BudgetedTask`].
+    pub fn chunk_columns(&self) -> impl ExactSizeIterator<Item = Region> {
+        let region = *self;
+
+        let (min_cx, min_cz) = (region.min.x.div_euclid(16), region.min.z.div_euclid(16));
+
+        let [width, length] = if region.is_empty() {
+            [0, 0]
+        } else {
+            [
+                (region.max.x.div_euclid(16) - min_cx + 1) as usize,
+                (region.max.z.div_euclid(16) - min_cz + 1) as usize,
+            ]
+        };
+
+        (0..width * length).map(move |i| {
+            let cx = min_cx + (i % width) as i32;
+            let cz = min_cz + (i / width) as i32;
+
+            region.intersection(&Region {
+                min: BlockPos::new(cx * 16, region.min.y, cz * 16),
+                max: BlockPos::new(cx * 16 + 15, region.max.y, cz * 16 + 15),
+            })
+        })
+    }
+
+    /// Returns the part of this region inside of `other`.
+    pub fn intersection(&self, other: &Region) -> Region {
+        Region {
+            min: BlockPos::new(
+                self.min.x.max(other.min.x),
+                self.min.y.max(other.min.y),
+                self.min.z.max(other.min.z),
+            ),
+            max: BlockPos::new(
+                self.max.x.min(other.max.x),
+                self.max.y.min(other.max.y),
+                self.max.z.min(other.max.z),
+            ),
+        }
+    }
+}
+
+/// Blocks copied from a [`ChunkLayer`] with [`copy`], which can be placed
+/// somewhere else with [`paste`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct Clipboard {
+    size: [u32; 3],
+    /// Indexed by `x + z * size_x + y * size_x * size_z`.
+    states: Vec<BlockState>,
+    block_entities: BTreeMap<usize, Compound>,
+}
+
+impl Clipboard {
+    /// Creates a clipboard of the given size filled with air.
+    pub fn new(size: [u32; 3]) -> Self {
+        let len = size.iter().map(|&n| n as usize).product();
+
+        Self {
+            size,
+            states: vec![BlockState::AIR; len],
+            block_entities: BTreeMap::new(),
+        }
+    }
+
+    /// The number of blocks along the X, Y, and Z axes.
+    pub fn size(&self) -> [u32; 3] {
+        self.size
+    }
+
+    /// Returns the block at `offset` from the minimum corner of the
+    /// clipboard, or `None` if the offset is out of bounds.
+    pub fn block(&self, offset: [u32; 3]) -> Option<BlockRef<'_>> {
+        let idx = self.index(offset)?;
+
+        Some(BlockRef::new(
+            self.states[idx],
+            self.block_entities.get(&idx),
+        ))
+    }
+
+    /// Sets the block at `offset` from the minimum corner of the clipboard.
+    /// Returns the previous block, or `None` if the offset is out of bounds.
+    pub fn set_block(&mut self, offset: [u32; 3], block: impl IntoBlock) -> Option<Block> {
+        let idx = self.index(offset)?;
+        let block = block.into_block();
+
+        let state = std::mem::replace(&mut self.states[idx], block.state);
+
+        let nbt = match block.nbt {
+            Some(nbt) => self.block_entities.insert(idx, nbt),
+            None => self.block_entities.remove(&idx),
+        };
+
+        Some(Block { state, nbt })
+    }
+
+    fn index(&self, [x, y, z]: [u32; 3]) -> Option<usize> {
+        let [sx, sy, sz] = self.size.map(|n| n as usize);
+        let [x, y, z] = [x, y, z].map(|n| n as usize);
+
+        (x < sx && y < sy && z < sz).then(|| x + z * sx + y * sx * sz)
+    }
+}
+
+/// Sets every block in `region` to `block`. Returns the number of blocks set.
+pub fn fill(layer: &mut ChunkLayer, region: Region, block: impl IntoBlock) -> usize {
+    let block = block.into_block();
+
+    for_each_part(layer, region, |chunk, part| {
+        let full_columns = part.x == (0..16) && part.z == (0..16);
+        let mut y = part.y.start;
+
+        while y < part.y.end {
+            if full_columns && y % 16 == 0 && y + 16 <= part.y.end {
+                chunk.fill_block_state_section(y / 16, block.state);
+
+                for y in y..y + 16 {
+                    for z in 0..16 {
+                        for x in 0..16 {
+                            chunk.set_block_entity(x, y, z, block.nbt.clone());
+                        }
+                    }
+                }
+
+                y += 16;
+            } else {
+                for z in part.z.clone() {
+                    for x in part.x.clone() {
+                        chunk.set_block(x, y, z, block.clone());
+                    }
+                }
+
+                y += 1;
+            }
+        }
+
+        part.volume()
+    })
+}
+
+/// Sets every block in `region` with the state `from` to `to`. Returns the
+/// number of blocks replaced.
+pub fn replace(
+    layer: &mut ChunkLayer,
+    region: Region,
+    from: BlockState,
+    to: impl IntoBlock,
+) -> usize {
+    let to = to.into_block();
+
+    for_each_part(layer, region, |chunk, part| {
+        let mut count = 0;
+
+        for y in part.y.clone() {
+            // Skip sections without the block in their palette.
+            if !chunk.section_block_states(y / 16).may_contain(from) {
+                continue;
+            }
+
+            for z in part.z.clone() {
+                for x in part.x.clone() {
+                    if chunk.block_state(x, y, z) == from {
+                        chunk.set_block(x, y, z, to.clone());
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    })
+}
+
+/// Returns the number of blocks in `region` matching `pred`. Chunk sections
+/// without a matching state in their palette are skipped, and sections which
+/// are completely covered by the region are counted a palette entry at a time.
+pub fn count_blocks(layer: &ChunkLayer, region: Region, pred: impl BlockPredicate) -> usize {
+    let mut count = 0;
+
+    for_each_candidate(layer, region, &pred, |chunk, part, ys| {
+        if part.x == (0..16) && part.z == (0..16) && ys.len() == 16 {
+            count += chunk
+                .section_block_states(ys.start / 16)
+                .count_matching(|state| pred.matches(state));
+            return;
+        }
+
+        for y in ys {
+            for z in part.z.clone() {
+                for x in part.x.clone() {
+                    if pred.matches(chunk.block_state(x, y, z)) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+    });
+
+    count
+}
+
+/// Returns the positions of the blocks in `region` matching `pred`, ordered
+/// by chunk and then by Y, Z, and X. Chunk sections without a matching state
+/// in their palette are skipped.
+pub fn find_blocks(layer: &ChunkLayer, region: Region, pred: impl BlockPredicate) -> Vec<BlockPos> {
+    let min_y = layer.min_y();
+    let mut positions = vec![];
+
+    for_each_candidate(layer, region, &pred, |chunk, part, ys| {
+        for y in ys {
+            for z in part.z.clone() {
+                for x in part.x.clone() {
+                    if pred.matches(chunk.block_state(x, y, z)) {
+                        positions.push(BlockPos::new(
+                            part.pos.x * 16 + x as i32,
+                            min_y + y as i32,
+                            part.pos.z * 16 + z as i32,
+                        ));
+                    }
+                }
+            }
+        }
+    });
+
+    positions
+}
+
+/// Calls `f` with the part of `region` in each loaded chunk and the range of
+/// Y coordinates of the part in each section, skipping sections without a
+/// state matching `pred` in their palette.
+fn for_each_candidate(
+    layer: &ChunkLayer,
+    region: Region,
+    pred: &impl BlockPredicate,
+    mut f: impl FnMut(&LoadedChunk, &ChunkPart, Range<u32>),
+) {
+    for part in chunk_parts(layer, region) {
+        let Some(chunk) = layer.chunk(part.pos) else {
+            continue;
+        };
+
+        let mut y = part.y.start;
+
+        while y < part.y.end {
+            let sect_end = (y / 16 * 16 + 16).min(part.y.end);
+
+            if chunk
+                .section_block_states(y / 16)
+                .may_contain_matching(|state| pred.matches(state))
+            {
+                f(chunk, &part, y..sect_end);
+            }
+
+            y = sect_end;
+        }
+    }
+}
+
+/// Creates a [`BudgetedTask`] which runs [`fill`] on `region` in the layer
+/// `layer` one chunk column at a time, so that large regions are filled across
+/// several ticks.
+pub fn fill_task(layer: Entity, region: Region, block: impl IntoBlock) -> BudgetedTask {
+    let block = block.into_block();
+
+    BudgetedTask::for_layer(layer, region.chunk_columns(), move |layer, part| {
+        fill(layer, part, block.clone());
+    })
+}
+
+/// Creates a [`BudgetedTask`] which runs [`replace`] on `region` in the layer
+/// `layer` one chunk column at a time.
+pub fn replace_task(
+    layer: Entity,
+    region: Region,
+    from: BlockState,
+    to: impl IntoBlock,
+) -> BudgetedTask {
+    let to = to.into_block();
+
+    BudgetedTask::for_layer(layer, region.chunk_columns(), move |layer, part| {
+        replace(layer, part, from, to.clone());
+    })
+}
+
+/// Sets the four vertical sides of `region` to `block`, leaving the floor,
+/// the ceiling, and the inside unchanged. Returns the number of blocks set.
+pub fn walls(layer: &mut ChunkLayer, region: Region, block: impl IntoBlock) -> usize {
+    if region.is_empty() {
+        return 0;
+    }
+
+    let block = block.into_block();
+    let Region { min, max } = region;
+
+    let mut count = fill(
+        layer,
+        Region::new(min, [min.x, max.y, max.z]),
+        block.clone(),
+    );
+
+    if max.x > min.x {
+        count += fill(
+            layer,
+            Region::new([max.x, min.y, min.z], max),
+            block.clone(),
+        );
+    }
+
+    // The corners are already covered by the sides along the X axis.
+    let inner = Region {
+        min: BlockPos::new(min.x + 1, min.y, min.z),
+        max: BlockPos::new(max.x - 1, max.y, min.z),
+    };
+    count += fill(layer, inner, block.clone());
+
+    if max.z > min.z {
+        let inner = Region {
+            min: BlockPos::new(min.x + 1, min.y, max.z),
+            max: BlockPos::new(max.x - 1, max.y, max.z),
+        };
+        count += fill(layer, inner, block);
+    }
+
+    count
+}
+
+/// Sets the faces of `region` to `block` and fills the inside with air.
+/// Returns the number of blocks set.
+pub fn hollow(layer: &mut ChunkLayer, region: Region, block: impl IntoBlock) -> usize {
+    if region.is_empty() {
+        return 0;
+    }
+
+    let block = block.into_block();
+    let Region { min, max } = region;
+
+    let mut count = walls(layer, region, block.clone());
+
+    // The floor and the ceiling, without the walls.
+    let floor = Region {
+        min: BlockPos::new(min.x + 1, min.y, min.z + 1),
+        max: BlockPos::new(max.x - 1, min.y, max.z - 1),
+    };
+    count += fill(layer, floor, block.clone());
+
+    if max.y > min.y {
+        let ceiling = Region {
+            min: BlockPos::new(min.x + 1, max.y, min.z + 1),
+            max: BlockPos::new(max.x - 1, max.y, max.z - 1),
+        };
+        count += fill(layer, ceiling, block);
+    }
+
+    let inside = Region {
+        min: BlockPos::new(min.x + 1, min.y + 1, min.z + 1),
+        max: BlockPos::new(max.x - 1, max.y - 1, max.z - 1),
+    };
+    count += fill(layer, inside, BlockState::AIR);
+
+    count
+}
+
+/// Copies the blocks in `region` to a new [`Clipboard`]. Blocks which are
+/// not loaded are copied as air.
+pub fn copy(layer: &ChunkLayer, region: Region) -> Clipboard {
+    let mut clipboard = Clipboard::new(region.size());
+    let min_y = layer.min_y();
+
+    for part in chunk_parts(layer, region) {
+        let Some(chunk) = layer.chunk(part.pos) else {
+            continue;
+        };
+
+        for y in part.y.clone() {
+            for z in part.z.clone() {
+                for x in part.x.clone() {
+                    let offset = part.offset(region.min, min_y, [x, y, z]);
+
+                    clipboard.set_block(offset, chunk.block(x, y, z));
+                }
+            }
+        }
+    }
+
+    clipboard
+}
+
+/// Places the blocks of `clipboard` with its minimum corner at `origin`. If
+/// `skip_air` is true, air in the clipboard doesn't replace blocks. Returns
+/// the number of blocks set.
+pub fn paste(
+    layer: &mut ChunkLayer,
+    clipboard: &Clipboard,
+    origin: impl Into<BlockPos>,
+    skip_air: bool,
+) -> usize {
+    let origin = origin.into();
+    let region = Region::with_size(origin, clipboard.size);
+    let min_y = layer.min_y();
+
+    for_each_part(layer, region, |chunk, part| {
+        let mut count = 0;
+
+        for y in part.y.clone() {
+            for z in part.z.clone() {
+                for x in part.x.clone() {
+                    let offset = part.offset(origin, min_y, [x, y, z]);
+
+                    let Some(block) = clipboard.block(offset) else {
+                        continue;
+                    };
+
+                    if skip_air && block.state.is_air() {
+                        continue;
+                    }
+
+                    chunk.set_block(x, y, z, block);
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    })
+}
+
+/// The part of a region inside of a single chunk, in the coordinates of the
+/// chunk.
+struct ChunkPart {
+    pos: ChunkPos,
+    x: Range<u32>,
+    y: Range<u32>,
+    z: Range<u32>,
+}
+
+impl ChunkPart {
+    fn volume(&self) -> usize {
+        self.x.len() * self.y.len() * self.z.len()
+    }
+
+    /// Returns the offset of a position in this part from `origin`.
+    fn offset(&self, origin: BlockPos, min_y: i32, [x, y, z]: [u32; 3]) -> [u32; 3] {
+        [
+            (self.pos.x * 16 + x as i32).abs_diff(origin.x),
+            (min_y + y as i32).abs_diff(origin.y),
+            (self.pos.z * 16 + z as i32).abs_diff(origin.z),
+        ]
+    }
+}
+
+/// Calls `f` with the part of `region` in each loaded chunk, and returns the
+/// sum of the returned counts.
+fn for_each_part(
+    layer: &mut ChunkLayer,
+    region: Region,
+    mut f: impl FnMut(&mut LoadedChunk, &ChunkPart) -> usize,
+) -> usize {
+    let parts: Vec<_> = chunk_parts(layer, region).collect();
+
+    parts
+        .iter()
+        .filter_map(|part| Some(f(layer.chunk_mut(part.pos)?, part)))
+        .sum()
+}
+
+fn chunk_parts(layer: &ChunkLayer, region: Region) -> impl Iterator<Item = ChunkPart> {
+    let min_y = layer.min_y() as i64;
+
+    // The vertical range in the coordinates of the chunks.
+    let y_start = (region.min.y as i64 - min_y).max(0);
+    let y_end = (region.max.y as i64 - min_y + 1).min(layer.height() as i64);
+
+    let (chunk_min, chunk_max) = if region.is_empty() || y_start >= y_end {
+        // An empty range of chunks.
+        ((0, 0), (-1, -1))
+    } else {
+        (
+            (region.min.x.div_euclid(16), region.min.z.div_euclid(16)),
+            (region.max.x.div_euclid(16), region.max.z.div_euclid(16)),
+        )
+    };
+
+    let local = |min: i32, max: i32, chunk: i32| {
+        let start = min.max(chunk * 16) - chunk * 16;
+        let end = max.min(chunk * 16 + 15) - chunk * 16 + 1;
+
+        start as u32..end as u32
+    };
+
+    (chunk_min.1..=chunk_max.1).flat_map(move |cz| {
+        (chunk_min.0..=chunk_max.0).map(move |cx| ChunkPart {
+            pos: ChunkPos::new(cx, cz),
+            x: local(region.min.x, region.max.x, cx),
+            y: y_start as u32..y_end as u32,
+            z: local(region.min.z, region.max.z, cz),
+        })
+    })
+}
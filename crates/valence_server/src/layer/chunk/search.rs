@@ -0,0 +1,146 @@
+use valence_generated::block::BlockKind;
+use valence_protocol::{BlockState, Ident};
+use valence_registry::tags::TagsRegistry;
+
+/// A condition on block states, used to count and find blocks.
+///
+/// Chunk sections whose palette doesn't contain a matching state are skipped
+/// without looking at their blocks, so counting and finding rare blocks is
+/// fast.
+pub trait BlockPredicate {
+    fn matches(&self, state: BlockState) -> bool;
+}
+
+impl BlockPredicate for BlockState {
+    fn matches(&self, state: BlockState) -> bool {
+        *self == state
+    }
+}
+
+/// Matches every state of the block kind.
+impl BlockPredicate for BlockKind {
+    fn matches(&self, state: BlockState) -> bool {
+        state.to_kind() == *self
+    }
+}
+
+impl<F: Fn(BlockState) -> bool> BlockPredicate for F {
+    fn matches(&self, state: BlockState) -> bool {
+        self(state)
+    }
+}
+
+const KIND_WORDS: usize = BlockKind::ALL.len().div_ceil(64);
+
+/// A set of [`BlockKind`]s, like a block tag. Matches every state of the
+/// kinds in the set.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BlockKindSet {
+    bits: [u64; KIND_WORDS],
+}
+
+impl BlockKindSet {
+    pub fn new() -> Self {
+        Self {
+            bits: [0; KIND_WORDS],
+        }
+    }
+
+    /// Returns the set of the kinds in the block tag `tag` of the
+    /// [`TagsRegistry`], or `None` if there is no such tag.
+    pub fn from_tag(tags: &TagsRegistry, tag: &str) -> Option<Self> {
+        let tag = Ident::new(tag).ok()?;
+
+        let ids = tags.registries.get("minecraft:block")?.get(tag.as_str())?;
+
+        Some(
+            ids.iter()
+                .filter_map(|id| BlockKind::from_raw(id.0.try_into().ok()?))
+                .collect(),
+        )
+    }
+
+    /// Adds a kind to the set. Returns whether it wasn't in the set before.
+    pub fn insert(&mut self, kind: BlockKind) -> bool {
+        let (word, bit) = Self::index(kind);
+        let inserted = self.bits[word] & bit == 0;
+        self.bits[word] |= bit;
+        inserted
+    }
+
+    /// Removes a kind from the set. Returns whether it was in the set.
+    pub fn remove(&mut self, kind: BlockKind) -> bool {
+        let (word, bit) = Self::index(kind);
+        let removed = self.bits[word] & bit != 0;
+        self.bits[word] &= !bit;
+        removed
+    }
+
+    pub fn contains(&self, kind: BlockKind) -> bool {
+        let (word, bit) = Self::index(kind);
+        self.bits[word] & bit != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&w| w == 0)
+    }
+
+    fn index(kind: BlockKind) -> (usize, u64) {
+        let raw = kind.to_raw() as usize;
+        (raw / 64, 1 << (raw % 64))
+    }
+}
+
+impl Default for BlockKindSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromIterator<BlockKind> for BlockKindSet {
+    fn from_iter<I: IntoIterator<Item = BlockKind>>(iter: I) -> Self {
+        let mut set = Self::new();
+
+        for kind in iter {
+            set.insert(kind);
+        }
+
+        set
+    }
+}
+
+impl BlockPredicate for BlockKindSet {
+    fn matches(&self, state: BlockState) -> bool {
+        self.contains(state.to_kind())
+    }
+}
+
+impl BlockPredicate for &BlockKindSet {
+    fn matches(&self, state: BlockState) -> bool {
+        self.contains(state.to_kind())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_kind_set() {
+        let mut set: BlockKindSet = [BlockKind::Stone, BlockKind::OakLog].into_iter().collect();
+
+        assert_eq!(set.len(), 2);
+        assert!(set.matches(BlockState::STONE));
+        assert!(set.matches(BlockState::OAK_LOG));
+        assert!(!set.matches(BlockState::DIRT));
+
+        assert!(!set.insert(BlockKind::Stone));
+        assert!(set.remove(BlockKind::Stone));
+        assert!(!set.matches(BlockState::STONE));
+        assert!(BlockKindSet::new().is_empty());
+    }
+}
@@ -0,0 +1,451 @@
+//! Helpers for procedurally placing blocks.
+//!
+//! The functions in this module return the block positions of a shape, which
+//! can be placed with [`ChunkLayer::set_blocks`]. A [`Turtle`] places blocks
+//! along a path of relative movements.
+
+use std::collections::HashMap;
+
+use valence_protocol::{BlockPos, Direction};
+
+use super::{Block, ChunkLayer, IntoBlock};
+
+/// Returns the positions on a straight line from `from` to `to`, including
+/// both ends.
+pub fn line(from: BlockPos, to: BlockPos) -> impl Iterator<Item = BlockPos> {
+    let delta = [to.x - from.x, to.y - from.y, to.z - from.z];
+    let steps = delta.iter().map(|d| d.abs()).max().unwrap_or(0);
+
+    (0..=steps).map(move |i| {
+        let lerp = |start: i32, d: i32| {
+            if steps == 0 {
+                start
+            } else {
+                start + (d as f64 * i as f64 / steps as f64).round() as i32
+            }
+        };
+
+        BlockPos::new(
+            lerp(from.x, delta[0]),
+            lerp(from.y, delta[1]),
+            lerp(from.z, delta[2]),
+        )
+    })
+}
+
+/// Returns the positions of a horizontal circle around `center`. If `filled`
+/// is false, only the outline is returned.
+pub fn circle(center: BlockPos, radius: u32, filled: bool) -> impl Iterator<Item = BlockPos> {
+    let r = radius as i32;
+
+    square_offsets(r).filter_map(move |(x, z)| {
+        in_shape([x, 0, z], radius, filled, &HORIZONTAL_NEIGHBORS).then_some(center.offset(x, 0, z))
+    })
+}
+
+/// Returns the positions of a sphere around `center`. If `filled` is false,
+/// only the surface is returned.
+pub fn sphere(center: BlockPos, radius: u32, filled: bool) -> impl Iterator<Item = BlockPos> {
+    let r = radius as i32;
+
+    (-r..=r).flat_map(move |y| {
+        square_offsets(r).filter_map(move |(x, z)| {
+            in_shape([x, y, z], radius, filled, &ALL_NEIGHBORS).then_some(center.offset(x, y, z))
+        })
+    })
+}
+
+/// Returns the positions of a vertical cylinder standing on `base`. If
+/// `filled` is false, only the walls are returned.
+pub fn cylinder(
+    base: BlockPos,
+    radius: u32,
+    height: u32,
+    filled: bool,
+) -> impl Iterator<Item = BlockPos> {
+    (0..height as i32).flat_map(move |y| circle(base.offset(0, y, 0), radius, filled))
+}
+
+/// Returns the positions of the box with the corners `a` and `b`. If `filled`
+/// is false, only the faces are returned.
+pub fn cuboid(a: BlockPos, b: BlockPos, filled: bool) -> impl Iterator<Item = BlockPos> {
+    let min = BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+    (min.y..=max.y).flat_map(move |y| {
+        (min.z..=max.z).flat_map(move |z| {
+            (min.x..=max.x).filter_map(move |x| {
+                let on_face = x == min.x
+                    || x == max.x
+                    || y == min.y
+                    || y == max.y
+                    || z == min.z
+                    || z == max.z;
+
+                (filled || on_face).then_some(BlockPos::new(x, y, z))
+            })
+        })
+    })
+}
+
+/// Returns the positions of the pixels of `text` written with `font`.
+///
+/// `origin` is the top left corner of the first character. Characters are
+/// written in the direction `right` with rows going downward, and are
+/// separated by one column. Characters missing from the font are skipped.
+pub fn text(origin: BlockPos, right: Direction, text: &str, font: &BitmapFont) -> Vec<BlockPos> {
+    let mut positions = vec![];
+    let mut column = 0;
+
+    for c in text.chars() {
+        let Some(glyph) = font.glyph(c) else {
+            continue;
+        };
+
+        for (row, &bits) in glyph.iter().enumerate() {
+            for col in 0..font.width {
+                if bits >> (font.width - 1 - col) & 1 == 1 {
+                    let pos = step(origin, right, (column + col) as i32);
+                    positions.push(pos.offset(0, -(row as i32), 0));
+                }
+            }
+        }
+
+        column += font.width + 1;
+    }
+
+    positions
+}
+
+/// A monospace font where each character is a grid of pixels.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BitmapFont {
+    width: u32,
+    height: u32,
+    /// Maps characters to their rows, top first. The leftmost pixel of a row
+    /// is its most significant bit.
+    glyphs: HashMap<char, Vec<u32>>,
+}
+
+impl BitmapFont {
+    /// Creates a font without any glyphs, where each glyph is `width` pixels
+    /// wide and `height` pixels high.
+    pub fn new(width: u32, height: u32) -> Self {
+        assert!(width <= 32, "glyphs can be at most 32 pixels wide");
+
+        Self {
+            width,
+            height,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// A 3x5 pixel font with uppercase letters, digits, and some punctuation.
+    /// Lowercase letters are written as uppercase letters.
+    pub fn small() -> Self {
+        let mut font = Self::new(3, 5);
+
+        for (c, rows) in SMALL_FONT {
+            font.insert(c, rows.iter().map(|&row| row as u32));
+        }
+
+        font
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Sets the glyph of `c`. Rows are listed top first, with the leftmost
+    /// pixel in the most significant of the lowest [`width`](Self::width)
+    /// bits.
+    pub fn insert(&mut self, c: char, rows: impl IntoIterator<Item = u32>) {
+        let mut rows: Vec<_> = rows.into_iter().collect();
+        rows.resize(self.height as usize, 0);
+        self.glyphs.insert(c, rows);
+    }
+
+    /// Returns the rows of the glyph of `c`, falling back to the uppercase
+    /// glyph.
+    pub fn glyph(&self, c: char) -> Option<&[u32]> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&c.to_ascii_uppercase()))
+            .map(|rows| rows.as_slice())
+    }
+}
+
+/// Places blocks while moving around, like a pen on paper.
+///
+/// The turtle starts facing `facing` with its pen down, so every position it
+/// moves over is set to its block.
+pub struct Turtle<'a> {
+    layer: &'a mut ChunkLayer,
+    position: BlockPos,
+    facing: Direction,
+    block: Block,
+    pen_down: bool,
+}
+
+impl<'a> Turtle<'a> {
+    pub fn new(
+        layer: &'a mut ChunkLayer,
+        position: impl Into<BlockPos>,
+        facing: Direction,
+        block: impl IntoBlock,
+    ) -> Self {
+        Self {
+            layer,
+            position: position.into(),
+            facing,
+            block: block.into_block(),
+            pen_down: true,
+        }
+    }
+
+    pub fn position(&self) -> BlockPos {
+        self.position
+    }
+
+    pub fn facing(&self) -> Direction {
+        self.facing
+    }
+
+    /// Sets the block placed from now on.
+    pub fn block(&mut self, block: impl IntoBlock) -> &mut Self {
+        self.block = block.into_block();
+        self
+    }
+
+    /// Stops placing blocks while moving.
+    pub fn pen_up(&mut self) -> &mut Self {
+        self.pen_down = false;
+        self
+    }
+
+    /// Starts placing blocks while moving, including at the current position.
+    pub fn pen_down(&mut self) -> &mut Self {
+        self.pen_down = true;
+        self.place()
+    }
+
+    /// Places the current block at the current position, regardless of the
+    /// pen.
+    pub fn place(&mut self) -> &mut Self {
+        self.layer.set_block(self.position, self.block.clone());
+        self
+    }
+
+    /// Moves `distance` blocks in the direction the turtle is facing.
+    pub fn forward(&mut self, distance: u32) -> &mut Self {
+        self.walk(self.facing, distance)
+    }
+
+    /// Moves `distance` blocks opposite to the direction the turtle is facing.
+    pub fn back(&mut self, distance: u32) -> &mut Self {
+        self.walk(opposite(self.facing), distance)
+    }
+
+    pub fn up(&mut self, distance: u32) -> &mut Self {
+        self.walk(Direction::Up, distance)
+    }
+
+    pub fn down(&mut self, distance: u32) -> &mut Self {
+        self.walk(Direction::Down, distance)
+    }
+
+    /// Turns 90 degrees counterclockwise when seen from above.
+    pub fn turn_left(&mut self) -> &mut Self {
+        self.facing = match self.facing {
+            Direction::North => Direction::West,
+            Direction::West => Direction::South,
+            Direction::South => Direction::East,
+            Direction::East => Direction::North,
+            vertical => vertical,
+        };
+        self
+    }
+
+    /// Turns 90 degrees clockwise when seen from above.
+    pub fn turn_right(&mut self) -> &mut Self {
+        self.turn_left().turn_left().turn_left()
+    }
+
+    pub fn face(&mut self, facing: Direction) -> &mut Self {
+        self.facing = facing;
+        self
+    }
+
+    /// Moves to `position` in a straight line.
+    pub fn goto(&mut self, position: impl Into<BlockPos>) -> &mut Self {
+        let to = position.into();
+
+        if self.pen_down {
+            self.layer
+                .set_blocks(line(self.position, to), self.block.clone());
+        }
+
+        self.position = to;
+        self
+    }
+
+    fn walk(&mut self, dir: Direction, distance: u32) -> &mut Self {
+        let to = step(self.position, dir, distance as i32);
+        self.goto(to)
+    }
+}
+
+fn step(pos: BlockPos, dir: Direction, distance: i32) -> BlockPos {
+    match dir {
+        Direction::Down => pos.offset(0, -distance, 0),
+        Direction::Up => pos.offset(0, distance, 0),
+        Direction::North => pos.offset(0, 0, -distance),
+        Direction::South => pos.offset(0, 0, distance),
+        Direction::West => pos.offset(-distance, 0, 0),
+        Direction::East => pos.offset(distance, 0, 0),
+    }
+}
+
+fn opposite(dir: Direction) -> Direction {
+    match dir {
+        Direction::Down => Direction::Up,
+        Direction::Up => Direction::Down,
+        Direction::North => Direction::South,
+        Direction::South => Direction::North,
+        Direction::West => Direction::East,
+        Direction::East => Direction::West,
+    }
+}
+
+fn square_offsets(r: i32) -> impl Iterator<Item = (i32, i32)> {
+    (-r..=r).flat_map(move |z| (-r..=r).map(move |x| (x, z)))
+}
+
+const HORIZONTAL_NEIGHBORS: [[i32; 3]; 4] = [[1, 0, 0], [-1, 0, 0], [0, 0, 1], [0, 0, -1]];
+
+const ALL_NEIGHBORS: [[i32; 3]; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+
+/// Returns whether `offset` from the center is part of a round shape with the
+/// given radius. If `filled` is false, only offsets with a neighbor outside of
+/// the shape are included.
+fn in_shape(offset: [i32; 3], radius: u32, filled: bool, neighbors: &[[i32; 3]]) -> bool {
+    // Rounding the radius up by half a block avoids single-block bumps.
+    let r = radius as f64 + 0.5;
+    let radius_squared = (r * r) as i64;
+
+    let inside = |[x, y, z]: [i32; 3]| (x * x + y * y + z * z) as i64 <= radius_squared;
+
+    if !inside(offset) {
+        return false;
+    }
+
+    filled
+        || neighbors
+            .iter()
+            .any(|n| !inside([offset[0] + n[0], offset[1] + n[1], offset[2] + n[2]]))
+}
+
+#[rustfmt::skip]
+const SMALL_FONT: [(char, [u8; 5]); 42] = [
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b110, 0b001, 0b010, 0b100, 0b111]),
+    ('3', [0b110, 0b001, 0b010, 0b001, 0b110]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b110, 0b001, 0b110]),
+    ('6', [0b011, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b110]),
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('?', [0b110, 0b001, 0b010, 0b000, 0b010]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_endpoints() {
+        let from = BlockPos::new(0, 0, 0);
+        let to = BlockPos::new(5, 2, -3);
+        let points: Vec<_> = line(from, to).collect();
+
+        assert_eq!(points.len(), 6);
+        assert_eq!(points.first(), Some(&from));
+        assert_eq!(points.last(), Some(&to));
+        assert_eq!(line(from, from).count(), 1);
+    }
+
+    #[test]
+    fn hollow_shapes() {
+        let origin = BlockPos::new(0, 0, 0);
+
+        assert_eq!(cuboid(origin, origin.offset(2, 2, 2), true).count(), 27);
+        assert_eq!(cuboid(origin, origin.offset(2, 2, 2), false).count(), 26);
+
+        let filled = circle(origin, 5, true).count();
+        let outline = circle(origin, 5, false).count();
+        assert!(outline < filled);
+        assert!(circle(origin, 5, false).all(|p| p.y == 0));
+
+        assert!(sphere(origin, 4, false).count() < sphere(origin, 4, true).count());
+    }
+
+    #[test]
+    fn bitmap_text() {
+        let font = BitmapFont::small();
+        let origin = BlockPos::new(0, 10, 0);
+
+        // 'I' has 3 + 1 + 1 + 1 + 3 pixels.
+        let pixels = text(origin, Direction::East, "i", &font);
+        assert_eq!(pixels.len(), 9);
+        assert!(pixels.contains(&origin));
+        assert!(pixels.contains(&BlockPos::new(1, 6, 0)));
+
+        // The second character starts after a column of spacing.
+        let pixels = text(origin, Direction::East, " I", &font);
+        assert!(pixels.contains(&BlockPos::new(4, 10, 0)));
+    }
+}
@@ -0,0 +1,439 @@
+//! A compact binary encoding of chunks, for storing worlds somewhere other
+//! than Anvil files.
+//!
+//! [`UnloadedChunk::to_bytes`] encodes the blocks, biomes and block entities
+//! of a chunk, and [`UnloadedChunk::from_bytes`] decodes them again. Unlike
+//! the Anvil format, the palettes of the chunk sections are kept as they are,
+//! so encoding and decoding doesn't need to rebuild them. With the `zstd`
+//! feature enabled, [`UnloadedChunk::to_compressed_bytes`] also compresses the
+//! encoded chunk.
+//!
+//! The [`ChunkStorage`] trait is implemented by anything which can save and
+//! load chunks, like a database or an object store. [`MemoryChunkStorage`] is
+//! a simple implementation which keeps the encoded chunks in memory.
+//!
+//! # Format
+//!
+//! The encoding starts with the bytes `VCHK`, a version byte, and a flags
+//! byte. If bit 0 of the flags is set, the rest of the data is compressed
+//! with zstd. The rest contains the number of sections, the block states and
+//! biomes of each section, and the block entities of the chunk. Block states
+//! are stored by their raw ID and biomes by their index in the
+//! [`BiomeRegistry`], so chunks should be loaded with the same biome registry
+//! they were saved with.
+//!
+//! [`BiomeRegistry`]: valence_registry::biome::BiomeRegistry
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_server::layer::chunk::storage::{ChunkStorage, MemoryChunkStorage};
+//! use valence_server::layer::chunk::{Chunk, UnloadedChunk};
+//! use valence_server::{BlockState, ChunkPos};
+//!
+//! let mut chunk = UnloadedChunk::with_height(64);
+//! chunk.set_block_state(3, 10, 7, BlockState::STONE);
+//!
+//! let bytes = chunk.to_bytes();
+//! let decoded = UnloadedChunk::from_bytes(&bytes).unwrap();
+//! assert_eq!(decoded.block_state(3, 10, 7), BlockState::STONE);
+//!
+//! let mut storage = MemoryChunkStorage::new();
+//! storage.save(ChunkPos::new(0, 0), &chunk).unwrap();
+//!
+//! let loaded = storage.load(ChunkPos::new(0, 0)).unwrap().unwrap();
+//! assert_eq!(loaded.height(), 64);
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+use valence_protocol::{BlockState, ChunkPos};
+use valence_registry::biome::BiomeId;
+use valence_registry::RegistryIdx;
+
+use super::chunk::{BiomeContainer, BlockStateContainer, MAX_HEIGHT, SECTION_BLOCK_COUNT};
+use super::{unloaded, LoadedChunk, UnloadedChunk};
+
+const MAGIC: [u8; 4] = *b"VCHK";
+/// The version of the format written by this module.
+const VERSION: u8 = 1;
+const FLAG_ZSTD: u8 = 0b1;
+
+/// An error while decoding a chunk with [`UnloadedChunk::from_bytes`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ChunkDecodeError {
+    #[error("data is not an encoded chunk")]
+    MissingMagic,
+    #[error("unsupported chunk format version {0}")]
+    UnsupportedVersion(u8),
+    #[error("chunk is compressed, but the `zstd` feature is not enabled")]
+    CompressionUnsupported,
+    #[error("failed to decompress chunk: {0}")]
+    Decompress(#[from] io::Error),
+    #[error("unexpected end of chunk data")]
+    UnexpectedEof,
+    #[error("invalid block state or biome {0}")]
+    InvalidValue(u32),
+    #[error("invalid block entity: {0}")]
+    BlockEntity(#[from] valence_nbt::binary::Error),
+    #[error("malformed chunk data: {0}")]
+    Malformed(&'static str),
+}
+
+impl UnloadedChunk {
+    /// Encodes this chunk without compression. See the [module
+    /// documentation](self) for details on the format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+        buf.push(VERSION);
+        buf.push(0);
+        self.write_body(&mut buf);
+        buf
+    }
+
+    /// Encodes this chunk and compresses it with zstd at the given
+    /// compression `level`. Level 0 uses zstd's default level.
+    #[cfg(feature = "zstd")]
+    pub fn to_compressed_bytes(&self, level: i32) -> Vec<u8> {
+        let mut body = vec![];
+        self.write_body(&mut body);
+
+        let mut buf = MAGIC.to_vec();
+        buf.push(VERSION);
+        buf.push(FLAG_ZSTD);
+        // Compressing to memory can only fail if the level is invalid, which
+        // zstd clamps instead.
+        buf.extend(zstd::bulk::compress(&body, level).expect("failed to compress chunk"));
+        buf
+    }
+
+    /// Decodes a chunk encoded with [`to_bytes`] or [`to_compressed_bytes`].
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    /// [`to_compressed_bytes`]: Self::to_compressed_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ChunkDecodeError> {
+        let Some(rest) = bytes.strip_prefix(&MAGIC) else {
+            return Err(ChunkDecodeError::MissingMagic);
+        };
+
+        let mut r = rest;
+
+        let version = read_u8(&mut r)?;
+        if version != VERSION {
+            return Err(ChunkDecodeError::UnsupportedVersion(version));
+        }
+
+        let flags = read_u8(&mut r)?;
+
+        if flags & !FLAG_ZSTD != 0 {
+            return Err(ChunkDecodeError::Malformed("unknown flags"));
+        }
+
+        if flags & FLAG_ZSTD != 0 {
+            #[cfg(feature = "zstd")]
+            {
+                let body = zstd::stream::decode_all(r)?;
+                return Self::read_body(&mut body.as_slice());
+            }
+
+            #[cfg(not(feature = "zstd"))]
+            return Err(ChunkDecodeError::CompressionUnsupported);
+        }
+
+        Self::read_body(&mut r)
+    }
+
+    fn write_body(&self, buf: &mut Vec<u8>) {
+        write_var_u32(buf, self.sections.len() as u32);
+
+        for sect in &self.sections {
+            sect.block_states
+                .write_compact(buf, |state| state.to_raw().into());
+            sect.biomes
+                .write_compact(buf, |biome| biome.to_index() as u32);
+        }
+
+        write_var_u32(buf, self.block_entities.len() as u32);
+
+        for (&idx, nbt) in &self.block_entities {
+            write_var_u32(buf, idx);
+            valence_nbt::to_binary(nbt, &mut *buf, "").expect("failed to encode block entity");
+        }
+    }
+
+    fn read_body(r: &mut &[u8]) -> Result<Self, ChunkDecodeError> {
+        let section_count = read_var_u32(r)?;
+
+        if section_count > MAX_HEIGHT / 16 {
+            return Err(ChunkDecodeError::Malformed("too many sections"));
+        }
+
+        let mut sections = Vec::with_capacity(section_count as usize);
+
+        for _ in 0..section_count {
+            let block_states = BlockStateContainer::read_compact(r, |raw| {
+                BlockState::from_raw(raw.try_into().ok()?)
+            })?;
+            let biomes =
+                BiomeContainer::read_compact(r, |idx| Some(BiomeId::from_index(idx as usize)))?;
+
+            sections.push(unloaded::Section {
+                block_states,
+                biomes,
+            });
+        }
+
+        let block_entity_count = read_var_u32(r)?;
+        let block_count = section_count * SECTION_BLOCK_COUNT as u32;
+        let mut block_entities = BTreeMap::new();
+
+        for _ in 0..block_entity_count {
+            let idx = read_var_u32(r)?;
+
+            if idx >= block_count {
+                return Err(ChunkDecodeError::Malformed(
+                    "block entity index out of bounds",
+                ));
+            }
+
+            let (nbt, _) = valence_nbt::from_binary::<String>(r)?;
+            block_entities.insert(idx, nbt);
+        }
+
+        if !r.is_empty() {
+            return Err(ChunkDecodeError::Malformed("trailing data"));
+        }
+
+        Ok(Self {
+            sections,
+            block_entities,
+        })
+    }
+}
+
+impl LoadedChunk {
+    /// Encodes the blocks, biomes and block entities of this chunk. See
+    /// [`UnloadedChunk::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_unloaded().to_bytes()
+    }
+
+    /// Encodes and compresses this chunk. See
+    /// [`UnloadedChunk::to_compressed_bytes`].
+    #[cfg(feature = "zstd")]
+    pub fn to_compressed_bytes(&self, level: i32) -> Vec<u8> {
+        self.to_unloaded().to_compressed_bytes(level)
+    }
+}
+
+/// A place where chunks are saved and loaded from, like a database.
+///
+/// Implementations usually store the output of [`UnloadedChunk::to_bytes`]
+/// under a key made from the chunk position. Every layer should have its own
+/// storage, or a storage which includes the layer in its keys.
+pub trait ChunkStorage {
+    type Error;
+
+    /// Loads the chunk at `pos`, or returns `None` if it hasn't been saved.
+    fn load(&mut self, pos: ChunkPos) -> Result<Option<UnloadedChunk>, Self::Error>;
+
+    /// Saves `chunk` at `pos`, replacing the chunk saved there before.
+    fn save(&mut self, pos: ChunkPos, chunk: &UnloadedChunk) -> Result<(), Self::Error>;
+
+    /// Removes the chunk at `pos`. Returns whether a chunk was saved there.
+    fn remove(&mut self, pos: ChunkPos) -> Result<bool, Self::Error>;
+}
+
+/// A [`ChunkStorage`] which keeps encoded chunks in memory.
+#[derive(Clone, Default, Debug)]
+pub struct MemoryChunkStorage {
+    chunks: FxHashMap<ChunkPos, Vec<u8>>,
+}
+
+impl MemoryChunkStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the encoded chunk at `pos`.
+    pub fn bytes(&self, pos: ChunkPos) -> Option<&[u8]> {
+        self.chunks.get(&pos).map(Vec::as_slice)
+    }
+
+    /// The number of saved chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+impl ChunkStorage for MemoryChunkStorage {
+    type Error = ChunkDecodeError;
+
+    fn load(&mut self, pos: ChunkPos) -> Result<Option<UnloadedChunk>, Self::Error> {
+        self.chunks
+            .get(&pos)
+            .map(|bytes| UnloadedChunk::from_bytes(bytes))
+            .transpose()
+    }
+
+    fn save(&mut self, pos: ChunkPos, chunk: &UnloadedChunk) -> Result<(), Self::Error> {
+        self.chunks.insert(pos, chunk.to_bytes());
+        Ok(())
+    }
+
+    fn remove(&mut self, pos: ChunkPos) -> Result<bool, Self::Error> {
+        Ok(self.chunks.remove(&pos).is_some())
+    }
+}
+
+pub(super) fn write_var_u32(buf: &mut Vec<u8>, mut val: u32) {
+    loop {
+        let byte = (val & 0x7f) as u8;
+        val >>= 7;
+
+        if val == 0 {
+            buf.push(byte);
+            return;
+        }
+
+        buf.push(byte | 0x80);
+    }
+}
+
+pub(super) fn read_var_u32(r: &mut &[u8]) -> Result<u32, ChunkDecodeError> {
+    let mut val = 0_u32;
+
+    for i in 0..5 {
+        let byte = read_u8(r)?;
+        val |= u32::from(byte & 0x7f) << (i * 7);
+
+        if byte & 0x80 == 0 {
+            return Ok(val);
+        }
+    }
+
+    Err(ChunkDecodeError::Malformed(
+        "variable-length integer is too long",
+    ))
+}
+
+pub(super) fn read_u8(r: &mut &[u8]) -> Result<u8, ChunkDecodeError> {
+    let (&byte, rest) = r.split_first().ok_or(ChunkDecodeError::UnexpectedEof)?;
+    *r = rest;
+    Ok(byte)
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_nbt::compound;
+    use valence_protocol::BlockState;
+
+    use super::*;
+    use crate::layer::chunk::Chunk;
+
+    fn test_chunk() -> UnloadedChunk {
+        let mut chunk = UnloadedChunk::with_height(64);
+
+        // A single-valued section, an indirect section and a direct section.
+        chunk.fill_block_state_section(0, BlockState::STONE);
+
+        for i in 0..16 {
+            chunk.set_block_state(i, 20, 3, BlockState::from_raw(i as u16 + 1).unwrap());
+        }
+
+        for i in 0..4096 {
+            chunk.set_block_state(
+                i % 16,
+                32 + i / 256,
+                i / 16 % 16,
+                BlockState::from_raw(i as u16).unwrap(),
+            );
+        }
+
+        chunk.set_biome(1, 2, 3, BiomeId::from_index(5));
+        chunk.set_block_entity(4, 50, 6, Some(compound! { "Text" => "hello" }));
+
+        chunk
+    }
+
+    fn assert_same(a: &UnloadedChunk, b: &UnloadedChunk) {
+        assert_eq!(a.height(), b.height());
+
+        for y in 0..a.height() {
+            for z in 0..16 {
+                for x in 0..16 {
+                    assert_eq!(a.block(x, y, z), b.block(x, y, z));
+                }
+            }
+        }
+
+        for y in 0..a.height() / 4 {
+            for z in 0..4 {
+                for x in 0..4 {
+                    assert_eq!(a.biome(x, y, z), b.biome(x, y, z));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_round_trip() {
+        let chunk = test_chunk();
+        let bytes = chunk.to_bytes();
+
+        assert_same(&chunk, &UnloadedChunk::from_bytes(&bytes).unwrap());
+
+        // Every truncation of the data is rejected instead of panicking.
+        for len in 0..bytes.len() {
+            assert!(UnloadedChunk::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compressed_round_trip() {
+        let chunk = test_chunk();
+        let bytes = chunk.to_compressed_bytes(0);
+
+        assert!(bytes.len() < chunk.to_bytes().len());
+        assert_same(&chunk, &UnloadedChunk::from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn invalid_data() {
+        assert!(matches!(
+            UnloadedChunk::from_bytes(b"nope"),
+            Err(ChunkDecodeError::MissingMagic)
+        ));
+
+        let mut bytes = UnloadedChunk::with_height(16).to_bytes();
+        bytes[4] = 99;
+        assert!(matches!(
+            UnloadedChunk::from_bytes(&bytes),
+            Err(ChunkDecodeError::UnsupportedVersion(99))
+        ));
+    }
+
+    #[test]
+    fn memory_storage() {
+        let mut storage = MemoryChunkStorage::new();
+        let pos = ChunkPos::new(3, -2);
+
+        assert!(storage.load(pos).unwrap().is_none());
+
+        storage.save(pos, &test_chunk()).unwrap();
+        assert_same(&storage.load(pos).unwrap().unwrap(), &test_chunk());
+
+        assert!(storage.remove(pos).unwrap());
+        assert!(!storage.remove(pos).unwrap());
+        assert!(storage.is_empty());
+    }
+}
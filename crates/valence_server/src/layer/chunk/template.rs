@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+use valence_protocol::ChunkPos;
+
+use super::{Chunk, ChunkLayer, UnloadedChunk};
+
+/// A read-only set of chunks which can be instanced into any number of
+/// [`ChunkLayer`]s, such as one arena map used by many concurrent matches.
+///
+/// Instances share the block and biome data of the template. A chunk section
+/// is only copied when an instance modifies it, so the memory used by an
+/// instance grows with the changes made to it rather than the size of the
+/// template.
+///
+/// Cloning a template is cheap.
+#[derive(Clone, Default, Debug)]
+pub struct ChunkTemplate {
+    chunks: Arc<FxHashMap<ChunkPos, UnloadedChunk>>,
+}
+
+impl ChunkTemplate {
+    /// Creates a template from a set of chunks.
+    pub fn new(chunks: impl IntoIterator<Item = (ChunkPos, UnloadedChunk)>) -> Self {
+        let chunks = chunks
+            .into_iter()
+            .map(|(pos, mut chunk)| {
+                // Compact the data once here instead of in every instance.
+                chunk.shrink_to_fit();
+                (pos, chunk)
+            })
+            .collect();
+
+        Self {
+            chunks: Arc::new(chunks),
+        }
+    }
+
+    /// Creates a template from the chunks currently loaded in `layer`.
+    pub fn from_layer(layer: &ChunkLayer) -> Self {
+        Self::new(
+            layer
+                .chunks()
+                .map(|(pos, chunk)| (pos, chunk.to_unloaded())),
+        )
+    }
+
+    /// Returns the chunk at the given position, if the template has one.
+    pub fn chunk(&self, pos: impl Into<ChunkPos>) -> Option<&UnloadedChunk> {
+        self.chunks.get(&pos.into())
+    }
+
+    /// Returns an iterator over the chunks in the template. The order of the
+    /// chunks is undefined.
+    pub fn chunks(&self) -> impl Iterator<Item = (ChunkPos, &UnloadedChunk)> + Clone + '_ {
+        self.chunks.iter().map(|(pos, chunk)| (*pos, chunk))
+    }
+
+    /// Returns the number of chunks in the template.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::BlockState;
+
+    use super::*;
+
+    #[test]
+    fn instances_copy_on_write() {
+        let mut chunk = UnloadedChunk::with_height(32);
+        chunk.set_block_state(0, 0, 0, BlockState::STONE);
+        chunk.set_block_state(0, 16, 0, BlockState::DIRT);
+
+        let template = ChunkTemplate::new([(ChunkPos::new(0, 0), chunk)]);
+
+        let mut instance = template.chunk([0, 0]).unwrap().clone();
+        assert!(instance.sections[0].block_states.is_shared());
+        assert!(instance.sections[1].block_states.is_shared());
+
+        instance.set_block_state(1, 0, 0, BlockState::GLASS);
+
+        // Only the modified section was copied.
+        assert!(!instance.sections[0].block_states.is_shared());
+        assert!(instance.sections[1].block_states.is_shared());
+
+        let original = template.chunk([0, 0]).unwrap();
+        assert_eq!(original.block_state(1, 0, 0), BlockState::AIR);
+        assert_eq!(instance.block_state(1, 0, 0), BlockState::GLASS);
+        assert_eq!(instance.block_state(0, 16, 0), BlockState::DIRT);
+    }
+}
@@ -6,9 +6,10 @@ use valence_protocol::BlockState;
 use valence_registry::biome::BiomeId;
 
 use super::chunk::{
-    check_biome_oob, check_block_oob, check_section_oob, BiomeContainer, BlockStateContainer,
-    Chunk, MAX_HEIGHT, SECTION_BLOCK_COUNT,
+    check_biome_oob, check_block_oob, check_section_oob, section_block_offsets, BiomeContainer,
+    BlockStateContainer, Chunk, MAX_HEIGHT, SECTION_BLOCK_COUNT,
 };
+use super::search::BlockPredicate;
 
 #[derive(Clone, Default, Debug)]
 pub struct UnloadedChunk {
@@ -149,6 +150,42 @@ impl Chunk for UnloadedChunk {
         self.sections[sect_y as usize].biomes.fill(biome);
     }
 
+    /// Sections without a matching state in their palette are skipped.
+    fn count_blocks(&self, pred: impl BlockPredicate) -> u32 {
+        self.sections
+            .iter()
+            .map(|sect| {
+                sect.block_states
+                    .count_matching(|state| pred.matches(state)) as u32
+            })
+            .sum()
+    }
+
+    /// Sections without a matching state in their palette are skipped.
+    fn contains_any(&self, pred: impl BlockPredicate) -> bool {
+        self.sections
+            .iter()
+            .any(|sect| sect.block_states.any_matching(|state| pred.matches(state)))
+    }
+
+    /// Sections containing only air are skipped.
+    fn iter_non_air(&self) -> Box<dyn Iterator<Item = [u32; 3]> + '_> {
+        Box::new(self.sections.iter().enumerate().flat_map(|(sect_y, sect)| {
+            sect.block_states
+                .indices_matching(|state| !state.is_air())
+                .map(move |idx| section_block_offsets(sect_y, idx))
+        }))
+    }
+
+    /// Unless a section has too many distinct states to be paletted, `f` is
+    /// only called once per state in the section's palette instead of once
+    /// per block.
+    fn map_states(&mut self, mut f: impl FnMut(BlockState) -> BlockState) {
+        for sect in &mut self.sections {
+            sect.block_states.map(&mut f);
+        }
+    }
+
     fn shrink_to_fit(&mut self) {
         for sect in &mut self.sections {
             sect.block_states.shrink_to_fit();
@@ -0,0 +1,264 @@
+//! Sending tall chunks to clients in parts.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use bevy_ecs::prelude::*;
+use valence_entity::Position;
+use valence_protocol::{BlockState, ChunkPos};
+
+use super::chunk::BlockStateContainer;
+use super::loaded::LoadedChunk;
+use super::paletted_container::PalettedContainer;
+use super::ChunkLayer;
+use crate::client::{Client, ViewDistance, VisibleChunkLayer};
+use crate::ChunkView;
+
+/// Settings for sending only the sections of chunks near a client's height in
+/// full.
+///
+/// In tall worlds, clients usually only see a part of each chunk column. When
+/// a chunk is sent to a client, the sections within [`radius`](Self::radius)
+/// sections of the client's section are sent in full, and the others are sent
+/// with less detail according to [`FarSections`]. Once the client comes
+/// within one section of the edge of the sections sent in full, the whole
+/// chunk is sent again, nearest chunks first.
+///
+/// Changes to blocks in sections which weren't sent in full are still sent to
+/// clients, so they may briefly see single blocks floating in the air.
+///
+/// Enable vertical streaming for a layer with
+/// [`ChunkLayer::set_vertical_streaming`](super::ChunkLayer::set_vertical_streaming).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VerticalStreaming {
+    radius: u32,
+    far_sections: FarSections,
+    upgrades_per_tick: u32,
+}
+
+/// How the sections far above or below a client are sent.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FarSections {
+    /// Far sections are sent as air.
+    Empty,
+    /// Every far section is sent filled with the most common of a few blocks
+    /// sampled from it, so the rough shape of the terrain is visible.
+    Uniform,
+}
+
+impl Default for VerticalStreaming {
+    fn default() -> Self {
+        Self {
+            radius: 4,
+            far_sections: FarSections::Uniform,
+            upgrades_per_tick: 8,
+        }
+    }
+}
+
+impl VerticalStreaming {
+    /// Creates settings which send four sections above and below clients in
+    /// full, and the others as [`FarSections::Uniform`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_radius(mut self, radius: u32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn with_far_sections(mut self, far_sections: FarSections) -> Self {
+        self.far_sections = far_sections;
+        self
+    }
+
+    /// Sets the number of chunks sent again in full to each client per tick.
+    pub fn with_upgrades_per_tick(mut self, upgrades_per_tick: u32) -> Self {
+        self.upgrades_per_tick = upgrades_per_tick;
+        self
+    }
+
+    /// The number of sections above and below the client's section which are
+    /// sent in full.
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    pub fn far_sections(&self) -> FarSections {
+        self.far_sections
+    }
+
+    pub fn upgrades_per_tick(&self) -> u32 {
+        self.upgrades_per_tick
+    }
+
+    /// Returns the sections sent in full to a client in the section `sect_y`
+    /// of a chunk with `sect_count` sections.
+    fn full_sections(&self, sect_y: u32, sect_count: u32) -> Range<u32> {
+        sect_y.saturating_sub(self.radius)..(sect_y + self.radius + 1).min(sect_count)
+    }
+}
+
+/// Returns the block a far section is replaced with.
+pub(super) fn far_section_block(
+    far_sections: FarSections,
+    states: &BlockStateContainer,
+) -> BlockState {
+    match far_sections {
+        FarSections::Empty => BlockState::AIR,
+        FarSections::Uniform => {
+            if let PalettedContainer::Single(state) = states {
+                return *state;
+            }
+
+            let mut counts: Vec<(BlockState, u32)> = vec![];
+
+            // Sample every fourth block along each axis.
+            for y in (0..16).step_by(4) {
+                for z in (0..16).step_by(4) {
+                    for x in (0..16).step_by(4) {
+                        let state = states.get(x + z * 16 + y * 16 * 16);
+
+                        match counts.iter_mut().find(|(s, _)| *s == state) {
+                            Some((_, count)) => *count += 1,
+                            None => counts.push((state, 1)),
+                        }
+                    }
+                }
+            }
+
+            counts
+                .into_iter()
+                .max_by_key(|&(_, count)| count)
+                .map_or(BlockState::AIR, |(state, _)| state)
+        }
+    }
+}
+
+/// The chunks of a client which were sent without all their sections in
+/// full, because of [`VerticalStreaming`].
+#[derive(Component, Default, Debug)]
+pub struct PartialChunks {
+    full_sections: HashMap<ChunkPos, Range<u32>>,
+}
+
+impl PartialChunks {
+    /// Returns whether the chunk at `pos` was sent in part.
+    pub fn contains(&self, pos: ChunkPos) -> bool {
+        self.full_sections.contains_key(&pos)
+    }
+
+    /// The number of chunks which were sent in part.
+    pub fn len(&self) -> usize {
+        self.full_sections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.full_sections.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.full_sections.clear();
+    }
+
+    /// Writes the initialization packets of `chunk` to `client`, leaving out
+    /// the sections far from `client_y` if the layer has vertical streaming.
+    pub(crate) fn write_init_packets(
+        &mut self,
+        client: &mut Client,
+        layer: &ChunkLayer,
+        chunk: &LoadedChunk,
+        pos: ChunkPos,
+        client_y: f64,
+    ) {
+        self.full_sections.remove(&pos);
+
+        if let Some(streaming) = &layer.info.vertical_streaming {
+            let sect_count = layer.height() / 16;
+            let full = streaming.full_sections(section_y(layer, client_y), sect_count);
+
+            if full != (0..sect_count) {
+                chunk.write_partial_init_packets(
+                    client,
+                    pos,
+                    &layer.info,
+                    full.clone(),
+                    streaming.far_sections,
+                );
+                self.full_sections.insert(pos, full);
+                return;
+            }
+        }
+
+        chunk.write_init_packets(client, pos, &layer.info);
+    }
+}
+
+fn section_y(layer: &ChunkLayer, y: f64) -> u32 {
+    let sect_count = (layer.height() / 16) as i32;
+    ((y.floor() as i32 - layer.min_y()).div_euclid(16)).clamp(0, sect_count - 1) as u32
+}
+
+/// Sends chunks in full to clients which get close to the parts which weren't
+/// sent in full.
+pub(crate) fn upgrade_partial_chunks(
+    mut clients: Query<(
+        &mut Client,
+        &mut PartialChunks,
+        &VisibleChunkLayer,
+        &Position,
+        &ViewDistance,
+    )>,
+    layers: Query<&ChunkLayer>,
+) {
+    for (mut client, mut partial, chunk_layer, pos, view_dist) in &mut clients {
+        if partial.is_empty() {
+            continue;
+        }
+
+        let Ok(layer) = layers.get(chunk_layer.0) else {
+            partial.clear();
+            continue;
+        };
+
+        let view = ChunkView::new(ChunkPos::from(pos.0), view_dist.get());
+
+        // Chunks which were unloaded don't need to be sent again.
+        partial
+            .full_sections
+            .retain(|&pos, _| view.contains(pos) && layer.chunk(pos).is_some());
+
+        let upgrades_per_tick = layer
+            .vertical_streaming()
+            .map_or(u32::MAX, |streaming| streaming.upgrades_per_tick);
+
+        let sect_y = section_y(layer, pos.0.y);
+        let near = sect_y.saturating_sub(1)..(sect_y + 2).min(layer.height() / 16);
+
+        let mut upgrades: Vec<_> = partial
+            .full_sections
+            .iter()
+            .filter(|(_, full)| {
+                // Without vertical streaming, all chunks are sent in full.
+                layer.vertical_streaming().is_none()
+                    || !(full.start <= near.start && near.end <= full.end)
+            })
+            .map(|(&pos, _)| pos)
+            .collect();
+
+        upgrades.sort_unstable_by_key(|chunk_pos| {
+            let dx = chunk_pos.x - view.pos.x;
+            let dz = chunk_pos.z - view.pos.z;
+            dx * dx + dz * dz
+        });
+
+        for chunk_pos in upgrades.into_iter().take(upgrades_per_tick as usize) {
+            if let Some(chunk) = layer.chunk(chunk_pos) {
+                chunk.write_init_packets(&mut *client, chunk_pos, layer.info());
+            }
+
+            partial.full_sections.remove(&chunk_pos);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Cloning layers, for giving every match of a minigame its own copy of a
+//! world.
+//!
+//! The [`CloneLayer`] command copies the chunks and dimension of a layer
+//! entity into another entity, and optionally the entities on the layer. The
+//! chunk data of the copy is shared with the original until either of them
+//! modifies it, so cloning a large layer is cheap.
+//!
+//! Bevy can't clone arbitrary components, so only the components registered
+//! in the [`CloneComponents`] resource are copied, both from the layer entity
+//! and from the entities on the layer.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::layer::clone::CloneLayer;
+//!
+//! /// Starts a match in a copy of the arena layer.
+//! fn start_match(mut commands: Commands, arena: Entity) -> Entity {
+//!     let layer = commands.spawn_empty().id();
+//!     commands.add(CloneLayer::new(arena, layer).with_entities());
+//!     layer
+//! }
+//! # let _ = start_match;
+//! ```
+
+use std::any::TypeId;
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use rustc_hash::FxHashSet;
+use valence_entity::active_status_effects::ActiveStatusEffects;
+use valence_entity::attributes::EntityAttributes;
+use valence_entity::living::Health;
+use valence_entity::tracked_data::TrackedData;
+use valence_entity::{
+    EntityAnimations, EntityId, EntityKind, EntityLayerId, EntityStatuses, HeadYaw, Look,
+    ObjectData, OldEntityLayerId, OldPosition, OnGround, Position, Velocity,
+};
+use valence_server_common::{Despawned, Server, UniqueId};
+
+use super::{ChunkLayer, EntityLayer};
+use crate::client::Client;
+
+type CloneFn = fn(&EntityRef) -> Option<Box<dyn FnOnce(&mut EntityWorldMut)>>;
+
+/// The components copied by [`CloneLayer`].
+///
+/// By default, the position, rotation, velocity and tracked data of entities
+/// are registered, as well as the attributes, effects and health of living
+/// entities. Plugins with components for layers or entities should register
+/// them if they should be copied too.
+#[derive(Resource)]
+pub struct CloneComponents {
+    types: FxHashSet<TypeId>,
+    clone_fns: Vec<CloneFn>,
+}
+
+impl CloneComponents {
+    /// Creates a registry without any components.
+    pub fn empty() -> Self {
+        Self {
+            types: FxHashSet::default(),
+            clone_fns: vec![],
+        }
+    }
+
+    /// Registers the component `C` to be copied. Registering a component
+    /// again does nothing.
+    pub fn register<C: Component + Clone>(&mut self) -> &mut Self {
+        if self.types.insert(TypeId::of::<C>()) {
+            self.clone_fns.push(|entity| {
+                let component = entity.get::<C>()?.clone();
+
+                Some(Box::new(move |target| {
+                    target.insert(component);
+                }))
+            });
+        }
+
+        self
+    }
+
+    pub fn is_registered<C: Component>(&self) -> bool {
+        self.types.contains(&TypeId::of::<C>())
+    }
+
+    /// Copies the registered components of `source` into `target`.
+    fn copy(&self, world: &mut World, source: Entity, target: Entity) {
+        let Some(source) = world.get_entity(source) else {
+            return;
+        };
+
+        let inserts: Vec<_> = self.clone_fns.iter().filter_map(|f| f(&source)).collect();
+
+        let mut target = world.entity_mut(target);
+
+        for insert in inserts {
+            insert(&mut target);
+        }
+    }
+}
+
+impl Default for CloneComponents {
+    fn default() -> Self {
+        let mut components = Self::empty();
+
+        components
+            .register::<Position>()
+            .register::<Look>()
+            .register::<HeadYaw>()
+            .register::<OnGround>()
+            .register::<Velocity>()
+            .register::<ObjectData>()
+            .register::<TrackedData>()
+            .register::<EntityAttributes>()
+            .register::<ActiveStatusEffects>()
+            .register::<Health>();
+
+        components
+    }
+}
+
+/// [`Command`] to turn `target` into a copy of the layer `template`.
+///
+/// The target gets a [`ChunkLayer`] with the dimension and chunks of the
+/// template, an empty [`EntityLayer`], and the components of the template
+/// registered in [`CloneComponents`]. With
+/// [`with_entities`](Self::with_entities), every entity on the template's
+/// entity layer except for clients is copied onto the target as well.
+///
+/// Nothing happens if the template doesn't have a [`ChunkLayer`] or the
+/// target doesn't exist.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CloneLayer {
+    pub template: Entity,
+    pub target: Entity,
+    /// Whether the entities on the template are copied.
+    pub entities: bool,
+}
+
+impl CloneLayer {
+    pub fn new(template: Entity, target: Entity) -> Self {
+        Self {
+            template,
+            target,
+            entities: false,
+        }
+    }
+
+    pub fn with_entities(mut self) -> Self {
+        self.entities = true;
+        self
+    }
+}
+
+impl Command for CloneLayer {
+    fn apply(self, world: &mut World) {
+        let Some(chunk_layer) = world.get::<ChunkLayer>(self.template) else {
+            return;
+        };
+
+        if world.get_entity(self.target).is_none() {
+            return;
+        }
+
+        let chunk_layer = chunk_layer.duplicate();
+        let entity_layer = EntityLayer::new(world.resource::<Server>());
+
+        if !world.contains_resource::<CloneComponents>() {
+            world.init_resource::<CloneComponents>();
+        }
+
+        world.resource_scope(|world, components: Mut<CloneComponents>| {
+            components.copy(world, self.template, self.target);
+
+            world
+                .entity_mut(self.target)
+                .insert((chunk_layer, entity_layer));
+
+            if !self.entities {
+                return;
+            }
+
+            let mut query = world.query_filtered::<(Entity, &EntityLayerId), (
+                With<EntityKind>,
+                Without<Client>,
+                Without<Despawned>,
+            )>();
+
+            let sources: Vec<_> = query
+                .iter(world)
+                .filter(|(_, layer)| layer.0 == self.template)
+                .map(|(entity, _)| entity)
+                .collect();
+
+            for source in sources {
+                let kind = *world.get::<EntityKind>(source).unwrap();
+
+                // The copy gets its own ID and UUID.
+                let copy = world
+                    .spawn((
+                        kind,
+                        EntityId::default(),
+                        UniqueId::default(),
+                        EntityLayerId(self.target),
+                        OldEntityLayerId::default(),
+                        Position::default(),
+                        OldPosition::default(),
+                        Look::default(),
+                        HeadYaw::default(),
+                        OnGround::default(),
+                        Velocity::default(),
+                        EntityStatuses::default(),
+                        EntityAnimations::default(),
+                        ObjectData::default(),
+                        TrackedData::default(),
+                    ))
+                    .id();
+
+                components.copy(world, source, copy);
+
+                // Registering `EntityLayerId` must not move the copy onto the
+                // template.
+                world.entity_mut(copy).insert(EntityLayerId(self.target));
+            }
+        });
+    }
+}
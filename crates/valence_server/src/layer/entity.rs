@@ -13,7 +13,6 @@ use valence_server_common::{Despawned, Server};
 use super::bvh::GetChunkPos;
 use super::message::Messages;
 use super::{Layer, UpdateLayersPostClientSet, UpdateLayersPreClientSet};
-use crate::client::Client;
 
 /// A [`Component`] containing Minecraft entities.
 #[derive(Component, Debug)]
@@ -53,6 +52,10 @@ pub(crate) enum LocalMsg {
     /// Spawn entities if the client is not in view of `src_pos`. Message data
     /// is the serialized form of [`Entity`].
     SpawnEntityTransition { pos: ChunkPos, src_pos: ChunkPos },
+    /// Send packet data about `entity` to all clients viewing the layer in view
+    /// of `pos`, except `entity` itself and clients hiding `entity`. Message
+    /// data is serialized packet data.
+    EntityPacketAt { pos: ChunkPos, entity: Entity },
     /// Send packet data to all clients viewing the layer in view of `pos`.
     /// Message data is serialized packet data.
     PacketAt { pos: ChunkPos },
@@ -76,6 +79,7 @@ pub(crate) enum LocalMsg {
 impl GetChunkPos for LocalMsg {
     fn chunk_pos(&self) -> ChunkPos {
         match *self {
+            LocalMsg::EntityPacketAt { pos, .. } => pos,
             LocalMsg::PacketAt { pos } => pos,
             LocalMsg::PacketAtExcept { pos, .. } => pos,
             LocalMsg::RadiusAt { center, .. } => center.into(),
@@ -470,7 +474,7 @@ fn change_entity_positions(
 }
 
 fn send_entity_update_messages(
-    entities: Query<(Entity, UpdateEntityQuery, Has<Client>), Without<Despawned>>,
+    entities: Query<UpdateEntityQuery, Without<Despawned>>,
     mut layers: Query<&mut EntityLayer>,
 ) {
     for layer in layers.iter_mut() {
@@ -478,24 +482,19 @@ fn send_entity_update_messages(
 
         for cell in layer.entities.values_mut() {
             for &entity in cell.iter() {
-                if let Ok((entity, update, is_client)) = entities.get(entity) {
+                if let Ok(update) = entities.get(entity) {
                     let chunk_pos = ChunkPos::from(update.pos.0);
 
                     // Send the update packets to all viewers. If the entity being updated is a
-                    // client, then we need to be careful to exclude the client itself from
-                    // receiving the update packets.
-                    let msg = if is_client {
-                        LocalMsg::PacketAtExcept {
+                    // client, then the client itself is excluded from receiving the update
+                    // packets.
+                    layer.messages.send_local_infallible(
+                        LocalMsg::EntityPacketAt {
                             pos: chunk_pos,
-                            except: entity,
-                        }
-                    } else {
-                        LocalMsg::PacketAt { pos: chunk_pos }
-                    };
-
-                    layer.messages.send_local_infallible(msg, |b| {
-                        update.write_update_packets(PacketWriter::new(b, layer.threshold))
-                    });
+                            entity,
+                        },
+                        |b| update.write_update_packets(PacketWriter::new(b, layer.threshold)),
+                    );
                 } else {
                     panic!(
                         "Entity {entity:?} was not properly removed from entity layer. Did you \
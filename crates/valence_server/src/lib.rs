@@ -20,39 +20,62 @@
 
 pub mod abilities;
 pub mod action;
+pub mod area_trigger;
+pub mod block_placement;
 pub mod brand;
+pub mod budget;
 mod chunk_view;
+pub mod click;
 pub mod client;
 pub mod client_command;
 pub mod client_settings;
+pub mod compression;
 pub mod custom_payload;
+pub mod emitter;
+pub mod entity_sound;
 pub mod event_loop;
+pub mod experience;
+pub mod filter;
 pub mod hand_swing;
 pub mod interact_block;
 pub mod interact_entity;
 pub mod interact_item;
 pub mod keepalive;
+pub mod lag_compensation;
 pub mod layer;
 pub mod message;
 pub mod movement;
 pub mod op_level;
+pub mod protocol_error;
+pub mod random;
 pub mod resource_pack;
+pub mod sign;
+pub mod sit;
+pub mod smooth_movement;
 pub mod spawn;
+pub mod spectate;
 pub mod status;
 pub mod status_effect;
+pub mod switch_layer;
 pub mod teleport;
 pub mod title;
+pub mod transfer;
+pub mod velocity;
+pub mod visibility;
 
+pub use bevy_app as app;
+pub use bevy_ecs as ecs;
 pub use chunk_view::ChunkView;
 pub use event_loop::{EventLoopPostUpdate, EventLoopPreUpdate, EventLoopUpdate};
 pub use layer::{ChunkLayer, EntityLayer, Layer, LayerBundle};
+pub use rand;
+pub use valence_entity as entity;
+pub use valence_nbt as nbt;
+pub use valence_protocol as protocol;
 pub use valence_protocol::{
     block, ident, item, math, text, uuid, BiomePos, BlockPos, BlockState, ChunkPos,
     CompressionThreshold, Difficulty, Direction, GameMode, Hand, Ident, ItemKind, ItemStack, Text,
     MINECRAFT_VERSION, PROTOCOL_VERSION,
 };
+pub use valence_registry as registry;
 pub use valence_server_common::*;
-pub use {
-    bevy_app as app, bevy_ecs as ecs, rand, valence_entity as entity, valence_nbt as nbt,
-    valence_protocol as protocol, valence_registry as registry,
-};
@@ -7,6 +7,7 @@ use valence_protocol::packets::play::{ChatMessageC2s, GameMessageS2c};
 use valence_protocol::text::IntoText;
 
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct MessagePlugin;
 
@@ -49,10 +50,11 @@ pub struct ChatMessageEvent {
 
 pub fn handle_chat_message(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut events: EventWriter<ChatMessageEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<ChatMessageC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<ChatMessageC2s>(&errors) {
             events.send(ChatMessageEvent {
                 client: packet.client,
                 message: pkt.message.0.into(),
@@ -7,6 +7,7 @@ use valence_protocol::packets::play::{
 };
 
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 use crate::teleport::TeleportState;
 
 pub struct MovementPlugin;
@@ -39,6 +40,7 @@ pub struct MovementEvent {
 
 fn handle_client_movement(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<(
         &mut Position,
         &mut Look,
@@ -49,7 +51,7 @@ fn handle_client_movement(
     mut movement_events: EventWriter<MovementEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<PositionAndOnGroundC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<PositionAndOnGroundC2s>(&errors) {
             if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
                 clients.get_mut(packet.client)
             {
@@ -73,7 +75,7 @@ fn handle_client_movement(
                     &mut movement_events,
                 );
             }
-        } else if let Some(pkt) = packet.decode::<FullC2s>() {
+        } else if let Some(pkt) = packet.decode_reporting::<FullC2s>(&errors) {
             if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
                 clients.get_mut(packet.client)
             {
@@ -100,7 +102,7 @@ fn handle_client_movement(
                     &mut movement_events,
                 );
             }
-        } else if let Some(pkt) = packet.decode::<LookAndOnGroundC2s>() {
+        } else if let Some(pkt) = packet.decode_reporting::<LookAndOnGroundC2s>(&errors) {
             if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
                 clients.get_mut(packet.client)
             {
@@ -127,7 +129,7 @@ fn handle_client_movement(
                     &mut movement_events,
                 );
             }
-        } else if let Some(pkt) = packet.decode::<OnGroundOnlyC2s>() {
+        } else if let Some(pkt) = packet.decode_reporting::<OnGroundOnlyC2s>(&errors) {
             if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
                 clients.get_mut(packet.client)
             {
@@ -151,7 +153,7 @@ fn handle_client_movement(
                     &mut movement_events,
                 );
             }
-        } else if let Some(pkt) = packet.decode::<VehicleMoveC2s>() {
+        } else if let Some(pkt) = packet.decode_reporting::<VehicleMoveC2s>(&errors) {
             if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
                 clients.get_mut(packet.client)
             {
@@ -0,0 +1,207 @@
+//! Diagnostics for malformed packets sent by clients.
+//!
+//! Packets which fail to decode are otherwise only logged and then ignored.
+//! Every such failure is also sent as a [`ProtocolErrorEvent`] and counted in
+//! [`ProtocolErrorStats`], so broken clients, corrupting proxies and clients
+//! with the wrong protocol version can be detected in production.
+//!
+//! Errors in [`PacketEvent::decode_reporting`], which Valence's own packet
+//! handlers use, are sent in [`Last`] of the tick they happen in. Errors which
+//! prevent a whole packet frame from being read, such as a bad length or
+//! compression, are sent when the client is disconnected because of them.
+//!
+//! [`PacketEvent::decode_reporting`]: crate::event_loop::PacketEvent::decode_reporting
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::protocol_error::ProtocolErrorEvent;
+//!
+//! fn kick_broken_clients(mut events: EventReader<ProtocolErrorEvent>, mut commands: Commands) {
+//!     for event in events.read() {
+//!         // Disconnect the client.
+//!         commands
+//!             .entity(event.client)
+//!             .remove::<valence_server::client::Client>();
+//!     }
+//! }
+//! # let _ = kick_broken_clients;
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use bevy_ecs::prelude::*;
+use bytes::Bytes;
+
+/// The largest number of bytes of a packet kept in a [`ProtocolError`].
+pub const SNIPPET_LEN: usize = 64;
+
+/// A malformed packet received from a client.
+#[derive(Clone, PartialEq, Eq, Debug, thiserror::Error)]
+#[error("{kind} (packet ID {packet_id:?}): {message}")]
+pub struct ProtocolError {
+    pub kind: ProtocolErrorKind,
+    /// The ID of the packet, if the packet frame could be read.
+    pub packet_id: Option<i32>,
+    /// The name of the packet the data was decoded as.
+    pub packet_name: Option<&'static str>,
+    /// The cause of the error.
+    pub message: String,
+    /// Up to [`SNIPPET_LEN`] bytes from the start of the packet data,
+    /// excluding the packet ID. Empty for [`ProtocolErrorKind::Frame`].
+    pub snippet: Bytes,
+}
+
+impl ProtocolError {
+    /// Creates an error for a packet frame which couldn't be read.
+    pub fn frame(message: impl fmt::Display) -> Self {
+        Self {
+            kind: ProtocolErrorKind::Frame,
+            packet_id: None,
+            packet_name: None,
+            message: format!("{message:#}"),
+            snippet: Bytes::new(),
+        }
+    }
+
+    pub(crate) fn packet(
+        kind: ProtocolErrorKind,
+        packet_id: i32,
+        packet_name: &'static str,
+        message: String,
+        data: &Bytes,
+    ) -> Self {
+        Self {
+            kind,
+            packet_id: Some(packet_id),
+            packet_name: Some(packet_name),
+            message,
+            snippet: data.slice(..data.len().min(SNIPPET_LEN)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ProtocolErrorKind {
+    /// The packet frame couldn't be read, for instance because of an invalid
+    /// length or compressed data. The client is disconnected.
+    Frame,
+    /// The packet data doesn't decode as the packet with its ID.
+    Decode,
+    /// The packet decoded, but data was left over. The packet is ignored.
+    TrailingBytes,
+}
+
+impl fmt::Display for ProtocolErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProtocolErrorKind::Frame => "malformed packet frame",
+            ProtocolErrorKind::Decode => "failed to decode packet",
+            ProtocolErrorKind::TrailingBytes => "trailing bytes after packet",
+        })
+    }
+}
+
+/// Sent for every [`ProtocolError`] of a client.
+#[derive(Event, Clone, PartialEq, Eq, Debug)]
+pub struct ProtocolErrorEvent {
+    pub client: Entity,
+    pub error: ProtocolError,
+}
+
+/// Counts of all the [`ProtocolError`]s since the server started.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct ProtocolErrorStats {
+    total: u64,
+    by_kind: BTreeMap<ProtocolErrorKind, u64>,
+    by_packet: BTreeMap<i32, u64>,
+}
+
+impl ProtocolErrorStats {
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// The number of errors of the kind `kind`.
+    pub fn count(&self, kind: ProtocolErrorKind) -> u64 {
+        self.by_kind.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// The number of errors in packets with the ID `packet_id`.
+    pub fn count_for_packet(&self, packet_id: i32) -> u64 {
+        self.by_packet.get(&packet_id).copied().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the packet IDs with errors and their counts.
+    pub fn packets(&self) -> impl Iterator<Item = (i32, u64)> + '_ {
+        self.by_packet.iter().map(|(&id, &count)| (id, count))
+    }
+
+    fn record(&mut self, error: &ProtocolError) {
+        self.total += 1;
+        *self.by_kind.entry(error.kind).or_default() += 1;
+
+        if let Some(id) = error.packet_id {
+            *self.by_packet.entry(id).or_default() += 1;
+        }
+    }
+}
+
+/// Resource collecting the errors that occur while decoding
+/// [`PacketEvent`]s, which is passed to [`PacketEvent::decode_reporting`].
+/// The errors are sent as [`ProtocolErrorEvent`]s at the end of the tick.
+///
+/// [`PacketEvent`]: crate::event_loop::PacketEvent
+/// [`PacketEvent::decode_reporting`]: crate::event_loop::PacketEvent::decode_reporting
+#[derive(Resource, Default, Debug)]
+pub struct ProtocolErrorSink(Mutex<Vec<SinkEntry>>);
+
+#[derive(Debug)]
+struct SinkEntry {
+    client: Entity,
+    timestamp: Instant,
+    error: ProtocolError,
+}
+
+impl ProtocolErrorSink {
+    pub(crate) fn push(&self, client: Entity, timestamp: Instant, error: ProtocolError) {
+        self.0.lock().unwrap().push(SinkEntry {
+            client,
+            timestamp,
+            error,
+        });
+    }
+}
+
+pub(crate) fn send_protocol_error_events(
+    sink: Res<ProtocolErrorSink>,
+    mut stats: ResMut<ProtocolErrorStats>,
+    mut events: EventWriter<ProtocolErrorEvent>,
+) {
+    let entries = std::mem::take(&mut *sink.0.lock().unwrap());
+
+    // The same packet may be decoded by more than one system.
+    let mut seen = HashSet::new();
+
+    for entry in entries {
+        if !seen.insert((entry.client, entry.timestamp, entry.error.packet_id)) {
+            continue;
+        }
+
+        send_protocol_error(&mut stats, &mut events, entry.client, entry.error);
+    }
+}
+
+pub(crate) fn send_protocol_error(
+    stats: &mut ProtocolErrorStats,
+    events: &mut EventWriter<ProtocolErrorEvent>,
+    client: Entity,
+    error: ProtocolError,
+) {
+    stats.record(&error);
+    events.send(ProtocolErrorEvent { client, error });
+}
@@ -0,0 +1,298 @@
+//! Seeded random number generation, so that gameplay can be reproduced from a
+//! seed.
+//!
+//! The [`GameRng`] resource is the root of all randomness. Independent streams
+//! are forked from it by label with [`SeededRng::fork`], which only depends on
+//! the seed and the label. Using one stream therefore never changes the
+//! numbers produced by another, and systems can be added or reordered without
+//! changing unrelated results.
+//!
+//! Every chunk layer gets a [`LayerRng`] forked from the [`GameRng`] in the
+//! order the layers were spawned. Worldgen, loot, and spawning in a layer
+//! should use its [`LayerRng`] instead of [`rand::thread_rng`].
+//!
+//! The random numbers for a seed are only guaranteed to be the same for the
+//! same version of Valence.
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_server::rand::Rng;
+//! use valence_server::random::{SeededRng, WeightedList};
+//!
+//! let mut loot = SeededRng::new(42).fork("loot");
+//!
+//! let table = WeightedList::from_iter([("diamond", 1), ("iron", 10), ("dirt", 89)]);
+//!
+//! let item = table.choose(&mut loot).unwrap();
+//! let count = loot.gen_range(1..=3);
+//! # let _ = (item, count);
+//! ```
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use derive_more::{Deref, DerefMut};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+use crate::ChunkLayer;
+
+pub struct RandomPlugin;
+
+impl Plugin for RandomPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<GameRng>() {
+            app.insert_resource(GameRng(SeededRng::new(rand::random())));
+        }
+
+        app.add_systems(First, init_layer_rngs);
+    }
+}
+
+/// A random number generator which remembers its seed and can be forked into
+/// independent streams.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed this generator was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns a new generator for the stream named `label`. The result only
+    /// depends on the seed of `self` and `label`, not on how many numbers were
+    /// generated by `self`.
+    pub fn fork(&self, label: &str) -> Self {
+        Self::new(mix(self.seed, fnv1a(label.as_bytes())))
+    }
+
+    /// Like [`Self::fork`], but for one of many streams with the same label,
+    /// such as one per chunk or entity.
+    pub fn fork_indexed(&self, label: &str, index: u64) -> Self {
+        Self::new(mix(mix(self.seed, fnv1a(label.as_bytes())), index))
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// The root random number generator of the server. Insert this resource
+/// before adding [`RandomPlugin`] to use a fixed seed, otherwise a random seed
+/// is used.
+#[derive(Resource, Clone, Debug, Deref, DerefMut)]
+pub struct GameRng(pub SeededRng);
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self(SeededRng::new(seed))
+    }
+}
+
+/// The random number generator of a chunk layer. Added automatically to
+/// layers without one, but can also be inserted with a seed of your choice.
+#[derive(Component, Clone, Debug, Deref, DerefMut)]
+pub struct LayerRng(pub SeededRng);
+
+/// Forks a [`LayerRng`] for new layers. Layers spawned in the same tick are
+/// handled in the order of their entity IDs.
+fn init_layer_rngs(
+    layers: Query<Entity, (Added<ChunkLayer>, Without<LayerRng>)>,
+    game_rng: Res<GameRng>,
+    mut count: Local<u64>,
+    mut commands: Commands,
+) {
+    let mut layers: Vec<_> = layers.iter().collect();
+    layers.sort_unstable();
+
+    for layer in layers {
+        commands
+            .entity(layer)
+            .insert(LayerRng(game_rng.fork_indexed("layer", *count)));
+        *count += 1;
+    }
+}
+
+/// A list of items with weights, where the chance of choosing an item is its
+/// weight divided by the total weight.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WeightedList<T> {
+    items: Vec<T>,
+    /// The cumulative weight up to and including each item.
+    cumulative: Vec<u64>,
+}
+
+impl<T> WeightedList<T> {
+    pub fn new() -> Self {
+        Self {
+            items: vec![],
+            cumulative: vec![],
+        }
+    }
+
+    /// Adds `item` with `weight`. Items with a weight of zero are never
+    /// chosen.
+    pub fn push(&mut self, item: T, weight: u32) {
+        self.cumulative.push(self.total_weight() + weight as u64);
+        self.items.push(item);
+    }
+
+    pub fn total_weight(&self) -> u64 {
+        self.cumulative.last().copied().unwrap_or(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the items and their weights.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, u32)> + '_ {
+        self.items.iter().enumerate().map(|(i, item)| {
+            let prev = if i == 0 { 0 } else { self.cumulative[i - 1] };
+            (item, (self.cumulative[i] - prev) as u32)
+        })
+    }
+
+    /// Chooses a random item. Returns `None` if the total weight is zero.
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&T> {
+        self.choose_index(rng).map(|i| &self.items[i])
+    }
+
+    /// Chooses the index of a random item. Returns `None` if the total weight
+    /// is zero.
+    pub fn choose_index<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let total = self.total_weight();
+
+        if total == 0 {
+            return None;
+        }
+
+        let target = rng.gen_range(0..total);
+
+        // The first item whose cumulative weight exceeds the target. Items with
+        // zero weight have the same cumulative weight as the previous item, so
+        // they are skipped over.
+        Some(self.cumulative.partition_point(|&w| w <= target))
+    }
+
+    /// Chooses `count` random items, allowing the same item to be chosen more
+    /// than once.
+    pub fn choose_multiple<'a, R: Rng + ?Sized>(
+        &'a self,
+        rng: &'a mut R,
+        count: usize,
+    ) -> impl Iterator<Item = &'a T> + 'a {
+        (0..count).filter_map(move |_| self.choose(rng))
+    }
+}
+
+impl<T> Default for WeightedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<(T, u32)> for WeightedList<T> {
+    fn from_iter<I: IntoIterator<Item = (T, u32)>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<(T, u32)> for WeightedList<T> {
+    fn extend<I: IntoIterator<Item = (T, u32)>>(&mut self, iter: I) {
+        for (item, weight) in iter {
+            self.push(item, weight);
+        }
+    }
+}
+
+/// Hashes `bytes` with FNV-1a, which unlike the standard library hashers is
+/// guaranteed to be stable.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Combines two values into a well distributed seed with the SplitMix64
+/// finalizer.
+fn mix(a: u64, b: u64) -> u64 {
+    let mut z = a ^ b.wrapping_add(0x9e3779b97f4a7c15).rotate_left(32);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forks_are_independent_of_usage() {
+        let mut a = SeededRng::new(1234);
+        let b = SeededRng::new(1234);
+
+        a.next_u64();
+
+        assert_eq!(a.fork("loot").next_u64(), b.clone().fork("loot").next_u64());
+        assert_ne!(b.fork("loot").next_u64(), b.fork("spawning").next_u64());
+        assert_ne!(
+            b.fork_indexed("chunk", 0).next_u64(),
+            b.fork_indexed("chunk", 1).next_u64()
+        );
+    }
+
+    #[test]
+    fn weighted_list() {
+        let list = WeightedList::from_iter([('a', 1), ('b', 0), ('c', 3)]);
+
+        assert_eq!(list.total_weight(), 4);
+        assert_eq!(
+            list.iter().collect::<Vec<_>>(),
+            [(&'a', 1), (&'b', 0), (&'c', 3)]
+        );
+
+        let mut rng = SeededRng::new(0);
+        let mut counts = [0; 3];
+
+        for _ in 0..4000 {
+            counts[list.choose_index(&mut rng).unwrap()] += 1;
+        }
+
+        assert_eq!(counts[1], 0);
+        assert!((800..1200).contains(&counts[0]), "{counts:?}");
+
+        assert_eq!(WeightedList::from_iter([((), 0)]).choose(&mut rng), None);
+    }
+}
@@ -1,18 +1,25 @@
+use std::borrow::Cow;
+
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use valence_protocol::packets::play::{ResourcePackSendS2c, ResourcePackStatusC2s};
-use valence_protocol::text::Text;
+use valence_protocol::text::{IntoText, Text};
 use valence_protocol::WritePacket;
 
-use crate::client::Client;
+use crate::client::{Client, DisconnectClient, FlushPacketsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct ResourcePackPlugin;
 
 impl Plugin for ResourcePackPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ResourcePackStatusEvent>()
-            .add_systems(EventLoopPreUpdate, handle_resource_pack_status);
+            .add_systems(
+                EventLoopPreUpdate,
+                (handle_resource_pack_status, kick_declined_required_packs).chain(),
+            )
+            .add_systems(PostUpdate, send_required_packs.before(FlushPacketsSet));
     }
 }
 
@@ -22,18 +29,94 @@ pub struct ResourcePackStatusEvent {
     pub status: ResourcePackStatusC2s,
 }
 
+impl ResourcePackStatusEvent {
+    /// Returns whether the client won't use the pack, either because it was
+    /// declined or failed to download.
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self.status,
+            ResourcePackStatusC2s::Declined | ResourcePackStatusC2s::FailedDownload
+        )
+    }
+}
+
+/// The status of the last resource pack sent to a client, as reported by the
+/// client.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ResourcePackState {
+    status: Option<ResourcePackStatusC2s>,
+}
+
+impl ResourcePackState {
+    /// Returns the last status sent by the client, or `None` if the client
+    /// hasn't responded to a resource pack yet.
+    pub fn status(&self) -> Option<ResourcePackStatusC2s> {
+        self.status
+    }
+
+    /// Returns whether the client has finished loading the pack.
+    pub fn is_loaded(&self) -> bool {
+        self.status == Some(ResourcePackStatusC2s::SuccessfullyLoaded)
+    }
+
+    /// Returns whether the client accepted the pack and is still downloading
+    /// it.
+    pub fn is_downloading(&self) -> bool {
+        self.status == Some(ResourcePackStatusC2s::Accepted)
+    }
+}
+
+/// A resource pack every client with this component must use. The pack is
+/// sent when the component is added or changed, and clients which decline it
+/// or fail to download it are kicked with
+/// [`kick_message`](Self::kick_message).
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct RequiredResourcePack {
+    /// The URL of the resource pack file.
+    pub url: String,
+    /// The SHA-1 hash of the resource pack file as a 40-character hexadecimal
+    /// string.
+    pub hash: String,
+    /// A message to be displayed with the resource pack dialog.
+    pub prompt: Option<Text>,
+    pub kick_message: Text,
+}
+
+impl RequiredResourcePack {
+    pub fn new(url: impl Into<String>, hash: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            hash: hash.into(),
+            prompt: None,
+            kick_message: "This server requires its resource pack.".into(),
+        }
+    }
+
+    pub fn with_prompt<'a>(mut self, prompt: impl IntoText<'a>) -> Self {
+        self.prompt = Some(prompt.into_text());
+        self
+    }
+
+    pub fn with_kick_message<'a>(mut self, message: impl IntoText<'a>) -> Self {
+        self.kick_message = message.into_text();
+        self
+    }
+}
+
 impl Client {
-    /// Requests that the client download and enable a resource pack.
+    /// Requests that the client download and enable a resource pack. The
+    /// client's response is sent as a [`ResourcePackStatusEvent`].
     ///
     /// # Arguments
     /// * `url` - The URL of the resource pack file.
     /// * `hash` - The SHA-1 hash of the resource pack file. The value must be a
     ///   40-character hexadecimal string.
     /// * `forced` - Whether a client should be kicked from the server upon
-    ///   declining the pack (this is enforced client-side)
+    ///   declining the pack (this is enforced client-side). To enforce this
+    ///   server-side, use [`RequiredResourcePack`] instead.
     /// * `prompt_message` - A message to be displayed with the resource pack
     ///   dialog.
-    pub fn set_resource_pack(
+    pub fn send_resource_pack(
         &mut self,
         url: &str,
         hash: &str,
@@ -44,17 +127,34 @@ impl Client {
             url,
             hash: hash.into(),
             forced,
-            prompt_message: prompt_message.map(|t| t.into()),
+            prompt_message: prompt_message.map(Cow::Owned),
         });
     }
+
+    #[deprecated = "use `send_resource_pack` instead"]
+    pub fn set_resource_pack(
+        &mut self,
+        url: &str,
+        hash: &str,
+        forced: bool,
+        prompt_message: Option<Text>,
+    ) {
+        self.send_resource_pack(url, hash, forced, prompt_message)
+    }
 }
 
 fn handle_resource_pack_status(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut events: EventWriter<ResourcePackStatusEvent>,
+    mut states: Query<&mut ResourcePackState>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<ResourcePackStatusC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<ResourcePackStatusC2s>(&errors) {
+            if let Ok(mut state) = states.get_mut(packet.client) {
+                state.status = Some(pkt);
+            }
+
             events.send(ResourcePackStatusEvent {
                 client: packet.client,
                 status: pkt,
@@ -62,3 +162,34 @@ fn handle_resource_pack_status(
         }
     }
 }
+
+fn send_required_packs(
+    mut clients: Query<
+        (&mut Client, &mut ResourcePackState, &RequiredResourcePack),
+        Changed<RequiredResourcePack>,
+    >,
+) {
+    for (mut client, mut state, pack) in &mut clients {
+        state.status = None;
+        client.send_resource_pack(&pack.url, &pack.hash, true, pack.prompt.clone());
+    }
+}
+
+fn kick_declined_required_packs(
+    mut events: EventReader<ResourcePackStatusEvent>,
+    packs: Query<&RequiredResourcePack>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        if !event.is_rejected() {
+            continue;
+        }
+
+        if let Ok(pack) = packs.get(event.client) {
+            commands.add(DisconnectClient {
+                client: event.client,
+                reason: pack.kick_message.clone(),
+            });
+        }
+    }
+}
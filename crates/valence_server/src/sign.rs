@@ -0,0 +1,287 @@
+//! Editing the text of signs.
+//!
+//! The [`OpenSignEditor`] command opens the sign editor of a client for a
+//! side of the sign at a position in its [`VisibleChunkLayer`]. When the
+//! client is done, the lines are checked, written to the `messages` of the
+//! side in the block entity of the sign, and sent as a [`SignEditEvent`].
+//!
+//! Edits are only accepted for the sign the editor was opened for, so clients
+//! can't change signs they weren't allowed to edit.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::block::BlockEntityKind;
+//! use valence_server::client::VisibleChunkLayer;
+//! use valence_server::interact_block::InteractBlockEvent;
+//! use valence_server::sign::OpenSignEditor;
+//! use valence_server::ChunkLayer;
+//!
+//! fn edit_signs(
+//!     clients: Query<&VisibleChunkLayer>,
+//!     layers: Query<&ChunkLayer>,
+//!     mut events: EventReader<InteractBlockEvent>,
+//!     mut commands: Commands,
+//! ) {
+//!     for event in events.read() {
+//!         let Ok(layer) = clients.get(event.client).and_then(|l| layers.get(l.0)) else {
+//!             continue;
+//!         };
+//!
+//!         let is_sign = layer
+//!             .block(event.position)
+//!             .is_some_and(|b| b.state.block_entity_kind() == Some(BlockEntityKind::Sign));
+//!
+//!         if is_sign {
+//!             commands.add(OpenSignEditor::new(event.client, event.position));
+//!         }
+//!     }
+//! }
+//! # let _ = edit_signs;
+//! ```
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use valence_nbt::{Compound, List, Value};
+use valence_protocol::block::BlockEntityKind;
+use valence_protocol::packets::play::{SignEditorOpenS2c, UpdateSignC2s};
+use valence_protocol::{BlockPos, Text, WritePacket};
+
+use crate::client::{Client, VisibleChunkLayer};
+use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::layer::chunk::Block;
+use crate::layer::ChunkLayer;
+use crate::protocol_error::ProtocolErrorSink;
+
+pub struct SignPlugin;
+
+impl Plugin for SignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SignEditEvent>()
+            .add_systems(EventLoopPreUpdate, handle_update_sign);
+    }
+}
+
+/// The most characters on a line of a sign, which is also the limit of the
+/// packet.
+pub const MAX_SIGN_LINE_CHARS: usize = 384;
+
+/// Sent when a client finished editing a sign opened with [`OpenSignEditor`].
+/// The lines are already written to the block entity of the sign.
+#[derive(Event, Clone, PartialEq, Eq, Debug)]
+pub struct SignEditEvent {
+    pub client: Entity,
+    pub position: BlockPos,
+    /// Whether the front or the back of the sign was edited.
+    pub is_front_text: bool,
+    /// The lines of the sign, without formatting codes and control
+    /// characters.
+    pub lines: [String; 4],
+}
+
+/// A [`Command`] to open the sign editor of a client for the sign at
+/// `position` in its [`VisibleChunkLayer`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct OpenSignEditor {
+    pub client: Entity,
+    pub position: BlockPos,
+    /// Whether the front or the back of the sign is edited.
+    ///
+    /// # Default Value
+    ///
+    /// `true`
+    pub is_front_text: bool,
+}
+
+impl OpenSignEditor {
+    pub fn new(client: Entity, position: impl Into<BlockPos>) -> Self {
+        Self {
+            client,
+            position: position.into(),
+            is_front_text: true,
+        }
+    }
+
+    pub fn with_front_text(mut self, is_front_text: bool) -> Self {
+        self.is_front_text = is_front_text;
+        self
+    }
+}
+
+impl Command for OpenSignEditor {
+    fn apply(self, world: &mut World) {
+        let Some(mut client) = world.get_mut::<Client>(self.client) else {
+            return;
+        };
+
+        client.write_packet(&SignEditorOpenS2c {
+            location: self.position,
+            is_front_text: self.is_front_text,
+        });
+
+        world.entity_mut(self.client).insert(SignEditor {
+            position: self.position,
+            is_front_text: self.is_front_text,
+        });
+    }
+}
+
+/// The sign a client may edit. Added by [`OpenSignEditor`] and removed once
+/// the client sends the lines.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SignEditor {
+    pub position: BlockPos,
+    pub is_front_text: bool,
+}
+
+/// Removes formatting codes and characters which can't be typed in chat, like
+/// vanilla does.
+fn filter_line(line: &str) -> String {
+    line.chars()
+        .filter(|&c| c != '§' && c >= ' ' && c != '\u{7f}')
+        .collect()
+}
+
+/// Writes `lines` to the side of the sign in the block entity `nbt`, keeping
+/// the other properties of the side like its color.
+fn write_lines(nbt: &mut Compound, is_front_text: bool, lines: &[String; 4]) {
+    let key = if is_front_text {
+        "front_text"
+    } else {
+        "back_text"
+    };
+
+    let messages = Value::List(List::String(
+        lines
+            .iter()
+            .map(|line| Text::text(line.clone()).into())
+            .collect(),
+    ));
+
+    match nbt.get_mut(key) {
+        Some(Value::Compound(side)) => {
+            side.insert("messages", messages);
+        }
+        _ => {
+            let mut side = Compound::new();
+            side.insert("messages", messages);
+            nbt.insert(key, side);
+        }
+    }
+}
+
+fn handle_update_sign(
+    mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    clients: Query<(&VisibleChunkLayer, Option<&SignEditor>)>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut events: EventWriter<SignEditEvent>,
+    mut commands: Commands,
+) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode_reporting::<UpdateSignC2s>(&errors) else {
+            continue;
+        };
+
+        let Ok((visible_layer, editor)) = clients.get(packet.client) else {
+            continue;
+        };
+
+        let expected = SignEditor {
+            position: pkt.position,
+            is_front_text: pkt.is_front_text,
+        };
+
+        if editor != Some(&expected) {
+            continue;
+        }
+
+        commands.entity(packet.client).remove::<SignEditor>();
+
+        if pkt
+            .lines
+            .iter()
+            .any(|line| line.0.chars().count() > MAX_SIGN_LINE_CHARS)
+        {
+            continue;
+        }
+
+        let Ok(mut layer) = layers.get_mut(visible_layer.0) else {
+            continue;
+        };
+
+        let is_sign = layer.block(pkt.position).is_some_and(|block| {
+            matches!(
+                block.state.block_entity_kind(),
+                Some(BlockEntityKind::Sign | BlockEntityKind::HangingSign)
+            )
+        });
+
+        if !is_sign {
+            continue;
+        }
+
+        let lines = pkt.lines.map(|line| filter_line(line.0));
+
+        if let Some(nbt) = layer.block_entity_mut(pkt.position) {
+            write_lines(nbt, pkt.is_front_text, &lines);
+        } else {
+            let mut nbt = Compound::new();
+            write_lines(&mut nbt, pkt.is_front_text, &lines);
+
+            let state = layer.block(pkt.position).expect("sign must exist").state;
+            layer.set_block(
+                pkt.position,
+                Block {
+                    state,
+                    nbt: Some(nbt),
+                },
+            );
+        }
+
+        events.send(SignEditEvent {
+            client: packet.client,
+            position: pkt.position,
+            is_front_text: pkt.is_front_text,
+            lines,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_sign_lines() {
+        assert_eq!(filter_line("§cHello\tworld\u{7f}!"), "cHelloworld!");
+        assert_eq!(filter_line("plain text"), "plain text");
+    }
+
+    #[test]
+    fn write_sign_lines_keeps_color() {
+        let mut nbt = Compound::new();
+        nbt.insert("front_text", {
+            let mut side = Compound::new();
+            side.insert("color", "red");
+            side
+        });
+
+        let lines = ["a".into(), "b".into(), "".into(), "".into()];
+        write_lines(&mut nbt, true, &lines);
+        write_lines(&mut nbt, false, &lines);
+
+        let Some(Value::Compound(front)) = nbt.get("front_text") else {
+            panic!("missing front text");
+        };
+
+        assert_eq!(front.get("color"), Some(&Value::String("red".into())));
+        assert!(matches!(
+            front.get("messages"),
+            Some(Value::List(List::String(messages))) if messages.len() == 4
+        ));
+        assert!(nbt.get("back_text").is_some());
+    }
+}
@@ -0,0 +1,275 @@
+//! Cosmetic sitting and lying for clients.
+//!
+//! Players can't sit or lie down on their own outside of vehicles and beds.
+//! The [`Sit`] and [`Lay`] commands mount the client on an invisible armor
+//! stand at its position, which other clients see as the player sitting. For
+//! lying, the player's pose is also set to [`Pose::Sleeping`]. The client
+//! stands up again when it presses the dismount key, when [`StandUp`] is
+//! applied, or when it is despawned.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::client::Client;
+//! use valence_server::sit::Sit;
+//!
+//! fn sit_everyone(clients: Query<Entity, Added<Client>>, mut commands: Commands) {
+//!     for client in &clients {
+//!         commands.add(Sit { client });
+//!     }
+//! }
+//! ```
+
+use std::borrow::Cow;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use valence_entity::armor_stand::{ArmorStandEntityBundle, ArmorStandFlags};
+use valence_entity::entity::{Flags, NoGravity};
+use valence_entity::{entity, EntityId, EntityLayerId, Pose, Position};
+use valence_math::DVec3;
+use valence_protocol::packets::play::{EntityPassengersSetS2c, PlayerInputC2s};
+use valence_protocol::{VarInt, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::client::Client;
+use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::layer::{EntityLayer, UpdateLayersPreClientSet};
+use crate::protocol_error::ProtocolErrorSink;
+use crate::Layer;
+
+pub struct SitPlugin;
+
+impl Plugin for SitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StandUpEvent>()
+            .add_systems(EventLoopPreUpdate, handle_dismount)
+            .add_systems(
+                PostUpdate,
+                (remove_seats_of_despawned_clients, update_seats).before(UpdateLayersPreClientSet),
+            );
+    }
+}
+
+/// How often the passengers of seats are sent again, in ticks, so that
+/// clients which start viewing a seated player see it sitting.
+const RESEND_PASSENGERS_INTERVAL: u32 = 20;
+
+/// Component for clients that are sitting or lying on a seat entity. Added by
+/// [`Sit`] and [`Lay`] and removed by [`StandUp`].
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct Seated {
+    seat: Entity,
+    pose: SeatPose,
+    /// The position of the client before it sat down, which it is moved back
+    /// to when standing up.
+    stand_pos: DVec3,
+    age: u32,
+}
+
+impl Seated {
+    /// The invisible armor stand the client is riding.
+    pub fn seat(&self) -> Entity {
+        self.seat
+    }
+
+    pub fn pose(&self) -> SeatPose {
+        self.pose
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SeatPose {
+    Sitting,
+    Lying,
+}
+
+/// A [`Command`] to make a client sit down where it stands. A client which is
+/// already seated is moved to a new seat.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Sit {
+    pub client: Entity,
+}
+
+/// A [`Command`] to make a client lie down where it stands.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Lay {
+    pub client: Entity,
+}
+
+/// A [`Command`] to make a seated client stand up. Does nothing if the client
+/// is not seated.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StandUp {
+    pub client: Entity,
+}
+
+/// Sent when a seated client stands up, including when it pressed the
+/// dismount key.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StandUpEvent {
+    pub client: Entity,
+}
+
+impl Command for Sit {
+    fn apply(self, world: &mut World) {
+        seat_client(world, self.client, SeatPose::Sitting);
+    }
+}
+
+impl Command for Lay {
+    fn apply(self, world: &mut World) {
+        seat_client(world, self.client, SeatPose::Lying);
+    }
+}
+
+impl Command for StandUp {
+    fn apply(self, world: &mut World) {
+        let Some(mut client) = world.get_entity_mut(self.client) else {
+            return;
+        };
+
+        let Some(seated) = client.take::<Seated>() else {
+            return;
+        };
+
+        if let Some(mut pos) = client.get_mut::<Position>() {
+            // Also teleports the client, so it doesn't end up in the block
+            // below the seat.
+            pos.0 = seated.stand_pos;
+        }
+
+        if seated.pose == SeatPose::Lying {
+            if let Some(mut pose) = client.get_mut::<entity::Pose>() {
+                pose.0 = Pose::Standing;
+            }
+        }
+
+        if let Some(mut seat) = world.get_entity_mut(seated.seat) {
+            // Removing the seat dismounts the client for all viewers.
+            seat.insert(Despawned);
+        }
+
+        world.send_event(StandUpEvent {
+            client: self.client,
+        });
+    }
+}
+
+fn seat_client(world: &mut World, client: Entity, pose: SeatPose) {
+    StandUp { client }.apply(world);
+
+    let Some(entity) = world.get_entity(client) else {
+        return;
+    };
+
+    let (Some(pos), Some(layer), true) = (
+        entity.get::<Position>().copied(),
+        entity.get::<EntityLayerId>().copied(),
+        entity.contains::<Client>(),
+    ) else {
+        return;
+    };
+
+    let mut entity_flags = Flags::default();
+    entity_flags.set_invisible(true);
+
+    let mut armor_stand_flags = ArmorStandFlags::default();
+    // Markers have no hitbox, so the seat can't be hit or collided with.
+    armor_stand_flags.set_marker(true);
+
+    let seat = world
+        .spawn(ArmorStandEntityBundle {
+            layer,
+            position: pos,
+            entity_flags,
+            entity_no_gravity: NoGravity(true),
+            armor_stand_armor_stand_flags: armor_stand_flags,
+            ..Default::default()
+        })
+        .id();
+
+    let mut entity = world.entity_mut(client);
+
+    if pose == SeatPose::Lying {
+        if let Some(mut entity_pose) = entity.get_mut::<entity::Pose>() {
+            entity_pose.0 = Pose::Sleeping;
+        }
+    }
+
+    entity.insert(Seated {
+        seat,
+        pose,
+        stand_pos: pos.0,
+        age: 0,
+    });
+}
+
+fn handle_dismount(
+    mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    clients: Query<(), With<Seated>>,
+    mut commands: Commands,
+) {
+    for packet in packets.read() {
+        if let Some(pkt) = packet.decode_reporting::<PlayerInputC2s>(&errors) {
+            if pkt.flags.unmount() && clients.contains(packet.client) {
+                commands.add(StandUp {
+                    client: packet.client,
+                });
+            }
+        }
+    }
+}
+
+fn remove_seats_of_despawned_clients(
+    clients: Query<&Seated, With<Despawned>>,
+    mut commands: Commands,
+) {
+    for seated in &clients {
+        if let Some(mut seat) = commands.get_entity(seated.seat) {
+            seat.insert(Despawned);
+        }
+    }
+}
+
+/// Sends the passengers of seats to viewers. Passengers are first sent on the
+/// tick after the seat is spawned, so clients know about the seat entity.
+fn update_seats(
+    mut clients: Query<(Entity, &mut Client, &EntityId, &EntityLayerId, &mut Seated)>,
+    seats: Query<(&EntityId, &Position)>,
+    mut layers: Query<&mut EntityLayer>,
+    mut commands: Commands,
+) {
+    for (entity, mut client, id, layer, mut seated) in &mut clients {
+        let Ok((seat_id, seat_pos)) = seats.get(seated.seat) else {
+            // The seat was despawned by someone else.
+            commands.add(StandUp { client: entity });
+            continue;
+        };
+
+        let age = seated.age;
+        seated.age = age.wrapping_add(1);
+
+        if age % RESEND_PASSENGERS_INTERVAL != 1 {
+            continue;
+        }
+
+        // Clients know themselves by the reserved entity ID 0.
+        client.write_packet(&EntityPassengersSetS2c {
+            entity_id: VarInt(seat_id.get()),
+            passengers: Cow::Borrowed(&[VarInt(0)]),
+        });
+
+        if let Ok(mut layer) = layers.get_mut(layer.0) {
+            layer
+                .view_except_writer(seat_pos.0, entity)
+                .write_packet(&EntityPassengersSetS2c {
+                    entity_id: VarInt(seat_id.get()),
+                    passengers: Cow::Borrowed(&[VarInt(id.get())]),
+                });
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Smooth movement of server-controlled entities such as NPCs and cinematic
+//! cameras.
+//!
+//! Setting the [`Position`] of an entity to a new value in irregular steps
+//! makes it stutter on the client, and its body and head snap to new
+//! rotations. Give the entity a [`SmoothMovement`] component and set a target
+//! instead. The entity is then moved an even distance every tick, turns at a
+//! limited speed, and has its [`Velocity`] and [`HeadYaw`] kept in sync so
+//! the client can interpolate it.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::{HeadYaw, Look, Position, Velocity};
+use valence_math::{DVec3, Vec3};
+use valence_server_common::Server;
+
+use crate::layer::UpdateLayersPreClientSet;
+
+pub struct SmoothMovementPlugin;
+
+impl Plugin for SmoothMovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SmoothMovementFinishedEvent>().add_systems(
+            PostUpdate,
+            update_smooth_movement.before(UpdateLayersPreClientSet),
+        );
+    }
+}
+
+/// How the progress of a movement is distributed over its duration.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum Easing {
+    /// Constant speed.
+    #[default]
+    Linear,
+    /// Accelerates at the start and decelerates at the end.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps the fraction of time passed to the fraction of distance covered.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Moves and turns an entity smoothly toward a target every tick.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct SmoothMovement {
+    /// The maximum number of degrees the entity turns per tick.
+    pub turn_speed: f32,
+    /// Whether the entity turns to face the direction it moves in when no
+    /// look target is set.
+    pub face_movement: bool,
+    pub easing: Easing,
+    movement: Option<Movement>,
+    look_target: Option<Look>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Movement {
+    /// The position at the start of the movement, which is set on the first
+    /// tick of the movement.
+    from: Option<DVec3>,
+    to: DVec3,
+    duration: MovementDuration,
+    elapsed: u32,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum MovementDuration {
+    Ticks(u32),
+    /// Blocks per tick, converted to ticks once the start is known.
+    Speed(f64),
+}
+
+impl Default for SmoothMovement {
+    fn default() -> Self {
+        Self {
+            turn_speed: 30.0,
+            face_movement: true,
+            easing: Easing::Linear,
+            movement: None,
+            look_target: None,
+        }
+    }
+}
+
+impl SmoothMovement {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the entity to `to` over `ticks` ticks, replacing the current
+    /// movement.
+    pub fn move_to(&mut self, to: DVec3, ticks: u32) {
+        self.movement = Some(Movement {
+            from: None,
+            to,
+            duration: MovementDuration::Ticks(ticks.max(1)),
+            elapsed: 0,
+        });
+    }
+
+    /// Moves the entity to `to` at `speed` blocks per tick, replacing the
+    /// current movement.
+    pub fn move_to_at_speed(&mut self, to: DVec3, speed: f64) {
+        self.movement = Some(Movement {
+            from: None,
+            to,
+            duration: MovementDuration::Speed(speed),
+            elapsed: 0,
+        });
+    }
+
+    /// Stops the current movement where the entity is now.
+    pub fn stop(&mut self) {
+        self.movement = None;
+    }
+
+    /// Returns whether the entity is currently moving to a target.
+    pub fn is_moving(&self) -> bool {
+        self.movement.is_some()
+    }
+
+    /// Returns the position the entity is moving to.
+    pub fn target(&self) -> Option<DVec3> {
+        self.movement.map(|m| m.to)
+    }
+
+    /// Turns the entity toward `look` until the look target is cleared.
+    pub fn set_look_target(&mut self, look: Look) {
+        self.look_target = Some(look);
+    }
+
+    /// Turns the entity toward the point `target` as seen from `eyes`.
+    pub fn look_at(&mut self, eyes: DVec3, target: DVec3) {
+        let dir = (target - eyes).as_vec3().normalize_or_zero();
+        if dir != Vec3::ZERO {
+            let mut look = self.look_target.unwrap_or_default();
+            look.set_vec(dir);
+            self.look_target = Some(look);
+        }
+    }
+
+    pub fn clear_look_target(&mut self) {
+        self.look_target = None;
+    }
+}
+
+/// Sent when an entity with [`SmoothMovement`] reaches its target.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SmoothMovementFinishedEvent {
+    pub entity: Entity,
+}
+
+/// Returns the difference from `from` to `to` in degrees along the shortest
+/// arc, in the range `-180..180`.
+fn angle_diff(from: f32, to: f32) -> f32 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Turns `from` toward `to` by at most `max_step` degrees.
+fn turn_toward(from: f32, to: f32, max_step: f32) -> f32 {
+    let diff = angle_diff(from, to);
+    let step = diff.clamp(-max_step, max_step);
+    let angle = from + step;
+
+    // Keep angles in the range -180..180.
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn update_smooth_movement(
+    mut entities: Query<(
+        Entity,
+        &mut SmoothMovement,
+        &mut Position,
+        &mut Look,
+        &mut HeadYaw,
+        &mut Velocity,
+    )>,
+    server: Res<Server>,
+    mut finished: EventWriter<SmoothMovementFinishedEvent>,
+) {
+    let tick_rate = server.tick_rate().get() as f32;
+
+    for (entity, mut smooth, mut pos, mut look, mut head_yaw, mut velocity) in &mut entities {
+        let smooth = &mut *smooth;
+        let mut delta = DVec3::ZERO;
+
+        if let Some(movement) = &mut smooth.movement {
+            let from = *movement.from.get_or_insert(pos.0);
+
+            let ticks = match movement.duration {
+                MovementDuration::Ticks(ticks) => ticks,
+                MovementDuration::Speed(speed) => {
+                    let ticks = (from.distance(movement.to) / speed.max(f64::EPSILON)).ceil();
+                    (ticks as u32).max(1)
+                }
+            };
+            movement.duration = MovementDuration::Ticks(ticks);
+            movement.elapsed += 1;
+
+            let progress = smooth.easing.apply(movement.elapsed as f64 / ticks as f64);
+            let new_pos = from.lerp(movement.to, progress);
+
+            delta = new_pos - pos.0;
+            if delta != DVec3::ZERO {
+                pos.0 = new_pos;
+            }
+
+            if movement.elapsed >= ticks {
+                smooth.movement = None;
+                finished.send(SmoothMovementFinishedEvent { entity });
+            }
+        }
+
+        // Keep the velocity in sync with the movement so the client predicts
+        // the entity's position between updates.
+        let new_velocity = delta.as_vec3() * tick_rate;
+        if velocity.0 != new_velocity {
+            velocity.0 = new_velocity;
+        }
+
+        let target_look = smooth.look_target.or_else(|| {
+            let horizontal = Vec3::new(delta.x as f32, 0.0, delta.z as f32);
+
+            (smooth.face_movement && horizontal.length_squared() > 1e-8).then(|| {
+                let mut facing = Look::new(look.yaw, 0.0);
+                facing.set_vec(horizontal.normalize());
+                facing
+            })
+        });
+
+        if let Some(target) = target_look {
+            let new_look = Look::new(
+                turn_toward(look.yaw, target.yaw, smooth.turn_speed),
+                turn_toward(look.pitch, target.pitch, smooth.turn_speed),
+            );
+
+            if *look != new_look {
+                *look = new_look;
+            }
+        }
+
+        // The head faces the same way as the body so it doesn't snap.
+        if head_yaw.0 != look.yaw {
+            head_yaw.0 = look.yaw;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turning_takes_the_shortest_arc() {
+        assert_eq!(angle_diff(170.0, -170.0), 20.0);
+        assert_eq!(angle_diff(-170.0, 170.0), -20.0);
+
+        assert_eq!(turn_toward(170.0, -170.0, 5.0), 175.0);
+        assert_eq!(turn_toward(175.0, -170.0, 30.0), -170.0);
+        assert_eq!(turn_toward(0.0, 90.0, 30.0), 30.0);
+    }
+
+    #[test]
+    fn easing() {
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::EaseInOut.apply(0.0), 0.0);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::EaseInOut.apply(1.0), 1.0);
+        assert!(Easing::EaseInOut.apply(0.1) < 0.1);
+    }
+}
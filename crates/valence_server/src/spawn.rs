@@ -6,9 +6,10 @@ use std::collections::BTreeSet;
 use bevy_ecs::prelude::*;
 use bevy_ecs::query::WorldQuery;
 use derive_more::{Deref, DerefMut};
-use valence_entity::EntityLayerId;
-use valence_protocol::packets::play::{GameJoinS2c, PlayerRespawnS2c, PlayerSpawnPositionS2c};
-use valence_protocol::{BlockPos, GameMode, GlobalPos, Ident, VarInt, WritePacket};
+use valence_protocol::packets::play::{
+    FeaturesS2c, GameJoinS2c, PlayerRespawnS2c, PlayerSpawnPositionS2c,
+};
+use valence_protocol::{ident, BlockPos, GameMode, GlobalPos, Ident, VarInt, WritePacket};
 use valence_registry::tags::TagsRegistry;
 use valence_registry::{BiomeRegistry, RegistryCodec};
 
@@ -54,6 +55,18 @@ impl Default for HasRespawnScreen {
     }
 }
 
+/// The feature flags enabled for the client, such as `minecraft:bundle` for
+/// experimental features. Only sent when the client joins, so changes made
+/// afterwards have no effect.
+#[derive(Component, Clone, PartialEq, Eq, Debug, Deref, DerefMut)]
+pub struct EnabledFeatures(pub BTreeSet<Ident<String>>);
+
+impl Default for EnabledFeatures {
+    fn default() -> Self {
+        Self(BTreeSet::from([ident!("minecraft:vanilla").into()]))
+    }
+}
+
 /// The position and angle that clients will respawn with. Also
 /// controls the position that compasses point towards.
 #[derive(Component, Copy, Clone, PartialEq, Default, Debug)]
@@ -81,6 +94,7 @@ pub struct ClientSpawnQuery {
     pub is_flat: &'static mut IsFlat,
     pub death_loc: &'static mut DeathLocation,
     pub portal_cooldown: &'static mut PortalCooldown,
+    pub enabled_features: &'static mut EnabledFeatures,
 }
 
 pub(super) fn initial_join(
@@ -107,6 +121,15 @@ pub(super) fn initial_join(
             position: *pos,
         });
 
+        let registry_codec = match client.enc.translator() {
+            Some(translator) => {
+                let mut codec = codec.cached_codec().clone();
+                translator.registry_codec(&mut codec);
+                Cow::Owned(codec)
+            }
+            None => Cow::Borrowed(codec.cached_codec()),
+        };
+
         // The login packet is prepended so that it's sent before all the other packets.
         // Some packets don't work correctly when sent before the game join packet.
         _ = client.enc.prepend_packet(&GameJoinS2c {
@@ -115,7 +138,7 @@ pub(super) fn initial_join(
             game_mode: *spawn.game_mode,
             previous_game_mode: spawn.prev_game_mode.0.into(),
             dimension_names: Cow::Owned(dimension_names),
-            registry_codec: Cow::Borrowed(codec.cached_codec()),
+            registry_codec,
             dimension_type_name: dimension_name.clone(),
             dimension_name,
             hashed_seed: spawn.hashed_seed.0 as i64,
@@ -130,67 +153,79 @@ pub(super) fn initial_join(
             portal_cooldown: VarInt(spawn.portal_cooldown.0),
         });
 
-        client.write_packet_bytes(tags.sync_tags_packet());
+        client.write_packet(&FeaturesS2c {
+            features: Cow::Borrowed(&spawn.enabled_features.0),
+        });
 
-        /*
-        // TODO: enable all the features?
-        q.client.write_packet(&FeatureFlags {
-            features: vec![Ident::new("vanilla").unwrap()],
-        })?;
-        */
+        client.write_packet_bytes(tags.sync_tags_packet());
     }
 }
 
 pub(super) fn respawn(
     mut clients: Query<
         (
+            Entity,
             &mut Client,
-            &EntityLayerId,
-            &DeathLocation,
-            &HashedSeed,
-            &GameMode,
-            &PrevGameMode,
-            &IsDebug,
-            &IsFlat,
+            &VisibleChunkLayer,
+            ClientSpawnQueryReadOnly,
+            Has<RespawnWritten>,
         ),
         Changed<VisibleChunkLayer>,
     >,
     chunk_layers: Query<&ChunkLayer>,
+    mut commands: Commands,
 ) {
-    for (mut client, loc, death_loc, hashed_seed, game_mode, prev_game_mode, is_debug, is_flat) in
-        &mut clients
-    {
+    for (entity, mut client, visible_chunk_layer, spawn, respawn_written) in &mut clients {
+        if respawn_written {
+            // The respawn packet was already written by `SwitchLayer`.
+            commands.entity(entity).remove::<RespawnWritten>();
+            continue;
+        }
+
         if client.is_added() {
             // No need to respawn since we are sending the game join packet this tick.
             continue;
         }
 
-        let Ok(chunk_layer) = chunk_layers.get(loc.0) else {
+        let Ok(chunk_layer) = chunk_layers.get(visible_chunk_layer.0) else {
             continue;
         };
 
-        let dimension_name = chunk_layer.dimension_type_name();
-
-        let last_death_location = death_loc.0.as_ref().map(|(id, pos)| GlobalPos {
-            dimension_name: id.as_str_ident().into(),
-            position: *pos,
-        });
-
-        client.write_packet(&PlayerRespawnS2c {
-            dimension_type_name: dimension_name.into(),
-            dimension_name: dimension_name.into(),
-            hashed_seed: hashed_seed.0,
-            game_mode: *game_mode,
-            previous_game_mode: prev_game_mode.0.into(),
-            is_debug: is_debug.0,
-            is_flat: is_flat.0,
-            copy_metadata: true,
-            last_death_location,
-            portal_cooldown: VarInt(0), // TODO
-        });
+        write_respawn_packet(&mut client, chunk_layer.dimension_type_name(), &spawn);
     }
 }
 
+/// Marks clients whose respawn packet for a change of [`VisibleChunkLayer`]
+/// was already written this tick by [`SwitchLayer`].
+///
+/// [`SwitchLayer`]: crate::switch_layer::SwitchLayer
+#[derive(Component, Copy, Clone, Debug)]
+pub(crate) struct RespawnWritten;
+
+pub(crate) fn write_respawn_packet(
+    client: &mut Client,
+    dimension_name: Ident<&str>,
+    spawn: &ClientSpawnQueryReadOnlyItem,
+) {
+    let last_death_location = spawn.death_loc.0.as_ref().map(|(id, pos)| GlobalPos {
+        dimension_name: id.as_str_ident().into(),
+        position: *pos,
+    });
+
+    client.write_packet(&PlayerRespawnS2c {
+        dimension_type_name: dimension_name.into(),
+        dimension_name: dimension_name.into(),
+        hashed_seed: spawn.hashed_seed.0,
+        game_mode: *spawn.game_mode,
+        previous_game_mode: spawn.prev_game_mode.0.into(),
+        is_debug: spawn.is_debug.0,
+        is_flat: spawn.is_flat.0,
+        copy_metadata: true,
+        last_death_location,
+        portal_cooldown: VarInt(spawn.portal_cooldown.0),
+    });
+}
+
 /// Sets the client's respawn and compass position.
 ///
 /// This also closes the "downloading terrain" screen when first joining, so
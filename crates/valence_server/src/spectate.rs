@@ -0,0 +1,281 @@
+//! Watching other entities through their eyes.
+//!
+//! The [`Spectate`] command attaches the camera of a client to another entity,
+//! usually a player in spectator mode watching a minigame. While
+//! [`Spectating`], the client is kept at the position of its target, so the
+//! chunks and entities around the target stay loaded, and follows the target
+//! into other layers. The camera is restored with [`StopSpectating`], when the
+//! target is despawned, or when the client sneaks.
+//!
+//! Clients can only leave the camera of another entity on their own in
+//! spectator mode, so spectating clients are usually put in
+//! [`GameMode::Spectator`](valence_protocol::GameMode::Spectator) first.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::spectate::Spectate;
+//! use valence_server::GameMode;
+//!
+//! /// Makes a client which lost watch the player who is still alive.
+//! fn watch_winner(
+//!     mut losers: Query<(Entity, &mut GameMode)>,
+//!     winner: Entity,
+//!     mut commands: Commands,
+//! ) {
+//!     for (client, mut game_mode) in &mut losers {
+//!         *game_mode = GameMode::Spectator;
+//!         commands.add(Spectate::new(client, winner));
+//!     }
+//! }
+//! # let _ = watch_winner;
+//! ```
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use valence_entity::{EntityId, EntityLayerId, Position};
+use valence_math::DVec3;
+use valence_protocol::packets::play::client_command_c2s::ClientCommand;
+use valence_protocol::packets::play::ClientCommandC2s;
+use valence_server_common::Despawned;
+
+use crate::client::{
+    update_view_and_layers, Client, UpdateClientsSet, VisibleChunkLayer, VisibleEntityLayers,
+};
+use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::layer::{ChunkLayer, UpdateLayersPreClientSet};
+use crate::protocol_error::ProtocolErrorSink;
+use crate::teleport::TeleportState;
+
+pub struct SpectatePlugin;
+
+impl Plugin for SpectatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StopSpectatingEvent>()
+            .add_systems(EventLoopPreUpdate, handle_sneaking)
+            .add_systems(
+                PostUpdate,
+                (
+                    follow_targets.before(UpdateLayersPreClientSet),
+                    update_cameras
+                        .after(update_view_and_layers)
+                        .in_set(UpdateClientsSet),
+                ),
+            );
+    }
+}
+
+/// Component for clients which see through the eyes of another entity. Added
+/// by [`Spectate`] and removed by [`StopSpectating`].
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Spectating {
+    target: Entity,
+    exit_on_sneak: bool,
+    camera_sent: bool,
+}
+
+impl Spectating {
+    /// The entity the client is watching.
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+
+    /// Whether the client stops spectating when it sneaks.
+    pub fn exit_on_sneak(&self) -> bool {
+        self.exit_on_sneak
+    }
+}
+
+/// A [`Command`] to attach the camera of a client to `target`. A client which
+/// is already spectating switches to the new target.
+///
+/// Targets which are spectating another entity themselves can't be watched.
+/// The client only follows its target into another layer if the target's
+/// [`EntityLayerId`] also has a [`ChunkLayer`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Spectate {
+    pub client: Entity,
+    pub target: Entity,
+    /// Whether the client stops spectating when it sneaks, like in vanilla.
+    ///
+    /// # Default Value
+    ///
+    /// `true`
+    pub exit_on_sneak: bool,
+}
+
+impl Spectate {
+    pub fn new(client: Entity, target: Entity) -> Self {
+        Self {
+            client,
+            target,
+            exit_on_sneak: true,
+        }
+    }
+
+    pub fn with_exit_on_sneak(mut self, exit_on_sneak: bool) -> Self {
+        self.exit_on_sneak = exit_on_sneak;
+        self
+    }
+}
+
+/// A [`Command`] to give a spectating client its own view back. The client is
+/// left at the last position of its target. Does nothing if the client is not
+/// spectating.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StopSpectating {
+    pub client: Entity,
+}
+
+/// Sent when a client stops spectating, including when it sneaked or its
+/// target was despawned.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StopSpectatingEvent {
+    pub client: Entity,
+    pub target: Entity,
+}
+
+impl Command for Spectate {
+    fn apply(self, world: &mut World) {
+        if self.client == self.target
+            || !world
+                .get_entity(self.target)
+                .is_some_and(|target| target.contains::<Position>())
+        {
+            return;
+        }
+
+        let Some(mut client) = world.get_entity_mut(self.client) else {
+            return;
+        };
+
+        if !client.contains::<Client>() {
+            return;
+        }
+
+        client.insert(Spectating {
+            target: self.target,
+            exit_on_sneak: self.exit_on_sneak,
+            camera_sent: false,
+        });
+    }
+}
+
+impl Command for StopSpectating {
+    fn apply(self, world: &mut World) {
+        let Some(mut entity) = world.get_entity_mut(self.client) else {
+            return;
+        };
+
+        let Some(spectating) = entity.take::<Spectating>() else {
+            return;
+        };
+
+        if let Some(mut client) = entity.get_mut::<Client>() {
+            client.reset_camera();
+        }
+
+        // The client still thinks it is where it started spectating, so
+        // teleport it to where it is now.
+        if let Some(mut state) = entity.get_mut::<TeleportState>() {
+            state.synced_pos = DVec3::NAN;
+        }
+
+        if let Some(mut pos) = entity.get_mut::<Position>() {
+            pos.set_changed();
+        }
+
+        world.send_event(StopSpectatingEvent {
+            client: self.client,
+            target: spectating.target,
+        });
+    }
+}
+
+fn handle_sneaking(
+    mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
+    clients: Query<&Spectating>,
+    mut commands: Commands,
+) {
+    for packet in packets.read() {
+        if let Some(pkt) = packet.decode_reporting::<ClientCommandC2s>(&errors) {
+            if pkt.action == ClientCommand::StartSneaking
+                && clients
+                    .get(packet.client)
+                    .is_ok_and(|spectating| spectating.exit_on_sneak)
+            {
+                commands.add(StopSpectating {
+                    client: packet.client,
+                });
+            }
+        }
+    }
+}
+
+/// Moves spectating clients to their targets without teleporting them, so
+/// the view of the client is around the target.
+fn follow_targets(
+    mut clients: Query<(
+        Entity,
+        &mut Spectating,
+        &mut Position,
+        &mut TeleportState,
+        &mut EntityLayerId,
+        &mut VisibleChunkLayer,
+        &mut VisibleEntityLayers,
+    )>,
+    targets: Query<(&Position, &EntityLayerId), (Without<Spectating>, Without<Despawned>)>,
+    chunk_layers: Query<(), With<ChunkLayer>>,
+    mut commands: Commands,
+) {
+    for (
+        entity,
+        mut spectating,
+        mut pos,
+        mut state,
+        mut layer_id,
+        mut chunk_layer,
+        mut entity_layers,
+    ) in &mut clients
+    {
+        let Ok((target_pos, target_layer)) = targets.get(spectating.target) else {
+            commands.add(StopSpectating { client: entity });
+            continue;
+        };
+
+        if pos.0 != target_pos.0 {
+            pos.0 = target_pos.0;
+            state.synced_pos = target_pos.0;
+        }
+
+        if layer_id.0 != target_layer.0 {
+            entity_layers.0.remove(&layer_id.0);
+            entity_layers.0.insert(target_layer.0);
+            layer_id.0 = target_layer.0;
+
+            if chunk_layer.0 != target_layer.0 && chunk_layers.contains(target_layer.0) {
+                // Respawning in the new layer resets the camera.
+                chunk_layer.0 = target_layer.0;
+                spectating.camera_sent = false;
+            }
+        }
+    }
+}
+
+/// Sends the camera of spectating clients after the target was spawned for
+/// them.
+fn update_cameras(mut clients: Query<(&mut Client, &mut Spectating)>, targets: Query<&EntityId>) {
+    for (mut client, mut spectating) in &mut clients {
+        if spectating.camera_sent {
+            continue;
+        }
+
+        if let Ok(id) = targets.get(spectating.target) {
+            client.set_camera(id.get());
+            spectating.camera_sent = true;
+        }
+    }
+}
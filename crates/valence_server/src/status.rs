@@ -3,6 +3,7 @@ use bevy_ecs::prelude::*;
 use valence_protocol::packets::play::ClientStatusC2s;
 
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 
 pub struct StatusPlugin;
 
@@ -26,11 +27,12 @@ pub struct RequestStatsEvent {
 
 fn handle_status(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut respawn_events: EventWriter<RequestRespawnEvent>,
     mut request_stats_events: EventWriter<RequestStatsEvent>,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<ClientStatusC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<ClientStatusC2s>(&errors) {
             match pkt {
                 ClientStatusC2s::PerformRespawn => respawn_events.send(RequestRespawnEvent {
                     client: packet.client,
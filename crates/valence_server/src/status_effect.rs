@@ -56,7 +56,7 @@ fn update_active_status_effects(
     }
 }
 
-fn create_packet(effect: &ActiveStatusEffect) -> EntityStatusEffectS2c {
+pub(crate) fn create_packet(effect: &ActiveStatusEffect) -> EntityStatusEffectS2c {
     EntityStatusEffectS2c {
         entity_id: VarInt(0), // We reserve ID 0 for clients.
         effect_id: VarInt(effect.status_effect().to_raw() as i32),
@@ -0,0 +1,285 @@
+//! Moving clients between layers.
+//!
+//! Changing the [`VisibleChunkLayer`] of a client respawns it in the new layer,
+//! which resets much of what the client knows about itself, such as its
+//! health, experience and status effects. The [`SwitchLayer`] command moves a
+//! client to another layer and position in one step, writing the respawn
+//! packet before anything else and then either sending the client's state
+//! again or resetting it, depending on the [`SwitchLayerConfig`].
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use valence_server::switch_layer::{SwitchLayer, SwitchLayerConfig};
+//!
+//! /// Sends a client to the spawn of the lobby, healed and without effects.
+//! fn send_to_lobby(commands: &mut Commands, client: Entity, lobby: Entity) {
+//!     commands.add(
+//!         SwitchLayer::new(client, lobby, [0.5, 64.0, 0.5]).with_config(SwitchLayerConfig {
+//!             keep_state: false,
+//!             ..Default::default()
+//!         }),
+//!     );
+//! }
+//! # let _ = send_to_lobby;
+//! ```
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+use valence_entity::active_status_effects::ActiveStatusEffects;
+use valence_entity::attributes::{EntityAttribute, EntityAttributes};
+use valence_entity::living::Health;
+use valence_entity::player::{Food, Saturation};
+use valence_entity::{EntityLayerId, Look, Position};
+use valence_math::DVec3;
+use valence_protocol::{BlockPos, GameMode, WritePacket};
+
+use crate::abilities::PlayerAbilitiesFlags;
+use crate::client::{Client, VisibleChunkLayer, VisibleEntityLayers};
+use crate::experience::{ExperienceLevel, ExperiencePoints};
+use crate::layer::ChunkLayer;
+use crate::spawn::{
+    write_respawn_packet, ClientSpawnQueryReadOnly, RespawnPosition, RespawnWritten,
+};
+use crate::teleport::TeleportState;
+
+/// What happens to a client when it is moved by [`SwitchLayer`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SwitchLayerConfig {
+    /// Whether the client keeps seeing its other visible entity layers.
+    /// Otherwise, only the entities of the new layer are visible.
+    ///
+    /// # Default Value
+    ///
+    /// `false`
+    pub keep_entity_layers: bool,
+    /// Whether the client keeps its health, food, experience, abilities and
+    /// status effects, which are sent to it again after respawning.
+    /// Otherwise, health and food are restored, experience is set to zero and
+    /// status effects are removed.
+    ///
+    /// # Default Value
+    ///
+    /// `true`
+    pub keep_state: bool,
+    /// The direction the client looks in after switching. `None` keeps the
+    /// current direction.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub look: Option<Look>,
+    /// The game mode of the client after switching. `None` keeps the current
+    /// game mode.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub game_mode: Option<GameMode>,
+    /// Whether the [`RespawnPosition`] of the client is moved to the
+    /// destination.
+    ///
+    /// # Default Value
+    ///
+    /// `false`
+    pub set_respawn_position: bool,
+}
+
+impl Default for SwitchLayerConfig {
+    fn default() -> Self {
+        Self {
+            keep_entity_layers: false,
+            keep_state: true,
+            look: None,
+            game_mode: None,
+            set_respawn_position: false,
+        }
+    }
+}
+
+/// A [`Command`] to move a client to `position` in the layer `layer`, which
+/// becomes its [`EntityLayerId`], [`VisibleChunkLayer`] and visible entity
+/// layer. If the client is already in the layer, it is only teleported.
+///
+/// Nothing happens if the client doesn't exist or `layer` doesn't have a
+/// [`ChunkLayer`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SwitchLayer {
+    pub client: Entity,
+    pub layer: Entity,
+    pub position: DVec3,
+    pub config: SwitchLayerConfig,
+}
+
+impl SwitchLayer {
+    pub fn new(client: Entity, layer: Entity, position: impl Into<DVec3>) -> Self {
+        Self {
+            client,
+            layer,
+            position: position.into(),
+            config: SwitchLayerConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: SwitchLayerConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl Command for SwitchLayer {
+    fn apply(self, world: &mut World) {
+        let Some(dimension_name) = world
+            .get::<ChunkLayer>(self.layer)
+            .map(|layer| layer.dimension_type_name().to_string_ident())
+        else {
+            return;
+        };
+
+        let Some(mut entity) = world.get_entity_mut(self.client) else {
+            return;
+        };
+
+        let Some(old_layer) = entity.get::<VisibleChunkLayer>().map(|layer| layer.0) else {
+            return;
+        };
+
+        let config = self.config;
+
+        if let (Some(game_mode), Some(mut current)) =
+            (config.game_mode, entity.get_mut::<GameMode>())
+        {
+            current.set_if_neq(game_mode);
+        }
+
+        if let Some(mut layer_id) = entity.get_mut::<EntityLayerId>() {
+            layer_id.set_if_neq(EntityLayerId(self.layer));
+        }
+
+        if let Some(mut visible) = entity.get_mut::<VisibleEntityLayers>() {
+            if config.keep_entity_layers {
+                visible.0.remove(&old_layer);
+            } else {
+                visible.0.clear();
+            }
+
+            visible.0.insert(self.layer);
+        }
+
+        if let Some(mut pos) = entity.get_mut::<Position>() {
+            pos.0 = self.position;
+        }
+
+        if let (Some(look), Some(mut current)) = (config.look, entity.get_mut::<Look>()) {
+            *current = look;
+        }
+
+        if config.set_respawn_position {
+            if let Some(mut respawn_pos) = entity.get_mut::<RespawnPosition>() {
+                respawn_pos.pos = BlockPos::from(self.position);
+            }
+        }
+
+        // Always teleport, even if the position didn't change.
+        if let Some(mut state) = entity.get_mut::<TeleportState>() {
+            state.synced_pos = DVec3::NAN;
+        }
+
+        if old_layer == self.layer {
+            return;
+        }
+
+        entity.get_mut::<VisibleChunkLayer>().unwrap().0 = self.layer;
+
+        // Write the respawn packet now so that everything written during the
+        // rest of the tick arrives after it.
+        let mut query = world.query::<(&mut Client, ClientSpawnQueryReadOnly)>();
+
+        let Ok((mut client, spawn)) = query.get_mut(world, self.client) else {
+            return;
+        };
+
+        write_respawn_packet(&mut client, dimension_name.as_str_ident(), &spawn);
+
+        let mut entity = world.entity_mut(self.client);
+
+        entity.insert(RespawnWritten);
+
+        if let Some(mut respawn_pos) = entity.get_mut::<RespawnPosition>() {
+            // The spawn position also closes the loading screen.
+            respawn_pos.set_changed();
+        }
+
+        if let Some(mut flags) = entity.get_mut::<PlayerAbilitiesFlags>() {
+            flags.set_changed();
+        }
+
+        if config.keep_state {
+            resend_state(&mut entity);
+        } else {
+            reset_state(&mut entity);
+        }
+    }
+}
+
+/// Makes the systems which sync the client's state send it again, and sends
+/// the active status effects.
+fn resend_state(entity: &mut EntityWorldMut) {
+    if let Some(mut health) = entity.get_mut::<Health>() {
+        health.set_changed();
+    }
+
+    if let Some(mut level) = entity.get_mut::<ExperienceLevel>() {
+        level.set_changed();
+    }
+
+    let effects: Vec<_> = entity
+        .get::<ActiveStatusEffects>()
+        .map(|effects| {
+            effects
+                .get_current_effects()
+                .into_iter()
+                .map(crate::status_effect::create_packet)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Some(mut client) = entity.get_mut::<Client>() {
+        for packet in &effects {
+            client.write_packet(packet);
+        }
+    }
+}
+
+fn reset_state(entity: &mut EntityWorldMut) {
+    let max_health = entity
+        .get::<EntityAttributes>()
+        .and_then(|attributes| attributes.get_compute_value(EntityAttribute::GenericMaxHealth))
+        .unwrap_or(20.0);
+
+    if let Some(mut health) = entity.get_mut::<Health>() {
+        health.0 = max_health as f32;
+    }
+
+    if let Some(mut food) = entity.get_mut::<Food>() {
+        *food = Food::default();
+    }
+
+    if let Some(mut saturation) = entity.get_mut::<Saturation>() {
+        *saturation = Saturation::default();
+    }
+
+    if let Some(mut level) = entity.get_mut::<ExperienceLevel>() {
+        level.0 = 0;
+    }
+
+    if let Some(mut points) = entity.get_mut::<ExperiencePoints>() {
+        points.0 = 0;
+    }
+
+    if let Some(mut effects) = entity.get_mut::<ActiveStatusEffects>() {
+        // The client already lost its effects when respawning.
+        effects.remove_all();
+    }
+}
@@ -9,6 +9,7 @@ use valence_protocol::WritePacket;
 
 use crate::client::{update_view_and_layers, Client, UpdateClientsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::protocol_error::ProtocolErrorSink;
 use crate::spawn::update_respawn_position;
 
 pub struct TeleportPlugin;
@@ -105,11 +106,12 @@ fn teleport(
 
 fn handle_teleport_confirmations(
     mut packets: EventReader<PacketEvent>,
+    errors: Res<ProtocolErrorSink>,
     mut clients: Query<&mut TeleportState>,
     mut commands: Commands,
 ) {
     for packet in packets.read() {
-        if let Some(pkt) = packet.decode::<TeleportConfirmC2s>() {
+        if let Some(pkt) = packet.decode_reporting::<TeleportConfirmC2s>(&errors) {
             if let Ok(mut state) = clients.get_mut(packet.client) {
                 if state.pending_teleports == 0 {
                     warn!(
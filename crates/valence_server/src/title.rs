@@ -1,8 +1,39 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
 use valence_protocol::encode::WritePacket;
 use valence_protocol::packets::play::{
     ClearTitleS2c, OverlayMessageS2c, SubtitleS2c, TitleFadeS2c, TitleS2c,
 };
-use valence_protocol::text::IntoText;
+use valence_protocol::text::{IntoText, Text};
+
+use crate::client::{Client, FlushPacketsSet};
+
+pub struct TitlePlugin;
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, animate_text.before(FlushPacketsSet));
+    }
+}
+
+/// How long a title fades in, stays, and fades out, in ticks.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TitleTimes {
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+/// The times used by the vanilla client when none are set.
+impl Default for TitleTimes {
+    fn default() -> Self {
+        Self {
+            fade_in: 10,
+            stay: 70,
+            fade_out: 20,
+        }
+    }
+}
 
 pub trait SetTitle {
     /// Displays a title to a client.
@@ -25,6 +56,19 @@ pub trait SetTitle {
     fn clear_title(&mut self);
 
     fn reset_title(&mut self);
+
+    /// Displays a title and subtitle with the given times in one go.
+    fn show_title<'a, 'b>(
+        &mut self,
+        title: impl IntoText<'a>,
+        subtitle: impl IntoText<'b>,
+        times: TitleTimes,
+    ) {
+        self.set_title_times(times.fade_in, times.stay, times.fade_out);
+        // The subtitle is only shown once the title packet arrives.
+        self.set_subtitle(subtitle);
+        self.set_title(title);
+    }
 }
 
 impl<T: WritePacket> SetTitle for T {
@@ -62,3 +106,167 @@ impl<T: WritePacket> SetTitle for T {
         self.write_packet(&ClearTitleS2c { reset: true });
     }
 }
+
+/// Where the frames of a [`TextAnimation`] are shown.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AnimationTarget {
+    ActionBar,
+    Title,
+    /// The subtitle is only visible while a title is displayed.
+    Subtitle,
+}
+
+/// Component for clients which cycles through frames of text in the action
+/// bar, title, or subtitle. The component removes itself once the last frame
+/// has been shown, unless it is repeating.
+///
+/// A single frame shown for a number of ticks can be used to keep the action
+/// bar visible for longer than the client would by itself.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct TextAnimation {
+    pub target: AnimationTarget,
+    pub frames: Vec<Text>,
+    /// The number of ticks each frame is shown for.
+    pub frame_ticks: u32,
+    /// Whether the animation starts over after the last frame.
+    pub repeat: bool,
+    tick: u32,
+}
+
+impl TextAnimation {
+    pub fn new(target: AnimationTarget, frames: Vec<Text>, frame_ticks: u32) -> Self {
+        Self {
+            target,
+            frames,
+            frame_ticks: frame_ticks.max(1),
+            repeat: false,
+            tick: 0,
+        }
+    }
+
+    /// Creates an animation which scrolls `text` through a window of `width`
+    /// characters, one character per frame.
+    pub fn scrolling(target: AnimationTarget, text: &str, width: usize, frame_ticks: u32) -> Self {
+        let frames = scrolling_frames(text, width)
+            .into_iter()
+            .map(Text::from)
+            .collect();
+
+        Self::new(target, frames, frame_ticks).repeating()
+    }
+
+    pub fn repeating(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+
+    /// Returns the index of the frame currently shown.
+    pub fn current_frame(&self) -> usize {
+        let frame = (self.tick / self.frame_ticks) as usize;
+
+        if self.repeat && !self.frames.is_empty() {
+            frame % self.frames.len()
+        } else {
+            frame
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        !self.repeat && self.current_frame() >= self.frames.len()
+    }
+}
+
+/// Returns the frames of `text` scrolling through a window of `width`
+/// characters. Text which already fits is returned as a single frame.
+///
+/// ```
+/// # use valence_server::title::scrolling_frames;
+/// assert_eq!(scrolling_frames("abc", 2), ["ab", "bc", "c ", " a"]);
+/// ```
+pub fn scrolling_frames(text: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+
+    if chars.len() <= width {
+        return vec![text.to_owned()];
+    }
+
+    // Pad with a space so the end of the text is separated from its start.
+    let cycle: Vec<char> = chars.iter().copied().chain([' ']).collect();
+
+    (0..cycle.len())
+        .map(|start| {
+            (0..width)
+                .map(|i| cycle[(start + i) % cycle.len()])
+                .collect()
+        })
+        .collect()
+}
+
+/// The client fades out the action bar after a few seconds, so it has to be
+/// sent again periodically.
+const ACTION_BAR_REFRESH_TICKS: u32 = 40;
+
+fn animate_text(
+    mut clients: Query<(Entity, &mut Client, &mut TextAnimation)>,
+    mut commands: Commands,
+) {
+    for (entity, mut client, mut anim) in &mut clients {
+        if anim.is_finished() || anim.frames.is_empty() {
+            commands.entity(entity).remove::<TextAnimation>();
+            continue;
+        }
+
+        let frame_start = anim.tick.is_multiple_of(anim.frame_ticks);
+        let frame = &anim.frames[anim.current_frame()];
+
+        match anim.target {
+            AnimationTarget::ActionBar
+                if frame_start || anim.tick.is_multiple_of(ACTION_BAR_REFRESH_TICKS) =>
+            {
+                client.set_action_bar(frame);
+            }
+            AnimationTarget::Title | AnimationTarget::Subtitle if frame_start => {
+                // Keep the title up until the next frame without fading.
+                client.set_title_times(0, anim.frame_ticks as i32 + 1, 0);
+
+                if anim.target == AnimationTarget::Title {
+                    client.set_title(frame);
+                } else {
+                    client.set_subtitle(frame);
+                }
+            }
+            _ => {}
+        }
+
+        anim.tick += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animation_frames() {
+        let mut anim = TextAnimation::new(
+            AnimationTarget::ActionBar,
+            vec!["a".into_text(), "b".into_text()],
+            2,
+        );
+
+        let frames: Vec<_> = (0..4)
+            .map(|_| {
+                let frame = anim.current_frame();
+                anim.tick += 1;
+                frame
+            })
+            .collect();
+
+        assert_eq!(frames, [0, 0, 1, 1]);
+        assert!(anim.is_finished());
+
+        anim.repeat = true;
+        assert_eq!(anim.current_frame(), 0);
+        assert!(!anim.is_finished());
+    }
+}
@@ -0,0 +1,225 @@
+//! Moving clients to other servers behind a proxy, and cookies which keep data
+//! about clients across transfers.
+//!
+//! Minecraft 1.20.1 has no transfer packet, so clients can only be moved to
+//! another server by the proxy they are connected through. [`Client::transfer`]
+//! asks the proxy to do so with the `Connect` message of the BungeeCord plugin
+//! channel. BungeeCord understands it out of the box, and Velocity does when
+//! `bungee-plugin-message-channel` is enabled in its configuration. Without a
+//! proxy the message is ignored by the client.
+//!
+//! Clients of this version can't store cookies either, so [`Cookies`] keeps
+//! them on the server, keyed by the UUID of the client. The UUID stays the
+//! same when a client is transferred away and back or reconnects, so the
+//! cookies can be read again while the client joins.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, ensure};
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use uuid::Uuid;
+use valence_protocol::{ident, Ident};
+
+use crate::client::Client;
+use crate::custom_payload::PluginMessage;
+
+pub struct TransferPlugin;
+
+impl Plugin for TransferPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Cookies>();
+    }
+}
+
+impl Client {
+    /// Asks the proxy this client is connected through to move it to the
+    /// server named `server` in the proxy's configuration. See the
+    /// [module docs](self) for the proxies supported.
+    pub fn transfer(&mut self, server: &str) {
+        self.send_typed_plugin_message(&BungeeCordMessage::Connect {
+            server: server.into(),
+        });
+    }
+}
+
+/// A request to the proxy on the BungeeCord plugin channel, which is sent
+/// through any client connected to the proxy.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BungeeCordMessage {
+    /// Moves the client the message is sent through to `server`.
+    Connect { server: String },
+    /// Moves the player named `player` to `server`.
+    ConnectOther { player: String, server: String },
+}
+
+impl PluginMessage for BungeeCordMessage {
+    const CHANNEL: Ident<&'static str> = ident!("bungeecord:main");
+
+    fn decode(mut data: &[u8]) -> anyhow::Result<Self> {
+        let msg = match read_utf(&mut data)?.as_ref() {
+            "Connect" => Self::Connect {
+                server: read_utf(&mut data)?.into_owned(),
+            },
+            "ConnectOther" => Self::ConnectOther {
+                player: read_utf(&mut data)?.into_owned(),
+                server: read_utf(&mut data)?.into_owned(),
+            },
+            other => bail!("unknown subchannel \"{other}\""),
+        };
+
+        ensure!(data.is_empty(), "trailing bytes in BungeeCord message");
+
+        Ok(msg)
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Connect { server } => {
+                write_utf(buf, "Connect");
+                write_utf(buf, server);
+            }
+            Self::ConnectOther { player, server } => {
+                write_utf(buf, "ConnectOther");
+                write_utf(buf, player);
+                write_utf(buf, server);
+            }
+        }
+    }
+}
+
+/// Writes a string the way Java's `DataOutput::writeUTF` does, as a big endian
+/// `u16` length followed by modified UTF-8. Strings longer than `u16::MAX`
+/// bytes are truncated.
+fn write_utf(buf: &mut Vec<u8>, s: &str) {
+    let bytes = cesu8::to_java_cesu8(s);
+    let len = bytes.len().min(u16::MAX as usize);
+
+    buf.extend_from_slice(&(len as u16).to_be_bytes());
+    buf.extend_from_slice(&bytes[..len]);
+}
+
+/// Reads a string written by Java's `DataOutput::writeUTF`.
+fn read_utf<'a>(data: &mut &'a [u8]) -> anyhow::Result<Cow<'a, str>> {
+    ensure!(data.len() >= 2, "missing string length");
+    let len = u16::from_be_bytes([data[0], data[1]]) as usize;
+    ensure!(data.len() >= 2 + len, "string is longer than the message");
+
+    let (s, rest) = data[2..].split_at(len);
+    *data = rest;
+
+    Ok(cesu8::from_java_cesu8(s)?)
+}
+
+/// Named pieces of data kept by the server for each client, keyed by the
+/// client's UUID. See the [module docs](self) for more information.
+///
+/// Cookies are kept until they are removed, also after the client has
+/// disconnected.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct Cookies {
+    cookies: HashMap<Uuid, BTreeMap<Ident<String>, Box<[u8]>>>,
+}
+
+impl Cookies {
+    /// Stores `data` as the cookie `key` of the client with `uuid`. Returns
+    /// the data the cookie had before, if any.
+    pub fn store(
+        &mut self,
+        uuid: Uuid,
+        key: impl Into<Ident<String>>,
+        data: impl Into<Box<[u8]>>,
+    ) -> Option<Box<[u8]>> {
+        self.cookies
+            .entry(uuid)
+            .or_default()
+            .insert(key.into(), data.into())
+    }
+
+    /// Returns the data of the cookie `key` of the client with `uuid`.
+    pub fn get(&self, uuid: Uuid, key: Ident<&str>) -> Option<&[u8]> {
+        self.cookies
+            .get(&uuid)?
+            .get(&Ident::<String>::from(key))
+            .map(|data| &**data)
+    }
+
+    /// Removes the cookie `key` of the client with `uuid`, returning its data.
+    pub fn remove(&mut self, uuid: Uuid, key: Ident<&str>) -> Option<Box<[u8]>> {
+        let cookies = self.cookies.get_mut(&uuid)?;
+        let data = cookies.remove(&Ident::<String>::from(key));
+
+        if cookies.is_empty() {
+            self.cookies.remove(&uuid);
+        }
+
+        data
+    }
+
+    /// Removes all the cookies of the client with `uuid`.
+    pub fn clear(&mut self, uuid: Uuid) {
+        self.cookies.remove(&uuid);
+    }
+
+    /// Returns an iterator over the keys and data of the cookies of the
+    /// client with `uuid`.
+    pub fn iter(&self, uuid: Uuid) -> impl Iterator<Item = (Ident<&str>, &[u8])> + '_ {
+        self.cookies
+            .get(&uuid)
+            .into_iter()
+            .flatten()
+            .map(|(key, data)| (key.as_str_ident(), &**data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bungeecord_message_round_trip() {
+        let msg = BungeeCordMessage::ConnectOther {
+            player: "Steve".into(),
+            server: "lobby\0".into(),
+        };
+
+        let mut buf = vec![];
+        msg.encode(&mut buf);
+
+        // Null characters take two bytes in modified UTF-8.
+        assert_eq!(&buf[..14], b"\0\x0cConnectOther");
+        assert_eq!(&buf[21..], b"\0\x07lobby\xc0\x80");
+        assert_eq!(BungeeCordMessage::decode(&buf).unwrap(), msg);
+
+        assert!(BungeeCordMessage::decode(&buf[..buf.len() - 1]).is_err());
+        assert!(BungeeCordMessage::decode(b"\0\x07Forward").is_err());
+    }
+
+    #[test]
+    fn cookies_per_client() {
+        let mut cookies = Cookies::default();
+        let steve = Uuid::from_u128(1);
+        let alex = Uuid::from_u128(2);
+
+        assert_eq!(cookies.store(steve, ident!("valence:team"), *b"red"), None);
+        cookies.store(alex, ident!("valence:team"), *b"blue");
+
+        assert_eq!(
+            cookies.store(steve, ident!("valence:team"), *b"green"),
+            Some(b"red".as_slice().into())
+        );
+        assert_eq!(
+            cookies.get(steve, ident!("valence:team")),
+            Some(b"green".as_slice())
+        );
+        assert_eq!(cookies.iter(alex).count(), 1);
+
+        assert!(cookies.remove(steve, ident!("valence:team")).is_some());
+        assert_eq!(cookies.get(steve, ident!("valence:team")), None);
+        assert_eq!(cookies.iter(steve).count(), 0);
+
+        cookies.clear(alex);
+        assert_eq!(cookies.get(alex, ident!("valence:team")), None);
+    }
+}
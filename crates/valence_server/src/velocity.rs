@@ -0,0 +1,164 @@
+//! Setting the velocity of entities and knocking them back.
+//!
+//! Clients move their own player, so the velocity of a player has to be sent
+//! to its client as well as to the clients viewing it. [`SetVelocity`] and
+//! [`ApplyKnockback`] take care of both, and are available on
+//! [`EntityCommands`] through [`VelocityCommandsExt`].
+//!
+//! The knockback of attacks, with the bonus of sprint hits and the knockback
+//! enchantment, is implemented by `valence_damage` on top of these.
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::{Command, EntityCommands};
+use valence_entity::attributes::{EntityAttribute, EntityAttributes};
+use valence_entity::{OnGround, Velocity};
+use valence_math::{Vec2, Vec3};
+
+use crate::client::Client;
+
+/// Returns the velocity of an entity after it is knocked back like in vanilla,
+/// in blocks per tick.
+///
+/// The entity keeps half of its `velocity` and is pushed horizontally by
+/// `strength` blocks per tick in `direction`. Entities on the ground are also
+/// pushed upwards, up to 0.4 blocks per tick.
+pub fn knockback_velocity(velocity: Vec3, on_ground: bool, direction: Vec2, strength: f32) -> Vec3 {
+    if strength <= 0.0 {
+        return velocity;
+    }
+
+    let push = direction.normalize_or_zero() * strength;
+
+    let y = if on_ground {
+        (velocity.y / 2.0 + strength).min(0.4)
+    } else {
+        velocity.y
+    };
+
+    Vec3::new(velocity.x / 2.0 + push.x, y, velocity.z / 2.0 + push.y)
+}
+
+/// [`Command`] to set the [`Velocity`] of an entity. If the entity is a
+/// client, the velocity is also sent to it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SetVelocity {
+    pub entity: Entity,
+    /// The new velocity in m/s.
+    pub velocity: Vec3,
+}
+
+impl Command for SetVelocity {
+    fn apply(self, world: &mut World) {
+        let Some(mut entity) = world.get_entity_mut(self.entity) else {
+            return;
+        };
+
+        if let Some(mut velocity) = entity.get_mut::<Velocity>() {
+            velocity.0 = self.velocity;
+        }
+
+        if let Some(mut client) = entity.get_mut::<Client>() {
+            client.set_velocity(self.velocity);
+        }
+    }
+}
+
+/// [`Command`] to knock an entity back with [`knockback_velocity`], taking its
+/// current [`Velocity`], whether it is on the ground and its knockback
+/// resistance into account.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ApplyKnockback {
+    pub entity: Entity,
+    /// The horizontal direction to push the entity in, as `(x, z)`.
+    pub direction: Vec2,
+    /// The horizontal push in blocks per tick, before knockback resistance.
+    pub strength: f32,
+}
+
+impl Command for ApplyKnockback {
+    fn apply(self, world: &mut World) {
+        let Some(entity) = world.get_entity(self.entity) else {
+            return;
+        };
+
+        let resistance = entity
+            .get::<EntityAttributes>()
+            .and_then(|a| a.get_compute_value(EntityAttribute::GenericKnockbackResistance))
+            .unwrap_or(0.0) as f32;
+
+        let velocity = entity.get::<Velocity>().map_or(Vec3::ZERO, |v| v.0) / 20.0;
+        let on_ground = entity.get::<OnGround>().is_none_or(|g| g.0);
+
+        let strength = self.strength * (1.0 - resistance.clamp(0.0, 1.0));
+
+        if strength <= 0.0 {
+            return;
+        }
+
+        SetVelocity {
+            entity: self.entity,
+            velocity: knockback_velocity(velocity, on_ground, self.direction, strength) * 20.0,
+        }
+        .apply(world);
+    }
+}
+
+/// Velocity helpers for [`EntityCommands`].
+pub trait VelocityCommandsExt {
+    /// Sets the velocity of the entity in m/s. See [`SetVelocity`].
+    fn set_velocity(&mut self, velocity: Vec3) -> &mut Self;
+
+    /// Knocks the entity back in `direction`. See [`ApplyKnockback`].
+    fn apply_knockback(&mut self, direction: Vec2, strength: f32) -> &mut Self;
+}
+
+impl VelocityCommandsExt for EntityCommands<'_, '_, '_> {
+    fn set_velocity(&mut self, velocity: Vec3) -> &mut Self {
+        let entity = self.id();
+        self.commands().add(SetVelocity { entity, velocity });
+        self
+    }
+
+    fn apply_knockback(&mut self, direction: Vec2, strength: f32) -> &mut Self {
+        let entity = self.id();
+        self.commands().add(ApplyKnockback {
+            entity,
+            direction,
+            strength,
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-6, "{a} != {b}");
+    }
+
+    #[test]
+    fn vanilla_knockback() {
+        let east = Vec2::new(1.0, 0.0);
+
+        assert_close(
+            knockback_velocity(Vec3::ZERO, true, east, 0.4),
+            Vec3::new(0.4, 0.4, 0.0),
+        );
+
+        // Half of the velocity is kept, and the upwards push is limited.
+        assert_close(
+            knockback_velocity(Vec3::new(0.2, 0.2, -0.2), true, east * 5.0, 0.5),
+            Vec3::new(0.6, 0.4, -0.1),
+        );
+
+        // Entities in the air aren't pushed upwards.
+        assert_close(
+            knockback_velocity(Vec3::new(0.0, -0.3, 0.0), false, east, 0.4),
+            Vec3::new(0.4, -0.3, 0.0),
+        );
+
+        assert_eq!(knockback_velocity(Vec3::ONE, true, east, 0.0), Vec3::ONE);
+    }
+}
@@ -0,0 +1,234 @@
+//! Per-client entity visibility rules.
+//!
+//! Entity layers normally show every entity to every client viewing the
+//! layer. [`VisibilityFilters`] narrow this down per client, for instance to
+//! hide vanished staff members or to show markers only to the members of a
+//! team. Hidden entities are never spawned for the client, and their updates
+//! are not sent to it, so modified clients can't learn about them either.
+//!
+//! Filters are evaluated every tick for the entities near each client. The
+//! result is stored in the client's [`HiddenEntities`] component, which is
+//! consulted when entity layer messages are written to the client.
+//!
+//! Packets written directly to layers, such as sounds and particles, are not
+//! filtered.
+//!
+//! # Examples
+//!
+//! ```
+//! use bevy_app::prelude::*;
+//! use bevy_ecs::prelude::*;
+//! use valence_server::op_level::OpLevel;
+//! use valence_server::visibility::VisibilityFilters;
+//!
+//! #[derive(Component)]
+//! struct Vanished;
+//!
+//! fn setup(mut filters: ResMut<VisibilityFilters>) {
+//!     // Only operators can see vanished players.
+//!     filters.add(|viewer: EntityRef, target: EntityRef| {
+//!         let is_op = viewer.get::<OpLevel>().is_some_and(|op| op.get() > 0);
+//!         !target.contains::<Vanished>() || is_op
+//!     });
+//! }
+//! # let _ = setup;
+//! ```
+
+use std::collections::BTreeSet;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemState;
+use valence_entity::query::EntityInitQuery;
+use valence_entity::{EntityId, EntityLayerId, Position};
+use valence_protocol::ChunkPos;
+use valence_server_common::Despawned;
+
+use crate::client::{
+    update_view_and_layers, Client, ClientMarker, EntityRemoveBuf, OldView, OldVisibleEntityLayers,
+    UpdateClientsSet, View, VisibleEntityLayers,
+};
+use crate::layer::{EntityLayer, UpdateLayersPreClientSet};
+
+pub struct VisibilityPlugin;
+
+impl Plugin for VisibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VisibilityFilters>().add_systems(
+            PostUpdate,
+            (
+                update_hidden_entities
+                    .after(UpdateLayersPreClientSet)
+                    .before(UpdateClientsSet),
+                apply_visibility_changes
+                    .in_set(UpdateClientsSet)
+                    .after(update_view_and_layers),
+            ),
+        );
+    }
+}
+
+/// Decides whether a client may see an entity.
+pub trait VisibilityFilter: Send + Sync + 'static {
+    /// Returns whether the client `viewer` may see `target`.
+    fn is_visible(&self, viewer: EntityRef, target: EntityRef) -> bool;
+}
+
+impl<F> VisibilityFilter for F
+where
+    F: Fn(EntityRef, EntityRef) -> bool + Send + Sync + 'static,
+{
+    fn is_visible(&self, viewer: EntityRef, target: EntityRef) -> bool {
+        self(viewer, target)
+    }
+}
+
+/// The [`VisibilityFilter`]s deciding which entities clients may see. An
+/// entity is visible to a client if every filter allows it.
+#[derive(Resource, Default)]
+pub struct VisibilityFilters {
+    filters: Vec<Box<dyn VisibilityFilter>>,
+}
+
+impl VisibilityFilters {
+    pub fn add(&mut self, filter: impl VisibilityFilter) {
+        self.filters.push(Box::new(filter));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn is_visible(&self, viewer: EntityRef, target: EntityRef) -> bool {
+        self.filters.iter().all(|f| f.is_visible(viewer, target))
+    }
+}
+
+/// The entities near a client which the [`VisibilityFilters`] hide from it.
+/// Updated automatically every tick.
+#[derive(Component, Default, Debug)]
+pub struct HiddenEntities {
+    hidden: BTreeSet<Entity>,
+    /// The hidden entities of the previous tick.
+    old_hidden: BTreeSet<Entity>,
+}
+
+impl HiddenEntities {
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.hidden.contains(&entity)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.hidden.iter().copied()
+    }
+
+    /// Whether entity layer messages about `entity` are withheld from the
+    /// client. Entities which are shown or hidden this tick are spawned or
+    /// despawned separately after the messages are written.
+    pub(crate) fn suppresses(&self, entity: Entity) -> bool {
+        self.hidden.contains(&entity) || self.old_hidden.contains(&entity)
+    }
+}
+
+fn update_hidden_entities(
+    world: &mut World,
+    state: &mut SystemState<(
+        Query<
+            (
+                Entity,
+                View,
+                OldView,
+                &VisibleEntityLayers,
+                &OldVisibleEntityLayers,
+            ),
+            With<ClientMarker>,
+        >,
+        Query<&EntityLayer>,
+    )>,
+    mut results: Local<Vec<(Entity, BTreeSet<Entity>)>>,
+) {
+    world.resource_scope(|world, filters: Mut<VisibilityFilters>| {
+        let (clients, layers) = state.get(world);
+
+        for (client, view, old_view, visible, old_visible) in &clients {
+            let mut hidden = BTreeSet::new();
+
+            if !filters.is_empty() {
+                let view = view.get();
+                let old_view = old_view.get();
+
+                // Entity layer messages are written for the old view, and new entities are
+                // spawned for the new view.
+                let chunks: BTreeSet<ChunkPos> = view.iter().chain(old_view.iter()).collect();
+
+                for &layer in visible.0.union(old_visible.get()) {
+                    let Ok(layer) = layers.get(layer) else {
+                        continue;
+                    };
+
+                    for &pos in &chunks {
+                        for entity in layer.entities_at(pos) {
+                            if entity != client
+                                && !filters.is_visible(world.entity(client), world.entity(entity))
+                            {
+                                hidden.insert(entity);
+                            }
+                        }
+                    }
+                }
+            }
+
+            results.push((client, hidden));
+        }
+
+        for (client, hidden) in results.drain(..) {
+            if let Some(mut entities) = world.get_mut::<HiddenEntities>(client) {
+                if entities.hidden != hidden {
+                    entities.hidden = hidden;
+                }
+            }
+        }
+    });
+}
+
+/// Despawns the entities which were hidden this tick and spawns the entities
+/// which were shown.
+fn apply_visibility_changes(
+    mut clients: Query<(
+        &mut Client,
+        &mut HiddenEntities,
+        &mut EntityRemoveBuf,
+        View,
+        &VisibleEntityLayers,
+    )>,
+    entities: Query<(EntityInitQuery, &Position, &EntityLayerId), Without<Despawned>>,
+    entity_ids: Query<&EntityId>,
+) {
+    for (mut client, mut hidden, mut remove_buf, view, visible) in &mut clients {
+        if hidden.hidden == hidden.old_hidden {
+            continue;
+        }
+
+        let hidden = &mut *hidden;
+
+        for &entity in hidden.hidden.difference(&hidden.old_hidden) {
+            if let Ok(id) = entity_ids.get(entity) {
+                remove_buf.push(id.get());
+            }
+        }
+
+        remove_buf.send_and_clear(&mut *client);
+
+        let view = view.get();
+
+        for &entity in hidden.old_hidden.difference(&hidden.hidden) {
+            if let Ok((init, pos, layer)) = entities.get(entity) {
+                if visible.0.contains(&layer.0) && view.contains(ChunkPos::from(pos.0)) {
+                    init.write_init_packets(pos.0, &mut *client);
+                }
+            }
+        }
+
+        hidden.old_hidden.clone_from(&hidden.hidden);
+    }
+}
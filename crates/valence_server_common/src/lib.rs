@@ -19,15 +19,16 @@
 #![allow(clippy::unusual_byte_groupings)]
 
 mod despawn;
+mod tick;
 mod uuid;
 
 use std::num::NonZeroU32;
-use std::time::Duration;
 
 use bevy_app::prelude::*;
-use bevy_app::ScheduleRunnerPlugin;
 use bevy_ecs::prelude::*;
+use bevy_ecs::schedule::ScheduleLabel;
 pub use despawn::*;
+pub use tick::{ScheduleTiming, TickSettings, TickTimings};
 use valence_protocol::CompressionThreshold;
 
 pub use crate::uuid::*;
@@ -40,8 +41,9 @@ pub const DEFAULT_TPS: NonZeroU32 = match NonZeroU32::new(20) {
 
 #[derive(Clone, Resource)]
 pub struct ServerSettings {
-    /// The target ticks per second (TPS) of the server. This is the number of
-    /// game updates that should occur in one second.
+    /// The initial target ticks per second (TPS) of the server. This is the
+    /// number of game updates that should occur in one second. It can be
+    /// changed while the server is running with [`TickSettings`].
     ///
     /// On each game update (tick), the server is expected to update game logic
     /// and respond to packets from clients. Once this is complete, the server
@@ -93,10 +95,19 @@ impl Plugin for ServerPlugin {
             tick_rate: settings.tick_rate,
         });
 
-        let tick_period = Duration::from_secs_f64((settings.tick_rate.get() as f64).recip());
+        app.insert_resource(TickSettings {
+            tick_rate: settings.tick_rate,
+        })
+        .init_resource::<TickTimings>();
 
         // Make the app loop forever at the configured TPS.
-        app.add_plugins(ScheduleRunnerPlugin::run_loop(tick_period));
+        app.set_runner(tick::run_loop);
+
+        // Time the schedules of the main schedule.
+        app.main_schedule_label = tick::TimedMain.intern();
+        app.add_schedule(Schedule::new(tick::TimedMain))
+            .add_systems(tick::TimedMain, tick::run_timed_main)
+            .add_systems(First, tick::apply_tick_settings);
 
         fn increment_tick_counter(mut server: ResMut<Server>) {
             server.current_tick += 1;
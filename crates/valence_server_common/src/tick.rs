@@ -0,0 +1,211 @@
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+
+use bevy_app::prelude::*;
+use bevy_app::{AppExit, MainScheduleOrder, PluginsState};
+use bevy_ecs::event::ManualEventReader;
+use bevy_ecs::prelude::*;
+use bevy_ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
+
+use crate::Server;
+
+/// The number of ticks the averages in [`TickTimings`] are smoothed over.
+const AVERAGE_TICKS: f64 = 100.0;
+
+/// Runtime control of how fast the server ticks. Changes take effect at the
+/// start of the next tick.
+///
+/// The ticking state packets which tell clients about a changed tick rate were
+/// added in 1.20.3, after the protocol version of Valence, so clients keep
+/// predicting movement and animations at 20 TPS.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct TickSettings {
+    /// The target ticks per second (TPS) of the server.
+    ///
+    /// # Default Value
+    ///
+    /// [`ServerSettings::tick_rate`](crate::ServerSettings::tick_rate)
+    pub tick_rate: NonZeroU32,
+}
+
+/// How long the schedules of the main schedule took to run. Updated at the end
+/// of every tick, so systems see the timings of the previous tick.
+///
+/// This can be used to find out what is using up the tick budget, for example
+/// in an admin command.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct TickTimings {
+    schedules: Vec<ScheduleTiming>,
+    last_tick: Duration,
+    average_tick: Duration,
+}
+
+/// The time spent in one of the schedules of the main schedule.
+#[derive(Copy, Clone, Debug)]
+pub struct ScheduleTiming {
+    pub label: InternedScheduleLabel,
+    /// The time it took in the last tick.
+    pub last: Duration,
+    /// The time it took on average in recent ticks.
+    pub average: Duration,
+}
+
+impl TickTimings {
+    /// Returns the timings of the schedules in the order they run.
+    pub fn schedules(&self) -> &[ScheduleTiming] {
+        &self.schedules
+    }
+
+    /// Returns the timing of the schedule with the given label, if it ran.
+    pub fn get(&self, label: impl ScheduleLabel) -> Option<&ScheduleTiming> {
+        let label = label.intern();
+        self.schedules.iter().find(|timing| timing.label == label)
+    }
+
+    /// Returns the time it took to run the last tick, excluding the time spent
+    /// waiting for the next tick.
+    pub fn last_tick(&self) -> Duration {
+        self.last_tick
+    }
+
+    /// Returns the time it took to run a tick on average in recent ticks.
+    pub fn average_tick(&self) -> Duration {
+        self.average_tick
+    }
+
+    /// Returns the average fraction of the tick period spent running ticks.
+    /// Values above `1.0` mean the server can't keep up with its tick rate.
+    pub fn average_usage(&self, server: &Server) -> f64 {
+        self.average_tick.as_secs_f64() * server.tick_rate().get() as f64
+    }
+
+    fn record(&mut self, label: InternedScheduleLabel, elapsed: Duration) {
+        match self
+            .schedules
+            .iter_mut()
+            .find(|timing| timing.label == label)
+        {
+            Some(timing) => {
+                timing.last = elapsed;
+                timing.average = smooth(timing.average, elapsed);
+            }
+            None => self.schedules.push(ScheduleTiming {
+                label,
+                last: elapsed,
+                average: elapsed,
+            }),
+        }
+    }
+}
+
+fn smooth(average: Duration, last: Duration) -> Duration {
+    average.mul_f64(1.0 - AVERAGE_TICKS.recip()) + last.mul_f64(AVERAGE_TICKS.recip())
+}
+
+/// Runs the schedules of [`MainScheduleOrder`] like [`Main`] does, but records
+/// their timings in [`TickTimings`].
+#[derive(ScheduleLabel, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct TimedMain;
+
+pub(crate) fn run_timed_main(world: &mut World, mut run_at_least_once: Local<bool>) {
+    if !*run_at_least_once {
+        let _ = world.try_run_schedule(PreStartup);
+        let _ = world.try_run_schedule(Startup);
+        let _ = world.try_run_schedule(PostStartup);
+        *run_at_least_once = true;
+    }
+
+    let tick_start = Instant::now();
+    let mut timings = vec![];
+
+    world.resource_scope(|world, order: Mut<MainScheduleOrder>| {
+        for &label in &order.labels {
+            let start = Instant::now();
+            let _ = world.try_run_schedule(label);
+            timings.push((label, start.elapsed()));
+        }
+    });
+
+    let mut tick_timings = world.resource_mut::<TickTimings>();
+
+    for (label, elapsed) in timings {
+        tick_timings.record(label, elapsed);
+    }
+
+    let last_tick = tick_start.elapsed();
+    tick_timings.last_tick = last_tick;
+    tick_timings.average_tick = smooth(tick_timings.average_tick, last_tick);
+}
+
+pub(crate) fn apply_tick_settings(settings: Res<TickSettings>, mut server: ResMut<Server>) {
+    if settings.is_changed() && server.tick_rate != settings.tick_rate {
+        server.tick_rate = settings.tick_rate;
+    }
+}
+
+/// Loops forever at the current tick rate of the [`Server`].
+pub(crate) fn run_loop(mut app: App) {
+    if app.plugins_state() != PluginsState::Cleaned {
+        while app.plugins_state() == PluginsState::Adding {
+            std::thread::yield_now();
+        }
+        app.finish();
+        app.cleanup();
+    }
+
+    let mut app_exit_reader = ManualEventReader::<AppExit>::default();
+
+    loop {
+        let start = Instant::now();
+
+        app.update();
+
+        if let Some(app_exit_events) = app.world.get_resource::<Events<AppExit>>() {
+            if app_exit_reader.read(app_exit_events).last().is_some() {
+                return;
+            }
+        }
+
+        let tick_rate = app.world.resource::<Server>().tick_rate();
+        let tick_period = Duration::from_secs_f64((tick_rate.get() as f64).recip());
+
+        if let Some(delay) = tick_period.checked_sub(start.elapsed()) {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServerPlugin;
+
+    #[test]
+    fn tick_settings_change_tick_rate() {
+        let mut app = App::new();
+        app.add_plugins(ServerPlugin);
+
+        app.update();
+        assert_eq!(app.world.resource::<Server>().tick_rate().get(), 20);
+
+        app.world.resource_mut::<TickSettings>().tick_rate = NonZeroU32::new(5).unwrap();
+        app.update();
+        assert_eq!(app.world.resource::<Server>().tick_rate().get(), 5);
+    }
+
+    #[test]
+    fn tick_timings_are_recorded() {
+        let mut app = App::new();
+        app.add_plugins(ServerPlugin)
+            .add_systems(Update, || std::thread::sleep(Duration::from_millis(2)));
+
+        app.update();
+
+        let timings = app.world.resource::<TickTimings>();
+        let update = timings.get(Update).unwrap();
+
+        assert!(update.last >= Duration::from_millis(2));
+        assert!(timings.last_tick() >= update.last);
+        assert!(timings.get(First).is_some());
+    }
+}
@@ -0,0 +1,326 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod mojang;
+
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use mojang::{FetchError, MojangClient, Profile};
+use tokio::runtime::{Handle, Runtime};
+use tracing::warn;
+use uuid::Uuid;
+use valence_server::client::Properties;
+use valence_server::entity::query::EntityInitQuery;
+use valence_server::entity::{EntityKind, EntityLayerId, Position};
+use valence_server::layer::UpdateLayersPreClientSet;
+use valence_server::protocol::packets::play::EntitiesDestroyS2c;
+use valence_server::protocol::profile::{PlayerTextures, Property};
+use valence_server::protocol::{VarInt, WritePacket};
+use valence_server::{Despawned, EntityLayer, Layer};
+
+pub struct SkinPlugin;
+
+impl Plugin for SkinPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = app
+            .world
+            .get_resource_or_insert_with(SkinSettings::default)
+            .clone();
+
+        app.insert_resource(Skins::new(settings))
+            .add_event::<SkinFetchedEvent>()
+            .add_systems(Update, (start_skin_fetches, finish_skin_fetches))
+            .add_systems(
+                PostUpdate,
+                // Viewers need the updated player list entry before the entity
+                // is spawned again, which the player list sends before layers
+                // are updated.
+                respawn_reskinned_players.before(UpdateLayersPreClientSet),
+            );
+    }
+}
+
+/// Settings for [`SkinPlugin`]. Must be inserted before the plugin is added
+/// to take effect.
+#[derive(Resource, Clone, Debug)]
+pub struct SkinSettings {
+    /// The tokio runtime skins are fetched on. If `None`, a runtime is created
+    /// when the first skin is fetched.
+    pub tokio_handle: Option<Handle>,
+    /// How long looked up profiles are cached for.
+    pub cache_ttl: Duration,
+}
+
+impl Default for SkinSettings {
+    fn default() -> Self {
+        Self {
+            tokio_handle: None,
+            cache_ttl: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// A signed "textures" property, which contains the skin and cape of a
+/// profile.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Skin {
+    /// The base64 encoded textures.
+    pub value: String,
+    /// The signature of the textures by Mojang. Vanilla clients don't show
+    /// skins without a valid signature.
+    pub signature: Option<String>,
+}
+
+impl Skin {
+    pub fn new(value: impl Into<String>, signature: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            signature: Some(signature.into()),
+        }
+    }
+
+    /// Returns the skin in the "textures" property of `properties`, if there
+    /// is one.
+    pub fn from_properties(properties: &[Property]) -> Option<Self> {
+        let textures = properties.iter().find(|p| p.name == "textures")?;
+
+        Some(Self {
+            value: textures.value.clone(),
+            signature: textures.signature.clone(),
+        })
+    }
+
+    /// Decodes the URLs of the skin and cape and the model of the skin.
+    pub fn textures(&self) -> Option<PlayerTextures> {
+        PlayerTextures::try_from_textures(&self.value).ok()
+    }
+
+    /// Replaces the "textures" property of `properties` with this skin.
+    pub fn apply(&self, properties: &mut Properties) {
+        properties.set("textures", &self.value, self.signature.clone());
+    }
+}
+
+/// Fetches the skin of an account in the background and applies it to the
+/// [`Properties`] of the entity. The component is removed once the fetch has
+/// finished, and a [`SkinFetchedEvent`] is sent.
+///
+/// Replacing the component before the fetch has finished discards the
+/// result of the earlier fetch.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub enum FetchSkin {
+    Username(String),
+    Uuid(Uuid),
+}
+
+/// Sent when the fetch started by a [`FetchSkin`] component has finished.
+#[derive(Event, Debug)]
+pub struct SkinFetchedEvent {
+    pub entity: Entity,
+    pub request: FetchSkin,
+    /// The skin, which was applied to the entity if it has [`Properties`].
+    pub result: Result<Skin, FetchError>,
+}
+
+/// Looks up skins for [`FetchSkin`] components.
+#[derive(Resource)]
+pub struct Skins {
+    client: Arc<MojangClient>,
+    tokio_handle: Option<Handle>,
+    // Holding a runtime handle is not enough to keep tokio working. We need
+    // to store the runtime here so we don't drop it.
+    runtime: Option<Runtime>,
+    results_send: flume::Sender<(Entity, FetchSkin, Result<Skin, FetchError>)>,
+    results_recv: flume::Receiver<(Entity, FetchSkin, Result<Skin, FetchError>)>,
+}
+
+impl Skins {
+    fn new(settings: SkinSettings) -> Self {
+        let (results_send, results_recv) = flume::unbounded();
+
+        Self {
+            client: Arc::new(MojangClient::new().ttl(settings.cache_ttl)),
+            tokio_handle: settings.tokio_handle,
+            runtime: None,
+            results_send,
+            results_recv,
+        }
+    }
+
+    /// The client used to fetch skins, for use in async code.
+    pub fn client(&self) -> &Arc<MojangClient> {
+        &self.client
+    }
+
+    fn handle(&mut self) -> Option<Handle> {
+        if self.tokio_handle.is_none() {
+            match Runtime::new() {
+                Ok(runtime) => {
+                    self.tokio_handle = Some(runtime.handle().clone());
+                    self.runtime = Some(runtime);
+                }
+                Err(e) => {
+                    warn!("failed to create tokio runtime for fetching skins: {e}");
+                    return None;
+                }
+            }
+        }
+
+        self.tokio_handle.clone()
+    }
+}
+
+fn start_skin_fetches(
+    requests: Query<(Entity, &FetchSkin), Changed<FetchSkin>>,
+    mut skins: ResMut<Skins>,
+) {
+    if requests.is_empty() {
+        return;
+    }
+
+    let Some(handle) = skins.handle() else {
+        return;
+    };
+
+    for (entity, request) in &requests {
+        let client = skins.client.clone();
+        let results_send = skins.results_send.clone();
+        let request = request.clone();
+
+        handle.spawn(async move {
+            let result = match &request {
+                FetchSkin::Username(username) => client.skin_by_name(username).await,
+                FetchSkin::Uuid(uuid) => client.skin(*uuid).await,
+            };
+
+            let _ = results_send.send((entity, request, result));
+        });
+    }
+}
+
+fn finish_skin_fetches(
+    skins: Res<Skins>,
+    mut entities: Query<(&FetchSkin, Option<&mut Properties>)>,
+    mut events: EventWriter<SkinFetchedEvent>,
+    mut commands: Commands,
+) {
+    for (entity, request, result) in skins.results_recv.try_iter() {
+        let Ok((current, properties)) = entities.get_mut(entity) else {
+            continue;
+        };
+
+        // The component was replaced while the skin was being fetched.
+        if *current != request {
+            continue;
+        }
+
+        if let (Ok(skin), Some(mut properties)) = (&result, properties) {
+            skin.apply(&mut properties);
+        }
+
+        commands.entity(entity).remove::<FetchSkin>();
+
+        events.send(SkinFetchedEvent {
+            entity,
+            request,
+            result,
+        });
+    }
+}
+
+/// Player entities are only given a skin when they are spawned, so they have
+/// to be spawned again for viewers to see a new one.
+fn respawn_reskinned_players(
+    players: Query<
+        (
+            Entity,
+            EntityInitQuery,
+            &Position,
+            &EntityLayerId,
+            Ref<Properties>,
+        ),
+        (Changed<Properties>, Without<Despawned>),
+    >,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (entity, init, pos, layer_id, properties) in &players {
+        if properties.is_added() || *init.kind != EntityKind::PLAYER {
+            continue;
+        }
+
+        let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+            continue;
+        };
+
+        let mut writer = layer.view_except_writer(pos.0, entity);
+
+        writer.write_packet(&EntitiesDestroyS2c {
+            entity_ids: Cow::Borrowed(&[VarInt(init.entity_id.get())]),
+        });
+
+        init.write_init_packets(pos.0, &mut writer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::prelude::*;
+    use valence_server::protocol::profile::SkinModel;
+
+    use super::*;
+
+    #[test]
+    fn skin_textures() {
+        let json = r#"{
+            "timestamp": 0,
+            "profileId": "069a79f444e94726a5befca90e38aaf5",
+            "profileName": "Notch",
+            "textures": {
+                "SKIN": {
+                    "url": "http://textures.minecraft.net/texture/skin",
+                    "metadata": { "model": "slim" }
+                },
+                "CAPE": { "url": "http://textures.minecraft.net/texture/cape" }
+            }
+        }"#;
+
+        let skin = Skin::new(BASE64_STANDARD.encode(json), "signature");
+        let textures = skin.textures().unwrap();
+
+        assert_eq!(textures.model, SkinModel::Slim);
+        assert_eq!(
+            textures.cape.unwrap().as_str(),
+            "http://textures.minecraft.net/texture/cape"
+        );
+
+        let mut properties = Properties::default();
+        skin.apply(&mut properties);
+
+        assert_eq!(Skin::from_properties(&properties), Some(skin));
+        assert_eq!(properties.player_textures().unwrap().model, SkinModel::Slim);
+
+        properties.remove_skin();
+        assert_eq!(Skin::from_properties(&properties), None);
+    }
+}
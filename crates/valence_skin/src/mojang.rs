@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+use uuid::Uuid;
+use valence_server::protocol::profile::Property;
+
+use crate::Skin;
+
+const PROFILE_BY_NAME_URL: &str = "https://api.mojang.com/users/profiles/minecraft";
+const PROFILE_BY_UUID_URL: &str = "https://sessionserver.mojang.com/session/minecraft/profile";
+
+/// The game profile of an account, as returned by the session server.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+pub struct Profile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<Property>,
+}
+
+impl Profile {
+    /// Returns the signed skin of the profile, if it has one.
+    pub fn skin(&self) -> Option<Skin> {
+        Skin::from_properties(&self.properties)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("no account named {0:?}")]
+    UnknownName(String),
+    #[error("{0:?} is not a valid username")]
+    InvalidName(String),
+    #[error("no account with the UUID {0}")]
+    UnknownUuid(Uuid),
+    #[error("the profile of {0} has no textures")]
+    NoTextures(Uuid),
+    #[error("rate limited by the Mojang API")]
+    RateLimited,
+    #[error("Mojang API request failed (status code {0})")]
+    Status(StatusCode),
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+/// A client for the parts of the Mojang API needed to look up skins.
+///
+/// Profiles are cached for [`ttl`](Self::ttl), including the absence of a
+/// profile, since the API is rate limited and skins rarely change. Signed
+/// profiles can only be requested for the same UUID about once a minute.
+pub struct MojangClient {
+    http: reqwest::Client,
+    ttl: Duration,
+    uuids: Mutex<HashMap<String, (Instant, Option<Uuid>)>>,
+    profiles: Mutex<HashMap<Uuid, (Instant, Option<Profile>)>>,
+}
+
+#[derive(Deserialize)]
+struct NameLookup {
+    id: Uuid,
+}
+
+impl MojangClient {
+    /// Creates a client with a cache TTL of five minutes.
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            ttl: Duration::from_secs(5 * 60),
+            uuids: Mutex::new(HashMap::new()),
+            profiles: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets how long looked up UUIDs and profiles are cached for.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Forgets all cached UUIDs and profiles.
+    pub fn clear(&self) {
+        self.uuids.lock().unwrap().clear();
+        self.profiles.lock().unwrap().clear();
+    }
+
+    /// Looks up the UUID of the account named `username`. Usernames are case
+    /// insensitive.
+    pub async fn uuid(&self, username: &str) -> Result<Uuid, FetchError> {
+        // The username ends up in the request path, so anything but a
+        // username must be kept out of it.
+        if !is_valid_username(username) {
+            return Err(FetchError::InvalidName(username.to_owned()));
+        }
+
+        let key = username.to_ascii_lowercase();
+
+        let uuid = match cached(&self.uuids, &key, self.ttl) {
+            Some(uuid) => uuid,
+            None => {
+                let url = format!("{PROFILE_BY_NAME_URL}/{username}");
+                let resp = self.http.get(url).send().await?;
+
+                let uuid = match resp.status() {
+                    StatusCode::OK => Some(resp.json::<NameLookup>().await?.id),
+                    StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => None,
+                    status => return Err(status_error(status)),
+                };
+
+                insert(&self.uuids, key, uuid, self.ttl);
+                uuid
+            }
+        };
+
+        uuid.ok_or_else(|| FetchError::UnknownName(username.to_owned()))
+    }
+
+    /// Fetches the signed profile of the account with the given UUID.
+    pub async fn profile(&self, uuid: Uuid) -> Result<Profile, FetchError> {
+        let profile = match cached(&self.profiles, &uuid, self.ttl) {
+            Some(profile) => profile,
+            None => {
+                let url = format!("{PROFILE_BY_UUID_URL}/{}?unsigned=false", uuid.simple());
+                let resp = self.http.get(url).send().await?;
+
+                let profile = match resp.status() {
+                    StatusCode::OK => Some(resp.json::<Profile>().await?),
+                    StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => None,
+                    status => return Err(status_error(status)),
+                };
+
+                insert(&self.profiles, uuid, profile.clone(), self.ttl);
+                profile
+            }
+        };
+
+        profile.ok_or(FetchError::UnknownUuid(uuid))
+    }
+
+    /// Fetches the signed skin of the account with the given UUID.
+    pub async fn skin(&self, uuid: Uuid) -> Result<Skin, FetchError> {
+        self.profile(uuid)
+            .await?
+            .skin()
+            .ok_or(FetchError::NoTextures(uuid))
+    }
+
+    /// Fetches the signed skin of the account named `username`.
+    pub async fn skin_by_name(&self, username: &str) -> Result<Skin, FetchError> {
+        let uuid = self.uuid(username).await?;
+        self.skin(uuid).await
+    }
+}
+
+impl Default for MojangClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn status_error(status: StatusCode) -> FetchError {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        FetchError::RateLimited
+    } else {
+        FetchError::Status(status)
+    }
+}
+
+fn cached<K: Hash + Eq, V: Clone>(
+    cache: &Mutex<HashMap<K, (Instant, V)>>,
+    key: &K,
+    ttl: Duration,
+) -> Option<V> {
+    match cache.lock().unwrap().get(key) {
+        Some((time, value)) if time.elapsed() < ttl => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn insert<K: Hash + Eq, V>(
+    cache: &Mutex<HashMap<K, (Instant, V)>>,
+    key: K,
+    value: V,
+    ttl: Duration,
+) {
+    let mut cache = cache.lock().unwrap();
+
+    // Expired entries are only dropped here, so the cache doesn't grow without
+    // bound.
+    cache.retain(|_, (time, _)| time.elapsed() < ttl);
+    cache.insert(key, (Instant::now(), value));
+}
+
+/// Returns whether `username` follows the rules for the names of Minecraft
+/// accounts: 1 to 16 ASCII letters, digits and underscores.
+fn is_valid_username(username: &str) -> bool {
+    (1..=16).contains(&username.len())
+        && username
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn username_rules() {
+        assert!(is_valid_username("Notch"));
+        assert!(is_valid_username("jeb_"));
+        assert!(is_valid_username("a"));
+        assert!(is_valid_username("0123456789abcdef"));
+
+        assert!(!is_valid_username(""));
+        assert!(!is_valid_username("0123456789abcdefg"));
+        assert!(!is_valid_username("../../users"));
+        assert!(!is_valid_username("Notch?at=0"));
+        assert!(!is_valid_username("Not ch"));
+        assert!(!is_valid_username("Nötch"));
+    }
+
+    #[tokio::test]
+    async fn invalid_username_is_not_requested() {
+        let client = MojangClient::new();
+
+        assert!(matches!(
+            client.uuid("Notch/../../x").await,
+            Err(FetchError::InvalidName(name)) if name == "Notch/../../x"
+        ));
+    }
+
+    #[test]
+    fn cache_expires() {
+        let cache = Mutex::new(HashMap::new());
+
+        insert(&cache, "notch".to_owned(), Some(Uuid::nil()), Duration::MAX);
+        assert_eq!(
+            cached(&cache, &"notch".to_owned(), Duration::MAX),
+            Some(Some(Uuid::nil()))
+        );
+        assert_eq!(cached(&cache, &"jeb_".to_owned(), Duration::MAX), None);
+
+        // Nothing is fresh with a TTL of zero, and inserting drops stale
+        // entries.
+        assert_eq!(cached(&cache, &"notch".to_owned(), Duration::ZERO), None);
+        insert(&cache, "jeb_".to_owned(), None, Duration::ZERO);
+        assert_eq!(cache.lock().unwrap().len(), 1);
+    }
+}
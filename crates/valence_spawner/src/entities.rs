@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use bevy_ecs::prelude::*;
+use valence_server::entity::{
+    blaze, cave_spider, chicken, cow, creeper, magma_cube, pig, sheep, silverfish, skeleton,
+    spider, zombie, EntityKind, EntityLayerId, HeadYaw, Look, Position,
+};
+
+use crate::SpawnContext;
+
+/// A function spawning a single entity for a spawner. Returns the spawned
+/// entity, or `None` if nothing was spawned.
+pub type SpawnFn = Box<dyn Fn(&mut World, &SpawnContext) -> Option<Entity> + Send + Sync + 'static>;
+
+/// Resource mapping entity kinds to the [`SpawnFn`] spawning them. Spawners
+/// with a kind missing from the registry don't spawn anything.
+#[derive(Resource)]
+pub struct SpawnerEntities {
+    spawn_fns: HashMap<EntityKind, SpawnFn>,
+}
+
+impl SpawnerEntities {
+    /// Creates a registry without any spawn functions.
+    pub fn empty() -> Self {
+        Self {
+            spawn_fns: HashMap::new(),
+        }
+    }
+
+    /// Sets the spawn function for `kind`, replacing the previous one.
+    pub fn register(
+        &mut self,
+        kind: EntityKind,
+        spawn_fn: impl Fn(&mut World, &SpawnContext) -> Option<Entity> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.spawn_fns.insert(kind, Box::new(spawn_fn));
+        self
+    }
+
+    pub fn unregister(&mut self, kind: EntityKind) -> Option<SpawnFn> {
+        self.spawn_fns.remove(&kind)
+    }
+
+    pub fn get(&self, kind: EntityKind) -> Option<&SpawnFn> {
+        self.spawn_fns.get(&kind)
+    }
+
+    /// Spawns the entity described by `ctx`.
+    pub fn spawn(&self, world: &mut World, ctx: &SpawnContext) -> Option<Entity> {
+        self.get(ctx.kind)?(world, ctx)
+    }
+}
+
+macro_rules! register_bundles {
+    ($entities:expr, $($kind:ident => $module:ident::$bundle:ident),* $(,)?) => {
+        $(
+            $entities.register(EntityKind::$kind, |world, ctx| {
+                let entity = world.spawn($module::$bundle {
+                    layer: EntityLayerId(ctx.layer),
+                    position: Position(ctx.position),
+                    look: Look::new(ctx.yaw, 0.0),
+                    head_yaw: HeadYaw(ctx.yaw),
+                    ..Default::default()
+                });
+
+                Some(entity.id())
+            });
+        )*
+    };
+}
+
+impl Default for SpawnerEntities {
+    fn default() -> Self {
+        let mut entities = Self::empty();
+
+        register_bundles!(
+            entities,
+            BLAZE => blaze::BlazeEntityBundle,
+            CAVE_SPIDER => cave_spider::CaveSpiderEntityBundle,
+            CHICKEN => chicken::ChickenEntityBundle,
+            COW => cow::CowEntityBundle,
+            CREEPER => creeper::CreeperEntityBundle,
+            MAGMA_CUBE => magma_cube::MagmaCubeEntityBundle,
+            PIG => pig::PigEntityBundle,
+            SHEEP => sheep::SheepEntityBundle,
+            SILVERFISH => silverfish::SilverfishEntityBundle,
+            SKELETON => skeleton::SkeletonEntityBundle,
+            SPIDER => spider::SpiderEntityBundle,
+            ZOMBIE => zombie::ZombieEntityBundle,
+        );
+
+        entities
+    }
+}
@@ -0,0 +1,404 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod entities;
+
+use std::collections::BTreeSet;
+
+use bevy_app::prelude::*;
+use bevy_ecs::event::ManualEventReader;
+use bevy_ecs::prelude::*;
+pub use entities::{SpawnFn, SpawnerEntities};
+use valence_difficulty::RegionalDifficulty;
+use valence_server::client::Client;
+use valence_server::entity::{EntityKind, EntityLayerId, Position};
+use valence_server::layer::chunk::Block;
+use valence_server::math::{Aabb, DVec3};
+use valence_server::nbt::{compound, Compound};
+use valence_server::protocol::packets::play::particle_s2c::Particle;
+use valence_server::protocol::packets::play::BlockEventS2c;
+use valence_server::protocol::WritePacket;
+use valence_server::rand::{Rng, RngCore};
+use valence_server::random::LayerRng;
+use valence_server::{BlockPos, BlockState, ChunkLayer, Layer};
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnerEntities>()
+            .add_event::<SpawnerSpawnEvent>()
+            .add_event::<CancelSpawnEvent>()
+            .configure_sets(Update, SpawnerSet::Tick.before(SpawnerSet::Spawn))
+            .add_systems(
+                Update,
+                (
+                    (update_spawner_blocks, tick_spawners)
+                        .chain()
+                        .in_set(SpawnerSet::Tick),
+                    spawn_entities.in_set(SpawnerSet::Spawn),
+                ),
+            );
+    }
+}
+
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SpawnerSet {
+    /// Spawner delays are counted down and [`SpawnerSpawnEvent`]s are sent.
+    Tick,
+    /// Entities are spawned for the [`SpawnerSpawnEvent`]s which weren't
+    /// cancelled.
+    Spawn,
+}
+
+/// Component for entities which represent a spawner block. The block entity
+/// data of the spawner block is updated when this component changes.
+///
+/// The default values are the same as vanilla spawners.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct Spawner {
+    /// The chunk layer the spawner block is in.
+    pub layer: Entity,
+    /// The position of the spawner block.
+    pub position: BlockPos,
+    /// The kind of entity spawned, which is also shown spinning inside the
+    /// spawner block.
+    pub kind: EntityKind,
+    /// The number of ticks until the next spawn.
+    pub delay: u16,
+    /// The minimum number of ticks between spawns.
+    pub min_spawn_delay: u16,
+    /// The maximum number of ticks between spawns.
+    pub max_spawn_delay: u16,
+    /// The number of entities spawned at once.
+    pub spawn_count: u16,
+    /// The maximum horizontal distance from the spawner entities are spawned
+    /// at.
+    pub spawn_range: u16,
+    /// The spawner doesn't spawn while this many entities of its kind are
+    /// within [`Spawner::spawn_range`].
+    pub max_nearby_entities: u16,
+    /// The spawner is only active while a player is within this many blocks.
+    pub required_player_range: u16,
+}
+
+impl Spawner {
+    pub fn new(layer: Entity, position: impl Into<BlockPos>, kind: EntityKind) -> Self {
+        Self {
+            layer,
+            position: position.into(),
+            kind,
+            delay: 20,
+            min_spawn_delay: 200,
+            max_spawn_delay: 800,
+            spawn_count: 4,
+            spawn_range: 4,
+            max_nearby_entities: 6,
+            required_player_range: 16,
+        }
+    }
+
+    /// The center of the spawner block.
+    pub fn center(&self) -> DVec3 {
+        DVec3::new(
+            self.position.x as f64 + 0.5,
+            self.position.y as f64 + 0.5,
+            self.position.z as f64 + 0.5,
+        )
+    }
+
+    /// Returns the block entity data of the spawner block.
+    pub fn block_entity_data(&self) -> Compound {
+        let mut spawn_data = Compound::new();
+
+        if let Some(id) = entity_id(self.kind) {
+            spawn_data.insert("entity", compound! { "id" => id });
+        }
+
+        compound! {
+            "SpawnData" => spawn_data,
+            "Delay" => self.delay as i16,
+            "MinSpawnDelay" => self.min_spawn_delay as i16,
+            "MaxSpawnDelay" => self.max_spawn_delay as i16,
+            "SpawnCount" => self.spawn_count as i16,
+            "SpawnRange" => self.spawn_range as i16,
+            "MaxNearbyEntities" => self.max_nearby_entities as i16,
+            "RequiredPlayerRange" => self.required_player_range as i16,
+        }
+    }
+
+    fn reset_delay(&mut self, rng: &mut dyn RngCore) {
+        let (min, max) = (self.min_spawn_delay, self.max_spawn_delay);
+
+        self.delay = if min < max {
+            rng.gen_range(min..max)
+        } else {
+            min
+        };
+    }
+}
+
+/// Returns the resource identifier of `kind`, such as `minecraft:zombie`.
+fn entity_id(kind: EntityKind) -> Option<String> {
+    let key = kind.translation_key()?.strip_prefix("entity.")?;
+    Some(key.replacen('.', ":", 1))
+}
+
+/// Information about an entity to spawn, passed to [`SpawnFn`]s.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SpawnContext {
+    /// The entity with the [`Spawner`] component.
+    pub spawner: Entity,
+    /// The chunk layer the spawner block is in.
+    pub layer: Entity,
+    pub kind: EntityKind,
+    /// The position to spawn the entity at.
+    pub position: DVec3,
+    /// The yaw to spawn the entity with.
+    pub yaw: f32,
+    /// The regional difficulty at the spawn position, for scaling the
+    /// equipment and attributes of the entity.
+    pub difficulty: RegionalDifficulty,
+}
+
+/// Sent for every entity a spawner is about to spawn.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct SpawnerSpawnEvent {
+    /// The entity with the [`Spawner`] component.
+    pub spawner: Entity,
+    pub kind: EntityKind,
+    pub position: DVec3,
+}
+
+/// Send this event after [`SpawnerSet::Tick`] and before
+/// [`SpawnerSet::Spawn`] to cancel all spawns of a spawner in the current
+/// tick.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CancelSpawnEvent {
+    /// The entity with the [`Spawner`] component.
+    pub spawner: Entity,
+}
+
+/// Sets the spawner block and its block entity data when a spawner is added
+/// or changed.
+fn update_spawner_blocks(
+    spawners: Query<&Spawner, Changed<Spawner>>,
+    mut layers: Query<&mut ChunkLayer>,
+) {
+    for spawner in &spawners {
+        if let Ok(mut layer) = layers.get_mut(spawner.layer) {
+            layer.set_block(
+                spawner.position,
+                Block::new(BlockState::SPAWNER, Some(spawner.block_entity_data())),
+            );
+        }
+    }
+}
+
+fn tick_spawners(
+    mut spawners: Query<(Entity, &mut Spawner)>,
+    players: Query<(&Position, &EntityLayerId), With<Client>>,
+    entities: Query<(&EntityKind, &Position, &EntityLayerId)>,
+    layers: Query<&ChunkLayer>,
+    mut layer_rngs: Query<&mut LayerRng>,
+    mut events: EventWriter<SpawnerSpawnEvent>,
+) {
+    let mut thread_rng = valence_server::rand::thread_rng();
+
+    for (spawner_entity, mut spawner) in &mut spawners {
+        let center = spawner.center();
+        let player_range = spawner.required_player_range as f64;
+
+        let is_active = players.iter().any(|(pos, layer)| {
+            layer.0 == spawner.layer && pos.0.distance_squared(center) <= player_range.powi(2)
+        });
+
+        if !is_active {
+            continue;
+        }
+
+        if spawner.delay > 0 {
+            // Don't trigger change detection, which would resend the block entity every
+            // tick.
+            spawner.bypass_change_detection().delay -= 1;
+            continue;
+        }
+
+        let Ok(layer) = layers.get(spawner.layer) else {
+            continue;
+        };
+
+        // Use the layer's generator so spawns can be reproduced from the seed.
+        let rng: &mut dyn RngCore = match layer_rngs.get_mut(spawner.layer) {
+            Ok(layer_rng) => &mut layer_rng.into_inner().0,
+            Err(_) => &mut thread_rng,
+        };
+
+        let range = spawner.spawn_range as f64;
+        let spawn_area = Aabb::new(
+            DVec3::new(center.x - range, center.y - 1.5, center.z - range),
+            DVec3::new(center.x + range, center.y + 2.5, center.z + range),
+        );
+
+        let mut nearby = entities
+            .iter()
+            .filter(|(kind, pos, layer)| {
+                **kind == spawner.kind
+                    && layer.0 == spawner.layer
+                    && spawn_area.contains_point(pos.0)
+            })
+            .count();
+
+        for _ in 0..spawner.spawn_count {
+            if nearby >= spawner.max_nearby_entities as usize {
+                break;
+            }
+
+            let position = DVec3::new(
+                center.x + (rng.gen::<f64>() - rng.gen::<f64>()) * range,
+                (spawner.position.y + rng.gen_range(-1..=1)) as f64,
+                center.z + (rng.gen::<f64>() - rng.gen::<f64>()) * range,
+            );
+
+            if !has_room(layer, position) {
+                continue;
+            }
+
+            events.send(SpawnerSpawnEvent {
+                spawner: spawner_entity,
+                kind: spawner.kind,
+                position,
+            });
+
+            nearby += 1;
+        }
+
+        spawner.reset_delay(rng);
+    }
+}
+
+/// Returns whether a mob standing at `position` doesn't collide with any
+/// blocks.
+fn has_room(layer: &ChunkLayer, position: DVec3) -> bool {
+    let feet = BlockPos::from(position);
+
+    [feet, feet.offset(0, 1, 0)].into_iter().all(|pos| {
+        layer
+            .block(pos)
+            .is_some_and(|block| !block.state.blocks_motion())
+    })
+}
+
+fn spawn_entities(
+    world: &mut World,
+    mut spawn_reader: Local<ManualEventReader<SpawnerSpawnEvent>>,
+    mut cancel_reader: Local<ManualEventReader<CancelSpawnEvent>>,
+) {
+    let cancelled: BTreeSet<_> = cancel_reader
+        .read(world.resource::<Events<CancelSpawnEvent>>())
+        .map(|event| event.spawner)
+        .collect();
+
+    let spawns: Vec<_> = spawn_reader
+        .read(world.resource::<Events<SpawnerSpawnEvent>>())
+        .filter(|event| !cancelled.contains(&event.spawner))
+        .copied()
+        .collect();
+
+    let mut spawned_by = BTreeSet::new();
+
+    for event in spawns {
+        let Some(spawner) = world.get::<Spawner>(event.spawner) else {
+            continue;
+        };
+
+        let layer = spawner.layer;
+
+        let yaw = match world.get_mut::<LayerRng>(layer) {
+            Some(mut rng) => rng.gen_range(-180.0..180.0),
+            None => valence_server::rand::thread_rng().gen_range(-180.0..180.0),
+        };
+
+        let ctx = SpawnContext {
+            spawner: event.spawner,
+            layer,
+            kind: event.kind,
+            position: event.position,
+            yaw,
+            difficulty: RegionalDifficulty::at(world, layer, BlockPos::from(event.position)),
+        };
+
+        let spawned = world
+            .resource_scope(|world, entities: Mut<SpawnerEntities>| entities.spawn(world, &ctx));
+
+        if spawned.is_none() {
+            continue;
+        }
+
+        spawned_by.insert(event.spawner);
+
+        if let Some(mut layer) = world.get_mut::<ChunkLayer>(ctx.layer) {
+            layer.play_particle(
+                &Particle::Poof,
+                false,
+                ctx.position + DVec3::new(0.0, 0.5, 0.0),
+                [0.3, 0.3, 0.3],
+                0.02,
+                20,
+            );
+        }
+    }
+
+    // Make the entity inside the spawner spin faster, like vanilla spawners do
+    // after spawning.
+    for spawner_entity in spawned_by {
+        let Some(spawner) = world.get::<Spawner>(spawner_entity) else {
+            continue;
+        };
+
+        let (layer, position) = (spawner.layer, spawner.position);
+
+        if let Some(mut layer) = world.get_mut::<ChunkLayer>(layer) {
+            layer.view_writer(position).write_packet(&BlockEventS2c {
+                position,
+                action_id: 1,
+                action_parameter: 0,
+                block_type: BlockState::SPAWNER.to_kind(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawner_entity_ids() {
+        assert_eq!(
+            entity_id(EntityKind::ZOMBIE).as_deref(),
+            Some("minecraft:zombie")
+        );
+        assert_eq!(
+            entity_id(EntityKind::CAVE_SPIDER).as_deref(),
+            Some("minecraft:cave_spider")
+        );
+    }
+}
@@ -0,0 +1,312 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod stat;
+
+use std::collections::BTreeMap;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use stat::{CustomStat, Stat};
+use valence_server::abilities::PlayerAbilitiesFlags;
+use valence_server::action::{DiggingEvent, DiggingState};
+use valence_server::client::{Client, VisibleChunkLayer};
+use valence_server::entity::entity::Flags;
+use valence_server::movement::MovementEvent;
+use valence_server::nbt::{Compound, Value};
+use valence_server::protocol::packets::play::statistics_s2c::Statistic;
+use valence_server::protocol::packets::play::StatisticsS2c;
+use valence_server::protocol::{VarInt, WritePacket};
+use valence_server::status::RequestStatsEvent;
+use valence_server::{ChunkLayer, EventLoopUpdate, GameMode};
+
+pub struct StatisticsPlugin;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveStatisticsEvent>()
+            .add_systems(
+                EventLoopUpdate,
+                (track_movement, track_mining, send_statistics),
+            )
+            .add_systems(Update, track_time)
+            .add_systems(Last, save_statistics);
+    }
+}
+
+/// The statistics of a player.
+///
+/// Statistics which were never incremented are zero and not stored.
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct PlayerStatistics {
+    values: BTreeMap<Stat, i32>,
+}
+
+impl PlayerStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of `stat`.
+    pub fn get(&self, stat: impl Into<Stat>) -> i32 {
+        self.values.get(&stat.into()).copied().unwrap_or(0)
+    }
+
+    /// Sets the value of `stat`.
+    pub fn set(&mut self, stat: impl Into<Stat>, value: i32) {
+        let stat = stat.into();
+
+        if value == 0 {
+            self.values.remove(&stat);
+        } else {
+            self.values.insert(stat, value);
+        }
+    }
+
+    /// Adds `amount` to the value of `stat`, saturating at [`i32::MAX`].
+    /// Returns the new value.
+    pub fn increment(&mut self, stat: impl Into<Stat>, amount: i32) -> i32 {
+        let stat = stat.into();
+        let value = self.get(stat).saturating_add(amount);
+        self.set(stat, value);
+        value
+    }
+
+    /// Returns an iterator over all the nonzero statistics and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (Stat, i32)> + '_ {
+        self.values.iter().map(|(&stat, &value)| (stat, value))
+    }
+
+    /// The number of nonzero statistics.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Converts the statistics to NBT with the layout of the `stats` object
+    /// of vanilla statistics files. For instance, the number of mined stone
+    /// blocks is at `"minecraft:mined"."minecraft:stone"`.
+    pub fn to_compound(&self) -> Compound {
+        let mut compound = Compound::new();
+
+        for (stat, value) in self.iter() {
+            let Some(name) = stat.name() else {
+                continue;
+            };
+
+            if !matches!(compound.get(stat.category_name()), Some(Value::Compound(_))) {
+                compound.insert(stat.category_name(), Compound::new());
+            }
+
+            if let Some(Value::Compound(category)) = compound.get_mut(stat.category_name()) {
+                category.insert(name, value);
+            }
+        }
+
+        compound
+    }
+
+    /// Reads statistics written by [`to_compound`](Self::to_compound).
+    /// Unknown statistics and values which aren't integers are skipped.
+    pub fn from_compound(compound: &Compound) -> Self {
+        let mut stats = Self::new();
+
+        for (category_name, category) in compound {
+            let Value::Compound(category) = category else {
+                continue;
+            };
+
+            for (name, value) in category {
+                if let (Some(stat), Value::Int(value)) =
+                    (Stat::from_names(category_name, name), value)
+                {
+                    stats.set(stat, *value);
+                }
+            }
+        }
+
+        stats
+    }
+}
+
+/// Sent at the end of the tick in which a client with [`PlayerStatistics`]
+/// disconnected, so its statistics can be saved.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct SaveStatisticsEvent {
+    pub client: Entity,
+    pub statistics: PlayerStatistics,
+}
+
+fn send_statistics(
+    mut events: EventReader<RequestStatsEvent>,
+    mut clients: Query<(&mut Client, Option<&PlayerStatistics>)>,
+) {
+    for event in events.read() {
+        let Ok((mut client, stats)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        // Clients without statistics still wait for a response.
+        let statistics = stats
+            .into_iter()
+            .flat_map(PlayerStatistics::iter)
+            .map(|(stat, value)| Statistic {
+                category_id: VarInt(stat.category_id()),
+                statistic_id: VarInt(stat.statistic_id()),
+                value: VarInt(value),
+            })
+            .collect();
+
+        client.write_packet(&StatisticsS2c { statistics });
+    }
+}
+
+fn track_time(mut clients: Query<(&mut PlayerStatistics, &Flags), With<Client>>) {
+    for (mut stats, flags) in &mut clients {
+        stats.increment(CustomStat::PlayTime, 1);
+        stats.increment(CustomStat::TotalWorldTime, 1);
+
+        if flags.sneaking() {
+            stats.increment(CustomStat::SneakTime, 1);
+        }
+    }
+}
+
+fn track_movement(
+    mut events: EventReader<MovementEvent>,
+    mut clients: Query<(&mut PlayerStatistics, &Flags, &PlayerAbilitiesFlags)>,
+) {
+    for event in events.read() {
+        let Ok((mut stats, flags, abilities)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        let delta = event.position - event.old_position;
+        let horizontal_cm = ((delta.x * delta.x + delta.z * delta.z).sqrt() * 100.0).round() as i32;
+
+        if event.old_on_ground && !event.on_ground && delta.y > 0.0 {
+            stats.increment(CustomStat::Jump, 1);
+        }
+
+        if horizontal_cm <= 0 {
+            continue;
+        }
+
+        let stat = if abilities.flying() {
+            CustomStat::FlyOneCm
+        } else if !event.on_ground {
+            continue;
+        } else if flags.sneaking() {
+            CustomStat::CrouchOneCm
+        } else if flags.sprinting() {
+            CustomStat::SprintOneCm
+        } else {
+            CustomStat::WalkOneCm
+        };
+
+        stats.increment(stat, horizontal_cm);
+    }
+}
+
+fn track_mining(
+    mut events: EventReader<DiggingEvent>,
+    mut clients: Query<(&mut PlayerStatistics, &GameMode, &VisibleChunkLayer)>,
+    layers: Query<&ChunkLayer>,
+) {
+    for event in events.read() {
+        if event.state != DiggingState::Stop {
+            continue;
+        }
+
+        let Ok((mut stats, game_mode, layer)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        if *game_mode != GameMode::Survival {
+            continue;
+        }
+
+        // The block is still there until the server breaks it.
+        if let Some(block) = layers
+            .get(layer.0)
+            .ok()
+            .and_then(|layer| layer.block(event.position))
+            .filter(|block| !block.state.is_air())
+        {
+            stats.increment(Stat::Mined(block.state.to_kind()), 1);
+        }
+    }
+}
+
+fn save_statistics(
+    mut removed: RemovedComponents<Client>,
+    clients: Query<&PlayerStatistics>,
+    mut events: EventWriter<SaveStatisticsEvent>,
+) {
+    for client in removed.read() {
+        if let Ok(stats) = clients.get(client) {
+            events.send(SaveStatisticsEvent {
+                client,
+                statistics: stats.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::block::BlockKind;
+    use valence_server::entity::EntityKind;
+    use valence_server::ItemKind;
+
+    use super::*;
+
+    #[test]
+    fn compound_round_trip() {
+        let mut stats = PlayerStatistics::new();
+
+        stats.increment(Stat::Mined(BlockKind::Stone), 3);
+        stats.increment(Stat::Dropped(ItemKind::Diamond), 1);
+        stats.increment(Stat::Killed(EntityKind::ZOMBIE), 2);
+        stats.increment(CustomStat::WalkOneCm, 1234);
+
+        let compound = stats.to_compound();
+
+        let Some(Value::Compound(mined)) = compound.get("minecraft:mined") else {
+            panic!("missing mined statistics");
+        };
+        assert_eq!(mined.get("minecraft:stone"), Some(&Value::Int(3)));
+
+        assert_eq!(PlayerStatistics::from_compound(&compound), stats);
+    }
+
+    #[test]
+    fn custom_stat_ids() {
+        assert_eq!(Stat::Custom(CustomStat::LeaveGame).statistic_id(), 0);
+        assert_eq!(Stat::Custom(CustomStat::Jump).statistic_id(), 21);
+        assert_eq!(
+            Stat::Custom(CustomStat::InteractWithSmithingTable).statistic_id(),
+            CustomStat::ALL.len() as i32 - 1
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use valence_server::block::BlockKind;
+use valence_server::entity::EntityKind;
+use valence_server::ItemKind;
+
+/// A statistic shown on the statistics screen of clients.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Stat {
+    /// The number of times a block was mined.
+    Mined(BlockKind),
+    /// The number of times an item was crafted.
+    Crafted(ItemKind),
+    /// The number of times an item was used.
+    Used(ItemKind),
+    /// The number of times an item broke because of its durability.
+    Broken(ItemKind),
+    /// The number of times an item was picked up.
+    PickedUp(ItemKind),
+    /// The number of times an item was dropped.
+    Dropped(ItemKind),
+    /// The number of times an entity was killed.
+    Killed(EntityKind),
+    /// The number of times an entity killed the player.
+    KilledBy(EntityKind),
+    /// One of the general statistics.
+    Custom(CustomStat),
+}
+
+impl Stat {
+    /// The ID of the statistic category in the `stat_type` registry.
+    pub const fn category_id(self) -> i32 {
+        match self {
+            Stat::Mined(_) => 0,
+            Stat::Crafted(_) => 1,
+            Stat::Used(_) => 2,
+            Stat::Broken(_) => 3,
+            Stat::PickedUp(_) => 4,
+            Stat::Dropped(_) => 5,
+            Stat::Killed(_) => 6,
+            Stat::KilledBy(_) => 7,
+            Stat::Custom(_) => 8,
+        }
+    }
+
+    /// The ID of the statistic within its category.
+    pub fn statistic_id(self) -> i32 {
+        match self {
+            Stat::Mined(block) => block.to_raw().into(),
+            Stat::Crafted(item)
+            | Stat::Used(item)
+            | Stat::Broken(item)
+            | Stat::PickedUp(item)
+            | Stat::Dropped(item) => item.to_raw().into(),
+            Stat::Killed(entity) | Stat::KilledBy(entity) => entity.get(),
+            Stat::Custom(custom) => custom as i32,
+        }
+    }
+
+    /// The name of the statistic category, like `minecraft:mined`.
+    pub const fn category_name(self) -> &'static str {
+        match self {
+            Stat::Mined(_) => "minecraft:mined",
+            Stat::Crafted(_) => "minecraft:crafted",
+            Stat::Used(_) => "minecraft:used",
+            Stat::Broken(_) => "minecraft:broken",
+            Stat::PickedUp(_) => "minecraft:picked_up",
+            Stat::Dropped(_) => "minecraft:dropped",
+            Stat::Killed(_) => "minecraft:killed",
+            Stat::KilledBy(_) => "minecraft:killed_by",
+            Stat::Custom(_) => "minecraft:custom",
+        }
+    }
+
+    /// The name of the statistic within its category, like `minecraft:stone`.
+    /// Returns `None` for unknown entity kinds.
+    pub fn name(self) -> Option<String> {
+        match self {
+            Stat::Mined(block) => Some(format!("minecraft:{}", block.to_str())),
+            Stat::Crafted(item)
+            | Stat::Used(item)
+            | Stat::Broken(item)
+            | Stat::PickedUp(item)
+            | Stat::Dropped(item) => Some(format!("minecraft:{}", item.to_str())),
+            Stat::Killed(entity) | Stat::KilledBy(entity) => entity_name(entity),
+            Stat::Custom(custom) => Some(format!("minecraft:{}", custom.to_str())),
+        }
+    }
+
+    /// Parses a statistic from the names of its category and itself. The
+    /// `minecraft:` namespace may be left out.
+    pub fn from_names(category: &str, name: &str) -> Option<Self> {
+        let category = category.strip_prefix("minecraft:").unwrap_or(category);
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+        let item = || ItemKind::from_str(name);
+        let entity = || {
+            (0..)
+                .map(EntityKind::new)
+                .map_while(|kind| Some((kind, entity_name(kind)?)))
+                .find(|(_, kind_name)| kind_name.strip_prefix("minecraft:") == Some(name))
+                .map(|(kind, _)| kind)
+        };
+
+        Some(match category {
+            "mined" => Stat::Mined(BlockKind::from_str(name)?),
+            "crafted" => Stat::Crafted(item()?),
+            "used" => Stat::Used(item()?),
+            "broken" => Stat::Broken(item()?),
+            "picked_up" => Stat::PickedUp(item()?),
+            "dropped" => Stat::Dropped(item()?),
+            "killed" => Stat::Killed(entity()?),
+            "killed_by" => Stat::KilledBy(entity()?),
+            "custom" => Stat::Custom(CustomStat::from_str(name)?),
+            _ => return None,
+        })
+    }
+}
+
+impl From<CustomStat> for Stat {
+    fn from(custom: CustomStat) -> Self {
+        Stat::Custom(custom)
+    }
+}
+
+fn entity_name(kind: EntityKind) -> Option<String> {
+    let key = kind.translation_key()?;
+    Some(format!(
+        "minecraft:{}",
+        key.strip_prefix("entity.minecraft.")?
+    ))
+}
+
+macro_rules! custom_stats {
+    ($($variant:ident => $name:literal,)*) => {
+        /// The general statistics of the `custom_stat` registry, in registry
+        /// order.
+        ///
+        /// Distances are counted in centimeters and times in ticks.
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum CustomStat {
+            $($variant,)*
+        }
+
+        impl CustomStat {
+            /// Parses a custom statistic from its name without namespace,
+            /// like `play_time`.
+            #[allow(clippy::should_implement_trait)]
+            pub fn from_str(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(CustomStat::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// The name of the custom statistic without namespace.
+            pub const fn to_str(self) -> &'static str {
+                match self {
+                    $(CustomStat::$variant => $name,)*
+                }
+            }
+
+            /// All custom statistics.
+            pub const ALL: &'static [Self] = &[$(CustomStat::$variant,)*];
+        }
+    };
+}
+
+custom_stats! {
+    LeaveGame => "leave_game",
+    PlayTime => "play_time",
+    TotalWorldTime => "total_world_time",
+    TimeSinceDeath => "time_since_death",
+    TimeSinceRest => "time_since_rest",
+    SneakTime => "sneak_time",
+    WalkOneCm => "walk_one_cm",
+    CrouchOneCm => "crouch_one_cm",
+    SprintOneCm => "sprint_one_cm",
+    WalkOnWaterOneCm => "walk_on_water_one_cm",
+    FallOneCm => "fall_one_cm",
+    ClimbOneCm => "climb_one_cm",
+    FlyOneCm => "fly_one_cm",
+    WalkUnderWaterOneCm => "walk_under_water_one_cm",
+    MinecartOneCm => "minecart_one_cm",
+    BoatOneCm => "boat_one_cm",
+    PigOneCm => "pig_one_cm",
+    HorseOneCm => "horse_one_cm",
+    AviateOneCm => "aviate_one_cm",
+    SwimOneCm => "swim_one_cm",
+    StriderOneCm => "strider_one_cm",
+    Jump => "jump",
+    Drop => "drop",
+    DamageDealt => "damage_dealt",
+    DamageDealtAbsorbed => "damage_dealt_absorbed",
+    DamageDealtResisted => "damage_dealt_resisted",
+    DamageBlockedByShield => "damage_blocked_by_shield",
+    DamageTaken => "damage_taken",
+    DamageAbsorbed => "damage_absorbed",
+    DamageResisted => "damage_resisted",
+    Deaths => "deaths",
+    MobKills => "mob_kills",
+    AnimalsBred => "animals_bred",
+    PlayerKills => "player_kills",
+    FishCaught => "fish_caught",
+    TalkedToVillager => "talked_to_villager",
+    TradedWithVillager => "traded_with_villager",
+    EatCakeSlice => "eat_cake_slice",
+    FillCauldron => "fill_cauldron",
+    UseCauldron => "use_cauldron",
+    CleanArmor => "clean_armor",
+    CleanBanner => "clean_banner",
+    CleanShulkerBox => "clean_shulker_box",
+    InteractWithBrewingstand => "interact_with_brewingstand",
+    InteractWithBeacon => "interact_with_beacon",
+    InspectDropper => "inspect_dropper",
+    InspectHopper => "inspect_hopper",
+    InspectDispenser => "inspect_dispenser",
+    PlayNoteblock => "play_noteblock",
+    TuneNoteblock => "tune_noteblock",
+    PotFlower => "pot_flower",
+    TriggerTrappedChest => "trigger_trapped_chest",
+    OpenEnderchest => "open_enderchest",
+    EnchantItem => "enchant_item",
+    PlayRecord => "play_record",
+    InteractWithFurnace => "interact_with_furnace",
+    InteractWithCraftingTable => "interact_with_crafting_table",
+    OpenChest => "open_chest",
+    SleepInBed => "sleep_in_bed",
+    OpenShulkerBox => "open_shulker_box",
+    OpenBarrel => "open_barrel",
+    InteractWithBlastFurnace => "interact_with_blast_furnace",
+    InteractWithSmoker => "interact_with_smoker",
+    InteractWithLectern => "interact_with_lectern",
+    InteractWithCampfire => "interact_with_campfire",
+    InteractWithCartographyTable => "interact_with_cartography_table",
+    InteractWithLoom => "interact_with_loom",
+    InteractWithStonecutter => "interact_with_stonecutter",
+    BellRing => "bell_ring",
+    RaidTrigger => "raid_trigger",
+    RaidWin => "raid_win",
+    InteractWithAnvil => "interact_with_anvil",
+    InteractWithGrindstone => "interact_with_grindstone",
+    TargetHit => "target_hit",
+    InteractWithSmithingTable => "interact_with_smithing_table",
+}
@@ -0,0 +1,411 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod schematic;
+mod transform;
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+use flate2::read::GzDecoder;
+pub use schematic::{Schematic, SchematicVersion};
+use thiserror::Error;
+pub use transform::{Mirror, Rotation};
+use valence_nbt::{Compound, List, Value};
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::layer::chunk::{shape, Block};
+use valence_server::{BlockPos, BlockState, ChunkLayer};
+
+/// A structure template, as saved by vanilla structure blocks.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Structure {
+    size: [u32; 3],
+    /// The block states of each palette. Most structures have a single
+    /// palette, but some have variants with the same blocks in different
+    /// materials.
+    palettes: Vec<Vec<BlockState>>,
+    blocks: Vec<RawBlock>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct RawBlock {
+    pos: BlockPos,
+    /// Index into the palette.
+    state: usize,
+    nbt: Option<Compound>,
+}
+
+/// A block of a [`Structure`], relative to the origin of the structure.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct StructureBlock<'a> {
+    pub pos: BlockPos,
+    pub state: BlockState,
+    /// The block entity data of the block.
+    pub nbt: Option<&'a Compound>,
+}
+
+impl Structure {
+    /// Reads a gzip compressed structure file, like the `.nbt` files saved by
+    /// structure blocks.
+    pub fn read(reader: impl Read) -> Result<Self, StructureError> {
+        let mut buf = vec![];
+        GzDecoder::new(reader).read_to_end(&mut buf)?;
+
+        let (root, _) = valence_nbt::from_binary::<String>(&mut buf.as_slice())?;
+
+        Self::from_nbt(&root)
+    }
+
+    /// Parses a structure from the root compound of a structure file.
+    pub fn from_nbt(root: &Compound) -> Result<Self, StructureError> {
+        let size = match root.get("size") {
+            Some(Value::List(List::Int(size)))
+                if size.len() == 3 && size.iter().all(|&n| n >= 0) =>
+            {
+                [size[0] as u32, size[1] as u32, size[2] as u32]
+            }
+            _ => return Err(StructureError::BadField("size")),
+        };
+
+        let palettes = match (root.get("palette"), root.get("palettes")) {
+            (Some(Value::List(List::Compound(palette))), _) => vec![parse_palette(palette)?],
+            (_, Some(Value::List(List::List(palettes)))) if !palettes.is_empty() => palettes
+                .iter()
+                .map(|palette| match palette {
+                    List::Compound(palette) => parse_palette(palette),
+                    List::End => Ok(vec![]),
+                    _ => Err(StructureError::BadField("palettes")),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => return Err(StructureError::BadField("palette")),
+        };
+
+        let blocks: &[Compound] = match root.get("blocks") {
+            Some(Value::List(List::Compound(blocks))) => blocks,
+            Some(Value::List(List::End)) => &[],
+            _ => return Err(StructureError::BadField("blocks")),
+        };
+
+        let blocks = blocks
+            .iter()
+            .map(|block| {
+                let pos = match block.get("pos") {
+                    Some(Value::List(List::Int(pos))) if pos.len() == 3 => {
+                        BlockPos::new(pos[0], pos[1], pos[2])
+                    }
+                    _ => return Err(StructureError::BadField("pos")),
+                };
+
+                let state = match block.get("state") {
+                    Some(&Value::Int(state)) => state,
+                    _ => return Err(StructureError::BadField("state")),
+                };
+
+                if palettes
+                    .iter()
+                    .any(|p| state < 0 || state as usize >= p.len())
+                {
+                    return Err(StructureError::BadPaletteIndex(state));
+                }
+
+                let nbt = match block.get("nbt") {
+                    Some(Value::Compound(nbt)) => {
+                        let mut nbt = nbt.clone();
+
+                        // The block entity is identified by the block, and
+                        // positioned by the layer.
+                        for key in ["id", "x", "y", "z"] {
+                            nbt.remove(key);
+                        }
+
+                        Some(nbt)
+                    }
+                    _ => None,
+                };
+
+                Ok(RawBlock {
+                    pos,
+                    state: state as usize,
+                    nbt,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            size,
+            palettes,
+            blocks,
+        })
+    }
+
+    /// The size of the structure along the X, Y, and Z axes.
+    pub fn size(&self) -> [u32; 3] {
+        self.size
+    }
+
+    /// The number of palettes of the structure, which can be selected with
+    /// [`PlaceSettings::with_palette`].
+    pub fn palette_count(&self) -> usize {
+        self.palettes.len()
+    }
+
+    /// Returns the blocks of the structure using its first palette. Positions
+    /// without a block are not included.
+    pub fn blocks(&self) -> impl Iterator<Item = StructureBlock<'_>> + '_ {
+        self.blocks.iter().map(|block| StructureBlock {
+            pos: block.pos,
+            state: self.palettes[0][block.state],
+            nbt: block.nbt.as_ref(),
+        })
+    }
+
+    /// Places the structure in `layer`, with the structure's origin at
+    /// `origin`. Blocks in unloaded chunks are skipped.
+    ///
+    /// Returns the area covered by the structure, which can be used to save
+    /// the blocks it replaced beforehand.
+    pub fn place(
+        &self,
+        layer: &mut ChunkLayer,
+        origin: impl Into<BlockPos>,
+        settings: &PlaceSettings,
+    ) -> StructureRegion {
+        let origin = origin.into();
+        let palette = &self.palettes[settings.palette.min(self.palettes.len() - 1)];
+
+        for block in &self.blocks {
+            let mut state = palette[block.state];
+
+            if state.to_kind() == BlockKind::StructureVoid {
+                continue;
+            }
+
+            if let Some(&kind) = settings.replacements.get(&state.to_kind()) {
+                state = replace_kind(state, kind);
+            }
+
+            let state = transform::transform_state(state, settings.mirror, settings.rotation);
+            let offset = transform::transform_pos(block.pos, settings.mirror, settings.rotation);
+
+            let nbt = match &block.nbt {
+                Some(nbt) if settings.block_entities && state.block_entity_kind().is_some() => {
+                    Some(nbt.clone())
+                }
+                // Initializes the block entity if there is one.
+                _ => state.block_entity_kind().map(|_| Compound::new()),
+            };
+
+            let pos = BlockPos::new(
+                origin.x + offset.x,
+                origin.y + offset.y,
+                origin.z + offset.z,
+            );
+
+            layer.set_block(pos, Block { state, nbt });
+        }
+
+        self.region(origin, settings)
+    }
+
+    /// Returns the area the structure would cover if it was placed at
+    /// `origin`, without placing it.
+    pub fn region(&self, origin: impl Into<BlockPos>, settings: &PlaceSettings) -> StructureRegion {
+        let origin = origin.into();
+        let [x, y, z] = self.size.map(|n| n.max(1) as i32 - 1);
+
+        let a =
+            transform::transform_pos(BlockPos::new(0, 0, 0), settings.mirror, settings.rotation);
+        let b =
+            transform::transform_pos(BlockPos::new(x, y, z), settings.mirror, settings.rotation);
+
+        StructureRegion {
+            min: BlockPos::new(
+                origin.x + a.x.min(b.x),
+                origin.y + a.y.min(b.y),
+                origin.z + a.z.min(b.z),
+            ),
+            max: BlockPos::new(
+                origin.x + a.x.max(b.x),
+                origin.y + a.y.max(b.y),
+                origin.z + a.z.max(b.z),
+            ),
+        }
+    }
+}
+
+/// Settings for [`Structure::place`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlaceSettings {
+    pub rotation: Rotation,
+    pub mirror: Mirror,
+    /// The index of the palette to use. Out of bounds indices use the last
+    /// palette.
+    pub palette: usize,
+    /// Blocks of the key kind are replaced by blocks of the value kind. The
+    /// properties of the block are kept where possible.
+    pub replacements: HashMap<BlockKind, BlockKind>,
+    /// Whether the block entity data saved in the structure is placed.
+    /// Otherwise, block entities are placed empty.
+    ///
+    /// # Default Value
+    ///
+    /// `true`
+    pub block_entities: bool,
+}
+
+impl Default for PlaceSettings {
+    fn default() -> Self {
+        Self {
+            rotation: Rotation::None,
+            mirror: Mirror::None,
+            palette: 0,
+            replacements: HashMap::new(),
+            block_entities: true,
+        }
+    }
+}
+
+impl PlaceSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_mirror(mut self, mirror: Mirror) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    pub fn with_palette(mut self, palette: usize) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_replacement(mut self, from: BlockKind, to: BlockKind) -> Self {
+        self.replacements.insert(from, to);
+        self
+    }
+
+    pub fn with_block_entities(mut self, block_entities: bool) -> Self {
+        self.block_entities = block_entities;
+        self
+    }
+}
+
+/// The area covered by a placed [`Structure`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StructureRegion {
+    /// The corner of the area with the smallest coordinates.
+    pub min: BlockPos,
+    /// The corner of the area with the largest coordinates.
+    pub max: BlockPos,
+}
+
+impl StructureRegion {
+    pub fn contains(&self, pos: impl Into<BlockPos>) -> bool {
+        let pos = pos.into();
+
+        (self.min.x..=self.max.x).contains(&pos.x)
+            && (self.min.y..=self.max.y).contains(&pos.y)
+            && (self.min.z..=self.max.z).contains(&pos.z)
+    }
+
+    /// Returns every position in the area.
+    pub fn positions(&self) -> impl Iterator<Item = BlockPos> {
+        shape::cuboid(self.min, self.max, true)
+    }
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum StructureError {
+    #[error("an I/O error occurred: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed to parse NBT: {0}")]
+    Nbt(#[from] valence_nbt::binary::Error),
+    #[error("missing or invalid field \"{0}\"")]
+    BadField(&'static str),
+    #[error("unknown block name of \"{0}\"")]
+    UnknownBlockName(String),
+    #[error("unknown property name of \"{0}\"")]
+    UnknownPropName(String),
+    #[error("unknown property value of \"{0}\"")]
+    UnknownPropValue(String),
+    #[error("invalid block palette index of {0}")]
+    BadPaletteIndex(i32),
+    #[error("unknown biome name of \"{0}\"")]
+    UnknownBiome(String),
+    #[error("unsupported schematic version {0}")]
+    UnsupportedVersion(i32),
+}
+
+fn parse_palette(palette: &[Compound]) -> Result<Vec<BlockState>, StructureError> {
+    palette
+        .iter()
+        .map(|block| {
+            let Some(Value::String(name)) = block.get("Name") else {
+                return Err(StructureError::BadField("Name"));
+            };
+
+            let path = name
+                .rsplit_once(':')
+                .map_or(name.as_str(), |(_, path)| path);
+
+            let Some(kind) = BlockKind::from_str(path) else {
+                return Err(StructureError::UnknownBlockName(name.clone()));
+            };
+
+            let mut state = kind.to_state();
+
+            if let Some(Value::Compound(properties)) = block.get("Properties") {
+                for (key, value) in properties {
+                    let Value::String(value) = value else {
+                        return Err(StructureError::BadField("Properties"));
+                    };
+
+                    let Some(name) = PropName::from_str(key) else {
+                        return Err(StructureError::UnknownPropName(key.clone()));
+                    };
+
+                    let Some(value) = PropValue::from_str(value) else {
+                        return Err(StructureError::UnknownPropValue(value.clone()));
+                    };
+
+                    state = state.set(name, value);
+                }
+            }
+
+            Ok(state)
+        })
+        .collect()
+}
+
+/// Changes the kind of a block state, keeping the properties both kinds have.
+fn replace_kind(state: BlockState, kind: BlockKind) -> BlockState {
+    state
+        .to_kind()
+        .props()
+        .iter()
+        .filter_map(|&name| Some((name, state.get(name)?)))
+        .fold(kind.to_state(), |new, (name, value)| new.set(name, value))
+}
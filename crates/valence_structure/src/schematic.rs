@@ -0,0 +1,604 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use valence_nbt::{compound, Compound, List, Value};
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::layer::chunk::region::{self, Clipboard, Region};
+use valence_server::layer::chunk::Block;
+use valence_server::registry::biome::BiomeId;
+use valence_server::registry::BiomeRegistry;
+use valence_server::{BiomePos, BlockPos, BlockState, ChunkLayer, Ident};
+
+use crate::StructureError;
+
+/// The data version of Minecraft 1.20.1, written to schematic files.
+const DATA_VERSION: i32 = 3465;
+
+/// A [`Clipboard`] with biomes, which can be read from and written to Sponge
+/// schematic files. These are the `.schem` files used by WorldEdit.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Schematic {
+    pub clipboard: Clipboard,
+    /// The position of the minimum corner of the clipboard, relative to the
+    /// player who copied it.
+    pub offset: BlockPos,
+    /// The biome of each block, indexed by `x + z * size_x + y * size_x *
+    /// size_z`. `None` if the schematic doesn't have biomes.
+    biomes: Option<Vec<BiomeId>>,
+}
+
+/// The version of the Sponge schematic format to write.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub enum SchematicVersion {
+    /// Version 2, which is read by all versions of WorldEdit for Minecraft
+    /// 1.13 and later. Biomes are saved per column, using the biome of the
+    /// lowest block.
+    #[default]
+    V2,
+    /// Version 3, which is read by WorldEdit 7.3 and later. Biomes are saved
+    /// per block.
+    V3,
+}
+
+impl Schematic {
+    /// Creates a schematic from a clipboard without biomes.
+    pub fn new(clipboard: Clipboard) -> Self {
+        Self {
+            clipboard,
+            offset: BlockPos::new(0, 0, 0),
+            biomes: None,
+        }
+    }
+
+    /// Copies the blocks and biomes of `region` in `layer`.
+    pub fn copy(layer: &ChunkLayer, region: Region) -> Self {
+        let clipboard = region::copy(layer, region);
+
+        let biomes = region
+            .positions()
+            .map(|pos| layer.biome(BiomePos::from(pos)).unwrap_or_default())
+            .collect();
+
+        Self {
+            clipboard,
+            offset: BlockPos::new(0, 0, 0),
+            biomes: Some(biomes),
+        }
+    }
+
+    /// Places the blocks of the schematic with the minimum corner of the
+    /// clipboard at `origin`, and sets the biomes of the area if the
+    /// schematic has biomes. Returns the number of blocks set.
+    ///
+    /// See [`region::paste`].
+    pub fn paste(
+        &self,
+        layer: &mut ChunkLayer,
+        origin: impl Into<BlockPos>,
+        skip_air: bool,
+    ) -> usize {
+        let origin = origin.into();
+
+        if let Some(biomes) = &self.biomes {
+            let region = Region::with_size(origin, self.clipboard.size());
+
+            for (pos, &biome) in region.positions().zip(biomes) {
+                layer.set_biome(BiomePos::from(pos), biome);
+            }
+        }
+
+        region::paste(layer, &self.clipboard, origin, skip_air)
+    }
+
+    /// Returns the biome of the block at `offset` from the minimum corner of
+    /// the clipboard.
+    pub fn biome(&self, offset: [u32; 3]) -> Option<BiomeId> {
+        let [sx, sy, sz] = self.clipboard.size();
+        let [x, y, z] = offset;
+
+        if x >= sx || y >= sy || z >= sz {
+            return None;
+        }
+
+        let idx = x as usize + z as usize * sx as usize + y as usize * (sx * sz) as usize;
+
+        self.biomes.as_ref()?.get(idx).copied()
+    }
+
+    /// Reads a gzip compressed Sponge schematic file of version 2 or 3.
+    /// Biome names are looked up in `biomes`.
+    pub fn read(reader: impl Read, biomes: &BiomeRegistry) -> Result<Self, StructureError> {
+        let mut buf = vec![];
+        GzDecoder::new(reader).read_to_end(&mut buf)?;
+
+        let (root, _) = valence_nbt::from_binary::<String>(&mut buf.as_slice())?;
+
+        Self::from_nbt(&root, biomes)
+    }
+
+    /// Parses the root compound of a Sponge schematic file.
+    pub fn from_nbt(root: &Compound, biomes: &BiomeRegistry) -> Result<Self, StructureError> {
+        // Version 3 nests the schematic in the root.
+        let root = match root.get("Schematic") {
+            Some(Value::Compound(schematic)) => schematic,
+            _ => root,
+        };
+
+        let version = match root.get("Version") {
+            Some(&Value::Int(version)) => version,
+            _ => return Err(StructureError::BadField("Version")),
+        };
+
+        let mut size = [0; 3];
+
+        for (n, key) in size.iter_mut().zip(["Width", "Height", "Length"]) {
+            *n = match root.get(key) {
+                Some(&Value::Short(n)) => n as u16 as u32,
+                _ => return Err(StructureError::BadField(key)),
+            };
+        }
+
+        let offset = match root.get("Offset") {
+            Some(Value::IntArray(offset)) if offset.len() == 3 => {
+                BlockPos::new(offset[0], offset[1], offset[2])
+            }
+            _ => BlockPos::new(0, 0, 0),
+        };
+
+        let volume = size.iter().map(|&n| n as usize).product::<usize>();
+
+        let (blocks, block_entities, biome_data, biomes_3d) = match version {
+            2 => (
+                root,
+                root.get("BlockEntities"),
+                get_compound(root, "BiomePalette").map(|palette| (palette, root.get("BiomeData"))),
+                false,
+            ),
+            3 => {
+                let Some(blocks) = get_compound(root, "Blocks") else {
+                    return Err(StructureError::BadField("Blocks"));
+                };
+
+                let biome_data = get_compound(root, "Biomes").and_then(|biomes| {
+                    Some((get_compound(biomes, "Palette")?, biomes.get("Data")))
+                });
+
+                (blocks, blocks.get("BlockEntities"), biome_data, true)
+            }
+            _ => return Err(StructureError::UnsupportedVersion(version)),
+        };
+
+        let Some(palette) = get_compound(blocks, "Palette") else {
+            return Err(StructureError::BadField("Palette"));
+        };
+
+        let data_key = if version == 2 { "BlockData" } else { "Data" };
+        let palette = read_palette(palette, parse_state)?;
+        let states = read_data(blocks.get(data_key), volume, data_key)?;
+
+        let mut clipboard = Clipboard::new(size);
+
+        for (i, state) in states.into_iter().enumerate() {
+            let Some(&state) = palette.get(&state) else {
+                return Err(StructureError::BadPaletteIndex(state));
+            };
+
+            clipboard.set_block(offset_of(i, size), state);
+        }
+
+        if let Some(Value::List(List::Compound(block_entities))) = block_entities {
+            for block_entity in block_entities {
+                let pos = match block_entity.get("Pos") {
+                    Some(Value::IntArray(pos)) if pos.len() == 3 && pos.iter().all(|&n| n >= 0) => {
+                        [pos[0] as u32, pos[1] as u32, pos[2] as u32]
+                    }
+                    _ => return Err(StructureError::BadField("Pos")),
+                };
+
+                let nbt = if version == 2 {
+                    let mut nbt = block_entity.clone();
+                    nbt.remove("Pos");
+                    nbt.remove("Id");
+                    nbt
+                } else {
+                    get_compound(block_entity, "Data")
+                        .cloned()
+                        .unwrap_or_default()
+                };
+
+                if let Some(block) = clipboard.block(pos) {
+                    let state = block.state;
+                    clipboard.set_block(pos, Block::new(state, Some(nbt)));
+                }
+            }
+        }
+
+        let biomes = match biome_data {
+            Some((palette, data)) => {
+                let palette = read_palette(palette, |name| {
+                    Ident::new(name)
+                        .ok()
+                        .and_then(|ident| biomes.index_of(ident.as_str_ident()))
+                        .ok_or_else(|| StructureError::UnknownBiome(name.to_owned()))
+                })?;
+
+                let area = (size[0] * size[2]) as usize;
+                let data = read_data(data, if biomes_3d { volume } else { area }, "Biomes")?;
+
+                let biomes = (0..volume)
+                    .map(|i| {
+                        // Version 2 biomes are the same for every block in a column.
+                        let idx = if biomes_3d { i } else { i % area.max(1) };
+
+                        palette
+                            .get(&data[idx])
+                            .copied()
+                            .ok_or(StructureError::BadPaletteIndex(data[idx]))
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Some(biomes)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            clipboard,
+            offset,
+            biomes,
+        })
+    }
+
+    /// Writes the schematic as a gzip compressed Sponge schematic file.
+    /// Biome names are looked up in `biomes`.
+    pub fn write(
+        &self,
+        writer: impl Write,
+        version: SchematicVersion,
+        biomes: &BiomeRegistry,
+    ) -> Result<(), StructureError> {
+        let root = self.to_nbt(version, biomes);
+
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        // Version 2 files have the schematic as the root compound, named
+        // `Schematic`.
+        let root_name = match version {
+            SchematicVersion::V2 => "Schematic",
+            SchematicVersion::V3 => "",
+        };
+        valence_nbt::to_binary(&root, &mut encoder, root_name)?;
+        encoder.finish()?;
+
+        Ok(())
+    }
+
+    /// Returns the root compound of a Sponge schematic file.
+    pub fn to_nbt(&self, version: SchematicVersion, biomes: &BiomeRegistry) -> Compound {
+        let size = self.clipboard.size();
+        let volume = size.iter().map(|&n| n as usize).product::<usize>();
+
+        let mut palette = Palette::default();
+        let mut data = vec![];
+        let mut block_entities = vec![];
+
+        for i in 0..volume {
+            let pos = offset_of(i, size);
+            let Some(block) = self.clipboard.block(pos) else {
+                continue;
+            };
+
+            write_var_int(&mut data, palette.index(format_state(block.state)));
+
+            if let (Some(nbt), Some(kind)) = (block.nbt, block.state.block_entity_kind()) {
+                let mut block_entity = compound! {
+                    "Pos" => Value::IntArray(pos_array(pos_of(i, size))),
+                    "Id" => kind.ident().as_str(),
+                };
+
+                match version {
+                    SchematicVersion::V2 => block_entity.extend(nbt.clone()),
+                    SchematicVersion::V3 => {
+                        block_entity.insert("Data", nbt.clone());
+                    }
+                }
+
+                block_entities.push(block_entity);
+            }
+        }
+
+        let mut root = compound! {
+            "Version" => match version {
+                SchematicVersion::V2 => 2,
+                SchematicVersion::V3 => 3,
+            },
+            "DataVersion" => DATA_VERSION,
+            "Width" => size[0] as u16 as i16,
+            "Height" => size[1] as u16 as i16,
+            "Length" => size[2] as u16 as i16,
+            "Offset" => Value::IntArray(pos_array(self.offset)),
+        };
+
+        let block_entities = List::Compound(block_entities);
+
+        let biomes = self.biomes.as_ref().map(|ids| {
+            let mut palette = Palette::default();
+            let mut data = vec![];
+
+            let count = match version {
+                // The bottom layer of blocks.
+                SchematicVersion::V2 => (size[0] * size[2]) as usize,
+                SchematicVersion::V3 => volume,
+            };
+
+            let names: HashMap<_, _> = biomes
+                .iter()
+                .map(|(id, name, _)| (id, name.to_string()))
+                .collect();
+
+            for id in ids.iter().take(count) {
+                let name = names
+                    .get(id)
+                    .cloned()
+                    .unwrap_or_else(|| "minecraft:plains".into());
+
+                write_var_int(&mut data, palette.index(name));
+            }
+
+            (palette.into_compound(), Value::ByteArray(data))
+        });
+
+        match version {
+            SchematicVersion::V2 => {
+                root.insert("PaletteMax", palette.len());
+                root.insert("Palette", palette.into_compound());
+                root.insert("BlockData", Value::ByteArray(data));
+                root.insert("BlockEntities", block_entities);
+
+                if let Some((palette, data)) = biomes {
+                    root.insert("BiomePaletteMax", palette.len() as i32);
+                    root.insert("BiomePalette", palette);
+                    root.insert("BiomeData", data);
+                }
+
+                root
+            }
+            SchematicVersion::V3 => {
+                root.insert(
+                    "Blocks",
+                    compound! {
+                        "Palette" => palette.into_compound(),
+                        "Data" => Value::ByteArray(data),
+                        "BlockEntities" => block_entities,
+                    },
+                );
+
+                if let Some((palette, data)) = biomes {
+                    root.insert(
+                        "Biomes",
+                        compound! {
+                            "Palette" => palette,
+                            "Data" => data,
+                        },
+                    );
+                }
+
+                compound! { "Schematic" => root }
+            }
+        }
+    }
+}
+
+/// Assigns indices to the entries of a palette in the order they are first
+/// seen.
+#[derive(Default)]
+struct Palette {
+    entries: HashMap<String, i32>,
+}
+
+impl Palette {
+    fn index(&mut self, entry: String) -> i32 {
+        let len = self.entries.len() as i32;
+        *self.entries.entry(entry).or_insert(len)
+    }
+
+    fn len(&self) -> i32 {
+        self.entries.len() as i32
+    }
+
+    fn into_compound(self) -> Compound {
+        self.entries
+            .into_iter()
+            .map(|(entry, idx)| (entry, Value::Int(idx)))
+            .collect()
+    }
+}
+
+fn get_compound<'a>(compound: &'a Compound, key: &str) -> Option<&'a Compound> {
+    match compound.get(key) {
+        Some(Value::Compound(value)) => Some(value),
+        _ => None,
+    }
+}
+
+fn read_palette<T>(
+    palette: &Compound,
+    parse: impl Fn(&str) -> Result<T, StructureError>,
+) -> Result<HashMap<i32, T>, StructureError> {
+    palette
+        .iter()
+        .map(|(name, idx)| match idx {
+            &Value::Int(idx) => Ok((idx, parse(name)?)),
+            _ => Err(StructureError::BadField("Palette")),
+        })
+        .collect()
+}
+
+/// Reads `len` palette indices encoded as VarInts.
+fn read_data(
+    data: Option<&Value>,
+    len: usize,
+    key: &'static str,
+) -> Result<Vec<i32>, StructureError> {
+    let Some(Value::ByteArray(bytes)) = data else {
+        return Err(StructureError::BadField(key));
+    };
+
+    let mut bytes = bytes.iter().map(|&b| b as u8);
+    let mut values = Vec::with_capacity(len);
+
+    while values.len() < len {
+        let mut value = 0_i32;
+
+        for shift in (0..35).step_by(7) {
+            let Some(byte) = bytes.next() else {
+                return Err(StructureError::BadField(key));
+            };
+
+            value |= ((byte & 0x7f) as i32) << shift;
+
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+fn write_var_int(data: &mut Vec<i8>, value: i32) {
+    let mut value = value as u32;
+
+    loop {
+        if value & !0x7f == 0 {
+            data.push(value as i8);
+            return;
+        }
+
+        data.push((value & 0x7f | 0x80) as u8 as i8);
+        value >>= 7;
+    }
+}
+
+/// Parses a block state like `minecraft:oak_stairs[facing=north,half=top]`.
+fn parse_state(name: &str) -> Result<BlockState, StructureError> {
+    let (kind_name, props) = match name.split_once('[') {
+        Some((kind_name, props)) => (kind_name, props.strip_suffix(']').unwrap_or(props)),
+        None => (name, ""),
+    };
+
+    let path = kind_name
+        .rsplit_once(':')
+        .map_or(kind_name, |(_, path)| path);
+
+    let Some(kind) = BlockKind::from_str(path) else {
+        return Err(StructureError::UnknownBlockName(kind_name.into()));
+    };
+
+    let mut state = kind.to_state();
+
+    for prop in props.split(',').filter(|prop| !prop.is_empty()) {
+        let (key, value) = prop.split_once('=').unwrap_or((prop, ""));
+
+        let Some(key) = PropName::from_str(key) else {
+            return Err(StructureError::UnknownPropName(key.into()));
+        };
+
+        let Some(value) = PropValue::from_str(value) else {
+            return Err(StructureError::UnknownPropValue(value.into()));
+        };
+
+        state = state.set(key, value);
+    }
+
+    Ok(state)
+}
+
+fn format_state(state: BlockState) -> String {
+    let kind = state.to_kind();
+
+    let props: Vec<_> = kind
+        .props()
+        .iter()
+        .filter_map(|&name| Some(format!("{}={}", name.to_str(), state.get(name)?.to_str())))
+        .collect();
+
+    if props.is_empty() {
+        format!("minecraft:{}", kind.to_str())
+    } else {
+        format!("minecraft:{}[{}]", kind.to_str(), props.join(","))
+    }
+}
+
+/// Converts an index in the block data to an offset in the clipboard.
+fn offset_of(i: usize, [sx, _, sz]: [u32; 3]) -> [u32; 3] {
+    let (sx, sz) = (sx as usize, sz as usize);
+
+    [
+        (i % sx) as u32,
+        (i / (sx * sz)) as u32,
+        (i / sx % sz) as u32,
+    ]
+}
+
+fn pos_of(i: usize, size: [u32; 3]) -> BlockPos {
+    let [x, y, z] = offset_of(i, size);
+    BlockPos::new(x as i32, y as i32, z as i32)
+}
+
+fn pos_array(pos: BlockPos) -> Vec<i32> {
+    vec![pos.x, pos.y, pos.z]
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::ident::ident;
+
+    use super::*;
+
+    #[test]
+    fn state_names() {
+        let stairs = BlockState::OAK_STAIRS
+            .set(PropName::Facing, PropValue::East)
+            .set(PropName::Half, PropValue::Top);
+
+        assert_eq!(parse_state(&format_state(stairs)).unwrap(), stairs);
+        assert_eq!(parse_state("stone").unwrap(), BlockState::STONE);
+        assert!(matches!(
+            parse_state("minecraft:stone[nope=1]"),
+            Err(StructureError::UnknownPropName(_))
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut registry = BiomeRegistry::default();
+        registry.insert(ident!("plains"), Default::default());
+        registry.insert(ident!("desert"), Default::default());
+        let desert = registry.index_of(ident!("desert")).unwrap();
+
+        let mut clipboard = Clipboard::new([3, 2, 200]);
+        clipboard.set_block([2, 1, 150], BlockState::GLASS);
+        clipboard.set_block(
+            [0, 0, 1],
+            Block::new(BlockState::CHEST, Some(compound! { "Lock" => "key" })),
+        );
+
+        let mut schematic = Schematic::new(clipboard);
+        schematic.offset = BlockPos::new(-1, 0, 5);
+        schematic.biomes = Some(vec![desert; 3 * 2 * 200]);
+
+        for version in [SchematicVersion::V2, SchematicVersion::V3] {
+            let mut buf = vec![];
+            schematic.write(&mut buf, version, &registry).unwrap();
+
+            let read = Schematic::read(buf.as_slice(), &registry).unwrap();
+            assert_eq!(read, schematic);
+            assert_eq!(read.biome([2, 1, 199]), Some(desert));
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use valence_server::block::{PropName, PropValue};
+use valence_server::{BlockPos, BlockState};
+
+/// A rotation around the vertical axis, applied after the [`Mirror`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    CounterClockwise90,
+}
+
+impl Rotation {
+    fn quarter_turns(self) -> u16 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Clockwise90 => 1,
+            Rotation::Clockwise180 => 2,
+            Rotation::CounterClockwise90 => 3,
+        }
+    }
+}
+
+/// A reflection of the structure, applied before the [`Rotation`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub enum Mirror {
+    #[default]
+    None,
+    /// Flips the Z axis, swapping north and south.
+    LeftRight,
+    /// Flips the X axis, swapping east and west.
+    FrontBack,
+}
+
+/// Transforms a position relative to the origin of a structure.
+pub(crate) fn transform_pos(pos: BlockPos, mirror: Mirror, rotation: Rotation) -> BlockPos {
+    let (x, z) = match mirror {
+        Mirror::None => (pos.x, pos.z),
+        Mirror::LeftRight => (pos.x, -pos.z),
+        Mirror::FrontBack => (-pos.x, pos.z),
+    };
+
+    let (x, z) = match rotation {
+        Rotation::None => (x, z),
+        Rotation::Clockwise90 => (-z, x),
+        Rotation::Clockwise180 => (-x, -z),
+        Rotation::CounterClockwise90 => (z, -x),
+    };
+
+    BlockPos::new(x, pos.y, z)
+}
+
+/// Transforms the properties of a block state which depend on the direction
+/// the block is placed in, like `facing`, `axis`, and the sides of fences.
+pub(crate) fn transform_state(
+    mut state: BlockState,
+    mirror: Mirror,
+    rotation: Rotation,
+) -> BlockState {
+    if mirror == Mirror::None && rotation == Rotation::None {
+        return state;
+    }
+
+    if let Some(facing) = state.get(PropName::Facing) {
+        if let Some(dir) = transform_dir(facing.to_str(), mirror, rotation) {
+            state = set_str(state, PropName::Facing, dir);
+        }
+    }
+
+    if rotation.quarter_turns() % 2 == 1 {
+        match state.get(PropName::Axis) {
+            Some(PropValue::X) => state = state.set(PropName::Axis, PropValue::Z),
+            Some(PropValue::Z) => state = state.set(PropName::Axis, PropValue::X),
+            _ => {}
+        }
+    }
+
+    // Signs, banners, and heads have 16 rotations.
+    if let Some(r) = state.get(PropName::Rotation).and_then(|r| r.to_u16()) {
+        let r = match mirror {
+            Mirror::None => r,
+            Mirror::LeftRight => (16 - r) % 16,
+            Mirror::FrontBack => (24 - r) % 16,
+        };
+
+        if let Some(r) = PropValue::from_u16((r + rotation.quarter_turns() * 4) % 16) {
+            state = state.set(PropName::Rotation, r);
+        }
+    }
+
+    // Fences, walls, panes, vines, and redstone wire connect to their sides.
+    const SIDES: [PropName; 4] = [
+        PropName::North,
+        PropName::East,
+        PropName::South,
+        PropName::West,
+    ];
+
+    let sides = SIDES.map(|side| state.get(side));
+
+    if sides.iter().any(Option::is_some) {
+        for (side, value) in SIDES.iter().zip(sides) {
+            let (Some(value), Some(dir)) = (value, transform_dir(side.to_str(), mirror, rotation))
+            else {
+                continue;
+            };
+
+            if let Some(new_side) = PropName::from_str(dir) {
+                state = state.set(new_side, value);
+            }
+        }
+    }
+
+    // Rail shapes contain directions, and the shapes of stairs, the halves of
+    // chests, and the hinges of doors are swapped by mirroring.
+    for prop in [PropName::Shape, PropName::Type, PropName::Hinge] {
+        if let Some(value) = state.get(prop) {
+            if let Some(value) = transform_value(value, mirror, rotation) {
+                state = state.set(prop, value);
+            }
+        }
+    }
+
+    state
+}
+
+fn transform_dir(dir: &str, mirror: Mirror, rotation: Rotation) -> Option<&'static str> {
+    const CLOCKWISE: [&str; 4] = ["north", "east", "south", "west"];
+
+    let mut idx = CLOCKWISE.iter().position(|d| *d == dir)?;
+
+    idx = match (mirror, idx) {
+        (Mirror::LeftRight, 0 | 2) | (Mirror::FrontBack, 1 | 3) => (idx + 2) % 4,
+        _ => idx,
+    };
+
+    Some(CLOCKWISE[(idx + rotation.quarter_turns() as usize) % 4])
+}
+
+fn transform_value(value: PropValue, mirror: Mirror, rotation: Rotation) -> Option<PropValue> {
+    let mut words: Vec<_> = value
+        .to_str()
+        .split('_')
+        .map(|word| match word {
+            "left" if mirror != Mirror::None => "right",
+            "right" if mirror != Mirror::None => "left",
+            _ => transform_dir(word, mirror, rotation).unwrap_or(word),
+        })
+        .collect();
+
+    // Curved rails are named with north or south first.
+    PropValue::from_str(&words.join("_")).or_else(|| {
+        words.reverse();
+        PropValue::from_str(&words.join("_"))
+    })
+}
+
+fn set_str(state: BlockState, name: PropName, value: &str) -> BlockState {
+    match PropValue::from_str(value) {
+        Some(value) => state.set(name, value),
+        None => state,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let pos = BlockPos::new(1, 2, 3);
+
+        assert_eq!(
+            transform_pos(pos, Mirror::None, Rotation::Clockwise90),
+            BlockPos::new(-3, 2, 1)
+        );
+        assert_eq!(
+            transform_pos(pos, Mirror::None, Rotation::CounterClockwise90),
+            BlockPos::new(3, 2, -1)
+        );
+        assert_eq!(
+            transform_pos(pos, Mirror::LeftRight, Rotation::Clockwise180),
+            BlockPos::new(-1, 2, 3)
+        );
+    }
+
+    #[test]
+    fn block_states() {
+        let stairs = BlockState::OAK_STAIRS
+            .set(PropName::Facing, PropValue::North)
+            .set(PropName::Shape, PropValue::InnerLeft);
+
+        let rotated = transform_state(stairs, Mirror::None, Rotation::Clockwise90);
+        assert_eq!(rotated.get(PropName::Facing), Some(PropValue::East));
+        assert_eq!(rotated.get(PropName::Shape), Some(PropValue::InnerLeft));
+
+        let mirrored = transform_state(stairs, Mirror::LeftRight, Rotation::None);
+        assert_eq!(mirrored.get(PropName::Facing), Some(PropValue::South));
+        assert_eq!(mirrored.get(PropName::Shape), Some(PropValue::InnerRight));
+
+        let rail = BlockState::RAIL.set(PropName::Shape, PropValue::SouthEast);
+        let rotated = transform_state(rail, Mirror::None, Rotation::Clockwise90);
+        assert_eq!(rotated.get(PropName::Shape), Some(PropValue::SouthWest));
+
+        let log = BlockState::OAK_LOG.set(PropName::Axis, PropValue::X);
+        let rotated = transform_state(log, Mirror::None, Rotation::CounterClockwise90);
+        assert_eq!(rotated.get(PropName::Axis), Some(PropValue::Z));
+
+        let fence = BlockState::OAK_FENCE
+            .set(PropName::North, PropValue::True)
+            .set(PropName::East, PropValue::False);
+        let rotated = transform_state(fence, Mirror::None, Rotation::Clockwise90);
+        assert_eq!(rotated.get(PropName::East), Some(PropValue::True));
+        assert_eq!(rotated.get(PropName::South), Some(PropValue::False));
+
+        let sign = BlockState::OAK_SIGN.set(PropName::Rotation, PropValue::_1);
+        let mirrored = transform_state(sign, Mirror::FrontBack, Rotation::Clockwise90);
+        assert_eq!(mirrored.get(PropName::Rotation), Some(PropValue::_11));
+    }
+}
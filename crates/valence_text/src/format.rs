@@ -0,0 +1,361 @@
+//! Helpers for formatting numbers, durations, and progress bars as [`Text`].
+//!
+//! Functions taking a `locale` expect the locale sent by the client in its
+//! settings, such as `en_us` or `de_de`.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::{Color, IntoText, Text};
+
+/// The separators used to write numbers in a locale.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct NumberFormat {
+    /// Separates groups of three digits in the integer part.
+    pub group: char,
+    /// Separates the integer part from the fraction.
+    pub decimal: char,
+}
+
+impl NumberFormat {
+    /// `1,234,567.89`
+    pub const ENGLISH: Self = Self {
+        group: ',',
+        decimal: '.',
+    };
+
+    /// `1.234.567,89`
+    pub const CONTINENTAL: Self = Self {
+        group: '.',
+        decimal: ',',
+    };
+
+    /// `1 234 567,89`, using a no-break space.
+    pub const SPACED: Self = Self {
+        group: '\u{a0}',
+        decimal: ',',
+    };
+
+    /// `1'234'567.89`
+    pub const SWISS: Self = Self {
+        group: '\'',
+        decimal: '.',
+    };
+
+    /// Returns the number format of the Minecraft locale `locale`. Unknown
+    /// locales use [`NumberFormat::ENGLISH`].
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.to_ascii_lowercase();
+
+        if locale == "de_ch" {
+            return Self::SWISS;
+        }
+
+        let language = locale.split('_').next().unwrap_or_default();
+
+        match language {
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "ro" | "hr" | "sl" | "sr"
+            | "el" | "vi" => Self::CONTINENTAL,
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg"
+            | "lt" | "lv" | "et" => Self::SPACED,
+            _ => Self::ENGLISH,
+        }
+    }
+
+    /// Writes `n` with group separators.
+    pub fn integer(self, n: i64) -> String {
+        let digits = n.unsigned_abs().to_string();
+        let mut res = String::with_capacity(digits.len() * 4 / 3 + 1);
+
+        if n < 0 {
+            res.push('-');
+        }
+
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                res.push(self.group);
+            }
+            res.push(digit);
+        }
+
+        res
+    }
+
+    /// Writes `n` with group separators and exactly `precision` digits after
+    /// the decimal separator.
+    pub fn decimal(self, n: f64, precision: usize) -> String {
+        if !n.is_finite() {
+            return n.to_string();
+        }
+
+        let fixed = format!("{:.*}", precision, n.abs());
+        let (int, frac) = fixed.split_once('.').unwrap_or((&fixed, ""));
+
+        let mut res = String::new();
+
+        // Rounding may turn a small negative number into zero.
+        if n.is_sign_negative() && fixed.bytes().any(|b| matches!(b, b'1'..=b'9')) {
+            res.push('-');
+        }
+
+        // The integer part is at most 309 digits, so it may not fit in an i64.
+        for (i, digit) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i).is_multiple_of(3) {
+                res.push(self.group);
+            }
+            res.push(digit);
+        }
+
+        if !frac.is_empty() {
+            res.push(self.decimal);
+            res.push_str(frac);
+        }
+
+        res
+    }
+}
+
+/// Formats `n` with the group separators of `locale`, such as `1,234,567` in
+/// `en_us` or `1.234.567` in `de_de`.
+pub fn number(n: i64, locale: &str) -> Text {
+    NumberFormat::for_locale(locale).integer(n).into_text()
+}
+
+/// Formats `n` with the separators of `locale` and `precision` digits after
+/// the decimal separator.
+pub fn decimal(n: f64, precision: usize, locale: &str) -> Text {
+    NumberFormat::for_locale(locale)
+        .decimal(n, precision)
+        .into_text()
+}
+
+/// Formats `n` with a metric suffix, such as `1.2k` or `3.4M`, using the
+/// decimal separator of `locale`. Numbers below a thousand are written as is.
+pub fn compact(n: i64, locale: &str) -> Text {
+    const SUFFIXES: [(i64, &str); 4] = [
+        (1_000_000_000_000, "T"),
+        (1_000_000_000, "B"),
+        (1_000_000, "M"),
+        (1_000, "k"),
+    ];
+
+    let format = NumberFormat::for_locale(locale);
+
+    for (scale, suffix) in SUFFIXES {
+        if n.unsigned_abs() >= scale as u64 {
+            let scaled = n as f64 / scale as f64;
+            let precision = usize::from(scaled.abs() < 100.0);
+
+            let mut s = format.decimal(scaled, precision);
+            if let Some(trimmed) = s.strip_suffix(&format!("{}0", format.decimal)) {
+                s.truncate(trimmed.len());
+            }
+            s.push_str(suffix);
+
+            return s.into_text();
+        }
+    }
+
+    n.to_string().into_text()
+}
+
+/// Formats `duration` with its two largest nonzero units, such as `1d 4h`,
+/// `3m 12s`, or `45s`. Durations below a second are written as `0s`.
+pub fn duration(duration: Duration) -> Text {
+    const UNITS: [(u64, &str); 4] = [(86_400, "d"), (3_600, "h"), (60, "m"), (1, "s")];
+
+    let mut secs = duration.as_secs();
+    let mut res = String::new();
+    let mut written = 0;
+
+    for (unit_secs, suffix) in UNITS {
+        let count = secs / unit_secs;
+        secs %= unit_secs;
+
+        if count > 0 {
+            if written > 0 {
+                res.push(' ');
+            }
+            let _ = write!(res, "{count}{suffix}");
+            written += 1;
+        } else if written > 0 {
+            // Don't skip over units, `1h 5s` is easily misread.
+            break;
+        }
+
+        if written == 2 {
+            break;
+        }
+    }
+
+    if res.is_empty() {
+        res.push_str("0s");
+    }
+
+    res.into_text()
+}
+
+/// Formats `duration` like a clock, such as `3:07` or `1:02:03`.
+pub fn clock(duration: Duration) -> Text {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}").into_text()
+    } else {
+        format!("{minutes}:{seconds:02}").into_text()
+    }
+}
+
+/// A horizontal bar made of unicode block characters.
+///
+/// # Examples
+///
+/// ```
+/// use valence_text::format::ProgressBar;
+/// use valence_text::Color;
+///
+/// let bar = ProgressBar::new(10).filled_color(Color::GREEN).text(0.5);
+///
+/// assert_eq!(bar.to_legacy_lossy(), "§a█████§8█████");
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ProgressBar {
+    width: usize,
+    filled: Color,
+    empty: Color,
+    partial: bool,
+}
+
+impl ProgressBar {
+    /// The characters for an eighth of a block up to seven eighths.
+    const PARTIAL: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+    /// Creates a bar `width` characters wide.
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            filled: Color::WHITE,
+            empty: Color::DARK_GRAY,
+            partial: false,
+        }
+    }
+
+    pub fn filled_color(mut self, color: impl Into<Color>) -> Self {
+        self.filled = color.into();
+        self
+    }
+
+    pub fn empty_color(mut self, color: impl Into<Color>) -> Self {
+        self.empty = color.into();
+        self
+    }
+
+    /// Whether to show partially filled characters at the end of the filled
+    /// part for a smoother bar. Partial characters are narrower than full
+    /// blocks in the default font, so the bar changes width while filling.
+    pub fn partial(mut self, partial: bool) -> Self {
+        self.partial = partial;
+        self
+    }
+
+    /// Returns the bar filled to `progress`, which is clamped to `0.0..=1.0`.
+    pub fn text(&self, progress: f32) -> Text {
+        let progress = if progress.is_nan() {
+            0.0
+        } else {
+            progress.clamp(0.0, 1.0)
+        };
+
+        let eighths = (progress * self.width as f32 * 8.0).round() as usize;
+        let full = eighths / 8;
+        let remainder = eighths % 8;
+
+        let mut filled = "█".repeat(full);
+        let mut empty_width = self.width - full;
+
+        if remainder > 0 {
+            if self.partial {
+                filled.push(Self::PARTIAL[remainder - 1]);
+                empty_width -= 1;
+            } else if remainder >= 4 {
+                filled.push('█');
+                empty_width -= 1;
+            }
+        }
+
+        let mut text = Text::default();
+
+        if !filled.is_empty() {
+            text += filled.color(self.filled);
+        }
+
+        if empty_width > 0 {
+            text += "█".repeat(empty_width).color(self.empty);
+        }
+
+        text
+    }
+}
+
+/// Returns a bar `width` characters wide filled to `progress`. See
+/// [`ProgressBar`] for more options.
+pub fn progress_bar(progress: f32, width: usize) -> Text {
+    ProgressBar::new(width).text(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number(1234567, "en_us").to_legacy_lossy(), "1,234,567");
+        assert_eq!(number(-1234, "de_de").to_legacy_lossy(), "-1.234");
+        assert_eq!(number(999, "fr_fr").to_legacy_lossy(), "999");
+        assert_eq!(number(1000, "fr_fr").to_legacy_lossy(), "1\u{a0}000");
+        assert_eq!(number(i64::MIN, "xx_xx").to_legacy_lossy().len(), 26);
+
+        assert_eq!(decimal(1234.5, 2, "en_us").to_legacy_lossy(), "1,234.50");
+        assert_eq!(decimal(-0.001, 1, "de_de").to_legacy_lossy(), "0,0");
+        assert_eq!(decimal(12.0, 0, "de_ch").to_legacy_lossy(), "12");
+
+        assert_eq!(compact(999, "en_us").to_legacy_lossy(), "999");
+        assert_eq!(compact(1200, "en_us").to_legacy_lossy(), "1.2k");
+        assert_eq!(compact(2_000_000, "de_de").to_legacy_lossy(), "2M");
+        assert_eq!(compact(-345_600, "en_us").to_legacy_lossy(), "-346k");
+    }
+
+    #[test]
+    fn durations() {
+        let text = |secs| duration(Duration::from_secs(secs)).to_legacy_lossy();
+
+        assert_eq!(text(0), "0s");
+        assert_eq!(text(45), "45s");
+        assert_eq!(text(192), "3m 12s");
+        assert_eq!(text(3605), "1h");
+        assert_eq!(text(100_000), "1d 3h");
+
+        assert_eq!(clock(Duration::from_secs(187)).to_legacy_lossy(), "3:07");
+        assert_eq!(
+            clock(Duration::from_secs(3723)).to_legacy_lossy(),
+            "1:02:03"
+        );
+    }
+
+    #[test]
+    fn progress_bars() {
+        let bar = ProgressBar::new(4)
+            .filled_color(Color::GREEN)
+            .empty_color(Color::RED);
+
+        assert_eq!(bar.text(0.0).to_legacy_lossy(), "§c████");
+        assert_eq!(bar.text(1.0).to_legacy_lossy(), "§a████");
+        assert_eq!(bar.text(2.0).to_legacy_lossy(), "§a████");
+        assert_eq!(bar.text(0.5).to_legacy_lossy(), "§a██§c██");
+        assert_eq!(bar.text(0.6).to_legacy_lossy(), "§a██§c██");
+
+        let bar = bar.partial(true);
+        assert_eq!(bar.text(0.6).to_legacy_lossy(), "§a██▍§c█");
+    }
+}
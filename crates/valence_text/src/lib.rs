@@ -12,6 +12,7 @@ use valence_ident::Ident;
 use valence_nbt::Value;
 
 pub mod color;
+pub mod format;
 mod into_text;
 #[cfg(test)]
 mod tests;
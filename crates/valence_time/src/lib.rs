@@ -0,0 +1,302 @@
+#![doc = include_str!("../README.md")]
+#![allow(clippy::type_complexity)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+use std::f64::consts::PI;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::{Client, FlushPacketsSet, UpdateClientsSet, VisibleChunkLayer};
+use valence_server::protocol::packets::play::WorldTimeUpdateS2c;
+use valence_server::protocol::WritePacket;
+use valence_server::ChunkLayer;
+
+pub struct TimePlugin;
+
+impl Plugin for TimePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TimeOfDayEvent>()
+            .add_systems(Update, tick_world_time)
+            .add_systems(
+                PostUpdate,
+                (
+                    init_time_on_layer_join.before(FlushPacketsSet),
+                    sync_layer_time.before(UpdateClientsSet),
+                ),
+            );
+    }
+}
+
+/// The number of ticks in a Minecraft day.
+pub const DAY_TICKS: i64 = 24000;
+
+/// The number of ticks between time updates sent to clients, matching the
+/// vanilla server. The client advances the time by itself in between.
+const SYNC_INTERVAL: i64 = 20;
+
+/// Component for chunk layers which holds the age of the world and the time of
+/// day.
+///
+/// A `time_of_day` of 0 is sunrise, 6000 noon, 12000 sunset and 18000
+/// midnight. The time of day keeps increasing past [`DAY_TICKS`] to count the
+/// days, which determines the moon phase.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct WorldTime {
+    world_age: i64,
+    time_of_day: i64,
+    daylight_cycle: bool,
+    needs_sync: bool,
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl WorldTime {
+    /// Creates a world time at `time_of_day` with the daylight cycle enabled.
+    pub fn new(time_of_day: i64) -> Self {
+        Self {
+            world_age: 0,
+            time_of_day,
+            daylight_cycle: true,
+            needs_sync: true,
+        }
+    }
+
+    /// The total number of ticks the world has existed for.
+    pub fn world_age(&self) -> i64 {
+        self.world_age
+    }
+
+    pub fn time_of_day(&self) -> i64 {
+        self.time_of_day
+    }
+
+    pub fn set_time_of_day(&mut self, time_of_day: i64) {
+        self.time_of_day = time_of_day;
+        self.needs_sync = true;
+    }
+
+    /// Adds `ticks` to the time of day, e.g. to skip the night.
+    pub fn add_time(&mut self, ticks: i64) {
+        self.set_time_of_day(self.time_of_day + ticks);
+    }
+
+    /// Whether the time of day advances every tick.
+    pub fn daylight_cycle(&self) -> bool {
+        self.daylight_cycle
+    }
+
+    pub fn set_daylight_cycle(&mut self, daylight_cycle: bool) {
+        self.daylight_cycle = daylight_cycle;
+        self.needs_sync = true;
+    }
+
+    /// The time within the current day, in \[0, [`DAY_TICKS`]).
+    pub fn day_time(&self) -> i64 {
+        self.time_of_day.rem_euclid(DAY_TICKS)
+    }
+
+    /// The number of days that have passed.
+    pub fn day(&self) -> i64 {
+        self.time_of_day.div_euclid(DAY_TICKS)
+    }
+
+    /// Whether it is day, which is the case between sunrise and sunset.
+    pub fn is_day(&self) -> bool {
+        self.day_time() < 12000
+    }
+
+    pub fn is_night(&self) -> bool {
+        !self.is_day()
+    }
+
+    pub fn moon_phase(&self) -> MoonPhase {
+        MoonPhase::from_day(self.day())
+    }
+
+    /// The angle of the sun in \[0, 1), where 0 is noon, 0.25 sunset, 0.5
+    /// midnight and 0.75 sunrise. Computed the same way as the vanilla client
+    /// does, so the sun moves faster around sunrise and sunset.
+    pub fn celestial_angle(&self) -> f64 {
+        let d = (self.day_time() as f64 / DAY_TICKS as f64 - 0.25).rem_euclid(1.0);
+        let e = 0.5 - (d * PI).cos() / 2.0;
+
+        (d * 2.0 + e) / 3.0
+    }
+
+    fn to_packet(&self) -> WorldTimeUpdateS2c {
+        // A negative time of day stops the client from advancing the time.
+        let time_of_day = if self.daylight_cycle {
+            self.time_of_day
+        } else if self.time_of_day == 0 {
+            -1
+        } else {
+            -self.time_of_day.abs()
+        };
+
+        WorldTimeUpdateS2c {
+            world_age: self.world_age,
+            time_of_day,
+        }
+    }
+}
+
+/// The phases of the moon, in the order they occur.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum MoonPhase {
+    FullMoon,
+    WaningGibbous,
+    ThirdQuarter,
+    WaningCrescent,
+    NewMoon,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+}
+
+impl MoonPhase {
+    const ALL: [Self; 8] = [
+        Self::FullMoon,
+        Self::WaningGibbous,
+        Self::ThirdQuarter,
+        Self::WaningCrescent,
+        Self::NewMoon,
+        Self::WaxingCrescent,
+        Self::FirstQuarter,
+        Self::WaxingGibbous,
+    ];
+
+    /// Returns the moon phase in the night of `day`.
+    pub fn from_day(day: i64) -> Self {
+        Self::ALL[day.rem_euclid(8) as usize]
+    }
+
+    /// How much of the moon is lit, from 0 for a new moon to 1 for a full
+    /// moon. Vanilla uses this to scale the chance of slimes spawning.
+    pub fn size(self) -> f32 {
+        match self {
+            Self::FullMoon => 1.0,
+            Self::WaningGibbous | Self::WaxingGibbous => 0.75,
+            Self::ThirdQuarter | Self::FirstQuarter => 0.5,
+            Self::WaningCrescent | Self::WaxingCrescent => 0.25,
+            Self::NewMoon => 0.0,
+        }
+    }
+}
+
+/// The points of the day reported by [`TimeOfDayEvent`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TimeOfDay {
+    Sunrise,
+    Noon,
+    Sunset,
+    Midnight,
+}
+
+impl TimeOfDay {
+    /// The [`WorldTime::day_time`] at which this point of the day is reached.
+    pub fn day_time(self) -> i64 {
+        match self {
+            Self::Sunrise => 0,
+            Self::Noon => 6000,
+            Self::Sunset => 12000,
+            Self::Midnight => 18000,
+        }
+    }
+}
+
+/// Sent when the time of day of a layer passes sunrise, noon, sunset, or
+/// midnight while advancing.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TimeOfDayEvent {
+    /// The chunk layer entity.
+    pub layer: Entity,
+    pub time: TimeOfDay,
+}
+
+fn tick_world_time(
+    mut layers: Query<(Entity, &mut WorldTime)>,
+    mut events: EventWriter<TimeOfDayEvent>,
+) {
+    for (layer, mut time) in &mut layers {
+        // Avoid triggering change detection on `needs_sync` alone.
+        let time = time.bypass_change_detection();
+
+        time.world_age += 1;
+
+        if !time.daylight_cycle {
+            continue;
+        }
+
+        time.time_of_day += 1;
+
+        for point in [
+            TimeOfDay::Sunrise,
+            TimeOfDay::Noon,
+            TimeOfDay::Sunset,
+            TimeOfDay::Midnight,
+        ] {
+            if time.day_time() == point.day_time() {
+                events.send(TimeOfDayEvent { layer, time: point });
+            }
+        }
+    }
+}
+
+fn sync_layer_time(mut layers: Query<(&mut ChunkLayer, &mut WorldTime), Without<Client>>) {
+    for (mut layer, mut time) in &mut layers {
+        if time.needs_sync || time.world_age % SYNC_INTERVAL == 0 {
+            layer.write_packet(&time.to_packet());
+            time.bypass_change_detection().needs_sync = false;
+        }
+    }
+}
+
+fn init_time_on_layer_join(
+    mut clients: Query<(&mut Client, &VisibleChunkLayer), Changed<VisibleChunkLayer>>,
+    layers: Query<&WorldTime, With<ChunkLayer>>,
+) {
+    for (mut client, visible_chunk_layer) in &mut clients {
+        if let Ok(time) = layers.get(visible_chunk_layer.0) {
+            client.write_packet(&time.to_packet());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_time() {
+        let mut time = WorldTime::new(6000);
+        assert!(time.is_day());
+        assert_eq!(time.celestial_angle(), 0.0);
+        assert_eq!(time.moon_phase(), MoonPhase::FullMoon);
+
+        time.set_time_of_day(DAY_TICKS * 4 + 18000);
+        assert!(time.is_night());
+        assert_eq!(time.day(), 4);
+        assert_eq!(time.moon_phase(), MoonPhase::NewMoon);
+        assert!((time.celestial_angle() - 0.5).abs() < 1e-9);
+    }
+}
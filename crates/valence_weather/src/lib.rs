@@ -18,6 +18,8 @@
     clippy::dbg_macro
 )]
 
+mod zone;
+
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use derive_more::{Deref, DerefMut};
@@ -26,24 +28,44 @@ use valence_server::protocol::packets::play::game_state_change_s2c::GameEventKin
 use valence_server::protocol::packets::play::GameStateChangeS2c;
 use valence_server::protocol::WritePacket;
 use valence_server::ChunkLayer;
+pub use zone::{
+    CurrentWeatherZone, WeatherZone, WeatherZoneEnterEvent, WeatherZoneLeaveEvent, WeatherZones,
+    ZoneAmbience,
+};
 
 pub struct WeatherPlugin;
 
 impl Plugin for WeatherPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            PostUpdate,
-            (
-                init_weather_on_layer_join,
-                change_client_rain_level,
-                change_client_thunder_level,
+        app.add_event::<WeatherZoneEnterEvent>()
+            .add_event::<WeatherZoneLeaveEvent>()
+            .add_systems(
+                PostUpdate,
+                (
+                    init_weather_on_layer_join,
+                    change_client_rain_level,
+                    change_client_thunder_level,
+                )
+                    .before(FlushPacketsSet),
+            )
+            .add_systems(
+                PostUpdate,
+                (change_layer_rain_level, change_layer_thunder_level).before(UpdateClientsSet),
             )
-                .before(FlushPacketsSet),
-        )
-        .add_systems(
-            PostUpdate,
-            (change_layer_rain_level, change_layer_thunder_level).before(UpdateClientsSet),
-        );
+            .add_systems(
+                PostUpdate,
+                (
+                    zone::init_current_weather_zones,
+                    // Zones override the weather sent to clients by the systems above.
+                    zone::update_weather_zones
+                        .after(UpdateClientsSet)
+                        .after(init_weather_on_layer_join)
+                        .after(change_client_rain_level)
+                        .after(change_client_thunder_level),
+                )
+                    .chain()
+                    .before(FlushPacketsSet),
+            );
     }
 }
 
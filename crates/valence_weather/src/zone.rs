@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use bevy_ecs::prelude::*;
+use valence_server::client::{Client, VisibleChunkLayer};
+use valence_server::entity::Position;
+use valence_server::math::{Aabb, DVec3};
+use valence_server::protocol::packets::play::game_state_change_s2c::GameEventKind;
+use valence_server::protocol::packets::play::GameStateChangeS2c;
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::protocol::WritePacket;
+use valence_server::ChunkLayer;
+
+use crate::{Rain, Thunder};
+
+/// Component containing the weather zones of a layer. Insert this on an
+/// entity with a [`ChunkLayer`] to change the weather in parts of it.
+///
+/// Clients inside a zone see the weather of the zone instead of the weather
+/// of the layer. Where zones overlap, the zone with the highest priority is
+/// used.
+#[derive(Component, Clone, Default, Debug)]
+pub struct WeatherZones {
+    zones: BTreeMap<String, WeatherZone>,
+}
+
+impl WeatherZones {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a zone with the given name, returning the zone that was
+    /// previously stored under that name.
+    pub fn insert(&mut self, name: impl Into<String>, zone: WeatherZone) -> Option<WeatherZone> {
+        self.zones.insert(name.into(), zone)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<WeatherZone> {
+        self.zones.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WeatherZone> {
+        self.zones.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut WeatherZone> {
+        self.zones.get_mut(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &WeatherZone)> + '_ {
+        self.zones.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.zones.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// Returns the zone with the highest priority containing the given
+    /// position. Ties are broken by name.
+    pub fn at(&self, pos: impl Into<DVec3>) -> Option<(&str, &WeatherZone)> {
+        let pos = pos.into();
+
+        self.iter()
+            .filter(|(_, z)| z.bounds.contains_point(pos))
+            .min_by(|a, b| b.1.priority.cmp(&a.1.priority).then(a.0.cmp(b.0)))
+    }
+}
+
+/// A volume of a layer with its own weather and ambience.
+#[derive(Clone, PartialEq, Debug)]
+pub struct WeatherZone {
+    pub bounds: Aabb,
+    /// Zones with a higher priority take precedence over overlapping zones
+    /// with a lower priority.
+    pub priority: i32,
+    /// The rain level inside of the zone, or `None` to keep the rain level of
+    /// the layer.
+    pub rain: Option<f32>,
+    /// The thunder level inside of the zone, or `None` to keep the thunder
+    /// level of the layer.
+    pub thunder: Option<f32>,
+    /// A sound repeatedly played to clients inside of the zone.
+    pub ambience: Option<ZoneAmbience>,
+}
+
+impl WeatherZone {
+    pub fn new(bounds: Aabb) -> Self {
+        Self {
+            bounds,
+            priority: 0,
+            rain: None,
+            thunder: None,
+            ambience: None,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_rain(mut self, rain: f32) -> Self {
+        self.rain = Some(rain);
+        self
+    }
+
+    pub fn with_thunder(mut self, thunder: f32) -> Self {
+        self.thunder = Some(thunder);
+        self
+    }
+
+    pub fn with_ambience(mut self, ambience: ZoneAmbience) -> Self {
+        self.ambience = Some(ambience);
+        self
+    }
+}
+
+/// An ambient sound of a [`WeatherZone`]. The sound is played at the
+/// position of each client inside of the zone, and only to that client.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ZoneAmbience {
+    pub sound: Sound,
+    pub category: SoundCategory,
+    pub volume: f32,
+    pub pitch: f32,
+    /// The number of ticks between two sounds. The first sound is played
+    /// when the client enters the zone.
+    pub interval: u32,
+}
+
+impl ZoneAmbience {
+    pub fn new(sound: Sound, interval: u32) -> Self {
+        Self {
+            sound,
+            category: SoundCategory::Ambient,
+            volume: 1.0,
+            pitch: 1.0,
+            interval,
+        }
+    }
+}
+
+/// The name of the weather zone a client is currently inside of. This is
+/// inserted on clients automatically and updated every tick.
+#[derive(Component, Clone, Default, Debug)]
+pub struct CurrentWeatherZone {
+    /// The layer and name of the zone.
+    zone: Option<(Entity, String)>,
+    /// Ticks until the next ambient sound is played.
+    ambience_cooldown: u32,
+}
+
+impl CurrentWeatherZone {
+    pub fn get(&self) -> Option<&str> {
+        self.zone.as_ref().map(|(_, name)| name.as_str())
+    }
+
+    /// Returns the layer containing the current zone.
+    pub fn layer(&self) -> Option<Entity> {
+        self.zone.as_ref().map(|(layer, _)| *layer)
+    }
+}
+
+/// Sent when a client enters a weather zone.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct WeatherZoneEnterEvent {
+    pub client: Entity,
+    /// The layer containing the zone.
+    pub layer: Entity,
+    pub zone: String,
+}
+
+/// Sent when a client leaves a weather zone or when the zone it was in is
+/// removed.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct WeatherZoneLeaveEvent {
+    pub client: Entity,
+    /// The layer containing the zone.
+    pub layer: Entity,
+    pub zone: String,
+}
+
+pub(crate) fn init_current_weather_zones(
+    mut commands: Commands,
+    clients: Query<Entity, (With<Client>, Without<CurrentWeatherZone>)>,
+) {
+    for entity in &clients {
+        commands
+            .entity(entity)
+            .insert(CurrentWeatherZone::default());
+    }
+}
+
+pub(crate) fn update_weather_zones(
+    mut clients: Query<(
+        Entity,
+        &mut Client,
+        &mut CurrentWeatherZone,
+        &Position,
+        Ref<VisibleChunkLayer>,
+        Option<Ref<Rain>>,
+        Option<Ref<Thunder>>,
+    )>,
+    layers: Query<(Ref<WeatherZones>, Option<Ref<Rain>>, Option<Ref<Thunder>>), With<ChunkLayer>>,
+    mut enter: EventWriter<WeatherZoneEnterEvent>,
+    mut leave: EventWriter<WeatherZoneLeaveEvent>,
+) {
+    for (entity, mut client, mut current, pos, layer, client_rain, client_thunder) in &mut clients {
+        let layer_data = layers.get(layer.0).ok();
+
+        let zone = layer_data
+            .as_ref()
+            .and_then(|(zones, _, _)| zones.at(pos.0));
+
+        let zone_changed = current.zone.as_ref().map(|(l, n)| (*l, n.as_str()))
+            != zone.map(|(name, _)| (layer.0, name));
+
+        if zone_changed {
+            if let Some((old_layer, old_name)) = current.zone.take() {
+                leave.send(WeatherZoneLeaveEvent {
+                    client: entity,
+                    layer: old_layer,
+                    zone: old_name,
+                });
+            }
+
+            if let Some((name, _)) = zone {
+                enter.send(WeatherZoneEnterEvent {
+                    client: entity,
+                    layer: layer.0,
+                    zone: name.to_owned(),
+                });
+
+                current.zone = Some((layer.0, name.to_owned()));
+            }
+
+            current.ambience_cooldown = 0;
+        }
+
+        let layer_rain = layer_data.as_ref().and_then(|(_, r, _)| r.as_ref());
+        let layer_thunder = layer_data.as_ref().and_then(|(_, _, t)| t.as_ref());
+
+        // The weather of the client or layer may have been sent to the client
+        // this tick, which would have replaced the weather of the zone.
+        let weather_resent = layer.is_changed()
+            || client_rain.as_ref().is_some_and(|r| r.is_changed())
+            || client_thunder.as_ref().is_some_and(|t| t.is_changed())
+            || layer_rain.is_some_and(|r| r.is_changed())
+            || layer_thunder.is_some_and(|t| t.is_changed())
+            || layer_data.as_ref().is_some_and(|(z, _, _)| z.is_changed());
+
+        let overrides = zone.map_or((None, None), |(_, z)| (z.rain, z.thunder));
+
+        if zone_changed || (weather_resent && zone.is_some()) {
+            let rain = overrides
+                .0
+                .or(client_rain.map(|r| r.0))
+                .or(layer_rain.map(|r| r.0))
+                .unwrap_or(0.0);
+
+            let thunder = overrides
+                .1
+                .or(client_thunder.map(|t| t.0))
+                .or(layer_thunder.map(|t| t.0))
+                .unwrap_or(0.0);
+
+            client.write_packet(&GameStateChangeS2c {
+                kind: GameEventKind::RainLevelChange,
+                value: rain,
+            });
+
+            client.write_packet(&GameStateChangeS2c {
+                kind: GameEventKind::ThunderLevelChange,
+                value: thunder,
+            });
+        }
+
+        let Some(ambience) = zone.and_then(|(_, z)| z.ambience) else {
+            continue;
+        };
+
+        // Bypass change detection, since the cooldown changes every tick.
+        let current = current.bypass_change_detection();
+
+        if current.ambience_cooldown == 0 {
+            current.ambience_cooldown = ambience.interval.max(1) - 1;
+
+            client.play_sound(
+                ambience.sound,
+                ambience.category,
+                pos.0,
+                ambience.volume,
+                ambience.pitch,
+            );
+        } else {
+            current.ambience_cooldown -= 1;
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use thiserror::Error;
+use valence_server::block::BlockKind;
+use valence_server::ident::ident;
+use valence_server::layer::chunk::{Chunk, UnloadedChunk};
+use valence_server::registry::biome::BiomeId;
+use valence_server::registry::BiomeRegistry;
+use valence_server::{BlockState, ChunkPos, Ident};
+
+/// Generates a superflat world made of horizontal layers of blocks, or an
+/// empty world if there are no layers.
+///
+/// Every chunk generated by a flat generator is the same, so generating a
+/// chunk is just a copy.
+#[derive(Clone, Debug)]
+pub struct FlatGenerator {
+    layers: Vec<FlatLayer>,
+    biome: BiomeId,
+    /// The chunk returned by [`FlatGenerator::generate`].
+    chunk: UnloadedChunk,
+}
+
+/// A layer of a [`FlatGenerator`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FlatLayer {
+    pub block: BlockState,
+    /// The number of blocks in the layer.
+    pub thickness: u32,
+}
+
+impl FlatLayer {
+    pub fn new(block: BlockState, thickness: u32) -> Self {
+        Self { block, thickness }
+    }
+}
+
+impl FlatGenerator {
+    /// Creates a generator for the vanilla overworld height, from Y -64 to
+    /// 319. The layers are listed from the bottom of the world upwards, and
+    /// layers which don't fit in the world are cut off.
+    pub fn new(layers: impl IntoIterator<Item = FlatLayer>, biome: BiomeId) -> Self {
+        let mut gen = Self {
+            layers: layers.into_iter().collect(),
+            biome,
+            chunk: UnloadedChunk::new(),
+        };
+
+        gen.build_chunk(384);
+        gen
+    }
+
+    /// Creates a generator for a world without any blocks.
+    pub fn void(biome: BiomeId) -> Self {
+        Self::new([], biome)
+    }
+
+    /// Parses the preset string of a vanilla superflat world, for example
+    /// `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:
+    /// plains`.
+    ///
+    /// The string is a comma separated list of layers from the bottom up,
+    /// optionally followed by a semicolon and the biome of the world. Each
+    /// layer is a block name, optionally prefixed by the thickness of the
+    /// layer and `*`. The biome defaults to `minecraft:plains`. Anything after
+    /// the biome, like the structures of the world, is ignored.
+    pub fn parse(preset: &str, biomes: &BiomeRegistry) -> Result<Self, ParseFlatError> {
+        let mut parts = preset.trim().split(';');
+
+        let layers = parts
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|layer| !layer.is_empty())
+            .map(parse_layer)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let biome = match parts.next().map(str::trim).filter(|b| !b.is_empty()) {
+            Some(name) => Ident::new(name)
+                .ok()
+                .and_then(|ident| biomes.index_of(ident.as_str_ident()))
+                .ok_or_else(|| ParseFlatError::UnknownBiome(name.to_owned()))?,
+            None => biomes.index_of(ident!("plains")).unwrap_or_default(),
+        };
+
+        Ok(Self::new(layers, biome))
+    }
+
+    /// Sets the vertical range of the generated chunks. This must match the
+    /// dimension type of the layer the chunks are inserted into.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `height` is not a multiple of 16.
+    pub fn with_height(mut self, height: u32) -> Self {
+        assert_eq!(height % 16, 0, "chunk height must be a multiple of 16");
+
+        self.build_chunk(height);
+        self
+    }
+
+    pub fn layers(&self) -> &[FlatLayer] {
+        &self.layers
+    }
+
+    pub fn biome(&self) -> BiomeId {
+        self.biome
+    }
+
+    /// Generates the chunk at `pos`.
+    pub fn generate(&self, _pos: ChunkPos) -> UnloadedChunk {
+        self.chunk.clone()
+    }
+
+    fn build_chunk(&mut self, height: u32) {
+        let mut chunk = UnloadedChunk::with_height(height);
+        chunk.fill_biomes(self.biome);
+
+        let mut y = 0;
+
+        for layer in &self.layers {
+            let top = (y + layer.thickness).min(height);
+
+            // Fill whole sections at once where possible.
+            while y < top {
+                if y % 16 == 0 && y + 16 <= top {
+                    chunk.fill_block_state_section(y / 16, layer.block);
+                    y += 16;
+                } else {
+                    for z in 0..16 {
+                        for x in 0..16 {
+                            chunk.set_block_state(x, y, z, layer.block);
+                        }
+                    }
+                    y += 1;
+                }
+            }
+        }
+
+        self.chunk = chunk;
+    }
+}
+
+fn parse_layer(layer: &str) -> Result<FlatLayer, ParseFlatError> {
+    let (thickness, name) = match layer.split_once('*') {
+        Some((thickness, name)) => {
+            let thickness = thickness
+                .trim()
+                .parse()
+                .map_err(|_| ParseFlatError::InvalidLayer(layer.to_owned()))?;
+
+            (thickness, name.trim())
+        }
+        None => (1, layer),
+    };
+
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+    let kind =
+        BlockKind::from_str(name).ok_or_else(|| ParseFlatError::UnknownBlock(name.to_owned()))?;
+
+    Ok(FlatLayer::new(kind.to_state(), thickness))
+}
+
+/// An error returned by [`FlatGenerator::parse`].
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum ParseFlatError {
+    #[error("invalid layer `{0}`")]
+    InvalidLayer(String),
+    #[error("unknown block `{0}`")]
+    UnknownBlock(String),
+    #[error("unknown biome `{0}`")]
+    UnknownBiome(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vanilla_preset() {
+        let mut biomes = BiomeRegistry::default();
+        biomes.insert(ident!("plains"), Default::default());
+        biomes.insert(ident!("desert"), Default::default());
+
+        let gen = FlatGenerator::parse(
+            "minecraft:bedrock,2*minecraft:dirt,grass_block;minecraft:desert;village",
+            &biomes,
+        )
+        .unwrap();
+
+        assert_eq!(
+            gen.layers(),
+            [
+                FlatLayer::new(BlockState::BEDROCK, 1),
+                FlatLayer::new(BlockState::DIRT, 2),
+                FlatLayer::new(BlockState::GRASS_BLOCK, 1),
+            ]
+        );
+        assert_eq!(gen.biome(), biomes.index_of(ident!("desert")).unwrap());
+        assert_ne!(gen.biome(), BiomeId::DEFAULT);
+
+        let chunk = gen.generate(ChunkPos::new(5, -3));
+
+        assert_eq!(chunk.block_state(3, 0, 7), BlockState::BEDROCK);
+        assert_eq!(chunk.block_state(3, 2, 7), BlockState::DIRT);
+        assert_eq!(chunk.block_state(3, 3, 7), BlockState::GRASS_BLOCK);
+        assert_eq!(chunk.block_state(3, 4, 7), BlockState::AIR);
+        assert_eq!(chunk.biome(0, 20, 3), gen.biome());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let biomes = BiomeRegistry::default();
+
+        assert_eq!(
+            FlatGenerator::parse("bedrock,x*dirt", &biomes).unwrap_err(),
+            ParseFlatError::InvalidLayer("x*dirt".into())
+        );
+        assert_eq!(
+            FlatGenerator::parse("bedrock,3*not_a_block", &biomes).unwrap_err(),
+            ParseFlatError::UnknownBlock("not_a_block".into())
+        );
+        assert_eq!(
+            FlatGenerator::parse("bedrock;minecraft:nowhere", &biomes).unwrap_err(),
+            ParseFlatError::UnknownBiome("minecraft:nowhere".into())
+        );
+    }
+
+    #[test]
+    fn layers_are_cut_off() {
+        let gen = FlatGenerator::new([FlatLayer::new(BlockState::STONE, 1000)], BiomeId::DEFAULT)
+            .with_height(32);
+
+        let chunk = gen.generate(ChunkPos::new(0, 0));
+
+        assert_eq!(chunk.height(), 32);
+        assert_eq!(chunk.block_state(0, 31, 0), BlockState::STONE);
+
+        let void = FlatGenerator::void(BiomeId::DEFAULT).generate(ChunkPos::new(0, 0));
+        assert_eq!(void.block_state(0, 0, 0), BlockState::AIR);
+    }
+}
@@ -0,0 +1,432 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links,
+    rustdoc::missing_crate_level_docs,
+    rustdoc::invalid_codeblock_attributes,
+    rustdoc::invalid_rust_codeblocks,
+    rustdoc::bare_urls,
+    rustdoc::invalid_html_tags
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_lifetimes,
+    unused_import_braces,
+    unreachable_pub,
+    clippy::dbg_macro
+)]
+
+mod flat;
+
+pub use flat::{FlatGenerator, FlatLayer, ParseFlatError};
+use noise::{NoiseFn, SuperSimplex};
+use valence_server::budget::BudgetedTask;
+use valence_server::ecs::entity::Entity;
+use valence_server::ident::ident;
+use valence_server::layer::chunk::{Chunk, UnloadedChunk};
+use valence_server::registry::biome::BiomeId;
+use valence_server::registry::BiomeRegistry;
+use valence_server::{BlockState, ChunkPos, Ident};
+
+/// The biomes placed by the [`WorldGenerator`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Beach,
+    Ocean,
+}
+
+impl Biome {
+    pub const ALL: [Self; 4] = [Self::Plains, Self::Desert, Self::Beach, Self::Ocean];
+
+    /// Returns the name of the biome in the biome registry.
+    pub fn name(self) -> Ident<&'static str> {
+        match self {
+            Biome::Plains => ident!("plains"),
+            Biome::Desert => ident!("desert"),
+            Biome::Beach => ident!("beach"),
+            Biome::Ocean => ident!("ocean"),
+        }
+    }
+}
+
+/// Generates chunks of noise-based terrain from a seed.
+///
+/// The generator doesn't keep any state between chunks, so chunks can be
+/// generated in any order and on any number of threads.
+#[derive(Clone, Debug)]
+pub struct WorldGenerator {
+    seed: u64,
+    min_y: i32,
+    height: u32,
+    sea_level: i32,
+    /// The biome of every column, if the world has a single biome.
+    biome: Option<Biome>,
+    /// The IDs of [`Biome::ALL`] in the biome registry.
+    biome_ids: [BiomeId; 4],
+    /// Decides where land and oceans are.
+    continents: SuperSimplex,
+    /// Decides where deserts are.
+    temperature: SuperSimplex,
+    hills: SuperSimplex,
+}
+
+impl WorldGenerator {
+    /// Creates a generator for the vanilla overworld height, from Y -64 to 319.
+    ///
+    /// Biomes which are missing from `biomes` are replaced with the default
+    /// biome.
+    pub fn new(seed: u64, biomes: &BiomeRegistry) -> Self {
+        Self {
+            seed,
+            min_y: -64,
+            height: 384,
+            sea_level: 63,
+            biome: None,
+            biome_ids: Biome::ALL.map(|b| biomes.index_of(b.name()).unwrap_or_default()),
+            continents: SuperSimplex::new(sub_seed(seed, 0)),
+            temperature: SuperSimplex::new(sub_seed(seed, 1)),
+            hills: SuperSimplex::new(sub_seed(seed, 2)),
+        }
+    }
+
+    /// Sets the vertical range of the generated chunks. This must match the
+    /// dimension type of the layer the chunks are inserted into.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `height` is not a multiple of 16.
+    pub fn with_height(mut self, min_y: i32, height: u32) -> Self {
+        assert_eq!(height % 16, 0, "chunk height must be a multiple of 16");
+
+        self.min_y = min_y;
+        self.height = height;
+        self
+    }
+
+    /// Sets the Y coordinate of the ocean surface.
+    ///
+    /// # Default Value
+    ///
+    /// 63
+    pub fn with_sea_level(mut self, sea_level: i32) -> Self {
+        self.sea_level = sea_level;
+        self
+    }
+
+    /// Makes every column of the world the given biome. The shape of the
+    /// terrain is unchanged, but its surface follows the biome.
+    pub fn with_biome(mut self, biome: Biome) -> Self {
+        self.biome = Some(biome);
+        self
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn sea_level(&self) -> i32 {
+        self.sea_level
+    }
+
+    /// Returns the Y coordinate of the highest solid block in the column at
+    /// `x` and `z`.
+    pub fn surface_height(&self, x: i32, z: i32) -> i32 {
+        self.column(x, z).height
+    }
+
+    /// Returns the biome of the column at `x` and `z`.
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.column(x, z).biome
+    }
+
+    /// Creates a [`BudgetedTask`] which generates the chunks within `radius`
+    /// chunks of `center` and inserts them into the layer `layer`, closest
+    /// chunks first. The chunks are generated across as many ticks as needed.
+    /// Chunks which are already loaded are left alone.
+    pub fn pregenerate_task(&self, layer: Entity, center: ChunkPos, radius: u32) -> BudgetedTask {
+        let generator = self.clone();
+        let radius = radius as i32;
+
+        let mut positions: Vec<_> = (center.z - radius..=center.z + radius)
+            .flat_map(|z| (center.x - radius..=center.x + radius).map(move |x| ChunkPos::new(x, z)))
+            .collect();
+
+        positions.sort_by_key(|pos| pos.distance_squared(center));
+
+        BudgetedTask::for_layer(layer, positions, move |layer, pos| {
+            if layer.chunk(pos).is_none() {
+                layer.insert_chunk(pos, generator.generate(pos));
+            }
+        })
+    }
+
+    /// Generates the chunk at `pos`.
+    pub fn generate(&self, pos: ChunkPos) -> UnloadedChunk {
+        let mut chunk = UnloadedChunk::with_height(self.height);
+        let top = self.min_y + self.height as i32 - 1;
+
+        for offset_z in 0..16 {
+            for offset_x in 0..16 {
+                let x = pos.x * 16 + offset_x as i32;
+                let z = pos.z * 16 + offset_z as i32;
+
+                let column = self.column(x, z);
+
+                for y in self.min_y..=column.height.max(self.sea_level).min(top) {
+                    let block = column.block_at(self, x, y, z);
+
+                    if !block.is_air() {
+                        let idx = (y - self.min_y) as u32;
+                        chunk.set_block_state(offset_x, idx, offset_z, block);
+                    }
+                }
+
+                // Scatter plants on the surface.
+                let above = column.height + 1;
+
+                if above <= top && above > self.sea_level {
+                    let plant = match column.biome {
+                        Biome::Plains if self.chance(x, z, 1, 0.12) => BlockState::GRASS,
+                        Biome::Desert if self.chance(x, z, 2, 0.01) => BlockState::DEAD_BUSH,
+                        _ => BlockState::AIR,
+                    };
+
+                    if !plant.is_air() {
+                        let idx = (above - self.min_y) as u32;
+                        chunk.set_block_state(offset_x, idx, offset_z, plant);
+                    }
+                }
+            }
+        }
+
+        // Biomes are stored in cells of 4x4x4 blocks.
+        for cell_z in 0..4 {
+            for cell_x in 0..4 {
+                let x = pos.x * 16 + cell_x as i32 * 4 + 2;
+                let z = pos.z * 16 + cell_z as i32 * 4 + 2;
+
+                let biome = self.biome_ids[self.biome_at(x, z) as usize];
+
+                for cell_y in 0..self.height / 4 {
+                    chunk.set_biome(cell_x, cell_y, cell_z, biome);
+                }
+            }
+        }
+
+        chunk
+    }
+
+    fn column(&self, x: i32, z: i32) -> Column {
+        let (x, z) = (f64::from(x), f64::from(z));
+        let sea_level = f64::from(self.sea_level);
+
+        // Positive values are land and negative values are oceans.
+        let continent = fbm(&self.continents, x / 1024.0, z / 1024.0, 5) * 1.6 - 0.1;
+        let temperature = fbm(&self.temperature, x / 768.0, z / 768.0, 3);
+
+        // Deserts are flatter than plains.
+        let desert = smoothstep(0.15, 0.3, temperature);
+        let land = smoothstep(0.0, 0.2, continent);
+        let hills = fbm(&self.hills, x / 96.0, z / 96.0, 4) * lerp(12.0, 5.0, desert) * land;
+
+        let height = if continent < 0.0 {
+            sea_level + continent * 60.0
+        } else {
+            sea_level + 1.0 + continent * 24.0 + hills
+        };
+
+        let height = (height.floor() as i32).max(self.min_y + 1);
+
+        let biome = if let Some(biome) = self.biome {
+            biome
+        } else if height < self.sea_level {
+            Biome::Ocean
+        } else if height <= self.sea_level + 2 && continent < 0.05 {
+            Biome::Beach
+        } else if temperature > 0.22 {
+            Biome::Desert
+        } else {
+            Biome::Plains
+        };
+
+        Column { height, biome }
+    }
+
+    /// Returns whether a random event with the given probability happens at
+    /// the column at `x` and `z`. The result only depends on the seed, the
+    /// position, and `salt`, which distinguishes unrelated events.
+    fn chance(&self, x: i32, z: i32, salt: u64, probability: f64) -> bool {
+        let pos = ((x as u64) << 32) | z as u32 as u64;
+        let hash = splitmix64(self.seed ^ splitmix64(pos ^ splitmix64(salt)));
+        let sample = (hash >> 11) as f64 / (1u64 << 53) as f64;
+        sample < probability
+    }
+}
+
+/// The result of the terrain noise for a column of blocks.
+struct Column {
+    height: i32,
+    biome: Biome,
+}
+
+impl Column {
+    fn block_at(&self, gen: &WorldGenerator, x: i32, y: i32, z: i32) -> BlockState {
+        if y == gen.min_y {
+            return BlockState::BEDROCK;
+        }
+
+        if y > self.height {
+            return if y <= gen.sea_level {
+                BlockState::WATER
+            } else {
+                BlockState::AIR
+            };
+        }
+
+        let depth = self.height - y;
+
+        // Vary the thickness of the surface a little.
+        let extra = i32::from(gen.chance(x, z, 0, 0.5));
+
+        match self.biome {
+            Biome::Plains if depth == 0 => BlockState::GRASS_BLOCK,
+            Biome::Plains if depth <= 3 + extra => BlockState::DIRT,
+            Biome::Desert | Biome::Beach if depth <= 3 + extra => BlockState::SAND,
+            Biome::Desert if depth <= 7 + extra => BlockState::SANDSTONE,
+            Biome::Ocean if depth <= 2 + extra => {
+                if self.height > gen.sea_level - 8 {
+                    BlockState::SAND
+                } else {
+                    BlockState::GRAVEL
+                }
+            }
+            _ => BlockState::STONE,
+        }
+    }
+}
+
+/// Fractal noise in the range `[-1, 1]`.
+fn fbm(noise: &SuperSimplex, x: f64, z: f64, octaves: u32) -> f64 {
+    let mut freq = 1.0;
+    let mut amp = 1.0;
+    let mut amp_sum = 0.0;
+    let mut sum = 0.0;
+
+    for _ in 0..octaves {
+        sum += noise.get([x * freq, z * freq]) * amp;
+        amp_sum += amp;
+
+        freq *= 2.0;
+        amp *= 0.5;
+    }
+
+    sum / amp_sum
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a * (1.0 - t) + b * t
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Derives the seed of one of the noise functions from the world seed.
+fn sub_seed(seed: u64, idx: u64) -> u32 {
+    splitmix64(seed.wrapping_add(idx)) as u32
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn generation_is_deterministic() {
+        let biomes = BiomeRegistry::default();
+        let a = WorldGenerator::new(42, &biomes);
+        let b = WorldGenerator::new(42, &biomes);
+        let c = WorldGenerator::new(43, &biomes);
+
+        let pos = ChunkPos::new(3, -7);
+        let (chunk_a, chunk_b) = (a.generate(pos), b.generate(pos));
+
+        for y in (0..chunk_a.height()).step_by(7) {
+            for z in 0..16 {
+                for x in 0..16 {
+                    assert_eq!(chunk_a.block_state(x, y, z), chunk_b.block_state(x, y, z));
+                }
+            }
+        }
+
+        let differs = (0..64).any(|i| a.surface_height(i * 100, 0) != c.surface_height(i * 100, 0));
+        assert!(differs, "different seeds should produce different terrain");
+    }
+
+    #[test]
+    fn columns_are_filled_up_to_the_surface() {
+        let gen = WorldGenerator::new(7, &BiomeRegistry::default());
+        let chunk = gen.generate(ChunkPos::new(0, 0));
+
+        for z in 0..16 {
+            for x in 0..16 {
+                let height = gen.surface_height(x, z);
+                let idx = (height - gen.min_y) as u32;
+
+                assert_eq!(
+                    chunk.block_state(x as u32, 0, z as u32),
+                    BlockState::BEDROCK
+                );
+                assert!(!chunk.block_state(x as u32, idx, z as u32).is_air());
+
+                let above = chunk.block_state(x as u32, idx + 1, z as u32);
+
+                if height < gen.sea_level() {
+                    assert_eq!(above, BlockState::WATER);
+                } else {
+                    assert!(above.is_air() || above.is_replaceable(), "{above:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_biome() {
+        let gen = WorldGenerator::new(1234, &BiomeRegistry::default()).with_biome(Biome::Desert);
+
+        for z in -16..16 {
+            for x in -16..16 {
+                assert_eq!(gen.biome_at(x * 64, z * 64), Biome::Desert);
+            }
+        }
+    }
+
+    #[test]
+    fn all_biomes_are_generated() {
+        let gen = WorldGenerator::new(1234, &BiomeRegistry::default());
+
+        let mut found = HashSet::new();
+
+        for z in -64..64 {
+            for x in -64..64 {
+                found.insert(gen.biome_at(x * 64, z * 64));
+            }
+        }
+
+        for biome in Biome::ALL {
+            assert!(found.contains(&biome), "{biome:?} was not generated");
+        }
+    }
+}
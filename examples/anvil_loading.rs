@@ -121,12 +121,12 @@ fn handle_chunk_loads(
 
     for event in events.read() {
         match &event.status {
-            ChunkLoadStatus::Success { .. } => {
+            ChunkLoadStatus::Success { .. } | ChunkLoadStatus::Generated => {
                 // The chunk was inserted into the world. Nothing for us to do.
             }
             ChunkLoadStatus::Empty => {
-                // There's no chunk here so let's insert an empty chunk. If we were doing
-                // terrain generation we would prepare that here.
+                // There's no chunk here so let's insert an empty chunk. A generator set
+                // with `AnvilLevel::with_generator` would be used instead if we had one.
                 layer.insert_chunk(event.pos, UnloadedChunk::new());
             }
             ChunkLoadStatus::Failed(e) => {
@@ -1,6 +1,8 @@
 #![allow(clippy::type_complexity)]
 
+use valence::block_placement::{oriented_state, resync_block, BlockPlacement};
 use valence::interact_block::InteractBlockEvent;
+use valence::inventory::block_inventory::block_entity_from_item;
 use valence::inventory::HeldItem;
 use valence::prelude::*;
 
@@ -122,14 +124,15 @@ fn digging(
 }
 
 fn place_blocks(
-    mut clients: Query<(&mut Inventory, &GameMode, &HeldItem)>,
-    mut layers: Query<&mut ChunkLayer>,
+    mut clients: Query<(&mut Client, &mut Inventory, &GameMode, &HeldItem)>,
+    mut layers: Query<(Entity, &mut ChunkLayer)>,
     mut events: EventReader<InteractBlockEvent>,
+    placement: BlockPlacement,
 ) {
-    let mut layer = layers.single_mut();
+    let (layer_entity, mut layer) = layers.single_mut();
 
     for event in events.read() {
-        let Ok((mut inventory, game_mode, held)) = clients.get_mut(event.client) else {
+        let Ok((mut client, mut inventory, game_mode, held)) = clients.get_mut(event.client) else {
             continue;
         };
         if event.hand != Hand::Main {
@@ -149,6 +152,22 @@ fn place_blocks(
             continue;
         };
 
+        let state = oriented_state(block_kind, event.face);
+
+        let real_pos =
+            match placement.validate(&layer, layer_entity, event.position, event.face, state) {
+                Ok(pos) => pos,
+                Err(_) => {
+                    // undo the block the client predicted
+                    let predicted = event.position.get_in_direction(event.face);
+                    resync_block(&mut client, &layer, predicted);
+                    continue;
+                }
+            };
+
+        // placed shulker boxes keep the contents of the item
+        let block = Block::new(state, block_entity_from_item(stack));
+
         if *game_mode == GameMode::Survival {
             // check if the player has the item in their inventory and remove
             // it.
@@ -159,15 +178,6 @@ fn place_blocks(
                 inventory.set_slot(slot_id, ItemStack::EMPTY);
             }
         }
-        let real_pos = event.position.get_in_direction(event.face);
-        let state = block_kind.to_state().set(
-            PropName::Axis,
-            match event.face {
-                Direction::Down | Direction::Up => PropValue::Y,
-                Direction::North | Direction::South => PropValue::Z,
-                Direction::West | Direction::East => PropValue::X,
-            },
-        );
-        layer.set_block(real_pos, state);
+        layer.set_block(real_pos, block);
     }
 }
@@ -1,5 +1,4 @@
-use valence::entity::player::PlayerEntityBundle;
-use valence::player_list::{DisplayName, Listed, PlayerListEntryBundle};
+use valence::player_list::npc::{LookAtPlayers, NpcBundle, NpcInteractEvent};
 use valence::prelude::*;
 use valence::text::IntoText;
 
@@ -15,6 +14,7 @@ pub fn main() {
                 init_clients,
                 despawn_disconnected_clients,
                 apply_custom_skin,
+                greet_players,
             ),
         )
         .run();
@@ -44,28 +44,16 @@ fn setup(
 
     let layer_id = commands.spawn(layer).id();
 
-    let npc_id = UniqueId::default();
-
-    commands.spawn(PlayerEntityBundle {
-        layer: EntityLayerId(layer_id),
-        uuid: npc_id,
-        position: Position::new((0.0, SPAWN_Y as f64 + 1.0, 6.0)),
-        look: Look::new(180.0, 0.0),
-        head_yaw: HeadYaw(180.0),
-        ..Default::default()
-    });
-
-    // In order for the player entity to be visible to other players, there must
-    // be an entry in the player list.
-    commands.spawn(PlayerListEntryBundle {
-        uuid: npc_id,
-        username: Username("Alice".into()),
-        // adjusts the appearance of the name in the player list only
-        display_name: DisplayName("Alice".color(Color::RED).into()),
-        // makes the fake player not appear in the player list
-        listed: Listed(false),
-        ..Default::default()
-    });
+    // The NPC has an unlisted player list entry, which is needed for the
+    // player entity to be visible to clients.
+    commands.spawn((
+        NpcBundle::new(layer_id, "Alice")
+            .with_position([0.0, SPAWN_Y as f64 + 1.0, 6.0])
+            .with_look(180.0, 0.0)
+            // adjusts the appearance of the name in the player list only
+            .with_display_name("Alice".color(Color::RED)),
+        LookAtPlayers::default(),
+    ));
 }
 
 fn init_clients(
@@ -107,3 +95,11 @@ fn apply_custom_skin(mut query: Query<&mut Properties, (Added<Properties>, Witho
         );
     }
 }
+
+fn greet_players(mut events: EventReader<NpcInteractEvent>, mut clients: Query<&mut Client>) {
+    for event in events.read() {
+        if let Ok(mut client) = clients.get_mut(event.client) {
+            client.send_chat_message("<Alice> Hello!");
+        }
+    }
+}
@@ -94,7 +94,7 @@ fn prompt_on_punch(mut clients: Query<&mut Client>, mut events: EventReader<Inte
     for event in events.read() {
         if let Ok(mut client) = clients.get_mut(event.client) {
             if event.interact == EntityInteraction::Attack {
-                client.set_resource_pack(
+                client.send_resource_pack(
                     "https://github.com/valence-rs/valence/raw/main/assets/example_pack.zip",
                     "d7c6108849fb190ec2a49f2d38b7f1f897d9ce9f",
                     false,
@@ -39,52 +39,116 @@ use registry::biome::BiomePlugin;
 use registry::dimension_type::DimensionTypePlugin;
 #[cfg(feature = "advancement")]
 pub use valence_advancement as advancement;
+#[cfg(feature = "anticheat")]
+pub use valence_anticheat as anticheat;
 #[cfg(feature = "anvil")]
 pub use valence_anvil as anvil;
 #[cfg(feature = "boss_bar")]
 pub use valence_boss_bar as boss_bar;
+#[cfg(feature = "chat")]
+pub use valence_chat as chat;
 #[cfg(feature = "command")]
 pub use valence_command as command;
 #[cfg(feature = "command")]
 pub use valence_command_macros as command_macros;
+#[cfg(feature = "crowd")]
+pub use valence_crowd as crowd;
+#[cfg(feature = "damage")]
+pub use valence_damage as damage;
+#[cfg(feature = "datapack")]
+pub use valence_datapack as datapack;
+#[cfg(feature = "difficulty")]
+pub use valence_difficulty as difficulty;
+#[cfg(feature = "dispenser")]
+pub use valence_dispenser as dispenser;
+#[cfg(feature = "entity_tag")]
+pub use valence_entity_tag as entity_tag;
 #[cfg(feature = "inventory")]
 pub use valence_inventory as inventory;
+#[cfg(feature = "journal")]
+pub use valence_journal as journal;
 pub use valence_lang as lang;
+#[cfg(feature = "map")]
+pub use valence_map as map;
+#[cfg(feature = "metrics")]
+pub use valence_metrics as metrics;
+#[cfg(feature = "minigame")]
+pub use valence_minigame as minigame;
 #[cfg(feature = "network")]
 pub use valence_network as network;
+#[cfg(feature = "permission")]
+pub use valence_permission as permission;
+#[cfg(feature = "player_data")]
+pub use valence_player_data as player_data;
 #[cfg(feature = "player_list")]
 pub use valence_player_list as player_list;
+#[cfg(feature = "rcon")]
+pub use valence_rcon as rcon;
+#[cfg(feature = "redstone")]
+pub use valence_redstone as redstone;
+#[cfg(feature = "region")]
+pub use valence_region as region;
 use valence_registry::RegistryPlugin;
+#[cfg(feature = "replay")]
+pub use valence_replay as replay;
 #[cfg(feature = "scoreboard")]
 pub use valence_scoreboard as scoreboard;
 use valence_server::abilities::AbilitiesPlugin;
 use valence_server::action::ActionPlugin;
+use valence_server::area_trigger::AreaTriggerPlugin;
+use valence_server::budget::WorkBudgetPlugin;
+use valence_server::click::ClickPlugin;
 use valence_server::client::ClientPlugin;
 use valence_server::client_command::ClientCommandPlugin;
 use valence_server::client_settings::ClientSettingsPlugin;
+use valence_server::compression::CompressionTuningPlugin;
 use valence_server::custom_payload::CustomPayloadPlugin;
+use valence_server::emitter::EmitterPlugin;
 use valence_server::entity::hitbox::HitboxPlugin;
 use valence_server::entity::EntityPlugin;
+use valence_server::entity_sound::EntitySoundPlugin;
 use valence_server::event_loop::EventLoopPlugin;
+use valence_server::experience::ExperiencePlugin;
 use valence_server::hand_swing::HandSwingPlugin;
 use valence_server::interact_block::InteractBlockPlugin;
 use valence_server::interact_entity::InteractEntityPlugin;
 use valence_server::interact_item::InteractItemPlugin;
 use valence_server::keepalive::KeepalivePlugin;
+use valence_server::lag_compensation::LagCompensationPlugin;
 use valence_server::layer::LayerPlugin;
 use valence_server::message::MessagePlugin;
 use valence_server::movement::MovementPlugin;
 use valence_server::op_level::OpLevelPlugin;
 pub use valence_server::protocol::status_effects;
+use valence_server::random::RandomPlugin;
 use valence_server::resource_pack::ResourcePackPlugin;
+use valence_server::sign::SignPlugin;
+use valence_server::sit::SitPlugin;
+use valence_server::smooth_movement::SmoothMovementPlugin;
+use valence_server::spectate::SpectatePlugin;
 use valence_server::status::StatusPlugin;
 use valence_server::status_effect::StatusEffectPlugin;
 use valence_server::teleport::TeleportPlugin;
+use valence_server::title::TitlePlugin;
+use valence_server::transfer::TransferPlugin;
+use valence_server::visibility::VisibilityPlugin;
 pub use valence_server::*;
+#[cfg(feature = "skin")]
+pub use valence_skin as skin;
+#[cfg(feature = "spawner")]
+pub use valence_spawner as spawner;
+#[cfg(feature = "statistics")]
+pub use valence_statistics as statistics;
+#[cfg(feature = "structure")]
+pub use valence_structure as structure;
+#[cfg(feature = "time")]
+pub use valence_time as time;
 #[cfg(feature = "weather")]
 pub use valence_weather as weather;
 #[cfg(feature = "world_border")]
 pub use valence_world_border as world_border;
+#[cfg(feature = "worldgen")]
+pub use valence_worldgen as worldgen;
 
 /// Contains the most frequently used items in Valence projects.
 ///
@@ -104,8 +168,9 @@ pub mod prelude {
     pub use uuid::Uuid;
     #[cfg(feature = "advancement")]
     pub use valence_advancement::{
-        event::AdvancementTabChangeEvent, Advancement, AdvancementBundle, AdvancementClientUpdate,
-        AdvancementCriteria, AdvancementDisplay, AdvancementFrameType, AdvancementRequirements,
+        event::AdvancementTabChangeEvent, Advancement, AdvancementBuilder, AdvancementBundle,
+        AdvancementClientUpdate, AdvancementCompletedEvent, AdvancementCriteria,
+        AdvancementDisplay, AdvancementFrameType, AdvancementProgress, AdvancementRequirements,
     };
     #[cfg(feature = "inventory")]
     pub use valence_inventory::{
@@ -184,7 +249,9 @@ impl PluginGroup for DefaultPlugins {
             .add(ClientCommandPlugin)
             .add(KeepalivePlugin)
             .add(InteractEntityPlugin)
+            .add(AreaTriggerPlugin)
             .add(ClientSettingsPlugin)
+            .add(CompressionTuningPlugin)
             .add(ActionPlugin)
             .add(TeleportPlugin)
             .add(MessagePlugin)
@@ -196,7 +263,21 @@ impl PluginGroup for DefaultPlugins {
             .add(ResourcePackPlugin)
             .add(StatusPlugin)
             .add(StatusEffectPlugin)
-            .add(AbilitiesPlugin);
+            .add(AbilitiesPlugin)
+            .add(ExperiencePlugin)
+            .add(TitlePlugin)
+            .add(TransferPlugin)
+            .add(EntitySoundPlugin)
+            .add(EmitterPlugin)
+            .add(LagCompensationPlugin)
+            .add(SmoothMovementPlugin)
+            .add(SitPlugin)
+            .add(SpectatePlugin)
+            .add(VisibilityPlugin)
+            .add(RandomPlugin)
+            .add(WorkBudgetPlugin)
+            .add(ClickPlugin)
+            .add(SignPlugin);
 
         #[cfg(feature = "log")]
         {
@@ -253,6 +334,115 @@ impl PluginGroup for DefaultPlugins {
             group = group.add(valence_scoreboard::ScoreboardPlugin);
         }
 
+        #[cfg(feature = "region")]
+        {
+            group = group.add(valence_region::RegionPlugin);
+        }
+
+        #[cfg(feature = "minigame")]
+        {
+            group = group.add(valence_minigame::MinigamePlugin);
+        }
+
+        #[cfg(feature = "dispenser")]
+        {
+            group = group.add(valence_dispenser::DispenserPlugin);
+        }
+
+        #[cfg(feature = "redstone")]
+        {
+            group = group.add(valence_redstone::RedstonePlugin);
+        }
+
+        #[cfg(feature = "time")]
+        {
+            group = group.add(valence_time::TimePlugin);
+        }
+
+        #[cfg(feature = "chat")]
+        {
+            group = group.add(valence_chat::ChatPlugin);
+        }
+
+        #[cfg(feature = "permission")]
+        {
+            group = group.add(valence_permission::PermissionPlugin);
+        }
+
+        #[cfg(feature = "spawner")]
+        {
+            group = group.add(valence_spawner::SpawnerPlugin);
+        }
+
+        #[cfg(feature = "journal")]
+        {
+            group = group.add(valence_journal::JournalPlugin);
+        }
+
+        #[cfg(feature = "replay")]
+        {
+            group = group.add(valence_replay::ReplayPlugin);
+        }
+
+        #[cfg(feature = "crowd")]
+        {
+            group = group.add(valence_crowd::CrowdPlugin);
+        }
+
+        #[cfg(feature = "damage")]
+        {
+            group = group.add(valence_damage::DamagePlugin);
+        }
+
+        #[cfg(feature = "difficulty")]
+        {
+            group = group.add(valence_difficulty::DifficultyPlugin);
+        }
+
+        #[cfg(feature = "entity_tag")]
+        {
+            group = group.add(valence_entity_tag::EntityTagPlugin);
+        }
+
+        #[cfg(feature = "statistics")]
+        {
+            group = group.add(valence_statistics::StatisticsPlugin);
+        }
+
+        #[cfg(feature = "skin")]
+        {
+            group = group.add(valence_skin::SkinPlugin);
+        }
+
+        #[cfg(feature = "datapack")]
+        {
+            group = group
+                .add(valence_datapack::DatapackPlugin)
+                .add(valence_datapack::BlockDropsPlugin);
+        }
+
+        #[cfg(feature = "map")]
+        {
+            group = group.add(valence_map::MapPlugin);
+        }
+
+        #[cfg(feature = "rcon")]
+        {
+            group = group
+                .add(valence_rcon::RconPlugin)
+                .add(valence_rcon::QueryPlugin);
+        }
+
+        #[cfg(feature = "metrics")]
+        {
+            group = group.add(valence_metrics::MetricsPlugin);
+        }
+
+        #[cfg(feature = "anticheat")]
+        {
+            group = group.add(valence_anticheat::MovementValidationPlugin);
+        }
+
         group
     }
 }
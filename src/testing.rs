@@ -89,6 +89,7 @@ pub fn create_mock_client(name: impl Into<String>) -> (ClientBundle, MockClientH
         username: name.into(),
         uuid: Uuid::from_bytes(rand::random()),
         ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        server_address: "localhost".into(),
         properties: Default::default(),
         conn: Box::new(conn.clone()),
         enc: PacketEncoder::new(),
@@ -201,6 +202,17 @@ impl MockClientHelper {
         self.conn.inject_send(self.scratch.split());
     }
 
+    /// Inject a packet with the ID `id` and the data `data`, which doesn't
+    /// need to be a valid packet.
+    pub fn send_raw(&mut self, id: i32, data: &[u8]) {
+        VarInt(id)
+            .encode((&mut self.scratch).writer())
+            .expect("failed to encode packet ID");
+        self.scratch.extend_from_slice(data);
+
+        self.conn.inject_send(self.scratch.split());
+    }
+
     /// Collect all packets that have been received by the client.
     #[track_caller]
     pub fn collect_received(&mut self) -> PacketFrames {
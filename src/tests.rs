@@ -1,11 +1,57 @@
+mod abilities;
+mod advancement;
+mod area_trigger;
 mod boss_bar;
+mod budget;
+#[cfg(feature = "chat")]
+mod chat;
+mod click;
 mod client;
+#[cfg(feature = "crowd")]
+mod crowd;
+mod custom_payload;
+#[cfg(feature = "damage")]
+mod damage;
+#[cfg(feature = "datapack")]
+mod datapack;
+#[cfg(feature = "difficulty")]
+mod difficulty;
+mod emitter;
+#[cfg(feature = "entity_tag")]
+mod entity_tag;
 mod example;
+mod experience;
+mod filter;
 mod hunger;
 mod inventory;
+#[cfg(feature = "journal")]
+mod journal;
 mod layer;
+#[cfg(feature = "map")]
+mod map;
+#[cfg(feature = "player_data")]
+mod player_data;
 mod player_list;
 mod potions;
+mod protocol_error;
+mod reach;
+#[cfg(feature = "region")]
+mod region;
+#[cfg(feature = "replay")]
+mod replay;
+mod resource_pack;
 mod scoreboard;
+mod sign;
+mod sit;
+#[cfg(feature = "skin")]
+mod skin;
+mod spectate;
+#[cfg(feature = "statistics")]
+mod statistics;
+#[cfg(feature = "structure")]
+mod structure;
+mod transfer;
+mod visibility;
 mod weather;
 mod world_border;
+mod worlds;
@@ -0,0 +1,88 @@
+use bevy_ecs::event::Events;
+
+use crate::abilities::{PlayerAbilitiesFlags, PlayerFlyingRejectedEvent, PlayerStartFlyingEvent};
+use crate::protocol::packets::play::{
+    GameStateChangeS2c, PlayerAbilitiesS2c, UpdatePlayerAbilitiesC2s,
+};
+use crate::testing::ScenarioSingleClient;
+use crate::GameMode;
+
+#[test]
+fn game_mode_change_sends_abilities() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    helper.clear_received();
+
+    *app.world.get_mut::<GameMode>(client).unwrap() = GameMode::Spectator;
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<GameStateChangeS2c>(1);
+    frames.assert_count::<PlayerAbilitiesS2c>(1);
+    frames.assert_order::<(GameStateChangeS2c, PlayerAbilitiesS2c)>();
+
+    let flags = frames.first::<PlayerAbilitiesS2c>().flags;
+    assert!(flags.allow_flying());
+    assert!(flags.flying());
+    assert_eq!(
+        app.world.resource::<Events<PlayerStartFlyingEvent>>().len(),
+        1
+    );
+
+    *app.world.get_mut::<GameMode>(client).unwrap() = GameMode::Creative;
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<PlayerAbilitiesS2c>(1);
+
+    let flags = app.world.get::<PlayerAbilitiesFlags>(client).unwrap();
+    assert!(flags.instant_break());
+    assert!(flags.flying());
+}
+
+#[test]
+fn flying_without_permission_is_rejected() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    *app.world.get_mut::<GameMode>(client).unwrap() = GameMode::Survival;
+
+    app.update();
+    helper.clear_received();
+
+    helper.send(&UpdatePlayerAbilitiesC2s::StartFlying);
+
+    app.update();
+
+    assert!(!app
+        .world
+        .get::<PlayerAbilitiesFlags>(client)
+        .unwrap()
+        .flying());
+    assert_eq!(
+        app.world
+            .resource::<Events<PlayerFlyingRejectedEvent>>()
+            .len(),
+        1
+    );
+    assert!(app
+        .world
+        .resource::<Events<PlayerStartFlyingEvent>>()
+        .is_empty());
+
+    let frames = helper.collect_received();
+    frames.assert_count::<PlayerAbilitiesS2c>(1);
+}
@@ -0,0 +1,133 @@
+use bevy_ecs::system::{Command, CommandQueue, Commands};
+
+use crate::advancement::event::AdvancementTabChangeEvent;
+use crate::advancement::{
+    Advancement, AdvancementBuilder, AdvancementClientUpdate, AdvancementCompletedEvent,
+    AdvancementDisplay, AdvancementProgress, GrantAdvancement, ShowToast,
+};
+use crate::ecs::event::Events;
+use crate::protocol::packets::play::{AdvancementTabC2s, AdvancementUpdateS2c};
+use crate::testing::ScenarioSingleClient;
+use crate::{ident, ItemKind, ItemStack};
+
+#[test]
+fn advancement_tree_progress() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    helper.clear_received();
+
+    let mut queue = CommandQueue::default();
+
+    let tree = AdvancementBuilder::new(ident!("quests:root").into())
+        .with_display(AdvancementDisplay::new(
+            "Quests",
+            "Things to do",
+            ItemStack::new(ItemKind::Book, 1, None),
+        ))
+        .with_criterion(ident!("joined").into())
+        .with_child(
+            AdvancementBuilder::new(ident!("quests:mine").into())
+                .with_any_criterion([ident!("stone").into(), ident!("dirt").into()]),
+        )
+        .spawn(&mut Commands::new(&mut queue, &app.world));
+
+    queue.apply(&mut app.world);
+
+    let root = tree.root();
+    let mine = tree.advancement(&ident!("quests:mine").into()).unwrap();
+    let dirt = tree
+        .criterion(&ident!("quests:mine").into(), &ident!("dirt").into())
+        .unwrap();
+
+    app.world
+        .get_mut::<AdvancementClientUpdate>(client)
+        .unwrap()
+        .new_advancements
+        .extend([root, mine]);
+
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<AdvancementUpdateS2c>(1);
+
+    // One of the alternatives completes the advancement.
+    app.world
+        .get_mut::<AdvancementProgress>(client)
+        .unwrap()
+        .grant_criterion(dirt);
+
+    GrantAdvancement {
+        client,
+        advancement: root,
+    }
+    .apply(&mut app.world);
+
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<AdvancementUpdateS2c>(1);
+
+    let progress = app.world.get::<AdvancementProgress>(client).unwrap();
+    assert!(progress.is_completed(root));
+    assert!(progress.is_completed(mine));
+    assert_eq!(
+        app.world
+            .resource::<Events<AdvancementCompletedEvent>>()
+            .len(),
+        2
+    );
+
+    // Tab selections name the root of the tab.
+    helper.send(&AdvancementTabC2s::OpenedTab {
+        tab_id: ident!("quests:root").into(),
+    });
+
+    app.update();
+    app.update();
+
+    let events = app.world.resource::<Events<AdvancementTabChangeEvent>>();
+    let mut reader = events.get_reader();
+    assert_eq!(reader.read(events).last().unwrap().tab, Some(root));
+}
+
+#[test]
+fn show_toast() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    ShowToast::new(client, "Hello!", ItemStack::new(ItemKind::Diamond, 1, None))
+        .apply(&mut app.world);
+
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<AdvancementUpdateS2c>(1);
+
+    let mut advancements = app.world.query::<&Advancement>();
+    assert_eq!(advancements.iter(&app.world).count(), 1);
+
+    // The temporary advancement is removed on the next tick.
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<AdvancementUpdateS2c>(1);
+    assert_eq!(advancements.iter(&app.world).count(), 0);
+}
@@ -0,0 +1,137 @@
+use bevy_app::App;
+use bevy_ecs::prelude::*;
+
+use crate::area_trigger::{AreaEnterEvent, AreaLeaveEvent, AreaTrigger};
+use crate::entity::hitbox::Hitbox;
+use crate::entity::interaction::InteractionEntityBundle;
+use crate::entity::Position;
+use crate::math::{Aabb, DVec3};
+use crate::testing::ScenarioSingleClient;
+use crate::Despawned;
+
+fn drain<E: Event>(app: &mut App) -> Vec<E> {
+    app.world.resource_mut::<Events<E>>().drain().collect()
+}
+
+#[test]
+fn area_trigger_enter_and_leave() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.world.get_mut::<Position>(client).unwrap().0 = DVec3::new(0.0, 64.0, 0.0);
+
+    let trigger = app
+        .world
+        .spawn(AreaTrigger::new(
+            layer,
+            Aabb::new(DVec3::new(5.0, 64.0, 0.0), DVec3::new(7.0, 66.0, 2.0)),
+        ))
+        .id();
+
+    app.update();
+
+    assert!(drain::<AreaEnterEvent>(&mut app).is_empty());
+
+    // The client's hitbox only has to overlap the area.
+    app.world.get_mut::<Position>(client).unwrap().0 = DVec3::new(4.8, 64.0, 1.0);
+    app.update();
+
+    assert_eq!(
+        drain::<AreaEnterEvent>(&mut app),
+        [AreaEnterEvent { trigger, client }]
+    );
+    assert!(app
+        .world
+        .get::<AreaTrigger>(trigger)
+        .unwrap()
+        .contains(client));
+
+    // Moving within the area doesn't send events.
+    app.world.get_mut::<Position>(client).unwrap().0 = DVec3::new(6.0, 64.0, 1.0);
+    app.update();
+
+    assert!(drain::<AreaEnterEvent>(&mut app).is_empty());
+    assert!(drain::<AreaLeaveEvent>(&mut app).is_empty());
+
+    app.world.get_mut::<Position>(client).unwrap().0 = DVec3::new(6.0, 70.0, 1.0);
+    app.update();
+
+    assert_eq!(
+        drain::<AreaLeaveEvent>(&mut app),
+        [AreaLeaveEvent { trigger, client }]
+    );
+    assert_eq!(
+        app.world
+            .get::<AreaTrigger>(trigger)
+            .unwrap()
+            .occupants()
+            .count(),
+        0
+    );
+
+    // Despawning the trigger makes the client leave it.
+    app.world.get_mut::<Position>(client).unwrap().0 = DVec3::new(6.0, 64.0, 1.0);
+    app.update();
+    assert_eq!(drain::<AreaEnterEvent>(&mut app).len(), 1);
+
+    app.world.entity_mut(trigger).insert(Despawned);
+    app.update();
+
+    assert_eq!(
+        drain::<AreaLeaveEvent>(&mut app),
+        [AreaLeaveEvent { trigger, client }]
+    );
+}
+
+#[test]
+fn area_trigger_follows_interaction_entity() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.world.get_mut::<Position>(client).unwrap().0 = DVec3::new(0.0, 64.0, 0.0);
+
+    let zone = Aabb::new(DVec3::new(-1.0, 0.0, -1.0), DVec3::new(1.0, 2.0, 1.0));
+
+    let interaction = app
+        .world
+        .spawn((
+            InteractionEntityBundle::default()
+                .with_layer(layer)
+                .with_position([10.0, 64.0, 10.0])
+                .with_size(2.0, 3.0)
+                .with_response(true),
+            AreaTrigger::new(layer, zone),
+        ))
+        .id();
+
+    // Hitboxes are sized the tick after they are added.
+    app.update();
+    app.update();
+
+    // The hitbox of the interaction entity matches its size.
+    assert_eq!(
+        app.world.get::<Hitbox>(interaction).unwrap().get(),
+        Aabb::new(DVec3::new(9.0, 64.0, 9.0), DVec3::new(11.0, 67.0, 11.0))
+    );
+    assert!(drain::<AreaEnterEvent>(&mut app).is_empty());
+
+    // The area moves along with the entity.
+    app.world.get_mut::<Position>(interaction).unwrap().0 = DVec3::new(0.0, 64.0, 0.0);
+    app.update();
+
+    assert_eq!(
+        drain::<AreaEnterEvent>(&mut app),
+        [AreaEnterEvent {
+            trigger: interaction,
+            client
+        }]
+    );
+}
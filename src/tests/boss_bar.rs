@@ -1,9 +1,11 @@
 use valence_boss_bar::{
-    BossBarBundle, BossBarColor, BossBarDivision, BossBarFlags, BossBarHealth, BossBarStyle,
-    BossBarTitle,
+    AttachedBossBarBundle, BossBarBundle, BossBarColor, BossBarDivision, BossBarFlags,
+    BossBarHealth, BossBarStyle, BossBarTitle,
 };
 use valence_server::client::VisibleEntityLayers;
-use valence_server::entity::EntityLayerId;
+use valence_server::entity::living::Health;
+use valence_server::entity::zombie::ZombieEntityBundle;
+use valence_server::entity::{EntityLayerId, Position};
 use valence_server::protocol::packets::play::BossBarS2c;
 use valence_server::text::IntoText;
 use valence_server::Despawned;
@@ -53,6 +55,49 @@ fn test_despawn() {
     frames.assert_count::<BossBarS2c>(1);
 }
 
+#[test]
+fn test_attached_to_entity() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let zombie = app
+        .world
+        .spawn(ZombieEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position([0.0, 0.0, 5.0].into()),
+            ..Default::default()
+        })
+        .id();
+
+    let boss_bar = app
+        .world
+        .spawn(AttachedBossBarBundle::new(zombie, 16.0))
+        .id();
+
+    app.update();
+
+    // The client is in range of the zombie.
+    helper.collect_received().assert_count::<BossBarS2c>(1);
+
+    app.world.get_mut::<Health>(zombie).unwrap().0 = 10.0;
+    app.update();
+
+    // The health of the boss bar follows the zombie.
+    helper.collect_received().assert_count::<BossBarS2c>(1);
+    assert_eq!(app.world.get::<BossBarHealth>(boss_bar).unwrap().0, 0.5);
+
+    app.world.entity_mut(zombie).insert(Despawned);
+    app.update();
+
+    // The boss bar is removed together with the zombie.
+    helper.collect_received().assert_count::<BossBarS2c>(1);
+    assert!(app.world.get_entity(boss_bar).is_none());
+}
+
 #[test]
 fn test_title_update() {
     let ScenarioSingleClient {
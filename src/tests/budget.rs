@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use bevy_app::App;
+use bevy_ecs::event::Events;
+
+use crate::budget::{BudgetedTask, TaskCompletedEvent, TaskProgressEvent, WorkBudget};
+use crate::layer::chunk::region::{self, Region};
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockState, ChunkPos};
+
+#[test]
+fn budgeted_task_spreads_across_ticks() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    // Only one item of every task runs per tick.
+    app.world.resource_mut::<WorkBudget>().per_tick = Duration::ZERO;
+
+    let mut chunk_layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+
+    for z in 0..2 {
+        for x in 0..2 {
+            chunk_layer.insert_chunk(ChunkPos::new(x, z), UnloadedChunk::new());
+        }
+    }
+
+    let task = app
+        .world
+        .spawn(region::fill_task(
+            layer,
+            Region::new([0, 0, 0], [31, 3, 31]),
+            BlockState::STONE,
+        ))
+        .id();
+
+    let filled = |app: &App| {
+        let chunk_layer = app.world.get::<ChunkLayer>(layer).unwrap();
+
+        [[0, 0, 0], [31, 0, 0], [0, 0, 31], [31, 3, 31]]
+            .map(|pos| chunk_layer.block(pos).unwrap().state == BlockState::STONE)
+    };
+
+    app.update();
+
+    assert_eq!(filled(&app), [true, false, false, false]);
+
+    let progress = app.world.resource::<Events<TaskProgressEvent>>();
+    assert_eq!(
+        progress.iter_current_update_events().last(),
+        Some(&TaskProgressEvent {
+            task,
+            done: 1,
+            total: Some(4),
+        })
+    );
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    assert_eq!(filled(&app), [true; 4]);
+
+    let completed = app.world.resource::<Events<TaskCompletedEvent>>();
+    assert_eq!(
+        completed.iter_current_update_events().last(),
+        Some(&TaskCompletedEvent { task, done: 4 })
+    );
+
+    assert!(app.world.get_entity(task).is_none());
+}
+
+#[test]
+fn budgeted_tasks_share_the_budget() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.world.resource_mut::<WorkBudget>().per_tick = Duration::ZERO;
+
+    let a = app.world.spawn(BudgetedTask::new(0..3, |_, _| {})).id();
+    let b = app.world.spawn(BudgetedTask::new(0..3, |_, _| {})).id();
+
+    app.update();
+
+    // Both tasks make progress even though the budget is used up.
+    for task in [a, b] {
+        assert_eq!(app.world.get::<BudgetedTask>(task).unwrap().done(), 1);
+    }
+
+    // Despawning a task cancels it.
+    app.world.despawn(b);
+
+    app.update();
+
+    assert_eq!(app.world.get::<BudgetedTask>(a).unwrap().done(), 2);
+}
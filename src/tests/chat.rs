@@ -0,0 +1,166 @@
+use bevy_app::App;
+use bevy_ecs::event::Events;
+use valence_server::protocol::packets::play::{
+    ChatMessageC2s, ChatMessageS2c, CommandExecutionC2s, DisconnectS2c, GameMessageS2c,
+};
+use valence_server::protocol::{Bounded, FixedBitSet, VarInt};
+
+use crate::chat::{ChatEvent, ChatSettings, CommandSpy};
+use crate::testing::{create_mock_client, MockClientHelper, ScenarioSingleClient};
+use crate::Despawned;
+
+fn chat_message(message: &str, timestamp: u64) -> ChatMessageC2s<'_> {
+    ChatMessageC2s {
+        message: Bounded(message),
+        timestamp,
+        salt: 0,
+        signature: None,
+        message_count: VarInt(0),
+        acknowledgement: FixedBitSet([0; 3]),
+    }
+}
+
+fn command(command: &str) -> CommandExecutionC2s<'_> {
+    CommandExecutionC2s {
+        command: Bounded(command),
+        timestamp: 0,
+        salt: 0,
+        argument_signatures: vec![],
+        message_count: VarInt(0),
+        acknowledgement: FixedBitSet([0; 3]),
+    }
+}
+
+fn chat_events(app: &App) -> usize {
+    app.world
+        .resource::<Events<ChatEvent>>()
+        .iter_current_update_events()
+        .count()
+}
+
+/// Spawns a command spy in the layer of the scenario.
+fn spawn_spy(app: &mut App, layer: bevy_ecs::entity::Entity) -> MockClientHelper {
+    let (mut bundle, helper) = create_mock_client("spy");
+    bundle.player.layer.0 = layer;
+    bundle.visible_chunk_layer.0 = layer;
+    bundle.visible_entity_layers.0.insert(layer);
+
+    app.world.spawn((bundle, CommandSpy));
+    app.update();
+
+    helper
+}
+
+#[test]
+fn out_of_order_chat_message_disconnects() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    helper.send(&chat_message("first", 2000));
+    app.update();
+
+    assert_eq!(chat_events(&app), 1);
+    helper.clear_received();
+
+    helper.send(&chat_message("second", 1000));
+    app.update();
+
+    assert_eq!(chat_events(&app), 0);
+    helper.collect_received().assert_count::<DisconnectS2c>(1);
+    assert!(app
+        .world
+        .get_entity(client)
+        .is_none_or(|e| e.contains::<Despawned>()));
+}
+
+#[test]
+fn commands_sent_without_spies_are_not_shown_later() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    helper.send(&command("secret"));
+    app.update();
+
+    let mut spy = spawn_spy(&mut app, layer);
+    app.update();
+
+    spy.collect_received().assert_count::<GameMessageS2c>(0);
+}
+
+#[test]
+fn commands_only_rejects_chat_messages() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.world.resource_mut::<ChatSettings>().commands_only = true;
+
+    app.update();
+    helper.clear_received();
+
+    helper.send(&chat_message("hello", 1000));
+    app.update();
+
+    // The sender is told that the message was blocked, but stays connected.
+    assert_eq!(chat_events(&app), 0);
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<GameMessageS2c>(1);
+    recvd.assert_count::<ChatMessageS2c>(0);
+    recvd.assert_count::<DisconnectS2c>(0);
+    assert!(!app.world.entity(client).contains::<Despawned>());
+
+    // Commands can still be run.
+    helper.send(&command("help"));
+    app.update();
+
+    helper.collect_received().assert_count::<DisconnectS2c>(0);
+}
+
+#[test]
+fn spies_see_each_command_once() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    let mut spy = spawn_spy(&mut app, layer);
+    spy.clear_received();
+
+    helper.send(&command("first"));
+    helper.send(&command("second"));
+
+    // Events are kept for two updates, so a reader which doesn't keep up
+    // would show the commands again.
+    app.update();
+    app.update();
+    app.update();
+
+    spy.collect_received().assert_count::<GameMessageS2c>(2);
+
+    // Spies don't see their own commands.
+    spy.send(&command("own"));
+    app.update();
+
+    spy.collect_received().assert_count::<GameMessageS2c>(0);
+}
@@ -0,0 +1,114 @@
+use bevy_app::App;
+use bevy_ecs::event::Events;
+
+use crate::click::{ClickEvent, ClickTarget, ClickType};
+use crate::entity::EntityId;
+use crate::interact_entity::EntityInteraction;
+use crate::math::Vec3;
+use crate::protocol::packets::play::{PlayerInteractBlockC2s, PlayerInteractEntityC2s};
+use crate::protocol::VarInt;
+use crate::testing::{MockClientHelper, ScenarioSingleClient};
+use crate::{BlockPos, Direction, Hand};
+
+fn right_click_entity(helper: &mut MockClientHelper, entity_id: i32) {
+    for hand in [Hand::Main, Hand::Off] {
+        helper.send(&PlayerInteractEntityC2s {
+            entity_id: VarInt(entity_id),
+            interact: EntityInteraction::InteractAt {
+                target: Vec3::ZERO,
+                hand,
+            },
+            sneaking: true,
+        });
+
+        helper.send(&PlayerInteractEntityC2s {
+            entity_id: VarInt(entity_id),
+            interact: EntityInteraction::Interact(hand),
+            sneaking: true,
+        });
+    }
+}
+
+#[test]
+fn click_events_are_debounced() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    // The client clicks on itself for simplicity.
+    let entity_id = app.world.get::<EntityId>(client).unwrap().get();
+
+    let clicks = |app: &App| {
+        app.world
+            .resource::<Events<ClickEvent>>()
+            .iter_current_update_events()
+            .copied()
+            .collect::<Vec<_>>()
+    };
+
+    right_click_entity(&mut helper, entity_id);
+
+    helper.send(&PlayerInteractEntityC2s {
+        entity_id: VarInt(entity_id),
+        interact: EntityInteraction::Attack,
+        sneaking: false,
+    });
+
+    for hand in [Hand::Main, Hand::Off] {
+        helper.send(&PlayerInteractBlockC2s {
+            hand,
+            position: BlockPos::new(1, 2, 3),
+            face: Direction::Up,
+            cursor_pos: Vec3::ZERO,
+            head_inside_block: false,
+            sequence: VarInt(0),
+        });
+    }
+
+    app.update();
+
+    assert_eq!(
+        clicks(&app),
+        [
+            ClickEvent {
+                client,
+                target: ClickTarget::Entity(client),
+                click: ClickType::Right,
+                hand: Hand::Main,
+                sneaking: true,
+            },
+            ClickEvent {
+                client,
+                target: ClickTarget::Entity(client),
+                click: ClickType::Left,
+                hand: Hand::Main,
+                sneaking: false,
+            },
+            ClickEvent {
+                client,
+                target: ClickTarget::Block(BlockPos::new(1, 2, 3)),
+                click: ClickType::Right,
+                hand: Hand::Main,
+                sneaking: false,
+            },
+        ]
+    );
+
+    // A double click is dropped.
+    right_click_entity(&mut helper, entity_id);
+    app.update();
+    assert!(clicks(&app).is_empty());
+
+    for _ in 0..4 {
+        app.update();
+    }
+
+    right_click_entity(&mut helper, entity_id);
+    app.update();
+    assert_eq!(clicks(&app).len(), 1);
+}
@@ -1,12 +1,18 @@
+use bevy_ecs::system::Command;
+
 use crate::abilities::PlayerAbilitiesFlags;
+use crate::client::VisibleChunkLayer;
 use crate::layer::chunk::UnloadedChunk;
 use crate::layer::ChunkLayer;
 use crate::math::DVec3;
 use crate::protocol::packets::play::{
-    FullC2s, MoveRelativeS2c, PlayerPositionLookS2c, TeleportConfirmC2s,
+    ChunkDataS2c, ChunkRenderDistanceCenterS2c, FullC2s, HealthUpdateS2c, MoveRelativeS2c,
+    PlayerPositionLookS2c, PlayerRespawnS2c, TeleportConfirmC2s,
 };
+use crate::registry::{BiomeRegistry, DimensionTypeRegistry};
+use crate::switch_layer::SwitchLayer;
 use crate::testing::{create_mock_client, ScenarioSingleClient};
-use crate::{ChunkPos, GameMode};
+use crate::{ident, ChunkPos, EntityLayer, GameMode, Server};
 
 #[test]
 fn client_teleport_and_move() {
@@ -101,3 +107,59 @@ fn client_gamemode_changed_ability() {
     assert!(!abilities.instant_break());
     assert!(!abilities.invulnerable());
 }
+
+#[test]
+fn switch_layer_respawns_before_chunks() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    let mut chunk_layer = ChunkLayer::new(
+        ident!("the_nether"),
+        app.world.resource::<DimensionTypeRegistry>(),
+        app.world.resource::<BiomeRegistry>(),
+        app.world.resource::<Server>(),
+    );
+
+    for z in -5..5 {
+        for x in 95..105 {
+            chunk_layer.insert_chunk(ChunkPos::new(x, z), UnloadedChunk::new());
+        }
+    }
+
+    let entity_layer = EntityLayer::new(app.world.resource::<Server>());
+    let nether = app.world.spawn((chunk_layer, entity_layer)).id();
+
+    app.update();
+    helper.clear_received();
+
+    SwitchLayer::new(client, nether, [1600.0, 64.0, 0.0]).apply(&mut app.world);
+
+    app.update();
+
+    assert_eq!(
+        app.world.get::<VisibleChunkLayer>(client).unwrap().0,
+        nether
+    );
+
+    let frames = helper.collect_received();
+
+    frames.assert_count::<PlayerRespawnS2c>(1);
+    frames.assert_count::<HealthUpdateS2c>(1);
+    frames.assert_order::<(
+        PlayerRespawnS2c,
+        ChunkRenderDistanceCenterS2c,
+        ChunkDataS2c,
+        PlayerPositionLookS2c,
+    )>();
+
+    // Switching doesn't respawn the client a second time.
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<PlayerRespawnS2c>(0);
+}
@@ -0,0 +1,81 @@
+use valence_crowd::{Crowd, CrowdBundle};
+
+use crate::entity::sheep::SheepEntityBundle;
+use crate::entity::{EntityLayerId, Position};
+use crate::layer::chunk::UnloadedChunk;
+use crate::protocol::packets::play::{EntitiesDestroyS2c, EntitySpawnS2c};
+use crate::testing::ScenarioSingleClient;
+use crate::{ChunkLayer, Server};
+
+#[test]
+fn crowd_level_of_detail() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let mut chunk_layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+
+    for z in -5..5 {
+        for x in -5..5 {
+            chunk_layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    let bundle = CrowdBundle::new(layer, app.world.resource::<Server>());
+    let crowd = app.world.spawn(bundle).id();
+
+    // Ten sheep in one cell, 40 blocks from the client.
+    for i in 0..10 {
+        app.world.spawn(SheepEntityBundle {
+            layer: EntityLayerId(crowd),
+            position: Position::new([f64::from(i % 5), 0.0, 40.0 + f64::from(i / 5)]),
+            ..Default::default()
+        });
+    }
+
+    app.update();
+
+    // The client sees the proxy of the cell.
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<EntitySpawnS2c>(1);
+    }
+
+    assert_eq!(app.world.get::<Crowd>(crowd).unwrap().proxy_count(), 1);
+
+    app.world
+        .get_mut::<Position>(client)
+        .unwrap()
+        .set([0.0, 0.0, 30.0]);
+
+    app.update();
+
+    // The client approached and sees the members instead.
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<EntitySpawnS2c>(10);
+        recvd.assert_count::<EntitiesDestroyS2c>(1);
+    }
+
+    let mut crowd_settings = app.world.get_mut::<Crowd>(crowd).unwrap();
+    crowd_settings.detail_distance = 10.0;
+    crowd_settings.cull_distance = 20.0;
+
+    app.world
+        .get_mut::<Position>(client)
+        .unwrap()
+        .set([0.0, 0.0, 0.0]);
+
+    app.update();
+
+    // The client is too far away to see anything.
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<EntitySpawnS2c>(0);
+        recvd.assert_count::<EntitiesDestroyS2c>(1);
+        assert_eq!(recvd.first::<EntitiesDestroyS2c>().entity_ids.len(), 10);
+    }
+}
@@ -0,0 +1,165 @@
+use bevy_app::App;
+use bevy_ecs::event::Events;
+
+use crate::custom_payload::{
+    AddPluginChannel, PluginChannelRegistry, PluginMessage, PluginMessageEvent,
+};
+use crate::protocol::packets::play::{CustomPayloadC2s, CustomPayloadS2c};
+use crate::protocol::{Bounded, Packet, RawBytes};
+use crate::testing::{create_mock_client, MockClientHelper, ScenarioSingleClient};
+use crate::{ident, Ident};
+
+/// Returns the channels and payloads of the custom payloads sent to a client.
+fn payloads(helper: &mut MockClientHelper) -> Vec<(String, Vec<u8>)> {
+    helper
+        .collect_received()
+        .0
+        .iter()
+        .filter(|frame| frame.id == CustomPayloadS2c::ID)
+        .map(|frame| {
+            let pkt = frame.decode::<CustomPayloadS2c>().unwrap();
+            (pkt.channel.to_string(), pkt.data.0 .0.to_vec())
+        })
+        .collect()
+}
+
+#[test]
+fn channels_are_announced() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut registry = app.world.resource_mut::<PluginChannelRegistry>();
+    registry.register(ident!("valence:a"));
+    registry.register(ident!("valence:b"));
+
+    app.update();
+
+    assert_eq!(
+        payloads(&mut helper),
+        [(
+            "minecraft:register".into(),
+            b"valence:a\0valence:b".to_vec()
+        )]
+    );
+
+    // Nothing is sent while the registry is unchanged.
+    app.update();
+    assert_eq!(payloads(&mut helper), []);
+
+    // Clients joining later are only sent the registered channels.
+    let (mut bundle, mut other) = create_mock_client("other");
+    bundle.player.layer.0 = layer;
+    bundle.visible_chunk_layer.0 = layer;
+    app.world.spawn(bundle);
+
+    app.update();
+
+    assert_eq!(payloads(&mut helper), []);
+    assert_eq!(
+        payloads(&mut other),
+        [(
+            "minecraft:register".into(),
+            b"valence:a\0valence:b".to_vec()
+        )]
+    );
+
+    // Changes are sent to all clients.
+    app.world
+        .resource_mut::<PluginChannelRegistry>()
+        .unregister(ident!("valence:a"));
+
+    app.update();
+
+    for helper in [&mut helper, &mut other] {
+        assert_eq!(
+            payloads(helper),
+            [
+                ("minecraft:register".into(), b"valence:b".to_vec()),
+                ("minecraft:unregister".into(), b"valence:a".to_vec())
+            ]
+        );
+    }
+
+    app.update();
+    assert_eq!(payloads(&mut helper), []);
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Ping(u32);
+
+impl PluginMessage for Ping {
+    const CHANNEL: Ident<&'static str> = ident!("valence:ping");
+
+    fn decode(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self(u32::from_be_bytes(data.try_into()?)))
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.0.to_be_bytes());
+    }
+}
+
+fn send_payload(helper: &mut MockClientHelper, channel: Ident<&str>, data: &[u8]) {
+    helper.send(&CustomPayloadC2s {
+        channel: channel.into(),
+        data: Bounded(RawBytes(data)),
+    });
+}
+
+fn pings(app: &App) -> Vec<Ping> {
+    app.world
+        .resource::<Events<PluginMessageEvent<Ping>>>()
+        .iter_current_update_events()
+        .map(|event| event.message.clone())
+        .collect()
+}
+
+#[test]
+fn plugin_messages_are_decoded() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.add_plugin_channel::<Ping>();
+
+    assert!(app
+        .world
+        .resource::<PluginChannelRegistry>()
+        .is_registered(Ping::CHANNEL));
+
+    app.update();
+
+    send_payload(&mut helper, Ping::CHANNEL, &7_u32.to_be_bytes());
+    app.update();
+
+    let events = app.world.resource::<Events<PluginMessageEvent<Ping>>>();
+    let event = events.iter_current_update_events().next().unwrap();
+    assert_eq!(event.client, client);
+    assert_eq!(event.message, Ping(7));
+
+    // Messages which don't decode and messages on other channels are skipped.
+    send_payload(&mut helper, Ping::CHANNEL, &[1, 2]);
+    send_payload(&mut helper, ident!("valence:other"), &7_u32.to_be_bytes());
+    app.update();
+
+    assert_eq!(pings(&app), []);
+
+    // Replies are encoded with the message.
+    helper.clear_received();
+
+    let mut client = app.world.get_mut::<crate::client::Client>(client).unwrap();
+    client.send_typed_plugin_message(&Ping(9));
+    app.update();
+
+    assert_eq!(
+        payloads(&mut helper),
+        [("valence:ping".into(), 9_u32.to_be_bytes().to_vec())]
+    );
+}
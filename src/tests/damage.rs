@@ -0,0 +1,152 @@
+use bevy_ecs::event::Events;
+use bevy_ecs::system::Command;
+
+use crate::damage::{
+    Damage, DamageContext, DamageEvent, DamageKind, DamageModifiers, Knockback, KnockbackEvent,
+    KnockbackProfile,
+};
+use crate::entity::entity::Flags;
+use crate::entity::living::Health;
+use crate::entity::player::AbsorptionAmount;
+use crate::entity::zombie::ZombieEntityBundle;
+use crate::entity::{EntityLayerId, Look, OnGround, Position, Velocity};
+use crate::inventory::Inventory;
+use crate::math::{DVec3, Vec3};
+use crate::nbt::{Compound, List};
+use crate::testing::ScenarioSingleClient;
+use crate::{ItemKind, ItemStack};
+
+fn last_damage_event(app: &crate::app::App) -> DamageEvent {
+    let events = app.world.resource::<bevy_ecs::event::Events<DamageEvent>>();
+    events
+        .iter_current_update_events()
+        .last()
+        .expect("no damage event was sent")
+        .clone()
+}
+
+#[test]
+fn damage_passes_through_modifiers() {
+    let ScenarioSingleClient {
+        mut app, client, ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world.get_mut::<Health>(client).unwrap().0 = 20.0;
+
+    // Full diamond armor.
+    let mut inv = app.world.get_mut::<Inventory>(client).unwrap();
+    inv.set_slot(5, ItemStack::new(ItemKind::DiamondHelmet, 1, None));
+    inv.set_slot(6, ItemStack::new(ItemKind::DiamondChestplate, 1, None));
+    inv.set_slot(7, ItemStack::new(ItemKind::DiamondLeggings, 1, None));
+    inv.set_slot(8, ItemStack::new(ItemKind::DiamondBoots, 1, None));
+
+    Damage::new(client, 10.0, DamageKind::Attack).apply(&mut app.world);
+
+    let event = last_damage_event(&app);
+    let names: Vec<_> = event.steps.iter().map(|s| s.name.as_ref()).collect();
+    assert_eq!(names, ["armor", "effects", "enchantments"]);
+    assert!((event.amount - 3.0).abs() < 1e-4, "{}", event.amount);
+
+    let health = app.world.get::<Health>(client).unwrap().0;
+    assert!((health - 17.0).abs() < 1e-4, "{health}");
+
+    // Armor doesn't help against falling, but a custom modifier halves it.
+    app.world.resource_mut::<DamageModifiers>().add(
+        "half",
+        DamageModifiers::ARMOR - 1,
+        |_: &DamageContext, amount: f32| amount / 2.0,
+    );
+    app.world.get_mut::<AbsorptionAmount>(client).unwrap().0 = 1.0;
+
+    Damage::new(client, 4.0, DamageKind::Fall).apply(&mut app.world);
+
+    let event = last_damage_event(&app);
+    assert_eq!(event.steps[0].name, "half");
+    assert_eq!(event.amount, 2.0);
+    assert_eq!(event.absorbed, 1.0);
+    assert_eq!(app.world.get::<AbsorptionAmount>(client).unwrap().0, 0.0);
+
+    let health = app.world.get::<Health>(client).unwrap().0;
+    assert!((health - 16.0).abs() < 1e-4, "{health}");
+
+    // Removing a modifier takes it out of the pipeline.
+    assert!(app.world.resource_mut::<DamageModifiers>().remove("half"));
+    assert!(!app.world.resource_mut::<DamageModifiers>().remove("half"));
+}
+
+#[test]
+fn knockback_from_sprint_hit() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    let victim = app
+        .world
+        .spawn(ZombieEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position::new([0.0, 0.0, 2.0]),
+            on_ground: OnGround(true),
+            ..Default::default()
+        })
+        .id();
+
+    // The client looks at the victim with a knockback II sword while
+    // sprinting.
+    app.world.get_mut::<Position>(client).unwrap().0 = DVec3::ZERO;
+    app.world.get_mut::<Look>(client).unwrap().yaw = 0.0;
+    app.world
+        .get_mut::<Flags>(client)
+        .unwrap()
+        .set_sprinting(true);
+
+    let mut enchantment = Compound::new();
+    enchantment.insert("id", "minecraft:knockback");
+    enchantment.insert("lvl", 2_i16);
+
+    let mut nbt = Compound::new();
+    nbt.insert("Enchantments", List::Compound(vec![enchantment]));
+
+    app.world
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::DiamondSword, 1, Some(nbt)));
+
+    Knockback::new(victim, client).apply(&mut app.world);
+
+    let event = *app
+        .world
+        .resource::<Events<KnockbackEvent>>()
+        .iter_current_update_events()
+        .last()
+        .unwrap();
+
+    assert!(event.sprint_hit);
+    assert_eq!(event.bonus_levels, 3);
+    assert!((event.velocity - Vec3::new(0.0, 8.0, 34.0)).length() < 1e-3);
+    assert_eq!(app.world.get::<Velocity>(victim).unwrap().0, event.velocity);
+
+    // The next hit isn't a sprint hit until the client sprints again.
+    assert!(!app.world.get::<Flags>(client).unwrap().sprinting());
+
+    *app.world.resource_mut::<KnockbackProfile>() = KnockbackProfile::LEGACY;
+    app.world.get_mut::<Velocity>(victim).unwrap().0 = Vec3::ZERO;
+
+    Knockback::new(victim, client).apply(&mut app.world);
+
+    let event = app
+        .world
+        .resource::<Events<KnockbackEvent>>()
+        .iter_current_update_events()
+        .last()
+        .unwrap();
+
+    assert!(!event.sprint_hit);
+    assert!((event.velocity - Vec3::new(0.0, 10.0, 28.0)).length() < 1e-3);
+}
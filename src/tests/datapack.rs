@@ -0,0 +1,368 @@
+use std::path::Path;
+use std::{env, fs};
+
+use bevy_app::App;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+
+use crate::action::DiggingEvent;
+use crate::command::scopes::CommandScopes;
+use crate::command::CommandExecutionEvent;
+use crate::datapack::recipe::{Ingredient, Recipe};
+use crate::datapack::{
+    BlockDropsEvent, DatapackSettings, DatapackSummary, DatapacksReloadedEvent, LootContext,
+    LootTables, Recipes, ReloadDatapacks,
+};
+use crate::entity::item::Stack;
+use crate::layer::chunk::UnloadedChunk;
+use crate::protocol::packets::play::player_action_c2s::PlayerAction;
+use crate::protocol::packets::play::{
+    GameMessageS2c, PlayerActionC2s, SynchronizeRecipesS2c, SynchronizeTagsS2c,
+};
+use crate::protocol::VarInt;
+use crate::rand::thread_rng;
+use crate::registry::tags::TagsRegistry;
+use crate::testing::{MockClientHelper, ScenarioSingleClient};
+use crate::{
+    ident, BlockPos, BlockState, ChunkLayer, Direction, EventLoopUpdate, GameMode, ItemKind,
+    ItemStack,
+};
+
+fn write(pack: &Path, path: &str, contents: &str) {
+    let path = pack.join("data").join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn pack_dir(name: &str) -> std::path::PathBuf {
+    let pack = env::temp_dir().join(format!("valence_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&pack);
+    pack
+}
+
+fn item_tag(app: &App, tag: &str) -> Option<Vec<VarInt>> {
+    app.world.resource::<TagsRegistry>().registries["minecraft:item"]
+        .get(tag)
+        .cloned()
+}
+
+#[test]
+fn datapacks_are_loaded_and_reloaded() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    // Let the client join first.
+    app.update();
+
+    let pack = pack_dir("datapack");
+
+    write(
+        &pack,
+        "test/tags/items/gems.json",
+        r##"{ "values": ["minecraft:diamond", "#minecraft:coals"] }"##,
+    );
+    write(
+        &pack,
+        "test/loot_tables/chests/gems.json",
+        r#"{ "pools": [{ "rolls": 2, "entries": [{ "type": "minecraft:item", "name": "minecraft:diamond" }] }] }"#,
+    );
+    write(
+        &pack,
+        "test/recipes/gem_block.json",
+        r##"{
+            "type": "minecraft:crafting_shapeless",
+            "ingredients": [{ "tag": "test:gems" }],
+            "result": { "item": "minecraft:diamond_block" }
+        }"##,
+    );
+
+    app.insert_resource(DatapackSettings {
+        packs: vec![pack.clone()],
+    });
+
+    ReloadDatapacks.apply(&mut app.world);
+    helper.clear_received();
+    app.update();
+
+    let events: Vec<_> = app
+        .world
+        .resource_mut::<Events<DatapacksReloadedEvent>>()
+        .drain()
+        .collect();
+    assert!(matches!(
+        events[..],
+        [DatapacksReloadedEvent {
+            result: Ok(DatapackSummary {
+                tags: 1,
+                loot_tables: 1,
+                recipes: 1
+            })
+        }]
+    ));
+
+    let gems = item_tag(&app, "test:gems").unwrap();
+    assert_eq!(gems[0], VarInt(ItemKind::Diamond.to_raw().into()));
+    assert!(gems.contains(&VarInt(ItemKind::Charcoal.to_raw().into())));
+
+    let loot_tables = app.world.resource::<LootTables>();
+    let mut rng = thread_rng();
+    assert_eq!(
+        loot_tables
+            .get("test:chests/gems")
+            .unwrap()
+            .generate(&mut LootContext::new(
+                loot_tables,
+                &app.world.resource::<TagsRegistry>().registries,
+                &mut rng
+            )),
+        [
+            ItemStack::new(ItemKind::Diamond, 1, None),
+            ItemStack::new(ItemKind::Diamond, 1, None)
+        ]
+    );
+
+    let Some(Recipe::Shapeless { ingredients, .. }) =
+        app.world.resource::<Recipes>().get("test:gem_block")
+    else {
+        panic!("missing recipe");
+    };
+    assert_eq!(
+        ingredients,
+        &[Ingredient(vec![
+            ItemKind::Diamond,
+            ItemKind::Coal,
+            ItemKind::Charcoal
+        ])]
+    );
+
+    // The client is sent the new recipes and tags.
+    let frames = helper.collect_received();
+    frames.assert_count::<SynchronizeRecipesS2c>(1);
+    frames.assert_count::<SynchronizeTagsS2c>(1);
+    frames.assert_order::<(SynchronizeRecipesS2c, SynchronizeTagsS2c)>();
+
+    // A broken pack keeps the loaded data.
+    write(
+        &pack,
+        "test/recipes/broken.json",
+        r#"{ "type": "test:unknown" }"#,
+    );
+
+    app.world
+        .entity_mut(client)
+        .insert(CommandScopes(["valence.command.reload".to_owned()].into()));
+    app.update();
+    helper.clear_received();
+
+    app.world.send_event(CommandExecutionEvent {
+        command: "reload".into(),
+        executor: client,
+    });
+    app.update();
+
+    assert!(app
+        .world
+        .resource::<Recipes>()
+        .get("test:gem_block")
+        .is_some());
+    assert!(app.world.resource::<Recipes>().get("test:broken").is_none());
+
+    let frames = helper.collect_received();
+    frames.assert_count::<GameMessageS2c>(1);
+    frames.assert_count::<SynchronizeTagsS2c>(0);
+
+    // Removing the pack restores the vanilla tags.
+    app.insert_resource(DatapackSettings::default());
+    ReloadDatapacks.apply(&mut app.world);
+
+    assert_eq!(item_tag(&app, "test:gems"), None);
+    assert!(app.world.resource::<Recipes>().recipes.is_empty());
+
+    fs::remove_dir_all(pack).unwrap();
+}
+
+#[test]
+fn no_datapacks_keep_registries() {
+    let ScenarioSingleClient { mut app, .. } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world
+        .resource_mut::<TagsRegistry>()
+        .registries
+        .get_mut("minecraft:item")
+        .unwrap()
+        .insert(ident!("test:custom").into(), vec![VarInt(1)]);
+
+    ReloadDatapacks.apply(&mut app.world);
+
+    let events: Vec<_> = app
+        .world
+        .resource_mut::<Events<DatapacksReloadedEvent>>()
+        .drain()
+        .collect();
+    assert!(matches!(
+        events[..],
+        [DatapacksReloadedEvent {
+            result: Ok(DatapackSummary {
+                tags: 0,
+                loot_tables: 0,
+                recipes: 0
+            })
+        }]
+    ));
+
+    assert_eq!(item_tag(&app, "test:custom"), Some(vec![VarInt(1)]));
+}
+
+#[test]
+fn unsupported_tag_entries_are_skipped() {
+    let ScenarioSingleClient { mut app, .. } = ScenarioSingleClient::new();
+
+    app.update();
+
+    let pack = pack_dir("fluid_tags");
+
+    // Fluid IDs aren't known, so the plain entries are skipped but the
+    // referenced vanilla tag is still resolved.
+    write(
+        &pack,
+        "test/tags/fluids/liquids.json",
+        r##"{ "values": [
+            "minecraft:water",
+            { "id": "minecraft:lava", "required": false },
+            "#minecraft:lava"
+        ] }"##,
+    );
+    write(
+        &pack,
+        "test/tags/items/gems.json",
+        r#"{ "values": ["minecraft:diamond"] }"#,
+    );
+
+    app.insert_resource(DatapackSettings {
+        packs: vec![pack.clone()],
+    });
+
+    ReloadDatapacks.apply(&mut app.world);
+
+    let events: Vec<_> = app
+        .world
+        .resource_mut::<Events<DatapacksReloadedEvent>>()
+        .drain()
+        .collect();
+    assert!(matches!(
+        events[..],
+        [DatapacksReloadedEvent {
+            result: Ok(DatapackSummary { tags: 2, .. })
+        }]
+    ));
+
+    let fluids = &app.world.resource::<TagsRegistry>().registries["minecraft:fluid"];
+    assert_eq!(fluids.get("test:liquids"), fluids.get("minecraft:lava"));
+    assert!(fluids.get("test:liquids").is_some());
+    assert_eq!(
+        item_tag(&app, "test:gems"),
+        Some(vec![VarInt(ItemKind::Diamond.to_raw().into())])
+    );
+
+    fs::remove_dir_all(pack).unwrap();
+}
+
+fn dig(helper: &mut MockClientHelper, position: BlockPos) {
+    helper.send(&PlayerActionC2s {
+        action: PlayerAction::StartDestroyBlock,
+        position,
+        direction: Direction::Up,
+        sequence: VarInt(0),
+    });
+}
+
+/// Breaks every block a client starts digging, like a server with instant
+/// mining.
+fn break_blocks(mut events: EventReader<DiggingEvent>, mut layers: Query<&mut ChunkLayer>) {
+    for event in events.read() {
+        for mut layer in &mut layers {
+            layer.set_block(event.position, BlockState::AIR);
+        }
+    }
+}
+
+#[test]
+fn broken_blocks_drop_loot() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let pack = pack_dir("block_drops");
+    write(
+        &pack,
+        "minecraft/loot_tables/blocks/stone.json",
+        r#"{
+            "type": "minecraft:block",
+            "pools": [{
+                "rolls": 1,
+                "entries": [{ "type": "minecraft:item", "name": "minecraft:cobblestone" }],
+                "conditions": [{ "condition": "minecraft:survives_explosion" }]
+            }]
+        }"#,
+    );
+
+    app.insert_resource(DatapackSettings {
+        packs: vec![pack.clone()],
+    });
+    ReloadDatapacks.apply(&mut app.world);
+    app.add_systems(EventLoopUpdate, break_blocks);
+
+    let mut chunk_layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block([0, 64, 0], BlockState::STONE);
+    chunk_layer.set_block([1, 64, 0], BlockState::STONE);
+
+    app.world.entity_mut(client).insert(GameMode::Survival);
+    app.update();
+
+    dig(&mut helper, BlockPos::new(0, 64, 0));
+    app.update();
+
+    let events: Vec<_> = app
+        .world
+        .resource_mut::<Events<BlockDropsEvent>>()
+        .drain()
+        .collect();
+    assert_eq!(
+        events,
+        [BlockDropsEvent {
+            client,
+            position: BlockPos::new(0, 64, 0),
+            state: BlockState::STONE,
+            items: vec![ItemStack::new(ItemKind::Cobblestone, 1, None)],
+        }]
+    );
+
+    let mut stacks = app.world.query::<&Stack>();
+    assert_eq!(
+        stacks
+            .iter(&app.world)
+            .map(|stack| stack.0.clone())
+            .collect::<Vec<_>>(),
+        [ItemStack::new(ItemKind::Cobblestone, 1, None)]
+    );
+
+    // Nothing drops in creative mode.
+    app.world.entity_mut(client).insert(GameMode::Creative);
+    dig(&mut helper, BlockPos::new(1, 64, 0));
+    app.update();
+
+    assert!(app.world.resource::<Events<BlockDropsEvent>>().is_empty());
+    assert_eq!(stacks.iter(&app.world).count(), 1);
+
+    fs::remove_dir_all(pack).unwrap();
+}
@@ -0,0 +1,127 @@
+use bevy_ecs::system::Command;
+
+use crate::damage::{difficulty_modifier, Damage, DamageEvent, DamageKind, DamageModifiers};
+use crate::difficulty::{InhabitedTime, RegionalDifficulty, WorldDifficulty};
+use crate::entity::living::Health;
+use crate::entity::zombie::ZombieEntityBundle;
+use crate::entity::{EntityLayerId, Position};
+use crate::protocol::packets::play::DifficultyS2c;
+use crate::testing::*;
+use crate::{ChunkPos, Difficulty};
+
+#[test]
+fn difficulty_is_synced_to_clients() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.world
+        .entity_mut(layer)
+        .insert(WorldDifficulty::new(Difficulty::Hard));
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<DifficultyS2c>(1);
+
+    app.update();
+    helper.clear_received();
+
+    app.world.get_mut::<WorldDifficulty>(layer).unwrap().locked = true;
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<DifficultyS2c>(1);
+}
+
+#[test]
+fn inhabited_time_raises_regional_difficulty() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.world
+        .entity_mut(layer)
+        .insert((WorldDifficulty::new(Difficulty::Hard), InhabitedTime::new()));
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let pos = app.world.get::<Position>(client).unwrap().0;
+    let inhabited = app.world.get::<InhabitedTime>(layer).unwrap();
+
+    assert_eq!(inhabited.get(ChunkPos::from(pos)), 3);
+    assert_eq!(inhabited.get(ChunkPos::new(100, 100)), 0);
+
+    let before = RegionalDifficulty::at(&app.world, layer, pos);
+
+    app.world
+        .get_mut::<InhabitedTime>(layer)
+        .unwrap()
+        .set(ChunkPos::from(pos), 1_000_000);
+
+    let after = RegionalDifficulty::at(&app.world, layer, pos);
+
+    assert_eq!(before.difficulty(), Difficulty::Hard);
+    assert!(after.effective() > before.effective());
+}
+
+#[test]
+fn mob_attacks_scale_with_difficulty() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world.resource_mut::<DamageModifiers>().add(
+        "difficulty",
+        DamageModifiers::DIFFICULTY,
+        difficulty_modifier,
+    );
+
+    let zombie = app
+        .world
+        .spawn(ZombieEntityBundle {
+            layer: EntityLayerId(layer),
+            ..Default::default()
+        })
+        .id();
+
+    let deal = |app: &mut crate::app::App, difficulty, attacker| {
+        app.world
+            .entity_mut(layer)
+            .insert(WorldDifficulty::new(difficulty));
+        app.world.get_mut::<Health>(client).unwrap().0 = 20.0;
+
+        let damage = Damage::new(client, 6.0, DamageKind::Attack);
+
+        match attacker {
+            Some(attacker) => damage.with_attacker(attacker),
+            None => damage,
+        }
+        .apply(&mut app.world);
+
+        let events = app.world.resource::<bevy_ecs::event::Events<DamageEvent>>();
+        events.iter_current_update_events().last().unwrap().steps[0].after
+    };
+
+    assert_eq!(deal(&mut app, Difficulty::Hard, Some(zombie)), 9.0);
+    assert_eq!(deal(&mut app, Difficulty::Easy, Some(zombie)), 4.0);
+    assert_eq!(deal(&mut app, Difficulty::Peaceful, Some(zombie)), 0.0);
+
+    // Only attacks by mobs are scaled.
+    assert_eq!(deal(&mut app, Difficulty::Hard, None), 6.0);
+    assert_eq!(deal(&mut app, Difficulty::Hard, Some(client)), 6.0);
+}
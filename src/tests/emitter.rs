@@ -0,0 +1,58 @@
+use crate::emitter::{ParticleEmitter, SoundEmitter};
+use crate::entity::{EntityLayerId, Position};
+use crate::protocol::packets::play::{ParticleS2c, PlaySoundS2c};
+use crate::protocol::sound::{Sound, SoundCategory};
+use crate::protocol::{Packet, Particle};
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn emitters_play_at_their_interval() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let emitter = app
+        .world
+        .spawn((
+            Position::new([0.0, 0.0, 0.0]),
+            EntityLayerId(layer),
+            ParticleEmitter::new(Particle::Flame).with_interval(2),
+            SoundEmitter::new(Sound::BlockFireAmbient, SoundCategory::Block).with_interval(3),
+        ))
+        .id();
+
+    let mut particles = vec![];
+    let mut sounds = vec![];
+
+    for _ in 0..6 {
+        app.update();
+
+        let recvd = helper.collect_received();
+        particles.push(recvd.0.iter().filter(|f| f.id == ParticleS2c::ID).count());
+        sounds.push(recvd.0.iter().filter(|f| f.id == PlaySoundS2c::ID).count());
+    }
+
+    assert_eq!(particles, [1, 0, 1, 0, 1, 0]);
+    assert_eq!(sounds, [1, 0, 0, 1, 0, 0]);
+
+    // The excluded client doesn't receive anything.
+    app.world
+        .get_mut::<ParticleEmitter>(emitter)
+        .unwrap()
+        .except = Some(client);
+    app.world.get_mut::<SoundEmitter>(emitter).unwrap().except = Some(client);
+
+    for _ in 0..6 {
+        app.update();
+    }
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<ParticleS2c>(0);
+    recvd.assert_count::<PlaySoundS2c>(0);
+}
@@ -0,0 +1,102 @@
+use crate::entity::entity::{CustomName, Flags, NameVisible};
+use crate::entity::zombie::ZombieEntityBundle;
+use crate::entity::EntityLayerId;
+use crate::entity_tag::{Glowing, NameTag, TagIndex, Tags, TeamColor};
+use crate::protocol::packets::play::TeamS2c;
+use crate::testing::*;
+use crate::Text;
+
+#[test]
+fn name_tag_sets_custom_name() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    let zombie = app
+        .world
+        .spawn((
+            ZombieEntityBundle {
+                layer: EntityLayerId(layer),
+                ..Default::default()
+            },
+            NameTag::new("Bob"),
+            Tags::new().with("boss"),
+        ))
+        .id();
+
+    app.update();
+
+    assert_eq!(
+        app.world.get::<CustomName>(zombie).unwrap().0,
+        Some(Text::from("Bob"))
+    );
+    assert!(app.world.get::<NameVisible>(zombie).unwrap().0);
+    assert_eq!(
+        app.world
+            .resource::<TagIndex>()
+            .entities_with("boss")
+            .collect::<Vec<_>>(),
+        [zombie]
+    );
+
+    app.world.entity_mut(zombie).remove::<(NameTag, Tags)>();
+
+    app.update();
+
+    assert_eq!(app.world.get::<CustomName>(zombie).unwrap().0, None);
+    assert!(!app.world.get::<NameVisible>(zombie).unwrap().0);
+    assert_eq!(app.world.resource::<TagIndex>().count("boss"), 0);
+}
+
+#[test]
+fn glowing_color_per_viewer() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    helper.clear_received();
+
+    let zombie = app
+        .world
+        .spawn((
+            ZombieEntityBundle {
+                layer: EntityLayerId(layer),
+                ..Default::default()
+            },
+            Glowing::default().with_viewer_color(client, TeamColor::Red),
+        ))
+        .id();
+
+    app.update();
+
+    assert!(app.world.get::<Flags>(zombie).unwrap().glowing());
+
+    let frames = helper.collect_received();
+    frames.assert_count::<TeamS2c>(1);
+
+    // Nothing changed.
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<TeamS2c>(0);
+
+    // Back to white, which leaves the team.
+    app.world
+        .get_mut::<Glowing>(zombie)
+        .unwrap()
+        .remove_viewer_color(client);
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<TeamS2c>(1);
+
+    app.world.entity_mut(zombie).remove::<Glowing>();
+
+    app.update();
+
+    assert!(!app.world.get::<Flags>(zombie).unwrap().glowing());
+}
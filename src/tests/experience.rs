@@ -0,0 +1,79 @@
+use crate::experience::{ExperienceLevel, ExperiencePoints, ExperienceQuery, MAX_LEVEL};
+use crate::protocol::packets::play::ExperienceBarUpdateS2c;
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn experience_bar_synced_on_change() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    app.world
+        .query::<ExperienceQuery>()
+        .get_mut(&mut app.world, client)
+        .unwrap()
+        .add_xp(20);
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<ExperienceBarUpdateS2c>(1);
+
+    let pkt = frames.first::<ExperienceBarUpdateS2c>();
+    assert_eq!(pkt.level.0, 2);
+    assert_eq!(pkt.total_xp.0, 20);
+
+    assert_eq!(
+        *app.world.get::<ExperienceLevel>(client).unwrap(),
+        ExperienceLevel(2)
+    );
+    assert_eq!(
+        *app.world.get::<ExperiencePoints>(client).unwrap(),
+        ExperiencePoints(20)
+    );
+}
+
+#[test]
+fn experience_bar_clamps_large_values() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let mut query = app.world.query::<ExperienceQuery>();
+    let mut xp = query.get_mut(&mut app.world, client).unwrap();
+    xp.add_xp(i32::MAX);
+    xp.add_xp(i32::MAX);
+    xp.add_xp(i32::MAX);
+
+    app.update();
+
+    // Points above `i32::MAX` don't wrap around to negative values.
+    let frames = helper.collect_received();
+    let pkt = frames.first::<ExperienceBarUpdateS2c>();
+    assert_eq!(pkt.level.0, MAX_LEVEL as i32);
+    assert_eq!(pkt.total_xp.0, i32::MAX);
+
+    // Neither do levels set directly.
+    app.world
+        .entity_mut(client)
+        .insert((ExperienceLevel(u32::MAX), ExperiencePoints(u32::MAX)));
+
+    app.update();
+
+    let frames = helper.collect_received();
+    let pkt = frames.first::<ExperienceBarUpdateS2c>();
+    assert_eq!(pkt.level.0, i32::MAX);
+    assert_eq!(pkt.total_xp.0, i32::MAX);
+}
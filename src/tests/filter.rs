@@ -0,0 +1,98 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemState;
+
+use crate::entity::item_frame::ItemFrameEntityBundle;
+use crate::entity::zombie::ZombieEntityBundle;
+use crate::entity::{EntityLayerId, Position};
+use crate::filter::{InLayer, LayerEntities, LivingEntity, Viewers, WithinDistance};
+use crate::testing::ScenarioSingleClient;
+use crate::{ChunkPos, EntityLayer, Server};
+
+#[test]
+fn layer_entities_and_viewers() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let other_layer = app
+        .world
+        .spawn(EntityLayer::new(app.world.resource::<Server>()))
+        .id();
+
+    let mut spawn_zombie = |layer, pos: [f64; 3]| {
+        app.world
+            .spawn(ZombieEntityBundle {
+                layer: EntityLayerId(layer),
+                position: Position::new(pos),
+                ..Default::default()
+            })
+            .id()
+    };
+
+    let near = spawn_zombie(layer, [3.0, 0.0, 4.0]);
+    let far = spawn_zombie(layer, [100.0, 0.0, 0.0]);
+    let elsewhere = spawn_zombie(other_layer, [1.0, 0.0, 0.0]);
+
+    let frame = app
+        .world
+        .spawn(ItemFrameEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position::new([1.0, 0.0, 1.0]),
+            ..Default::default()
+        })
+        .id();
+
+    // Index the entities by chunk.
+    app.update();
+
+    let mut state = SystemState::<(LayerEntities<With<LivingEntity>>, LayerEntities, Viewers)>::new(
+        &mut app.world,
+    );
+    let (living, all, viewers) = state.get(&app.world);
+
+    let mut found: Vec<_> = living
+        .iter((InLayer(layer), WithinDistance::new([0.0, 0.0, 0.0], 10.0)))
+        .collect();
+    found.sort();
+
+    // The client is a living entity at the origin as well.
+    let mut expected = vec![client, near];
+    expected.sort();
+    assert_eq!(found, expected);
+
+    assert!(all
+        .iter((InLayer(layer), WithinDistance::new([0.0, 0.0, 0.0], 10.0)))
+        .any(|e| e == frame));
+
+    let mut in_layer: Vec<_> = living.iter(InLayer(layer)).collect();
+    in_layer.sort();
+    let mut expected = vec![client, near, far];
+    expected.sort();
+    assert_eq!(in_layer, expected);
+
+    assert_eq!(
+        living.closest([0.0, 0.0, 0.0], InLayer(other_layer)),
+        Some((elsewhere, 1.0))
+    );
+
+    assert_eq!(
+        viewers
+            .of_chunk(layer, ChunkPos::new(0, 0))
+            .collect::<Vec<_>>(),
+        [client]
+    );
+    assert_eq!(
+        viewers.of_chunk(other_layer, ChunkPos::new(0, 0)).count(),
+        0
+    );
+    assert_eq!(viewers.of_chunk(layer, ChunkPos::new(100, 0)).count(), 0);
+    assert_eq!(
+        viewers
+            .of_entities_at(layer, ChunkPos::new(0, 0))
+            .collect::<Vec<_>>(),
+        [client]
+    );
+}
@@ -1,17 +1,26 @@
 use bevy_app::prelude::*;
+use bevy_ecs::event::Events;
 use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
 
+use crate::block::{PropName, PropValue};
+use crate::inventory::block_inventory::{
+    block_entity_from_item, item_from_block, BlockInventories, BlockInventory, OpenBlockInventory,
+};
+use crate::inventory::book::BookEditEvent;
+use crate::inventory::contents::ContainerContents;
 use crate::inventory::{
     convert_to_player_slot_id, ClickMode, ClientInventoryState, CursorItem, DropItemStackEvent,
     HeldItem, Inventory, InventoryKind, OpenInventory, SlotChange,
 };
+use crate::layer::chunk::{Block, UnloadedChunk};
 use crate::protocol::packets::play::{
-    ClickSlotC2s, CloseScreenS2c, CreativeInventoryActionC2s, InventoryS2c, OpenScreenS2c,
-    ScreenHandlerSlotUpdateS2c, UpdateSelectedSlotC2s,
+    BlockEventS2c, BookUpdateC2s, ClickSlotC2s, CloseScreenS2c, CreativeInventoryActionC2s,
+    InventoryS2c, OpenScreenS2c, PlaySoundS2c, ScreenHandlerSlotUpdateS2c, UpdateSelectedSlotC2s,
 };
-use crate::protocol::VarInt;
-use crate::testing::ScenarioSingleClient;
-use crate::{GameMode, ItemKind, ItemStack};
+use crate::protocol::{Bounded, VarInt};
+use crate::testing::{create_mock_client, ScenarioSingleClient};
+use crate::{BlockPos, BlockState, ChunkLayer, GameMode, ItemKind, ItemStack};
 
 #[test]
 fn test_should_open_inventory() {
@@ -1153,3 +1162,230 @@ fn dragging_items() {
         );
     }
 }
+
+#[test]
+fn test_block_inventory_shared_between_viewers() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let (mut bundle, _) = create_mock_client("other");
+    bundle.visible_chunk_layer.0 = layer;
+    let other_client = app.world.spawn(bundle).id();
+
+    // A double chest facing north.
+    let chest = BlockState::CHEST.set(PropName::Facing, PropValue::North);
+    let left = BlockPos::new(0, 64, 0);
+    let right = BlockPos::new(1, 64, 0);
+
+    let mut chunk_layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block(left, chest.set(PropName::Type, PropValue::Left));
+    chunk_layer.set_block(right, chest.set(PropName::Type, PropValue::Right));
+
+    app.update();
+    helper.clear_received();
+
+    OpenBlockInventory {
+        client,
+        position: left,
+    }
+    .apply(&mut app.world);
+    OpenBlockInventory {
+        client: other_client,
+        position: right,
+    }
+    .apply(&mut app.world);
+
+    app.update();
+
+    let inventory = app.world.get::<OpenInventory>(client).unwrap().entity;
+    assert_eq!(
+        app.world.get::<OpenInventory>(other_client).unwrap().entity,
+        inventory
+    );
+
+    let inventories = app.world.resource::<BlockInventories>();
+    assert_eq!(inventories.get(layer, left), Some(inventory));
+    assert_eq!(inventories.get(layer, right), Some(inventory));
+
+    assert_eq!(
+        app.world.get::<Inventory>(inventory).unwrap().kind(),
+        InventoryKind::Generic9x6
+    );
+
+    let block_inventory = app.world.get::<BlockInventory>(inventory).unwrap();
+    assert_eq!(block_inventory.positions(), [right, left]);
+    assert_eq!(block_inventory.viewers().len(), 2);
+
+    // Both halves open their lids, and the sound is played once.
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<OpenScreenS2c>(1);
+    sent_packets.assert_count::<BlockEventS2c>(2);
+    sent_packets.assert_count::<PlaySoundS2c>(1);
+    assert_eq!(sent_packets.first::<BlockEventS2c>().action_parameter, 2);
+
+    app.world.entity_mut(other_client).remove::<OpenInventory>();
+    app.update();
+
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<BlockEventS2c>(2);
+    sent_packets.assert_count::<PlaySoundS2c>(0);
+    assert_eq!(sent_packets.first::<BlockEventS2c>().action_parameter, 1);
+
+    // Breaking the chest closes the inventory.
+    app.world
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .set_block(left, BlockState::AIR);
+
+    app.update();
+
+    assert!(app.world.get_entity(inventory).is_none());
+    assert_eq!(
+        app.world.resource::<BlockInventories>().get(layer, right),
+        None
+    );
+
+    app.update();
+
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<CloseScreenS2c>(1);
+    assert!(app.world.get::<OpenInventory>(client).is_none());
+}
+
+#[test]
+fn test_shulker_box_contents_round_trip() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let mut shulker_box = ItemStack::new(ItemKind::BlueShulkerBox, 1, None);
+    let mut contents = ContainerContents::new();
+    contents.set(4, ItemStack::new(ItemKind::Diamond, 10, None));
+    contents.write_to_item(&mut shulker_box);
+
+    // Place the item, keeping its contents in the block entity.
+    let pos = BlockPos::new(0, 64, 0);
+
+    let mut chunk_layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block(
+        pos,
+        Block::new(
+            BlockState::BLUE_SHULKER_BOX,
+            block_entity_from_item(&shulker_box),
+        ),
+    );
+
+    app.update();
+
+    OpenBlockInventory {
+        client,
+        position: pos,
+    }
+    .apply(&mut app.world);
+
+    app.update();
+
+    let inventory = app.world.get::<OpenInventory>(client).unwrap().entity;
+    let mut inventory = app.world.get_mut::<Inventory>(inventory).unwrap();
+
+    assert_eq!(
+        inventory.slot(4),
+        &ItemStack::new(ItemKind::Diamond, 10, None)
+    );
+
+    inventory.set_slot(20, ItemStack::new(ItemKind::Emerald, 2, None));
+
+    app.update();
+
+    // Breaking the block keeps the changed contents in the item.
+    let chunk_layer = app.world.get::<ChunkLayer>(layer).unwrap();
+    let item = item_from_block(chunk_layer, pos).unwrap();
+    assert_eq!(item.item, ItemKind::BlueShulkerBox);
+
+    let contents = ContainerContents::from_item(&item).unwrap();
+    assert_eq!(contents.len(), 2);
+    assert_eq!(
+        contents.get(20),
+        Some(&ItemStack::new(ItemKind::Emerald, 2, None))
+    );
+}
+
+#[test]
+fn test_should_write_and_sign_book() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::WritableBook, 1, None));
+
+    helper.send(&BookUpdateC2s {
+        slot: VarInt(0),
+        entries: Bounded(vec![Bounded("first page")]),
+        title: None,
+    });
+
+    app.update();
+
+    let stack = app.world.get::<Inventory>(client).unwrap().slot(36).clone();
+    assert_eq!(stack.item, ItemKind::WritableBook);
+    assert!(stack.nbt.unwrap().get("pages").is_some());
+
+    // Titles must not be empty.
+    helper.send(&BookUpdateC2s {
+        slot: VarInt(0),
+        entries: Bounded(vec![Bounded("first page")]),
+        title: Some(Bounded(" ")),
+    });
+
+    app.update();
+
+    assert!(app
+        .world
+        .resource::<Events<BookEditEvent>>()
+        .iter_current_update_events()
+        .next()
+        .is_none());
+
+    helper.send(&BookUpdateC2s {
+        slot: VarInt(0),
+        entries: Bounded(vec![Bounded("first page")]),
+        title: Some(Bounded("Title")),
+    });
+
+    app.update();
+
+    let stack = app.world.get::<Inventory>(client).unwrap().slot(36).clone();
+    assert_eq!(stack.item, ItemKind::WrittenBook);
+
+    let nbt = stack.nbt.unwrap();
+    assert_eq!(nbt.get("title"), Some(&"Title".into()));
+    assert_eq!(nbt.get("author"), Some(&"test".into()));
+
+    let events = app.world.resource::<Events<BookEditEvent>>();
+    assert_eq!(
+        events.iter_current_update_events().last(),
+        Some(&BookEditEvent {
+            client,
+            slot: 36,
+            pages: vec!["first page".into()],
+            title: Some("Title".into()),
+        })
+    );
+}
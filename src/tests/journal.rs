@@ -0,0 +1,126 @@
+use bevy_ecs::system::SystemState;
+use valence_journal::{Actor, BlockJournal, JournalQuery, JournaledEdits, RollbackResult};
+use valence_server::uuid::Uuid;
+
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockState, ChunkPos};
+
+#[test]
+fn journal_and_rollback() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.world
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .insert_chunk(ChunkPos::new(0, 0), UnloadedChunk::with_height(64));
+
+    let griefer = Actor::Player(Uuid::from_u128(1));
+    let builder = Actor::Player(Uuid::from_u128(2));
+
+    let mut state = SystemState::<JournaledEdits>::new(&mut app.world);
+    let mut edits = state.get_mut(&mut app.world);
+
+    edits.set_block(layer, [0, 0, 0], BlockState::STONE, &builder);
+    edits.set_block(layer, [1, 0, 0], BlockState::STONE, &builder);
+
+    edits.set_block(layer, [0, 0, 0], BlockState::TNT, &griefer);
+    edits.set_block(layer, [1, 0, 0], BlockState::TNT, &griefer);
+    edits.set_block(layer, [1, 0, 0], BlockState::LAVA, &griefer);
+    edits.set_block(layer, [2, 0, 0], BlockState::LAVA, &griefer);
+
+    // Someone already cleaned up this block, so it must not be rolled back.
+    edits.set_block(layer, [2, 0, 0], BlockState::DIRT, &builder);
+
+    let result = edits.rollback(&JournalQuery::new().actor(griefer.clone()), &Actor::Server);
+
+    assert_eq!(
+        result,
+        RollbackResult {
+            restored: 2,
+            skipped: 1
+        }
+    );
+
+    state.apply(&mut app.world);
+
+    let chunk_layer = app.world.get::<ChunkLayer>(layer).unwrap();
+    assert_eq!(
+        chunk_layer.block([0, 0, 0]).unwrap().state,
+        BlockState::STONE
+    );
+    assert_eq!(
+        chunk_layer.block([1, 0, 0]).unwrap().state,
+        BlockState::STONE
+    );
+    assert_eq!(
+        chunk_layer.block([2, 0, 0]).unwrap().state,
+        BlockState::DIRT
+    );
+
+    let journal = app.world.resource::<BlockJournal>();
+    assert_eq!(journal.query(&JournalQuery::new().actor(griefer)).len(), 4);
+    assert_eq!(
+        journal
+            .query(&JournalQuery::new().actor(Actor::Server))
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn rollback_task() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.world
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .insert_chunk(ChunkPos::new(0, 0), UnloadedChunk::with_height(64));
+
+    let griefer = Actor::Player(Uuid::from_u128(1));
+
+    let mut state = SystemState::<JournaledEdits>::new(&mut app.world);
+    let mut edits = state.get_mut(&mut app.world);
+
+    for x in 0..10 {
+        edits.set_block(layer, [x, 0, 0], BlockState::TNT, &griefer);
+    }
+
+    state.apply(&mut app.world);
+
+    let task = app
+        .world
+        .resource::<BlockJournal>()
+        .rollback_task(&JournalQuery::new().actor(griefer), Actor::Server);
+
+    let task = app.world.spawn(task).id();
+
+    while app.world.get_entity(task).is_some() {
+        app.update();
+    }
+
+    let chunk_layer = app.world.get::<ChunkLayer>(layer).unwrap();
+
+    for x in 0..10 {
+        assert_eq!(chunk_layer.block([x, 0, 0]).unwrap().state, BlockState::AIR);
+    }
+
+    let journal = app.world.resource::<BlockJournal>();
+    assert_eq!(
+        journal
+            .query(&JournalQuery::new().actor(Actor::Server))
+            .len(),
+        10
+    );
+}
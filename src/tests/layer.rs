@@ -1,19 +1,29 @@
 use std::collections::BTreeSet;
 
+use bevy_ecs::entity::Entity;
+use bevy_ecs::system::Command;
 use bevy_ecs::world::EntityWorldMut;
 
-use crate::client::{ViewDistance, VisibleEntityLayers};
+use crate::client::{ViewDistance, VisibleChunkLayer, VisibleEntityLayers};
 use crate::entity::cow::CowEntityBundle;
-use crate::entity::{EntityLayerId, Position};
-use crate::layer::chunk::UnloadedChunk;
+use crate::entity::{EntityId, EntityLayerId, Position};
+use crate::layer::chunk::region::{self, Region};
+use crate::layer::chunk::{
+    Block, BlockKindSet, Chunk, PartialChunks, UnloadedChunk, VerticalStreaming,
+};
+use crate::layer::clone::CloneLayer;
 use crate::layer::{ChunkLayer, EntityLayer};
+use crate::nbt::{compound, List, Value};
 use crate::protocol::packets::play::{
-    BlockEntityUpdateS2c, ChunkDataS2c, ChunkDeltaUpdateS2c, EntitiesDestroyS2c, EntitySpawnS2c,
-    MoveRelativeS2c, UnloadChunkS2c,
+    BlockEntityUpdateS2c, BlockUpdateS2c, ChunkDataS2c, ChunkDeltaUpdateS2c, EntitiesDestroyS2c,
+    EntitySpawnS2c, GameJoinS2c, MoveRelativeS2c, UnloadChunkS2c,
 };
 use crate::protocol::Packet;
+use crate::registry::dimension_type::{DimensionEffects, DimensionType, DimensionTypeError};
+use crate::registry::tags::TagsRegistry;
+use crate::registry::{BiomeRegistry, DimensionTypeRegistry};
 use crate::testing::ScenarioSingleClient;
-use crate::{BlockState, ChunkView, Despawned, Server};
+use crate::{ident, BlockPos, BlockState, ChunkView, Despawned, Server};
 
 #[test]
 fn block_create_destroy() {
@@ -214,6 +224,83 @@ fn chunk_viewer_count() {
     helper.collect_received().assert_count::<ChunkDataS2c>(3);
 }
 
+#[test]
+fn vertical_streaming() {
+    let ScenarioSingleClient {
+        mut app,
+        client: client_ent,
+        mut helper,
+        layer: layer_ent,
+    } = ScenarioSingleClient::new();
+
+    let mut client = app.world.entity_mut(client_ent);
+
+    client.get_mut::<Position>().unwrap().set([8.0, 64.0, 8.0]);
+    client.get_mut::<ViewDistance>().unwrap().set(2);
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.set_vertical_streaming(Some(
+        VerticalStreaming::new()
+            .with_radius(2)
+            .with_upgrades_per_tick(2),
+    ));
+
+    for pos in ChunkView::new([0, 0].into(), 2).iter() {
+        layer.insert_chunk(pos, UnloadedChunk::new());
+    }
+
+    app.update(); // Tick.
+
+    // All chunks are sent in part since the world is taller than five sections.
+    let partial = app.world.get::<PartialChunks>(client_ent).unwrap().len();
+
+    assert!(partial > 2);
+    helper
+        .collect_received()
+        .assert_count::<ChunkDataS2c>(partial);
+
+    // Moving within the sections sent in full doesn't send anything.
+    let mut client = app.world.entity_mut(client_ent);
+    client.get_mut::<Position>().unwrap().set([8.0, 70.0, 8.0]);
+
+    app.update(); // Tick.
+
+    helper.collect_received().assert_count::<ChunkDataS2c>(0);
+
+    // Moving far up sends the chunks again in full, a few per tick.
+    let mut client = app.world.entity_mut(client_ent);
+    client.get_mut::<Position>().unwrap().set([8.0, 250.0, 8.0]);
+
+    app.update(); // Tick.
+
+    helper.collect_received().assert_count::<ChunkDataS2c>(2);
+    assert_eq!(
+        app.world.get::<PartialChunks>(client_ent).unwrap().len(),
+        partial - 2
+    );
+    assert!(!app
+        .world
+        .get::<PartialChunks>(client_ent)
+        .unwrap()
+        .contains([0, 0].into()));
+
+    // Disabling vertical streaming sends the remaining chunks in full.
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+    layer.set_vertical_streaming(None);
+
+    app.update(); // Tick.
+
+    helper
+        .collect_received()
+        .assert_count::<ChunkDataS2c>(partial - 2);
+    assert!(app
+        .world
+        .get::<PartialChunks>(client_ent)
+        .unwrap()
+        .is_empty());
+}
+
 #[test]
 fn entity_layer_switching() {
     let ScenarioSingleClient {
@@ -465,3 +552,367 @@ fn chunk_entity_spawn_despawn() {
         recvd.assert_count::<EntitiesDestroyS2c>(0);
     }
 }
+
+#[test]
+fn region_operations() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        mut helper,
+        layer: layer_ent,
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([1, 0], UnloadedChunk::new());
+
+    let min_y = layer.min_y();
+
+    // A section which is not filled with a single block.
+    layer.set_block([3, min_y + 20, 3], BlockState::DIRT);
+
+    app.update();
+    helper.clear_received();
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    // Two full sections and part of a third in each chunk. The part outside
+    // of the loaded chunks is skipped.
+    let region = Region::new([0, min_y, 0], [40, min_y + 35, 15]);
+    assert_eq!(
+        region::fill(&mut layer, region, BlockState::STONE),
+        32 * 36 * 16
+    );
+    assert_eq!(
+        layer.block([3, min_y + 20, 3]).unwrap().state,
+        BlockState::STONE
+    );
+    assert_eq!(
+        layer.block([31, min_y + 35, 0]).unwrap().state,
+        BlockState::STONE
+    );
+    assert_eq!(
+        layer.block([0, min_y + 36, 0]).unwrap().state,
+        BlockState::AIR
+    );
+
+    app.update();
+
+    // One packet per changed section.
+    helper
+        .collect_received()
+        .assert_count::<ChunkDeltaUpdateS2c>(6);
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    let small = Region::new([0, min_y + 30, 0], [4, min_y + 40, 4]);
+    assert_eq!(
+        region::replace(&mut layer, small, BlockState::STONE, BlockState::GLASS),
+        5 * 6 * 5
+    );
+
+    // The walls of a 5x3x5 box, without the inside.
+    let room = Region::new([10, min_y + 50, 0], [14, min_y + 52, 4]);
+    assert_eq!(
+        region::walls(&mut layer, room, BlockState::OAK_PLANKS),
+        3 * 16
+    );
+    assert_eq!(
+        layer.block([12, min_y + 51, 2]).unwrap().state,
+        BlockState::AIR
+    );
+
+    assert_eq!(
+        region::hollow(&mut layer, room, BlockState::GLASS),
+        room.volume() as usize
+    );
+    assert_eq!(
+        layer.block([12, min_y + 50, 2]).unwrap().state,
+        BlockState::GLASS
+    );
+    assert_eq!(
+        layer.block([14, min_y + 51, 0]).unwrap().state,
+        BlockState::GLASS
+    );
+
+    // Copying keeps block entities, and pasting can skip air.
+    layer.set_block(
+        [12, min_y + 51, 2],
+        Block::new(BlockState::CHEST, Some(compound! { "Lock" => "key" })),
+    );
+
+    let clipboard = region::copy(&layer, room);
+    assert_eq!(clipboard.size(), [5, 3, 5]);
+    assert_eq!(clipboard.block([2, 1, 2]).unwrap().state, BlockState::CHEST);
+
+    layer.set_block([21, min_y + 51, 1], BlockState::DIRT);
+
+    let origin = BlockPos::new(20, min_y + 50, 0);
+    assert_eq!(region::paste(&mut layer, &clipboard, origin, true), 67);
+
+    let chest = layer.block([22, min_y + 51, 2]).unwrap();
+    assert_eq!(chest.state, BlockState::CHEST);
+    assert_eq!(chest.nbt, Some(&compound! { "Lock" => "key" }));
+    assert_eq!(
+        layer.block([21, min_y + 51, 1]).unwrap().state,
+        BlockState::DIRT
+    );
+
+    region::paste(&mut layer, &clipboard, origin, false);
+    assert_eq!(
+        layer.block([21, min_y + 51, 1]).unwrap().state,
+        BlockState::AIR
+    );
+}
+
+#[test]
+fn region_block_search() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer: layer_ent,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    let diamond_ores =
+        BlockKindSet::from_tag(app.world.resource::<TagsRegistry>(), "diamond_ores").unwrap();
+    assert_eq!(diamond_ores.len(), 2);
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([1, 0], UnloadedChunk::new());
+
+    let min_y = layer.min_y();
+
+    region::fill(
+        &mut layer,
+        Region::new([0, min_y, 0], [31, min_y + 31, 15]),
+        BlockState::STONE,
+    );
+
+    let ores = [
+        BlockPos::new(2, min_y + 3, 4),
+        BlockPos::new(20, min_y + 3, 4),
+        BlockPos::new(20, min_y + 40, 4),
+    ];
+
+    layer.set_block(ores[0], BlockState::DIAMOND_ORE);
+    layer.set_block(ores[1], BlockState::DEEPSLATE_DIAMOND_ORE);
+    layer.set_block(ores[2], BlockState::DIAMOND_ORE);
+
+    let everything = Region::new([-100, min_y, -100], [100, min_y + 100, 100]);
+
+    assert_eq!(region::find_blocks(&layer, everything, &diamond_ores), ores);
+    assert_eq!(
+        region::count_blocks(&layer, everything, BlockState::DIAMOND_ORE),
+        2
+    );
+    assert_eq!(
+        region::count_blocks(&layer, everything, BlockState::STONE),
+        32 * 32 * 16 - 2
+    );
+
+    // Only the part of the region inside of the chunks is searched.
+    let part = Region::new([0, min_y, 0], [10, min_y + 10, 10]);
+    assert_eq!(region::find_blocks(&layer, part, &diamond_ores), [ores[0]]);
+    assert_eq!(
+        region::count_blocks(&layer, part, BlockState::STONE),
+        11 * 11 * 11 - 1
+    );
+
+    let chunk = layer.chunk([1, 0]).unwrap();
+    assert_eq!(chunk.count_blocks(&diamond_ores), 2);
+    assert_eq!(
+        chunk.block_histogram()[&BlockState::STONE],
+        16 * 32 * 16 - 1
+    );
+}
+
+#[test]
+fn empty_chunk_placeholders() {
+    let ScenarioSingleClient {
+        mut app,
+        client: client_ent,
+        mut helper,
+        layer: layer_ent,
+    } = ScenarioSingleClient::new();
+
+    let mut client = app.world.entity_mut(client_ent);
+
+    client.get_mut::<Position>().unwrap().set([8.0, 64.0, 8.0]);
+    client.get_mut::<ViewDistance>().unwrap().set(2);
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+
+    // Pad the chunk with placeholders.
+    for x in -1..=1 {
+        for z in -1..=1 {
+            if layer.chunk([x, z]).is_none() {
+                layer.insert_empty_chunk([x, z]);
+            }
+        }
+    }
+
+    assert!(!layer.chunk([0, 0]).unwrap().is_empty_placeholder());
+    assert!(layer.chunk([1, 0]).unwrap().is_empty_placeholder());
+
+    app.update();
+
+    // Placeholders are sent like regular chunks.
+    helper.collect_received().assert_count::<ChunkDataS2c>(9);
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    assert_eq!(layer.block([16, 10, 0]).unwrap().state, BlockState::AIR);
+
+    layer.set_block([16, 10, 0], BlockState::STONE);
+
+    let chunk = layer.chunk([1, 0]).unwrap();
+    assert!(!chunk.is_empty_placeholder());
+    assert_eq!(chunk.count_blocks(BlockState::STONE), 1);
+
+    app.update();
+
+    helper.collect_received().assert_count::<BlockUpdateS2c>(1);
+}
+
+#[test]
+fn clone_layer_with_entities() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        helper: _,
+        layer: template,
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(template).unwrap();
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    let min_y = layer.min_y();
+    layer.set_block([1, min_y, 1], BlockState::STONE);
+
+    let cow = app
+        .world
+        .spawn(CowEntityBundle {
+            position: Position::new([5.0, 70.0, 5.0]),
+            layer: EntityLayerId(template),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+
+    let copy = app.world.spawn_empty().id();
+    CloneLayer::new(template, copy)
+        .with_entities()
+        .apply(&mut app.world);
+
+    app.update();
+
+    // The chunks are copied, and modifying the copy leaves the template alone.
+    let mut layer = app.world.get_mut::<ChunkLayer>(copy).unwrap();
+    assert_eq!(layer.min_y(), min_y);
+    assert_eq!(layer.block([1, min_y, 1]).unwrap().state, BlockState::STONE);
+    layer.set_block([1, min_y, 1], BlockState::DIRT);
+
+    let layer = app.world.get::<ChunkLayer>(template).unwrap();
+    assert_eq!(layer.block([1, min_y, 1]).unwrap().state, BlockState::STONE);
+
+    assert!(app.world.get::<EntityLayer>(copy).is_some());
+
+    // The cow is copied with a new ID, but the client is not.
+    let mut entities = app
+        .world
+        .query::<(Entity, &EntityLayerId, &EntityId, &Position)>();
+    let copies: Vec<_> = entities
+        .iter(&app.world)
+        .filter(|(_, layer, _, _)| layer.0 == copy)
+        .map(|(entity, _, id, pos)| (entity, *id, pos.0))
+        .collect();
+
+    assert_eq!(copies.len(), 1);
+
+    let (copied_cow, id, pos) = copies[0];
+    assert_ne!(copied_cow, client);
+    assert_ne!(id, *app.world.get::<EntityId>(cow).unwrap());
+    assert_eq!(pos, [5.0, 70.0, 5.0].into());
+}
+
+#[test]
+fn custom_dimension_type() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.world
+        .resource_mut::<DimensionTypeRegistry>()
+        .register(
+            ident!("test:tall"),
+            DimensionType {
+                min_y: -256,
+                height: 768,
+                logical_height: 768,
+                effects: DimensionEffects::TheEnd,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let dimensions = app.world.resource::<DimensionTypeRegistry>();
+    let biomes = app.world.resource::<BiomeRegistry>();
+    let server = app.world.resource::<Server>();
+
+    assert_eq!(
+        ChunkLayer::try_new(ident!("test:missing"), dimensions, biomes, server).unwrap_err(),
+        DimensionTypeError::NotRegistered(ident!("test:missing").into())
+    );
+
+    let chunk_layer = ChunkLayer::try_new(ident!("test:tall"), dimensions, biomes, server).unwrap();
+
+    assert_eq!(chunk_layer.min_y(), -256);
+    assert_eq!(chunk_layer.height(), 768);
+
+    let entity_layer = EntityLayer::new(server);
+    let layer = app.world.spawn((chunk_layer, entity_layer)).id();
+
+    app.world.get_mut::<VisibleChunkLayer>(client).unwrap().0 = layer;
+
+    app.update();
+
+    // The client joins with the custom dimension type in the registry codec.
+    let frames = helper.collect_received();
+    let join = frames.first::<GameJoinS2c>();
+
+    assert_eq!(join.dimension_type_name, ident!("test:tall"));
+
+    let Some(Value::Compound(registry)) = join.registry_codec.get("minecraft:dimension_type")
+    else {
+        panic!("missing dimension type registry");
+    };
+
+    let Some(Value::List(List::Compound(entries))) = registry.get("value") else {
+        panic!("missing dimension types");
+    };
+
+    let entry = entries
+        .iter()
+        .find(|entry| entry.get("name") == Some(&Value::String("test:tall".into())))
+        .expect("missing custom dimension type");
+
+    let Some(Value::Compound(element)) = entry.get("element") else {
+        panic!("missing dimension type element");
+    };
+
+    assert_eq!(element.get("height"), Some(&Value::Int(768)));
+    assert_eq!(element.get("min_y"), Some(&Value::Int(-256)));
+    assert_eq!(
+        element.get("effects"),
+        Some(&Value::String("minecraft:the_end".into()))
+    );
+}
@@ -0,0 +1,86 @@
+use crate::inventory::Inventory;
+use crate::map::{BannerColor, KnownMaps, MapBundle, MapCanvas, MapColor, MapId, MapMarker};
+use crate::protocol::packets::play::MapUpdateS2c;
+use crate::testing::ScenarioSingleClient;
+use crate::ItemStack;
+
+#[test]
+fn held_maps_are_sent_and_updated() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    // Let the client join first.
+    app.update();
+
+    let red = MapColor::nearest([255, 0, 0]);
+    let mut canvas = MapCanvas::new();
+    canvas.fill(red);
+    canvas.add_marker(MapMarker::banner(BannerColor::Blue, [10, -20]).with_name("Home"));
+
+    let map = app
+        .world
+        .spawn(MapBundle {
+            id: MapId(7),
+            canvas,
+        })
+        .id();
+
+    helper.clear_received();
+    app.update();
+
+    // The client doesn't hold the map yet.
+    helper.collect_received().assert_count::<MapUpdateS2c>(0);
+
+    app.world
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, MapId(7).item_stack());
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<MapUpdateS2c>(1);
+
+    let pkt = frames.first::<MapUpdateS2c>();
+    assert_eq!(pkt.map_id.0, 7);
+    assert_eq!(pkt.icons.unwrap().len(), 1);
+
+    let data = pkt.data.unwrap();
+    assert_eq!((data.columns, data.rows, data.position), (128, 128, [0, 0]));
+    assert!(data.data.iter().all(|&c| c == red.0));
+
+    assert!(app
+        .world
+        .get::<KnownMaps>(client)
+        .unwrap()
+        .contains(MapId(7)));
+
+    // Only the changed pixels are sent, even after the map is put away.
+    app.world
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::EMPTY);
+
+    let black = MapColor::nearest([0, 0, 0]);
+    let mut canvas = app.world.get_mut::<MapCanvas>(map).unwrap();
+    canvas.set_pixel(3, 4, black);
+    canvas.set_pixel(5, 4, black);
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<MapUpdateS2c>(1);
+
+    let pkt = frames.first::<MapUpdateS2c>();
+    assert_eq!(pkt.icons, None);
+
+    let data = pkt.data.unwrap();
+    assert_eq!((data.columns, data.rows, data.position), (3, 1, [3, 4]));
+    assert_eq!(data.data, [black.0, red.0, black.0]);
+
+    // Nothing is sent while the map doesn't change.
+    app.update();
+    helper.collect_received().assert_count::<MapUpdateS2c>(0);
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+use crate::client::Client;
+use crate::entity::living::Health;
+use crate::entity::player::Food;
+use crate::entity::Position;
+use crate::inventory::{HeldItem, Inventory};
+use crate::math::DVec3;
+use crate::nbt::{compound, Value};
+use crate::player_data::{
+    CustomPlayerData, FlatFileProvider, PlayerData, PlayerDataLoaded, PlayerDataPlugin,
+    PlayerDataSettings,
+};
+use crate::testing::ScenarioSingleClient;
+use crate::{GameMode, ItemKind, ItemStack, UniqueId};
+
+#[test]
+fn player_data_is_loaded_on_join_and_saved_on_leave() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        helper: _,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    let dir = env::temp_dir().join(format!("valence_player_data_{}", std::process::id()));
+    let provider = FlatFileProvider::new(&dir);
+    let uuid = app.world.get::<UniqueId>(client).unwrap().0;
+
+    let mut saved = PlayerData {
+        position: DVec3::new(5.5, 70.0, -5.5),
+        game_mode: GameMode::Creative,
+        health: 10.0,
+        food: 8,
+        selected_slot: 2,
+        custom: compound! { "coins" => 5 },
+        ..Default::default()
+    };
+    saved
+        .inventory
+        .set(38, ItemStack::new(ItemKind::Diamond, 3, None));
+
+    provider.save_blocking(uuid, &saved).unwrap();
+
+    app.insert_resource(PlayerDataSettings {
+        provider: Arc::new(provider.clone()),
+        tokio_handle: None,
+    })
+    .add_plugins(PlayerDataPlugin);
+
+    // The data is loaded in the background.
+    let start = Instant::now();
+    while app.world.get::<PlayerDataLoaded>(client).is_none() {
+        assert!(start.elapsed() < Duration::from_secs(10), "data not loaded");
+        std::thread::sleep(Duration::from_millis(5));
+        app.update();
+    }
+
+    assert_eq!(app.world.get::<Position>(client).unwrap().0, saved.position);
+    assert_eq!(
+        *app.world.get::<GameMode>(client).unwrap(),
+        GameMode::Creative
+    );
+    assert_eq!(app.world.get::<Health>(client).unwrap().0, 10.0);
+    assert_eq!(app.world.get::<Food>(client).unwrap().0, 8);
+    assert_eq!(app.world.get::<HeldItem>(client).unwrap().hotbar_idx(), 2);
+    assert_eq!(
+        *app.world.get::<Inventory>(client).unwrap().slot(38),
+        ItemStack::new(ItemKind::Diamond, 3, None)
+    );
+    assert_eq!(
+        app.world
+            .get::<CustomPlayerData>(client)
+            .unwrap()
+            .get("coins"),
+        Some(&Value::Int(5))
+    );
+
+    // Change the player's state and disconnect.
+    app.world.get_mut::<Health>(client).unwrap().0 = 4.0;
+    app.world
+        .get_mut::<CustomPlayerData>(client)
+        .unwrap()
+        .insert("coins", 6);
+    app.world.entity_mut(client).remove::<Client>();
+
+    app.update();
+
+    let start = Instant::now();
+    let loaded = loop {
+        assert!(start.elapsed() < Duration::from_secs(10), "data not saved");
+
+        match provider.load_blocking(uuid).unwrap() {
+            Some(data) if data.health == 4.0 => break data,
+            _ => std::thread::sleep(Duration::from_millis(5)),
+        }
+    };
+
+    saved.health = 4.0;
+    saved.custom.insert("coins", 6);
+    assert_eq!(loaded, saved);
+
+    fs::remove_dir_all(dir).unwrap();
+}
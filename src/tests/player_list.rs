@@ -1,7 +1,17 @@
+use bevy_ecs::event::Events;
+use valence_player_list::npc::{LookAtPlayers, NpcBundle, NpcInteractEvent};
+use valence_player_list::{HiddenEntries, PlayerList, PlayerListEntryBundle};
+
+use crate::entity::{EntityId, HeadYaw, Look};
+use crate::interact_entity::EntityInteraction;
 use crate::layer::chunk::UnloadedChunk;
-use crate::protocol::packets::play::{PlayerListS2c, PlayerSpawnS2c};
+use crate::protocol::packets::play::{
+    PlayerInteractEntityC2s, PlayerListS2c, PlayerRemoveS2c, PlayerSpawnS2c,
+};
+use crate::protocol::VarInt;
 use crate::testing::{create_mock_client, ScenarioSingleClient};
-use crate::ChunkLayer;
+use crate::uuid::Uuid;
+use crate::{ChunkLayer, Despawned, UniqueId};
 
 #[test]
 fn player_list_arrives_before_player_spawn() {
@@ -64,3 +74,128 @@ fn player_list_arrives_before_player_spawn() {
         assert_eq!(pkt.entries.len(), 2);
     }
 }
+
+#[test]
+fn hidden_entries_are_removed_per_client() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    let uuid = Uuid::from_u128(42);
+
+    app.world.spawn(PlayerListEntryBundle {
+        uuid: UniqueId(uuid),
+        username: PlayerList::ordered_username(0),
+        ..Default::default()
+    });
+
+    app.update();
+    helper.clear_received();
+
+    let mut hidden = HiddenEntries::new();
+    hidden.hide(uuid);
+    app.world.entity_mut(client).insert(hidden);
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerRemoveS2c>(1);
+        assert_eq!(recvd.first::<PlayerRemoveS2c>().uuids.as_ref(), [uuid]);
+    }
+
+    app.world
+        .get_mut::<HiddenEntries>(client)
+        .unwrap()
+        .show(uuid);
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerListS2c>(1);
+        assert!(recvd.first::<PlayerListS2c>().actions.add_player());
+    }
+}
+
+#[test]
+fn npc_lifecycle() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: layer_ent,
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    for z in -5..5 {
+        for x in -5..5 {
+            layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    app.update();
+    helper.clear_received();
+
+    // The client is at the origin, north of the NPC.
+    let npc = app
+        .world
+        .spawn((
+            NpcBundle::new(layer_ent, "npc").with_position([0.0, 0.0, 5.0]),
+            LookAtPlayers::default(),
+        ))
+        .id();
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerListS2c>(1);
+        recvd.assert_count::<PlayerSpawnS2c>(1);
+        recvd.assert_order::<(PlayerListS2c, PlayerSpawnS2c)>();
+
+        let pkt = recvd.first::<PlayerListS2c>();
+        assert!(pkt.actions.add_player());
+        assert!(!pkt.entries[0].listed);
+    }
+
+    let look = *app.world.get::<Look>(npc).unwrap();
+    assert_eq!(look.yaw.rem_euclid(360.0).round(), 180.0);
+    assert_eq!(app.world.get::<HeadYaw>(npc).unwrap().0, look.yaw);
+
+    let entity_id = app.world.get::<EntityId>(npc).unwrap().get();
+
+    helper.send(&PlayerInteractEntityC2s {
+        entity_id: VarInt(entity_id),
+        interact: EntityInteraction::Attack,
+        sneaking: false,
+    });
+
+    app.update();
+
+    let events = app
+        .world
+        .resource::<Events<NpcInteractEvent>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].client, client);
+    assert_eq!(events[0].npc, npc);
+    assert_eq!(events[0].interact, EntityInteraction::Attack);
+
+    helper.clear_received();
+
+    app.world.entity_mut(npc).insert(Despawned);
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerRemoveS2c>(1);
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Instant;
+
+use bevy_ecs::event::Events;
+use bytes::Bytes;
+
+use crate::event_loop::PacketEvent;
+use crate::protocol::packets::play::ClientCommandC2s;
+use crate::protocol::Packet;
+use crate::protocol_error::{ProtocolErrorEvent, ProtocolErrorKind, ProtocolErrorStats};
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn malformed_packets_are_reported() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    // The entity ID VarInt is cut off.
+    helper.send_raw(ClientCommandC2s::ID, &[0xff]);
+    // A valid packet followed by another byte.
+    helper.send_raw(ClientCommandC2s::ID, &[0, 0, 0, 42]);
+
+    app.update();
+
+    let events: Vec<_> = app
+        .world
+        .resource_mut::<Events<ProtocolErrorEvent>>()
+        .drain()
+        .collect();
+
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| event.client == client
+        && event.error.packet_id == Some(ClientCommandC2s::ID)
+        && event.error.packet_name == Some(ClientCommandC2s::NAME)));
+    assert_eq!(events[0].error.kind, ProtocolErrorKind::Decode);
+    assert_eq!(&events[0].error.snippet[..], [0xff]);
+    assert_eq!(events[1].error.kind, ProtocolErrorKind::TrailingBytes);
+
+    let stats = app.world.resource::<ProtocolErrorStats>();
+    assert_eq!(stats.total(), 2);
+    assert_eq!(stats.count(ProtocolErrorKind::Decode), 1);
+    assert_eq!(stats.count_for_packet(ClientCommandC2s::ID), 2);
+}
+
+#[test]
+fn injected_packet_events_are_reported() {
+    let ScenarioSingleClient {
+        mut app, client, ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    // Packet events may also come from outside of the event loop, such as from
+    // a proxy or a bot.
+    app.world.send_event(PacketEvent {
+        client,
+        timestamp: Instant::now(),
+        id: ClientCommandC2s::ID,
+        data: Bytes::from_static(&[0xff]),
+    });
+
+    app.update();
+
+    let events: Vec<_> = app
+        .world
+        .resource_mut::<Events<ProtocolErrorEvent>>()
+        .drain()
+        .collect();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].client, client);
+    assert_eq!(events[0].error.kind, ProtocolErrorKind::Decode);
+}
@@ -0,0 +1,189 @@
+use bevy_ecs::event::Events;
+use valence_server::entity::hitbox::Hitbox;
+use valence_server::entity::zombie::ZombieEntityBundle;
+use valence_server::entity::{EntityId, EntityLayerId, Position};
+use valence_server::interact_entity::{
+    InteractEntitySettings, InteractRejection, RejectedInteractEvent,
+};
+use valence_server::layer::chunk::UnloadedChunk;
+use valence_server::math::DVec3;
+use valence_server::protocol::packets::play::PlayerInteractEntityC2s;
+use valence_server::protocol::VarInt;
+use valence_server::{BlockState, ChunkLayer, Direction, EntityLayer, Server};
+
+use crate::interact_entity::{EntityInteraction, InteractEntityEvent};
+use crate::testing::ScenarioSingleClient;
+
+fn scenario() -> ScenarioSingleClient {
+    let scenario = ScenarioSingleClient::new();
+    let mut app = scenario.app;
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(scenario.layer).unwrap();
+
+    for z in -1..1 {
+        for x in -1..1 {
+            layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    app.world
+        .get_mut::<Position>(scenario.client)
+        .unwrap()
+        .set([0.5, 0.0, 0.5]);
+
+    app.update();
+
+    ScenarioSingleClient { app, ..scenario }
+}
+
+#[test]
+fn block_raycast() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer,
+    } = scenario();
+
+    let mut chunk_layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+
+    // Blocks without collision shapes are passed through.
+    chunk_layer.set_block([0, 1, 3], BlockState::GRASS);
+    chunk_layer.set_block([0, 1, 5], BlockState::STONE);
+
+    let eyes = DVec3::new(0.5, 1.62, 0.5);
+
+    let hit = chunk_layer.raycast(eyes, DVec3::Z, 10.0).unwrap();
+
+    assert_eq!(hit.pos, [0, 1, 5].into());
+    assert_eq!(hit.state, BlockState::STONE);
+    assert_eq!(hit.face, Direction::North);
+    assert_eq!(hit.distance, 4.5);
+    assert_eq!(hit.point(eyes, DVec3::Z), DVec3::new(0.5, 1.62, 5.0));
+
+    assert_eq!(chunk_layer.raycast(eyes, DVec3::Z, 4.0), None);
+    assert_eq!(chunk_layer.raycast(eyes, DVec3::NEG_Z, 10.0), None);
+
+    // Slabs are hit on their collision shape, not the full block.
+    chunk_layer.set_block([3, 0, 0], BlockState::SMOOTH_STONE_SLAB);
+
+    let hit = chunk_layer
+        .raycast(DVec3::new(3.5, 2.0, 0.5), DVec3::NEG_Y, 10.0)
+        .unwrap();
+
+    assert_eq!(hit.pos, [3, 0, 0].into());
+    assert_eq!(hit.face, Direction::Up);
+    assert_eq!(hit.distance, 1.5);
+}
+
+#[test]
+fn interact_reach_and_line_of_sight() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = scenario();
+
+    let zombie = app
+        .world
+        .spawn(ZombieEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position::new([0.5, 0.0, 4.5]),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+
+    let entity_id = app.world.get::<EntityId>(zombie).unwrap().get();
+
+    let mut interact = |app: &mut bevy_app::App| {
+        helper.send(&PlayerInteractEntityC2s {
+            entity_id: VarInt(entity_id),
+            interact: EntityInteraction::Attack,
+            sneaking: false,
+        });
+
+        app.update();
+
+        let accepted = app
+            .world
+            .resource::<Events<InteractEntityEvent>>()
+            .iter_current_update_events()
+            .count();
+
+        let rejected = app
+            .world
+            .resource::<Events<RejectedInteractEvent>>()
+            .iter_current_update_events()
+            .map(|event| {
+                assert_eq!(event.client, client);
+                assert_eq!(event.entity, zombie);
+                event.reason
+            })
+            .collect::<Vec<_>>();
+
+        (accepted, rejected)
+    };
+
+    // Within reach.
+    assert_eq!(interact(&mut app), (1, vec![]));
+
+    // Out of reach. The hitbox follows the zombie.
+    app.world
+        .get_mut::<Position>(zombie)
+        .unwrap()
+        .set([0.5, 0.0, 10.5]);
+
+    app.update();
+
+    assert!((app.world.get::<Hitbox>(zombie).unwrap().min().z - 10.2).abs() < 1e-9);
+
+    let (accepted, rejected) = interact(&mut app);
+    assert_eq!(accepted, 0);
+    assert!(matches!(
+        rejected[..],
+        [InteractRejection::OutOfReach { distance }] if (distance - 9.7).abs() < 1e-9
+    ));
+
+    // Through a wall.
+    app.world
+        .get_mut::<Position>(zombie)
+        .unwrap()
+        .set([0.5, 0.0, 4.5]);
+
+    app.world
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .set_block([0, 1, 2], BlockState::STONE);
+
+    app.update();
+
+    // Line of sight isn't required by default.
+    assert_eq!(interact(&mut app), (1, vec![]));
+
+    app.world
+        .resource_mut::<InteractEntitySettings>()
+        .require_line_of_sight = true;
+
+    assert_eq!(
+        interact(&mut app),
+        (
+            0,
+            vec![InteractRejection::Obstructed {
+                block: [0, 1, 2].into()
+            }]
+        )
+    );
+
+    // In a layer the client can't see.
+    let other_layer = EntityLayer::new(app.world.resource::<Server>());
+    let other_layer = app.world.spawn(other_layer).id();
+
+    app.world.get_mut::<EntityLayerId>(zombie).unwrap().0 = other_layer;
+
+    app.update();
+
+    assert_eq!(interact(&mut app), (0, vec![InteractRejection::OtherLayer]));
+}
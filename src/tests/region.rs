@@ -0,0 +1,285 @@
+use bevy_app::App;
+use bevy_ecs::event::Events;
+use valence_server::action::DiggingEvent;
+use valence_server::interact_block::InteractBlockEvent;
+use valence_server::interact_entity::{EntityInteraction, InteractEntityEvent};
+use valence_server::math::Vec3;
+use valence_server::protocol::packets::play::player_action_c2s::PlayerAction;
+use valence_server::protocol::packets::play::{
+    BlockUpdateS2c, PlayerActionC2s, PlayerActionResponseS2c, PlayerInteractBlockC2s,
+    PlayerInteractEntityC2s,
+};
+use valence_server::protocol::VarInt;
+
+use crate::entity::{EntityId, Position};
+use crate::inventory::Inventory;
+use crate::layer::chunk::UnloadedChunk;
+use crate::region::{Region, RegionDeniedEvent, RegionFlag, RegionShape, Regions};
+use crate::testing::{create_mock_client, ScenarioSingleClient};
+use crate::{BlockPos, BlockState, ChunkLayer, Direction, Hand, ItemKind, ItemStack, UniqueId};
+
+fn scenario(region: Region) -> ScenarioSingleClient {
+    let scenario = ScenarioSingleClient::new();
+    let mut app = scenario.app;
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(scenario.layer).unwrap();
+
+    for z in -2..2 {
+        for x in -2..2 {
+            layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    let mut regions = Regions::new();
+    regions.insert("spawn", region);
+    app.world.entity_mut(scenario.layer).insert(regions);
+
+    app.world
+        .get_mut::<Position>(scenario.client)
+        .unwrap()
+        .set([0.5, 1.0, 0.5]);
+
+    app.update();
+
+    ScenarioSingleClient { app, ..scenario }
+}
+
+fn count<E: bevy_ecs::event::Event>(app: &App) -> usize {
+    app.world
+        .resource::<Events<E>>()
+        .iter_current_update_events()
+        .count()
+}
+
+fn denials(app: &App) -> Vec<(RegionFlag, BlockPos)> {
+    app.world
+        .resource::<Events<RegionDeniedEvent>>()
+        .iter_current_update_events()
+        .map(|event| (event.flag, event.position))
+        .collect()
+}
+
+#[test]
+fn denied_block_actions_are_cancelled() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = scenario(
+        Region::new(RegionShape::cuboid([-8, 0, -8], [8, 16, 8]))
+            .with_flag(RegionFlag::Break, false)
+            .with_flag(RegionFlag::Build, false),
+    );
+
+    app.world
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .set_block([2, 0, 0], BlockState::STONE);
+
+    app.update();
+    helper.clear_received();
+
+    // Returns the number of digging events after the client starts digging at
+    // `pos`, checking that the client was sent `resyncs` block updates.
+    let mut dig = |app: &mut App, pos: BlockPos, resyncs: usize| {
+        helper.send(&PlayerActionC2s {
+            action: PlayerAction::StartDestroyBlock,
+            position: pos,
+            direction: Direction::Up,
+            sequence: VarInt(1),
+        });
+
+        app.update();
+
+        // The action is acknowledged even if it is denied.
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerActionResponseS2c>(1);
+        recvd.assert_count::<BlockUpdateS2c>(resyncs);
+
+        count::<DiggingEvent>(app)
+    };
+
+    // Breaking blocks inside the region is denied before any digging events are
+    // sent, and the block is resynced.
+    assert_eq!(dig(&mut app, [2, 0, 0].into(), 1), 0);
+    assert_eq!(denials(&app), [(RegionFlag::Break, [2, 0, 0].into())]);
+
+    // Outside of the region breaking is allowed.
+    assert_eq!(dig(&mut app, [12, 0, 0].into(), 0), 1);
+    assert!(denials(&app).is_empty());
+
+    // Members of the region may break blocks in it.
+    let uuid = app.world.get::<UniqueId>(client).unwrap().0;
+    let mut regions = app.world.get_mut::<Regions>(layer).unwrap();
+    let region = regions.remove("spawn").unwrap();
+    regions.insert("spawn", region.with_member(uuid));
+
+    assert_eq!(dig(&mut app, [2, 0, 0].into(), 0), 1);
+    assert!(denials(&app).is_empty());
+
+    let mut regions = app.world.get_mut::<Regions>(layer).unwrap();
+    let region = regions.remove("spawn").unwrap();
+    regions.insert(
+        "spawn",
+        Region::new(region.shape)
+            .with_flag(RegionFlag::Break, false)
+            .with_flag(RegionFlag::Build, false),
+    );
+
+    // Returns the number of block interaction events after the client uses the
+    // stone block, checking that the client was sent `resyncs` block updates.
+    let mut interact = |app: &mut App, resyncs: usize| {
+        helper.send(&PlayerInteractBlockC2s {
+            hand: Hand::Main,
+            position: [2, 0, 0].into(),
+            face: Direction::Up,
+            cursor_pos: Vec3::new(0.5, 1.0, 0.5),
+            head_inside_block: false,
+            sequence: VarInt(2),
+        });
+
+        app.update();
+
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerActionResponseS2c>(1);
+        recvd.assert_count::<BlockUpdateS2c>(resyncs);
+
+        count::<InteractBlockEvent>(app)
+    };
+
+    // Using a block with an empty hand can't place anything.
+    assert_eq!(interact(&mut app, 0), 1);
+    assert!(denials(&app).is_empty());
+
+    // Holding a block, the interaction could place it where building is
+    // denied.
+    app.world
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Stone, 1, None));
+
+    assert_eq!(interact(&mut app, 2), 0);
+    assert_eq!(denials(&app), [(RegionFlag::Build, [2, 1, 0].into())]);
+
+    // Interacting is denied regardless of the held item.
+    app.world
+        .get_mut::<Regions>(layer)
+        .unwrap()
+        .get_mut("spawn")
+        .unwrap()
+        .flags
+        .set(RegionFlag::Interact, false);
+
+    app.world
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::EMPTY);
+
+    assert_eq!(interact(&mut app, 2), 0);
+    assert_eq!(denials(&app), [(RegionFlag::Interact, [2, 0, 0].into())]);
+}
+
+#[test]
+fn pvp_is_checked_for_each_player_at_their_position() {
+    let ScenarioSingleClient {
+        mut app,
+        client: attacker,
+        mut helper,
+        layer,
+    } = scenario(
+        Region::new(RegionShape::cuboid([2, 0, -8], [8, 16, 8])).with_flag(RegionFlag::Pvp, false),
+    );
+
+    let (mut bundle, _) = create_mock_client("victim");
+    bundle.player.layer.0 = layer;
+    bundle.visible_chunk_layer.0 = layer;
+    bundle.visible_entity_layers.0.insert(layer);
+    bundle.player.position.0 = [-1.5, 1.0, 0.5].into();
+
+    let victim = app.world.spawn(bundle).id();
+
+    app.update();
+
+    let victim_id = app.world.get::<EntityId>(victim).unwrap().get();
+    let attacker_uuid = app.world.get::<UniqueId>(attacker).unwrap().0;
+    let victim_uuid = app.world.get::<UniqueId>(victim).unwrap().0;
+
+    let mut attack = |app: &mut App| {
+        helper.send(&PlayerInteractEntityC2s {
+            entity_id: VarInt(victim_id),
+            interact: EntityInteraction::Attack,
+            sneaking: false,
+        });
+
+        app.update();
+
+        count::<InteractEntityEvent>(app)
+    };
+
+    // Both players are outside of the region.
+    assert_eq!(attack(&mut app), 1);
+    assert!(denials(&app).is_empty());
+
+    // The victim is protected by the region.
+    app.world
+        .get_mut::<Position>(victim)
+        .unwrap()
+        .set([2.5, 1.0, 0.5]);
+
+    assert_eq!(attack(&mut app), 0);
+    assert_eq!(denials(&app), [(RegionFlag::Pvp, [2, 1, 0].into())]);
+
+    // The attacker being a member of the region doesn't matter, since the
+    // attacker isn't in it.
+    let set_region = |app: &mut App, region: Region| {
+        app.world
+            .get_mut::<Regions>(layer)
+            .unwrap()
+            .insert("spawn", region);
+    };
+
+    let shape = RegionShape::cuboid([2, 0, -8], [8, 16, 8]);
+
+    set_region(
+        &mut app,
+        Region::new(shape.clone())
+            .with_flag(RegionFlag::Pvp, false)
+            .with_member(attacker_uuid),
+    );
+
+    assert_eq!(attack(&mut app), 0);
+    assert_eq!(denials(&app), [(RegionFlag::Pvp, [2, 1, 0].into())]);
+
+    // Members of the region may fight inside of it.
+    set_region(
+        &mut app,
+        Region::new(shape.clone())
+            .with_flag(RegionFlag::Pvp, false)
+            .with_member(victim_uuid),
+    );
+
+    assert_eq!(attack(&mut app), 1);
+    assert!(denials(&app).is_empty());
+
+    // The attacker is in the region and the victim is not.
+    set_region(
+        &mut app,
+        Region::new(shape)
+            .with_flag(RegionFlag::Pvp, false)
+            .with_member(victim_uuid),
+    );
+
+    app.world
+        .get_mut::<Position>(attacker)
+        .unwrap()
+        .set([3.5, 1.0, 0.5]);
+
+    app.world
+        .get_mut::<Position>(victim)
+        .unwrap()
+        .set([1.5, 1.0, 0.5]);
+
+    assert_eq!(attack(&mut app), 0);
+    assert_eq!(denials(&app), [(RegionFlag::Pvp, [3, 1, 0].into())]);
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use bevy_ecs::prelude::*;
+
+use crate::client::VisibleChunkLayer;
+use crate::math::DVec3;
+use crate::protocol::packets::play::{EntitiesDestroyS2c, ExperienceOrbSpawnS2c, KeepAliveS2c};
+use crate::protocol::{Encode, Packet, VarInt};
+use crate::replay::{Replay, ReplayFrame, ReplayPlayback};
+use crate::testing::ScenarioSingleClient;
+
+fn frame<P: Packet + Encode>(millis: u64, pkt: &P) -> ReplayFrame {
+    let mut body = vec![];
+    pkt.encode(&mut body).unwrap();
+
+    ReplayFrame {
+        time: Duration::from_millis(millis),
+        id: P::ID,
+        body: body.into(),
+    }
+}
+
+fn orb(entity_id: i32) -> ExperienceOrbSpawnS2c {
+    ExperienceOrbSpawnS2c {
+        entity_id: VarInt(entity_id),
+        position: DVec3::ZERO,
+        count: 1,
+    }
+}
+
+#[test]
+fn play_back_replay() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let replay = Replay::from_frames(vec![
+        frame(0, &orb(1000)),
+        frame(0, &KeepAliveS2c { id: 1 }),
+        frame(1000, &orb(1001)),
+    ]);
+
+    app.world
+        .entity_mut(client)
+        .insert(ReplayPlayback::new(replay));
+
+    // The client leaves its layer and starts watching.
+    app.update();
+    assert_eq!(
+        app.world.get::<VisibleChunkLayer>(client).unwrap().0,
+        Entity::PLACEHOLDER
+    );
+
+    app.update();
+    let frames = helper.collect_received();
+    frames.assert_count::<ExperienceOrbSpawnS2c>(1);
+    frames.assert_count::<KeepAliveS2c>(0);
+
+    let mut playback = app.world.get_mut::<ReplayPlayback>(client).unwrap();
+    playback.seek(Duration::from_secs(5));
+    assert_eq!(playback.position(), Duration::from_secs(1));
+
+    app.update();
+    assert_eq!(
+        helper
+            .collect_received()
+            .first::<ExperienceOrbSpawnS2c>()
+            .entity_id,
+        VarInt(1001)
+    );
+
+    let playback = app.world.get::<ReplayPlayback>(client).unwrap();
+    assert!(playback.is_finished());
+
+    // Seeking backwards clears the client and starts over.
+    let mut playback = app.world.get_mut::<ReplayPlayback>(client).unwrap();
+    playback.paused = true;
+    playback.seek(Duration::ZERO);
+
+    app.update();
+    let frames = helper.collect_received();
+    frames.assert_order::<(EntitiesDestroyS2c, ExperienceOrbSpawnS2c)>();
+    assert_eq!(
+        frames.first::<EntitiesDestroyS2c>().entity_ids[..],
+        [VarInt(1000), VarInt(1001)]
+    );
+    assert_eq!(
+        frames.first::<ExperienceOrbSpawnS2c>().entity_id,
+        VarInt(1000)
+    );
+
+    // Stopping the playback returns the client to its layer.
+    app.world.entity_mut(client).remove::<ReplayPlayback>();
+    app.update();
+
+    assert_eq!(
+        helper
+            .collect_received()
+            .first::<EntitiesDestroyS2c>()
+            .entity_ids[..],
+        [VarInt(1000)]
+    );
+    assert_eq!(app.world.get::<VisibleChunkLayer>(client).unwrap().0, layer);
+}
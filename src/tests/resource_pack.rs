@@ -0,0 +1,165 @@
+use bevy_app::App;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::event::Events;
+
+use crate::protocol::packets::play::{DisconnectS2c, ResourcePackSendS2c, ResourcePackStatusC2s};
+use crate::resource_pack::{RequiredResourcePack, ResourcePackState, ResourcePackStatusEvent};
+use crate::testing::ScenarioSingleClient;
+use crate::Despawned;
+
+const URL: &str = "https://example.com/pack.zip";
+const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+fn status_events(app: &App) -> Vec<ResourcePackStatusEvent> {
+    app.world
+        .resource::<Events<ResourcePackStatusEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect()
+}
+
+fn state(app: &App, client: Entity) -> ResourcePackState {
+    *app.world.get::<ResourcePackState>(client).unwrap()
+}
+
+#[test]
+fn resource_pack_state_tracks_status() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    assert_eq!(state(&app, client).status(), None);
+
+    helper.send(&ResourcePackStatusC2s::Accepted);
+    app.update();
+
+    let state_now = state(&app, client);
+    assert!(state_now.is_downloading());
+    assert!(!state_now.is_loaded());
+
+    let events = status_events(&app);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].client, client);
+    assert!(!events[0].is_rejected());
+
+    helper.send(&ResourcePackStatusC2s::SuccessfullyLoaded);
+    app.update();
+
+    let state_now = state(&app, client);
+    assert!(state_now.is_loaded());
+    assert!(!state_now.is_downloading());
+
+    helper.send(&ResourcePackStatusC2s::FailedDownload);
+    app.update();
+
+    assert_eq!(
+        state(&app, client).status(),
+        Some(ResourcePackStatusC2s::FailedDownload)
+    );
+    assert!(status_events(&app)[0].is_rejected());
+
+    helper.send(&ResourcePackStatusC2s::Declined);
+    app.update();
+
+    let state_now = state(&app, client);
+    assert_eq!(state_now.status(), Some(ResourcePackStatusC2s::Declined));
+    assert!(!state_now.is_loaded());
+    assert!(status_events(&app)[0].is_rejected());
+
+    // Without a required pack, the client isn't kicked.
+    assert!(!app.world.entity(client).contains::<Despawned>());
+}
+
+#[test]
+fn required_resource_pack_is_sent_and_resent() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    app.world
+        .entity_mut(client)
+        .insert(RequiredResourcePack::new(URL, HASH).with_prompt("Please accept the pack"));
+    app.update();
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<ResourcePackSendS2c>(1);
+
+    let pkt = recvd.first::<ResourcePackSendS2c>();
+    assert_eq!(pkt.url, URL);
+    assert_eq!(pkt.hash.0, HASH);
+    assert!(pkt.forced);
+    assert!(pkt.prompt_message.is_some());
+
+    helper.send(&ResourcePackStatusC2s::SuccessfullyLoaded);
+    app.update();
+
+    assert!(state(&app, client).is_loaded());
+    helper
+        .collect_received()
+        .assert_count::<ResourcePackSendS2c>(0);
+
+    // Changing the pack sends it again and resets the state.
+    app.world
+        .get_mut::<RequiredResourcePack>(client)
+        .unwrap()
+        .url = "https://example.com/other.zip".into();
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<ResourcePackSendS2c>(1);
+    assert_eq!(state(&app, client).status(), None);
+}
+
+#[test]
+fn clients_rejecting_required_pack_are_kicked() {
+    for status in [
+        ResourcePackStatusC2s::Declined,
+        ResourcePackStatusC2s::FailedDownload,
+    ] {
+        let ScenarioSingleClient {
+            mut app,
+            client,
+            mut helper,
+            ..
+        } = ScenarioSingleClient::new();
+
+        app.world
+            .entity_mut(client)
+            .insert(RequiredResourcePack::new(URL, HASH).with_kick_message("No pack, no play"));
+
+        app.update();
+        helper.clear_received();
+
+        // Accepting the pack is fine.
+        helper.send(&ResourcePackStatusC2s::Accepted);
+        app.update();
+
+        helper.collect_received().assert_count::<DisconnectS2c>(0);
+
+        helper.send(&status);
+        app.update();
+
+        let recvd = helper.collect_received();
+        recvd.assert_count::<DisconnectS2c>(1);
+        assert_eq!(
+            recvd.first::<DisconnectS2c>().reason.to_legacy_lossy(),
+            "No pack, no play"
+        );
+        assert!(app
+            .world
+            .get_entity(client)
+            .is_none_or(|e| e.contains::<Despawned>()));
+    }
+}
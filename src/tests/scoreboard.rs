@@ -4,7 +4,7 @@ use crate::client::VisibleEntityLayers;
 use crate::entity::EntityLayerId;
 use crate::layer::EntityLayer;
 use crate::protocol::packets::play::{
-    ScoreboardDisplayS2c, ScoreboardObjectiveUpdateS2c, ScoreboardPlayerUpdateS2c,
+    ScoreboardDisplayS2c, ScoreboardObjectiveUpdateS2c, ScoreboardPlayerUpdateS2c, TeamS2c,
 };
 use crate::testing::ScenarioSingleClient;
 use crate::text::IntoText;
@@ -190,3 +190,44 @@ fn should_only_update_score_diff() {
         recvd.assert_count::<ScoreboardPlayerUpdateS2c>(1);
     }
 }
+
+#[test]
+fn should_update_team_members() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let team = app
+        .world
+        .spawn(TeamBundle {
+            name: Team::new("red"),
+            members: TeamMembers(["foo".into(), "bar".into()].into()),
+            layer: EntityLayerId(layer),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+
+    // The team is created and its members are added.
+    helper.collect_received().assert_count::<TeamS2c>(2);
+
+    helper.clear_received();
+
+    {
+        let mut members = app.world.get_mut::<TeamMembers>(team).unwrap();
+        members.remove("foo");
+        members.insert("baz".into());
+    }
+
+    app.update();
+
+    // One packet removes "foo", another adds "baz".
+    helper.collect_received().assert_count::<TeamS2c>(2);
+}
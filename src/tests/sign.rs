@@ -0,0 +1,92 @@
+use bevy_app::App;
+use bevy_ecs::event::Events;
+use bevy_ecs::system::Command;
+
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::nbt::{List, Value};
+use crate::protocol::packets::play::{SignEditorOpenS2c, UpdateSignC2s};
+use crate::protocol::Bounded;
+use crate::sign::{OpenSignEditor, SignEditEvent, SignEditor};
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockPos, BlockState, ChunkPos};
+
+fn update_sign(position: BlockPos, lines: [&str; 4]) -> UpdateSignC2s<'_> {
+    UpdateSignC2s {
+        position,
+        is_front_text: true,
+        lines: lines.map(Bounded),
+    }
+}
+
+fn sign_edits(app: &App) -> Vec<SignEditEvent> {
+    app.world
+        .resource::<Events<SignEditEvent>>()
+        .iter_current_update_events()
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn sign_edit_writes_lines() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let position = BlockPos::new(1, 2, 3);
+
+    let mut chunk_layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk(ChunkPos::new(0, 0), UnloadedChunk::new());
+    chunk_layer.set_block(position, BlockState::OAK_SIGN);
+
+    app.update();
+    helper.clear_received();
+
+    // Edits without an open editor are ignored.
+    helper.send(&update_sign(position, ["ignored", "", "", ""]));
+    app.update();
+    assert!(sign_edits(&app).is_empty());
+
+    OpenSignEditor::new(client, position).apply(&mut app.world);
+    app.update();
+
+    let open = helper.collect_received();
+    open.assert_count::<SignEditorOpenS2c>(1);
+    assert!(app.world.get::<SignEditor>(client).is_some());
+
+    helper.send(&update_sign(position, ["§cHello", "world", "", ""]));
+    app.update();
+
+    assert_eq!(
+        sign_edits(&app),
+        [SignEditEvent {
+            client,
+            position,
+            is_front_text: true,
+            lines: ["cHello".into(), "world".into(), "".into(), "".into()],
+        }]
+    );
+    assert!(app.world.get::<SignEditor>(client).is_none());
+
+    let chunk_layer = app.world.get::<ChunkLayer>(layer).unwrap();
+    let nbt = chunk_layer.block(position).unwrap().nbt.unwrap();
+
+    let Some(Value::Compound(front)) = nbt.get("front_text") else {
+        panic!("missing front text");
+    };
+
+    let Some(Value::List(List::String(messages))) = front.get("messages") else {
+        panic!("missing messages");
+    };
+
+    assert_eq!(messages.len(), 4);
+    assert!(messages[0].contains("cHello"));
+
+    // The editor is closed after the first edit.
+    helper.send(&update_sign(position, ["again", "", "", ""]));
+    app.update();
+    assert!(sign_edits(&app).is_empty());
+}
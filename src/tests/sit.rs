@@ -0,0 +1,56 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::Command;
+
+use crate::entity::armor_stand::ArmorStandEntity;
+use crate::protocol::packets::play::player_input_c2s::PlayerInputFlags;
+use crate::protocol::packets::play::{EntityPassengersSetS2c, PlayerInputC2s};
+use crate::sit::{Seated, Sit, StandUpEvent};
+use crate::testing::ScenarioSingleClient;
+use crate::Despawned;
+
+#[test]
+fn sit_and_dismount() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    Sit { client }.apply(&mut app.world);
+
+    let seat = app.world.get::<Seated>(client).unwrap().seat();
+    assert!(app.world.get::<ArmorStandEntity>(seat).is_some());
+
+    // The passengers are sent once the client knows about the seat.
+    app.update();
+    helper
+        .collect_received()
+        .assert_count::<EntityPassengersSetS2c>(0);
+
+    app.update();
+    let frames = helper.collect_received();
+    frames.assert_count::<EntityPassengersSetS2c>(1);
+    assert_eq!(
+        frames.first::<EntityPassengersSetS2c>().passengers[..],
+        [0.into()]
+    );
+
+    helper.send(&PlayerInputC2s {
+        sideways: 0.0,
+        forward: 0.0,
+        flags: PlayerInputFlags::new().with_unmount(true),
+    });
+
+    app.update();
+
+    assert!(app.world.get::<Seated>(client).is_none());
+    assert!(app
+        .world
+        .get_entity(seat)
+        .is_none_or(|seat| seat.contains::<Despawned>()));
+    assert_eq!(app.world.resource::<Events<StandUpEvent>>().len(), 1);
+}
@@ -0,0 +1,51 @@
+use valence_player_list::npc::NpcBundle;
+
+use crate::client::Properties;
+use crate::layer::chunk::UnloadedChunk;
+use crate::protocol::packets::play::{EntitiesDestroyS2c, PlayerListS2c, PlayerSpawnS2c};
+use crate::skin::Skin;
+use crate::testing::ScenarioSingleClient;
+use crate::ChunkLayer;
+
+#[test]
+fn changing_skin_respawns_player_entity() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.world
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .insert_chunk([0, 0], UnloadedChunk::new());
+
+    let npc = app
+        .world
+        .spawn(NpcBundle::new(layer, "npc").with_position([0.0, 0.0, 5.0]))
+        .id();
+
+    app.update();
+    helper.clear_received();
+
+    // Nothing is respawned when the skin doesn't change.
+    app.update();
+    helper
+        .collect_received()
+        .assert_count::<EntitiesDestroyS2c>(0);
+
+    Skin::new("textures", "signature").apply(&mut app.world.get_mut::<Properties>(npc).unwrap());
+
+    app.update();
+
+    let recvd = helper.collect_received();
+
+    recvd.assert_count::<PlayerListS2c>(1);
+    recvd.assert_count::<EntitiesDestroyS2c>(1);
+    recvd.assert_count::<PlayerSpawnS2c>(1);
+    recvd.assert_order::<(PlayerListS2c, EntitiesDestroyS2c, PlayerSpawnS2c)>();
+
+    let pkt = recvd.first::<PlayerListS2c>();
+    assert_eq!(pkt.entries[0].properties[0].value, "textures");
+}
@@ -0,0 +1,67 @@
+use bevy_ecs::system::Command;
+
+use crate::entity::zombie::ZombieEntityBundle;
+use crate::entity::{EntityId, EntityLayerId, Position};
+use crate::math::DVec3;
+use crate::protocol::packets::play::{EntitySpawnS2c, PlayerPositionLookS2c, SetCameraEntityS2c};
+use crate::spectate::{Spectate, Spectating};
+use crate::testing::ScenarioSingleClient;
+use crate::Despawned;
+
+#[test]
+fn spectating_follows_target() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    helper.clear_received();
+
+    let zombie = app
+        .world
+        .spawn(ZombieEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position(DVec3::new(200.0, 64.0, 200.0)),
+            ..Default::default()
+        })
+        .id();
+
+    Spectate::new(client, zombie).apply(&mut app.world);
+
+    app.update();
+
+    assert_eq!(
+        app.world.get::<Position>(client).unwrap().0,
+        DVec3::new(200.0, 64.0, 200.0)
+    );
+
+    let frames = helper.collect_received();
+    frames.assert_count::<PlayerPositionLookS2c>(0);
+    frames.assert_order::<(EntitySpawnS2c, SetCameraEntityS2c)>();
+
+    let zombie_id = app.world.get::<EntityId>(zombie).unwrap().get();
+    assert_eq!(frames.first::<SetCameraEntityS2c>().entity_id.0, zombie_id);
+
+    app.world.get_mut::<Position>(zombie).unwrap().0.x += 100.0;
+
+    app.update();
+
+    assert_eq!(app.world.get::<Position>(client).unwrap().0.x, 300.0);
+    helper.clear_received();
+
+    // The camera is restored when the target is despawned.
+    app.world.entity_mut(zombie).insert(Despawned);
+
+    app.update();
+    app.update();
+
+    assert!(!app.world.entity(client).contains::<Spectating>());
+
+    let frames = helper.collect_received();
+    frames.assert_count::<PlayerPositionLookS2c>(1);
+    assert_eq!(frames.first::<SetCameraEntityS2c>().entity_id.0, 0);
+}
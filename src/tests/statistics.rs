@@ -0,0 +1,63 @@
+use crate::client::Client;
+use crate::ecs::event::Events;
+use crate::entity::Position;
+use crate::math::DVec3;
+use crate::protocol::packets::play::{ClientStatusC2s, PositionAndOnGroundC2s, StatisticsS2c};
+use crate::statistics::{CustomStat, PlayerStatistics, SaveStatisticsEvent, Stat};
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn statistics_tracking_and_request() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.world.get_mut::<Position>(client).unwrap().0 = DVec3::new(0.0, 64.0, 0.0);
+    app.world.entity_mut(client).insert(PlayerStatistics::new());
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+    helper.confirm_initial_pending_teleports();
+    helper.clear_received();
+
+    helper.send(&PositionAndOnGroundC2s {
+        position: DVec3::new(3.0, 64.0, 4.0),
+        on_ground: true,
+    });
+
+    app.update();
+
+    let stats = app.world.get::<PlayerStatistics>(client).unwrap();
+    assert_eq!(stats.get(CustomStat::WalkOneCm), 500);
+    assert_eq!(stats.get(CustomStat::PlayTime), 2);
+
+    let len = stats.len();
+
+    helper.send(&ClientStatusC2s::RequestStats);
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<StatisticsS2c>(1);
+
+    let pkt = frames.first::<StatisticsS2c>();
+    assert_eq!(pkt.statistics.len(), len);
+    assert!(pkt.statistics.iter().any(|stat| {
+        stat.category_id.0 == 8
+            && stat.statistic_id.0 == Stat::Custom(CustomStat::WalkOneCm).statistic_id()
+            && stat.value.0 == 500
+    }));
+
+    // The statistics are handed out for saving when the client disconnects.
+    app.world.entity_mut(client).remove::<Client>();
+
+    app.update();
+
+    let events = app.world.resource::<Events<SaveStatisticsEvent>>();
+    let event = events.iter_current_update_events().next().unwrap();
+    assert_eq!(event.client, client);
+    assert_eq!(event.statistics.get(CustomStat::WalkOneCm), 500);
+}
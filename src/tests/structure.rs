@@ -0,0 +1,155 @@
+use crate::block::{BlockKind, PropName, PropValue};
+use crate::layer::chunk::region::Region;
+use crate::layer::chunk::UnloadedChunk;
+use crate::nbt::{compound, List, Value};
+use crate::registry::BiomeRegistry;
+use crate::structure::{PlaceSettings, Rotation, Schematic, SchematicVersion, Structure};
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockPos, BlockState, ChunkLayer};
+
+fn house() -> Structure {
+    let block = |pos: [i32; 3], state: i32| {
+        compound! {
+            "pos" => List::Int(pos.to_vec()),
+            "state" => state,
+        }
+    };
+
+    let mut chest = block([0, 1, 0], 2);
+    chest.insert(
+        "nbt",
+        compound! {
+            "id" => "minecraft:chest",
+            "CustomName" => "\"Loot\"",
+        },
+    );
+
+    let root = compound! {
+        "DataVersion" => 3465,
+        "size" => List::Int(vec![3, 2, 1]),
+        "palette" => List::Compound(vec![
+            compound! { "Name" => "minecraft:oak_planks" },
+            compound! {
+                "Name" => "minecraft:oak_stairs",
+                "Properties" => compound! { "facing" => "north", "half" => "bottom" },
+            },
+            compound! {
+                "Name" => "minecraft:chest",
+                "Properties" => compound! { "facing" => "east" },
+            },
+        ]),
+        "blocks" => List::Compound(vec![
+            block([0, 0, 0], 0),
+            block([1, 0, 0], 0),
+            block([2, 0, 0], 1),
+            chest,
+        ]),
+        "entities" => List::End,
+    };
+
+    Structure::from_nbt(&root).unwrap()
+}
+
+#[test]
+fn place_rotated_structure() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+
+    for z in -1..=0 {
+        for x in -1..=0 {
+            layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    let house = house();
+    assert_eq!(house.size(), [3, 2, 1]);
+    assert_eq!(house.blocks().count(), 4);
+
+    let settings = PlaceSettings::new()
+        .with_rotation(Rotation::Clockwise90)
+        .with_replacement(BlockKind::OakStairs, BlockKind::SpruceStairs);
+
+    let origin = BlockPos::new(0, 64, 0);
+    let region = house.place(&mut layer, origin, &settings);
+
+    // The structure extends along +Z instead of +X after rotating it.
+    assert_eq!(region.min, BlockPos::new(0, 64, 0));
+    assert_eq!(region.max, BlockPos::new(0, 65, 2));
+    assert_eq!(region.positions().count(), 6);
+    assert!(region.contains([0, 65, 1]));
+    assert!(!region.contains([1, 64, 0]));
+
+    assert_eq!(
+        layer.block([0, 64, 1]).unwrap().state,
+        BlockState::OAK_PLANKS
+    );
+
+    let stairs = layer.block([0, 64, 2]).unwrap().state;
+    assert_eq!(stairs.to_kind(), BlockKind::SpruceStairs);
+    assert_eq!(stairs.get(PropName::Facing), Some(PropValue::East));
+    assert_eq!(stairs.get(PropName::Half), Some(PropValue::Bottom));
+
+    let chest = layer.block([0, 65, 0]).unwrap();
+    assert_eq!(chest.state.get(PropName::Facing), Some(PropValue::South));
+    assert_eq!(
+        chest.nbt.unwrap().get("CustomName"),
+        Some(&Value::String("\"Loot\"".into()))
+    );
+    assert!(chest.nbt.unwrap().get("id").is_none());
+
+    let without_entities = PlaceSettings::new().with_block_entities(false);
+    house.place(&mut layer, [0, 70, 0], &without_entities);
+
+    assert!(layer.block([0, 71, 0]).unwrap().nbt.unwrap().is_empty());
+}
+
+#[test]
+fn schematic_copy_and_paste() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer: layer_ent,
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([1, 0], UnloadedChunk::new());
+
+    house().place(&mut layer, [0, 64, 0], &PlaceSettings::new());
+
+    let region = Region::new([0, 64, 0], [2, 65, 0]);
+    let schematic = Schematic::copy(&layer, region);
+
+    let biomes = app.world.resource::<BiomeRegistry>();
+
+    let mut file = vec![];
+    schematic
+        .write(&mut file, SchematicVersion::V3, biomes)
+        .unwrap();
+
+    let schematic = Schematic::read(file.as_slice(), biomes).unwrap();
+    assert_eq!(schematic.clipboard.size(), [3, 2, 1]);
+    assert!(schematic.biome([0, 0, 0]).is_some());
+
+    let mut layer = app.world.get_mut::<ChunkLayer>(layer_ent).unwrap();
+    assert_eq!(schematic.paste(&mut layer, [20, 64, 4], true), 4);
+
+    assert_eq!(
+        layer.block([21, 64, 4]).unwrap().state,
+        BlockState::OAK_PLANKS
+    );
+
+    let chest = layer.block([20, 65, 4]).unwrap();
+    assert_eq!(chest.state.to_kind(), BlockKind::Chest);
+    assert_eq!(
+        chest.nbt.unwrap().get("CustomName"),
+        Some(&Value::String("\"Loot\"".into()))
+    );
+}
@@ -0,0 +1,54 @@
+use crate::client::Client;
+use crate::custom_payload::PluginMessage;
+use crate::protocol::packets::play::CustomPayloadS2c;
+use crate::testing::ScenarioSingleClient;
+use crate::transfer::{BungeeCordMessage, Cookies};
+use crate::{ident, Despawned, UniqueId};
+
+#[test]
+fn transfer_through_proxy() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer: _,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let uuid = app.world.get::<UniqueId>(client).unwrap().0;
+    app.world
+        .resource_mut::<Cookies>()
+        .store(uuid, ident!("valence:team"), *b"red");
+
+    app.world
+        .get_mut::<Client>(client)
+        .unwrap()
+        .transfer("minigames");
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<CustomPayloadS2c>(1);
+
+    let pkt = frames.first::<CustomPayloadS2c>();
+    assert_eq!(pkt.channel.as_str(), "bungeecord:main");
+    assert_eq!(
+        BungeeCordMessage::decode(pkt.data.0 .0).unwrap(),
+        BungeeCordMessage::Connect {
+            server: "minigames".into()
+        }
+    );
+
+    // Cookies outlive the client.
+    app.world.entity_mut(client).insert(Despawned);
+    app.update();
+
+    assert_eq!(
+        app.world
+            .resource::<Cookies>()
+            .get(uuid, ident!("valence:team")),
+        Some(b"red".as_slice())
+    );
+}
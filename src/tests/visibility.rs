@@ -0,0 +1,85 @@
+use bevy_ecs::prelude::*;
+
+use crate::entity::Position;
+use crate::layer::chunk::UnloadedChunk;
+use crate::protocol::packets::play::{EntitiesDestroyS2c, MoveRelativeS2c, PlayerSpawnS2c};
+use crate::testing::{create_mock_client, ScenarioSingleClient};
+use crate::visibility::{HiddenEntities, VisibilityFilters};
+use crate::ChunkLayer;
+
+#[derive(Component)]
+struct Vanished;
+
+#[test]
+fn vanished_players_are_hidden() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.world
+        .resource_mut::<VisibilityFilters>()
+        .add(|_viewer: EntityRef, target: EntityRef| !target.contains::<Vanished>());
+
+    let mut chunk_layer = app.world.get_mut::<ChunkLayer>(layer).unwrap();
+
+    for z in -5..5 {
+        for x in -5..5 {
+            chunk_layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    let (mut bundle, _) = create_mock_client("vanished");
+    bundle.player.layer.0 = layer;
+    bundle.visible_chunk_layer.0 = layer;
+    bundle.visible_entity_layers.0.insert(layer);
+
+    let vanished = app.world.spawn((bundle, Vanished)).id();
+
+    app.update();
+
+    // The vanished player is never spawned.
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerSpawnS2c>(0);
+    }
+
+    assert!(app
+        .world
+        .get::<HiddenEntities>(client)
+        .unwrap()
+        .contains(vanished));
+
+    app.world
+        .get_mut::<Position>(vanished)
+        .unwrap()
+        .set([1.0, 0.0, 0.0]);
+
+    app.update();
+
+    // Nor are its movements sent.
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<MoveRelativeS2c>(0);
+    }
+
+    app.world.entity_mut(vanished).remove::<Vanished>();
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerSpawnS2c>(1);
+    }
+
+    app.world.entity_mut(vanished).insert(Vanished);
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<EntitiesDestroyS2c>(1);
+    }
+}
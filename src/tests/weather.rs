@@ -1,6 +1,12 @@
-use crate::protocol::packets::play::GameStateChangeS2c;
+use crate::entity::Position;
+use crate::math::{Aabb, DVec3};
+use crate::protocol::packets::play::{GameStateChangeS2c, PlaySoundS2c};
+use crate::protocol::sound::Sound;
 use crate::testing::*;
-use crate::weather::{Rain, Thunder, WeatherBundle};
+use crate::weather::{
+    CurrentWeatherZone, Rain, Thunder, WeatherBundle, WeatherZone, WeatherZoneEnterEvent,
+    WeatherZoneLeaveEvent, WeatherZones, ZoneAmbience,
+};
 
 #[test]
 fn test_client_initialization_on_join() {
@@ -132,6 +138,78 @@ fn test_chunk_layer_thunder_change() {
     frames.assert_count::<GameStateChangeS2c>(1);
 }
 
+#[test]
+fn test_weather_zone_enter_and_leave() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = prepare(false);
+
+    let mut zones = WeatherZones::new();
+    zones.insert(
+        "swamp",
+        WeatherZone::new(Aabb::new(
+            DVec3::new(10.0, -10.0, 10.0),
+            DVec3::new(20.0, 10.0, 20.0),
+        ))
+        .with_rain(1.0)
+        .with_ambience(ZoneAmbience::new(Sound::WeatherRain, 20)),
+    );
+    app.world.entity_mut(layer).insert(zones);
+
+    app.update();
+    helper.clear_received();
+
+    // Entering the zone sends the weather of the zone and its ambience.
+    app.world
+        .get_mut::<Position>(client)
+        .unwrap()
+        .set([15.0, 0.0, 15.0]);
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<GameStateChangeS2c>(2);
+    frames.assert_count::<PlaySoundS2c>(1);
+
+    assert_eq!(
+        app.world.get::<CurrentWeatherZone>(client).unwrap().get(),
+        Some("swamp")
+    );
+    let enter = app
+        .world
+        .resource::<bevy_ecs::event::Events<WeatherZoneEnterEvent>>();
+    assert_eq!(enter.iter_current_update_events().count(), 1);
+
+    // Changing the weather of the layer doesn't affect clients in the zone.
+    app.world.get_mut::<Rain>(layer).unwrap().0 = 0.0;
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<GameStateChangeS2c>(3);
+    frames.assert_count::<PlaySoundS2c>(0);
+
+    // Leaving the zone restores the weather of the layer.
+    app.world
+        .get_mut::<Position>(client)
+        .unwrap()
+        .set([0.0, 0.0, 0.0]);
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<GameStateChangeS2c>(2);
+
+    assert_eq!(
+        app.world.get::<CurrentWeatherZone>(client).unwrap().get(),
+        None
+    );
+    let leave = app
+        .world
+        .resource::<bevy_ecs::event::Events<WeatherZoneLeaveEvent>>();
+    assert_eq!(leave.iter_current_update_events().count(), 1);
+}
+
 fn prepare(client_weather: bool) -> ScenarioSingleClient {
     let mut s = ScenarioSingleClient::new();
 
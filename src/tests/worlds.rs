@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+use std::{env, fs};
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::CommandQueue;
+
+use crate::anvil::worlds::{WorldError, WorldName, Worlds};
+use crate::anvil::AnvilLevel;
+use crate::registry::{BiomeRegistry, DimensionTypeRegistry};
+use crate::testing::ScenarioSingleClient;
+use crate::{ident, ChunkPos, LayerBundle, Server};
+
+fn worlds_root(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("valence_worlds_{name}_{}", std::process::id()))
+}
+
+/// Runs `f` with the [`Worlds`] resource and applies the commands it queued.
+fn with_worlds<R>(
+    world: &mut World,
+    f: impl FnOnce(&mut Worlds, &mut Commands, LayerBundle, &BiomeRegistry) -> R,
+) -> R {
+    let layer = LayerBundle::new(
+        ident!("overworld"),
+        world.resource::<DimensionTypeRegistry>(),
+        world.resource::<BiomeRegistry>(),
+        world.resource::<Server>(),
+    );
+
+    world.resource_scope(|world, mut worlds: Mut<Worlds>| {
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+
+        let res = f(
+            &mut worlds,
+            &mut commands,
+            layer,
+            world.resource::<BiomeRegistry>(),
+        );
+
+        queue.apply(world);
+        res
+    })
+}
+
+#[test]
+fn create_load_and_unload_worlds() {
+    let ScenarioSingleClient { mut app, .. } = ScenarioSingleClient::new();
+
+    let root = worlds_root("load");
+    app.insert_resource(Worlds::new(&root));
+
+    let layer = with_worlds(&mut app.world, |worlds, commands, layer, biomes| {
+        worlds.create(commands, "lobby", layer, biomes, None)
+    })
+    .unwrap();
+
+    assert!(root.join("lobby").join("region").is_dir());
+    assert!(app.world.get::<AnvilLevel>(layer).is_some());
+    assert_eq!(
+        app.world.get::<WorldName>(layer).map(WorldName::as_str),
+        Some("lobby")
+    );
+
+    let res = with_worlds(&mut app.world, |worlds, commands, layer, biomes| {
+        worlds.create(commands, "lobby", layer, biomes, None)
+    });
+    assert!(matches!(res, Err(WorldError::AlreadyExists(_))));
+
+    let res = with_worlds(&mut app.world, |worlds, commands, layer, biomes| {
+        worlds.load(commands, "lobby", layer, biomes, None)
+    });
+    assert!(matches!(res, Err(WorldError::AlreadyLoaded(_))));
+
+    let res = with_worlds(&mut app.world, |worlds, commands, layer, biomes| {
+        worlds.load(commands, "missing", layer, biomes, None)
+    });
+    assert!(matches!(res, Err(WorldError::NotFound(_))));
+
+    let res = with_worlds(&mut app.world, |worlds, commands, layer, biomes| {
+        worlds.create(commands, "../escape", layer, biomes, None)
+    });
+    assert!(matches!(res, Err(WorldError::InvalidName(_))));
+
+    app.update();
+
+    // Unloading despawns the layer but keeps the directory.
+    let unloaded = with_worlds(&mut app.world, |worlds, commands, _, _| {
+        worlds.unload(commands, "lobby")
+    })
+    .unwrap();
+    assert_eq!(unloaded, layer);
+
+    app.update();
+
+    assert!(app.world.get_entity(layer).is_none());
+    assert!(root.join("lobby").is_dir());
+
+    let worlds = app.world.resource::<Worlds>();
+    assert_eq!(worlds.get("lobby"), None);
+    assert_eq!(worlds.list().unwrap(), ["lobby"]);
+
+    let res = with_worlds(&mut app.world, |worlds, commands, _, _| {
+        worlds.unload(commands, "lobby")
+    });
+    assert!(matches!(res, Err(WorldError::NotLoaded(_))));
+
+    // The world can be loaded again.
+    let reloaded = with_worlds(&mut app.world, |worlds, commands, layer, biomes| {
+        worlds.load(commands, "lobby", layer, biomes, None)
+    })
+    .unwrap();
+    assert_eq!(app.world.resource::<Worlds>().get("lobby"), Some(reloaded));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn delete_loaded_world() {
+    let ScenarioSingleClient { mut app, .. } = ScenarioSingleClient::new();
+
+    let root = worlds_root("delete");
+    app.insert_resource(Worlds::new(&root));
+
+    let layer = with_worlds(&mut app.world, |worlds, commands, layer, biomes| {
+        worlds.create(commands, "arena", layer, biomes, None)
+    })
+    .unwrap();
+
+    // Start the chunk worker and give it some work.
+    app.update();
+
+    let mut level = app.world.get_mut::<AnvilLevel>(layer).unwrap();
+    for x in -4..4 {
+        for z in -4..4 {
+            level.force_chunk_load(ChunkPos::new(x, z));
+        }
+    }
+
+    app.update();
+
+    with_worlds(&mut app.world, |worlds, commands, _, _| {
+        worlds.delete(commands, "arena")
+    })
+    .unwrap();
+
+    // The directory is removed once the chunk worker has stopped.
+    assert!(root.join("arena").is_dir());
+
+    app.update();
+
+    assert!(!root.join("arena").exists());
+    assert!(app.world.get_entity(layer).is_none());
+    assert_eq!(app.world.resource::<Worlds>().get("arena"), None);
+
+    let res = with_worlds(&mut app.world, |worlds, commands, _, _| {
+        worlds.delete(commands, "arena")
+    });
+    assert!(matches!(res, Err(WorldError::NotFound(_))));
+
+    // Worlds which aren't loaded are removed immediately.
+    fs::create_dir_all(root.join("old").join("region")).unwrap();
+
+    with_worlds(&mut app.world, |worlds, commands, _, _| {
+        worlds.delete(commands, "old")
+    })
+    .unwrap();
+
+    assert!(!root.join("old").exists());
+
+    fs::remove_dir_all(root).unwrap();
+}